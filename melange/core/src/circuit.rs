@@ -106,11 +106,11 @@ impl Circuit {
         name: String,
         item: impl Into<NameSpaceEntry>,
     ) -> Result<()> {
-        let item = item.into();
-        if let Some(old) = self.namespace.insert(name.clone(), item) {
+        if let Some(old) = self.namespace.get(&name) {
             let old = old.kind();
-            unreachable!("an {old} '{name}' was already declared in this circuit")
+            bail!("an {old} '{name}' was already declared in this circuit")
         }
+        self.namespace.insert(name, item.into());
         Ok(())
     }
 
@@ -251,6 +251,20 @@ impl Circuit {
         self.nodes.index(name)
     }
 
+    /// Lookup an instance by name
+    ///
+    /// # Returns
+    ///
+    /// The instance in this circuit that has the name `name`
+    ///
+    /// If no such instance exists returns `None`
+    pub fn lookup_instance(&self, name: &str) -> Option<InstanceId> {
+        match self.namespace.get(name) {
+            Some(&NameSpaceEntry::Instance(inst)) => Some(inst),
+            _ => None,
+        }
+    }
+
     /// returns the name of a node
     pub fn node_name(&self, node: Node) -> &str {
         &self.nodes[node]
@@ -9,13 +9,23 @@ use stdx::{impl_debug_display, impl_idx_from};
 use typed_index_collections::TiSlice;
 
 use crate::circuit::Node;
+use crate::devices::capacitor::Capacitor;
+use crate::devices::controlled_sources::ControlledSrc;
+use crate::devices::inductor::Inductor;
+use crate::devices::isource::CurrentSrc;
 pub use crate::devices::params::{DeviceParams, ParamId, Type};
 use crate::devices::resistor::Resistor;
 use crate::devices::vsource::VoltageSrc;
-use crate::simulation::{MatrixEntryIter, SimBuilder, SimInfo};
+use crate::simulation::{EvalRetFlags, MatrixEntryIter, SimBuilder, SimInfo};
 
+mod capacitor;
+mod controlled_sources;
+mod inductor;
+mod isource;
 mod params;
 mod resistor;
+#[cfg(test)]
+mod tests;
 mod vsource;
 
 pub trait DeviceImpl {
@@ -89,10 +99,13 @@ pub trait InstanceImpl {
 
     fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter);
 
-    fn eval(&mut self, sim_info: SimInfo<'_>) -> Result<()>;
+    fn eval(&mut self, sim_info: SimInfo<'_>) -> Result<EvalRetFlags>;
 
     unsafe fn load_matrix_resist(&self);
     unsafe fn load_matrix_react(&self, alpha: f64);
+    /// loads the resistive jacobian plus the reactive jacobian scaled by `alpha`
+    /// into the resistive matrix entries
+    unsafe fn load_matrix_tran(&self, alpha: f64);
 
     fn load_residual_react(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>);
     fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>);
@@ -106,6 +119,65 @@ pub trait InstanceImpl {
     fn load_lead_current_resist(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]);
     fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]);
     fn load_ac_lead_current(&self, _ac_solve: &TiSlice<Node, Complex64>, _dst: &mut [Complex64]) {}
+
+    /// largest timestep requested by the last call to `eval`
+    fn bound_step(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    /// next point in time the simulator must not step over
+    fn next_breakpoint(&self) -> f64 {
+        f64::INFINITY
+    }
+
+    /// called after the timepoint `time` with the solution `solution` was accepted
+    fn accept_timepoint(&mut self, _time: f64, _solution: &TiSlice<Node, f64>) {}
+    fn reject_timepoint(&mut self) {}
+
+    /// scales the value of independent sources, used for source stepping
+    fn set_source_factor(&mut self, _factor: f64) {}
+
+    /// the noise sources of this instance, only valid after `process_params`
+    fn noise_sources(&self) -> Vec<NoiseSource> {
+        Vec::new()
+    }
+
+    /// loads the power spectral density of each noise source at `freq` into `dst`.
+    /// Must be called after `eval` was called with `CALC_NOISE`
+    fn load_noise(&self, _freq: f64, _dst: &mut [f64]) {}
+
+    /// pairs of (indices into `noise_sources`) noise sources that are correlated,
+    /// only valid after `process_params`
+    fn noise_correlations(&self) -> Vec<(usize, usize)> {
+        Vec::new()
+    }
+
+    /// loads the cross spectral density of each pair in `noise_correlations` at `freq` into `dst`.
+    /// Must be called after `eval` was called with `CALC_NOISE`
+    fn load_noise_correlated(&self, _freq: f64, _dst: &mut [Complex64]) {}
+
+    /// the operating point variables of this instance.
+    /// Must be called after `eval` was called with `CALC_OP`
+    fn op_vars(&self) -> Vec<OpVar> {
+        Vec::new()
+    }
+}
+
+/// A noise current flowing from `hi` to `lo`
+#[derive(Debug, Clone)]
+pub struct NoiseSource {
+    pub name: String,
+    pub hi: Node,
+    pub lo: Node,
+}
+
+/// An operating point variable (like `gm` or `cgg`) of an instance
+#[derive(Debug, Clone)]
+pub struct OpVar {
+    pub name: &'static str,
+    pub units: &'static str,
+    pub description: &'static str,
+    pub value: f64,
 }
 
 pub struct DeviceInfo {
@@ -117,5 +189,13 @@ pub struct DeviceInfo {
 }
 
 pub(crate) fn default_devices() -> impl Iterator<Item = Box<dyn DeviceImpl>> {
-    [VoltageSrc::init_dev(), Resistor::init_dev()].into_iter()
+    [
+        VoltageSrc::init_dev(),
+        CurrentSrc::init_dev(),
+        Resistor::init_dev(),
+        Capacitor::init_dev(),
+        Inductor::init_dev(),
+    ]
+    .into_iter()
+    .chain(ControlledSrc::init_devs())
 }
@@ -0,0 +1,183 @@
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use stdx::iter::zip;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::{update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type};
+use crate::simulation::{EvalRetFlags, MatrixEntryIter, SimBuilder};
+
+pub struct Capacitor;
+
+impl Capacitor {
+    pub fn init_dev() -> Box<dyn DeviceImpl> {
+        Box::new(Self)
+    }
+}
+
+impl DeviceImpl for Capacitor {
+    fn get_name(&self) -> &'static str {
+        "capacitor"
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["A", "C"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param("c", Type::Real);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(CapacitorModel::default())
+    }
+}
+
+const C: ParamId = ParamId(0u32);
+
+const MATRIX_ANODE_ANODE: usize = 0;
+const MATRIX_ANODE_CATHODE: usize = 1;
+const MATRIX_CATHODE_ANODE: usize = 2;
+const MATRIX_CATHODE_CATHODE: usize = 3;
+
+#[derive(Default, Clone)]
+struct CapacitorModel {
+    cap: Cell<Option<f64>>,
+}
+
+impl ModelImpl for CapacitorModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        match param {
+            C => self.cap.set(Some(val)),
+            _ => unreachable!("capacitor: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn super::InstanceImpl> {
+        Box::new(CapacitorInstance {
+            anode: Node::GROUND,
+            cathode: Node::GROUND,
+            cap: self.cap.get(),
+            capacitance: 0.0,
+            resist_entries: [NonNull::dangling(); 4],
+            react_entries: [NonNull::dangling(); 4],
+        })
+    }
+}
+
+struct CapacitorInstance {
+    anode: Node,
+    cathode: Node,
+    cap: Option<f64>,
+    capacitance: f64,
+    /// only used during transient analysis to load the discretized charge derivative
+    resist_entries: [NonNull<Cell<f64>>; 4],
+    react_entries: [NonNull<Cell<f64>>; 4],
+}
+
+impl CapacitorInstance {
+    unsafe fn load_entries(entries: &[NonNull<Cell<f64>>; 4], val: f64) {
+        update_matrix_entry(entries[MATRIX_ANODE_ANODE].as_ref(), val);
+        update_matrix_entry(entries[MATRIX_ANODE_CATHODE].as_ref(), -val);
+        update_matrix_entry(entries[MATRIX_CATHODE_ANODE].as_ref(), -val);
+        update_matrix_entry(entries[MATRIX_CATHODE_CATHODE].as_ref(), val);
+    }
+
+    fn charge(&self, solve: &TiSlice<Node, f64>) -> f64 {
+        (solve[self.anode] - solve[self.cathode]) * self.capacitance
+    }
+}
+
+impl InstanceImpl for CapacitorInstance {
+    fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
+        for ((resist, react), entry) in
+            zip(&mut self.resist_entries, &mut self.react_entries).zip(matrix_entries)
+        {
+            *resist = entry.resist();
+            *react = entry.react();
+        }
+    }
+
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<EvalRetFlags> {
+        Ok(EvalRetFlags::empty())
+    }
+
+    unsafe fn load_matrix_resist(&self) {}
+
+    unsafe fn load_matrix_react(&self, alpha: f64) {
+        Self::load_entries(&self.react_entries, alpha * self.capacitance)
+    }
+
+    unsafe fn load_matrix_tran(&self, alpha: f64) {
+        Self::load_entries(&self.resist_entries, alpha * self.capacitance)
+    }
+
+    fn load_residual_react(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        let charge = self.charge(prev_solve);
+        rhs[self.anode] += charge;
+        rhs[self.cathode] -= charge;
+    }
+
+    fn load_residual_resist(
+        &self,
+        _prev_solve: &TiSlice<Node, f64>,
+        _rhs: &mut TiSlice<Node, f64>,
+    ) {
+    }
+
+    fn load_lead_current_resist(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[0] = 0.0;
+        dst[1] = 0.0;
+    }
+
+    fn load_lead_current_react(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        let charge = self.charge(dc_solve);
+        dst[0] = charge;
+        dst[1] = -charge;
+    }
+
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        sim_builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let (anode, cathode) = if let &[anode, cathode] = terminals {
+            (anode, cathode)
+        } else {
+            bail!("capacitor: all terminals must be connected")
+        };
+
+        self.anode = anode;
+        self.cathode = cathode;
+
+        // IMPORTANT: keep the order here in sync with the MATRIX_ indices
+        sim_builder.ensure_matrix_entry(anode, anode);
+        sim_builder.ensure_matrix_entry(anode, cathode);
+        sim_builder.ensure_matrix_entry(cathode, anode);
+        sim_builder.ensure_matrix_entry(cathode, cathode);
+
+        match self.cap {
+            Some(cap) => self.capacitance = cap,
+            None => bail!("capacitor: capacitance must be set"),
+        };
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            C => self.cap = Some(val),
+            _ => unreachable!("capacitor: unknown numeric parameter {param:?}"),
+        };
+    }
+}
@@ -0,0 +1,264 @@
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::{update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type};
+use crate::simulation::{EvalRetFlags, MatrixEntryIter, SimBuilder};
+
+/// The linear controlled sources `vcvs`, `vccs`, `ccvs` and `cccs`.
+///
+/// All controlled sources have the terminals `p n ps ns`. The output is connected between
+/// `p` and `n`, output currents flow from `p` through the source to `n`.
+/// Voltage controlled sources sense the voltage between `ps` and `ns`, current controlled
+/// sources sense the current that flows from `ps` through the device to `ns`
+/// (the sensing terminals are shorted).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ControlledSrc {
+    Vcvs,
+    Vccs,
+    Ccvs,
+    Cccs,
+}
+
+impl ControlledSrc {
+    pub fn init_devs() -> [Box<dyn DeviceImpl>; 4] {
+        [
+            Box::new(ControlledSrc::Vcvs),
+            Box::new(ControlledSrc::Vccs),
+            Box::new(ControlledSrc::Ccvs),
+            Box::new(ControlledSrc::Cccs),
+        ]
+    }
+
+    fn gain_name(self) -> &'static str {
+        match self {
+            ControlledSrc::Vcvs | ControlledSrc::Cccs => "gain",
+            ControlledSrc::Vccs => "gm",
+            ControlledSrc::Ccvs => "rm",
+        }
+    }
+
+    fn has_output_branch(self) -> bool {
+        matches!(self, ControlledSrc::Vcvs | ControlledSrc::Ccvs)
+    }
+
+    fn has_sense_branch(self) -> bool {
+        matches!(self, ControlledSrc::Ccvs | ControlledSrc::Cccs)
+    }
+}
+
+impl DeviceImpl for ControlledSrc {
+    fn get_name(&self) -> &'static str {
+        match self {
+            ControlledSrc::Vcvs => "vcvs",
+            ControlledSrc::Vccs => "vccs",
+            ControlledSrc::Ccvs => "ccvs",
+            ControlledSrc::Cccs => "cccs",
+        }
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["p", "n", "ps", "ns"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param(self.gain_name(), Type::Real);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(ControlledSrcModel { kind: *self, gain: Cell::new(None) })
+    }
+}
+
+const GAIN: ParamId = ParamId(0u32);
+
+struct ControlledSrcModel {
+    kind: ControlledSrc,
+    gain: Cell<Option<f64>>,
+}
+
+impl ModelImpl for ControlledSrcModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        match param {
+            GAIN => self.gain.set(Some(val)),
+            _ => unreachable!("{}: unknown numeric parameter {param:?}", self.kind.get_name()),
+        };
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn super::InstanceImpl> {
+        Box::new(ControlledSrcInstance {
+            kind: self.kind,
+            gain: self.gain.get(),
+            p: Node::GROUND,
+            n: Node::GROUND,
+            ps: Node::GROUND,
+            ns: Node::GROUND,
+            branch: Node::GROUND,
+            sense: Node::GROUND,
+            stamps: Vec::new(),
+            matrix_entries: Vec::new(),
+        })
+    }
+}
+
+struct ControlledSrcInstance {
+    kind: ControlledSrc,
+    gain: Option<f64>,
+    p: Node,
+    n: Node,
+    ps: Node,
+    ns: Node,
+    /// the current through the output of voltage sources
+    branch: Node,
+    /// the sensed current of current controlled sources
+    sense: Node,
+    /// the (constant) jacobian entries as `(column, row, value)`,
+    /// the residual is simply the product of the jacobian and the solution
+    stamps: Vec<(Node, Node, f64)>,
+    matrix_entries: Vec<NonNull<Cell<f64>>>,
+}
+
+impl ControlledSrcInstance {
+    fn stamp(&mut self, builder: &mut SimBuilder, column: Node, row: Node, val: f64) {
+        builder.ensure_matrix_entry(column, row);
+        self.stamps.push((column, row, val));
+    }
+
+    /// Stamps the branch equation `V(hi, lo) = 0` with the branch current flowing from `hi` to `lo`
+    fn stamp_branch(&mut self, builder: &mut SimBuilder, branch: Node, hi: Node, lo: Node) {
+        self.stamp(builder, branch, hi, 1.0);
+        self.stamp(builder, branch, lo, -1.0);
+        self.stamp(builder, hi, branch, 1.0);
+        self.stamp(builder, lo, branch, -1.0);
+    }
+
+    /// the current flowing from `p` to `n` and from `ps` to `ns`
+    fn lead_currents<T>(&self, solve: &TiSlice<Node, T>) -> (T, T)
+    where
+        T: Copy + Default + std::ops::Sub<Output = T> + std::ops::Mul<f64, Output = T>,
+    {
+        let gain = self.gain.unwrap_or(0.0);
+        match self.kind {
+            ControlledSrc::Vcvs => (solve[self.branch], T::default()),
+            ControlledSrc::Vccs => ((solve[self.ps] - solve[self.ns]) * gain, T::default()),
+            ControlledSrc::Ccvs => (solve[self.branch], solve[self.sense]),
+            ControlledSrc::Cccs => (solve[self.sense] * gain, solve[self.sense]),
+        }
+    }
+}
+
+impl InstanceImpl for ControlledSrcInstance {
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let name = self.kind.get_name();
+        let [p, n, ps, ns] = if let &[p, n, ps, ns] = terminals {
+            [p, n, ps, ns]
+        } else {
+            bail!("{name}: all terminals must be connected")
+        };
+        let gain = match self.gain {
+            Some(gain) => gain,
+            None => bail!("{name}: {} must be set", self.kind.gain_name()),
+        };
+
+        self.p = p;
+        self.n = n;
+        self.ps = ps;
+        self.ns = ns;
+        self.stamps.clear();
+
+        if self.kind.has_sense_branch() {
+            self.sense = builder.new_internal_branch("sense");
+            self.stamp_branch(builder, self.sense, ps, ns);
+        }
+
+        if self.kind.has_output_branch() {
+            self.branch = builder.new_internal_branch("branch");
+            self.stamp_branch(builder, self.branch, p, n);
+        }
+
+        match self.kind {
+            ControlledSrc::Vcvs => {
+                self.stamp(builder, ps, self.branch, -gain);
+                self.stamp(builder, ns, self.branch, gain);
+            }
+            ControlledSrc::Vccs => {
+                self.stamp(builder, ps, p, gain);
+                self.stamp(builder, ns, p, -gain);
+                self.stamp(builder, ps, n, -gain);
+                self.stamp(builder, ns, n, gain);
+            }
+            ControlledSrc::Ccvs => self.stamp(builder, self.sense, self.branch, -gain),
+            ControlledSrc::Cccs => {
+                self.stamp(builder, self.sense, p, gain);
+                self.stamp(builder, self.sense, n, -gain);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            GAIN => self.gain = Some(val),
+            _ => unreachable!("{}: unknown numeric parameter {param:?}", self.kind.get_name()),
+        };
+    }
+
+    fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
+        self.matrix_entries = matrix_entries.map(|entry| entry.resist()).collect();
+    }
+
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<EvalRetFlags> {
+        Ok(EvalRetFlags::empty())
+    }
+
+    unsafe fn load_matrix_resist(&self) {
+        for (entry, &(_, _, val)) in self.matrix_entries.iter().zip(&self.stamps) {
+            update_matrix_entry(entry.as_ref(), val);
+        }
+    }
+
+    unsafe fn load_matrix_react(&self, _alpha: f64) {}
+
+    unsafe fn load_matrix_tran(&self, _alpha: f64) {
+        self.load_matrix_resist()
+    }
+
+    fn load_residual_react(&self, _prev_solve: &TiSlice<Node, f64>, _rhs: &mut TiSlice<Node, f64>) {
+    }
+
+    fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        for &(column, row, val) in &self.stamps {
+            rhs[row] += val * prev_solve[column];
+        }
+    }
+
+    fn load_lead_current_resist(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        let (out, sense) = self.lead_currents(dc_solve);
+        dst.copy_from_slice(&[out, -out, sense, -sense]);
+    }
+
+    fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, _dst: &mut [f64]) {}
+
+    fn load_ac_lead_current(&self, ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        let (out, sense) = self.lead_currents(ac_solve);
+        dst.copy_from_slice(&[out, -out, sense, -sense]);
+    }
+}
@@ -0,0 +1,190 @@
+use std::cell::Cell;
+use std::ptr::NonNull;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use typed_index_collections::TiSlice;
+
+use super::{ModelImpl, ParamId, SimInfo};
+use crate::circuit::Node;
+use crate::devices::{update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type};
+use crate::simulation::{EvalRetFlags, MatrixEntryIter, SimBuilder};
+
+pub struct Inductor;
+
+impl Inductor {
+    pub fn init_dev() -> Box<dyn DeviceImpl> {
+        Box::new(Self)
+    }
+}
+
+impl DeviceImpl for Inductor {
+    fn get_name(&self) -> &'static str {
+        "inductor"
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["A", "C"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param("l", Type::Real);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(InductorModel::default())
+    }
+}
+
+const L: ParamId = ParamId(0u32);
+
+const MATRIX_ANODE_BR: usize = 0;
+const MATRIX_BR_ANODE: usize = 1;
+const MATRIX_CATHODE_BR: usize = 2;
+const MATRIX_BR_CATHODE: usize = 3;
+const MATRIX_BR_BR: usize = 4;
+
+#[derive(Default, Clone)]
+struct InductorModel {
+    ind: Cell<Option<f64>>,
+}
+
+impl ModelImpl for InductorModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        match param {
+            L => self.ind.set(Some(val)),
+            _ => unreachable!("inductor: unknown numeric parameter {param:?}"),
+        };
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn super::InstanceImpl> {
+        Box::new(InductorInstance {
+            anode: Node::GROUND,
+            cathode: Node::GROUND,
+            branch: Node::GROUND,
+            ind: self.ind.get(),
+            inductance: 0.0,
+            resist_entries: [NonNull::dangling(); 5],
+            react_entry: NonNull::dangling(),
+        })
+    }
+}
+
+/// The branch equation of the inductor is `V(A, C) - d/dt (L * I) = 0`
+/// where the branch current `I` is an additional unknown.
+struct InductorInstance {
+    anode: Node,
+    cathode: Node,
+    branch: Node,
+    ind: Option<f64>,
+    inductance: f64,
+    resist_entries: [NonNull<Cell<f64>>; 5],
+    react_entry: NonNull<Cell<f64>>,
+}
+
+impl InductorInstance {
+    fn flux(&self, solve: &TiSlice<Node, f64>) -> f64 {
+        self.inductance * solve[self.branch]
+    }
+}
+
+impl InstanceImpl for InductorInstance {
+    fn populate_matrix_ptrs(&mut self, matrix_entries: MatrixEntryIter) {
+        for (i, entry) in matrix_entries.enumerate() {
+            self.resist_entries[i] = entry.resist();
+            if i == MATRIX_BR_BR {
+                self.react_entry = entry.react();
+            }
+        }
+    }
+
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<EvalRetFlags> {
+        Ok(EvalRetFlags::empty())
+    }
+
+    unsafe fn load_matrix_resist(&self) {
+        update_matrix_entry(self.resist_entries[MATRIX_ANODE_BR].as_ref(), 1.0);
+        update_matrix_entry(self.resist_entries[MATRIX_BR_ANODE].as_ref(), 1.0);
+        update_matrix_entry(self.resist_entries[MATRIX_CATHODE_BR].as_ref(), -1.0);
+        update_matrix_entry(self.resist_entries[MATRIX_BR_CATHODE].as_ref(), -1.0);
+    }
+
+    unsafe fn load_matrix_react(&self, alpha: f64) {
+        update_matrix_entry(self.react_entry.as_ref(), -alpha * self.inductance);
+    }
+
+    unsafe fn load_matrix_tran(&self, alpha: f64) {
+        self.load_matrix_resist();
+        update_matrix_entry(self.resist_entries[MATRIX_BR_BR].as_ref(), -alpha * self.inductance);
+    }
+
+    fn load_residual_react(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        rhs[self.branch] -= self.flux(prev_solve);
+    }
+
+    fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        rhs[self.anode] += prev_solve[self.branch];
+        rhs[self.cathode] -= prev_solve[self.branch];
+        rhs[self.branch] += prev_solve[self.anode] - prev_solve[self.cathode];
+    }
+
+    fn load_lead_current_resist(&self, dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[0] = dc_solve[self.branch];
+        dst[1] = -dc_solve[self.branch];
+    }
+
+    fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        dst[0] = 0.0;
+        dst[1] = 0.0;
+    }
+
+    fn load_ac_lead_current(&self, ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        dst[0] = ac_solve[self.branch];
+        dst[1] = -ac_solve[self.branch];
+    }
+
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        sim_builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let (anode, cathode) = if let &[anode, cathode] = terminals {
+            (anode, cathode)
+        } else {
+            bail!("inductor: all terminals must be connected")
+        };
+
+        let branch = sim_builder.new_internal_branch("branch");
+        self.anode = anode;
+        self.cathode = cathode;
+        self.branch = branch;
+
+        // IMPORTANT: keep the order here in sync with the MATRIX_ indices
+        sim_builder.ensure_matrix_entry(anode, branch);
+        sim_builder.ensure_matrix_entry(branch, anode);
+        sim_builder.ensure_matrix_entry(cathode, branch);
+        sim_builder.ensure_matrix_entry(branch, cathode);
+        sim_builder.ensure_matrix_entry(branch, branch);
+
+        match self.ind {
+            Some(ind) => self.inductance = ind,
+            None => bail!("inductor: inductance must be set"),
+        };
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            L => self.ind = Some(val),
+            _ => unreachable!("inductor: unknown numeric parameter {param:?}"),
+        };
+    }
+}
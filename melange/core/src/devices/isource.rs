@@ -0,0 +1,177 @@
+use std::cell::Cell;
+use std::rc::Rc;
+
+use anyhow::{bail, Result};
+use num_complex::Complex64;
+use typed_index_collections::TiSlice;
+
+use crate::circuit::Node;
+use crate::devices::{DeviceImpl, DeviceParams, InstanceImpl, Type};
+use crate::simulation::{EvalRetFlags, MatrixEntryIter, SimBuilder};
+
+use super::{ModelImpl, ParamId, SimInfo};
+
+/// An independent current source. The current flows from `A` through the source to `C`.
+pub struct CurrentSrc;
+
+impl CurrentSrc {
+    pub fn init_dev() -> Box<dyn DeviceImpl> {
+        Box::new(Self)
+    }
+}
+
+const DC: ParamId = ParamId(0u32);
+const MAG: ParamId = ParamId(1u32);
+const PHASE: ParamId = ParamId(2u32);
+
+impl DeviceImpl for CurrentSrc {
+    fn get_name(&self) -> &'static str {
+        "isource"
+    }
+
+    fn get_terminals(&self) -> Box<[&'static str]> {
+        vec!["A", "C"].into_boxed_slice()
+    }
+
+    fn get_params(&self) -> DeviceParams {
+        let mut res = DeviceParams::default();
+        res.insert_instance_param("dc", Type::Real);
+        res.insert_instance_param("mag", Type::Real);
+        res.insert_instance_param("phase", Type::Real);
+        res
+    }
+
+    fn new_model(&self) -> Rc<dyn ModelImpl> {
+        Rc::new(CurrentSrcModel::default())
+    }
+}
+
+#[derive(Default)]
+struct CurrentSrcModel {
+    dc: Cell<f64>,
+    mag: Cell<f64>,
+    phase: Cell<f64>,
+}
+
+impl ModelImpl for CurrentSrcModel {
+    fn process_params(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_real_param(&self, param: ParamId, val: f64) {
+        let dst = match param {
+            DC => &self.dc,
+            MAG => &self.mag,
+            PHASE => &self.phase,
+            _ => unreachable!("isource: unknown num param {param:?}"),
+        };
+        dst.set(val);
+    }
+
+    fn new_instance(self: Rc<Self>) -> Box<dyn super::InstanceImpl> {
+        Box::new(CurrentSrcInstance {
+            anode: Node::GROUND,
+            cathode: Node::GROUND,
+            dc: self.dc.get(),
+            mag: self.mag.get(),
+            phase: self.phase.get(),
+            src_fact: 1.0,
+        })
+    }
+}
+
+struct CurrentSrcInstance {
+    anode: Node,
+    cathode: Node,
+    dc: f64,
+    mag: f64,
+    phase: f64,
+    src_fact: f64,
+}
+
+impl CurrentSrcInstance {
+    fn dc(&self) -> f64 {
+        self.dc * self.src_fact
+    }
+
+    fn ac(&self) -> Complex64 {
+        Complex64::from_polar(self.mag, self.phase)
+    }
+}
+
+impl InstanceImpl for CurrentSrcInstance {
+    fn process_params(
+        &mut self,
+        _temp: f64,
+        _builder: &mut SimBuilder,
+        terminals: &[Node],
+    ) -> Result<()> {
+        let [anode, cathode] = if let &[anode, cathode] = terminals {
+            [anode, cathode]
+        } else {
+            bail!("isource: all terminals must be connected")
+        };
+
+        self.anode = anode;
+        self.cathode = cathode;
+        Ok(())
+    }
+
+    fn set_real_param(&mut self, param: ParamId, val: f64) {
+        match param {
+            DC => self.dc = val,
+            MAG => self.mag = val,
+            PHASE => self.phase = val,
+            _ => unreachable!("isource: unknown num param {param:?}"),
+        };
+    }
+
+    fn populate_matrix_ptrs(&mut self, _matrix_entries: MatrixEntryIter) {}
+
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<EvalRetFlags> {
+        Ok(EvalRetFlags::empty())
+    }
+
+    unsafe fn load_matrix_resist(&self) {}
+
+    unsafe fn load_matrix_react(&self, _alpha: f64) {}
+
+    unsafe fn load_matrix_tran(&self, _alpha: f64) {}
+
+    fn load_residual_react(&self, _prev_solve: &TiSlice<Node, f64>, _rhs: &mut TiSlice<Node, f64>) {
+    }
+
+    fn load_residual_resist(&self, _prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
+        let dc = self.dc();
+        rhs[self.anode] += dc;
+        rhs[self.cathode] -= dc;
+    }
+
+    fn load_ac_residual(
+        &self,
+        _prev_solve: &TiSlice<Node, f64>,
+        rhs: &mut TiSlice<Node, Complex64>,
+    ) {
+        let ac = self.ac();
+        rhs[self.anode] -= ac;
+        rhs[self.cathode] += ac;
+    }
+
+    fn load_lead_current_resist(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
+        let dc = self.dc();
+        dst[0] = dc;
+        dst[1] = -dc;
+    }
+
+    fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, _dst: &mut [f64]) {}
+
+    fn set_source_factor(&mut self, factor: f64) {
+        self.src_fact = factor;
+    }
+
+    fn load_ac_lead_current(&self, _ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
+        let ac = self.ac();
+        dst[0] = ac;
+        dst[1] = -ac;
+    }
+}
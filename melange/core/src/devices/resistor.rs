@@ -8,8 +8,10 @@ use typed_index_collections::TiSlice;
 
 use super::{ModelImpl, ParamId, SimInfo};
 use crate::circuit::Node;
-use crate::devices::{update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type};
-use crate::simulation::{MatrixEntryIter, SimBuilder};
+use crate::devices::{
+    update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, NoiseSource, Type,
+};
+use crate::simulation::{EvalRetFlags, MatrixEntryIter, SimBuilder};
 
 pub struct Resistor;
 
@@ -41,6 +43,8 @@ impl DeviceImpl for Resistor {
 
 const R: ParamId = ParamId(0u32);
 
+const BOLTZMANN: f64 = 1.380649e-23;
+
 const MATRIX_ANODE_ANODE: usize = 0;
 const MATRIX_ANODE_CATHODE: usize = 1;
 const MATRIX_CATHODE_ANODE: usize = 2;
@@ -70,6 +74,7 @@ impl ModelImpl for ResistorModel {
             res: self.res.get(),
             matrix_entries: [NonNull::dangling(); 4],
             conductance: 0.0,
+            temp: 0.0,
         })
     }
 }
@@ -78,6 +83,7 @@ struct ResistorInstance {
     anode: Node,
     cathode: Node,
     conductance: f64,
+    temp: f64,
     res: Option<f64>,
     matrix_entries: [NonNull<Cell<f64>>; 4],
 }
@@ -89,8 +95,8 @@ impl InstanceImpl for ResistorInstance {
         }
     }
 
-    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<()> {
-        Ok(())
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<EvalRetFlags> {
+        Ok(EvalRetFlags::empty())
     }
 
     unsafe fn load_matrix_resist(&self) {
@@ -102,6 +108,10 @@ impl InstanceImpl for ResistorInstance {
 
     unsafe fn load_matrix_react(&self, _alpha: f64) {}
 
+    unsafe fn load_matrix_tran(&self, _alpha: f64) {
+        self.load_matrix_resist()
+    }
+
     fn load_residual_react(&self, _prev_solve: &TiSlice<Node, f64>, _rhs: &mut TiSlice<Node, f64>) {
     }
 
@@ -121,7 +131,7 @@ impl InstanceImpl for ResistorInstance {
 
     fn process_params(
         &mut self,
-        _temp: f64,
+        temp: f64,
         sim_builder: &mut SimBuilder,
         terminals: &[Node],
     ) -> Result<()> {
@@ -133,6 +143,7 @@ impl InstanceImpl for ResistorInstance {
 
         self.anode = anode;
         self.cathode = cathode;
+        self.temp = temp;
 
         sim_builder.ensure_matrix_entry(anode, anode);
         sim_builder.ensure_matrix_entry(anode, cathode);
@@ -152,4 +163,12 @@ impl InstanceImpl for ResistorInstance {
             _ => unreachable!("vsource: unknown numeric parameter {param:?}"),
         };
     }
+
+    fn noise_sources(&self) -> Vec<NoiseSource> {
+        vec![NoiseSource { name: "thermal".to_owned(), hi: self.anode, lo: self.cathode }]
+    }
+
+    fn load_noise(&self, _freq: f64, dst: &mut [f64]) {
+        dst[0] = 4.0 * BOLTZMANN * self.temp * self.conductance;
+    }
 }
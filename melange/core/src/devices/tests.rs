@@ -0,0 +1,109 @@
+use anyhow::Result;
+use num_complex::Complex64;
+
+use crate::expr::CircuitParam;
+use crate::netlist::Netlist;
+use crate::simulation::SimConfig;
+use crate::tests::parse;
+use crate::{Arena, ExprEvalCtx};
+
+#[test]
+fn controlled_sources() -> Result<()> {
+    let src = r#"
+i1 (0 ctrl) isource dc=1m
+r1 (ctrl 0) resistor r=1k
+g1 (0 out ctrl 0) vccs gm=2m
+r2 (out 0) resistor r=1k
+e1 (amp 0 out 0) vcvs gain=10
+c1 (amp 0) capacitor c=1p
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let out = circuit.lookup_node("out").expect("node out");
+    let amp = circuit.lookup_node("amp").expect("node amp");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let solution = sim.dc_op()?;
+    assert!((solution[out] - 2.0).abs() < 1e-9);
+    assert!((solution[amp] - 20.0).abs() < 1e-9);
+    Ok(())
+}
+
+#[test]
+fn ac_lc_tank() -> Result<()> {
+    let src = r#"
+v1 (in 0) vsource dc=0 mag=1
+r1 (in out) resistor r=1k
+l1 (out 0) inductor l=1m
+c1 (out 0) capacitor c=1n
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let out = circuit.lookup_node("out").expect("node out");
+    let l1 = circuit.lookup_instance("l1").expect("instance l1");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    // the tank resonates at omega = 1/sqrt(LC) = 1e6 where its impedance is infinite
+    for omega in [1e4, 1e5, 1e6, 1e7] {
+        sim.set_omega(omega);
+        let val = sim.ac()?[out];
+        let admittance = Complex64::new(0.0, omega * 1e-9 - 1.0 / (omega * 1e-3));
+        let expected = Complex64::new(1.0, 0.0) / (1.0 + 1e3 * admittance);
+        assert!((val - expected).norm() < 1e-9, "omega = {omega}: {val} != {expected}");
+
+        let il = sim.ac_lead_current(l1)?[0];
+        let expected = val / Complex64::new(0.0, omega * 1e-3);
+        assert!((il - expected).norm() < 1e-12, "omega = {omega}: {il} != {expected}");
+    }
+    Ok(())
+}
+
+#[test]
+fn current_controlled_sources() -> Result<()> {
+    // the current of i1 is sensed by h1 and f1 in series
+    let src = r#"
+i1 (0 ctrl) isource dc=1m mag=2m
+h1 (out 0 ctrl mid) ccvs rm=2k
+r2 (out 0) resistor r=1k
+f1 (0 amp mid 0) cccs gain=3
+r3 (amp 0) resistor r=1k
+c3 (amp 0) capacitor c=1n
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let ctrl = circuit.lookup_node("ctrl").expect("node ctrl");
+    let out = circuit.lookup_node("out").expect("node out");
+    let amp = circuit.lookup_node("amp").expect("node amp");
+    let h1 = circuit.lookup_instance("h1").expect("instance h1");
+    let f1 = circuit.lookup_instance("f1").expect("instance f1");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let solution = sim.dc_op()?;
+    // the sensing terminals are shorted
+    assert!(solution[ctrl].abs() < 1e-9);
+    assert!((solution[out] - 2.0).abs() < 1e-9);
+    assert!((solution[amp] - 3.0).abs() < 1e-9);
+    let sensed = sim.dc_lead_current(h1)?[2];
+    assert!((sensed - 1e-3).abs() < 1e-12, "{sensed}");
+    let currents = sim.dc_lead_current(f1)?;
+    let (iout, sensed) = (currents[0], currents[2]);
+    assert!((sensed - 1e-3).abs() < 1e-12, "{sensed}");
+    assert!((iout - 3e-3).abs() < 1e-12, "{iout}");
+
+    for omega in [1e3, 1e6, 1e7] {
+        sim.set_omega(omega);
+        let ac = sim.ac()?;
+        let (vout, vamp) = (ac[out], ac[amp]);
+        let expected = Complex64::new(4.0, 0.0);
+        assert!((vout - expected).norm() < 1e-9, "omega = {omega}: {vout} != {expected}");
+        let expected = 6e-3 * 1e3 / Complex64::new(1.0, omega * 1e-6);
+        assert!((vamp - expected).norm() < 1e-9, "omega = {omega}: {vamp} != {expected}");
+    }
+    Ok(())
+}
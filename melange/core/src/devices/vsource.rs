@@ -10,7 +10,7 @@ use typed_index_collections::TiSlice;
 
 use crate::circuit::Node;
 use crate::devices::{update_matrix_entry, DeviceImpl, DeviceParams, InstanceImpl, Type};
-use crate::simulation::{MatrixEntryIter, SimBuilder};
+use crate::simulation::{EvalRetFlags, MatrixEntryIter, SimBuilder};
 
 use super::{ModelImpl, ParamId, SimInfo};
 
@@ -82,6 +82,7 @@ impl ModelImpl for VoltageSrcModel {
             branch: Node::GROUND,
             dc: self.dc.get(),
             ac: Complex64::from_polar(self.mag.get(), self.phase.get()),
+            src_fact: 1.0,
             matrix_entries: [NonNull::dangling(); 4],
         })
     }
@@ -93,6 +94,7 @@ struct VoltageSrcInstance {
     branch: Node,
     dc: f64,
     ac: Complex64,
+    src_fact: f64,
     matrix_entries: [NonNull<Cell<f64>>; 4],
 }
 
@@ -138,8 +140,8 @@ impl InstanceImpl for VoltageSrcInstance {
         }
     }
 
-    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<()> {
-        Ok(())
+    fn eval(&mut self, _sim_info: SimInfo<'_>) -> Result<EvalRetFlags> {
+        Ok(EvalRetFlags::empty())
     }
 
     unsafe fn load_matrix_resist(&self) {
@@ -151,13 +153,17 @@ impl InstanceImpl for VoltageSrcInstance {
 
     unsafe fn load_matrix_react(&self, _alpha: f64) {}
 
+    unsafe fn load_matrix_tran(&self, _alpha: f64) {
+        self.load_matrix_resist()
+    }
+
     fn load_residual_react(&self, _prev_solve: &TiSlice<Node, f64>, _rhs: &mut TiSlice<Node, f64>) {
     }
 
     fn load_residual_resist(&self, prev_solve: &TiSlice<Node, f64>, rhs: &mut TiSlice<Node, f64>) {
         rhs[self.anode] += prev_solve[self.branch];
         rhs[self.cathode] -= prev_solve[self.branch];
-        rhs[self.branch] -= self.dc;
+        rhs[self.branch] -= self.dc * self.src_fact;
         rhs[self.branch] += prev_solve[self.anode] - prev_solve[self.cathode];
     }
 
@@ -176,6 +182,10 @@ impl InstanceImpl for VoltageSrcInstance {
 
     fn load_lead_current_react(&self, _dc_solve: &TiSlice<Node, f64>, _dst: &mut [f64]) {}
 
+    fn set_source_factor(&mut self, factor: f64) {
+        self.src_fact = factor;
+    }
+
     fn load_ac_lead_current(&self, ac_solve: &TiSlice<Node, Complex64>, dst: &mut [Complex64]) {
         dst[0] = ac_solve[self.branch];
         dst[1] = -ac_solve[self.branch];
@@ -194,7 +194,7 @@ impl Circuit {
             }
 
             None => {
-                bail!("'{}' not found", instance.name);
+                bail!("'{}' not found", instance.master);
             }
         };
 
@@ -6,6 +6,9 @@ use stdx::{impl_debug_display, impl_idx_from};
 use typed_index_collections::{TiSlice, TiVec};
 use typed_indexmap::TiMap;
 
+#[cfg(test)]
+mod tests;
+
 #[derive(PartialEq, Debug, Clone, Copy)]
 pub enum Expr {
     Eval(ExprPtr),
@@ -144,6 +147,7 @@ impl Expr {
         Ok(res)
     }
 
+    /// Creates the negation `-arg`. Constants are folded and double negations cancel.
     pub fn neg(arena: &mut Arena, arg: Expr) -> Result<Expr> {
         match arg {
             Expr::Eval(arg) => {
@@ -154,7 +158,7 @@ impl Expr {
                 };
                 Ok(ptr.into())
             }
-            Expr::Value(arg) => Ok((1.0 / arg.to_num()?).into()),
+            Expr::Value(arg) => Ok((-arg.to_num()?).into()),
         }
     }
 
@@ -637,6 +641,11 @@ impl Arena {
         self.params.push_and_get_key(TiMap::default())
     }
 
+    /// Interns a string so it can be used as a [`Value`]
+    pub fn str_val(&mut self, val: &str) -> Value {
+        Value::Str(self.intern.get_or_intern(val))
+    }
+
     /// Lookup an parameter by name
     ///
     /// # Returns
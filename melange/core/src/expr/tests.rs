@@ -0,0 +1,27 @@
+use anyhow::Result;
+
+use crate::{Arena, Expr, ExprEvalCtx};
+
+#[test]
+fn neg_constant() -> Result<()> {
+    let mut arena = Arena::new();
+    assert_eq!(Expr::neg(&mut arena, 2.0.into())?, Expr::from(-2.0));
+    assert_eq!(Expr::neg(&mut arena, (-0.5).into())?, Expr::from(0.5));
+    // negation and inversion are different operations
+    assert_eq!(Expr::inv(&mut arena, 2.0.into())?, Expr::from(0.5));
+    Ok(())
+}
+
+#[test]
+fn neg_param() -> Result<()> {
+    let mut arena = Arena::new();
+    let ctx = arena.add_ctx();
+    let (param, x) = arena.def_param(ctx, "x".to_owned())?;
+    let neg = Expr::neg(&mut arena, x)?;
+    assert_eq!(Expr::neg(&mut arena, neg)?, x);
+
+    let mut eval_ctx = ExprEvalCtx::new(&arena);
+    eval_ctx.set_param(param, 2.0.into());
+    assert_eq!(neg.eval_num(eval_ctx.borrow())?, -2.0);
+    Ok(())
+}
@@ -1,6 +1,7 @@
 pub use crate::circuit::Circuit;
 pub use crate::elaboration::CircuitDescription;
-pub use crate::expr::{Arena, Expr, ExprEvalCtx, Value};
+pub use crate::expr::{Arena, CircuitParam, Expr, ExprEvalCtx, Value};
+pub use crate::veriloga::Opts as VaOpts;
 
 // #[macro_use]
 // mod utils;
@@ -8,9 +9,10 @@ pub mod circuit;
 mod devices;
 pub mod elaboration;
 mod expr;
+pub mod netlist;
 pub mod simulation;
 mod utils;
 mod veriloga;
 
-// #[cfg(all(test, not(windows)))]
-// mod tests;
+#[cfg(test)]
+mod tests;
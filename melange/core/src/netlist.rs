@@ -0,0 +1,71 @@
+//! A parser for the subset of the spectre netlist language that is required to describe circuits
+//! for melange. The following statements are supported:
+//!
+//! * instance lines like `r1 (a b) resistor r=1k`
+//! * `model`, `subckt` (and `inline subckt`) and `parameters` definitions
+//! * `include` (including `section`s of a `library`) and `ahdl_include`
+//! * the `dc`, `ac`, `tran` and `noise` analysis statements
+//!
+//! Parameter expressions are lowered into the [`Arena`] of the circuit, subcircuit instances are
+//! flattened so the result is an ordinary [`Circuit`].
+
+use anyhow::Result;
+use camino::Utf8Path;
+
+use crate::circuit::Node;
+use crate::{veriloga, Arena, Circuit, Expr};
+
+mod ast;
+mod lexer;
+mod lower;
+mod parser;
+#[cfg(test)]
+mod tests;
+
+/// A parsed netlist: the circuit and the analyses that should be run on it
+pub struct Netlist {
+    pub circuit: Circuit,
+    /// The analysis statements in the order they appear within the netlist
+    pub analyses: Vec<Analysis>,
+}
+
+/// An analysis statement like `tran1 tran stop=1u`.
+/// melange does not run these analyses automatically, this is left to the API consumer.
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    pub name: String,
+    pub kind: AnalysisKind,
+    /// The nodes listed after the analysis name (for example the output of a noise analysis)
+    pub nodes: Vec<Node>,
+    pub parameters: Vec<(String, Expr)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnalysisKind {
+    Dc,
+    Ac,
+    Tran,
+    Noise,
+}
+
+impl Netlist {
+    /// Parses the netlist at `path` and elaborates it into a [`Circuit`].
+    /// Include statements are resolved relative to the file that contains them.
+    pub fn parse(path: &Utf8Path, earena: &mut Arena, opts: &veriloga::Opts) -> Result<Netlist> {
+        let file = parser::parse_file(path, 0)?;
+        let name = path.file_stem().unwrap_or("netlist").to_owned();
+        lower::lower(name, file.statements, earena, opts)
+    }
+
+    /// Parses a netlist from a string and elaborates it into a [`Circuit`] called `name`.
+    /// Include statements are resolved relative to the current working directory.
+    pub fn parse_str(
+        name: String,
+        src: &str,
+        earena: &mut Arena,
+        opts: &veriloga::Opts,
+    ) -> Result<Netlist> {
+        let file = parser::parse_src(src, Utf8Path::new(&name), 0)?;
+        lower::lower(name, file.statements, earena, opts)
+    }
+}
@@ -0,0 +1,60 @@
+//! The syntax tree of a spectre netlist. Includes are already resolved,
+//! so the tree of a netlist contains the statements of all included files.
+
+use std::rc::Rc;
+
+use camino::Utf8PathBuf;
+
+use crate::netlist::AnalysisKind;
+
+pub(super) enum Statement {
+    Parameters(Vec<Param>),
+    Model(Model),
+    Instance(Instance),
+    Analysis(Analysis),
+    Subckt(Rc<Subckt>),
+    AhdlInclude(Utf8PathBuf),
+    Global(Vec<String>),
+}
+
+pub(super) struct Param {
+    pub name: String,
+    pub val: Expr,
+}
+
+pub(super) struct Model {
+    pub name: String,
+    pub device: String,
+    pub params: Vec<Param>,
+}
+
+pub(super) struct Instance {
+    pub name: String,
+    pub nodes: Vec<String>,
+    pub master: String,
+    pub params: Vec<Param>,
+}
+
+pub(super) struct Analysis {
+    pub name: String,
+    pub kind: AnalysisKind,
+    pub nodes: Vec<String>,
+    pub params: Vec<Param>,
+}
+
+pub(super) struct Subckt {
+    pub name: String,
+    pub terminals: Vec<String>,
+    pub body: Vec<Statement>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(super) enum Expr {
+    Num(f64),
+    Str(String),
+    Ident(String),
+    Call(String, Vec<Expr>),
+    Unary(&'static str, Box<Expr>),
+    Binary(&'static str, Box<Expr>, Box<Expr>),
+    Cond(Box<Expr>, Box<Expr>, Box<Expr>),
+}
@@ -0,0 +1,205 @@
+use anyhow::{bail, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(super) enum TokenKind {
+    Ident,
+    Number,
+    /// a string literal, the text does not include the quotes
+    Str,
+    LParen,
+    RParen,
+    LBracket,
+    RBracket,
+    Assign,
+    Comma,
+    Question,
+    Colon,
+    /// an unary or binary operator like `+` or `<=`
+    Op,
+    Newline,
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(super) struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub line: u32,
+}
+
+/// Splits spectre source code into tokens.
+/// Comments and line continuations are removed, consecutive line breaks are merged.
+pub(super) fn tokenize(src: &str) -> Result<Vec<Token<'_>>> {
+    let bytes = src.as_bytes();
+    let mut tokens: Vec<Token> = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+    let mut line_start = true;
+
+    let skip_line = |pos: &mut usize| {
+        while *pos < bytes.len() && bytes[*pos] != b'\n' {
+            *pos += 1
+        }
+    };
+
+    while pos < bytes.len() {
+        let start = pos;
+        let c = bytes[pos];
+        let next = bytes.get(pos + 1).copied().unwrap_or(0);
+        let kind = match c {
+            b'\n' => {
+                pos += 1;
+                line += 1;
+                line_start = true;
+                if !matches!(tokens.last(), None | Some(Token { kind: TokenKind::Newline, .. })) {
+                    tokens.push(Token { kind: TokenKind::Newline, text: "\n", line: line - 1 });
+                }
+                continue;
+            }
+            b' ' | b'\t' | b'\r' => {
+                pos += 1;
+                continue;
+            }
+            b'\\' if next == b'\n' || (next == b'\r' && bytes.get(pos + 2) == Some(&b'\n')) => {
+                skip_line(&mut pos);
+                pos += 1;
+                line += 1;
+                continue;
+            }
+            b'*' if line_start => {
+                skip_line(&mut pos);
+                continue;
+            }
+            b'/' if next == b'/' => {
+                skip_line(&mut pos);
+                continue;
+            }
+            b'"' => {
+                pos += 1;
+                while pos < bytes.len() && bytes[pos] != b'"' {
+                    if bytes[pos] == b'\n' {
+                        bail!("line {line}: unterminated string literal")
+                    }
+                    pos += 1
+                }
+                if pos == bytes.len() {
+                    bail!("line {line}: unterminated string literal")
+                }
+                pos += 1;
+                line_start = false;
+                tokens.push(Token { kind: TokenKind::Str, text: &src[start + 1..pos - 1], line });
+                continue;
+            }
+            b'0'..=b'9' => {
+                lex_number(bytes, &mut pos);
+                TokenKind::Number
+            }
+            b'.' if next.is_ascii_digit() => {
+                lex_number(bytes, &mut pos);
+                TokenKind::Number
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                pos += 1;
+                while pos < bytes.len() {
+                    match bytes[pos] {
+                        c if c.is_ascii_alphanumeric() || c == b'_' || c == b'.' => pos += 1,
+                        // global nodes end with `!`, make sure not to consume `!=`
+                        b'!' if bytes.get(pos + 1) != Some(&b'=') => pos += 1,
+                        _ => break,
+                    }
+                }
+                TokenKind::Ident
+            }
+            b'(' => single(&mut pos, TokenKind::LParen),
+            b')' => single(&mut pos, TokenKind::RParen),
+            b'[' => single(&mut pos, TokenKind::LBracket),
+            b']' => single(&mut pos, TokenKind::RBracket),
+            b',' => single(&mut pos, TokenKind::Comma),
+            b'?' => single(&mut pos, TokenKind::Question),
+            b':' => single(&mut pos, TokenKind::Colon),
+            b'=' if next == b'=' => {
+                pos += 2;
+                TokenKind::Op
+            }
+            b'=' => single(&mut pos, TokenKind::Assign),
+            b'!' | b'<' | b'>' if next == b'=' => {
+                pos += 2;
+                TokenKind::Op
+            }
+            b'&' | b'|' | b'*' | b'<' | b'>' if next == c => {
+                pos += 2;
+                TokenKind::Op
+            }
+            b'+' | b'-' | b'*' | b'/' | b'%' | b'^' | b'~' | b'!' | b'<' | b'>' | b'&' | b'|' => {
+                single(&mut pos, TokenKind::Op)
+            }
+            _ => bail!("line {line}: unexpected character '{}'", char::from(c)),
+        };
+
+        line_start = false;
+        tokens.push(Token { kind, text: &src[start..pos], line });
+    }
+
+    tokens.push(Token { kind: TokenKind::Newline, text: "\n", line });
+    tokens.push(Token { kind: TokenKind::Eof, text: "", line });
+    Ok(tokens)
+}
+
+fn single(pos: &mut usize, kind: TokenKind) -> TokenKind {
+    *pos += 1;
+    kind
+}
+
+fn lex_number(bytes: &[u8], pos: &mut usize) {
+    while *pos < bytes.len() && (bytes[*pos].is_ascii_digit() || bytes[*pos] == b'.') {
+        *pos += 1
+    }
+
+    if matches!(bytes.get(*pos), Some(b'e' | b'E')) {
+        let exp_start = match bytes.get(*pos + 1) {
+            Some(b'+' | b'-') => *pos + 2,
+            _ => *pos + 1,
+        };
+        if bytes.get(exp_start).map_or(false, u8::is_ascii_digit) {
+            *pos = exp_start;
+            while *pos < bytes.len() && bytes[*pos].is_ascii_digit() {
+                *pos += 1
+            }
+        }
+    }
+
+    // scale factors (and invalid suffixes that are reported by the parser)
+    while *pos < bytes.len() && (bytes[*pos].is_ascii_alphanumeric() || bytes[*pos] == b'_') {
+        *pos += 1
+    }
+}
+
+/// Parses a number literal including a spectre scale factor (for example `1.5k` or `10u`)
+pub(super) fn parse_number(text: &str) -> Option<f64> {
+    let suffix_start = text
+        .char_indices()
+        .find(|&(i, c)| {
+            c.is_ascii_alphabetic()
+                && !(matches!(c, 'e' | 'E')
+                    && text[i + 1..]
+                        .starts_with(|c: char| c.is_ascii_digit() || c == '+' || c == '-'))
+        })
+        .map_or(text.len(), |(i, _)| i);
+
+    let (mantissa, suffix) = text.split_at(suffix_start);
+    let scale = match suffix {
+        "" => 1.0,
+        "T" => 1e12,
+        "G" => 1e9,
+        "M" => 1e6,
+        "K" | "k" => 1e3,
+        "m" => 1e-3,
+        "u" => 1e-6,
+        "n" => 1e-9,
+        "p" => 1e-12,
+        "f" => 1e-15,
+        "a" => 1e-18,
+        _ => return None,
+    };
+    mantissa.parse::<f64>().ok().map(|val| val * scale)
+}
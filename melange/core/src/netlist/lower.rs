@@ -0,0 +1,394 @@
+use std::rc::Rc;
+
+use ahash::{AHashMap, AHashSet};
+use anyhow::{bail, Context, Result};
+use stdx::iter::zip;
+
+use crate::circuit::Circuit;
+use crate::elaboration::{CircuitInstanceDescription, CircuitModelDescription, ParamDescription};
+use crate::expr::CircuitParamCtx;
+use crate::netlist::ast::{self, Instance, Param, Statement, Subckt};
+use crate::netlist::{Analysis, Netlist};
+use crate::{veriloga, Arena, Expr};
+
+/// Converts the syntax tree of a netlist into a flat [`Circuit`].
+/// Subcircuit instances are expanded recursively.
+pub(super) fn lower(
+    name: String,
+    statements: Vec<Statement>,
+    earena: &mut Arena,
+    opts: &veriloga::Opts,
+) -> Result<Netlist> {
+    let mut circ = Circuit::new(name, earena);
+    // Verilog-A devices can be used before the ahdl_include statement
+    for stmt in &statements {
+        if let Statement::AhdlInclude(path) = stmt {
+            circ.load_veriloga_file(path.clone(), opts)?;
+        }
+    }
+
+    let mut lowering =
+        Lowering { circ, earena, globals: AHashSet::default(), analyses: Vec::new() };
+    let mut scope = Scope {
+        parent: None,
+        prefix: String::new(),
+        ctx: lowering.circ.ctx,
+        subckt: None,
+        params: AHashMap::default(),
+        models: AHashMap::default(),
+        subckts: AHashMap::default(),
+        nodes: AHashMap::default(),
+    };
+    lowering.lower_body(&mut scope, &statements, AHashMap::default())?;
+
+    Ok(Netlist { circuit: lowering.circ, analyses: lowering.analyses })
+}
+
+/// The names visible within the top level netlist or an expanded subcircuit instance
+struct Scope<'a> {
+    parent: Option<&'a Scope<'a>>,
+    /// prepended to the names of all instances, models and internal nodes
+    prefix: String,
+    ctx: CircuitParamCtx,
+    /// the subcircuit that is expanded in this scope
+    subckt: Option<Rc<Subckt>>,
+    params: AHashMap<String, Expr>,
+    /// maps the netlist name of a model to its name within the circuit
+    models: AHashMap<String, String>,
+    subckts: AHashMap<String, Rc<Subckt>>,
+    /// maps the terminals of a subcircuit to the nodes connected by the instance
+    nodes: AHashMap<String, String>,
+}
+
+impl Scope<'_> {
+    fn lookup<T>(&self, f: impl Fn(&Self) -> Option<T>) -> Option<T> {
+        let mut scope = Some(self);
+        while let Some(current) = scope {
+            if let Some(res) = f(current) {
+                return Some(res);
+            }
+            scope = current.parent;
+        }
+        None
+    }
+
+    fn is_expanding(&self, subckt: &Rc<Subckt>) -> bool {
+        self.lookup(|scope| match &scope.subckt {
+            Some(expanded) if Rc::ptr_eq(expanded, subckt) => Some(()),
+            _ => None,
+        })
+        .is_some()
+    }
+}
+
+struct Lowering<'a> {
+    circ: Circuit,
+    earena: &'a mut Arena,
+    globals: AHashSet<String>,
+    analyses: Vec<Analysis>,
+}
+
+impl Lowering<'_> {
+    /// Lowers the statements of the top level netlist or a subcircuit.
+    /// `overrides` contains the parameter values specified by a subcircuit instance.
+    fn lower_body(
+        &mut self,
+        scope: &mut Scope,
+        body: &[Statement],
+        mut overrides: AHashMap<String, Expr>,
+    ) -> Result<()> {
+        // subcircuits and global nodes can be used before their declaration
+        for stmt in body {
+            match stmt {
+                Statement::Subckt(subckt) => {
+                    if scope.subckts.insert(subckt.name.clone(), subckt.clone()).is_some() {
+                        bail!("subckt '{}' was defined multiple times", subckt.name)
+                    }
+                }
+                Statement::Global(nodes) => self.globals.extend(nodes.iter().cloned()),
+                _ => (),
+            }
+        }
+
+        for stmt in body {
+            if let Statement::Parameters(params) = stmt {
+                for param in params {
+                    let val = match overrides.remove(&param.name) {
+                        Some(val) => val,
+                        None => self.lower_expr(scope, &param.val).with_context(|| {
+                            format!("while lowering parameter '{}'", param.name)
+                        })?,
+                    };
+                    let (id, read_expr) = self.earena.def_param(scope.ctx, param.name.clone())?;
+                    self.circ.param_assignments.insert(id, val);
+                    scope.params.insert(param.name.clone(), read_expr);
+                }
+            }
+        }
+
+        if let Some(name) = overrides.keys().next() {
+            let subckt = scope.subckt.as_ref().map_or("", |subckt| &subckt.name);
+            bail!("subckt '{subckt}' has no parameter '{name}'")
+        }
+
+        for stmt in body {
+            if let Statement::Model(model) = stmt {
+                let name = format!("{}{}", scope.prefix, model.name);
+                let parameters = self
+                    .lower_params(scope, &model.params)
+                    .with_context(|| format!("while lowering model '{name}'"))?;
+                let descr = CircuitModelDescription {
+                    name: name.clone(),
+                    device: model.device.clone(),
+                    parameters,
+                };
+                self.circ
+                    .elaborate_model(descr)
+                    .with_context(|| format!("while elaborating model '{name}'"))?;
+                scope.models.insert(model.name.clone(), name);
+            }
+        }
+
+        for stmt in body {
+            match stmt {
+                Statement::Instance(inst) => {
+                    let name = format!("{}{}", scope.prefix, inst.name);
+                    self.lower_instance(scope, inst)
+                        .with_context(|| format!("while elaborating instance '{name}'"))?;
+                }
+                Statement::Analysis(analysis) => {
+                    let parameters = self
+                        .lower_params(scope, &analysis.params)
+                        .with_context(|| format!("while lowering analysis '{}'", analysis.name))?;
+                    let nodes = analysis
+                        .nodes
+                        .iter()
+                        .map(|node| {
+                            let node = self.node_name(scope, node);
+                            self.circ.node(node)
+                        })
+                        .collect();
+                    self.analyses.push(Analysis {
+                        name: format!("{}{}", scope.prefix, analysis.name),
+                        kind: analysis.kind,
+                        nodes,
+                        parameters,
+                    });
+                }
+                _ => (),
+            }
+        }
+
+        Ok(())
+    }
+
+    fn lower_instance(&mut self, scope: &Scope, inst: &Instance) -> Result<()> {
+        if let Some(subckt) = scope.lookup(|scope| scope.subckts.get(&inst.master).cloned()) {
+            return self.expand_subckt(scope, inst, subckt);
+        }
+
+        let master = scope
+            .lookup(|scope| scope.models.get(&inst.master).cloned())
+            .unwrap_or_else(|| inst.master.clone());
+        let descr = CircuitInstanceDescription {
+            name: format!("{}{}", scope.prefix, inst.name),
+            master,
+            parameters: self.lower_params(scope, &inst.params)?,
+            terminal_connections: inst
+                .nodes
+                .iter()
+                .map(|node| self.node_name(scope, node))
+                .collect(),
+        };
+        self.circ.elaborate_instance(descr)?;
+        Ok(())
+    }
+
+    fn expand_subckt(&mut self, scope: &Scope, inst: &Instance, subckt: Rc<Subckt>) -> Result<()> {
+        if scope.is_expanding(&subckt) {
+            bail!("subckt '{}' instantiates itself", subckt.name)
+        }
+
+        if inst.nodes.len() != subckt.terminals.len() {
+            bail!(
+                "subckt '{}' has {} terminals but {} terminals were connected",
+                subckt.name,
+                subckt.terminals.len(),
+                inst.nodes.len()
+            )
+        }
+
+        let mut overrides = AHashMap::with_capacity(inst.params.len());
+        for param in &inst.params {
+            let val = self.lower_expr(scope, &param.val)?;
+            overrides.insert(param.name.clone(), val);
+        }
+
+        let nodes = zip(&subckt.terminals, &inst.nodes)
+            .map(|(terminal, node)| (terminal.clone(), self.node_name(scope, node)))
+            .collect();
+
+        let mut child = Scope {
+            parent: Some(scope),
+            prefix: format!("{}{}.", scope.prefix, inst.name),
+            ctx: self.earena.add_ctx(),
+            subckt: Some(subckt.clone()),
+            params: AHashMap::default(),
+            models: AHashMap::default(),
+            subckts: AHashMap::default(),
+            nodes,
+        };
+        self.lower_body(&mut child, &subckt.body, overrides)
+    }
+
+    /// Returns the name of the circuit node that `node` refers to within `scope`
+    fn node_name(&self, scope: &Scope, node: &str) -> String {
+        if node == "0" {
+            return "ground".to_owned();
+        }
+
+        if self.globals.contains(node) {
+            return node.to_owned();
+        }
+
+        match scope.nodes.get(node) {
+            Some(node) => node.clone(),
+            None => format!("{}{node}", scope.prefix),
+        }
+    }
+
+    fn lower_params(&mut self, scope: &Scope, params: &[Param]) -> Result<ParamDescription> {
+        params
+            .iter()
+            .map(|param| {
+                let val = self.lower_expr(scope, &param.val)?;
+                Ok((param.name.clone(), val))
+            })
+            .collect()
+    }
+
+    fn lower_expr(&mut self, scope: &Scope, expr: &ast::Expr) -> Result<Expr> {
+        let earena = &mut *self.earena;
+        let res = match expr {
+            ast::Expr::Num(val) => (*val).into(),
+            ast::Expr::Str(val) => earena.str_val(val).into(),
+            ast::Expr::Ident(name) => {
+                let param = scope.lookup(|scope| scope.params.get(name).copied()).or_else(|| {
+                    earena.lookup_param_by_name(CircuitParamCtx::ROOT, name).map(|(_, val)| val)
+                });
+                match param {
+                    Some(param) => param,
+                    None => bail!("unknown parameter '{name}'"),
+                }
+            }
+            ast::Expr::Call(name, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| self.lower_expr(scope, arg))
+                    .collect::<Result<Vec<_>>>()?;
+                lower_call(self.earena, name, &args)?
+            }
+            ast::Expr::Unary(op, arg) => {
+                let arg = self.lower_expr(scope, arg)?;
+                let earena = &mut *self.earena;
+                match *op {
+                    "-" => Expr::neg(earena, arg)?,
+                    "!" => Expr::eq(earena, arg, 0f64.into()),
+                    "+" => arg,
+                    _ => bail!("unknown unary operator '{op}'"),
+                }
+            }
+            ast::Expr::Binary(op, lhs, rhs) => {
+                let lhs = self.lower_expr(scope, lhs)?;
+                let rhs = self.lower_expr(scope, rhs)?;
+                lower_binary(self.earena, op, lhs, rhs)?
+            }
+            ast::Expr::Cond(cond, then_val, else_val) => {
+                let cond = self.lower_expr(scope, cond)?;
+                let then_val = self.lower_expr(scope, then_val)?;
+                let else_val = self.lower_expr(scope, else_val)?;
+                Expr::cond(self.earena, cond, then_val, else_val)?
+            }
+        };
+        Ok(res)
+    }
+}
+
+fn lower_binary(earena: &mut Arena, op: &str, lhs: Expr, rhs: Expr) -> Result<Expr> {
+    match op {
+        "+" => Expr::add(earena, lhs, rhs),
+        "-" => {
+            let rhs = Expr::neg(earena, rhs)?;
+            Expr::add(earena, lhs, rhs)
+        }
+        "*" => Expr::mul(earena, lhs, rhs),
+        "/" => {
+            let rhs = Expr::inv(earena, rhs)?;
+            Expr::mul(earena, lhs, rhs)
+        }
+        "%" => Expr::modulo(earena, lhs, rhs),
+        "**" => Expr::pow(earena, lhs, rhs),
+        "==" => Ok(Expr::eq(earena, lhs, rhs)),
+        "!=" => Ok(Expr::neq(earena, lhs, rhs)),
+        "<" => Expr::lt(earena, lhs, rhs),
+        "<=" => Expr::le(earena, lhs, rhs),
+        ">" => Expr::lt(earena, rhs, lhs),
+        ">=" => Expr::le(earena, rhs, lhs),
+        "<<" => Expr::shl(earena, lhs, rhs),
+        ">>" => Expr::shr(earena, lhs, rhs),
+        "&&" => Expr::logic_and(earena, lhs, rhs),
+        "||" => Expr::logic_or(earena, lhs, rhs),
+        "&" => Expr::and(earena, lhs, rhs),
+        "|" => Expr::or(earena, lhs, rhs),
+        "^" => Expr::xolr(earena, lhs, rhs),
+        _ => unreachable!("unknown binary operator {op}"),
+    }
+}
+
+type UnaryBuilder = fn(&mut Arena, Expr) -> Result<Expr>;
+type BinaryBuilder = fn(&mut Arena, Expr, Expr) -> Result<Expr>;
+
+fn lower_call(earena: &mut Arena, name: &str, args: &[Expr]) -> Result<Expr> {
+    let unary: Option<UnaryBuilder> = match name {
+        "exp" => Some(Expr::exp),
+        "log" | "ln" => Some(Expr::log),
+        "log10" => Some(Expr::log10),
+        "sqrt" => Some(Expr::sqrt),
+        "abs" => Some(Expr::abs),
+        "sin" => Some(Expr::sin),
+        "cos" => Some(Expr::cos),
+        "tan" => Some(Expr::tan),
+        "asin" => Some(Expr::asin),
+        "acos" => Some(Expr::acos),
+        "atan" => Some(Expr::atam),
+        "sinh" => Some(Expr::sinh),
+        "cosh" => Some(Expr::cosh),
+        "tanh" => Some(Expr::tanh),
+        "asinh" => Some(Expr::asinh),
+        "atanh" => Some(Expr::atanh),
+        "ceil" => Some(Expr::ceil),
+        "floor" => Some(Expr::floor),
+        "int" => Some(Expr::int),
+        _ => None,
+    };
+    if let Some(builder) = unary {
+        return match *args {
+            [arg] => builder(earena, arg),
+            _ => bail!("{name} expects 1 argument but {} were provided", args.len()),
+        };
+    }
+
+    let binary: BinaryBuilder = match name {
+        "pow" => Expr::pow,
+        "min" => Expr::min,
+        "max" => Expr::max,
+        "atan2" => Expr::atan2,
+        "hypot" => Expr::hypot,
+        "fmod" => Expr::fmod,
+        _ => bail!("unknown function '{name}'"),
+    };
+    match *args {
+        [lhs, rhs] => binary(earena, lhs, rhs),
+        _ => bail!("{name} expects 2 arguments but {} were provided", args.len()),
+    }
+}
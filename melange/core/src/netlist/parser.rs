@@ -0,0 +1,443 @@
+use std::fs::read_to_string;
+use std::rc::Rc;
+
+use anyhow::{bail, Context, Result};
+use camino::Utf8Path;
+use log::warn;
+
+use crate::netlist::ast::{Analysis, Expr, Instance, Model, Param, Statement, Subckt};
+use crate::netlist::lexer::{parse_number, tokenize, Token, TokenKind};
+use crate::netlist::AnalysisKind;
+
+/// Deeper include chains are almost certainly caused by a file including itself.
+const MAX_INCLUDE_DEPTH: u32 = 32;
+
+/// binary operators and their precedence (higher binds stronger)
+const BINARY_OPS: [(&str, u8); 19] = [
+    ("||", 1),
+    ("&&", 2),
+    ("|", 3),
+    ("^", 4),
+    ("&", 5),
+    ("==", 6),
+    ("!=", 6),
+    ("<", 7),
+    ("<=", 7),
+    (">", 7),
+    (">=", 7),
+    ("<<", 8),
+    (">>", 8),
+    ("+", 9),
+    ("-", 9),
+    ("*", 10),
+    ("/", 10),
+    ("%", 10),
+    ("**", 11),
+];
+const POW_PRECEDENCE: u8 = 11;
+
+/// The statements of a file and the sections of the libraries defined within that file
+#[derive(Default)]
+pub(super) struct SourceFile {
+    pub statements: Vec<Statement>,
+    pub sections: Vec<(String, Vec<Statement>)>,
+}
+
+pub(super) fn parse_file(path: &Utf8Path, include_depth: u32) -> Result<SourceFile> {
+    let src = read_to_string(path).with_context(|| format!("failed to read {path}"))?;
+    parse_src(&src, path, include_depth)
+}
+
+/// Parses spectre source code. `path` is used for error messages and to resolve includes.
+pub(super) fn parse_src(src: &str, path: &Utf8Path, include_depth: u32) -> Result<SourceFile> {
+    let tokens = tokenize(src).with_context(|| format!("failed to parse {path}"))?;
+    let mut parser = Parser { tokens, pos: 0, path, include_depth, res: SourceFile::default() };
+    let mut statements = Vec::new();
+    parser.statements(&mut statements, &[])?;
+    parser.res.statements = statements;
+    Ok(parser.res)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token<'a>>,
+    pos: usize,
+    path: &'a Utf8Path,
+    include_depth: u32,
+    res: SourceFile,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Token<'a> {
+        self.nth(0)
+    }
+
+    fn nth(&self, n: usize) -> Token<'a> {
+        self.tokens[(self.pos + n).min(self.tokens.len() - 1)]
+    }
+
+    fn bump(&mut self) -> Token<'a> {
+        let tok = self.peek();
+        if tok.kind != TokenKind::Eof {
+            self.pos += 1;
+        }
+        tok
+    }
+
+    fn eat(&mut self, kind: TokenKind) -> bool {
+        let matches = self.peek().kind == kind;
+        if matches {
+            self.bump();
+        }
+        matches
+    }
+
+    fn error(&self, msg: impl std::fmt::Display) -> anyhow::Error {
+        anyhow::anyhow!("{}:{}: {msg}", self.path, self.peek().line)
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<Token<'a>> {
+        let tok = self.peek();
+        if tok.kind != kind {
+            return Err(self.error(format!("expected {expected} but found '{}'", tok.text.trim())));
+        }
+        Ok(self.bump())
+    }
+
+    fn expect_ident(&mut self) -> Result<&'a str> {
+        self.expect(TokenKind::Ident, "a name").map(|tok| tok.text)
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.peek().text != keyword {
+            return Err(self.error(format!("expected '{keyword}'")));
+        }
+        self.bump();
+        Ok(())
+    }
+
+    fn expect_newline(&mut self) -> Result<()> {
+        self.expect(TokenKind::Newline, "the end of the line").map(|_| ())
+    }
+
+    /// Parses statements until a line starts with one of the `end` keywords
+    /// (or the end of the file if `end` is empty). The keyword itself is not consumed.
+    fn statements(&mut self, dst: &mut Vec<Statement>, end: &[&str]) -> Result<()> {
+        loop {
+            let tok = self.peek();
+            match tok.kind {
+                TokenKind::Eof if end.is_empty() => return Ok(()),
+                TokenKind::Eof => return Err(self.error(format!("expected '{}'", end[0]))),
+                TokenKind::Newline => {
+                    self.bump();
+                }
+                TokenKind::Ident if end.contains(&tok.text) => return Ok(()),
+                _ => self.statement(dst)?,
+            }
+        }
+    }
+
+    fn statement(&mut self, dst: &mut Vec<Statement>) -> Result<()> {
+        let line = self.peek().line;
+        let first = self.expect_ident()?;
+        match first {
+            "simulator" => {
+                self.expect_keyword("lang")?;
+                self.expect(TokenKind::Assign, "'='")?;
+                let lang = self.expect_ident()?;
+                if lang != "spectre" {
+                    bail!("{}:{line}: only the spectre netlist language is supported", self.path)
+                }
+            }
+            "global" => {
+                let mut nodes = Vec::new();
+                while self.peek().kind != TokenKind::Newline {
+                    nodes.push(self.node()?);
+                }
+                dst.push(Statement::Global(nodes));
+            }
+            "include" => {
+                let path = self.expect(TokenKind::Str, "a file name")?.text;
+                let section = if self.peek().text == "section" {
+                    self.bump();
+                    self.expect(TokenKind::Assign, "'='")?;
+                    Some(self.expect_ident()?)
+                } else {
+                    None
+                };
+                self.include(path, section, dst)?;
+            }
+            "ahdl_include" => {
+                let path = self.expect(TokenKind::Str, "a file name")?.text;
+                dst.push(Statement::AhdlInclude(self.resolve_path(path)));
+            }
+            "parameters" => dst.push(Statement::Parameters(self.params()?)),
+            "model" => {
+                let name = self.expect_ident()?.to_owned();
+                let device = self.expect_ident()?.to_owned();
+                let params = self.params()?;
+                dst.push(Statement::Model(Model { name, device, params }))
+            }
+            "inline" | "subckt" => {
+                if first == "inline" {
+                    self.expect_keyword("subckt")?;
+                }
+                dst.push(Statement::Subckt(Rc::new(self.subckt()?)));
+            }
+            "library" => {
+                self.library()?;
+            }
+            "save" => {
+                warn!("{}:{line}: save statements are ignored", self.path);
+                while self.peek().kind != TokenKind::Newline {
+                    self.bump();
+                }
+            }
+            name => self.instance(name, line, dst)?,
+        }
+
+        self.expect_newline()
+    }
+
+    fn resolve_path(&self, path: &str) -> camino::Utf8PathBuf {
+        match self.path.parent() {
+            Some(dir) => dir.join(path),
+            None => path.into(),
+        }
+    }
+
+    fn include(
+        &mut self,
+        path: &str,
+        section: Option<&str>,
+        dst: &mut Vec<Statement>,
+    ) -> Result<()> {
+        if self.include_depth == MAX_INCLUDE_DEPTH {
+            return Err(self.error("maximum include depth exceeded (recursive include?)"));
+        }
+        let line = self.peek().line;
+        let path = self.resolve_path(path);
+        let file = parse_file(&path, self.include_depth + 1)
+            .with_context(|| format!("included from {}:{line}", self.path))?;
+        match section {
+            Some(section) => {
+                let statements = file
+                    .sections
+                    .into_iter()
+                    .find(|(name, _)| name == section)
+                    .map(|(_, statements)| statements);
+                match statements {
+                    Some(statements) => dst.extend(statements),
+                    None => {
+                        return Err(self.error(format!("section {section} not found in {path}")))
+                    }
+                }
+            }
+            None => dst.extend(file.statements),
+        }
+        Ok(())
+    }
+
+    fn library(&mut self) -> Result<()> {
+        self.expect_ident()?;
+        self.expect_newline()?;
+        loop {
+            let mut statements = Vec::new();
+            self.statements(&mut statements, &["section", "endlibrary"])?;
+            if !statements.is_empty() {
+                return Err(self.error("only sections are allowed within a library"));
+            }
+            if self.bump().text == "endlibrary" {
+                if self.peek().kind == TokenKind::Ident {
+                    self.bump();
+                }
+                return Ok(());
+            }
+
+            let name = self.expect_ident()?.to_owned();
+            self.expect_newline()?;
+            self.statements(&mut statements, &["endsection"])?;
+            self.bump();
+            if self.peek().kind == TokenKind::Ident {
+                self.bump();
+            }
+            self.expect_newline()?;
+            self.res.sections.push((name, statements));
+        }
+    }
+
+    fn subckt(&mut self) -> Result<Subckt> {
+        let name = self.expect_ident()?.to_owned();
+        let mut terminals = Vec::new();
+        let parens = self.eat(TokenKind::LParen);
+        while !matches!(self.peek().kind, TokenKind::Newline | TokenKind::RParen) {
+            terminals.push(self.node()?);
+        }
+        if parens {
+            self.expect(TokenKind::RParen, "')'")?;
+        }
+        self.expect_newline()?;
+
+        let mut body = Vec::new();
+        self.statements(&mut body, &["ends"])?;
+        self.bump();
+        if self.peek().kind == TokenKind::Ident {
+            let end_name = self.bump().text;
+            if end_name != name {
+                return Err(
+                    self.error(format!("expected 'ends {name}' but found 'ends {end_name}'"))
+                );
+            }
+        }
+
+        Ok(Subckt { name, terminals, body })
+    }
+
+    fn instance(&mut self, name: &str, line: u32, dst: &mut Vec<Statement>) -> Result<()> {
+        let mut nodes = Vec::new();
+        let master = if self.eat(TokenKind::LParen) {
+            while !self.eat(TokenKind::RParen) {
+                nodes.push(self.node()?);
+            }
+            self.expect_ident()?.to_owned()
+        } else {
+            // without parenthesis the master is the last name before the parameters
+            while matches!(self.peek().kind, TokenKind::Ident | TokenKind::Number)
+                && self.nth(1).kind != TokenKind::Assign
+            {
+                nodes.push(self.node()?);
+            }
+            match nodes.pop() {
+                Some(master) if !master.starts_with(|c: char| c.is_ascii_digit()) => master,
+                _ => return Err(self.error(format!("expected the master of '{name}'"))),
+            }
+        };
+        let params = self.params()?;
+        let name = name.to_owned();
+
+        let kind = match &*master {
+            "dc" => AnalysisKind::Dc,
+            "ac" => AnalysisKind::Ac,
+            "tran" => AnalysisKind::Tran,
+            "noise" => AnalysisKind::Noise,
+            "options" | "info" => {
+                warn!("{}:{line}: {master} statements are ignored", self.path);
+                return Ok(());
+            }
+            _ => {
+                dst.push(Statement::Instance(Instance { name, nodes, master, params }));
+                return Ok(());
+            }
+        };
+
+        dst.push(Statement::Analysis(Analysis { name, kind, nodes, params }));
+        Ok(())
+    }
+
+    fn node(&mut self) -> Result<String> {
+        let tok = self.peek();
+        match tok.kind {
+            TokenKind::Ident | TokenKind::Number => {
+                self.bump();
+                Ok(tok.text.to_owned())
+            }
+            _ => Err(self.error(format!("expected a node but found '{}'", tok.text.trim()))),
+        }
+    }
+
+    fn params(&mut self) -> Result<Vec<Param>> {
+        let mut params = Vec::new();
+        while self.peek().kind == TokenKind::Ident && self.nth(1).kind == TokenKind::Assign {
+            let name = self.bump().text.to_owned();
+            self.bump();
+            let val = self.expr()?;
+            params.push(Param { name, val });
+        }
+        Ok(params)
+    }
+
+    fn expr(&mut self) -> Result<Expr> {
+        let cond = self.binary(0)?;
+        if !self.eat(TokenKind::Question) {
+            return Ok(cond);
+        }
+        let then_val = self.expr()?;
+        self.expect(TokenKind::Colon, "':'")?;
+        let else_val = self.expr()?;
+        Ok(Expr::Cond(Box::new(cond), Box::new(then_val), Box::new(else_val)))
+    }
+
+    fn binary(&mut self, min_precedence: u8) -> Result<Expr> {
+        let mut lhs = self.unary()?;
+        loop {
+            let tok = self.peek();
+            if tok.kind != TokenKind::Op {
+                break;
+            }
+            let (op, precedence) = match BINARY_OPS.iter().find(|(op, _)| *op == tok.text) {
+                Some(&(op, precedence)) if precedence >= min_precedence => (op, precedence),
+                _ => break,
+            };
+            self.bump();
+            // ** is right associative
+            let rhs_precedence = if op == "**" { precedence } else { precedence + 1 };
+            let rhs = self.binary(rhs_precedence)?;
+            lhs = Expr::Binary(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        let tok = self.peek();
+        let op = match tok.text {
+            "-" if tok.kind == TokenKind::Op => "-",
+            "+" if tok.kind == TokenKind::Op => "+",
+            "!" if tok.kind == TokenKind::Op => "!",
+            _ => return self.primary(),
+        };
+        self.bump();
+        // unary operators bind weaker than `**`
+        let arg = self.binary(POW_PRECEDENCE)?;
+        Ok(Expr::Unary(op, Box::new(arg)))
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        let tok = self.peek();
+        let res = match tok.kind {
+            TokenKind::Number => match parse_number(tok.text) {
+                Some(val) => Expr::Num(val),
+                None => return Err(self.error(format!("invalid number '{}'", tok.text))),
+            },
+            TokenKind::Str => Expr::Str(tok.text.to_owned()),
+            TokenKind::Ident if self.nth(1).kind == TokenKind::LParen => {
+                self.bump();
+                self.bump();
+                let mut args = Vec::new();
+                if !self.eat(TokenKind::RParen) {
+                    loop {
+                        args.push(self.expr()?);
+                        if self.eat(TokenKind::RParen) {
+                            break;
+                        }
+                        self.expect(TokenKind::Comma, "','")?;
+                    }
+                }
+                return Ok(Expr::Call(tok.text.to_owned(), args));
+            }
+            TokenKind::Ident => Expr::Ident(tok.text.to_owned()),
+            TokenKind::LParen => {
+                self.bump();
+                let res = self.expr()?;
+                self.expect(TokenKind::RParen, "')'")?;
+                return Ok(res);
+            }
+            TokenKind::LBracket => return Err(self.error("lists are not supported")),
+            _ => {
+                return Err(
+                    self.error(format!("expected an expression but found '{}'", tok.text.trim()))
+                )
+            }
+        };
+        self.bump();
+        Ok(res)
+    }
+}
@@ -0,0 +1,118 @@
+use anyhow::Result;
+
+use crate::expr::CircuitParam;
+use crate::netlist::{AnalysisKind, Netlist};
+use crate::simulation::SimConfig;
+use crate::tests::parse;
+use crate::{Arena, ExprEvalCtx};
+
+#[test]
+fn divider() -> Result<()> {
+    let src = r#"
+// a resistive divider
+simulator lang=spectre
+parameters rtot=2k vin=2
+v1 (in 0) vsource dc=vin
+r1 (in out) resistor r=rtot/2
+r2 out 0 resistor \
+    r=rtot - 1k
+op1 dc
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, analyses } = parse(src, &mut arena)?;
+    assert_eq!(analyses.len(), 1);
+    assert_eq!(analyses[0].kind, AnalysisKind::Dc);
+
+    let out = circuit.lookup_node("out").expect("node out");
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let solution = sim.dc_op()?;
+    assert!((solution[out] - 1.0).abs() < 1e-9);
+    Ok(())
+}
+
+#[test]
+fn subckt() -> Result<()> {
+    let src = r#"
+subckt div (a b)
+parameters r=1k
+    r1 (a mid) resistor r=r
+    r2 (mid b) resistor r=r
+ends div
+v1 (in 0) vsource dc=1
+x1 (in 0) div r=2k
+"#;
+    let mut arena = Arena::new();
+    let netlist = parse(src, &mut arena)?;
+    let circuit = &netlist.circuit;
+    assert!(circuit.lookup_node("x1.mid").is_some());
+    assert!(circuit.lookup_node("mid").is_none());
+    assert!(circuit.lookup_instance("x1.r1").is_some());
+    assert!(circuit.lookup_instance("r1").is_none());
+    assert_eq!(circuit.num_instances(), 3);
+    Ok(())
+}
+
+#[test]
+fn recursive_subckt() {
+    let src = r#"
+subckt loop (a b)
+    x1 (a b) loop
+ends
+x1 (a 0) loop
+"#;
+    let mut arena = Arena::new();
+    assert!(parse(src, &mut arena).is_err());
+}
+
+#[test]
+fn syntax_error_location() {
+    let src = "r1 (a 0) resistor r=(1k\n";
+    let mut arena = Arena::new();
+    let err = parse(src, &mut arena).err().expect("missing parenthesis is an error");
+    assert!(err.to_string().starts_with("test:1:"), "{err}");
+}
+
+#[test]
+fn negative_values() -> Result<()> {
+    let src = r#"
+parameters a=4
+v1 (in 0) vsource dc=-a
+r1 (in 0) resistor r=1k
+v2 (neg 0) vsource dc=-0.5
+r2 (neg 0) resistor r=1k
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let inp = circuit.lookup_node("in").expect("node in");
+    let neg = circuit.lookup_node("neg").expect("node neg");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let solution = sim.dc_op()?;
+    // a unary minus negates its argument instead of inverting it
+    assert!((solution[inp] + 4.0).abs() < 1e-9);
+    assert!((solution[neg] + 0.5).abs() < 1e-9);
+    Ok(())
+}
+
+#[test]
+fn unary_plus() -> Result<()> {
+    let src = r#"
+parameters a=4
+v1 (in 0) vsource dc=+a
+r1 (in 0) resistor r=1k
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let inp = circuit.lookup_node("in").expect("node in");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let solution = sim.dc_op()?;
+    assert!((solution[inp] - 4.0).abs() < 1e-9);
+    Ok(())
+}
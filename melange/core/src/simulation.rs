@@ -1,4 +1,5 @@
-use std::mem::replace;
+use std::cell::Cell;
+use std::mem::{replace, swap};
 use std::rc::Rc;
 
 use anyhow::{bail, Context, Result};
@@ -9,16 +10,30 @@ use stdx::iter::zip;
 use typed_index_collections::{TiSlice, TiVec};
 
 use crate::circuit::{CircuitModelSrc, InstanceId, ModelId, Node};
+pub use crate::devices::OpVar;
 use crate::devices::{InstanceImpl, ModelImpl, Type};
 use crate::expr::{CircuitParam, ExprEvalCtxRef};
+pub use crate::simulation::flags::EvalRetFlags;
 use crate::simulation::flags::{EvalFlags, OperatingPointAnalysis, SimulationState};
 pub use crate::simulation::matrix::MatrixEntryIter;
 use crate::simulation::matrix::{MatrixBuilder, SimulationMatrix};
+pub use crate::simulation::noise::{NoiseContribution, NoiseSolution};
+use crate::simulation::sweep::SweepTarget;
+pub use crate::simulation::sweep::{DcSweepSolution, SweepParam};
+use crate::simulation::tran::Integration;
+pub use crate::simulation::tran::{IntegrationMethod, TranSolution};
 use crate::utils::PrettyPrint;
 use crate::{Arena, Circuit, Value};
 
 mod flags;
+mod homotopy;
 mod matrix;
+mod noise;
+mod op_vars;
+mod sweep;
+#[cfg(test)]
+mod tests;
+mod tran;
 
 pub struct Simulation<'a> {
     circ: &'a Circuit,
@@ -34,10 +49,16 @@ pub struct Simulation<'a> {
     ac_solution: TiVec<Node, Complex64>,
     residual_resist: TiVec<Node, f64>,
     residual_react: TiVec<Node, f64>,
+    /// states of the limiting functions, swapped after every Newton iteration
+    prev_state: Vec<Cell<f64>>,
+    next_state: Vec<Cell<f64>>,
+    tran_solution: TranSolution,
     pub config: SimConfig,
     state: SimulationState,
 
     omega: f64,
+    /// conductance between every unknown and ground, only nonzero during gmin stepping
+    gshunt: f64,
 }
 
 #[derive(Debug, Clone)]
@@ -46,12 +67,15 @@ pub struct NodeInfo {
     pub atol: f64,
     pub units: &'static str,
     pub residual_units: &'static str,
+    /// The unknown is a flow (branch current) instead of a node potential
+    pub is_flow: bool,
 }
 
 pub struct SimBuilder<'a> {
     instance: InstanceId,
     matrix_builder: &'a mut MatrixBuilder,
     node_info: &'a mut TiVec<Node, NodeInfo>,
+    num_states: u32,
     circ: &'a Circuit,
     pub config: &'a SimConfig,
 }
@@ -63,11 +87,11 @@ impl<'a> SimBuilder<'a> {
     }
 
     pub fn new_internal_branch(&mut self, name: &'static str) -> Node {
-        self.new_internal_unknown(name, self.config.voltage_atol, "V", "A")
+        self.new_internal_unknown(name, self.config.voltage_atol, "V", "A", true)
     }
 
     pub fn new_internal_node(&mut self, name: &'static str) -> Node {
-        self.new_internal_unknown(name, self.config.current_atol, "A", "V")
+        self.new_internal_unknown(name, self.config.current_atol, "A", "V", false)
     }
     pub fn new_internal_unknown(
         &mut self,
@@ -75,14 +99,21 @@ impl<'a> SimBuilder<'a> {
         atol: f64,
         units: &'static str,
         residual_units: &'static str,
+        is_flow: bool,
     ) -> Node {
         let name = format!("{}::{name}", self.circ[self.instance].name);
-        self.node_info.push_and_get_key(NodeInfo { atol, name, units, residual_units })
+        self.node_info.push_and_get_key(NodeInfo { atol, name, units, residual_units, is_flow })
     }
 
     pub fn ensure_matrix_entry(&mut self, column: Node, row: Node) {
         self.matrix_builder.insert(self.instance, column, row)
     }
+
+    /// allocates a state for a limiting function and returns its index
+    pub fn new_state(&mut self) -> u32 {
+        self.num_states += 1;
+        self.num_states - 1
+    }
 }
 
 impl Circuit {
@@ -121,6 +152,7 @@ impl Circuit {
                 atol: config.current_atol,
                 units: "V",
                 residual_units: "A",
+                is_flow: false,
             })
             .collect();
 
@@ -137,7 +169,11 @@ impl Circuit {
             ac_solution: vec![Complex64::default(); self.num_nodes() as usize].into(),
             residual_resist: vec![0f64; self.num_nodes() as usize].into(),
             residual_react: vec![0f64; self.num_nodes() as usize].into(),
+            prev_state: Vec::new(),
+            next_state: Vec::new(),
+            tran_solution: TranSolution::default(),
             omega: 1.0,
+            gshunt: 0.0,
         };
 
         Ok(res)
@@ -201,10 +237,45 @@ impl Simulation<'_> {
         Ok(Self::matrix_table(&self.nodes, matrix))
     }
 
-    pub fn prepare_solver(&mut self, mut eval_ctx: ExprEvalCtxRef, arena: &Arena) -> Result<()> {
+    pub fn prepare_solver(&mut self, eval_ctx: ExprEvalCtxRef, arena: &Arena) -> Result<()> {
+        self.setup_solver(eval_ctx, arena, &[])
+    }
+
+    /// Like [`prepare_solver`](Self::prepare_solver) but the values in `overrides` take
+    /// precedence over the default values of the circuit parameters.
+    pub fn prepare_solver_with_overrides(
+        &mut self,
+        eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+        overrides: &[(CircuitParam, f64)],
+    ) -> Result<()> {
+        let overrides: Vec<_> =
+            overrides.iter().map(|&(param, val)| (SweepTarget::Circuit(param), val)).collect();
+        self.setup_solver(eval_ctx, arena, &overrides)
+    }
+
+    /// Evaluates all parameters and sets up the instances and the matrix.
+    /// `overrides` overwrite the values of individual parameters.
+    fn setup_solver(
+        &mut self,
+        mut eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+        overrides: &[(SweepTarget, f64)],
+    ) -> Result<()> {
         self.wipe_solution();
+        for &(target, val) in overrides {
+            if let SweepTarget::Circuit(param) = target {
+                eval_ctx.set_param(param, val.into());
+            }
+        }
+        let is_overridden = |param: CircuitParam| {
+            overrides
+                .iter()
+                .any(|&(target, _)| matches!(target, SweepTarget::Circuit(dst) if dst == param))
+        };
+
         for param in arena.ctx_params(self.circ.ctx) {
-            if self.circ.param_assignments.contains_key(&param) {
+            if self.circ.param_assignments.contains_key(&param) || is_overridden(param) {
                 continue;
             }
 
@@ -216,6 +287,9 @@ impl Simulation<'_> {
         }
 
         for (&param, &val) in self.circ.param_assignments.iter() {
+            if is_overridden(param) {
+                continue;
+            }
             let val = val.eval(eval_ctx.borrow());
             eval_ctx.set_param(param, val?);
         }
@@ -267,6 +341,7 @@ impl Simulation<'_> {
             instance: 0u32.into(),
             matrix_builder: &mut self.matrix_builder,
             node_info: &mut self.nodes,
+            num_states: 0,
             config: &self.config,
         };
 
@@ -302,10 +377,24 @@ impl Simulation<'_> {
                 }
             }
 
+            for &(target, val) in overrides {
+                if let SweepTarget::Instance(target_inst, param) = target {
+                    if target_inst == inst {
+                        instance_data.set_real_param(param, val);
+                    }
+                }
+            }
+
             builder.process_instance(inst);
             instance_data.process_params(temp, &mut builder, &self.circ[inst].connections)?;
         }
 
+        let num_states = builder.num_states as usize;
+        // required for gmin stepping
+        self.matrix_builder.ensure_diagonal(self.nodes.len());
+        self.prev_state.resize(num_states, Cell::new(0f64));
+        self.next_state.resize(num_states, Cell::new(0f64));
+
         let num_nodes = self.nodes.len();
         self.solution.resize(num_nodes, 0f64);
         self.residual_resist.resize(num_nodes, 0f64);
@@ -324,6 +413,9 @@ impl Simulation<'_> {
 
     pub fn wipe_solution(&mut self) {
         self.solution.raw.fill(0.0);
+        for state in self.prev_state.iter().chain(&self.next_state) {
+            state.set(0.0)
+        }
         self.state.clear()
     }
 
@@ -347,15 +439,20 @@ impl Simulation<'_> {
         self.state = SimulationState::AT_AC_OP;
     }
 
-    // pub fn noise_op(&mut self) -> Result<&TiSlice<Node, f64>> {
-    //     self.solve_op(OperatingPointAnalysis::Noise)?;
-    //     Ok(&self.solution)
-    // }
+    pub fn noise_op(&mut self) -> Result<&TiSlice<Node, f64>> {
+        self.solve_op(OperatingPointAnalysis::Noise)?;
+        Ok(&self.solution)
+    }
 
-    // pub fn restore_noise_op(&mut self, op: &TiSlice<Node, f64>) {
-    //     self.solution.copy_from_slice(op);
-    //     self.stat = SimulationState::AT_NOISE_OP;
-    // }
+    pub fn restore_noise_op(&mut self, op: &TiSlice<Node, f64>) {
+        self.solution.copy_from_slice(op);
+        self.state = SimulationState::AT_NOISE_OP;
+    }
+
+    /// The current solution (node voltages and internal unknowns)
+    pub fn solution(&self) -> &TiSlice<Node, f64> {
+        &self.solution
+    }
 
     pub fn set_initial_guess(&mut self, guess: &TiSlice<Node, f64>) {
         self.solution.copy_from_slice(guess);
@@ -370,29 +467,98 @@ impl Simulation<'_> {
         }
 
         let flags = analysis.eval_flags();
+        let maxiters = self.config.maxiters;
+        if self.newton(flags, 0f64, None, maxiters)?.is_none()
+            && !self.gmin_stepping(flags)?
+            && !self.source_stepping(flags)?
+        {
+            bail!(
+                "Simulation failed to converge after {maxiters} iterations \
+                 (gmin stepping and source stepping failed too)"
+            )
+        }
+
+        self.state = op_flag;
+        Ok(())
+    }
 
+    /// Runs Newton iterations starting at the current solution.
+    /// During time integration the charges are discretized as `alpha * q + hist`.
+    /// Returns the flags of the final evaluation or `None` if no solution
+    /// was found within `maxiters` iterations.
+    fn newton(
+        &mut self,
+        flags: EvalFlags,
+        abstime: f64,
+        integration: Option<&Integration>,
+        maxiters: u32,
+    ) -> Result<Option<EvalRetFlags>> {
         let debug = self.config.debug;
         let matrix =
             self.matrix.as_mut().context("Simulation must be populated before it can run")?;
+        let load_react = flags.contains(EvalFlags::CALC_REACT_RESIDUAL);
 
         let mut i = 0;
         loop {
-            let sim_info = SimInfo { abstime: 0f64, prev_solve: &self.solution, flags };
+            // limiting functions initialize the solution during the first iteration
+            // of an operating point
+            let flags = if i == 0 && flags.contains(EvalFlags::ANALYSIS_STATIC) {
+                flags | EvalFlags::INIT_LIM
+            } else {
+                flags
+            };
+            if load_react {
+                self.residual_react.raw.fill(0.0);
+            }
+
+            let sim_info = SimInfo {
+                abstime,
+                prev_solve: &self.solution,
+                prev_state: &self.prev_state,
+                next_state: &self.next_state,
+                flags,
+            };
+            let mut ret_flags = EvalRetFlags::empty();
             for inst in &mut *self.instance_data {
-                inst.eval(sim_info)?;
+                ret_flags |= inst.eval(sim_info)?;
 
                 // this is save because we call populate_matrix_ptrs during Simulation construction
-                unsafe { inst.load_matrix_resist() }
+                match integration {
+                    Some(integration) => unsafe { inst.load_matrix_tran(integration.alpha) },
+                    None => unsafe { inst.load_matrix_resist() },
+                }
                 inst.load_residual_resist(&self.solution, &mut self.residual_resist);
-
-                if analysis.time_integration() {
-                    let alpha = 0.0;
-                    unsafe { inst.load_matrix_react(alpha) }
+                if load_react {
                     inst.load_residual_react(&self.solution, &mut self.residual_react);
                 }
             }
+            swap(&mut self.prev_state, &mut self.next_state);
+
+            // the shunt conductances connect node potentials to ground,
+            // rows of branch currents are voltage equations that must remain unchanged
+            if self.gshunt != 0.0 {
+                for (node, (dst, &val)) in
+                    zip(&mut self.residual_resist.raw, &self.solution.raw).enumerate().skip(1)
+                {
+                    if self.nodes.raw[node].is_flow {
+                        continue;
+                    }
+                    let idx = node as i32 - 1;
+                    if let Some(entry) = matrix.nonlinear_matrix.get(idx, idx) {
+                        entry.set(entry.get() + self.gshunt);
+                    }
+                    *dst += self.gshunt * val;
+                }
+            }
 
-            // TODO time integration for tran analysis
+            if let Some(integration) = integration {
+                for ((dst, charge), hist) in
+                    zip(&mut self.residual_resist.raw, &self.residual_react.raw)
+                        .zip(&integration.hist.raw)
+                {
+                    *dst += integration.alpha * charge + hist;
+                }
+            }
 
             if debug {
                 print_stdout(Self::matrix_table(&self.nodes, &matrix.nonlinear_matrix)).unwrap();
@@ -411,7 +577,8 @@ impl Simulation<'_> {
 
             // reset matrix
             matrix.nonlinear_matrix.write_zero();
-            let mut found_solution = true;
+            // a limited solution is not the solution of the original system
+            let mut found_solution = !ret_flags.contains(EvalRetFlags::LIM);
             for ((dst, delta), node_info) in
                 zip(&mut self.solution.raw[1..], &mut self.residual_resist.raw[1..])
                     .zip(&self.nodes.raw[1..])
@@ -419,8 +586,8 @@ impl Simulation<'_> {
                 let delta = replace(delta, 0f64);
                 let new_val = *dst - delta;
                 let atol = node_info.atol;
-                let tol = atol.max(new_val * self.config.rtol);
-                if delta > tol {
+                let tol = atol.max(new_val.abs() * self.config.rtol);
+                if delta.abs() > tol {
                     found_solution = false;
                 }
                 *dst = new_val;
@@ -431,17 +598,14 @@ impl Simulation<'_> {
             }
 
             if found_solution && i > 0 {
-                break;
+                return Ok(Some(ret_flags));
             }
             i += 1;
 
-            if i == self.config.maxiters {
-                bail!("Simulation failed to converge after {i} iterations")
+            if i == maxiters {
+                return Ok(None);
             }
         }
-
-        self.state = op_flag;
-        Ok(())
     }
 
     pub fn set_omega(&mut self, omega: f64) {
@@ -463,31 +627,31 @@ impl Simulation<'_> {
         matrix.ac_matrix.write_zero();
         self.ac_solution.raw.fill(Complex64::default());
 
-        if self.state.contains(SimulationState::HAS_AC_EVAL) {
-            for inst in &mut *self.instance_data {
-                unsafe {
-                    inst.load_matrix_react(self.omega);
-                }
-                inst.load_ac_residual(&self.solution, &mut self.ac_solution);
-            }
-        } else {
-            let sim_info =
-                SimInfo { abstime: 0f64, prev_solve: &self.solution, flags: EvalFlags::AC };
-            for inst in &mut *self.instance_data {
+        // the devices only need to be evaluated once, other frequencies reuse the results
+        let eval = !self.state.contains(SimulationState::HAS_AC_EVAL);
+        let sim_info = SimInfo {
+            abstime: 0f64,
+            prev_solve: &self.solution,
+            prev_state: &self.prev_state,
+            next_state: &self.next_state,
+            flags: EvalFlags::AC,
+        };
+        for inst in &mut *self.instance_data {
+            if eval {
                 inst.eval(sim_info)?;
-
-                unsafe {
-                    inst.load_matrix_resist();
-                    inst.load_matrix_react(self.omega);
-                }
-                inst.load_ac_residual(&self.solution, &mut self.ac_solution);
             }
-            self.state.insert(SimulationState::HAS_AC_EVAL);
 
-            for (dst, src) in zip(matrix.ac_matrix.data(), matrix.nonlinear_matrix.data()) {
-                let val = Complex64::new(src.get(), dst.get().im);
-                dst.set(val);
+            unsafe {
+                inst.load_matrix_resist();
+                inst.load_matrix_react(self.omega);
             }
+            inst.load_ac_residual(&self.solution, &mut self.ac_solution);
+        }
+        self.state.insert(SimulationState::HAS_AC_EVAL);
+
+        for (dst, src) in zip(matrix.ac_matrix.data(), matrix.nonlinear_matrix.data()) {
+            let val = Complex64::new(src.get(), dst.get().im);
+            dst.set(val);
         }
 
         if self.config.debug {
@@ -527,6 +691,14 @@ pub struct SimConfig {
     pub voltage_atol: f64,
     pub current_atol: f64,
     pub rtol: f64,
+    /// maximum number of Newton iterations per timepoint
+    pub tran_maxiters: u32,
+    pub integration: IntegrationMethod,
+    /// factor by which the local truncation error may exceed the tolerances
+    pub trtol: f64,
+    pub charge_atol: f64,
+    /// initial conductance to ground of gmin stepping
+    pub gmin_start: f64,
 }
 
 impl Default for SimConfig {
@@ -537,6 +709,11 @@ impl Default for SimConfig {
             voltage_atol: 1e-6,
             current_atol: 1e-12,
             rtol: 1e-3,
+            tran_maxiters: 20,
+            integration: IntegrationMethod::Trapezoidal,
+            trtol: 7.0,
+            charge_atol: 1e-14,
+            gmin_start: 1e-3,
         }
     }
 }
@@ -545,5 +722,7 @@ impl Default for SimConfig {
 pub struct SimInfo<'a> {
     pub abstime: f64,
     pub prev_solve: &'a TiSlice<Node, f64>,
+    pub prev_state: &'a [Cell<f64>],
+    pub next_state: &'a [Cell<f64>],
     pub flags: EvalFlags,
 }
@@ -0,0 +1,116 @@
+//! Continuation methods that are used when Newton's method fails to find an operating point.
+//! Both methods solve a sequence of modified problems that gradually approach the original
+//! problem. The solution of each step is the initial guess of the next step.
+
+use anyhow::Result;
+use log::debug;
+
+use crate::simulation::flags::EvalFlags;
+use crate::simulation::Simulation;
+
+/// the smallest step a continuation method may take before giving up
+const MIN_STEP: f64 = 1e-4;
+
+impl Simulation<'_> {
+    /// Connects a conductance between every node and ground that is reduced until it vanishes.
+    /// Returns whether the operating point was found.
+    pub(super) fn gmin_stepping(&mut self, flags: EvalFlags) -> Result<bool> {
+        debug!("Newton failed, starting gmin stepping");
+        let maxiters = self.config.maxiters;
+        self.wipe_solution();
+        let mut converged = self.solution.clone();
+        // the conductance is stepped logarithmically
+        let mut gmin = self.config.gmin_start.log10();
+        let mut last_gmin = None;
+        let mut step = 1.0;
+
+        let res = loop {
+            let final_step = gmin < f64::EPSILON.log10();
+            self.gshunt = if final_step { 0.0 } else { 10f64.powf(gmin) };
+
+            let converged_step = match self.newton(flags, 0f64, None, maxiters) {
+                Ok(ret_flags) => ret_flags.is_some(),
+                Err(err) => break Err(err),
+            };
+            if converged_step {
+                if final_step {
+                    break Ok(true);
+                }
+                converged.copy_from_slice(&self.solution);
+                last_gmin = Some(gmin);
+                step = (step * 2.0).min(4.0);
+                gmin -= step;
+                continue;
+            }
+
+            self.solution.copy_from_slice(&converged);
+            match last_gmin {
+                Some(last_gmin) => {
+                    step /= 4.0;
+                    if step < MIN_STEP {
+                        break Ok(false);
+                    }
+                    gmin = last_gmin - step;
+                }
+                // even the initial conductance is too small
+                None if gmin < 0.0 => gmin += 1.0,
+                None => break Ok(false),
+            }
+        };
+
+        self.gshunt = 0.0;
+        res
+    }
+
+    /// Ramps all independent sources up from zero.
+    /// Returns whether the operating point was found.
+    pub(super) fn source_stepping(&mut self, flags: EvalFlags) -> Result<bool> {
+        debug!("Newton failed, starting source stepping");
+        let maxiters = self.config.maxiters;
+        self.wipe_solution();
+        let mut converged = self.solution.clone();
+        let mut factor = 0.0;
+        let mut last_factor = None;
+        let mut step = 0.1;
+
+        let res = loop {
+            self.set_source_factor(factor);
+            let converged_step = match self.newton(flags, 0f64, None, maxiters) {
+                Ok(ret_flags) => ret_flags.is_some(),
+                Err(err) => break Err(err),
+            };
+            if converged_step {
+                if factor == 1.0 {
+                    break Ok(true);
+                }
+                converged.copy_from_slice(&self.solution);
+                last_factor = Some(factor);
+                step = (step * 2.0).min(0.5);
+                factor = (factor + step).min(1.0);
+                continue;
+            }
+
+            self.solution.copy_from_slice(&converged);
+            match last_factor {
+                Some(last_factor) => {
+                    step /= 4.0;
+                    if step < MIN_STEP {
+                        break Ok(false);
+                    }
+                    factor = last_factor + step;
+                }
+                // not even the circuit without sources can be solved
+                None => break Ok(false),
+            }
+        };
+
+        self.set_source_factor(1.0);
+        res
+    }
+
+    fn set_source_factor(&mut self, factor: f64) {
+        for inst in &mut *self.instance_data {
+            inst.set_source_factor(factor)
+        }
+    }
+}
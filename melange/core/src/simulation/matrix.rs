@@ -89,6 +89,14 @@ impl MatrixBuilder {
         }
     }
 
+    /// Inserts the diagonal entries of the `num_nodes` unknowns. `num_nodes` includes
+    /// ground which has no row in the matrix, so its entry is skipped.
+    pub fn ensure_diagonal(&mut self, num_nodes: usize) {
+        for idx in 0..num_nodes as i32 - 1 {
+            self.inner.add_entry(idx, idx);
+        }
+    }
+
     pub fn reset(&mut self, circ: &Circuit) {
         self.inner.reset(circ.num_unknowns() as i32);
         for instance_entries in &mut *self.instance_entries {
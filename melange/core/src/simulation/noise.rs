@@ -0,0 +1,149 @@
+use std::f64::consts::TAU;
+
+use anyhow::{bail, Context, Result};
+use num_complex::Complex64;
+use stdx::iter::zip;
+use typed_index_collections::{TiSlice, TiVec};
+
+use crate::circuit::{InstanceId, Node};
+use crate::devices::NoiseSource;
+use crate::simulation::flags::EvalFlags;
+use crate::simulation::{SimInfo, Simulation};
+
+#[cfg(test)]
+mod tests;
+
+/// The output noise of a small-signal noise analysis.
+/// All densities are power spectral densities at the output (V²/Hz).
+#[derive(Debug, Clone, Default)]
+pub struct NoiseSolution {
+    pub freq: Vec<f64>,
+    pub total: Vec<f64>,
+    /// the contribution of each instance at each frequency
+    pub instances: TiVec<InstanceId, Vec<f64>>,
+    pub sources: Vec<NoiseContribution>,
+}
+
+/// The contribution of a single noise source at each frequency
+#[derive(Debug, Clone)]
+pub struct NoiseContribution {
+    pub instance: InstanceId,
+    pub name: String,
+    pub density: Vec<f64>,
+}
+
+impl Simulation<'_> {
+    /// Runs a small-signal noise analysis around the noise operating point.
+    /// The output noise is measured as the voltage between `out_hi` and `out_lo`.
+    ///
+    /// The transfer functions from all noise sources to the output are obtained
+    /// by solving a single adjoint (transposed) system at each frequency.
+    pub fn noise(&mut self, out_hi: Node, out_lo: Node, freqs: &[f64]) -> Result<NoiseSolution> {
+        if out_hi == out_lo {
+            bail!("noise output nodes must be distinct")
+        }
+
+        self.noise_op()?;
+
+        let sim_info = SimInfo {
+            abstime: 0f64,
+            prev_solve: &self.solution,
+            prev_state: &self.prev_state,
+            next_state: &self.next_state,
+            flags: EvalFlags::NOISE,
+        };
+        let mut sources: TiVec<InstanceId, Vec<NoiseSource>> = TiVec::new();
+        let mut correlations: TiVec<InstanceId, Vec<(usize, usize)>> = TiVec::new();
+        for inst in &mut *self.instance_data {
+            inst.eval(sim_info)?;
+            sources.push(inst.noise_sources());
+            correlations.push(inst.noise_correlations());
+        }
+
+        let mut res = NoiseSolution {
+            freq: freqs.to_owned(),
+            total: vec![0f64; freqs.len()],
+            instances: vec![vec![0f64; freqs.len()]; sources.len()].into(),
+            sources: sources
+                .iter_enumerated()
+                .flat_map(|(instance, sources)| {
+                    sources.iter().map(move |src| NoiseContribution {
+                        instance,
+                        name: src.name.clone(),
+                        density: vec![0f64; freqs.len()],
+                    })
+                })
+                .collect(),
+        };
+
+        let matrix =
+            self.matrix.as_mut().context("simulation must be setup before noise() is called")?;
+        let mut adjoint: TiVec<Node, Complex64> =
+            vec![Complex64::default(); self.nodes.len()].into();
+        let mut densities = Vec::new();
+        let mut cross_densities = Vec::new();
+
+        for (i, &freq) in freqs.iter().enumerate() {
+            matrix.nonlinear_matrix.write_zero();
+            matrix.ac_matrix.write_zero();
+            for inst in &*self.instance_data {
+                // this is save because we call populate_matrix_ptrs during Simulation construction
+                unsafe {
+                    inst.load_matrix_resist();
+                    inst.load_matrix_react(TAU * freq);
+                }
+            }
+            for (dst, src) in zip(matrix.ac_matrix.data(), matrix.nonlinear_matrix.data()) {
+                dst.set(Complex64::new(src.get(), dst.get().im));
+            }
+
+            if matrix.ac_matrix.lu_factorize(None) {
+                bail!("noise matrix is singular at {freq} Hz")
+            }
+
+            // the solution of the adjoint system are the transfer functions
+            // from a current between a node and ground to the output voltage
+            adjoint.raw.fill(Complex64::default());
+            adjoint[out_hi] += 1.0;
+            adjoint[out_lo] -= 1.0;
+            adjoint[Node::GROUND] = Complex64::default();
+            matrix.ac_matrix.solve_linear_tranose_system(&mut adjoint.raw[1..]);
+
+            let mut contributions = res.sources.iter_mut();
+            for (((inst, sources), correlations), instance_total) in
+                zip(self.instance_data.iter(), &sources).zip(&correlations).zip(&mut res.instances)
+            {
+                densities.clear();
+                densities.resize(sources.len(), 0f64);
+                inst.load_noise(freq, &mut densities);
+                for (src, &density) in zip(sources, &densities) {
+                    let gain = transfer_function(&adjoint, src).norm_sqr();
+                    let contribution = gain * density;
+                    contributions.next().unwrap().density[i] = contribution;
+                    instance_total[i] += contribution;
+                    res.total[i] += contribution;
+                }
+
+                // correlated sources contribute H_1*S_12*conj(H_2) + H_2*S_21*conj(H_1)
+                // = 2*Re(H_1*conj(H_2)*S_12) to the output noise. This cross term is only
+                // attributed to the instance and not to the individual sources
+                cross_densities.clear();
+                cross_densities.resize(correlations.len(), Complex64::default());
+                inst.load_noise_correlated(freq, &mut cross_densities);
+                for (&(src1, src2), &density) in zip(correlations, &cross_densities) {
+                    let gain = transfer_function(&adjoint, &sources[src1])
+                        * transfer_function(&adjoint, &sources[src2]).conj();
+                    let contribution = 2.0 * (gain * density).re;
+                    instance_total[i] += contribution;
+                    res.total[i] += contribution;
+                }
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+fn transfer_function(adjoint: &TiSlice<Node, Complex64>, src: &NoiseSource) -> Complex64 {
+    adjoint[src.hi] - adjoint[src.lo]
+}
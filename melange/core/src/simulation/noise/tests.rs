@@ -0,0 +1,78 @@
+use std::f64::consts::PI;
+
+use anyhow::Result;
+
+use crate::circuit::Node;
+use crate::expr::CircuitParam;
+use crate::netlist::Netlist;
+use crate::simulation::SimConfig;
+use crate::tests::parse;
+use crate::{Arena, ExprEvalCtx};
+
+const BOLTZMANN: f64 = 1.380649e-23;
+
+#[test]
+fn noise_resistor() -> Result<()> {
+    let src = r#"
+r1 (out 0) resistor r=1k
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let out = circuit.lookup_node("out").expect("node out");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let freqs = [1.0, 1e3, 1e6];
+    let res = sim.noise(out, Node::GROUND, &freqs)?;
+    // the thermal noise current 4kT/R is turned into a voltage by the same resistor
+    let expected = 4.0 * BOLTZMANN * 300.0 * 1e3;
+    for val in &res.total {
+        assert!((val - expected).abs() < 1e-9 * expected, "{val} != {expected}");
+    }
+    Ok(())
+}
+
+#[test]
+fn noise_superposition() -> Result<()> {
+    let src = r#"
+r1 (out 0) resistor r=1k
+r2 (out 0) resistor r=3k
+c1 (out 0) capacitor c=1n
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let out = circuit.lookup_node("out").expect("node out");
+    let r1 = circuit.lookup_instance("r1").expect("instance r1");
+    let r2 = circuit.lookup_instance("r2").expect("instance r2");
+    let c1 = circuit.lookup_instance("c1").expect("instance c1");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let freqs = [1e3, 1e5, 1e6];
+    let res = sim.noise(out, Node::GROUND, &freqs)?;
+    assert_eq!(res.sources.len(), 2);
+
+    let rp: f64 = 1e3 * 3e3 / 4e3;
+    for (i, freq) in freqs.into_iter().enumerate() {
+        let omega = 2.0 * PI * freq;
+        let gain = rp * rp / (1.0 + (omega * rp * 1e-9).powi(2));
+        let mut sum = 0.0;
+        for (src, r) in res.sources.iter().zip([1e3, 3e3]) {
+            let expected = 4.0 * BOLTZMANN * 300.0 / r * gain;
+            let val = src.density[i];
+            assert!((val - expected).abs() < 1e-9 * expected, "f = {freq}: {val} != {expected}");
+            assert_eq!(res.instances[src.instance][i], val);
+            sum += val;
+        }
+        assert_eq!([res.sources[0].instance, res.sources[1].instance], [r1, r2]);
+        assert_eq!(res.instances[c1][i], 0.0);
+
+        let total = res.total[i];
+        assert!((total - sum).abs() < 1e-12 * sum, "f = {freq}: {total} != {sum}");
+        let expected = 4.0 * BOLTZMANN * 300.0 / rp * gain;
+        assert!((total - expected).abs() < 1e-9 * expected, "f = {freq}: {total} != {expected}");
+    }
+    Ok(())
+}
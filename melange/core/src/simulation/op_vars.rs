@@ -0,0 +1,68 @@
+use anyhow::{bail, Result};
+use cli_table::{print_stdout, Cell, Style, Table};
+use typed_index_collections::TiVec;
+
+use crate::circuit::InstanceId;
+use crate::devices::OpVar;
+use crate::simulation::flags::{EvalFlags, SimulationState};
+use crate::simulation::{SimInfo, Simulation};
+use crate::utils::PrettyPrint;
+
+#[cfg(test)]
+mod tests;
+
+impl Simulation<'_> {
+    /// Solves the DC operating point and returns the operating point variables
+    /// (like `gm`, `gds` or `cgg`) of every instance.
+    pub fn dc_op_vars(&mut self) -> Result<TiVec<InstanceId, Vec<OpVar>>> {
+        self.dc_op()?;
+        self.op_vars()
+    }
+
+    /// Returns the operating point variables of every instance at the DC operating point
+    /// that was solved last, without solving it again.
+    pub fn op_vars(&mut self) -> Result<TiVec<InstanceId, Vec<OpVar>>> {
+        if !self.state.contains(SimulationState::AT_DC_OP) {
+            bail!("the DC operating point must be solved before its opvars are available")
+        }
+
+        // the devices are evaluated once more at the converged solution to store the opvars,
+        // limiting is disabled so the solution is used as is
+        let sim_info = SimInfo {
+            abstime: 0f64,
+            prev_solve: &self.solution,
+            prev_state: &self.prev_state,
+            next_state: &self.next_state,
+            flags: EvalFlags::OP_VARS,
+        };
+        let mut res = TiVec::with_capacity(self.instance_data.len());
+        for inst in &mut *self.instance_data {
+            inst.eval(sim_info)?;
+            res.push(inst.op_vars());
+        }
+        Ok(res)
+    }
+
+    pub fn print_op_vars(&mut self) -> Result<()> {
+        let op_vars = self.dc_op_vars()?;
+        let mut table = Vec::new();
+        for (inst, vars) in op_vars.iter_enumerated() {
+            for var in vars {
+                table.push(vec![
+                    self.circ[inst].name.clone().cell().bold(true),
+                    var.name.cell(),
+                    var.value.pretty_str().cell(),
+                    var.units.cell(),
+                ]);
+            }
+        }
+        let header = vec![
+            "instance".cell().bold(true),
+            "name".cell().bold(true),
+            "value".cell().bold(true),
+            "units".cell().bold(true),
+        ];
+        print_stdout(table.table().title(header)).unwrap();
+        Ok(())
+    }
+}
@@ -0,0 +1,86 @@
+use anyhow::Result;
+
+use crate::expr::CircuitParam;
+use crate::netlist::Netlist;
+use crate::simulation::SimConfig;
+use crate::tests::{parse, va_file};
+use crate::{Arena, ExprEvalCtx};
+
+/// A resistor that reports its conductance and current as operating point variables
+const OPVARS: &str = r#"
+`include "disciplines.vams"
+
+module opvars_res(a, c);
+    inout a, c;
+    electrical a, c;
+    parameter real r = 1k from (0:inf);
+    (*desc="conductance", units="S"*) real g;
+    (*desc="current", units="A"*) real id;
+
+    analog begin
+        g = 1 / r;
+        id = g * V(a, c);
+        I(a, c) <+ id;
+    end
+endmodule
+"#;
+
+#[test]
+fn dc_op_vars() -> Result<()> {
+    let opvars = va_file("dc_op_vars.va", OPVARS)?;
+    let src = format!(
+        r#"
+ahdl_include "{opvars}"
+v1 (in 0) vsource dc=3
+x1 (in 0) opvars_res r=2k
+"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let v1 = circuit.lookup_instance("v1").expect("instance v1");
+    let x1 = circuit.lookup_instance("x1").expect("instance x1");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let op_vars = sim.dc_op_vars()?;
+    // builtin devices have no opvars
+    assert!(op_vars[v1].is_empty());
+
+    let vars: Vec<_> =
+        op_vars[x1].iter().map(|var| (var.name, var.units, var.description)).collect();
+    assert_eq!(vars, [("g", "S", "conductance"), ("id", "A", "current")]);
+    let (g, id) = (op_vars[x1][0].value, op_vars[x1][1].value);
+    assert!((g - 0.5e-3).abs() < 1e-15, "{g}");
+    assert!((id - 1.5e-3).abs() < 1e-12, "{id}");
+    Ok(())
+}
+
+#[test]
+fn op_vars_reuse_solution() -> Result<()> {
+    let opvars = va_file("op_vars_reuse_solution.va", OPVARS)?;
+    let src = format!(
+        r#"
+ahdl_include "{opvars}"
+v1 (in 0) vsource dc=3
+x1 (in 0) opvars_res r=2k
+"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let x1 = circuit.lookup_instance("x1").expect("instance x1");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    assert!(sim.op_vars().is_err(), "opvars are only available at the operating point");
+
+    let solution = sim.dc_op()?.to_owned();
+    let current = sim.dc_lead_current(x1)?[0];
+    let op_vars = sim.op_vars()?;
+    assert_eq!(sim.solution().raw.to_vec(), solution.raw);
+    assert!((current - 1.5e-3).abs() < 1e-12, "{current}");
+    let id = op_vars[x1][1].value;
+    assert!((id - current).abs() < 1e-12, "{id} != {current}");
+    Ok(())
+}
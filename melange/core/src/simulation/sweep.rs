@@ -0,0 +1,113 @@
+use anyhow::{bail, Context, Result};
+use typed_index_collections::TiVec;
+
+use crate::circuit::{InstanceId, Node};
+use crate::devices::{ParamId, Type};
+use crate::expr::{CircuitParam, ExprEvalCtxRef};
+use crate::simulation::Simulation;
+use crate::Arena;
+
+#[cfg(test)]
+mod tests;
+
+/// The quantity that is varied by a DC sweep
+#[derive(Debug, Clone)]
+pub enum SweepParam {
+    /// A circuit parameter, all expressions that depend on it are reevaluated
+    Circuit(CircuitParam),
+    /// A real instance parameter, usually the value of a source (for example `dc` of a `vsource`)
+    Instance(InstanceId, String),
+}
+
+/// The resolved [`SweepParam`] (or parameter override) that is overwritten during setup
+#[derive(Debug, Clone, Copy)]
+pub(super) enum SweepTarget {
+    Circuit(CircuitParam),
+    Instance(InstanceId, ParamId),
+}
+
+/// The operating points of a DC sweep
+#[derive(Debug, Clone, Default)]
+pub struct DcSweepSolution {
+    pub values: Vec<f64>,
+    /// the solution (node voltages and internal unknowns) at each sweep point
+    pub solution: Vec<TiVec<Node, f64>>,
+    /// the terminal currents of every instance at each sweep point
+    pub lead_currents: Vec<TiVec<InstanceId, Vec<f64>>>,
+}
+
+impl DcSweepSolution {
+    pub fn waveform(&self, node: Node) -> impl Iterator<Item = f64> + '_ {
+        self.solution.iter().map(move |solution| solution[node])
+    }
+
+    pub fn lead_current(
+        &self,
+        inst: InstanceId,
+        terminal: usize,
+    ) -> impl Iterator<Item = f64> + '_ {
+        self.lead_currents.iter().map(move |currents| currents[inst][terminal])
+    }
+}
+
+impl Simulation<'_> {
+    /// Solves the DC operating point for each of the `values` of `param`.
+    ///
+    /// Each operating point starts from the solution of the previous sweep point.
+    /// If Newton's method still fails, gmin stepping and source stepping are used.
+    /// After the sweep the simulation remains at the operating point of the last value.
+    pub fn dc_sweep(
+        &mut self,
+        param: &SweepParam,
+        values: &[f64],
+        mut eval_ctx: ExprEvalCtxRef,
+        arena: &Arena,
+    ) -> Result<DcSweepSolution> {
+        let target = self.resolve_sweep_param(param)?;
+        let mut res = DcSweepSolution {
+            values: values.to_owned(),
+            solution: Vec::with_capacity(values.len()),
+            lead_currents: Vec::with_capacity(values.len()),
+        };
+
+        for &val in values {
+            self.setup_solver(eval_ctx.borrow(), arena, &[(target, val)])?;
+            if let Some(prev) = res.solution.last() {
+                self.set_initial_guess(prev);
+            }
+            self.dc_op().with_context(|| format!("DC sweep failed at {val}"))?;
+
+            let lead_currents = self
+                .instance_data
+                .iter_enumerated()
+                .map(|(inst, data)| {
+                    let mut dst = vec![0f64; self.circ[inst].connections.len()];
+                    data.load_lead_current_resist(&self.solution, &mut dst);
+                    dst
+                })
+                .collect();
+            res.lead_currents.push(lead_currents);
+            res.solution.push(self.solution.clone());
+        }
+
+        Ok(res)
+    }
+
+    fn resolve_sweep_param(&self, param: &SweepParam) -> Result<SweepTarget> {
+        let res = match *param {
+            SweepParam::Circuit(param) => SweepTarget::Circuit(param),
+            SweepParam::Instance(inst, ref name) => {
+                let dev = &self.circ[self.circ[self.circ[inst].model].device];
+                let (param, info) = match dev.parameters.lookup_param(name) {
+                    Some(param) => param,
+                    None => bail!("unknown parameter '{name}' for {}", dev.name),
+                };
+                if !info.is_instance_param || info.ty != Type::Real {
+                    bail!("'{name}' of {} is not a real instance parameter", dev.name)
+                }
+                SweepTarget::Instance(inst, param)
+            }
+        };
+        Ok(res)
+    }
+}
@@ -0,0 +1,31 @@
+use anyhow::Result;
+
+use crate::expr::CircuitParam;
+use crate::netlist::Netlist;
+use crate::simulation::{SimConfig, SweepParam};
+use crate::tests::parse;
+use crate::{Arena, ExprEvalCtx};
+
+#[test]
+fn dc_sweep() -> Result<()> {
+    let src = r#"
+parameters vin=0
+v1 (in 0) vsource dc=vin
+r1 (in out) resistor r=1k
+r2 (out 0) resistor r=3k
+"#;
+    let mut arena = Arena::new();
+    let Netlist { mut circuit, .. } = parse(src, &mut arena)?;
+    let out = circuit.lookup_node("out").expect("node out");
+    let (vin, _) = circuit.lookup_param("vin", &arena).expect("parameter vin");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let values = [0.0, 1.0, 2.0, 4.0];
+    let res = sim.dc_sweep(&SweepParam::Circuit(vin), &values, ctx.borrow(), &arena)?;
+    for (val, out) in values.iter().zip(res.waveform(out)) {
+        assert!((out - 0.75 * val).abs() < 1e-9);
+    }
+    Ok(())
+}
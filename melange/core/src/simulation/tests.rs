@@ -0,0 +1,136 @@
+use std::f64::consts::PI;
+
+use anyhow::Result;
+use num_complex::Complex64;
+
+use crate::expr::CircuitParam;
+use crate::netlist::Netlist;
+use crate::simulation::flags::OperatingPointAnalysis;
+use crate::simulation::SimConfig;
+use crate::tests::{parse, va_file};
+use crate::{Arena, ExprEvalCtx};
+
+#[test]
+fn parameter_overrides() -> Result<()> {
+    let src = r#"
+parameters vin=1 vhalf=vin/2
+v1 (in 0) vsource dc=vhalf
+r1 (in 0) resistor r=1k
+"#;
+    let mut arena = Arena::new();
+    let Netlist { mut circuit, .. } = parse(src, &mut arena)?;
+    let node = circuit.lookup_node("in").expect("node in");
+    let (vin, _) = circuit.lookup_param("vin", &arena).expect("parameter vin");
+
+    let mut sim = circuit.setup_simulation(SimConfig::default())?;
+    let mut ctx = ExprEvalCtx::new(&arena);
+    let overrides = [(CircuitParam::TEMPERATURE, 300.0), (vin, 4.0)];
+    sim.prepare_solver_with_overrides(ctx.borrow(), &arena, &overrides)?;
+    sim.dc_op()?;
+    assert!((sim.solution()[node] - 2.0).abs() < 1e-9);
+    Ok(())
+}
+
+#[test]
+fn ac_frequency_sweep() -> Result<()> {
+    let src = r#"
+v1 (in 0) vsource dc=0 mag=1
+r1 (in out) resistor r=1k
+c1 (out 0) capacitor c=1n
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let out = circuit.lookup_node("out").expect("node out");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    // only the first frequency evaluates the devices, the others reuse the results
+    for freq in [1e3, 1e5, 1e6, 1e7] {
+        let omega = 2.0 * PI * freq;
+        sim.set_omega(omega);
+        let val = sim.ac()?[out];
+        let expected = Complex64::new(1.0, 0.0) / Complex64::new(1.0, omega * 1e-6);
+        assert!((val - expected).norm() < 1e-9, "f = {freq}: {val} != {expected}");
+    }
+    Ok(())
+}
+
+/// Second order laplace filters defined by their coefficients and by their zeros and poles
+const LAPLACE: &str = r#"
+`include "disciplines.vams"
+
+module laplace_filters(inp, nd, zp);
+    inout inp, nd, zp;
+    electrical inp, nd, zp;
+
+    analog begin
+        V(nd) <+ laplace_nd(V(inp), '{1, 1e-7}, '{1, 1.4e-6, 1e-12});
+        V(zp) <+ laplace_zp(V(inp), '{-1e7, 0}, '{-1e6, 1e6, -1e6, -1e6});
+    end
+endmodule
+"#;
+
+#[test]
+fn ac_laplace() -> Result<()> {
+    let laplace = va_file("ac_laplace.va", LAPLACE)?;
+    let src = format!(
+        r#"
+ahdl_include "{laplace}"
+v1 (in 0) vsource dc=0 mag=1
+x1 (in nd zp) laplace_filters
+"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let nd = circuit.lookup_node("nd").expect("node nd");
+    let zp = circuit.lookup_node("zp").expect("node zp");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let one = Complex64::new(1.0, 0.0);
+    let pole = Complex64::new(-1e6, 1e6);
+    for omega in [1e3, 1e5, 1e6, 3e6, 1e7, 1e8] {
+        sim.set_omega(omega);
+        let ac = sim.ac()?;
+        let s = Complex64::new(0.0, omega);
+
+        let expected = (1.0 + 1e-7 * s) / (1.0 + 1.4e-6 * s + 1e-12 * s * s);
+        let val = ac[nd];
+        assert!((val - expected).norm() < 1e-9, "omega = {omega}: {val} != {expected}");
+
+        // H(s) = (1 - s/zero) / ((1 - s/pole) * (1 - s/conj(pole)))
+        let expected = (one + s / 1e7) / ((one - s / pole) * (one - s / pole.conj()));
+        let val = ac[zp];
+        assert!((val - expected).norm() < 1e-9, "omega = {omega}: {val} != {expected}");
+    }
+    Ok(())
+}
+
+#[test]
+fn gshunt() -> Result<()> {
+    let src = r#"
+v1 (in 0) vsource dc=1
+r1 (in out) resistor r=1k
+r2 (out 0) resistor r=1k
+"#;
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(src, &mut arena)?;
+    let inp = circuit.lookup_node("in").expect("node in");
+    let out = circuit.lookup_node("out").expect("node out");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    sim.gshunt = 1e-3;
+    let maxiters = sim.config.maxiters;
+    let flags = OperatingPointAnalysis::DC.eval_flags();
+    let converged = sim.newton(flags, 0.0, None, maxiters)?;
+    assert!(converged.is_some());
+    // the shunt only loads the nodes, the branch equation of the source remains exact
+    let solution = sim.solution();
+    assert!((solution[inp] - 1.0).abs() < 1e-12, "{}", solution[inp]);
+    assert!((solution[out] - 1.0 / 3.0).abs() < 1e-9, "{}", solution[out]);
+    Ok(())
+}
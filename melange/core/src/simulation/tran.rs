@@ -0,0 +1,375 @@
+use std::collections::VecDeque;
+
+use anyhow::{bail, Result};
+use stdx::iter::zip;
+use typed_index_collections::{TiSlice, TiVec};
+
+use crate::circuit::{InstanceId, Node};
+use crate::simulation::flags::{EvalFlags, EvalRetFlags, OperatingPointAnalysis, SimulationState};
+use crate::simulation::Simulation;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IntegrationMethod {
+    BackwardEuler,
+    Trapezoidal,
+    Gear2,
+}
+
+impl IntegrationMethod {
+    fn order(self) -> usize {
+        match self {
+            IntegrationMethod::BackwardEuler => 1,
+            IntegrationMethod::Trapezoidal | IntegrationMethod::Gear2 => 2,
+        }
+    }
+
+    /// constant of the leading term of the local truncation error
+    fn error_const(self) -> f64 {
+        match self {
+            IntegrationMethod::BackwardEuler => 0.5,
+            IntegrationMethod::Trapezoidal => 1.0 / 12.0,
+            IntegrationMethod::Gear2 => 2.0 / 9.0,
+        }
+    }
+}
+
+/// The discretization of the time derivative `dq/dt = alpha * q + hist`
+/// at the next timepoint.
+pub(super) struct Integration {
+    pub alpha: f64,
+    pub hist: TiVec<Node, f64>,
+    method: IntegrationMethod,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TranSolution {
+    pub time: Vec<f64>,
+    pub solution: Vec<TiVec<Node, f64>>,
+    /// the terminal currents of every instance at each timepoint
+    pub lead_currents: Vec<TiVec<InstanceId, Vec<f64>>>,
+}
+
+impl TranSolution {
+    pub fn waveform(&self, node: Node) -> impl Iterator<Item = f64> + '_ {
+        self.solution.iter().map(move |solution| solution[node])
+    }
+
+    pub fn lead_current(
+        &self,
+        inst: InstanceId,
+        terminal: usize,
+    ) -> impl Iterator<Item = f64> + '_ {
+        self.lead_currents.iter().map(move |currents| currents[inst][terminal])
+    }
+
+    fn clear(&mut self) {
+        self.time.clear();
+        self.solution.clear();
+        self.lead_currents.clear();
+    }
+}
+
+struct TimePoint {
+    time: f64,
+    charge: TiVec<Node, f64>,
+}
+
+/// The accepted timepoints required by the integration method and
+/// the estimation of the local truncation error (newest first).
+struct History {
+    points: VecDeque<TimePoint>,
+    charge_deriv: TiVec<Node, f64>,
+}
+
+impl History {
+    /// enough to estimate the truncation error of a second order method
+    const LEN: usize = 3;
+
+    fn new(time: f64, charge: &TiSlice<Node, f64>) -> History {
+        let mut points = VecDeque::with_capacity(Self::LEN);
+        points.push_front(TimePoint { time, charge: charge.to_owned() });
+        History { points, charge_deriv: vec![0f64; charge.len()].into() }
+    }
+
+    fn time(&self) -> f64 {
+        self.points[0].time
+    }
+
+    /// Forget all but the last timepoint. Used after discontinuities (breakpoints)
+    /// where the old timepoints do not describe the waveform anymore.
+    fn restart(&mut self) {
+        self.points.truncate(1);
+    }
+
+    fn integration(&self, method: IntegrationMethod, h: f64) -> Integration {
+        let q1 = &self.points[0].charge;
+        // multistep methods fall back to backward euler until there is enough history
+        let method = match method {
+            IntegrationMethod::Trapezoidal | IntegrationMethod::Gear2 if self.points.len() < 2 => {
+                IntegrationMethod::BackwardEuler
+            }
+            method => method,
+        };
+
+        let (alpha, hist) = match method {
+            IntegrationMethod::BackwardEuler => {
+                let alpha = 1.0 / h;
+                (alpha, q1.iter().map(|q1| -alpha * q1).collect())
+            }
+            IntegrationMethod::Trapezoidal => {
+                let alpha = 2.0 / h;
+                let hist =
+                    zip(q1, &self.charge_deriv).map(|(q1, deriv)| -alpha * q1 - deriv).collect();
+                (alpha, hist)
+            }
+            IntegrationMethod::Gear2 => {
+                let q2 = &self.points[1].charge;
+                let h2 = self.points[0].time - self.points[1].time;
+                let alpha = (2.0 * h + h2) / (h * (h + h2));
+                let a1 = -(h + h2) / (h * h2);
+                let a2 = h / (h2 * (h + h2));
+                (alpha, zip(q1, q2).map(|(q1, q2)| a1 * q1 + a2 * q2).collect())
+            }
+        };
+
+        Integration { alpha, hist, method }
+    }
+
+    /// Estimates the local truncation error of the charges at `time` from their
+    /// divided differences. Returns the ratio between the largest error and its tolerance
+    /// or `None` if there is not enough history to estimate the error.
+    fn truncation_error(
+        &self,
+        integration: &Integration,
+        time: f64,
+        charge: &TiSlice<Node, f64>,
+        rtol: f64,
+        atol: f64,
+    ) -> Option<f64> {
+        let order = integration.method.order();
+        if self.points.len() < order + 1 {
+            return None;
+        }
+
+        let mut times = [0f64; Self::LEN + 1];
+        times[0] = time;
+        for (dst, point) in zip(&mut times[1..], &self.points) {
+            *dst = point.time;
+        }
+        let times = &times[..order + 2];
+
+        let h = time - self.time();
+        let factorial: f64 = (1..=order + 1).map(|i| i as f64).product();
+        let scale = integration.method.error_const() * h.powi(order as i32 + 1) * factorial;
+
+        let mut ratio = 0f64;
+        let mut diff = [0f64; Self::LEN + 1];
+        for (node, &q) in charge.iter_enumerated().skip(1) {
+            diff[0] = q;
+            for (dst, point) in zip(&mut diff[1..order + 2], &self.points) {
+                *dst = point.charge[node];
+            }
+
+            for j in 1..times.len() {
+                for l in (j..times.len()).rev() {
+                    diff[l] = (diff[l - 1] - diff[l]) / (times[l - j] - times[l]);
+                }
+            }
+
+            let lte = (scale * diff[order + 1]).abs();
+            let tol = atol.max(rtol * q.abs().max(self.points[0].charge[node].abs()));
+            ratio = ratio.max(lte / tol);
+        }
+
+        Some(ratio)
+    }
+
+    fn accept(&mut self, integration: &Integration, time: f64, charge: &TiSlice<Node, f64>) {
+        for ((deriv, q), hist) in
+            zip(&mut self.charge_deriv.raw, &charge.raw).zip(&integration.hist.raw)
+        {
+            *deriv = integration.alpha * q + hist;
+        }
+
+        let point = if self.points.len() == Self::LEN {
+            let mut point = self.points.pop_back().unwrap();
+            point.time = time;
+            point.charge.copy_from_slice(charge);
+            point
+        } else {
+            TimePoint { time, charge: charge.to_owned() }
+        };
+        self.points.push_front(point);
+    }
+}
+
+impl Simulation<'_> {
+    /// Runs a transient analysis from the operating point at `t = 0` until `tstop`.
+    /// The timestep is chosen by the local truncation error and never exceeds `tstep`.
+    pub fn tran(&mut self, tstop: f64, tstep: f64) -> Result<&TranSolution> {
+        if tstop <= 0.0 || tstep <= 0.0 {
+            bail!("tstop and tstep of a transient analysis must be positive")
+        }
+
+        self.solve_op(OperatingPointAnalysis::TranIc)?;
+        // the solution is no longer an operating point once the first timestep is attempted
+        self.state = SimulationState::empty();
+
+        let method = self.config.integration;
+        let hmax = tstep.min(tstop / 50.0);
+        let hmin = tstop * 1e-12;
+        let mut h = tstep.min(tstop / 100.0) * 0.1;
+
+        let mut history = History::new(0.0, &self.residual_react);
+        let mut accepted_solution = self.solution.clone();
+        let mut accepted_state: Vec<_> = self.prev_state.iter().map(|state| state.get()).collect();
+        let mut charges: TiVec<InstanceId, Vec<f64>> = self
+            .circ
+            .instances()
+            .map(|inst| vec![0f64; self.circ[inst].connections.len()])
+            .collect();
+        self.tran_solution.clear();
+        self.save_timepoint(0.0, &mut charges);
+        for inst in &mut *self.instance_data {
+            inst.accept_timepoint(0.0, &self.solution);
+        }
+        let (mut bound_step, mut next_breakpoint) = self.step_limits(0.0, hmin);
+
+        let mut time = 0.0;
+        while time < tstop {
+            h = h.min(hmax).min(bound_step);
+            let mut at_breakpoint = false;
+            if time + h >= next_breakpoint - hmin {
+                h = next_breakpoint - time;
+                at_breakpoint = true;
+            }
+            let final_step = time + h >= tstop - hmin;
+            if final_step {
+                h = tstop - time;
+            }
+
+            let integration = history.integration(method, h);
+            let flags =
+                if final_step { EvalFlags::TRAN | EvalFlags::FINAL_STEP } else { EvalFlags::TRAN };
+            let ret_flags =
+                self.newton(flags, time + h, Some(&integration), self.config.tran_maxiters)?;
+
+            // the timestep for the next attempt, either after a rejection or an accepted timepoint
+            let (accept, next_h) = match ret_flags {
+                // the operating point of the next timepoint lies too far away
+                None => (false, h / 8.0),
+                Some(_) => {
+                    let (_, breakpoint) = self.step_limits(time, hmin);
+                    if breakpoint < time + h - hmin {
+                        // an event occurred between the two timepoints
+                        (false, breakpoint - time)
+                    } else {
+                        let ratio = history.truncation_error(
+                            &integration,
+                            time + h,
+                            &self.residual_react,
+                            self.config.rtol,
+                            self.config.charge_atol,
+                        );
+                        let exp = 1.0 / (integration.method.order() as f64 + 1.0);
+                        match ratio.map(|ratio| ratio / self.config.trtol) {
+                            Some(ratio) if ratio > 1.0 => {
+                                (false, h * (0.9 * ratio.powf(-exp)).max(0.25))
+                            }
+                            Some(ratio) => (true, h * (0.9 * ratio.powf(-exp)).min(2.0)),
+                            None => (true, 2.0 * h),
+                        }
+                    }
+                }
+            };
+
+            if !accept {
+                if next_h < hmin {
+                    bail!("timestep too small at t = {time}")
+                }
+                h = next_h;
+                self.solution.copy_from_slice(&accepted_solution);
+                for (dst, &val) in zip(&self.prev_state, &accepted_state) {
+                    dst.set(val)
+                }
+                for inst in &mut *self.instance_data {
+                    inst.reject_timepoint();
+                }
+                continue;
+            }
+
+            time = if final_step { tstop } else { time + h };
+            h = next_h;
+            history.accept(&integration, time, &self.residual_react);
+            accepted_solution.copy_from_slice(&self.solution);
+            for (dst, state) in zip(&mut accepted_state, &self.prev_state) {
+                *dst = state.get()
+            }
+            for inst in &mut *self.instance_data {
+                inst.accept_timepoint(time, &self.solution);
+            }
+            self.save_timepoint(time, &mut charges);
+
+            // $finish and $stop end the analysis at the current timepoint
+            let stop = EvalRetFlags::FINISH | EvalRetFlags::STOP;
+            if ret_flags.map_or(false, |flags| flags.intersects(stop)) {
+                break;
+            }
+
+            (bound_step, next_breakpoint) = self.step_limits(time, hmin);
+            if at_breakpoint {
+                history.restart();
+                h *= 0.1;
+            }
+        }
+
+        Ok(&self.tran_solution)
+    }
+
+    /// Stores the accepted solution at `time`. The charging currents of the terminals
+    /// are approximated by a backward difference of the terminal charges in `charges`,
+    /// which are updated to the charges at `time`.
+    fn save_timepoint(&mut self, time: f64, charges: &mut TiSlice<InstanceId, Vec<f64>>) {
+        let prev_time = self.tran_solution.time.last().copied();
+        let mut charge = Vec::new();
+        let lead_currents = zip(&*self.instance_data, &mut charges.raw)
+            .map(|(data, prev_charge)| {
+                let mut dst = vec![0f64; prev_charge.len()];
+                data.load_lead_current_resist(&self.solution, &mut dst);
+                charge.clear();
+                charge.resize(prev_charge.len(), 0f64);
+                data.load_lead_current_react(&self.solution, &mut charge);
+                if let Some(prev_time) = prev_time {
+                    let h = time - prev_time;
+                    for ((dst, q), prev_q) in zip(&mut dst, &charge).zip(&*prev_charge) {
+                        *dst += (q - prev_q) / h;
+                    }
+                }
+                prev_charge.copy_from_slice(&charge);
+                dst
+            })
+            .collect();
+
+        self.tran_solution.time.push(time);
+        self.tran_solution.solution.push(self.solution.clone());
+        self.tran_solution.lead_currents.push(lead_currents);
+    }
+
+    /// The largest timestep and the next breakpoint requested by the devices
+    /// during the last evaluation. Breakpoints that were already reached are ignored.
+    fn step_limits(&self, time: f64, hmin: f64) -> (f64, f64) {
+        let mut bound_step = f64::INFINITY;
+        let mut breakpoint = f64::INFINITY;
+        for inst in &*self.instance_data {
+            bound_step = bound_step.min(inst.bound_step());
+            let inst_breakpoint = inst.next_breakpoint();
+            if inst_breakpoint > time + hmin {
+                breakpoint = breakpoint.min(inst_breakpoint)
+            }
+        }
+        (bound_step, breakpoint)
+    }
+}
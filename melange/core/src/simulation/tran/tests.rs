@@ -0,0 +1,369 @@
+use std::f64::consts::PI;
+
+use anyhow::{Context, Result};
+use num_complex::Complex64;
+
+use crate::expr::CircuitParam;
+use crate::netlist::Netlist;
+use crate::simulation::{IntegrationMethod, SimConfig};
+use crate::tests::{parse, va_file, VSTEP};
+use crate::{Arena, ExprEvalCtx};
+
+#[test]
+fn tran_lead_currents() -> Result<()> {
+    let vstep = va_file("tran_lead_currents.va", VSTEP)?;
+    let src = format!(
+        r#"
+ahdl_include "{vstep}"
+v1 (in 0) vstep v=1 td=1u tr=10n
+r1 (in out) resistor r=1k
+c1 (out 0) capacitor c=1n
+"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let inp = circuit.lookup_node("in").expect("node in");
+    let out = circuit.lookup_node("out").expect("node out");
+    let r1 = circuit.lookup_instance("r1").expect("instance r1");
+    let c1 = circuit.lookup_instance("c1").expect("instance c1");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.tran(5e-6, 1e-7)?;
+
+    let vin: Vec<_> = res.waveform(inp).collect();
+    let vout: Vec<_> = res.waveform(out).collect();
+    let ir: Vec<_> = res.lead_current(r1, 0).collect();
+    let ic: Vec<_> = res.lead_current(c1, 0).collect();
+    assert_eq!(ir.len(), res.time.len());
+    assert_eq!(ic[0], 0.0);
+    for i in 1..res.time.len() {
+        let expected = (vin[i] - vout[i]) / 1e3;
+        assert!((ir[i] - expected).abs() < 1e-12, "t = {}: {} != {expected}", res.time[i], ir[i]);
+        // the charging current is the backward difference of the charge
+        let expected = 1e-9 * (vout[i] - vout[i - 1]) / (res.time[i] - res.time[i - 1]);
+        assert!((ic[i] - expected).abs() < 1e-12, "t = {}: {} != {expected}", res.time[i], ic[i]);
+    }
+    Ok(())
+}
+
+/// The time constant of the first order filters used to test the transient analysis.
+/// Their input ramps from 0 to 1 between `STEP_TD` and `STEP_TD + STEP_TR`.
+const TAU: f64 = 1e-6;
+const STEP_TD: f64 = 1e-6;
+const STEP_TR: f64 = 1e-8;
+
+fn step_input(t: f64) -> f64 {
+    ((t - STEP_TD) / STEP_TR).clamp(0.0, 1.0)
+}
+
+/// The analytic response of a first order lowpass to `step_input`
+fn lowpass_step(t: f64) -> f64 {
+    let t = t - STEP_TD;
+    if t <= 0.0 {
+        0.0
+    } else if t < STEP_TR {
+        (t - TAU * (1.0 - (-t / TAU).exp())) / STEP_TR
+    } else {
+        1.0 - TAU / STEP_TR * ((STEP_TR / TAU).exp() - 1.0) * (-t / TAU).exp()
+    }
+}
+
+/// Runs a transient analysis of a netlist that is driven by a `vstep` source `v1` between
+/// `in` and ground and returns the largest deviation of `out` from `expected`.
+fn step_error(
+    name: &str,
+    netlist: &str,
+    integration: IntegrationMethod,
+    expected: impl Fn(f64) -> f64,
+) -> Result<f64> {
+    let vstep = va_file(name, VSTEP)?;
+    let src = format!(
+        r#"
+ahdl_include "{vstep}"
+v1 (in 0) vstep v=1 td={STEP_TD} tr={STEP_TR}
+{netlist}"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let out = circuit.lookup_node("out").expect("node out");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let config = SimConfig { integration, trtol: 1.0, ..SimConfig::default() };
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, config)?;
+    let res = sim.tran(6.0 * TAU, TAU / 100.0)?;
+
+    let err = res
+        .time
+        .iter()
+        .zip(res.waveform(out))
+        .map(|(&t, vout)| (vout - expected(t)).abs())
+        .fold(0.0, f64::max);
+    Ok(err)
+}
+
+fn rc_step_error(name: &str, integration: IntegrationMethod) -> Result<f64> {
+    let netlist = r#"
+r1 (in out) resistor r=1k
+c1 (out 0) capacitor c=1n
+"#;
+    step_error(name, netlist, integration, lowpass_step)
+}
+
+#[test]
+fn tran_rc_backward_euler() -> Result<()> {
+    let err = rc_step_error("tran_rc_be.va", IntegrationMethod::BackwardEuler)?;
+    assert!(err < 5e-3, "{err}");
+    Ok(())
+}
+
+#[test]
+fn tran_rc_trapezoidal() -> Result<()> {
+    let err = rc_step_error("tran_rc_trap.va", IntegrationMethod::Trapezoidal)?;
+    assert!(err < 1e-3, "{err}");
+    Ok(())
+}
+
+#[test]
+fn tran_rc_gear2() -> Result<()> {
+    let err = rc_step_error("tran_rc_gear2.va", IntegrationMethod::Gear2)?;
+    assert!(err < 1e-3, "{err}");
+    Ok(())
+}
+
+#[test]
+fn tran_rl() -> Result<()> {
+    // a highpass: the voltage across the inductor decays once the input settles
+    let netlist = r#"
+r1 (in out) resistor r=1k
+l1 (out 0) inductor l=1m
+"#;
+    let expected = |t| step_input(t) - lowpass_step(t);
+    let err = step_error("tran_rl.va", netlist, IntegrationMethod::Trapezoidal, expected)?;
+    assert!(err < 1e-3, "{err}");
+    Ok(())
+}
+
+#[test]
+fn tran_breakpoints() -> Result<()> {
+    let vstep = va_file("tran_breakpoints.va", VSTEP)?;
+    // neither corner of the ramp is a multiple of the maximum timestep
+    let src = format!(
+        r#"
+ahdl_include "{vstep}"
+v1 (in 0) vstep v=1 td=1.234u tr=7n
+r1 (in 0) resistor r=1k
+"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let inp = circuit.lookup_node("in").expect("node in");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.tran(5e-6, 1e-7)?;
+
+    let vin: Vec<_> = res.waveform(inp).collect();
+    let hit = |time: f64| res.time.iter().position(|&t| (t - time).abs() < 1e-15);
+    let start = hit(1.234e-6).context("start of the ramp is a timepoint")?;
+    let end = hit(1.241e-6).context("end of the ramp is a timepoint")?;
+    assert!(vin[..=start].iter().all(|&v| v.abs() < 1e-12), "{vin:?}");
+    assert!(vin[end..].iter().all(|&v| (v - 1.0).abs() < 1e-12), "{vin:?}");
+    for i in start + 1..end {
+        let expected = (res.time[i] - 1.234e-6) / 7e-9;
+        assert!((vin[i] - expected).abs() < 1e-6, "t = {}: {} != {expected}", res.time[i], vin[i]);
+    }
+    Ok(())
+}
+
+/// A voltage source that is 1 between `td` and `td + pw`. The edges are shaped by a
+/// `transition` filter with the rise time `tr` and the fall time `tf`.
+const VPULSE: &str = r#"
+`include "disciplines.vams"
+
+module vpulse(p, n);
+    inout p, n;
+    electrical p, n;
+    parameter real td = 0 from [0:inf);
+    parameter real pw = 1u from (0:inf);
+    parameter real tr = 1n from (0:inf);
+    parameter real tf = 1n from (0:inf);
+    real level;
+
+    analog begin
+        @(timer(td))
+            level = 1;
+        @(timer(td + pw))
+            level = 0;
+        V(p, n) <+ transition(level, 0, tr, tf);
+    end
+endmodule
+"#;
+
+#[test]
+fn tran_transition_rise_fall() -> Result<()> {
+    let vpulse = va_file("tran_transition_rise_fall.va", VPULSE)?;
+    let src = format!(
+        r#"
+ahdl_include "{vpulse}"
+v1 (in 0) vpulse td=1u pw=2u tr=7n tf=23n
+r1 (in 0) resistor r=1k
+"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let inp = circuit.lookup_node("in").expect("node in");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.tran(5e-6, 1e-7)?;
+
+    let vin: Vec<_> = res.waveform(inp).collect();
+    let hit = |time: f64| res.time.iter().position(|&t| (t - time).abs() < 1e-15);
+    let rise_start = hit(1e-6).context("start of the rising edge is a timepoint")?;
+    let rise_end = hit(1.007e-6).context("end of the rising edge is a timepoint")?;
+    let fall_start = hit(3e-6).context("start of the falling edge is a timepoint")?;
+    let fall_end = hit(3.023e-6).context("end of the falling edge is a timepoint")?;
+
+    for (i, (&t, &v)) in res.time.iter().zip(&vin).enumerate() {
+        let expected = if i <= rise_start || i >= fall_end {
+            0.0
+        } else if i < rise_end {
+            (t - 1e-6) / 7e-9
+        } else if i <= fall_start {
+            1.0
+        } else {
+            1.0 - (t - 3e-6) / 23e-9
+        };
+        assert!((v - expected).abs() < 1e-6, "t = {t}: {v} != {expected}");
+    }
+    Ok(())
+}
+
+/// A comparator whose output switches to 1 once `V(inp)` rises above `vth`
+const COMPARATOR: &str = r#"
+`include "disciplines.vams"
+
+module comparator(inp, out);
+    inout inp, out;
+    electrical inp, out;
+    parameter real vth = 0.5;
+    real level;
+
+    analog begin
+        @(cross(V(inp) - vth, +1, 1p))
+            level = 1;
+        V(out) <+ level;
+    end
+endmodule
+"#;
+
+#[test]
+fn tran_cross_breakpoint() -> Result<()> {
+    let vstep = va_file("tran_cross_breakpoint_vstep.va", VSTEP)?;
+    let comparator = va_file("tran_cross_breakpoint.va", COMPARATOR)?;
+    // the input ramps from 0 to 1 between 1u and 1.1u, so it crosses vth at 1.03u
+    let src = format!(
+        r#"
+ahdl_include "{vstep}"
+ahdl_include "{comparator}"
+v1 (in 0) vstep v=1 td=1u tr=100n
+x1 (in out) comparator vth=0.3
+r1 (out 0) resistor r=1k
+"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let out = circuit.lookup_node("out").expect("node out");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.tran(5e-6, 1e-7)?;
+
+    let crossing = 1.03e-6;
+    let tol = 1e-12;
+    assert!(
+        res.time.iter().any(|&t| (t - crossing).abs() <= tol),
+        "the crossing is not a timepoint: {:?}",
+        res.time
+    );
+    for (&t, vout) in res.time.iter().zip(res.waveform(out)) {
+        if t < crossing - tol {
+            assert!(vout.abs() < 1e-12, "t = {t}: output switched before the crossing");
+        } else if t > crossing + tol {
+            assert!((vout - 1.0).abs() < 1e-12, "t = {t}: output did not switch");
+        }
+    }
+    Ok(())
+}
+
+/// A sinusoidal input that is sampled by first and second order `zi` filters
+/// (defined by their coefficients and by their zeros and poles) with a period of 1u
+const ZI: &str = r#"
+`include "constants.vams"
+`include "disciplines.vams"
+
+module zi_filters(nd, zp);
+    inout nd, zp;
+    electrical nd, zp;
+    parameter real freq = 125k;
+    real inp;
+
+    analog begin
+        inp = sin(2 * `M_PI * freq * $abstime);
+        V(nd) <+ zi_nd(inp, '{0.5}, '{1, -0.5}, 1u);
+        V(zp) <+ zi_zp(inp, '{0.5, 0}, '{0.25, 0.5, 0.25, -0.5}, 1u);
+    end
+endmodule
+"#;
+
+#[test]
+fn tran_zi() -> Result<()> {
+    let zi = va_file("tran_zi.va", ZI)?;
+    let src = format!(
+        r#"
+ahdl_include "{zi}"
+x1 (nd zp) zi_filters
+"#
+    );
+    let mut arena = Arena::new();
+    let Netlist { circuit, .. } = parse(&src, &mut arena)?;
+    let nd = circuit.lookup_node("nd").expect("node nd");
+    let zp = circuit.lookup_node("zp").expect("node zp");
+
+    let mut ctx = ExprEvalCtx::new(&arena);
+    ctx.set_param(CircuitParam::TEMPERATURE, 300.0.into());
+    let mut sim = circuit.prepare_simulation(ctx.borrow(), &arena, SimConfig::default())?;
+    let res = sim.tran(80e-6, 2.5e-7)?;
+    let vnd: Vec<_> = res.waveform(nd).collect();
+    let vzp: Vec<_> = res.waveform(zp).collect();
+
+    // once the initial transient decayed the samples follow the frequency response
+    // H(z) evaluated on the unit circle at z = exp(j*omega*T)
+    let (period, omega) = (1e-6, 2.0 * PI * 125e3);
+    let z = Complex64::from_polar(1.0, omega * period);
+    let one = Complex64::new(1.0, 0.0);
+    let h_nd = 0.5 / (1.0 - 0.5 / z);
+    let pole = Complex64::new(0.25, 0.5);
+    let h_zp = (1.0 - 0.5 / z) / ((one - pole / z) * (one - pole.conj() / z));
+    for k in 50..80 {
+        let sample = k as f64 * period;
+        let i = res
+            .time
+            .iter()
+            .position(|&t| (t - sample).abs() < 1e-12)
+            .with_context(|| format!("sample {k} is a timepoint"))?;
+        // the output of the filter at the sample is Im(H(z) * exp(j*omega*t))
+        let phasor = Complex64::from_polar(1.0, omega * sample);
+        for (val, h) in [(vnd[i], h_nd), (vzp[i], h_zp)] {
+            let expected = (h * phasor).im;
+            assert!((val - expected).abs() < 1e-9, "t = {sample}: {val} != {expected}");
+        }
+    }
+    Ok(())
+}
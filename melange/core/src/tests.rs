@@ -1,12 +1,54 @@
-use anyhow::Result;
+use std::env;
+use std::fs::{create_dir_all, write};
+
+use anyhow::{Context, Result};
 use camino::Utf8PathBuf;
 use stdx::project_root;
 
 use crate::expr::CircuitParam;
+use crate::netlist::Netlist;
 use crate::simulation::SimConfig;
 use crate::utils::PrettyPrint;
 use crate::{veriloga, Arena, Circuit, ExprEvalCtx};
 
+pub(crate) fn parse(src: &str, arena: &mut Arena) -> Result<Netlist> {
+    Netlist::parse_str("test".to_owned(), src, arena, &veriloga::Opts::default())
+}
+
+/// A voltage source that steps from 0 to `v` at `td`. The `timer` event requests a breakpoint
+/// at `td` where the ramp of the `transition` filter (with the rise time `tr`) starts.
+pub(crate) const VSTEP: &str = r#"
+`include "disciplines.vams"
+
+module vstep(p, n);
+    inout p, n;
+    electrical p, n;
+    parameter real v = 1;
+    parameter real td = 0 from [0:inf);
+    parameter real tr = 1n from (0:inf);
+    real level;
+
+    analog begin
+        @(timer(td))
+            level = v;
+        V(p, n) <+ transition(level, 0, tr);
+    end
+endmodule
+"#;
+
+/// Writes a Verilog-A file to the temporary directory so that a netlist can load it with
+/// `ahdl_include`. Tests run in parallel so each test must use a different `name`.
+pub(crate) fn va_file(name: &str, src: &str) -> Result<Utf8PathBuf> {
+    let dir = Utf8PathBuf::from_path_buf(env::temp_dir())
+        .ok()
+        .context("temporary directory is not valid utf-8")?
+        .join("melange_tests");
+    create_dir_all(&dir)?;
+    let path = dir.join(name);
+    write(&path, src)?;
+    Ok(path)
+}
+
 const ATOL: f64 = 1e-9;
 const RTOL: f64 = 1e-2;
 fn approx_eq(val: f64, ref_val: f64) -> bool {
@@ -16,7 +58,7 @@ fn approx_eq(val: f64, ref_val: f64) -> bool {
 macro_rules! assert_approx_eq {
     ($val: expr, $ref: expr, $($fmt: tt)+) => {{
         if !approx_eq($val, $ref) {
-            panic!("assertion failed (left == right): {}\n left: {}\n right: {}", format_args!($($fmt)*),$val.pretty_str(), $refl.pretty_str())
+            panic!("assertion failed (left == right): {}\n left: {}\n right: {}", format_args!($($fmt)*),$val.pretty_str(), $ref.pretty_str())
         }
     }};
     ($val: expr, $ref: expr) => {{
@@ -28,8 +70,8 @@ macro_rules! assert_approx_eq {
 
 macro_rules! assert_approx_eq_cmplx {
     ($val: expr, $ref_real: literal + j $ref_imag: literal, $($fmt: tt)+) => {{
-        if !approx_eq($val.re, $ref_real) || !approx_eq($val.im, $ref_imag) ||  {
-            panic!("assertion failed (left == right): {}\n left: {}\n right: {}", format_args!($($fmt)*),$vall.pretty_str(), Complex64::new($ref_real, $ref_imag).pretty_str())
+        if !approx_eq($val.re, $ref_real) || !approx_eq($val.im, $ref_imag) {
+            panic!("assertion failed (left == right): {}\n left: {}\n right: {}", format_args!($($fmt)*),$val.pretty_str(), num_complex::Complex64::new($ref_real, $ref_imag).pretty_str())
         }
     }};
 
@@ -41,7 +83,7 @@ macro_rules! assert_approx_eq_cmplx {
 
     ($val: expr, $ref_real: literal - j $ref_imag: literal, $($fmt: tt)+) => {{
         if !approx_eq($val.re, $ref_real) || !approx_eq($val.im, -$ref_imag) {
-            panic!("assertion failed (left == right): {}\n left: {}\n right: {}", format_args!($($fmt)*),$vall.pretty_str(), Complex64::new($ref_real, - $ref_imag).pretty_str())
+            panic!("assertion failed (left == right): {}\n left: {}\n right: {}", format_args!($($fmt)*),$val.pretty_str(), num_complex::Complex64::new($ref_real, - $ref_imag).pretty_str())
         }
     }};
 
@@ -88,6 +130,7 @@ fn veriloga() -> Result<()> {
 
     let gnd = circ.lookup_node("ground").expect("ground node");
     let node_x = circ.node("X".to_owned());
+    let node_dt = circ.node("dT".to_owned());
 
     let path = Utf8PathBuf::from_path_buf(project_root())
         .expect("only utf8 paths are supported")
@@ -95,15 +138,18 @@ fn veriloga() -> Result<()> {
         .join("DIODE")
         .join("diode.va");
     circ.load_veriloga_file(path, &veriloga::Opts::default())?;
-    let (vdc_param, vdc) = arena.def_param(circ.ctx, "vdc".to_owned())?;
+    let (vdc_param, vdc) = circ.def_param("vdc".to_owned(), None, &mut arena)?;
 
     let (vsrc1, _) =
         circ.new_device_instance_by_name("vsrc1".to_owned(), "vsource", vec![node_x, gnd])?;
     circ.set_instance_param(vsrc1, "dc", vdc)?;
     circ.set_instance_param(vsrc1, "mag", 1.0.into())?;
 
-    let (_, diode1) =
-        circ.new_device_instance_by_name("diode1".to_owned(), "diode_va", vec![node_x, gnd])?;
+    let (_, diode1) = circ.new_device_instance_by_name(
+        "diode1".to_owned(),
+        "diode_va",
+        vec![node_x, gnd, node_dt],
+    )?;
 
     circ.set_model_param(diode1, "rs", 5f64.into())?;
     circ.set_model_param(diode1, "is", 1e-13.into())?;
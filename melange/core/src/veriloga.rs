@@ -9,22 +9,26 @@ use libc::c_void;
 use libloading::Library;
 use log::{debug, error, info, warn};
 use openvaf::{
-    AbsPathBuf, CompilationDestination, CompilationTermination, LintLevel, OptLevel, Target,
+    AbsPathBuf, CompilationDestination, CompilationTermination, LintLevel, MessageFormat, OptLevel,
+    Target,
 };
 
 use crate::devices::DeviceImpl;
 use crate::veriloga::osdi_0_4::{
-    OsdiDescriptor, LOG_FMT_ERR, LOG_LVL_DEBUG, LOG_LVL_DISPLAY, LOG_LVL_ERR, LOG_LVL_FATAL,
-    LOG_LVL_INFO, LOG_LVL_MASK, LOG_LVL_WARN,
+    OsdiDescriptor, OsdiLimFunction, LOG_FMT_ERR, LOG_LVL_DEBUG, LOG_LVL_DISPLAY, LOG_LVL_ERR,
+    LOG_LVL_FATAL, LOG_LVL_INFO, LOG_LVL_MASK, LOG_LVL_WARN,
 };
 use crate::veriloga::osdi_device::OsdiDevice;
 
 pub(crate) use osdi_0_4::{
     ANALYSIS_AC, ANALYSIS_DC, ANALYSIS_IC, ANALYSIS_NOISE, ANALYSIS_STATIC, ANALYSIS_TRAN,
-    CALC_NOISE, CALC_REACT_JACOBIAN, CALC_REACT_RESIDUAL, CALC_RESIST_JACOBIAN,
-    CALC_RESIST_RESIDUAL,
+    CALC_NOISE, CALC_OP, CALC_REACT_JACOBIAN, CALC_REACT_LIM_RHS, CALC_REACT_RESIDUAL,
+    CALC_RESIST_JACOBIAN, CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL, ENABLE_LIM,
+    EVAL_RET_FLAG_EVENT, EVAL_RET_FLAG_FATAL, EVAL_RET_FLAG_FINISH, EVAL_RET_FLAG_LIM,
+    EVAL_RET_FLAG_STOP, FINAL_STEP, INITIAL_STEP, INIT_LIM,
 };
 
+mod lim_functions;
 // autogenerated
 #[allow(warnings)]
 mod osdi_0_4;
@@ -62,10 +66,14 @@ pub fn compile_va(path: &Utf8Path, opts: &Opts) -> Result<Vec<Box<dyn DeviceImpl
             bail!("failed to find cache directory\nhelp: consider setting it manually")
         }
     };
+    // melange provides the delayed values of absdelay (see `osdi_device::DelayLine`) so the
+    // pade approximation is only used outside of transient analysis where it is exact
+    let mut lints = vec![("approximated_absdelay".to_owned(), LintLevel::Allow)];
+    lints.extend(opts.lints.iter().cloned());
     let openvaf_opts = openvaf::Opts {
         defines: opts.defines.clone(),
         codegen_opts: opts.codegen_opts.clone(),
-        lints: opts.lints.clone(),
+        lints,
         input: path.to_owned(),
         output: CompilationDestination::Cache { cache_dir },
         include: opts.include.clone(),
@@ -73,6 +81,10 @@ pub fn compile_va(path: &Utf8Path, opts: &Opts) -> Result<Vec<Box<dyn DeviceImpl
         target: Target::host_target()
             .context("openvaf does currently not support this hardware/os")?,
         target_cpu: "native".to_owned(),
+        sensitivities: Vec::new(),
+        message_format: MessageFormat::Human,
+        depfile: false,
+        depfile_json: false,
         dry_run: false,
     };
 
@@ -96,9 +108,9 @@ unsafe fn load_osdi_lib(path: &Utf8Path) -> Result<&'static [OsdiDescriptor]> {
     let major_version: &u32 = *lib.get(b"OSDI_VERSION_MAJOR\0")?;
     let minor_version: &u32 = *lib.get(b"OSDI_VERSION_MINOR\0")?;
 
-    if *major_version != 0 || *minor_version != 3 {
+    if *major_version != 0 || *minor_version != 4 {
         bail!(
-            "melange only supports OSDI v0.3 but {path} targets v{major_version}.{minor_version}",
+            "melange only supports OSDI v0.4 but {path} targets v{major_version}.{minor_version}",
         );
     }
 
@@ -108,6 +120,21 @@ unsafe fn load_osdi_lib(path: &Utf8Path) -> Result<&'static [OsdiDescriptor]> {
     let descriptors: &[OsdiDescriptor] =
         slice::from_raw_parts(descriptors, *num_descriptors as usize);
 
+    if let Ok(lim_table_len) = lib.get::<&u32>(b"OSDI_LIM_TABLE_LEN\0") {
+        let lim_table: *mut OsdiLimFunction = *lib.get(b"OSDI_LIM_TABLE\0")?;
+        let lim_table = slice::from_raw_parts_mut(lim_table, **lim_table_len as usize);
+        for entry in lim_table {
+            let name =
+                CStr::from_ptr(entry.name).to_str().expect("all OSDI strings must be valid utf-8");
+            entry.func_ptr = lim_functions::lookup(name, entry.num_args).with_context(|| {
+                format!(
+                    "{path} uses the unknown limit function {name} ({} arguments)",
+                    entry.num_args
+                )
+            })?;
+        }
+    }
+
     if let Ok(osdi_log_ptr) =
         lib.get::<*mut unsafe fn(*mut c_void, *const c_char, u32)>(b"osdi_log\0")
     {
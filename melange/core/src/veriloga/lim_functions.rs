@@ -0,0 +1,156 @@
+//! The builtin limiting functions that Verilog-A models can call with `$limit`.
+//! These follow the implementations in SPICE (`DEVpnjlim`, `DEVfetlim`, ...).
+
+use std::ffi::c_void;
+
+pub(super) fn lookup(name: &str, num_args: u32) -> Option<*mut c_void> {
+    let fun = match (name, num_args) {
+        ("pnjlim", 2) => pnjlim as extern "C" fn(bool, *mut bool, f64, f64, f64, f64) -> f64 as _,
+        ("fetlim", 1) => fetlim as extern "C" fn(bool, *mut bool, f64, f64, f64) -> f64 as _,
+        ("limvds", 0) => limvds as extern "C" fn(bool, *mut bool, f64, f64) -> f64 as _,
+        ("limitlog", 1) => limitlog as extern "C" fn(bool, *mut bool, f64, f64, f64) -> f64 as _,
+        _ => return None,
+    };
+    Some(fun)
+}
+
+extern "C" fn pnjlim(
+    init: bool,
+    check: *mut bool,
+    vnew: f64,
+    vold: f64,
+    vt: f64,
+    vcrit: f64,
+) -> f64 {
+    if init {
+        unsafe { *check = true };
+        return vcrit;
+    }
+
+    if vnew > vcrit && (vnew - vold).abs() > 2.0 * vt {
+        unsafe { *check = true };
+        if vold > 0.0 {
+            let arg = 1.0 + (vnew - vold) / vt;
+            if arg > 0.0 {
+                vold + vt * arg.ln()
+            } else {
+                vcrit
+            }
+        } else {
+            vt * (vnew / vt).ln()
+        }
+    } else {
+        vnew
+    }
+}
+
+extern "C" fn fetlim(init: bool, check: *mut bool, vnew: f64, vold: f64, vto: f64) -> f64 {
+    if init {
+        unsafe { *check = true };
+        return vto + 0.1;
+    }
+
+    let vtsthi = (2.0 * (vold - vto)).abs() + 2.0;
+    let vtstlo = (vold - vto).abs() + 1.0;
+    let vtox = vto + 3.5;
+    let delv = vnew - vold;
+
+    let res = if vold >= vto {
+        if vold >= vtox {
+            if delv <= 0.0 {
+                // going off
+                if vnew >= vtox {
+                    if -delv > vtstlo {
+                        vold - vtstlo
+                    } else {
+                        vnew
+                    }
+                } else {
+                    vnew.max(vto + 2.0)
+                }
+            } else if delv >= vtsthi {
+                // staying on
+                vold + vtsthi
+            } else {
+                vnew
+            }
+        } else if delv <= 0.0 {
+            // middle region decreasing
+            vnew.max(vto - 0.5)
+        } else {
+            // middle region increasing
+            vnew.min(vto + 4.0)
+        }
+    } else if delv <= 0.0 {
+        // off
+        if -delv > vtsthi {
+            vold - vtsthi
+        } else {
+            vnew
+        }
+    } else {
+        let vtemp = vto + 0.5;
+        if vnew <= vtemp {
+            if delv > vtstlo {
+                vold + vtstlo
+            } else {
+                vnew
+            }
+        } else {
+            vtemp
+        }
+    };
+
+    if res != vnew {
+        unsafe { *check = true };
+    }
+    res
+}
+
+extern "C" fn limvds(init: bool, check: *mut bool, vnew: f64, vold: f64) -> f64 {
+    if init {
+        unsafe { *check = true };
+        return 0.1;
+    }
+
+    let res = if vold >= 3.5 {
+        if vnew > vold {
+            vnew.min(3.0 * vold + 2.0)
+        } else if vnew < 3.5 {
+            vnew.max(2.0)
+        } else {
+            vnew
+        }
+    } else if vnew > vold {
+        vnew.min(4.0)
+    } else {
+        vnew.max(-0.5)
+    };
+
+    if res != vnew {
+        unsafe { *check = true };
+    }
+    res
+}
+
+extern "C" fn limitlog(init: bool, check: *mut bool, vnew: f64, vold: f64, lim_tol: f64) -> f64 {
+    if init {
+        unsafe { *check = true };
+        return 0.0;
+    }
+
+    if vnew.is_nan() || vold.is_nan() {
+        unsafe { *check = true };
+        return 0.0;
+    }
+
+    if vnew > vold + lim_tol {
+        unsafe { *check = true };
+        vold + lim_tol + ((vnew - vold) / lim_tol).log10()
+    } else if vnew < vold - lim_tol {
+        unsafe { *check = true };
+        vold - lim_tol - ((vold - vnew) / lim_tol).log10()
+    } else {
+        vnew
+    }
+}
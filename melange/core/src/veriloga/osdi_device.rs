@@ -1,22 +1,30 @@
 use anyhow::{bail, Result};
+use num_complex::Complex64;
 use std::alloc::{alloc_zeroed, handle_alloc_error, Layout};
 use std::cell::Cell;
+use std::collections::VecDeque;
 use std::ffi::{c_void, CStr, CString};
 use std::mem::{align_of, swap};
 use std::os::raw::c_char;
 use std::rc::Rc;
-use std::{ptr, slice};
+use std::{fmt, ptr, slice};
 use stdx::format_to;
 use stdx::iter::zip;
 use typed_index_collections::TiSlice;
 
 use crate::circuit::Node;
-use crate::devices::{DeviceImpl, DeviceParams, InstanceImpl, ModelImpl, ParamId, Type};
-use crate::simulation::{MatrixEntryIter, SimBuilder, SimInfo};
+use crate::devices::{
+    DeviceImpl, DeviceParams, InstanceImpl, ModelImpl, NoiseSource, OpVar, ParamId, Type,
+};
+use crate::simulation::{EvalRetFlags, MatrixEntryIter, SimBuilder, SimInfo};
 use crate::veriloga::osdi_0_4::{
-    OsdiDescriptor, OsdiInitInfo, OsdiJacobianEntry, OsdiNode, OsdiNodePair, OsdiParamOpvar,
-    OsdiSimInfo, OsdiSimParas, ACCESS_FLAG_SET, EVAL_RET_FLAG_FATAL, INIT_ERR_OUT_OF_BOUNDS,
-    PARA_KIND_INST, PARA_TY_INT, PARA_TY_MASK, PARA_TY_REAL, PARA_TY_STR,
+    OsdiAbsdelay, OsdiDescriptor, OsdiInitInfo, OsdiJacobianEntry, OsdiNode, OsdiNodePair,
+    OsdiNoiseCorrelation, OsdiNoiseSource, OsdiParamBound, OsdiParamMeta, OsdiParamOpvar,
+    OsdiSimInfo, OsdiSimParas, ACCESS_FLAG_INSTANCE, ACCESS_FLAG_READ, ACCESS_FLAG_SET,
+    ANALYSIS_TRAN, CALC_REACT_LIM_RHS, CALC_RESIST_LIM_RHS, EVAL_RET_FLAG_FATAL, EXACT_ABSDELAY,
+    INIT_ERR_OUT_OF_BOUNDS, PARA_BOUND_DYNAMIC, PARA_BOUND_EXCLUDE, PARA_BOUND_MAX_INCLUSIVE,
+    PARA_BOUND_MIN_INCLUSIVE, PARA_KIND_INST, PARA_TY_INT, PARA_TY_MASK, PARA_TY_REAL, PARA_TY_STR,
+    PHASE_AWARE,
 };
 
 impl OsdiDescriptor {
@@ -36,6 +44,23 @@ impl OsdiDescriptor {
         unsafe { slice::from_raw_parts(self.param_opvar, self.num_params as usize) }
     }
 
+    fn param_meta(&self) -> &[OsdiParamMeta] {
+        // # SAFETY: OsdiDescriptor can only be constructed from FFI and is assumed to contain
+        // valid data
+        unsafe { slice::from_raw_parts(self.param_meta, self.num_params as usize) }
+    }
+
+    fn opvars(&self) -> &[OsdiParamOpvar] {
+        // # SAFETY: OsdiDescriptor can only be constructed from FFI and is assumed to contain
+        // valid data. The opvars are stored directly after the parameters
+        unsafe {
+            slice::from_raw_parts(
+                self.param_opvar.add(self.num_params as usize),
+                self.num_opvars as usize,
+            )
+        }
+    }
+
     fn collapsible(&self) -> &[OsdiNodePair] {
         // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
         unsafe { slice::from_raw_parts(self.collapsible, self.num_collapsible as usize) }
@@ -46,6 +71,23 @@ impl OsdiDescriptor {
         unsafe { slice::from_raw_parts(self.jacobian_entries, self.num_jacobian_entries as usize) }
     }
 
+    fn noise_sources(&self) -> &[OsdiNoiseSource] {
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe { slice::from_raw_parts(self.noise_sources, self.num_noise_src as usize) }
+    }
+
+    fn noise_correlations(&self) -> &[OsdiNoiseCorrelation] {
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe {
+            slice::from_raw_parts(self.noise_correlations, self.num_noise_correlations as usize)
+        }
+    }
+
+    fn absdelays(&self) -> &[OsdiAbsdelay] {
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe { slice::from_raw_parts(self.absdelays, self.num_absdelays as usize) }
+    }
+
     fn check_init_result(&self, res: OsdiInitInfo) -> Result<()> {
         if (res.flags & EVAL_RET_FLAG_FATAL) != 0 {
             bail!("Verilog-A $fatal was called")
@@ -54,15 +96,24 @@ impl OsdiDescriptor {
         if res.num_errors != 0 {
             let mut msg = String::default();
 
+            let violations = self.bound_violations(res.errors, res.num_errors);
             for i in 0..res.num_errors as usize {
                 let err = unsafe { &*res.errors.add(i) };
 
                 match err.code {
                     INIT_ERR_OUT_OF_BOUNDS => {
-                        let param = unsafe { err.payload.parameter_id };
-                        let param = unsafe { *self.params()[param as usize].name };
-                        let param = unsafe { osdi_str(param) };
-                        format_to!(msg, "value supplied for parameter '{param}' is out of bounds\n")
+                        let id = unsafe { err.payload.parameter_id } as usize;
+                        let violation = unsafe { &*violations.add(i) };
+                        let param = unsafe { osdi_str(*self.params()[id].name) };
+                        format_to!(msg, "value ");
+                        if !violation.value.is_nan() {
+                            format_to!(msg, "{} ", violation.value);
+                        }
+                        format_to!(msg, "supplied for parameter '{param}' ");
+                        match self.param_meta()[id].bounds().get(violation.bound as usize) {
+                            Some(bound) => format_to!(msg, "violates the bound '{bound}'\n"),
+                            None => format_to!(msg, "is out of bounds\n"),
+                        }
                     }
 
                     code => format_to!(msg, "unknown error: {code}\n"),
@@ -88,6 +139,38 @@ impl Drop for OsdiInitInfo {
     }
 }
 
+impl OsdiParamMeta {
+    fn bounds(&self) -> &[OsdiParamBound] {
+        // SAFETY: the descriptor is assumed valid
+        unsafe { slice::from_raw_parts(self.bounds, self.num_bounds as usize) }
+    }
+}
+
+impl fmt::Display for OsdiParamBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.flags & PARA_BOUND_EXCLUDE != 0 { "exclude" } else { "from" };
+        if self.flags & PARA_BOUND_DYNAMIC != 0 {
+            return write!(f, "{kind} <depends on other parameters>");
+        }
+
+        // string bounds are stored without numeric values
+        if self.min.is_nan() {
+            let val = unsafe { osdi_str(self.str_val) };
+            return write!(f, "{kind} \"{val}\"");
+        }
+
+        let min_inclusive = self.flags & PARA_BOUND_MIN_INCLUSIVE != 0;
+        let max_inclusive = self.flags & PARA_BOUND_MAX_INCLUSIVE != 0;
+        if self.min == self.max && min_inclusive && max_inclusive {
+            return write!(f, "{kind} {}", self.min);
+        }
+
+        let open = if min_inclusive { '[' } else { '(' };
+        let close = if max_inclusive { ']' } else { ')' };
+        write!(f, "{kind} {open}{}:{}{close}", self.min, self.max)
+    }
+}
+
 unsafe fn osdi_str(raw: *mut c_char) -> &'static str {
     CStr::from_ptr(raw).to_str().expect("All OSDI strings must be encoded in UTF-8")
 }
@@ -246,6 +329,9 @@ impl ModelImpl for OsdiModel {
             descriptor: self.descriptor,
             data: alloc(self.descriptor.instance_size as usize),
             model_data: self.data,
+            eval_flags: 0,
+            accepted_op_state: vec![Cell::new(0.0); self.descriptor.num_op_states as usize].into(),
+            delay_lines: (0..self.descriptor.num_absdelays).map(|_| DelayLine::default()).collect(),
             _model: self,
         })
     }
@@ -255,9 +341,51 @@ struct OsdiInstance {
     descriptor: &'static OsdiDescriptor,
     data: *mut c_void,
     model_data: *mut c_void,
+    /// flags passed to the last call of eval
+    eval_flags: u32,
+    /// operator states at the last accepted timepoint
+    accepted_op_state: Box<[Cell<f64>]>,
+    /// history of the input of each `absdelay`
+    delay_lines: Box<[DelayLine]>,
     _model: Rc<OsdiModel>, // only kept to ensure the data stays live
 }
 
+/// The input of an `absdelay` at the accepted timepoints. Melange provides the delayed
+/// values to the model so that `absdelay` is exact instead of a pade approximation.
+#[derive(Default)]
+struct DelayLine {
+    samples: VecDeque<(f64, f64)>,
+}
+
+impl DelayLine {
+    /// The input at `time` interpolated linearly between the accepted timepoints.
+    /// `val` is the input at the current timepoint `abstime`.
+    fn delayed(&self, time: f64, abstime: f64, val: f64) -> f64 {
+        let Some(&(start, start_val)) = self.samples.front() else { return val };
+        if time <= start {
+            return start_val;
+        }
+        let end = self.samples.iter().position(|&(t, _)| t > time);
+        let ((t0, x0), (t1, x1)) = match end {
+            Some(end) => (self.samples[end - 1], self.samples[end]),
+            None => (*self.samples.back().unwrap(), (abstime, val)),
+        };
+        if t1 <= t0 {
+            return x0;
+        }
+        x0 + (x1 - x0) * (time - t0) / (t1 - t0)
+    }
+
+    /// Records the input at an accepted timepoint and drops the samples that are no longer
+    /// required. The delay is assumed to not increase (which holds for constant delays).
+    fn accept(&mut self, time: f64, val: f64, delay: f64) {
+        self.samples.push_back((time, val));
+        while self.samples.len() > 1 && self.samples[1].0 <= time - delay {
+            self.samples.pop_front();
+        }
+    }
+}
+
 impl Drop for OsdiInstance {
     fn drop(&mut self) {
         unsafe { dealloc(self.data, self.descriptor.instance_size as usize) }
@@ -283,6 +411,43 @@ impl OsdiInstance {
         }
     }
 
+    fn state_idx(&self) -> &[Cell<u32>] {
+        let ptr = self.data as *mut u8;
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe {
+            let ptr = ptr.add(self.descriptor.state_idx_off as usize) as *mut Cell<u32>;
+            slice::from_raw_parts_mut(ptr, self.descriptor.num_states as usize)
+        }
+    }
+
+    fn op_state(&self) -> &[Cell<f64>] {
+        let ptr = self.data as *mut u8;
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe {
+            let ptr = ptr.add(self.descriptor.op_state_offset as usize) as *mut Cell<f64>;
+            slice::from_raw_parts_mut(ptr, self.descriptor.num_op_states as usize)
+        }
+    }
+
+    fn read_f64(&self, offset: u32) -> f64 {
+        if offset == u32::MAX {
+            return f64::INFINITY;
+        }
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe { ((self.data as *mut u8).add(offset as usize) as *const f64).read() }
+    }
+
+    fn write_f64(&self, offset: u32, val: f64) {
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe { ((self.data as *mut u8).add(offset as usize) as *mut f64).write(val) }
+    }
+
+    /// The value of the input unknown of an `absdelay` in `solution`
+    fn absdelay_input(&self, absdelay: &OsdiAbsdelay, solution: &TiSlice<Node, f64>) -> f64 {
+        let node: Node = self.node_mapping()[absdelay.input as usize].get().into();
+        solution[node]
+    }
+
     fn collapsed(&self) -> &[bool] {
         let ptr = self.data as *mut u8;
         // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
@@ -391,7 +556,9 @@ impl InstanceImpl for OsdiInstance {
             let name = unsafe { osdi_str(node_info.name) };
             let units = unsafe { osdi_str(node_info.units) };
             let residual_units = unsafe { osdi_str(node_info.residual_units) };
-            *node = sim_builder.new_internal_unknown(name, tol, units, residual_units).into();
+            *node = sim_builder
+                .new_internal_unknown(name, tol, units, residual_units, node_info.is_flow)
+                .into();
         }
 
         let node_mapping = self.node_mapping();
@@ -412,6 +579,10 @@ impl InstanceImpl for OsdiInstance {
             sim_builder.ensure_matrix_entry(column, row)
         }
 
+        for state in self.state_idx() {
+            state.set(sim_builder.new_state())
+        }
+
         Ok(())
     }
 
@@ -459,7 +630,7 @@ impl InstanceImpl for OsdiInstance {
         }
     }
 
-    fn eval(&mut self, sim_info: SimInfo<'_>) -> Result<()> {
+    fn eval(&mut self, sim_info: SimInfo<'_>) -> Result<EvalRetFlags> {
         let sim_params = OsdiSimParas {
             names: &mut ptr::null_mut(),
             vals: ptr::null_mut(),
@@ -467,14 +638,28 @@ impl InstanceImpl for OsdiInstance {
             vals_str: ptr::null_mut(),
         };
 
+        // melange signals the initial and final step of each analysis
+        let mut flags = sim_info.flags.bits() | PHASE_AWARE;
+        // the delay lines only hold samples during transient analysis
+        if flags & ANALYSIS_TRAN != 0 {
+            for (absdelay, line) in zip(self.descriptor.absdelays(), &*self.delay_lines) {
+                let delay = self.read_f64(absdelay.delay_offset);
+                let input = self.absdelay_input(absdelay, sim_info.prev_solve);
+                let val = line.delayed(sim_info.abstime - delay, sim_info.abstime, input);
+                self.write_f64(absdelay.value_offset, val);
+            }
+            flags |= EXACT_ABSDELAY;
+        }
+
         let mut info = OsdiSimInfo {
             paras: sim_params,
             abstime: sim_info.abstime,
             prev_solve: sim_info.prev_solve.as_ptr() as *mut f64,
-            prev_state: ptr::null_mut(),
-            next_state: ptr::null_mut(),
-            flags: sim_info.flags.bits(),
+            prev_state: sim_info.prev_state.as_ptr() as *mut f64,
+            next_state: sim_info.next_state.as_ptr() as *mut f64,
+            flags,
         };
+        self.eval_flags = info.flags;
 
         let ret_flags = self.descriptor.eval(
             b"foo\0".as_ptr() as *mut c_void,
@@ -486,12 +671,7 @@ impl InstanceImpl for OsdiInstance {
             bail!("Simulation aborted with $fatal")
         }
 
-        // TODO only during tran
-        // if (ret_flags & EVAL_RET_FLAG_FINISH) != 0 {
-        //     bail!("Simulation aborted with $finish")
-        // }
-
-        Ok(())
+        Ok(EvalRetFlags::from_bits_truncate(ret_flags))
     }
 
     unsafe fn load_matrix_resist(&self) {
@@ -502,12 +682,21 @@ impl InstanceImpl for OsdiInstance {
         self.descriptor.load_jacobian_react(self.data, self.model_data, alpha)
     }
 
+    unsafe fn load_matrix_tran(&self, alpha: f64) {
+        self.descriptor.load_jacobian_tran(self.data, self.model_data, alpha)
+    }
+
+    // the residual is evaluated at the limited voltages, the limit rhs
+    // turns it into the linearization around the unlimited solution
     fn load_residual_react(
         &self,
         _prev_solve: &TiSlice<Node, f64>,
         residual: &mut TiSlice<Node, f64>,
     ) {
-        self.descriptor.load_residual_react(self.data, self.model_data, residual.as_mut_ptr())
+        self.descriptor.load_residual_react(self.data, self.model_data, residual.as_mut_ptr());
+        if self.eval_flags & CALC_REACT_LIM_RHS != 0 {
+            self.descriptor.load_limit_rhs_react(self.data, self.model_data, residual.as_mut_ptr())
+        }
     }
 
     fn load_residual_resist(
@@ -515,7 +704,10 @@ impl InstanceImpl for OsdiInstance {
         _prev_solve: &TiSlice<Node, f64>,
         residual: &mut TiSlice<Node, f64>,
     ) {
-        self.descriptor.load_residual_resist(self.data, self.model_data, residual.as_mut_ptr())
+        self.descriptor.load_residual_resist(self.data, self.model_data, residual.as_mut_ptr());
+        if self.eval_flags & CALC_RESIST_LIM_RHS != 0 {
+            self.descriptor.load_limit_rhs_resist(self.data, self.model_data, residual.as_mut_ptr())
+        }
     }
 
     fn load_lead_current_resist(&self, _dc_solve: &TiSlice<Node, f64>, dst: &mut [f64]) {
@@ -537,4 +729,96 @@ impl InstanceImpl for OsdiInstance {
             };
         }
     }
+
+    fn bound_step(&self) -> f64 {
+        self.read_f64(self.descriptor.bound_step_offset)
+    }
+
+    fn next_breakpoint(&self) -> f64 {
+        self.read_f64(self.descriptor.breakpoint_offset)
+    }
+
+    fn accept_timepoint(&mut self, time: f64, solution: &TiSlice<Node, f64>) {
+        for (dst, src) in zip(&*self.accepted_op_state, self.op_state()) {
+            dst.set(src.get());
+        }
+        for (i, absdelay) in self.descriptor.absdelays().iter().enumerate() {
+            let delay = self.read_f64(absdelay.delay_offset);
+            let input = self.absdelay_input(absdelay, solution);
+            self.delay_lines[i].accept(time, input, delay);
+        }
+    }
+
+    fn reject_timepoint(&mut self) {
+        for (dst, src) in zip(self.op_state(), &*self.accepted_op_state) {
+            dst.set(src.get());
+        }
+    }
+
+    fn noise_sources(&self) -> Vec<NoiseSource> {
+        let node_mapping = self.node_mapping();
+        let node = |node: u32| {
+            if node == u32::MAX {
+                Node::GROUND
+            } else {
+                node_mapping[node as usize].get().into()
+            }
+        };
+
+        self.descriptor
+            .noise_sources()
+            .iter()
+            .map(|src| NoiseSource {
+                name: unsafe { osdi_str(src.name) }.to_owned(),
+                hi: node(src.nodes.node_1),
+                lo: node(src.nodes.node_2),
+            })
+            .collect()
+    }
+
+    fn load_noise(&self, freq: f64, dst: &mut [f64]) {
+        self.descriptor.load_noise(self.data, self.model_data, freq, dst.as_mut_ptr())
+    }
+
+    fn noise_correlations(&self) -> Vec<(usize, usize)> {
+        self.descriptor
+            .noise_correlations()
+            .iter()
+            .map(|corr| (corr.src_1 as usize, corr.src_2 as usize))
+            .collect()
+    }
+
+    fn load_noise_correlated(&self, freq: f64, dst: &mut [Complex64]) {
+        // Complex64 is repr(C) and therefore layout compatible with (real, imaginary) pairs
+        let dst = dst.as_mut_ptr().cast();
+        self.descriptor.load_noise_correlated(self.data, self.model_data, freq, dst)
+    }
+
+    fn op_vars(&self) -> Vec<OpVar> {
+        // the ids of the opvars start after the parameters
+        let ids = self.descriptor.num_params..;
+        zip(ids, self.descriptor.opvars())
+            .filter_map(|(id, opvar)| {
+                let flags = ACCESS_FLAG_READ | ACCESS_FLAG_INSTANCE;
+                let ptr = self.descriptor.access(self.data, self.model_data, id, flags);
+                if ptr.is_null() {
+                    return None;
+                }
+                // SAFETY: access returns a pointer to a value of the type stored in the flags
+                let value = unsafe {
+                    match opvar.flags & PARA_TY_MASK {
+                        PARA_TY_REAL => *(ptr as *const f64),
+                        PARA_TY_INT => *(ptr as *const i32) as f64,
+                        // string opvars can not be reported as numbers
+                        _ => return None,
+                    }
+                };
+                // SAFETY: the descriptor is assumed valid
+                let (name, units, description) = unsafe {
+                    (osdi_str(*opvar.name), osdi_str(opvar.units), osdi_str(opvar.description))
+                };
+                Some(OpVar { name, units, description, value })
+            })
+            .collect()
+    }
 }
@@ -1,5 +1,5 @@
-# MOCKUP currently melange is not complete
-# This file represents a goal API of melange but some details may change
+from pathlib import Path
+
 from melange import Circuit, CircuitInstance
 
 import numpy as np
@@ -8,36 +8,36 @@ from matplotlib import pyplot as plt
 # any values (like length) could be replaced by parameters to allow sweeping
 
 
-# simulation parameters 
+# simulation parameters
 vg = np.linspace(-2,2,100)
 freq = 10e9
 temp = 300
 
 # create circuit
 circ = Circuit("test_circuit")
-circ.load_veriloga_file("bsimbulk.va")
-fet = CircuitInstance(circ, "test_fet", "bsimbulk", ports=["drain", "gate" ,"gnd", "gnd"])
+circ.load_veriloga_file(Path(__file__).parents[2] / "integration_tests" / "BSIMBULK" / "bsimbulk.va")
+fet = CircuitInstance(circ, "test_fet", "bsimbulk", ports=["drain", "gate" ,"0", "0"])
 fet.set_param("RSH", 1e-3) # real model has many more parameter
-vdd = CircuitInstance(circ, "vdd", "vsource", ports=["drain", "gnd"])
+vdd = CircuitInstance(circ, "vdd", "vsource", ports=["drain", "0"])
 vdd.set_param("dc", 2)
-vdd.set_param("ac", 0)
-vin = CircuitInstance(circ, "vin", "vsource", ports=["gate", "gnd"])
+vdd.set_param("mag", 0)
+vin = CircuitInstance(circ, "vin", "vsource", ports=["gate", "0"])
 vin.set_param("dc", "gate_bias")
-vin.set_param("ac", 1)
+vin.set_param("mag", 1)
 
 # set circuit parameters and setup simulation for each parameter
 simulation = circ.prepare_sim(temp=temp, gate_bias=vg)
 
 # obain drain current from dc simulations
 simulation.dc_op()
-id = simulation.lead_current("vdd", "anode");
+id = simulation.lead_current("vdd", "A")
 # plot transfer characteristic Id(Vg)
 plt.plot(vg, id)
 
-# deterime ft from ac simulations (using the previously simulated operating points)
+# deterime ft from ac simulations
 simulation.ac(freq=freq)
-y21 = simulation.ac_lead_current("vdd", "anode")
-y11 = simulation.ac_lead_current("vin", "anode")
+y21 = simulation.ac_lead_current("vdd", "A")
+y11 = simulation.ac_lead_current("vin", "A")
 ft = freq/np.imag(y11/y21)
 # plot ft characteristic ft(Vg)
 plt.plot(vg, ft)
@@ -0,0 +1,26 @@
+from setuptools import setup
+from setuptools_rust import RustExtension, Binding
+
+
+extension = RustExtension(
+    "melange",
+    path="Cargo.toml",
+    rust_version=">=1.64",
+    binding=Binding.PyO3,
+    debug=False,
+)
+
+
+setup(
+    name="melange",
+    version="0.0.0",
+    author="DSPOM",
+    author_email="dspom@protonmail.com",
+    description="Circuit simulation with Verilog-A compact models",
+    license="GPL-3",
+    python_requires=">=3.8",
+    install_requires=["numpy"],
+    # rust extensions are not zip safe, just like C-extensions.
+    zip_safe=False,
+    rust_extensions=[extension],
+)
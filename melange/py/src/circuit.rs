@@ -0,0 +1,177 @@
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Context, Result};
+use camino::Utf8PathBuf;
+use melange_core::circuit::{CircuitModelSrc, InstanceId, ModelId};
+use melange_core::elaboration::CircuitInstanceDescription;
+use melange_core::netlist::Netlist;
+use melange_core::{Arena, Circuit, Expr, VaOpts};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+
+use crate::simulation::PySimulation;
+
+/// A circuit together with the arena that stores its parameter expressions
+#[pyclass(name = "Circuit", unsendable)]
+pub struct PyCircuit {
+    pub(crate) circ: Circuit,
+    pub(crate) arena: Arena,
+}
+
+#[pymethods]
+impl PyCircuit {
+    #[new]
+    fn new(name: String) -> PyCircuit {
+        let mut arena = Arena::new();
+        let circ = Circuit::new(name, &mut arena);
+        PyCircuit { circ, arena }
+    }
+
+    /// Creates a circuit from a spectre netlist. Analyses within the netlist are ignored.
+    #[staticmethod]
+    #[pyo3(signature = (path, include = Vec::new(), defines = Vec::new()))]
+    fn from_netlist(
+        path: PathBuf,
+        include: Vec<PathBuf>,
+        defines: Vec<String>,
+    ) -> Result<PyCircuit> {
+        let opts = va_opts(include, defines)?;
+        let mut arena = Arena::new();
+        let netlist = Netlist::parse(&utf8_path(path)?, &mut arena, &opts)?;
+        Ok(PyCircuit { circ: netlist.circuit, arena })
+    }
+
+    /// Compiles a Verilog-A file and registers its modules as devices.
+    /// Returns the names of the new devices.
+    #[pyo3(signature = (path, include = Vec::new(), defines = Vec::new()))]
+    fn load_veriloga_file(
+        &mut self,
+        path: PathBuf,
+        include: Vec<PathBuf>,
+        defines: Vec<String>,
+    ) -> Result<Vec<&'static str>> {
+        let opts = va_opts(include, defines)?;
+        let devices = self.circ.load_veriloga_file(utf8_path(path)?, &opts)?;
+        Ok(devices.into_iter().map(|dev| self.circ[dev].name).collect())
+    }
+
+    /// The names of all nodes in the circuit
+    fn nodes(&self) -> Vec<String> {
+        self.circ.nodes().map(|node| self.circ.node_name(node).to_owned()).collect()
+    }
+
+    /// Sets up a simulation of this circuit. All keyword arguments set circuit parameters
+    /// to either a number or a 1D array. If arrays are passed, the analyses run once for each
+    /// of their elements and all results gain a leading axis.
+    /// `temp` defaults to 300.15 K.
+    #[pyo3(signature = (**params))]
+    fn prepare_sim(slf: &PyCell<Self>, params: Option<&PyDict>) -> Result<PySimulation> {
+        PySimulation::new(slf, params)
+    }
+}
+
+/// An explicit model that can be shared by multiple instances
+#[pyclass(name = "CircuitModel", unsendable)]
+pub struct PyModel {
+    circ: Py<PyCircuit>,
+    id: ModelId,
+}
+
+#[pymethods]
+impl PyModel {
+    #[new]
+    fn new(circ: &PyCell<PyCircuit>, name: String, device: &str) -> Result<PyModel> {
+        let id = circ.borrow_mut().circ.new_model_by_name(name, device)?;
+        Ok(PyModel { circ: circ.into(), id })
+    }
+
+    /// Sets a parameter to a number or to the circuit parameter with the given name.
+    /// Circuit parameters that do not exist yet are created.
+    fn set_param(&self, py: Python<'_>, name: &str, val: &PyAny) -> Result<()> {
+        let mut circ = self.circ.borrow_mut(py);
+        let expr = param_expr(&mut circ, val)?;
+        circ.circ.set_model_param(self.id, name, expr)
+    }
+}
+
+/// An instance of a device or of a `CircuitModel`
+#[pyclass(name = "CircuitInstance", unsendable)]
+pub struct PyInstance {
+    circ: Py<PyCircuit>,
+    pub(crate) id: InstanceId,
+}
+
+#[pymethods]
+impl PyInstance {
+    /// `master` is the name of a device or model. `ports` are the names of the nodes that are
+    /// connected to the terminals. The node `0` (or `ground`) is the reference node.
+    #[new]
+    #[pyo3(signature = (circ, name, master, ports = Vec::new()))]
+    fn new(
+        circ: &PyCell<PyCircuit>,
+        name: String,
+        master: String,
+        ports: Vec<String>,
+    ) -> Result<PyInstance> {
+        let terminal_connections = ports
+            .into_iter()
+            .map(|port| if port == "0" { "ground".to_owned() } else { port })
+            .collect();
+        let descr = CircuitInstanceDescription {
+            name,
+            master,
+            parameters: Vec::new(),
+            terminal_connections,
+        };
+        let id = circ.borrow_mut().circ.elaborate_instance(descr)?;
+        Ok(PyInstance { circ: circ.into(), id })
+    }
+
+    #[getter]
+    fn name(&self, py: Python<'_>) -> String {
+        self.circ.borrow(py).circ[self.id].name.clone()
+    }
+
+    /// Sets a parameter to a number or to the circuit parameter with the given name.
+    /// Circuit parameters that do not exist yet are created.
+    fn set_param(&self, py: Python<'_>, name: &str, val: &PyAny) -> Result<()> {
+        let mut circ = self.circ.borrow_mut(py);
+        let expr = param_expr(&mut circ, val)?;
+        let model = circ.circ[self.id].model;
+        // instances of a device receive all parameters on their implicit model
+        if matches!(circ.circ[model].src, CircuitModelSrc::Implicit(_)) {
+            circ.circ.set_model_param(model, name, expr)
+        } else {
+            circ.circ.set_instance_param(self.id, name, expr)
+        }
+    }
+}
+
+fn param_expr(circ: &mut PyCircuit, val: &PyAny) -> Result<Expr> {
+    if let Ok(name) = val.extract::<&str>() {
+        let expr = match circ.circ.lookup_param(name, &circ.arena) {
+            Some((_, expr)) => expr,
+            None => circ.circ.def_param(name.to_owned(), None, &mut circ.arena)?.1,
+        };
+        return Ok(expr);
+    }
+
+    let val: f64 = val
+        .extract()
+        .context("parameter values must be numbers or the name of a circuit parameter")?;
+    Ok(val.into())
+}
+
+fn va_opts(include: Vec<PathBuf>, defines: Vec<String>) -> Result<VaOpts> {
+    let mut opts = VaOpts::default();
+    for dir in include {
+        opts.add_include_dir(&utf8_path(dir)?)?;
+    }
+    opts.defines = defines;
+    Ok(opts)
+}
+
+fn utf8_path(path: PathBuf) -> Result<Utf8PathBuf> {
+    Utf8PathBuf::from_path_buf(path)
+        .map_err(|path| anyhow!("{} is not a valid utf-8 path", path.display()))
+}
@@ -0,0 +1,23 @@
+//! Python bindings for melange.
+//!
+//! Circuits are built with the `Circuit`, `CircuitModel` and `CircuitInstance` classes (or parsed
+//! from a netlist). `Circuit.prepare_sim` creates a `Simulation` that runs the analyses for one or
+//! multiple values of the circuit parameters and returns the results as NumPy arrays.
+
+use pyo3::prelude::*;
+
+use crate::circuit::{PyCircuit, PyInstance, PyModel};
+use crate::simulation::PySimulation;
+
+mod circuit;
+mod simulation;
+
+#[pymodule]
+fn melange(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
+    m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+    m.add_class::<PyCircuit>()?;
+    m.add_class::<PyModel>()?;
+    m.add_class::<PyInstance>()?;
+    m.add_class::<PySimulation>()?;
+    Ok(())
+}
@@ -0,0 +1,444 @@
+use std::f64::consts::TAU;
+
+use anyhow::{bail, Context, Result};
+use melange_core::circuit::{InstanceId, Node};
+use melange_core::simulation::{
+    IntegrationMethod, NoiseSolution, OpVar, SimConfig, Simulation, TranSolution,
+};
+use melange_core::{Circuit, CircuitParam, ExprEvalCtx};
+use num_complex::Complex64;
+use numpy::{Element, PyArray1, PyArrayDyn};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use typed_index_collections::TiVec;
+
+use crate::circuit::{PyCircuit, PyInstance};
+
+/// 27°C, used if `temp` is not passed to `prepare_sim`
+const DEFAULT_TEMP: f64 = 300.15;
+
+/// Runs the analyses of a circuit for one or multiple sets of circuit parameters.
+/// Each analysis stores its results which are then accessed with the corresponding methods
+/// (for example `dc_op` and `voltage`).
+#[pyclass(name = "Simulation", unsendable)]
+pub struct PySimulation {
+    circ: Py<PyCircuit>,
+    /// the values of the circuit parameters at each point
+    points: Vec<Vec<(CircuitParam, f64)>>,
+    /// whether arrays were passed to `prepare_sim`, results have a leading axis for the points
+    swept: bool,
+    op: Option<Vec<OpPoint>>,
+    ac: Option<FreqResult<Vec<AcPoint>>>,
+    noise: Option<FreqResult<NoiseSolution>>,
+    tran: Option<TranResult>,
+}
+
+struct OpPoint {
+    solution: TiVec<Node, f64>,
+    lead_currents: TiVec<InstanceId, Vec<f64>>,
+    op_vars: TiVec<InstanceId, Vec<OpVar>>,
+}
+
+struct AcPoint {
+    solution: TiVec<Node, Complex64>,
+    lead_currents: TiVec<InstanceId, Vec<Complex64>>,
+}
+
+/// The results of a small-signal analysis at each point
+struct FreqResult<T> {
+    num_freqs: usize,
+    /// whether an array of frequencies was passed, results have a trailing axis for the frequencies
+    swept: bool,
+    data: Vec<T>,
+}
+
+struct TranResult {
+    /// the timepoints requested by the user
+    time: Vec<f64>,
+    data: Vec<TranSolution>,
+}
+
+impl PySimulation {
+    pub(crate) fn new(circ: &PyCell<PyCircuit>, params: Option<&PyDict>) -> Result<PySimulation> {
+        let mut values = Vec::new();
+        let mut num_points = None;
+        let mut has_temp = false;
+        {
+            let mut circ = circ.borrow_mut();
+            let circ = &mut *circ;
+            for (name, val) in params.into_iter().flatten() {
+                let name: &str = name.extract()?;
+                let param = if name == "temp" {
+                    has_temp = true;
+                    CircuitParam::TEMPERATURE
+                } else {
+                    match circ.circ.lookup_param(name, &circ.arena) {
+                        Some((param, _)) => param,
+                        None => bail!("unknown circuit parameter '{name}'"),
+                    }
+                };
+
+                let (vals, is_array) = scalar_or_array(val)
+                    .with_context(|| format!("invalid value for parameter '{name}'"))?;
+                if is_array {
+                    match num_points {
+                        Some(num_points) if num_points != vals.len() => {
+                            bail!("all array parameters must have the same length")
+                        }
+                        _ => num_points = Some(vals.len()),
+                    }
+                }
+                values.push((param, vals, is_array));
+            }
+        }
+
+        if !has_temp {
+            values.push((CircuitParam::TEMPERATURE, vec![DEFAULT_TEMP], false));
+        }
+
+        let points = (0..num_points.unwrap_or(1))
+            .map(|i| {
+                values
+                    .iter()
+                    .map(|(param, vals, is_array)| {
+                        (*param, if *is_array { vals[i] } else { vals[0] })
+                    })
+                    .collect()
+            })
+            .collect();
+
+        Ok(PySimulation {
+            circ: circ.into(),
+            points,
+            swept: num_points.is_some(),
+            op: None,
+            ac: None,
+            noise: None,
+            tran: None,
+        })
+    }
+
+    /// Runs `analysis` at every point. Each point starts from the final solution
+    /// of the previous point which helps convergence during sweeps.
+    fn run<T>(
+        &self,
+        py: Python<'_>,
+        config: SimConfig,
+        mut analysis: impl FnMut(&mut Simulation<'_>, &Circuit) -> Result<T>,
+    ) -> Result<Vec<T>> {
+        let circ = self.circ.borrow(py);
+        let mut sim = circ.circ.setup_simulation(config)?;
+        let mut guess: Option<TiVec<Node, f64>> = None;
+        let mut res = Vec::with_capacity(self.points.len());
+        for point in &self.points {
+            let mut ctx = ExprEvalCtx::new(&circ.arena);
+            sim.prepare_solver_with_overrides(ctx.borrow(), &circ.arena, point)?;
+            // the number of internal unknowns may change with the parameters
+            if let Some(guess) =
+                guess.as_deref().filter(|guess| guess.len() == sim.solution().len())
+            {
+                sim.set_initial_guess(guess);
+            }
+            res.push(analysis(&mut sim, &circ.circ)?);
+            guess = Some(sim.solution().to_owned());
+        }
+        Ok(res)
+    }
+
+    /// Creates an array with a leading axis for the points (if parameters were swept)
+    /// followed by the `(len, swept)` axes in `inner` that were swept
+    fn to_array<'py, T: Element>(
+        &self,
+        py: Python<'py>,
+        data: Vec<T>,
+        inner: &[(usize, bool)],
+    ) -> Result<&'py PyArrayDyn<T>> {
+        let mut shape = Vec::with_capacity(inner.len() + 1);
+        if self.swept {
+            shape.push(self.points.len());
+        }
+        shape.extend(inner.iter().filter(|(_, swept)| *swept).map(|(len, _)| *len));
+        Ok(PyArray1::from_vec(py, data).reshape(shape)?)
+    }
+
+    fn lookup_node(&self, py: Python<'_>, name: &str) -> Result<Node> {
+        let name = if name == "0" { "ground" } else { name };
+        self.circ
+            .borrow(py)
+            .circ
+            .lookup_node(name)
+            .with_context(|| format!("unknown node '{name}'"))
+    }
+
+    /// Resolves an instance that is passed as `CircuitInstance` or by name
+    fn lookup_instance(&self, py: Python<'_>, inst: &PyAny) -> Result<InstanceId> {
+        if let Ok(inst) = inst.extract::<PyRef<PyInstance>>() {
+            return Ok(inst.id);
+        }
+        let name: &str = inst.extract()?;
+        let circ = self.circ.borrow(py);
+        circ.circ.lookup_instance(name).with_context(|| format!("unknown instance '{name}'"))
+    }
+
+    /// Resolves an instance and one of its terminals (by name or index)
+    fn lookup_terminal(
+        &self,
+        py: Python<'_>,
+        inst: &PyAny,
+        terminal: &PyAny,
+    ) -> Result<(InstanceId, usize)> {
+        let inst = self.lookup_instance(py, inst)?;
+        let circ = self.circ.borrow(py);
+        let circ = &circ.circ;
+        let info = &circ[inst];
+        let terminal = match terminal.extract::<usize>() {
+            Ok(terminal) => terminal,
+            Err(_) => {
+                let name: &str = terminal.extract()?;
+                let terminals = &circ[circ[info.model].device].terminals;
+                terminals
+                    .iter()
+                    .position(|&terminal| terminal == name)
+                    .with_context(|| format!("'{}' has no terminal '{name}'", info.name))?
+            }
+        };
+        if terminal >= info.connections.len() {
+            bail!("terminal {terminal} of '{}' is not connected", info.name)
+        }
+        Ok((inst, terminal))
+    }
+
+    fn op(&self) -> Result<&[OpPoint]> {
+        self.op.as_deref().context("dc_op() must be called first")
+    }
+
+    fn ac_result(&self) -> Result<&FreqResult<Vec<AcPoint>>> {
+        self.ac.as_ref().context("ac() must be called first")
+    }
+
+    fn noise_result(&self) -> Result<&FreqResult<NoiseSolution>> {
+        self.noise.as_ref().context("noise() must be called first")
+    }
+
+    fn tran_result(&self) -> Result<&TranResult> {
+        self.tran.as_ref().context("tran() must be called first")
+    }
+}
+
+#[pymethods]
+impl PySimulation {
+    /// Solves the DC operating point
+    fn dc_op(&mut self, py: Python<'_>) -> Result<()> {
+        let op = self.run(py, SimConfig::default(), |sim, circ| {
+            let solution = sim.dc_op()?.to_owned();
+            // the lead currents and opvars are computed from the solution above
+            let lead_currents =
+                circ.instances().map(|inst| sim.dc_lead_current(inst)).collect::<Result<_>>()?;
+            let op_vars = sim.op_vars()?;
+            Ok(OpPoint { solution, lead_currents, op_vars })
+        })?;
+        self.op = Some(op);
+        Ok(())
+    }
+
+    /// The DC voltage of a node
+    fn voltage<'py>(&self, py: Python<'py>, node: &str) -> Result<&'py PyArrayDyn<f64>> {
+        let node = self.lookup_node(py, node)?;
+        let data = self.op()?.iter().map(|op| op.solution[node]).collect();
+        self.to_array(py, data, &[])
+    }
+
+    /// The DC current flowing into a terminal of an instance
+    fn lead_current<'py>(
+        &self,
+        py: Python<'py>,
+        inst: &PyAny,
+        terminal: &PyAny,
+    ) -> Result<&'py PyArrayDyn<f64>> {
+        let (inst, terminal) = self.lookup_terminal(py, inst, terminal)?;
+        let data = self.op()?.iter().map(|op| op.lead_currents[inst][terminal]).collect();
+        self.to_array(py, data, &[])
+    }
+
+    /// An operating point variable (like `gm`) of an instance at the DC operating point
+    fn op_var<'py>(
+        &self,
+        py: Python<'py>,
+        inst: &PyAny,
+        name: &str,
+    ) -> Result<&'py PyArrayDyn<f64>> {
+        let inst = self.lookup_instance(py, inst)?;
+        let circ = self.circ.borrow(py);
+        let data = self
+            .op()?
+            .iter()
+            .map(|op| {
+                let var = op.op_vars[inst].iter().find(|var| var.name == name);
+                var.map(|var| var.value).with_context(|| {
+                    format!("'{}' has no operating point variable '{name}'", circ.circ[inst].name)
+                })
+            })
+            .collect::<Result<_>>()?;
+        self.to_array(py, data, &[])
+    }
+
+    /// Runs a small-signal analysis at one frequency or an array of frequencies (in Hz)
+    fn ac(&mut self, py: Python<'_>, freq: &PyAny) -> Result<()> {
+        let (freqs, swept) = scalar_or_array(freq)?;
+        let data = self.run(py, SimConfig::default(), |sim, circ| {
+            freqs
+                .iter()
+                .map(|&freq| -> Result<AcPoint> {
+                    sim.set_omega(TAU * freq);
+                    let solution = sim.ac()?.to_owned();
+                    let lead_currents = circ
+                        .instances()
+                        .map(|inst| sim.ac_lead_current(inst))
+                        .collect::<Result<_>>()?;
+                    Ok(AcPoint { solution, lead_currents })
+                })
+                .collect::<Result<Vec<_>>>()
+        })?;
+        self.ac = Some(FreqResult { num_freqs: freqs.len(), swept, data });
+        Ok(())
+    }
+
+    /// The complex small-signal voltage of a node
+    fn ac_voltage<'py>(&self, py: Python<'py>, node: &str) -> Result<&'py PyArrayDyn<Complex64>> {
+        let node = self.lookup_node(py, node)?;
+        let ac = self.ac_result()?;
+        let data = ac.data.iter().flatten().map(|point| point.solution[node]).collect();
+        self.to_array(py, data, &[(ac.num_freqs, ac.swept)])
+    }
+
+    /// The complex small-signal current flowing into a terminal of an instance
+    fn ac_lead_current<'py>(
+        &self,
+        py: Python<'py>,
+        inst: &PyAny,
+        terminal: &PyAny,
+    ) -> Result<&'py PyArrayDyn<Complex64>> {
+        let (inst, terminal) = self.lookup_terminal(py, inst, terminal)?;
+        let ac = self.ac_result()?;
+        let data =
+            ac.data.iter().flatten().map(|point| point.lead_currents[inst][terminal]).collect();
+        self.to_array(py, data, &[(ac.num_freqs, ac.swept)])
+    }
+
+    /// Runs a noise analysis at one frequency or an array of frequencies (in Hz).
+    /// The output noise is measured between the nodes `out_hi` and `out_lo`.
+    fn noise(&mut self, py: Python<'_>, out_hi: &str, out_lo: &str, freq: &PyAny) -> Result<()> {
+        let (freqs, swept) = scalar_or_array(freq)?;
+        let out_hi = self.lookup_node(py, out_hi)?;
+        let out_lo = self.lookup_node(py, out_lo)?;
+        let data =
+            self.run(py, SimConfig::default(), |sim, _| sim.noise(out_hi, out_lo, &freqs))?;
+        self.noise = Some(FreqResult { num_freqs: freqs.len(), swept, data });
+        Ok(())
+    }
+
+    /// The power spectral density of the output noise (V²/Hz)
+    fn noise_total<'py>(&self, py: Python<'py>) -> Result<&'py PyArrayDyn<f64>> {
+        let noise = self.noise_result()?;
+        let data = noise.data.iter().flat_map(|point| point.total.iter().copied()).collect();
+        self.to_array(py, data, &[(noise.num_freqs, noise.swept)])
+    }
+
+    /// The contribution of an instance to the output noise (V²/Hz)
+    fn noise_contribution<'py>(
+        &self,
+        py: Python<'py>,
+        inst: &PyAny,
+    ) -> Result<&'py PyArrayDyn<f64>> {
+        let inst = self.lookup_instance(py, inst)?;
+        let noise = self.noise_result()?;
+        let data =
+            noise.data.iter().flat_map(|point| point.instances[inst].iter().copied()).collect();
+        self.to_array(py, data, &[(noise.num_freqs, noise.swept)])
+    }
+
+    /// Runs a transient analysis until the last of the (sorted) timepoints in `time`.
+    /// The timestep never exceeds the spacing of `time` and all waveforms are
+    /// interpolated at `time`. `method` is either `"be"`, `"trap"` or `"gear2"`.
+    #[pyo3(signature = (time, method = "trap"))]
+    fn tran(&mut self, py: Python<'_>, time: Vec<f64>, method: &str) -> Result<()> {
+        let integration = match method {
+            "be" => IntegrationMethod::BackwardEuler,
+            "trap" => IntegrationMethod::Trapezoidal,
+            "gear2" => IntegrationMethod::Gear2,
+            _ => bail!("unknown integration method '{method}'"),
+        };
+        let tstop = match time.last() {
+            Some(&tstop) => tstop,
+            None => bail!("at least one timepoint is required"),
+        };
+        if time[0] < 0.0 || time.windows(2).any(|window| window[1] < window[0]) {
+            bail!("the timepoints must be sorted and must not be negative")
+        }
+        let tstep = time
+            .windows(2)
+            .map(|window| window[1] - window[0])
+            .filter(|&h| h > 0.0)
+            .fold(tstop, f64::min);
+
+        let config = SimConfig { integration, ..SimConfig::default() };
+        let data = self.run(py, config, |sim, _| Ok(sim.tran(tstop, tstep)?.clone()))?;
+        self.tran = Some(TranResult { time, data });
+        Ok(())
+    }
+
+    /// The voltage of a node at each timepoint
+    fn tran_voltage<'py>(&self, py: Python<'py>, node: &str) -> Result<&'py PyArrayDyn<f64>> {
+        let node = self.lookup_node(py, node)?;
+        let tran = self.tran_result()?;
+        let mut data = Vec::with_capacity(tran.data.len() * tran.time.len());
+        for solution in &tran.data {
+            interpolate(&solution.time, solution.waveform(node), &tran.time, &mut data);
+        }
+        self.to_array(py, data, &[(tran.time.len(), true)])
+    }
+
+    /// The current flowing into a terminal of an instance at each timepoint
+    fn tran_lead_current<'py>(
+        &self,
+        py: Python<'py>,
+        inst: &PyAny,
+        terminal: &PyAny,
+    ) -> Result<&'py PyArrayDyn<f64>> {
+        let (inst, terminal) = self.lookup_terminal(py, inst, terminal)?;
+        let tran = self.tran_result()?;
+        let mut data = Vec::with_capacity(tran.data.len() * tran.time.len());
+        for solution in &tran.data {
+            let waveform = solution.lead_current(inst, terminal);
+            interpolate(&solution.time, waveform, &tran.time, &mut data);
+        }
+        self.to_array(py, data, &[(tran.time.len(), true)])
+    }
+}
+
+/// Extracts either a single number or a 1D array of numbers.
+/// Returns whether an array was passed.
+fn scalar_or_array(val: &PyAny) -> Result<(Vec<f64>, bool)> {
+    if let Ok(val) = val.extract::<f64>() {
+        return Ok((vec![val], false));
+    }
+    let vals = val.extract().context("expected a number or a 1D array of numbers")?;
+    Ok((vals, true))
+}
+
+/// Linearly interpolates the waveform `src` at the sorted timepoints `src_time`
+/// onto `time` and appends the result to `dst`
+fn interpolate(src_time: &[f64], src: impl Iterator<Item = f64>, time: &[f64], dst: &mut Vec<f64>) {
+    let src: Vec<f64> = src.collect();
+    dst.extend(time.iter().map(|&t| {
+        let i = src_time.partition_point(|&src_t| src_t < t);
+        if i == 0 {
+            src[0]
+        } else if i == src.len() {
+            src[i - 1]
+        } else {
+            let frac = (t - src_time[i - 1]) / (src_time[i] - src_time[i - 1]);
+            src[i - 1] + frac * (src[i] - src[i - 1])
+        }
+    }))
+}
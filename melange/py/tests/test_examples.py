@@ -0,0 +1,46 @@
+"""Runs the workflows of the examples in melange/examples.
+
+The examples are executed as scripts (with a non-interactive matplotlib backend)
+and the results they compute are checked for plausibility.
+Run with `pytest melange/py/tests` after installing the bindings with `pip install melange/py`.
+"""
+
+import runpy
+from pathlib import Path
+
+import numpy as np
+import pytest
+
+EXAMPLES = Path(__file__).parents[2] / "examples"
+
+
+def test_mos_characteristics(monkeypatch):
+    pytest.importorskip("matplotlib")
+    monkeypatch.setenv("MPLBACKEND", "Agg")
+    res = runpy.run_path(str(EXAMPLES / "mos_characteristics.py"))
+
+    vg, id, ft = res["vg"], res["id"], res["ft"]
+    assert id.shape == vg.shape
+    assert ft.shape == vg.shape
+    assert np.all(np.isfinite(id))
+    assert np.all(np.isfinite(ft))
+    # the drain current flows into the supply so its lead current is negative,
+    # its magnitude grows once the transistor turns on
+    assert abs(id[-1]) > 100 * abs(id[0])
+
+    # a single dc_op computes the voltages and lead currents of every bias point
+    simulation = res["simulation"]
+    assert np.allclose(simulation.voltage("gate"), vg)
+    assert np.allclose(simulation.voltage("drain"), 2.0)
+
+
+def test_optimize_circuit_param():
+    res = runpy.run_path(str(EXAMPLES / "optimize_circuit_param.py"))
+    amplifier_gain = res["amplifier_gain"]
+
+    gain = amplifier_gain(1e-6, 1e-6)
+    assert np.isfinite(gain).all()
+    # the geometry changes the transconductance and therefore the gain
+    wide = amplifier_gain(1e-6, 10e-6)
+    assert np.isfinite(wide).all()
+    assert not np.allclose(gain, wide)
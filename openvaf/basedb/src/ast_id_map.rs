@@ -95,6 +95,7 @@ pub(crate) fn has_id_map_entry(kind: SyntaxKind) -> bool {
             || ast::BlockStmt::can_cast(kind)
             || ast::Param::can_cast(kind)
             || ast::Var::can_cast(kind)
+            || ast::Instance::can_cast(kind)
             || ast::FunctionArg::can_cast(kind)
             || ast::NatureAttr::can_cast(kind)
             || ast::DisciplineAttr::can_cast(kind)
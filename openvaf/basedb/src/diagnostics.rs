@@ -1,9 +1,15 @@
-pub use sink::{print_all, ConsoleSink, DiagnosticSink};
+use std::ops::Range;
+
+pub use json::{write_str as write_json_str, JsonSink};
+pub use sarif::SarifSink;
+pub use sink::{print_all, ConsoleSink, DiagnosticSink, MessageFormat, OutputSink};
 
 use crate::lints::{Lint, LintData, LintLevel, LintSrc};
 use crate::{BaseDB, FileId};
 
+mod json;
 mod preprocessor_error;
+mod sarif;
 pub mod sink;
 mod syntax_error;
 
@@ -21,6 +27,13 @@ pub trait Diagnostic {
 
     fn build_report(&self, root_file: FileId, db: &dyn BaseDB) -> Report;
 
+    /// The (unresolved) span of the primary label. Machine readable sinks use this
+    /// to reconstruct the macro expansion backtrace which is lost once the span
+    /// is resolved to a file.
+    fn primary_span(&self, _root_file: FileId, _db: &dyn BaseDB) -> Option<CtxSpan> {
+        None
+    }
+
     fn to_report(&self, root_file: FileId, db: &dyn BaseDB) -> Option<Report> {
         if let Some((lint, lint_src)) = self.lint(root_file, db) {
             let (lvl, is_default) = lint_src.lvl(lint, root_file, db);
@@ -52,7 +65,51 @@ pub trait Diagnostic {
 
 pub const HINT_UNSUPPORTED: &str = "this is allowed by VerilogAMS language spec but was purposefully excluded from the supported language subset\nmore details can be found in the OpenVAF documentation";
 
-// TODO support expansion backtrace
+/// A single step of the expansion backtrace of a diagnostic.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExpansionSite {
+    pub span: FileSpan,
+    /// whether this step was caused by an `include directive (instead of a macro call)
+    pub is_include: bool,
+}
+
+/// Returns the sites at which the code that `span` points to was expanded (innermost first).
+/// Expansion steps that are already covered by the primary label of `report` are skipped.
+pub fn expansion_backtrace(
+    report: &Report,
+    span: CtxSpan,
+    sm: &SourceMap,
+    db: &dyn BaseDB,
+) -> Vec<ExpansionSite> {
+    let expansion = sm.lookup_expansion(span);
+    let primary = report.labels.iter().find(|label| label.style == LabelStyle::Primary);
+    let start = primary
+        .and_then(|label| {
+            expansion.iter().rposition(|site| {
+                let range: Range<usize> = site.range.into();
+                site.file == label.file_id
+                    && range.start <= label.range.start
+                    && label.range.end <= range.end
+            })
+        })
+        .unwrap_or(0);
+
+    expansion[start + 1..]
+        .iter()
+        .map(|&span| {
+            let is_include = db
+                .file_text(span.file)
+                .ok()
+                .and_then(|text| {
+                    text.get(Range::from(span.range)).map(|src| src.starts_with("`include"))
+                })
+                .unwrap_or(false);
+            ExpansionSite { span, is_include }
+        })
+        .collect()
+}
+
+// TODO support expansion backtrace in the console output
 
 pub fn to_unified_spans<const N: usize>(
     sm: &SourceMap,
@@ -0,0 +1,188 @@
+//! A [`DiagnosticSink`] that prints each diagnostic as a single line of JSON.
+//!
+//! Every line is an object of the following form:
+//!
+//! ```json
+//! {
+//!     "severity": "error" | "warning" | "note" | "help" | "bug",
+//!     "code": "L001" | null,
+//!     "lint": "lint_name" | null,
+//!     "message": "...",
+//!     "spans": [{
+//!         "file": "/path/to/file.va",
+//!         "byte_start": 10, "byte_end": 12,
+//!         "line_start": 1, "column_start": 11,
+//!         "line_end": 1, "column_end": 13,
+//!         "is_primary": true,
+//!         "label": "..."
+//!     }],
+//!     "expansion": [{ "kind": "macro" | "include", "span": { "file": ..., ... } }],
+//!     "notes": ["..."]
+//! }
+//! ```
+//!
+//! Lines and columns are 1-based and columns count unicode codepoints. All spans are already
+//! resolved to the file they were written in. `expansion` lists the macro calls and `` `include ``
+//! directives (innermost first) that caused the code at the primary span to be compiled.
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::ops::Range;
+
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use syntax::sourcemap::FileSpan;
+use syntax::{TextRange, TextSize};
+
+use crate::diagnostics::{expansion_backtrace, Diagnostic, DiagnosticSink, ExpansionSite, Report};
+use crate::{BaseDB, FileId};
+
+pub struct JsonSink<'a> {
+    error_cnt: usize,
+    db: &'a dyn BaseDB,
+    dst: Box<dyn Write + 'a>,
+}
+
+impl<'a> JsonSink<'a> {
+    pub fn new(db: &'a dyn BaseDB) -> JsonSink<'a> {
+        JsonSink::new_with(db, Box::new(io::stdout()))
+    }
+
+    pub fn new_with(db: &'a dyn BaseDB, dst: Box<dyn Write + 'a>) -> JsonSink<'a> {
+        JsonSink { error_cnt: 0, db, dst }
+    }
+
+    fn emit(&mut self, report: &Report, lint: Option<&str>, expansion: &[ExpansionSite]) {
+        if report.severity >= Severity::Error {
+            self.error_cnt += 1;
+        }
+
+        let mut dst = String::with_capacity(256);
+        dst.push_str("{\"severity\":");
+        write_str(&mut dst, severity_name(report.severity));
+        dst.push_str(",\"code\":");
+        write_opt_str(&mut dst, report.code.as_deref());
+        dst.push_str(",\"lint\":");
+        write_opt_str(&mut dst, lint);
+        dst.push_str(",\"message\":");
+        write_str(&mut dst, &report.message);
+
+        dst.push_str(",\"spans\":[");
+        for (i, label) in report.labels.iter().enumerate() {
+            if i != 0 {
+                dst.push(',');
+            }
+            dst.push('{');
+            self.write_span(&mut dst, label.file_id, label.range.clone());
+            let _ = write!(dst, ",\"is_primary\":{}", label.style == LabelStyle::Primary);
+            dst.push_str(",\"label\":");
+            write_str(&mut dst, &label.message);
+            dst.push('}');
+        }
+
+        dst.push_str("],\"expansion\":[");
+        for (i, site) in expansion.iter().enumerate() {
+            if i != 0 {
+                dst.push(',');
+            }
+            dst.push_str("{\"kind\":");
+            write_str(&mut dst, if site.is_include { "include" } else { "macro" });
+            dst.push_str(",\"span\":{");
+            let FileSpan { range, file } = site.span;
+            self.write_span(&mut dst, file, range.into());
+            dst.push_str("}}");
+        }
+
+        dst.push_str("],\"notes\":[");
+        for (i, note) in report.notes.iter().enumerate() {
+            if i != 0 {
+                dst.push(',');
+            }
+            write_str(&mut dst, note);
+        }
+        dst.push_str("]}");
+
+        writeln!(self.dst, "{}", dst).expect("failed to write diagnostic");
+    }
+
+    fn write_span(&self, dst: &mut String, file: FileId, range: Range<usize>) {
+        let (line_start, column_start) = line_col(self.db, file, range.start);
+        let (line_end, column_end) = line_col(self.db, file, range.end);
+        dst.push_str("\"file\":");
+        write_str(dst, &self.db.file_path(file).to_string());
+        let _ = write!(
+            dst,
+            ",\"byte_start\":{},\"byte_end\":{},\"line_start\":{line_start},\"column_start\":{column_start},\"line_end\":{line_end},\"column_end\":{column_end}",
+            range.start, range.end
+        );
+    }
+}
+
+impl DiagnosticSink for JsonSink<'_> {
+    fn add_report(&mut self, report: Report) {
+        self.emit(&report, None, &[])
+    }
+
+    fn add_diagnostic(&mut self, diagnostic: &dyn Diagnostic, root_file: FileId, db: &dyn BaseDB) {
+        if let Some(report) = diagnostic.to_report(root_file, db) {
+            let lint = diagnostic.lint(root_file, db).map(|(lint, _)| db.lint_data(lint).name);
+            let expansion = diagnostic.primary_span(root_file, db).map_or_else(Vec::new, |span| {
+                expansion_backtrace(&report, span, &db.sourcemap(root_file), db)
+            });
+            self.emit(&report, lint, &expansion)
+        }
+    }
+
+    fn summary(&mut self, _target_name: &dyn std::fmt::Display) -> bool {
+        self.error_cnt != 0
+    }
+}
+
+pub(super) fn severity_name(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Bug => "bug",
+        Severity::Error => "error",
+        Severity::Warning => "warning",
+        Severity::Note => "note",
+        Severity::Help => "help",
+    }
+}
+
+/// Returns the 1-based line and column (counted in unicode codepoints) of `offset`.
+pub(super) fn line_col(db: &dyn BaseDB, file: FileId, offset: usize) -> (usize, usize) {
+    let offset = TextSize::from(offset as u32);
+    let line = db.line(offset, file);
+    let line_start = db.line_range(line, file).start().min(offset);
+    let range = TextRange::new(line_start, offset);
+    let col = db
+        .file_text(file)
+        .ok()
+        .and_then(|text| text.get(Range::<usize>::from(range)).map(|src| src.chars().count()))
+        .unwrap_or_else(|| range.len().into());
+    (usize::from(line) + 1, col + 1)
+}
+
+pub(super) fn write_opt_str(dst: &mut String, val: Option<&str>) {
+    match val {
+        Some(val) => write_str(dst, val),
+        None => dst.push_str("null"),
+    }
+}
+
+/// Writes `val` as a quoted and escaped JSON string.
+pub fn write_str(dst: &mut String, val: &str) {
+    dst.push('"');
+    for c in val.chars() {
+        match c {
+            '"' => dst.push_str("\\\""),
+            '\\' => dst.push_str("\\\\"),
+            '\n' => dst.push_str("\\n"),
+            '\r' => dst.push_str("\\r"),
+            '\t' => dst.push_str("\\t"),
+            c if (c as u32) < 0x20 => {
+                let _ = write!(dst, "\\u{:04x}", c as u32);
+            }
+            c => dst.push(c),
+        }
+    }
+    dst.push('"');
+}
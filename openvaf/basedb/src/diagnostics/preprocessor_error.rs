@@ -2,6 +2,7 @@ use std::fmt::Write;
 
 use ahash::AHashSet;
 use stdx::iter::zip;
+use syntax::sourcemap::CtxSpan;
 use syntax::PreprocessorDiagnostic;
 use vfs::FileId;
 
@@ -274,4 +275,20 @@ impl Diagnostic for PreprocessorDiagnostic {
 
         report.with_message(self.to_string())
     }
+
+    fn primary_span(&self, _root_file: FileId, _db: &dyn BaseDB) -> Option<CtxSpan> {
+        match *self {
+            PreprocessorDiagnostic::MacroArgumentCountMismatch { span, .. }
+            | PreprocessorDiagnostic::MacroNotFound { span, .. }
+            | PreprocessorDiagnostic::MacroNotDefined { span, .. }
+            | PreprocessorDiagnostic::MacroRecursion { span, .. }
+            | PreprocessorDiagnostic::UnsupportedCompDir { span, .. }
+            | PreprocessorDiagnostic::UnexpectedEof { span, .. }
+            | PreprocessorDiagnostic::MissingOrUnexpectedToken { span, .. }
+            | PreprocessorDiagnostic::UnexpectedToken(span)
+            | PreprocessorDiagnostic::MacroOverwritten { new: span, .. } => Some(span),
+            PreprocessorDiagnostic::FileNotFound { span, .. }
+            | PreprocessorDiagnostic::InvalidTextFormat { span, .. } => span,
+        }
+    }
 }
@@ -0,0 +1,205 @@
+//! A [`DiagnosticSink`] that produces a [SARIF 2.1.0] log, the format consumed by code scanning
+//! tools. The log is only written by [`SarifSink::finish`], which must be called once all
+//! diagnostics have been added.
+//!
+//! Each diagnostic becomes a `result` whose `ruleId` is the name of the lint that produced it
+//! (or the severity for hard errors). Secondary labels are reported as `relatedLocations` and
+//! the macro expansion backtrace of the primary location as a `stack`.
+//!
+//! [SARIF 2.1.0]: https://docs.oasis-open.org/sarif/sarif/v2.1.0/sarif-v2.1.0.html
+
+use std::fmt::Write as _;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::Path;
+
+use ahash::AHashSet;
+use codespan_reporting::diagnostic::{LabelStyle, Severity};
+use vfs::VfsPath;
+
+use crate::diagnostics::json::{line_col, severity_name, write_str};
+use crate::diagnostics::{expansion_backtrace, Diagnostic, DiagnosticSink, ExpansionSite, Report};
+use crate::{BaseDB, FileId};
+
+pub struct SarifSink<'a> {
+    error_cnt: usize,
+    db: &'a dyn BaseDB,
+    dst: Box<dyn Write + 'a>,
+    rules: Vec<&'static str>,
+    known_rules: AHashSet<&'static str>,
+    results: Vec<String>,
+}
+
+impl<'a> SarifSink<'a> {
+    pub fn new(db: &'a dyn BaseDB) -> SarifSink<'a> {
+        SarifSink::new_with(db, Box::new(io::stdout()))
+    }
+
+    pub fn new_with(db: &'a dyn BaseDB, dst: Box<dyn Write + 'a>) -> SarifSink<'a> {
+        SarifSink {
+            error_cnt: 0,
+            db,
+            dst,
+            rules: Vec::new(),
+            known_rules: AHashSet::default(),
+            results: Vec::new(),
+        }
+    }
+
+    fn add_result(
+        &mut self,
+        report: &Report,
+        lint: Option<&'static str>,
+        expansion: &[ExpansionSite],
+    ) {
+        let level = match report.severity {
+            Severity::Error | Severity::Bug => {
+                self.error_cnt += 1;
+                "error"
+            }
+            Severity::Warning => "warning",
+            Severity::Note | Severity::Help => "note",
+        };
+
+        let rule = lint.unwrap_or_else(|| severity_name(report.severity));
+        if self.known_rules.insert(rule) {
+            self.rules.push(rule)
+        }
+
+        let mut dst = String::with_capacity(512);
+        dst.push_str("{\"ruleId\":");
+        write_str(&mut dst, rule);
+        let _ = write!(dst, ",\"level\":\"{level}\",\"message\":{{\"text\":");
+        let mut message = report.message.clone();
+        for note in &report.notes {
+            message.push('\n');
+            message.push_str(note);
+        }
+        write_str(&mut dst, &message);
+        dst.push('}');
+
+        let (primary, secondary): (Vec<_>, Vec<_>) =
+            report.labels.iter().partition(|label| label.style == LabelStyle::Primary);
+
+        dst.push_str(",\"locations\":[");
+        for (i, label) in primary.iter().enumerate() {
+            if i != 0 {
+                dst.push(',');
+            }
+            self.write_location(&mut dst, label.file_id, label.range.clone(), &label.message);
+        }
+
+        dst.push_str("],\"relatedLocations\":[");
+        for (i, label) in secondary.iter().enumerate() {
+            if i != 0 {
+                dst.push(',');
+            }
+            self.write_location(&mut dst, label.file_id, label.range.clone(), &label.message);
+        }
+        dst.push(']');
+
+        if !expansion.is_empty() {
+            dst.push_str(
+                ",\"stacks\":[{\"message\":{\"text\":\"macro expansion backtrace\"},\"frames\":[",
+            );
+            for (i, site) in expansion.iter().enumerate() {
+                if i != 0 {
+                    dst.push(',');
+                }
+                let message =
+                    if site.is_include { "included from here" } else { "in this macro call" };
+                dst.push_str("{\"location\":");
+                self.write_location(&mut dst, site.span.file, site.span.range.into(), message);
+                dst.push('}');
+            }
+            dst.push_str("]}]");
+        }
+
+        dst.push('}');
+        self.results.push(dst)
+    }
+
+    fn write_location(&self, dst: &mut String, file: FileId, range: Range<usize>, message: &str) {
+        let (start_line, start_column) = line_col(self.db, file, range.start);
+        let (end_line, end_column) = line_col(self.db, file, range.end);
+        dst.push_str("{\"physicalLocation\":{\"artifactLocation\":{\"uri\":");
+        write_str(dst, &file_uri(&self.db.file_path(file)));
+        let _ = write!(
+            dst,
+            "}},\"region\":{{\"startLine\":{start_line},\"startColumn\":{start_column},\"endLine\":{end_line},\"endColumn\":{end_column},\"byteOffset\":{},\"byteLength\":{}}}}}",
+            range.start,
+            range.len()
+        );
+        if !message.is_empty() {
+            dst.push_str(",\"message\":{\"text\":");
+            write_str(dst, message);
+            dst.push('}');
+        }
+        dst.push('}');
+    }
+
+    /// Writes the SARIF log containing all diagnostics added to this sink.
+    pub fn finish(mut self) -> io::Result<()> {
+        let mut log = String::with_capacity(1024);
+        log.push_str("{\"$schema\":\"https://json.schemastore.org/sarif-2.1.0.json\",\"version\":\"2.1.0\",\"runs\":[{\"tool\":{\"driver\":{\"name\":\"OpenVAF\",\"informationUri\":\"https://openvaf.semimod.de\",\"rules\":[");
+        for (i, rule) in self.rules.iter().enumerate() {
+            if i != 0 {
+                log.push(',');
+            }
+            log.push_str("{\"id\":");
+            write_str(&mut log, rule);
+            log.push('}');
+        }
+        log.push_str("]}},\"columnKind\":\"unicodeCodePoints\",\"results\":[");
+        log.push_str(&self.results.join(","));
+        log.push_str("]}]}");
+        writeln!(self.dst, "{}", log)?;
+        self.dst.flush()
+    }
+}
+
+impl DiagnosticSink for SarifSink<'_> {
+    fn add_report(&mut self, report: Report) {
+        self.add_result(&report, None, &[])
+    }
+
+    fn add_diagnostic(&mut self, diagnostic: &dyn Diagnostic, root_file: FileId, db: &dyn BaseDB) {
+        if let Some(report) = diagnostic.to_report(root_file, db) {
+            let lint = diagnostic.lint(root_file, db).map(|(lint, _)| db.lint_data(lint).name);
+            let expansion = diagnostic.primary_span(root_file, db).map_or_else(Vec::new, |span| {
+                expansion_backtrace(&report, span, &db.sourcemap(root_file), db)
+            });
+            self.add_result(&report, lint, &expansion)
+        }
+    }
+
+    fn summary(&mut self, _target_name: &dyn std::fmt::Display) -> bool {
+        self.error_cnt != 0
+    }
+}
+
+/// The URI of a file on disk. Virtual files (that only exist in memory) are returned as is.
+fn file_uri(path: &VfsPath) -> String {
+    let path = match path.as_path() {
+        Some(path) => AsRef::<Path>::as_ref(path).to_string_lossy().into_owned(),
+        None => return path.to_string(),
+    };
+
+    let mut uri = String::from("file://");
+    if !path.starts_with('/') {
+        // windows paths (C:\foo) need an additional slash
+        uri.push('/');
+    }
+    for byte in path.bytes() {
+        match byte {
+            b'\\' => uri.push('/'),
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            byte => {
+                let _ = write!(uri, "%{byte:02X}");
+            }
+        }
+    }
+    uri
+}
@@ -1,4 +1,6 @@
 use std::fmt::Display;
+use std::io;
+use std::str::FromStr;
 use std::sync::Arc;
 
 use codespan_reporting::diagnostic::Severity;
@@ -8,7 +10,7 @@ use codespan_reporting::term::termcolor::{StandardStream, WriteColor};
 use codespan_reporting::term::{emit, Chars, Config};
 use vfs::VfsPath;
 
-use crate::diagnostics::{Diagnostic, Report};
+use crate::diagnostics::{Diagnostic, JsonSink, Report, SarifSink};
 use crate::{BaseDB, FileId};
 
 pub trait DiagnosticSink {
@@ -28,6 +30,10 @@ pub trait DiagnosticSink {
             .into_iter()
             .for_each(|diagnostic| self.add_diagnostic(diagnostic, root_file, db))
     }
+
+    /// Reports how many errors/warnings were emitted while compiling `target_name`.
+    /// Returns `true` if any errors were emitted.
+    fn summary(&mut self, target_name: &dyn Display) -> bool;
 }
 
 struct FileSrc<'a> {
@@ -97,31 +103,6 @@ impl<'a> ConsoleSink<'a> {
         ConsoleSink::new_with(db, Box::new(buffer))
     }
 
-    pub fn summary(&mut self, target_name: &impl Display) -> bool {
-        if self.error_cnt != 0 {
-            let warn = if self.warning_cnt != 0 {
-                format!("; {} warning emitted", self.warning_cnt)
-            } else {
-                String::new()
-            };
-            let message = format!(
-                "could not compile `{}` due to {} previous errors{}",
-                target_name, self.error_cnt, warn
-            );
-
-            self.print_simple_message(Severity::Error, message);
-            return true;
-        }
-
-        if self.warning_cnt != 0 {
-            let message = format!("`{}` generated {} warning", target_name, self.warning_cnt);
-            self.print_simple_message(Severity::Warning, message);
-            self.warning_cnt = 0;
-        }
-
-        false
-    }
-
     pub fn print_simple_message(&mut self, severity: Severity, msg: String) {
         emit(
             &mut self.dst,
@@ -189,6 +170,31 @@ impl DiagnosticSink for ConsoleSink<'_> {
         )
         .expect("Span emitting should never fail");
     }
+
+    fn summary(&mut self, target_name: &dyn Display) -> bool {
+        if self.error_cnt != 0 {
+            let warn = if self.warning_cnt != 0 {
+                format!("; {} warning emitted", self.warning_cnt)
+            } else {
+                String::new()
+            };
+            let message = format!(
+                "could not compile `{}` due to {} previous errors{}",
+                target_name, self.error_cnt, warn
+            );
+
+            self.print_simple_message(Severity::Error, message);
+            return true;
+        }
+
+        if self.warning_cnt != 0 {
+            let message = format!("`{}` generated {} warning", target_name, self.warning_cnt);
+            self.print_simple_message(Severity::Warning, message);
+            self.warning_cnt = 0;
+        }
+
+        false
+    }
 }
 
 pub fn print_all<'a>(
@@ -198,3 +204,79 @@ pub fn print_all<'a>(
 ) {
     ConsoleSink::new(db).add_diagnostics(diagnostics, root_file, db)
 }
+
+/// The format in which diagnostics are emitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MessageFormat {
+    /// human readable (and colored) messages printed to stderr
+    #[default]
+    Human,
+    /// one JSON object per diagnostic printed to stdout
+    Json,
+    /// a SARIF log printed to stdout
+    Sarif,
+}
+
+impl FromStr for MessageFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(MessageFormat::Human),
+            "json" => Ok(MessageFormat::Json),
+            "sarif" => Ok(MessageFormat::Sarif),
+            _ => Err(format!("unknown message format '{s}' (expected human, json or sarif)")),
+        }
+    }
+}
+
+/// A sink that emits diagnostics in the selected [`MessageFormat`].
+pub enum OutputSink<'a> {
+    Console(ConsoleSink<'a>),
+    Json(JsonSink<'a>),
+    Sarif(SarifSink<'a>),
+}
+
+impl<'a> OutputSink<'a> {
+    pub fn new(db: &'a dyn BaseDB, format: MessageFormat) -> OutputSink<'a> {
+        match format {
+            MessageFormat::Human => OutputSink::Console(ConsoleSink::new(db)),
+            MessageFormat::Json => OutputSink::Json(JsonSink::new(db)),
+            MessageFormat::Sarif => OutputSink::Sarif(SarifSink::new(db)),
+        }
+    }
+
+    /// Writes any output that is only emitted once all diagnostics are known (the SARIF log).
+    pub fn finish(self) -> io::Result<()> {
+        match self {
+            OutputSink::Console(_) | OutputSink::Json(_) => Ok(()),
+            OutputSink::Sarif(sink) => sink.finish(),
+        }
+    }
+}
+
+impl DiagnosticSink for OutputSink<'_> {
+    fn add_report(&mut self, report: Report) {
+        match self {
+            OutputSink::Console(sink) => sink.add_report(report),
+            OutputSink::Json(sink) => sink.add_report(report),
+            OutputSink::Sarif(sink) => sink.add_report(report),
+        }
+    }
+
+    fn add_diagnostic(&mut self, diagnostic: &dyn Diagnostic, root_file: FileId, db: &dyn BaseDB) {
+        match self {
+            OutputSink::Console(sink) => sink.add_diagnostic(diagnostic, root_file, db),
+            OutputSink::Json(sink) => sink.add_diagnostic(diagnostic, root_file, db),
+            OutputSink::Sarif(sink) => sink.add_diagnostic(diagnostic, root_file, db),
+        }
+    }
+
+    fn summary(&mut self, target_name: &dyn Display) -> bool {
+        match self {
+            OutputSink::Console(sink) => sink.summary(target_name),
+            OutputSink::Json(sink) => sink.summary(target_name),
+            OutputSink::Sarif(sink) => sink.summary(target_name),
+        }
+    }
+}
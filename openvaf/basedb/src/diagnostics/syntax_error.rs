@@ -1,7 +1,7 @@
 use std::iter::once;
 
 use stdx::iter::zip;
-use syntax::sourcemap::FileSpan;
+use syntax::sourcemap::{CtxSpan, FileSpan};
 use syntax::SyntaxKind::BLOCK_STMT;
 use syntax::{AstNode, SyntaxError, TextRange, TextSize};
 
@@ -567,8 +567,69 @@ impl Diagnostic for SyntaxError {
 
                 Report::error().with_labels(labels)
             }
+            SyntaxError::IllegalArrayBound { range } => {
+                let FileSpan { range, file: file_id } = parse.to_file_span(range, &sm);
+                Report::error().with_labels(vec![Label {
+                    style: LabelStyle::Primary,
+                    file_id,
+                    range: range.into(),
+                    message: "expected an integer literal".to_owned(),
+                }])
+            }
+            SyntaxError::ArrayVarWithInitializer { range, .. } => {
+                let FileSpan { range, file: file_id } = parse.to_file_span(range, &sm);
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id,
+                        range: range.into(),
+                        message: "illegal initializer".to_owned(),
+                    }])
+                    .with_notes(vec!["help: array elements are initialized to zero".to_owned()])
+            }
         };
 
         report.with_message(self.to_string())
     }
+
+    fn primary_span(&self, root_file: FileId, db: &dyn BaseDB) -> Option<CtxSpan> {
+        let parse = db.parse(root_file);
+        let range = match *self {
+            SyntaxError::UnexpectedToken { span, .. }
+            | SyntaxError::SurplusToken { span, .. }
+            | SyntaxError::MissingToken { span, .. } => span,
+            SyntaxError::IllegalRootSegment { path_segment, .. } => path_segment,
+            SyntaxError::BlockItemsAfterStmt { ref items, .. }
+            | SyntaxError::BlockItemsWithoutScope { ref items, .. } => items.first()?.range(),
+            SyntaxError::FunItemsAfterBody { ref items, .. } => items.first()?.range(),
+            SyntaxError::MultipleFunBodys { ref additional_bodys, .. } => {
+                *additional_bodys.first()?
+            }
+            SyntaxError::FunWithoutBody { fun } => fun,
+            SyntaxError::IllegalBranchNodeCnt { arg_list, .. } => arg_list,
+            SyntaxError::IllegalBranchNodeExpr { ref illegal_nodes, .. } => {
+                *illegal_nodes.first()?
+            }
+            SyntaxError::ReservedIdentifier { src, .. } => src.range(),
+            SyntaxError::DuplicatePort { ref pos, .. } => *pos.get(1)?,
+            SyntaxError::PortNotDeclaredInModule { pos, .. } => pos,
+            SyntaxError::MixedModuleHead { ref module_ports } => module_ports
+                .to_node(parse.tree().syntax())
+                .declarations()
+                .next()?
+                .syntax()
+                .text_range(),
+            SyntaxError::IllegalBodyPorts { ref body_ports, .. } => *body_ports.first()?,
+            SyntaxError::IllegalInfToken { range }
+            | SyntaxError::UnitsExpectedStringLiteral { range }
+            | SyntaxError::IllegalDisciplineAttrIdent { range }
+            | SyntaxError::IllegalNatureIdent { range }
+            | SyntaxError::IllegalAttriubte { range, .. }
+            | SyntaxError::IllegalNetType { range, .. }
+            | SyntaxError::RangeConstraintForNonNumericParameter { range, .. }
+            | SyntaxError::IllegalArrayBound { range }
+            | SyntaxError::ArrayVarWithInitializer { range, .. } => range,
+        };
+        Some(parse.to_ctx_span(range, &db.sourcemap(root_file)))
+    }
 }
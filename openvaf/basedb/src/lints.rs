@@ -179,5 +179,7 @@ pub mod builtin {
         pub const variant_const_simparam = LintData{default_lvl: Warn, documentation_id: 15};
         pub const port_without_direction = LintData{default_lvl: Deny, documentation_id: 16};
         pub const trivial_probe = LintData{default_lvl: Warn, documentation_id: 17};
+        pub const approximated_absdelay = LintData{default_lvl: Warn, documentation_id: 18};
+        pub const analog_filter_fallback = LintData{default_lvl: Warn, documentation_id: 19};
     }
 }
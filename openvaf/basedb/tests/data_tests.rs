@@ -1,4 +1,4 @@
-use basedb::diagnostics::{ConsoleSink, DiagnosticSink};
+use basedb::diagnostics::{ConsoleSink, DiagnosticSink, JsonSink, SarifSink};
 use basedb::{BaseDB, BaseDatabase, VfsPath, VfsStorage};
 use codespan_reporting::term::termcolor::Buffer;
 use expect_test::expect_file;
@@ -98,7 +98,61 @@ fn ast_test(file: &Path) -> Result {
     Ok(())
 }
 
+const MESSAGE_FORMAT_SRC: &str = r#"`define FOO 1
+`define FOO 2
+`define CALL `UNDEFINED
+module test;
+`CALL
+endmodule
+"#;
+
+fn message_format_test() -> Result {
+    let db = TestDataBase::new(
+        VfsPath::new_virtual_path("/root.va".to_owned()),
+        MESSAGE_FORMAT_SRC.to_owned().into(),
+    );
+    let root_file = db.root_file();
+    let diagnostics = db.preprocess(root_file).diagnostics;
+
+    let mut json = Vec::new();
+    {
+        let mut sink = JsonSink::new_with(&db, Box::new(&mut json));
+        sink.add_diagnostics(&*diagnostics, root_file, &db);
+        assert!(sink.summary(&"root.va"));
+    }
+    let json = String::from_utf8(json).unwrap();
+    let lines: Vec<_> = json.lines().collect();
+    assert_eq!(lines.len(), 2, "{json}");
+    assert!(lines.iter().any(|line| line.contains(r#""lint":"macro_overwritten""#)), "{json}");
+    let not_found = lines.iter().find(|line| line.contains(r#""severity":"error""#)).unwrap();
+    assert!(not_found.contains(r#""line_start":3"#), "{not_found}");
+    assert!(
+        not_found.contains(r#""expansion":[{"kind":"macro","span":{"file":"/root.va""#),
+        "{not_found}"
+    );
+    assert!(not_found.contains(r#""line_start":5,"column_start":1"#), "{not_found}");
+
+    let mut sarif = Vec::new();
+    {
+        let mut sink = SarifSink::new_with(&db, Box::new(&mut sarif));
+        sink.add_diagnostics(&*diagnostics, root_file, &db);
+        sink.finish()?;
+    }
+    let sarif = String::from_utf8(sarif).unwrap();
+    assert!(sarif.contains(r#""version":"2.1.0""#), "{sarif}");
+    assert!(sarif.contains(r#""ruleId":"macro_overwritten","level":"warning""#), "{sarif}");
+    assert!(
+        sarif.contains(
+            r#""frames":[{"location":{"physicalLocation":{"artifactLocation":{"uri":"/root.va"}"#
+        ),
+        "{sarif}"
+    );
+
+    Ok(())
+}
+
 harness! {
+    Test::new("message_format", &message_format_test),
     Test::from_dir_filtered("integration", &integration_test, &Path::is_dir, &ignore_dev_tests, &project_root().join("integration_tests")),
     Test::from_dir_filtered("ui", &ui_test, &is_va_file, &ignore_never, &openvaf_test_data("syn_ui")),
     Test::from_dir_filtered("ast", &ast_test, &is_va_file, &ignore_never, &openvaf_test_data("ast"))
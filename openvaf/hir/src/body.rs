@@ -1,40 +1,94 @@
 use std::sync::Arc;
 
 use hir_def::db::HirDefDB;
-use hir_def::DefWithBodyId;
+use hir_def::{DefWithBodyId, InstancePathId, NodeId};
 use hir_ty::db::HirTyDB;
 use hir_ty::inference;
 use hir_ty::types::{Signature, Ty};
+use stdx::Ieee64;
 
-pub use hir_def::expr::Event;
-pub use hir_def::{/*expr::CaseCond,*/ BuiltIn, Case, ExprId, Literal, ParamSysFun, StmtId, Type};
+pub use hir_def::expr::{Event, GlobalEvent};
+pub use hir_def::{BuiltIn, Case, ExprId, Literal, ParamSysFun, StmtId, Type};
 pub use syntax::ast::{BinaryOp, UnaryOp};
 
-use crate::{Branch, CompilationDB, Node};
+use crate::{Branch, CompilationDB, ModuleInstance, Node};
 use crate::{BranchWrite, Function, FunctionArg, NatureAttribute, Parameter, Variable};
 
 #[derive(Debug, Clone)]
 pub struct Body {
+    id: DefWithBodyId,
     body: Arc<hir_def::body::Body>,
     infere: Arc<inference::InferenceResult>,
+    inst: Option<InstancePathId>,
+    /// The nodes the ports of `inst` are connected to
+    ports: Arc<[(NodeId, Node)]>,
 }
 impl Body {
     pub(crate) fn new(id: DefWithBodyId, db: &CompilationDB) -> Body {
-        Body { body: db.body(id), infere: db.inference_result(id) }
+        Body::new_in(id, None, db)
+    }
+
+    /// Creates a body whose declarations refer to the module instance `inst`
+    pub(crate) fn new_in(
+        id: DefWithBodyId,
+        inst: Option<InstancePathId>,
+        db: &CompilationDB,
+    ) -> Body {
+        let ports: Arc<[_]> = match inst {
+            Some(path) => ModuleInstance { path }
+                .module(db)
+                .ports(db)
+                .into_iter()
+                .map(|port| (port.id, Node::new(db, port.id, inst)))
+                .collect(),
+            None => Arc::new([]),
+        };
+        Body { id, body: db.body(id), infere: db.inference_result(id), inst, ports }
     }
 
     pub fn borrow(&self) -> BodyRef<'_> {
-        BodyRef { body: &self.body, infere: &self.infere }
+        BodyRef {
+            id: self.id,
+            body: &self.body,
+            infere: &self.infere,
+            inst: self.inst,
+            ports: &self.ports,
+        }
     }
 }
 
 #[derive(Debug, Clone, Copy)]
 pub struct BodyRef<'a> {
+    id: DefWithBodyId,
     body: &'a hir_def::body::Body,
     infere: &'a inference::InferenceResult,
+    inst: Option<InstancePathId>,
+    ports: &'a [(NodeId, Node)],
 }
 
 impl<'a> BodyRef<'a> {
+    /// The module instance this body is lowered for
+    /// (`None` for the top-level module).
+    pub fn instance(&self) -> Option<ModuleInstance> {
+        self.inst.map(|path| ModuleInstance { path })
+    }
+
+    fn node(&self, id: NodeId) -> Node {
+        match self.ports.iter().find(|(port, _)| *port == id) {
+            Some(&(_, node)) => node,
+            None => Node { id, inst: self.inst },
+        }
+    }
+
+    fn branch_write(&self, branch: inference::BranchWrite) -> BranchWrite {
+        match branch {
+            inference::BranchWrite::Named(id) => BranchWrite::Named(Branch { id, inst: self.inst }),
+            inference::BranchWrite::Unnamed { hi, lo } => {
+                BranchWrite::Unnamed { hi: self.node(hi), lo: lo.map(|lo| self.node(lo)) }
+            }
+        }
+    }
+
     pub fn entry(&self) -> &'a [StmtId] {
         &self.body.entry_stmts
     }
@@ -55,8 +109,8 @@ impl<'a> BodyRef<'a> {
 
     fn resolve_path(&self, expr: ExprId) -> Ref {
         match self.infere.expr_types[expr] {
-            Ty::Var(_, id) => Ref::Variable(Variable { id }),
-            Ty::Param(_, id) => Ref::Parameter(Parameter { id }),
+            Ty::Var(_, id) => Ref::Variable(Variable { id, inst: self.inst }),
+            Ty::Param(_, id) => Ref::Parameter(Parameter { id, inst: self.inst }),
             Ty::FunctionVar { fun, arg: Some(arg), .. } => {
                 Ref::FunctionArg(FunctionArg { fun_id: fun, arg_id: arg })
             }
@@ -74,6 +128,11 @@ impl<'a> BodyRef<'a> {
         }
     }
 
+    /// Returns the (frequency, power) pairs of the `noise_table`/`noise_table_log` call `expr`
+    pub fn noise_table(&self, db: &CompilationDB, expr: ExprId) -> Arc<[(Ieee64, Ieee64)]> {
+        db.noise_table(self.id, expr).expect("invalid noise tables are reported during validation")
+    }
+
     pub fn get_call_signature(&self, expr: ExprId) -> Signature {
         self.infere.resolved_signatures.get(&expr).copied().unwrap_or(Signature(u32::MAX))
     }
@@ -116,24 +175,63 @@ impl<'a> BodyRef<'a> {
         }
     }
 
+    /// Evaluates a numeric expression that only consists of literals and basic
+    /// arithmetic (`+`, `-`, `*`, `/`). Integers are widened to reals and `inf`
+    /// evaluates to infinity. Returns `None` for anything that can only be
+    /// evaluated at runtime (parameter references, function calls, ...).
+    pub fn const_eval_real(&self, expr: ExprId) -> Option<f64> {
+        let val = match &self.body.exprs[expr] {
+            hir_def::Expr::Literal(Literal::Int(val)) => *val as f64,
+            hir_def::Expr::Literal(Literal::Float(val)) => f64::from(*val),
+            hir_def::Expr::Literal(Literal::Inf) => f64::INFINITY,
+            hir_def::Expr::UnaryOp { expr, op: UnaryOp::Neg } => -self.const_eval_real(*expr)?,
+            hir_def::Expr::UnaryOp { expr, op: UnaryOp::Identity } => {
+                self.const_eval_real(*expr)?
+            }
+            hir_def::Expr::BinaryOp { lhs, rhs, op: Some(op) } => {
+                let lhs = self.const_eval_real(*lhs)?;
+                let rhs = self.const_eval_real(*rhs)?;
+                match op {
+                    BinaryOp::Addition => lhs + rhs,
+                    BinaryOp::Subtraction => lhs - rhs,
+                    BinaryOp::Multiplication => lhs * rhs,
+                    BinaryOp::Division if self.expr_type(expr) == Type::Integer => {
+                        if rhs == 0.0 {
+                            return None;
+                        }
+                        (lhs / rhs).trunc()
+                    }
+                    BinaryOp::Division => lhs / rhs,
+                    _ => return None,
+                }
+            }
+            _ => return None,
+        };
+        match self.needs_cast(expr) {
+            // reals are rounded when they are converted to integers
+            Some((_, Type::Integer)) => Some(val.round()),
+            _ => Some(val),
+        }
+    }
+
     pub fn into_node(&self, expr: ExprId) -> Node {
         let id = self.infere.expr_types[expr].unwrap_node();
-        Node { id }
+        self.node(id)
     }
 
     pub fn into_port_flow(&self, expr: ExprId) -> Node {
         let id = self.infere.expr_types[expr].unwrap_port_flow();
-        Node { id }
+        self.node(id)
     }
 
     pub fn into_parameter(&self, expr: ExprId) -> Parameter {
         let id = self.infere.expr_types[expr].unwrap_param();
-        Parameter { id }
+        Parameter { id, inst: self.inst }
     }
 
     pub fn into_branch(&self, expr: ExprId) -> Branch {
         let id = self.infere.expr_types[expr].unwrap_branch();
-        Branch { id }
+        Branch { id, inst: self.inst }
     }
 
     pub fn get_expr(&self, expr: ExprId) -> Expr<'a> {
@@ -162,6 +260,10 @@ impl<'a> BodyRef<'a> {
                 };
                 Expr::Call { fun, args }
             }
+            hir_def::Expr::Index { base, index } => {
+                let var = self.infere.expr_types[base].unwrap_var();
+                Expr::Index { var: Variable { id: var, inst: self.inst }, index }
+            }
             hir_def::Expr::Array(ref args) => Expr::Array(args),
             hir_def::Expr::Literal(ref literal) => Expr::Literal(literal),
             _ => panic!("invalid HIR: {:?}", self.body.exprs[expr]),
@@ -180,14 +282,22 @@ impl<'a> BodyRef<'a> {
         match self.body.stmts[stmnt] {
             hir_def::Stmt::Empty | hir_def::Stmt::Missing => None,
             hir_def::Stmt::Expr(e) => Some(Stmt::Expr(e)),
-            hir_def::Stmt::EventControl { ref event, body } => {
-                Some(Stmt::EventControl { event, body })
+            hir_def::Stmt::EventControl { ref events, body } => {
+                Some(Stmt::EventControl { events, body })
             }
             hir_def::Stmt::Assignment { val, .. } => {
                 let stmt = match self.infere.assignment_destination[&stmnt] {
-                    inference::AssignDst::Var(id) => {
-                        Stmt::Assignment { lhs: AssignmentLhs::Variable(Variable { id }), rhs: val }
-                    }
+                    inference::AssignDst::Var(id) => Stmt::Assignment {
+                        lhs: AssignmentLhs::Variable(Variable { id, inst: self.inst }),
+                        rhs: val,
+                    },
+                    inference::AssignDst::ArrayElement { var, index } => Stmt::Assignment {
+                        lhs: AssignmentLhs::ArrayElement {
+                            var: Variable { id: var, inst: self.inst },
+                            index,
+                        },
+                        rhs: val,
+                    },
                     inference::AssignDst::FunVar { fun, arg: None } => Stmt::Assignment {
                         lhs: AssignmentLhs::FunctionReturn(Function { id: fun }),
                         rhs: val,
@@ -198,12 +308,12 @@ impl<'a> BodyRef<'a> {
                     },
                     inference::AssignDst::Flow(branch) => Stmt::Contribute {
                         kind: ContributeKind::Flow,
-                        branch: branch.into(),
+                        branch: self.branch_write(branch),
                         rhs: val,
                     },
                     inference::AssignDst::Potential(branch) => Stmt::Contribute {
                         kind: ContributeKind::Potential,
-                        branch: branch.into(),
+                        branch: self.branch_write(branch),
                         rhs: val,
                     },
                 };
@@ -225,6 +335,7 @@ impl<'a> BodyRef<'a> {
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum AssignmentLhs {
     Variable(Variable),
+    ArrayElement { var: Variable, index: ExprId },
     FunctionReturn(Function),
     FunctionArg(FunctionArg),
 }
@@ -238,7 +349,7 @@ pub enum ContributeKind {
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub enum Stmt<'a> {
     Expr(ExprId),
-    EventControl { event: &'a Event, body: StmtId },
+    EventControl { events: &'a [Event], body: StmtId },
     Contribute { kind: ContributeKind, branch: BranchWrite, rhs: ExprId },
     Assignment { lhs: AssignmentLhs, rhs: ExprId },
     Block { body: &'a [StmtId] },
@@ -265,6 +376,7 @@ pub enum Expr<'a> {
     UnaryOp { expr: ExprId, op: UnaryOp },
     Select { cond: ExprId, then_val: ExprId, else_val: ExprId },
     Call { fun: ResolvedFun, args: &'a [ExprId] },
+    Index { var: Variable, index: ExprId },
     Array(&'a [ExprId]),
     Literal(&'a Literal),
 }
@@ -280,6 +392,7 @@ impl Expr<'_> {
     pub fn as_assignment_lhs(&self) -> AssignmentLhs {
         match *self {
             Expr::Read(Ref::Variable(var)) => AssignmentLhs::Variable(var),
+            Expr::Index { var, index } => AssignmentLhs::ArrayElement { var, index },
             Expr::Read(Ref::FunctionArg(arg)) => AssignmentLhs::FunctionArg(arg),
             Expr::Read(Ref::FunctionReturn(fun)) => AssignmentLhs::FunctionReturn(fun),
             _ => panic!("{self:?} is not a lhs reference"),
@@ -23,8 +23,8 @@ use hir_def::LocalFunctionArgId;
 use hir_def::NatureAttrId;
 use hir_def::NatureId;
 use hir_def::{
-    AliasParamId, BlockId, BlockLoc, BranchId, FunctionId, Lookup, ModuleId, ModuleLoc, NodeId,
-    ParamId, VarId,
+    AliasParamId, BlockId, BlockLoc, BranchId, FunctionId, InstancePathId, InstancePathLoc, Intern,
+    Lookup, ModuleId, ModuleLoc, NodeId, ParamId, VarId,
 };
 use hir_ty::db::HirTyDB as HirDatabase;
 use hir_ty::inference;
@@ -36,14 +36,16 @@ pub use basedb::diagnostics::DiagnosticSink;
 pub use hir_def::body::{ConstraintValue, ParamConstraint};
 pub use hir_def::expr::CaseCond;
 pub use hir_def::nameres::diagnostics::PathResolveError;
-pub use hir_def::{BuiltIn, Case, Literal, ParamSysFun, Path, Type};
+pub use hir_def::{ArrayBounds, BuiltIn, Case, Literal, ParamSysFun, Path, Type};
 pub use hir_ty::builtin;
 pub use rec_declarations::RecDeclarations;
+pub use syntax::ast::ConstraintKind;
 pub use syntax::name::Name;
 
 pub use crate::attributes::AstCache;
 pub use crate::body::{
-    AssignmentLhs, Body, BodyRef, ContributeKind, Expr, ExprId, Ref, ResolvedFun, Stmt, StmtId,
+    AssignmentLhs, Body, BodyRef, ContributeKind, Event, Expr, ExprId, GlobalEvent, Ref,
+    ResolvedFun, Stmt, StmtId,
 };
 pub use crate::db::CompilationDB;
 
@@ -55,11 +57,12 @@ mod rec_declarations;
 
 pub mod signatures {
     pub use hir_ty::builtin::{
-        ABSDELAY_MAX, ABS_INT, ABS_REAL, DDX_POT, IDTMOD_IC, IDTMOD_IC_MODULUS,
-        IDTMOD_IC_MODULUS_OFFSET, IDTMOD_IC_MODULUS_OFFSET_NATURE, IDTMOD_IC_MODULUS_OFFSET_TOL,
-        IDTMOD_NO_IC, IDT_IC, IDT_IC_ASSERT, IDT_IC_ASSERT_NATURE, IDT_IC_ASSERT_TOL, IDT_NO_IC,
-        LIMIT_BUILTIN_FUNCTION, MAX_INT, MAX_REAL, NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES,
-        NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW, SIMPARAM_DEFAULT, SIMPARAM_NO_DEFAULT,
+        ABSDELAY_MAX, ABS_INT, ABS_REAL, DDX_POT, FFLUSH_ALL, FOPEN_NO_MODE, IDTMOD_IC,
+        IDTMOD_IC_MODULUS, IDTMOD_IC_MODULUS_OFFSET, IDTMOD_IC_MODULUS_OFFSET_NATURE,
+        IDTMOD_IC_MODULUS_OFFSET_TOL, IDTMOD_NO_IC, IDT_IC, IDT_IC_ASSERT, IDT_IC_ASSERT_NATURE,
+        IDT_IC_ASSERT_TOL, IDT_NO_IC, LIMIT_BUILTIN_FUNCTION, MAX_INT, MAX_REAL,
+        NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES, NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW,
+        SIMPARAM_DEFAULT, SIMPARAM_NO_DEFAULT, SLEW_NO_MAX,
     };
     pub use hir_ty::types::{BOOL_EQ, INT_EQ, INT_OP, REAL_EQ, REAL_OP, STR_EQ};
 }
@@ -118,6 +121,12 @@ impl CompilationUnit {
     pub fn preprocess(&self, db: &CompilationDB) -> syntax::Preprocess {
         db.preprocess(self.root_file)
     }
+
+    /// Files other than sources that are read during compilation
+    /// (the tables of `noise_table` and `noise_table_log`).
+    pub fn data_files(self, db: &CompilationDB) -> Vec<FileId> {
+        hir_ty::noise_table::noise_table_files(db, self.root_file)
+    }
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
@@ -155,11 +164,16 @@ impl Module {
     }
 
     pub fn internal_nodes(self, db: &CompilationDB) -> Vec<Node> {
-        db.module_data(self.id).internal_nodes.iter().map(|&id| Node { id }).collect()
+        db.module_data(self.id).internal_nodes.iter().map(|&id| Node { id, inst: None }).collect()
     }
 
     pub fn ports(self, db: &CompilationDB) -> Vec<Node> {
-        db.module_data(self.id).ports.iter().map(|&id| Node { id }).collect()
+        db.module_data(self.id).ports.iter().map(|&id| Node { id, inst: None }).collect()
+    }
+
+    /// list of all (valid) module instances declared in this module.
+    pub fn instances(self, db: &CompilationDB) -> Vec<ModuleInstance> {
+        ModuleInstance::instances_in(db, self.id, None)
     }
 
     pub fn rec_declarations(self, db: &CompilationDB) -> RecDeclarations<'_> {
@@ -181,7 +195,129 @@ impl Module {
         path: &Path,
     ) -> Result<Variable, PathResolveError> {
         let scope = self.id.lookup(db).scope;
-        scope.resolve_item_path(db, path).map(|id| Variable { id })
+        scope.resolve_item_path(db, path).map(|id| Variable { id, inst: None })
+    }
+}
+
+/// A module instance after elaboration. Instances of the same module item that are nested
+/// within different parent instances are distinct.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModuleInstance {
+    path: InstancePathId,
+}
+
+stdx::impl_debug! {
+    match ModuleInstance{
+        ModuleInstance{ path } => "{path:?}";
+    }
+}
+
+impl ModuleInstance {
+    fn instances_in(
+        db: &CompilationDB,
+        module: ModuleId,
+        parent: Option<InstancePathId>,
+    ) -> Vec<ModuleInstance> {
+        db.module_data(module)
+            .instances
+            .iter()
+            .filter(|&&inst| db.module_inst_info(inst).is_some())
+            .map(|&inst| ModuleInstance { path: InstancePathLoc { parent, inst }.intern(db) })
+            .collect()
+    }
+
+    fn lookup(self, db: &CompilationDB) -> InstancePathLoc {
+        self.path.lookup(db)
+    }
+
+    /// The name of the instance within its parent module
+    pub fn name(self, db: &CompilationDB) -> String {
+        db.module_inst_data(self.lookup(db).inst).name.to_string()
+    }
+
+    /// The hierarchical name of this instance (for example `inst1.inst2`)
+    pub fn path(self, db: &CompilationDB) -> String {
+        match self.parent(db) {
+            Some(parent) => format!("{}.{}", parent.path(db), self.name(db)),
+            None => self.name(db),
+        }
+    }
+
+    pub fn parent(self, db: &CompilationDB) -> Option<ModuleInstance> {
+        self.lookup(db).parent.map(|path| ModuleInstance { path })
+    }
+
+    /// The module that is instantiated
+    pub fn module(self, db: &CompilationDB) -> Module {
+        let id = db.module_inst_info(self.lookup(db).inst).unwrap().module;
+        Module { id }
+    }
+
+    /// list of all module instances nested within this instance.
+    pub fn instances(self, db: &CompilationDB) -> Vec<ModuleInstance> {
+        ModuleInstance::instances_in(db, self.module(db).id, Some(self.path))
+    }
+
+    pub fn internal_nodes(self, db: &CompilationDB) -> Vec<Node> {
+        db.module_data(self.module(db).id)
+            .internal_nodes
+            .iter()
+            .map(|&id| Node { id, inst: Some(self.path) })
+            .collect()
+    }
+
+    /// All parameters of the instantiated module (including localparams).
+    pub fn params(self, db: &CompilationDB) -> Vec<Parameter> {
+        Scope::Module(self.module(db))
+            .declarations(db)
+            .into_iter()
+            .filter_map(|(_, def)| match def {
+                ScopeDef::Parameter(param) => {
+                    Some(Parameter { id: param.id, inst: Some(self.path) })
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// The parameter overrides of this instance. The overwritten parameter is paired
+    /// with the index of the corresponding entry in [`arguments`](Self::arguments).
+    pub fn param_overrides(self, db: &CompilationDB) -> Vec<(Parameter, usize)> {
+        let info = db.module_inst_info(self.lookup(db).inst).unwrap();
+        info.param_overrides
+            .iter()
+            .enumerate()
+            .filter_map(|(i, param)| Some((Parameter { id: (*param)?, inst: Some(self.path) }, i)))
+            .collect()
+    }
+
+    /// The parameter overrides and port connections of this instance.
+    /// These expressions are evaluated in the context of the parent.
+    pub fn arguments(self, db: &CompilationDB) -> Body {
+        let loc = self.lookup(db);
+        Body::new_in(loc.inst.into(), loc.parent, db)
+    }
+
+    /// Returns the node of the parent that is connected to `port`
+    fn connection(self, db: &CompilationDB, port: NodeId) -> Node {
+        let loc = self.lookup(db);
+        let info = db.module_inst_info(loc.inst).unwrap();
+        let num_overrides = info.param_overrides.len();
+        let i = info.port_connections.iter().position(|&it| it == Some(port)).unwrap();
+        let body = db.body(loc.inst.into());
+        let expr = body.stmts[body.entry_stmts[num_overrides + i]].unwrap_expr();
+        let id = db.inference_result(loc.inst.into()).expr_types[expr].unwrap_node();
+        Node::new(db, id, loc.parent)
+    }
+
+    pub fn analog_initial_block(&self, db: &CompilationDB) -> Body {
+        let module = self.module(db).id;
+        Body::new_in(DefWithBodyId::ModuleId { initial: true, module }, Some(self.path), db)
+    }
+
+    pub fn analog_block(&self, db: &CompilationDB) -> Body {
+        let module = self.module(db).id;
+        Body::new_in(DefWithBodyId::ModuleId { initial: false, module }, Some(self.path), db)
     }
 }
 
@@ -239,6 +375,11 @@ impl Function {
     pub fn body(&self, db: &CompilationDB) -> Body {
         Body::new(self.id.into(), db)
     }
+
+    /// The body of this function when called from within `inst`
+    pub fn instantiated_body(&self, db: &CompilationDB, inst: Option<ModuleInstance>) -> Body {
+        Body::new_in(self.id.into(), inst.map(|inst| inst.path), db)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -292,10 +433,13 @@ impl From<inference::BranchWrite> for BranchWrite {
     #[inline]
     fn from(inner: inference::BranchWrite) -> Self {
         match inner {
-            inference::BranchWrite::Named(branch) => BranchWrite::Named(Branch { id: branch }),
-            inference::BranchWrite::Unnamed { hi, lo } => {
-                BranchWrite::Unnamed { hi: Node { id: hi }, lo: lo.map(|id| Node { id }) }
+            inference::BranchWrite::Named(branch) => {
+                BranchWrite::Named(Branch { id: branch, inst: None })
             }
+            inference::BranchWrite::Unnamed { hi, lo } => BranchWrite::Unnamed {
+                hi: Node { id: hi, inst: None },
+                lo: lo.map(|id| Node { id, inst: None }),
+            },
         }
     }
 }
@@ -351,16 +495,17 @@ impl Scope {
                 let res = match def {
                     ScopeDefItem::ModuleId(id) => ScopeDef::ModuleInstance(Module { id }),
                     ScopeDefItem::BlockId(id) => ScopeDef::Block(Block { id }),
-                    ScopeDefItem::NodeId(id) => ScopeDef::Node(Node { id }),
-                    ScopeDefItem::VarId(id) => ScopeDef::Variable(Variable { id }),
-                    ScopeDefItem::ParamId(id) => ScopeDef::Parameter(Parameter { id }),
+                    ScopeDefItem::NodeId(id) => ScopeDef::Node(Node { id, inst: None }),
+                    ScopeDefItem::VarId(id) => ScopeDef::Variable(Variable { id, inst: None }),
+                    ScopeDefItem::ParamId(id) => ScopeDef::Parameter(Parameter { id, inst: None }),
                     ScopeDefItem::AliasParamId(id) => {
                         ScopeDef::AliasParameter(AliasParameter { id })
                     }
-                    ScopeDefItem::BranchId(id) => ScopeDef::Branch(Branch { id }),
+                    ScopeDefItem::BranchId(id) => ScopeDef::Branch(Branch { id, inst: None }),
                     ScopeDefItem::FunctionId(id) => ScopeDef::Function(Function { id }),
                     // implementation details
-                    ScopeDefItem::BuiltIn(_)
+                    ScopeDefItem::ModuleInstId(_)
+                    | ScopeDefItem::BuiltIn(_)
                     | ScopeDefItem::NatureId(_)
                     | ScopeDefItem::NatureAccess(_)
                     | ScopeDefItem::DisciplineId(_)
@@ -378,18 +523,40 @@ impl Scope {
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Node {
     id: NodeId,
+    inst: Option<InstancePathId>,
 }
 
 stdx::impl_debug! {
     match Node{
-        Node{ id } => "{id:?}";
+        Node{ id, inst: None } => "{id:?}";
+        Node{ id, inst: Some(inst) } => "{inst:?}.{id:?}";
     }
 }
 
 impl Node {
+    /// Creates the node `id` within `inst`. The ports of an instance
+    /// are replaced with the nodes they are connected to.
+    pub(crate) fn new(db: &CompilationDB, id: NodeId, inst: Option<InstancePathId>) -> Node {
+        match inst {
+            Some(path) if db.node_data(id).is_port() => ModuleInstance { path }.connection(db, id),
+            _ => Node { id, inst },
+        }
+    }
+
     #[inline]
     pub fn name(self, db: &CompilationDB) -> SmolStr {
-        db.node_data(self.id).name.clone().into()
+        let name = &db.node_data(self.id).name;
+        match self.instance() {
+            Some(inst) => format!("{}.{}", inst.path(db), name).into(),
+            None => name.clone().into(),
+        }
+    }
+
+    /// The module instance this node belongs to
+    /// (`None` for nodes of the top-level module).
+    #[inline]
+    pub fn instance(self) -> Option<ModuleInstance> {
+        self.inst.map(|path| ModuleInstance { path })
     }
 
     #[inline]
@@ -422,11 +589,13 @@ impl Node {
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Variable {
     id: VarId,
+    inst: Option<InstancePathId>,
 }
 
 stdx::impl_debug! {
     match Variable{
-        Variable{ id } => "{id:?}";
+        Variable{ id, inst: None } => "{id:?}";
+        Variable{ id, inst: Some(inst) } => "{inst:?}.{id:?}";
     }
 }
 
@@ -439,8 +608,20 @@ impl Variable {
         db.var_data(self.id).ty.clone()
     }
 
+    /// The index range of an array variable or `None` for scalar variables
+    pub fn bounds(self, db: &CompilationDB) -> Option<ArrayBounds> {
+        db.var_data(self.id).bounds
+    }
+
+    /// The initial value of the variable (or of each element for array variables)
     pub fn init(self, db: &CompilationDB) -> Body {
-        Body::new(self.id.into(), db)
+        Body::new_in(self.id.into(), self.inst, db)
+    }
+
+    /// The module instance this variable belongs to
+    /// (`None` for variables of the top-level module).
+    pub fn instance(self) -> Option<ModuleInstance> {
+        self.inst.map(|path| ModuleInstance { path })
     }
 
     pub fn get_attr(&self, db: &CompilationDB, ast: &AstCache, name: &str) -> Option<ast::Attr> {
@@ -448,9 +629,17 @@ impl Variable {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Parameter {
     id: ParamId,
+    inst: Option<InstancePathId>,
+}
+
+stdx::impl_debug! {
+    match Parameter{
+        Parameter{ id, inst: None } => "Parameter {{ id: {id:?} }}";
+        Parameter{ id, inst: Some(inst) } => "Parameter {{ id: {id:?}, inst: {inst:?} }}";
+    }
 }
 
 impl Parameter {
@@ -467,13 +656,24 @@ impl Parameter {
     }
 
     pub fn init(self, db: &CompilationDB) -> Body {
-        Body::new(self.id.into(), db)
+        Body::new_in(self.id.into(), self.inst, db)
     }
 
     pub fn ty(self, db: &CompilationDB) -> Type {
         db.param_ty(self.id)
     }
 
+    /// The module instance this parameter belongs to
+    /// (`None` for parameters of the top-level module).
+    pub fn instance(self) -> Option<ModuleInstance> {
+        self.inst.map(|path| ModuleInstance { path })
+    }
+
+    /// Returns the same parameter of the instantiated module (ignoring the instance).
+    pub fn uninstantiated(self) -> Parameter {
+        Parameter { id: self.id, inst: None }
+    }
+
     pub fn get_attr(&self, db: &CompilationDB, ast: &AstCache, name: &str) -> Option<ast::Attr> {
         ast.resolve_attribute(name, self.id.lookup(db).ast_id(db).erased())
     }
@@ -498,7 +698,7 @@ impl AliasParameter {
         db.resolve_alias(self.id).and_then(|alias| match alias {
             hir_ty::db::Alias::Cycel => None,
             hir_ty::db::Alias::Param(id) => {
-                Some(ResolvedAliasParameter::Parameter(Parameter { id }))
+                Some(ResolvedAliasParameter::Parameter(Parameter { id, inst: None }))
             }
             hir_ty::db::Alias::ParamSysFun(param) => {
                 Some(ResolvedAliasParameter::SystemParameter(param))
@@ -538,11 +738,13 @@ impl BranchKind {
 #[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct Branch {
     id: BranchId,
+    inst: Option<InstancePathId>,
 }
 
 stdx::impl_debug! {
     match Branch{
-        Branch{ id } => "{id:?}";
+        Branch{ id, inst: None } => "{id:?}";
+        Branch{ id, inst: Some(inst) } => "{inst:?}.{id:?}";
     }
 }
 
@@ -557,12 +759,11 @@ impl Branch {
     }
 
     pub fn kind(self, db: &CompilationDB) -> BranchKind {
+        let node = |id| Node::new(db, id, self.inst);
         match db.branch_info(self.id).unwrap().kind {
-            hir_ty::lower::BranchKind::PortFlow(node) => BranchKind::PortFlow(Node { id: node }),
-            hir_ty::lower::BranchKind::NodeGnd(node) => BranchKind::NodeGnd(Node { id: node }),
-            hir_ty::lower::BranchKind::Nodes(hi, lo) => {
-                BranchKind::Nodes(Node { id: hi }, Node { id: lo })
-            }
+            hir_ty::lower::BranchKind::PortFlow(id) => BranchKind::PortFlow(node(id)),
+            hir_ty::lower::BranchKind::NodeGnd(id) => BranchKind::NodeGnd(node(id)),
+            hir_ty::lower::BranchKind::Nodes(hi, lo) => BranchKind::Nodes(node(hi), node(lo)),
         }
     }
 
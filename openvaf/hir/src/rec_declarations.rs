@@ -74,13 +74,13 @@ impl Iterator for RecDeclarations<'_> {
                         continue;
                     }
                     ScopeDefItem::ModuleId(id) => ScopeDef::ModuleInstance(Module { id }),
-                    ScopeDefItem::NodeId(id) => ScopeDef::Node(Node { id }),
-                    ScopeDefItem::VarId(id) => ScopeDef::Variable(Variable { id }),
-                    ScopeDefItem::ParamId(id) => ScopeDef::Parameter(Parameter { id }),
+                    ScopeDefItem::NodeId(id) => ScopeDef::Node(Node { id, inst: None }),
+                    ScopeDefItem::VarId(id) => ScopeDef::Variable(Variable { id, inst: None }),
+                    ScopeDefItem::ParamId(id) => ScopeDef::Parameter(Parameter { id, inst: None }),
                     ScopeDefItem::AliasParamId(id) => {
                         ScopeDef::AliasParameter(AliasParameter { id })
                     }
-                    ScopeDefItem::BranchId(id) => ScopeDef::Branch(Branch { id }),
+                    ScopeDefItem::BranchId(id) => ScopeDef::Branch(Branch { id, inst: None }),
                     _ => continue,
                 };
                 return Some((name.clone(), def));
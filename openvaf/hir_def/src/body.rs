@@ -14,7 +14,8 @@ use crate::item_tree::{DisciplineAttr, ItemTreeId, ItemTreeNode, NatureAttr};
 use crate::nameres::{DefMapSource, LocalScopeId};
 use crate::{
     DefWithBodyId, DisciplineAttrLoc, DisciplineLoc, Expr, ExprId, FunctionLoc, Literal, Lookup,
-    ModuleLoc, NatureAttrLoc, NatureLoc, ParamId, ParamLoc, ScopeId, Stmt, StmtId, Type, VarLoc,
+    ModuleInstLoc, ModuleLoc, NatureAttrLoc, NatureLoc, ParamId, ParamLoc, ScopeId, Stmt, StmtId,
+    Type, VarLoc,
 };
 
 mod lower;
@@ -132,10 +133,13 @@ impl Body {
                 let expr = if let Some(expr) = ast.default() {
                     ctx.collect_expr(expr)
                 } else {
-                    let default_val = match db.var_data(var).ty {
+                    // array elements are initialized with the default of the element type
+                    let default_val = match db.var_data(var).ty.base_type() {
                         Type::Real => Literal::Float(Ieee64::with_float(0.0)),
-                        Type::Integer => Literal::Int(0),
-                        _ => unreachable!("invalid var type (TODO arrays)"),
+                        // invalid array bounds have already been reported
+                        Type::Integer | Type::Err => Literal::Int(0),
+                        Type::String => Literal::String("".into()),
+                        _ => unreachable!("invalid var type"),
                     };
                     ctx.alloc_expr_desugared(Expr::Literal(default_val))
                 };
@@ -187,6 +191,37 @@ impl Body {
                 let stmt = ctx.alloc_stmt_desugared(Stmt::Expr(expr));
                 body.entry_stmts = vec![stmt].into_boxed_slice();
             }
+            DefWithBodyId::ModuleInstId(inst) => {
+                let ModuleInstLoc { scope, id: item_tree } = inst.lookup(db);
+
+                let ast_id = tree[item_tree].ast_id();
+                let ast = ast_id_map.get(ast_id).to_node(ast.syntax());
+                let curr_scope = (scope, ast_id.into());
+
+                let mut ctx = LowerCtx {
+                    db,
+                    source_map: &mut source_map,
+                    body: &mut body,
+                    ast_id_map: &ast_id_map,
+                    curr_scope,
+                    registry: &registry,
+                };
+
+                // the entry stmts are the parameter overrides followed by the port connections
+                // this matches the order of ModuleInstData::{param_overrides, port_connections}
+                let param_overrides = ast
+                    .syntax()
+                    .parent()
+                    .and_then(ast::ModuleInst::cast)
+                    .and_then(|decl| decl.param_overrides());
+                let args = param_overrides.iter().flat_map(|it| it.args()).chain(ast.args());
+                body.entry_stmts = args
+                    .map(|arg| {
+                        let expr = ctx.collect_opt_expr(arg.val());
+                        ctx.alloc_stmt_desugared(Stmt::Expr(expr))
+                    })
+                    .collect();
+            }
         }
 
         (Arc::new(body), Arc::new(source_map))
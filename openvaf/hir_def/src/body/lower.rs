@@ -87,6 +87,12 @@ impl LowerCtx<'_> {
                 }
             }
 
+            ast::Expr::IndexExpr(e) => {
+                let base = self.collect_opt_expr(e.base());
+                let index = self.collect_opt_expr(e.index());
+                Expr::Index { base, index }
+            }
+
             ast::Expr::PortFlow(port_flow) => {
                 if let Some(path) = port_flow.port().and_then(Path::resolve) {
                     Expr::Path { path, port: true }
@@ -152,19 +158,27 @@ impl LowerCtx<'_> {
     }
 
     fn collect_event_stmt(&mut self, event_stmt: &ast::EventStmt) -> StmtId {
-        let kind = if event_stmt.initial_step_token().is_some() {
+        let events: Vec<_> =
+            event_stmt.events().filter_map(|event| self.collect_event(event)).collect();
+        if events.is_empty() {
+            return self.collect_opt_stmt(event_stmt.stmt());
+        }
+
+        let stmt = Stmt::EventControl { events, body: self.collect_opt_stmt(event_stmt.stmt()) };
+        self.alloc_stmt(stmt, AstPtr::new(event_stmt).cast().unwrap(), event_stmt.attrs())
+    }
+
+    fn collect_event(&mut self, event: ast::Event) -> Option<Event> {
+        let kind = if event.initial_step_token().is_some() {
             GlobalEvent::InitialStep
-        } else if event_stmt.final_step_token().is_some() {
+        } else if event.final_step_token().is_some() {
             GlobalEvent::FinalStep
         } else {
-            return self.collect_opt_stmt(event_stmt.stmt());
+            return event.expr().map(|expr| Event::Monitored(self.collect_expr(expr)));
         };
 
-        let phases = event_stmt.sim_phases().map(|lit| lit.unescaped_value()).collect();
-        let event = Event::Global { kind, phases };
-        let stmt = Stmt::EventControl { event, body: self.collect_opt_stmt(event_stmt.stmt()) };
-
-        self.alloc_stmt(stmt, AstPtr::new(event_stmt).cast().unwrap(), event_stmt.attrs())
+        let phases = event.sim_phases().map(|lit| lit.unescaped_value()).collect();
+        Some(Event::Global { kind, phases })
     }
 
     fn collect_case_stmt(&mut self, case_stmt: &ast::CaseStmt) -> Stmt {
@@ -7,8 +7,9 @@ use typed_index_collections::TiSlice;
 use crate::db::HirDefDB;
 use crate::item_tree::{self, BranchKind, DisciplineAttrKind, Domain, NatureRef};
 use crate::{
-    AliasParamId, BranchId, DisciplineId, FunctionId, Intern, ItemTree, LocalFunctionArgId,
-    LocalNatureAttrId, Lookup, ModuleId, NatureId, NodeId, NodeLoc, ParamId, Path, Type, VarId,
+    AliasParamId, ArrayBounds, BranchId, DisciplineId, FunctionId, Intern, ItemTree,
+    LocalFunctionArgId, LocalNatureAttrId, Lookup, ModuleId, ModuleInstId, ModuleInstLoc, NatureId,
+    NodeId, NodeLoc, ParamId, ParamLoc, Path, Type, VarId,
 };
 
 #[derive(Debug, Eq, PartialEq, Clone, Hash)]
@@ -121,13 +122,14 @@ impl NatureData {
 pub struct VarData {
     pub name: Name,
     pub ty: Type,
+    pub bounds: Option<ArrayBounds>,
 }
 
 impl VarData {
     pub fn var_data_query(db: &dyn HirDefDB, id: VarId) -> Arc<VarData> {
         let loc = id.lookup(db);
         let var = &loc.item_tree(db)[loc.id];
-        Arc::new(VarData { name: var.name.clone(), ty: var.ty.clone() })
+        Arc::new(VarData { name: var.name.clone(), ty: var.ty.clone(), bounds: var.bounds })
     }
 }
 
@@ -236,6 +238,9 @@ pub struct ModuleData {
     pub name: Name,
     pub ports: Vec<NodeId>,
     pub internal_nodes: Vec<NodeId>,
+    /// Parameters that can be overwritten by an instance (no localparams) in declaration order.
+    pub params: Vec<ParamId>,
+    pub instances: Vec<ModuleInstId>,
 }
 
 impl ModuleData {
@@ -247,6 +252,46 @@ impl ModuleData {
         let ports = (0..num_ports).map(|id| NodeLoc { module, id: id.into() }.intern(db)).collect();
         let internal_nodes =
             (num_ports..num_nodes).map(|id| NodeLoc { module, id: id.into() }.intern(db)).collect();
-        Arc::new(ModuleData { name: item_tree[loc.id].name.clone(), ports, internal_nodes })
+        let mut params = Vec::new();
+        let mut instances = Vec::new();
+        for item in &item_tree[loc.id].items {
+            match *item {
+                item_tree::ModuleItem::Parameter(id) if !item_tree[id].is_local => {
+                    params.push(ParamLoc { scope: loc.scope, id }.intern(db))
+                }
+                item_tree::ModuleItem::ModuleInst(id) => {
+                    instances.push(ModuleInstLoc { scope: loc.scope, id }.intern(db))
+                }
+                _ => (),
+            }
+        }
+        Arc::new(ModuleData {
+            name: item_tree[loc.id].name.clone(),
+            ports,
+            internal_nodes,
+            params,
+            instances,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ModuleInstData {
+    pub name: Name,
+    pub module: Name,
+    pub param_overrides: Vec<Option<Name>>,
+    pub port_connections: Vec<Option<Name>>,
+}
+
+impl ModuleInstData {
+    pub fn module_inst_data_query(db: &dyn HirDefDB, inst: ModuleInstId) -> Arc<ModuleInstData> {
+        let loc = inst.lookup(db);
+        let inst = &loc.item_tree(db)[loc.id];
+        Arc::new(ModuleInstData {
+            name: inst.name.clone(),
+            module: inst.module.clone(),
+            param_overrides: inst.param_overrides.clone(),
+            port_connections: inst.port_connections.clone(),
+        })
     }
 }
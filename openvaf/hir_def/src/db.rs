@@ -5,16 +5,17 @@ use stdx::Upcast;
 
 use crate::body::{Body, BodySourceMap, ParamExprs};
 use crate::data::{
-    AliasParamData, BranchData, DisciplineData, FunctionData, ModuleData, NatureData, NodeData,
-    ParamData, VarData,
+    AliasParamData, BranchData, DisciplineData, FunctionData, ModuleData, ModuleInstData,
+    NatureData, NodeData, ParamData, VarData,
 };
 use crate::item_tree::ItemTree;
 use crate::nameres::{DefMap, ScopeOrigin};
 use crate::{
     AliasParamId, AliasParamLoc, BlockId, BlockLoc, BranchId, BranchLoc, DefWithBodyId,
     DisciplineAttrId, DisciplineAttrLoc, DisciplineId, DisciplineLoc, FunctionArgId,
-    FunctionArgLoc, FunctionId, FunctionLoc, ModuleId, ModuleLoc, NatureAttrId, NatureAttrLoc,
-    NatureId, NatureLoc, NodeId, NodeLoc, ParamId, ParamLoc, VarId, VarLoc,
+    FunctionArgLoc, FunctionId, FunctionLoc, InstancePathId, InstancePathLoc, ModuleId,
+    ModuleInstId, ModuleInstLoc, ModuleLoc, NatureAttrId, NatureAttrLoc, NatureId, NatureLoc,
+    NodeId, NodeLoc, ParamId, ParamLoc, VarId, VarLoc,
 };
 
 #[salsa::query_group(InternDatabase)]
@@ -45,6 +46,10 @@ pub trait InternDB: BaseDB {
     fn intern_function_arg(&self, loc: FunctionArgLoc) -> FunctionArgId;
     #[salsa::interned]
     fn intern_alias_param(&self, loc: AliasParamLoc) -> AliasParamId;
+    #[salsa::interned]
+    fn intern_module_inst(&self, loc: ModuleInstLoc) -> ModuleInstId;
+    #[salsa::interned]
+    fn intern_instance_path(&self, loc: InstancePathLoc) -> InstancePathId;
 }
 
 #[salsa::query_group(HirDefDatabase)]
@@ -102,6 +107,9 @@ pub trait HirDefDB: InternDB + Upcast<dyn BaseDB> {
     #[salsa::invoke(ModuleData::module_data_query)]
     fn module_data(&self, module: ModuleId) -> Arc<ModuleData>;
 
+    #[salsa::invoke(ModuleInstData::module_inst_data_query)]
+    fn module_inst_data(&self, inst: ModuleInstId) -> Arc<ModuleInstData>;
+
     #[salsa::transparent]
     fn find_module(&self, root_file: FileId) -> ModuleId;
 }
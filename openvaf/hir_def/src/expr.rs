@@ -81,6 +81,10 @@ pub enum Expr {
         fun: Option<Path>,
         args: Vec<ExprId>,
     },
+    Index {
+        base: ExprId,
+        index: ExprId,
+    },
     Array(Vec<ExprId>),
     Literal(Literal),
 }
@@ -95,6 +99,10 @@ impl Expr {
                 f(rhs);
             }
             Expr::UnaryOp { expr, .. } => f(expr),
+            Expr::Index { base, index } => {
+                f(base);
+                f(index);
+            }
             Expr::Select { cond, then_val, else_val } => {
                 f(cond);
                 f(then_val);
@@ -124,7 +132,7 @@ pub enum Stmt {
     Missing,
     Empty,
     Expr(ExprId),
-    EventControl { event: Event, body: StmtId },
+    EventControl { events: Vec<Event>, body: StmtId },
     Assignment { dst: ExprId, val: ExprId, assignment_kind: ast::AssignOp },
     Block { /*scope: Option<BlockId>,*/ body: Vec<StmtId> },
     If { cond: ExprId, then_branch: StmtId, else_branch: StmtId },
@@ -139,11 +147,10 @@ pub enum GlobalEvent {
     FinalStep,
 }
 
-// non_exhaustive because currently the full standard is not implemented
 #[derive(Debug, Eq, PartialEq, Hash, Clone)]
-#[non_exhaustive]
 pub enum Event {
     Global { kind: GlobalEvent, phases: Vec<String> },
+    Monitored(ExprId),
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
@@ -162,7 +169,14 @@ impl Stmt {
     #[inline]
     pub fn walk_child_exprs(&self, mut f: impl FnMut(ExprId)) {
         match *self {
-            Stmt::Empty | Stmt::Missing | Stmt::Block { .. } | Stmt::EventControl { .. } => (),
+            Stmt::Empty | Stmt::Missing | Stmt::Block { .. } => (),
+            Stmt::EventControl { ref events, .. } => {
+                for event in events {
+                    if let Event::Monitored(expr) = *event {
+                        f(expr)
+                    }
+                }
+            }
             Stmt::If { cond: expr, .. }
             | Stmt::ForLoop { cond: expr, .. }
             | Stmt::WhileLoop { cond: expr, .. }
@@ -29,7 +29,8 @@ use typed_index_collections::TiVec;
 
 use crate::db::HirDefDB;
 use crate::{
-    LocalDisciplineAttrId, LocalFunctionArgId, LocalNatureAttrId, LocalNodeId, Path, Type,
+    ArrayBounds, LocalDisciplineAttrId, LocalFunctionArgId, LocalNatureAttrId, LocalNodeId, Path,
+    Type,
 };
 
 /// The item tree of a source file.
@@ -69,6 +70,7 @@ impl ItemTree {
             ports,
             branches,
             functions,
+            module_insts,
         } = &mut self.data;
         modules.shrink_to_fit();
         disciplines.shrink_to_fit();
@@ -81,6 +83,7 @@ impl ItemTree {
         ports.shrink_to_fit();
         branches.shrink_to_fit();
         functions.shrink_to_fit();
+        module_insts.shrink_to_fit();
         nature_attrs.shrink_to_fit();
         discipline_attrs.shrink_to_fit();
     }
@@ -105,6 +108,7 @@ pub(crate) struct ItemTreeData {
     pub ports: Arena<Port>,
     pub branches: Arena<Branch>,
     pub functions: Arena<Function>,
+    pub module_insts: Arena<ModuleInst>,
 }
 
 /// Trait implemented by all item nodes in the item tree.
@@ -224,6 +228,7 @@ item_tree_nodes! {
     Port in ports -> ast::PortDecl,
     Branch in branches -> ast::BranchDecl,
     Function in functions -> ast::Function,
+    ModuleInst in module_insts -> ast::Instance,
     NatureAttr in nature_attrs -> ast::NatureAttr,
     DisciplineAttr in discipline_attrs -> ast::DisciplineAttr,
 }
@@ -246,6 +251,7 @@ pub enum ModuleItem {
     Branch(ItemTreeId<Branch>),
     Node(LocalNodeId),
     Function(ItemTreeId<Function>),
+    ModuleInst(ItemTreeId<ModuleInst>),
 }
 
 impl_from_typed! (
@@ -255,7 +261,8 @@ impl_from_typed! (
     Variable(ItemTreeId<Var>),
     Branch(ItemTreeId<Branch>),
     Node(LocalNodeId),
-    Function(ItemTreeId<Function>) for ModuleItem
+    Function(ItemTreeId<Function>),
+    ModuleInst(ItemTreeId<ModuleInst>) for ModuleItem
 );
 
 #[derive(Debug, Eq, PartialEq, Clone)]
@@ -284,6 +291,8 @@ pub struct Net {
 pub struct Var {
     pub name: Name,
     pub ty: Type,
+    /// The index range for array variables
+    pub bounds: Option<ArrayBounds>,
     pub ast_id: AstId<ast::Var>,
 }
 
@@ -382,6 +391,20 @@ pub struct Branch {
     pub ast_id: AstId<ast::BranchDecl>,
 }
 
+/// An instance of another module (`res #(.r(1k)) r1 (a, b);`).
+/// A single declaration with multiple instances is split into one item per instance.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct ModuleInst {
+    pub name: Name,
+    pub module: Name,
+    /// One entry for each parameter override.
+    /// Named overrides (`.name(val)`) store the name, ordered overrides store `None`.
+    pub param_overrides: Vec<Option<Name>>,
+    /// One entry for each port connection, same convention as `param_overrides`.
+    pub port_connections: Vec<Option<Name>>,
+    pub ast_id: AstId<ast::Instance>,
+}
+
 #[derive(Debug, Eq, PartialEq, Clone)]
 pub struct Block {
     pub name: Option<Name>,
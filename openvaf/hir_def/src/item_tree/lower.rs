@@ -10,14 +10,14 @@ use typed_index_collections::TiVec;
 
 use super::{
     Block, Branch, BranchKind, Discipline, DisciplineAttr, DisciplineAttrKind, Domain, Function,
-    FunctionArg, FunctionItem, ItemTree, ItemTreeId, Module, ModuleItem, Nature, NatureAttr,
-    NatureRef, NatureRefKind, Net, Node, Param, Port, RootItem, Var,
+    FunctionArg, FunctionItem, ItemTree, ItemTreeId, Module, ModuleInst, ModuleItem, Nature,
+    NatureAttr, NatureRef, NatureRefKind, Net, Node, Param, Port, RootItem, Var,
 };
 // use tracing::trace;
 use crate::db::HirDefDB;
 use crate::item_tree::AliasParam;
 use crate::types::AsType;
-use crate::{LocalFunctionArgId, LocalNodeId, Path, Type};
+use crate::{ArrayBounds, LocalFunctionArgId, LocalNodeId, Path, Type};
 
 fn is_input(direction: &Option<ast::Direction>) -> bool {
     direction.as_ref().map_or(false, |it| it.input_token().is_some() || it.inout_token().is_some())
@@ -273,6 +273,7 @@ impl Ctx {
                 }
                 ast::ModuleItem::BranchDecl(branch) => self.lower_branch(branch, dst),
                 ast::ModuleItem::AliasParam(alias) => self.lower_alias_param(alias, dst),
+                ast::ModuleItem::ModuleInst(inst) => self.lower_module_inst(inst, dst),
             };
         }
     }
@@ -357,6 +358,35 @@ impl Ctx {
         }
     }
 
+    fn lower_module_inst(&mut self, decl: ast::ModuleInst, dst: &mut Vec<ModuleItem>) {
+        let module = match decl.module() {
+            Some(module) => module.as_name(),
+            None => return,
+        };
+        let param_overrides: Vec<_> = decl.param_overrides().map_or_else(Vec::new, |overrides| {
+            overrides.args().map(|arg| arg.name_ref().map(|name| name.as_name())).collect()
+        });
+
+        for inst in decl.instances() {
+            let name = match inst.name() {
+                Some(name) => name.as_name(),
+                None => continue,
+            };
+            let port_connections =
+                inst.args().map(|arg| arg.name_ref().map(|name| name.as_name())).collect();
+            let ast_id = self.source_ast_id_map.ast_id(&inst);
+            let inst = ModuleInst {
+                name,
+                module: module.clone(),
+                param_overrides: param_overrides.clone(),
+                port_connections,
+                ast_id,
+            };
+            let id = self.tree.data.module_insts.push_and_get_key(inst);
+            dst.push(id.into());
+        }
+    }
+
     fn lower_module_ports(
         &mut self,
         ports: ast::ModulePorts,
@@ -533,10 +563,25 @@ impl Ctx {
         let ty = decl.ty().as_type();
         for var in decl.vars() {
             if let Some(name) = var.name() {
+                let (ty, bounds) = match var.range() {
+                    Some(range) => {
+                        // invalid bounds are reported during syntax validation
+                        let bound = |expr: Option<ast::Expr>| expr?.as_int_const();
+                        match (bound(range.start()), bound(range.end())) {
+                            (Some(start), Some(end)) => {
+                                let bounds = ArrayBounds { start, end };
+                                (ty.clone().to_dim(&[bounds.size()]), Some(bounds))
+                            }
+                            _ => (Type::Err, None),
+                        }
+                    }
+                    None => (ty.clone(), None),
+                };
                 let var = Var {
                     name: name.as_name(),
                     ast_id: self.source_ast_id_map.ast_id(&var),
-                    ty: ty.clone(),
+                    ty,
+                    bounds,
                 };
                 let id = self.tree.data.variables.push_and_get_key(var);
                 dst.push(id.into())
@@ -124,6 +124,17 @@ impl<'a> Printer<'a> {
                     let param = &self.tree[param];
                     wln!(self, "aliasparam {} = {:?}", param.name, param.src);
                 }
+                ModuleItem::ModuleInst(inst) => {
+                    let inst = &self.tree[inst];
+                    wln!(
+                        self,
+                        "instance {} = {{module: {}, param_overrides: {:?}, port_connections: {:?}}}",
+                        inst.name,
+                        inst.module,
+                        inst.param_overrides,
+                        inst.port_connections
+                    );
+                }
             }
         }
     }
@@ -162,7 +173,10 @@ impl<'a> Printer<'a> {
 
     fn print_var(&mut self, var: ItemTreeId<Var>) {
         let var = &self.tree[var];
-        wln!(self, "var {} {}", var.ty, var.name);
+        match var.bounds {
+            Some(bounds) => wln!(self, "var {} {}{}", var.ty.base_type(), var.name, bounds),
+            None => wln!(self, "var {} {}", var.ty, var.name),
+        }
     }
 
     fn print_scope_items(&mut self, items: &[BlockScopeItem]) {
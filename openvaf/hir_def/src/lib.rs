@@ -28,11 +28,12 @@ use crate::db::HirDefDB;
 pub use crate::expr::{Case, Expr, ExprId, Literal, Stmt, StmtId};
 pub use crate::item_tree::{
     AliasParam, Branch, BranchKind, Discipline, DisciplineAttr, Function, ItemTree, ItemTreeId,
-    ItemTreeNode, Module, Nature, NatureAttr, NatureRef, NatureRefKind, NodeTypeDecl, Param, Var,
+    ItemTreeNode, Module, ModuleInst, Nature, NatureAttr, NatureRef, NatureRefKind, NodeTypeDecl,
+    Param, Var,
 };
 use crate::nameres::ScopeDefItem;
 pub use crate::path::Path;
-pub use crate::types::Type;
+pub use crate::types::{ArrayBounds, Type};
 
 impl ParamSysFun {
     pub fn default_value(self) -> f64 {
@@ -292,6 +293,19 @@ impl_intern!(AliasParamId, AliasParamLoc, intern_alias_param, lookup_intern_alia
 pub type FunctionLoc = ItemLoc<Function>;
 impl_intern!(FunctionId, FunctionLoc, intern_function, lookup_intern_function);
 
+pub type ModuleInstLoc = ItemLoc<ModuleInst>;
+impl_intern!(ModuleInstId, ModuleInstLoc, intern_module_inst, lookup_intern_module_inst);
+
+/// A module instance after elaboration. Each module instance
+/// is identified by the path of instances leading to it from a top-level module.
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+pub struct InstancePathLoc {
+    pub parent: Option<InstancePathId>,
+    pub inst: ModuleInstId,
+}
+
+impl_intern!(InstancePathId, InstancePathLoc, intern_instance_path, lookup_intern_instance_path);
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct NodeLoc {
     pub module: ModuleId,
@@ -411,6 +425,7 @@ pub enum DefWithBodyId {
     VarId(VarId),
     NatureAttrId(NatureAttrId),
     DisciplineAttrId(DisciplineAttrId),
+    ModuleInstId(ModuleInstId),
 }
 
 impl DefWithBodyId {
@@ -422,11 +437,12 @@ impl DefWithBodyId {
             DefWithBodyId::VarId(id) => id.lookup(db).scope.root_file,
             DefWithBodyId::NatureAttrId(id) => id.lookup(db).nature.lookup(db).root_file,
             DefWithBodyId::DisciplineAttrId(id) => id.lookup(db).discipline.lookup(db).root_file,
+            DefWithBodyId::ModuleInstId(id) => id.lookup(db).scope.root_file,
         }
     }
 }
 
-impl_from!(ParamId, FunctionId,VarId,NatureAttrId,DisciplineAttrId for DefWithBodyId);
+impl_from!(ParamId, FunctionId,VarId,NatureAttrId,DisciplineAttrId,ModuleInstId for DefWithBodyId);
 impl TryFrom<ScopeDefItem> for DefWithBodyId {
     type Error = ();
     fn try_from(src: ScopeDefItem) -> Result<DefWithBodyId, ()> {
@@ -435,6 +451,7 @@ impl TryFrom<ScopeDefItem> for DefWithBodyId {
             ScopeDefItem::ParamId(param) => param.into(),
             ScopeDefItem::FunctionId(fun) => fun.into(),
             ScopeDefItem::NatureAttrId(attr) => attr.into(),
+            ScopeDefItem::ModuleInstId(inst) => inst.into(),
             _ => return Err(()),
         };
         Ok(res)
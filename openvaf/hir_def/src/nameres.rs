@@ -15,7 +15,7 @@ use crate::db::HirDefDB;
 use crate::nameres::diagnostics::PathResolveError;
 use crate::{
     AliasParamId, BlockId, BranchId, DisciplineId, FunctionArgId, FunctionId, Lookup, ModuleId,
-    NatureAttrId, NatureId, NodeId, ParamId, VarId,
+    ModuleInstId, NatureAttrId, NatureId, NodeId, ParamId, VarId,
 };
 
 mod collect;
@@ -89,6 +89,7 @@ pub enum ScopeDefItem {
     FunctionReturn(FunctionId),
     FunctionArgId(FunctionArgId),
     NatureAttrId(NatureAttrId),
+    ModuleInstId(ModuleInstId),
 }
 
 impl ScopeDefItem {
@@ -110,6 +111,7 @@ impl ScopeDefItem {
             ScopeDefItem::BuiltIn(_) | ScopeDefItem::ParamSysFun(_) => return None,
             ScopeDefItem::AliasParamId(id) => id.lookup(db).ast_id(db).into(),
             ScopeDefItem::NatureAttrId(id) => id.lookup(db).ast_id(db).into(),
+            ScopeDefItem::ModuleInstId(id) => id.lookup(db).ast_id(db).into(),
         };
         Some(id)
     }
@@ -186,6 +188,12 @@ impl ScopeDefItem {
             ScopeDefItem::BuiltIn(_) | ScopeDefItem::ParamSysFun(_) => return None,
             ScopeDefItem::AliasParamId(id) => ast_id_map.get(id.lookup(db).ast_id(db)).range(),
             ScopeDefItem::NatureAttrId(id) => ast_id_map.get(id.lookup(db).ast_id(db)).range(),
+            ScopeDefItem::ModuleInstId(id) => ast_id_map
+                .get(id.lookup(db).ast_id(db))
+                .to_node(parse.tree().syntax())
+                .name()?
+                .syntax()
+                .text_range(),
         };
 
         Some(res)
@@ -208,6 +216,7 @@ impl_from! {
     ParamSysFun,
     // DisciplineAttrId,
     FunctionArgId,
+    ModuleInstId,
     BuiltIn
 
     for ScopeDefItem
@@ -247,7 +256,8 @@ scope_item_kinds! {
     BranchId => "branch",
     FunctionId => "function",
     BuiltIn => "function",
-    FunctionArgId => "function argument"
+    FunctionArgId => "function argument",
+    ModuleInstId => "module instance"
 }
 
 #[derive(PartialEq, Eq, Hash, Clone, Copy, Debug)]
@@ -298,6 +308,11 @@ impl DefMap {
         collect::collect_function_map(db, fun)
     }
 
+    /// All builtin functions (including system functions) that are visible in every scope.
+    pub fn builtin_items() -> impl Iterator<Item = (&'static Name, ScopeDefItem)> {
+        BUILTIN_SCOPE.iter().map(|(name, item)| (name, *item))
+    }
+
     pub fn resolve_local_name_in_scope(
         &self,
         mut scope: LocalScopeId,
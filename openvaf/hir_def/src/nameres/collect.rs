@@ -282,6 +282,9 @@ impl DefCollector<'_> {
                 ModuleItem::AliasParameter(id) => {
                     self.insert_item_decl(scope, self.tree[id].name.clone(), id)
                 }
+                ModuleItem::ModuleInst(id) => {
+                    self.insert_item_decl(scope, self.tree[id].name.clone(), id)
+                }
             }
         }
     }
@@ -4,7 +4,7 @@ use basedb::diagnostics::{Diagnostic, Label, LabelStyle, Report};
 use basedb::{AstIdMap, BaseDB, FileId};
 use stdx::{impl_display, pretty};
 use syntax::name::Name;
-use syntax::sourcemap::{FileSpan, SourceMap};
+use syntax::sourcemap::{CtxSpan, FileSpan, SourceMap};
 use syntax::{Parse, SourceFile};
 
 use crate::db::HirDefDB;
@@ -89,4 +89,13 @@ impl Diagnostic for DefDiagnosticWrapped<'_> {
             }
         }
     }
+
+    fn primary_span(&self, _root_file: FileId, _db: &dyn BaseDB) -> Option<CtxSpan> {
+        match self.diag {
+            DefDiagnostic::AlreadyDeclared { new, .. } => {
+                let range = new.text_range(self.db, self.ast_id_map, self.parse)?;
+                Some(self.parse.to_ctx_span(range, self.sm))
+            }
+        }
+    }
 }
@@ -95,7 +95,7 @@ impl Type {
 
     pub fn base_type(&self) -> &Type {
         let mut curr = self;
-        while let Type::Array { ty, .. } = self {
+        while let Type::Array { ty, .. } = curr {
             curr = ty
         }
         curr
@@ -136,6 +136,34 @@ impl Type {
     }
 }
 
+/// The index range `[start:end]` of an array variable.
+/// Both bounds are inclusive and `start` may be larger than `end`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArrayBounds {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl ArrayBounds {
+    /// The number of elements of the array
+    pub fn size(&self) -> u32 {
+        self.start.abs_diff(self.end) + 1
+    }
+
+    /// Returns the position of the array element at `index` or `None` if `index` is out of bounds
+    pub fn position(&self, index: i32) -> Option<u32> {
+        let lo = self.start.min(self.end);
+        let hi = self.start.max(self.end);
+        (lo..=hi).contains(&index).then(|| index.abs_diff(lo))
+    }
+}
+
+impl_display! {
+    match ArrayBounds{
+        ArrayBounds{start, end} => "[{}:{}]", start, end;
+    }
+}
+
 pub trait AsType {
     fn as_type(&self) -> Type;
 }
@@ -1,5 +1,4 @@
-use hir::Node;
-use hir::{BodyRef, ExprId};
+use hir::{BodyRef, ExprId, ModuleInstance, Node};
 use mir::builder::InstBuilder;
 use mir::{Block, Value};
 use stdx::iter::zip;
@@ -15,6 +14,12 @@ pub struct BodyLoweringCtx<'a, 'c1, 'c2> {
 
 impl<'c1, 'c2> BodyLoweringCtx<'_, 'c1, 'c2> {
     pub fn lower_entry_stmts(&mut self) {
+        let mut retained_vars = Vec::new();
+        for &stmnt in self.body.entry() {
+            self.collect_event_writes(stmnt, false, &mut retained_vars);
+        }
+        self.ctx.retain_vars(retained_vars);
+
         for &stmnt in self.body.entry() {
             self.lower_stmt(stmnt)
         }
@@ -86,4 +91,31 @@ impl LoweringCtx<'_, '_> {
     pub fn lower_expr_body(&mut self, body: BodyRef, i: usize) -> Value {
         BodyLoweringCtx { ctx: self, body, path: "" }.lower_expr(body.get_entry_expr(i))
     }
+
+    /// Lowers the behaviour of a module instance and all instances nested within it.
+    /// The parameters of the instance are computed upfront so that they are available
+    /// everywhere within the instance.
+    pub fn lower_instance(&mut self, inst: ModuleInstance, parent_path: &str) {
+        let args = inst.arguments(self.db);
+        for (param, i) in inst.param_overrides(self.db) {
+            let val = BodyLoweringCtx { ctx: self, body: args.borrow(), path: parent_path }
+                .lower_expr(args.borrow().get_entry_expr(i));
+            self.inst_params.insert(param, val);
+        }
+        for param in inst.params(self.db) {
+            self.read_param(param);
+        }
+
+        let path = format!("{parent_path}.{}", inst.name(self.db));
+        // lower analog initial blocks first
+        let body = inst.analog_initial_block(self.db);
+        BodyLoweringCtx { ctx: self, body: body.borrow(), path: &path }.lower_entry_stmts();
+        // ... and normal analog blocks afterwards
+        let body = inst.analog_block(self.db);
+        BodyLoweringCtx { ctx: self, body: body.borrow(), path: &path }.lower_entry_stmts();
+
+        for child in inst.instances(self.db) {
+            self.lower_instance(child, &path);
+        }
+    }
 }
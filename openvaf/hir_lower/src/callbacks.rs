@@ -1,4 +1,4 @@
-use hir::{Node, Parameter};
+use hir::{Node, Parameter, Type};
 use lasso::Spur;
 use mir::{FunctionSignature, Param};
 use stdx::Ieee64;
@@ -8,6 +8,8 @@ use crate::LimitState;
 
 #[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
 pub enum ParamInfoKind {
+    /// Called with the offending value (as a real) and the index of the violated bound
+    /// (`-1` if it can not be attributed to a single bound)
     Invalid,
     MinInclusive,
     MaxInclusive,
@@ -26,6 +28,7 @@ pub enum CallBackKind {
     ParamInfo(ParamInfoKind, Parameter),
     CollapseHint(Node, Option<Node>),
     LimDiscontinuity,
+    EventDetected,
     Analysis,
     BuiltinLimit { name: Spur, num_args: u32 },
     StoreLimit(LimitState),
@@ -33,6 +36,49 @@ pub enum CallBackKind {
     WhiteNoise { name: Spur, idx: u32 },
     FlickerNoise { name: Spur, idx: u32 },
     NoiseTable(Box<NoiseTable>),
+    // a small-signal stimulus that is only active during the analysis `analysis`
+    AcStim { analysis: Spur },
+    File(FileOp),
+    // `$fdisplay` and friends: writes a formatted message to a file descriptor
+    FilePrint { arg_tys: Box<[FmtArg]> },
+    // `$fscanf`: receives the previous value of each output and returns the number of
+    // matched conversions followed by the (possibly unchanged) outputs
+    FileScan { arg_tys: Box<[Type]> },
+}
+
+/// Operations on the files owned by an instance. Files are identified by a multi channel
+/// or file descriptor (`Integer`) just like in Verilog.
+#[derive(Debug, Clone, Copy, Hash, Eq, PartialEq)]
+pub enum FileOp {
+    Open,
+    Close,
+    /// flushes the descriptor (-1 flushes all files)
+    Flush,
+    Eof,
+    Tell,
+    Seek,
+    Rewind,
+    /// returns the number of characters read and the line
+    Gets,
+    /// returns the error code and its description
+    Error,
+}
+
+impl FileOp {
+    pub fn num_params(self) -> u16 {
+        match self {
+            FileOp::Open => 2,
+            FileOp::Seek => 3,
+            _ => 1,
+        }
+    }
+
+    pub fn num_returns(self) -> u16 {
+        match self {
+            FileOp::Gets | FileOp::Error => 2,
+            _ => 1,
+        }
+    }
 }
 
 impl CallBackKind {
@@ -70,7 +116,7 @@ impl CallBackKind {
             },
             CallBackKind::ParamInfo(kind, param) => FunctionSignature {
                 name: format!("set_{:?}({:?})", kind, param),
-                params: 0,
+                params: if *kind == ParamInfoKind::Invalid { 2 } else { 0 },
                 returns: 0,
                 has_sideeffects: true,
             },
@@ -104,6 +150,12 @@ impl CallBackKind {
                 returns: 0,
                 has_sideeffects: true,
             },
+            CallBackKind::EventDetected => FunctionSignature {
+                name: "$event_detected".to_owned(),
+                params: 0,
+                returns: 0,
+                has_sideeffects: true,
+            },
             CallBackKind::Analysis => FunctionSignature {
                 name: "analysis".to_owned(),
                 params: 1,
@@ -131,14 +183,38 @@ impl CallBackKind {
             CallBackKind::NoiseTable(table) => FunctionSignature {
                 name: format!(
                     "table_noise{}({:?}, {:?})",
-                    if table.log { "lob" } else { "" },
+                    if table.log { "_log" } else { "" },
                     table.name,
                     &table.vals
                 ),
-                params: 1,
+                params: 0,
+                returns: 1,
+                has_sideeffects: false,
+            },
+            CallBackKind::AcStim { analysis } => FunctionSignature {
+                name: format!("ac_stim({analysis:?})"),
+                params: 2,
                 returns: 1,
                 has_sideeffects: false,
             },
+            CallBackKind::File(op) => FunctionSignature {
+                name: format!("$file_{op:?}"),
+                params: op.num_params(),
+                returns: op.num_returns(),
+                has_sideeffects: true,
+            },
+            CallBackKind::FilePrint { arg_tys } => FunctionSignature {
+                name: "$fdisplay".to_owned(),
+                params: arg_tys.len() as u16 + 2,
+                returns: 0,
+                has_sideeffects: true,
+            },
+            CallBackKind::FileScan { arg_tys } => FunctionSignature {
+                name: "$fscanf".to_owned(),
+                params: arg_tys.len() as u16 + 2,
+                returns: arg_tys.len() as u16 + 1,
+                has_sideeffects: true,
+            },
         }
     }
     pub fn is_noise(&self) -> bool {
@@ -150,6 +226,12 @@ impl CallBackKind {
         )
     }
 
+    /// Noise sources and ac stimuli are small-signal sources: They are zero during
+    /// large-signal analysis and only contribute to small-signal analysis.
+    pub fn is_small_signal_src(&self) -> bool {
+        self.is_noise() || matches!(self, CallBackKind::AcStim { .. })
+    }
+
     pub fn op_dependent(&self) -> bool {
         matches!(
             self,
@@ -159,6 +241,7 @@ impl CallBackKind {
                 | CallBackKind::Analysis
                 | CallBackKind::SimParamStr
                 | CallBackKind::LimDiscontinuity
+                | CallBackKind::EventDetected
                 | CallBackKind::BuiltinLimit { .. }
         )
     }
@@ -181,19 +264,16 @@ pub struct NoiseTable {
 }
 
 impl NoiseTable {
-    // TODO: read from disk
+    /// Creates a new noise table from (frequency, power) pairs. The pairs are sorted by
+    /// frequency, only the first entry is kept for duplicate frequencies.
     pub fn new(
-        vals: impl IntoIterator<Item = (f64, f64)>,
+        vals: impl IntoIterator<Item = (Ieee64, Ieee64)>,
         log: bool,
         name: Spur,
         idx: u32,
     ) -> Self {
-        let mut vals: Vec<(Ieee64, Ieee64)> = if log {
-            vals.into_iter().map(|(f, pwr)| (f.into(), pwr.into())).collect()
-        } else {
-            vals.into_iter().map(|(f, pwr)| (f.log10().into(), pwr.into())).collect()
-        };
-        vals.sort_unstable_by(|(f1, _), (f2, _)| f1.partial_cmp(f2).unwrap());
+        let mut vals: Vec<(Ieee64, Ieee64)> = vals.into_iter().collect();
+        vals.sort_by(|(f1, _), (f2, _)| f1.partial_cmp(f2).unwrap());
         vals.dedup_by_key(|(f, _)| *f);
         Self { name, log, vals: vals.into_boxed_slice(), idx }
     }
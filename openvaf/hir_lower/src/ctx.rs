@@ -1,15 +1,18 @@
-use ahash::AHashSet;
-use hir::{CompilationDB, Node, Type, Variable};
+use ahash::{AHashMap, AHashSet};
+use hir::{ArrayBounds, BuiltIn, CompilationDB, Node, Parameter, Type, Variable};
+use indexmap::IndexMap;
 use mir::builder::{InsertBuilder, InstBuilder};
 use mir::{
-    Block, DataFlowGraph, FuncRef, Inst, Opcode, SourceLoc, Value, FALSE, F_ZERO, INFINITY, TRUE,
+    Block, DataFlowGraph, FuncRef, Inst, Opcode, SourceLoc, Value, FALSE, F_ONE, F_ZERO, INFINITY,
+    TRUE, ZERO,
 };
 use mir_build::{FuncInstBuilder, FunctionBuilder, Place};
+use stdx::iter::zip;
 use typed_indexmap::TiSet;
 
 use crate::{
-    CallBackKind, HirInterner, ImplicitEquation, ImplicitEquationKind, LimitState, ParamKind,
-    PlaceKind,
+    ArrayIndex, CallBackKind, HirInterner, ImplicitEquation, ImplicitEquationKind, LimitState,
+    OpState, OpStateKind, ParamKind, PlaceKind,
 };
 
 pub struct LoweringCtx<'a, 'c> {
@@ -25,6 +28,11 @@ pub struct LoweringCtx<'a, 'c> {
     /// but necessary to avoid accidental correlation/opimization.
     /// For example white_noise(x) - white_noise(x) is not zero.
     pub num_noise_sources: u32,
+    /// The values of the parameters of module instances.
+    /// These are not parameters of the compiled model but are
+    /// instead computed from the overrides (or default values).
+    pub inst_params: AHashMap<Parameter, Value>,
+    retained_vars: IndexMap<PlaceKind, OpState, ahash::RandomState>,
 }
 
 impl<'a, 'c> LoweringCtx<'a, 'c> {
@@ -43,6 +51,8 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
             inside_lim: false,
             intern,
             num_noise_sources: 0,
+            inst_params: AHashMap::default(),
+            retained_vars: IndexMap::default(),
         }
     }
 
@@ -63,6 +73,81 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
         val
     }
 
+    /// Reads the element of an array variable at `index`. If the index is
+    /// not known at compile time, each element is compared to the index at runtime.
+    /// Reading out of bounds yields zero.
+    pub fn read_array_element(&mut self, var: Variable, index: ArrayIndex) -> Value {
+        match index {
+            ArrayIndex::Const(pos) => self.use_place(PlaceKind::ArrayElement { var, pos }),
+            ArrayIndex::Dynamic(index) => self.select_array_element(var, index, 0),
+        }
+    }
+
+    fn select_array_element(&mut self, var: Variable, index: Value, pos: u32) -> Value {
+        let bounds = var.bounds(self.db).unwrap();
+        if pos == bounds.size() {
+            return match var.ty(self.db).base_type() {
+                Type::Real => F_ZERO,
+                _ => ZERO,
+            };
+        }
+
+        let cond = self.is_array_element(bounds, index, pos);
+        self.make_select(cond, |ctx, is_elem| {
+            if is_elem {
+                ctx.use_place(PlaceKind::ArrayElement { var, pos })
+            } else {
+                ctx.select_array_element(var, index, pos + 1)
+            }
+        })
+    }
+
+    /// Writes `val` to the element of an array variable at `index`.
+    /// Writing out of bounds has no effect.
+    pub fn write_array_element(&mut self, var: Variable, index: ArrayIndex, val: Value) {
+        match index {
+            ArrayIndex::Const(pos) => self.def_place(PlaceKind::ArrayElement { var, pos }, val),
+            ArrayIndex::Dynamic(index) => self.assign_array_element(var, index, val, 0),
+        }
+    }
+
+    fn assign_array_element(&mut self, var: Variable, index: Value, val: Value, pos: u32) {
+        let bounds = var.bounds(self.db).unwrap();
+        if pos == bounds.size() {
+            return;
+        }
+
+        let cond = self.is_array_element(bounds, index, pos);
+        self.make_cond(cond, |ctx, is_elem| {
+            if is_elem {
+                ctx.def_place(PlaceKind::ArrayElement { var, pos }, val)
+            } else {
+                ctx.assign_array_element(var, index, val, pos + 1)
+            }
+        });
+    }
+
+    fn is_array_element(&mut self, bounds: ArrayBounds, index: Value, pos: u32) -> Value {
+        let elem_index = bounds.start.min(bounds.end) + pos as i32;
+        let elem_index = self.iconst(elem_index);
+        self.func.ins().ieq(index, elem_index)
+    }
+
+    /// This function should be used for reading parameters to correctly
+    /// handle parameters of module instances
+    pub fn read_param(&mut self, param: Parameter) -> Value {
+        if param.instance().is_none() {
+            return self.use_param(ParamKind::Param(param));
+        }
+        if let Some(&val) = self.inst_params.get(&param) {
+            return val;
+        }
+        // parameter was not overwritten, use the default value
+        let val = self.lower_expr_body(param.init(self.db).borrow(), 0);
+        self.inst_params.insert(param, val);
+        val
+    }
+
     /// Defclares a mutable memory locations (places) which will
     /// be translated to SSA (phi stmts where necessary) automatically.
     /// If the requested memory location already exists then that place
@@ -79,11 +164,15 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
                 | PlaceKind::ParamMin(_)
                 | PlaceKind::ParamMax(_) => return place,
 
-                PlaceKind::Var(var) => self.use_param(ParamKind::HiddenState(var)),
+                PlaceKind::Var(var) | PlaceKind::ArrayElement { var, .. } => {
+                    self.use_param(ParamKind::HiddenState(var))
+                }
                 PlaceKind::ImplicitResidual { .. } | PlaceKind::Contribute { .. } => F_ZERO,
                 PlaceKind::CollapseImplicitEquation(_) => TRUE,
                 PlaceKind::IsVoltageSrc(_) => FALSE,
-                PlaceKind::BoundStep => INFINITY,
+                PlaceKind::BoundStep | PlaceKind::Breakpoint => INFINITY,
+                PlaceKind::AbsdelayDelay(_) => F_ZERO,
+                PlaceKind::OpState(state) => self.use_param(ParamKind::PrevOpState(state)),
             };
             let entry = self.func.func.layout.entry_block().unwrap();
             self.func.def_var_at(place, init, entry);
@@ -240,6 +329,491 @@ impl<'a, 'c> LoweringCtx<'a, 'c> {
         self.func.def_var(place, residual_val);
     }
 
+    /// Allocates `N` states for the analog operator `op`. Operator states
+    /// retain their value between evaluations (see [`PlaceKind::OpState`]).
+    pub fn op_states<const N: usize>(&mut self, op: BuiltIn) -> [OpState; N] {
+        [(); N].map(|_| self.intern.op_states.push_and_get_key(OpStateKind::Operator(op)))
+    }
+
+    /// Variables that are assigned by an event must retain their value between evaluations
+    /// (for example a variable that is only assigned in an `@(initial_step)` block).
+    /// These variables are stored as operator states (one for each element of an array).
+    /// They are initialized normally during the initial step and restored from the previous
+    /// evaluation otherwise. String variables are not retained.
+    pub fn retain_vars(&mut self, vars: impl IntoIterator<Item = Variable>) {
+        let mut new_places = Vec::new();
+        for var in vars {
+            if self.no_equations
+                || !matches!(var.ty(self.db).base_type(), Type::Real | Type::Integer)
+            {
+                continue;
+            }
+            let places: Vec<_> = match var.bounds(self.db) {
+                Some(bounds) => {
+                    (0..bounds.size()).map(|pos| PlaceKind::ArrayElement { var, pos }).collect()
+                }
+                None => vec![PlaceKind::Var(var)],
+            };
+            for place in places {
+                if self.retained_vars.contains_key(&place) {
+                    continue;
+                }
+                let state = self.intern.op_states.push_and_get_key(OpStateKind::Var(var));
+                self.retained_vars.insert(place, state);
+                new_places.push((place, state));
+            }
+        }
+
+        if new_places.is_empty() {
+            return;
+        }
+
+        let initial_step = self.use_param(ParamKind::InitialStep);
+        self.make_cond(initial_step, |ctx, initial_step| {
+            if initial_step {
+                return;
+            }
+            for &(place, state) in &new_places {
+                let mut val = ctx.use_param(ParamKind::PrevOpState(state));
+                let ty = place.ty(ctx.db);
+                if ty == Type::Integer {
+                    val = ctx.insert_cast(val, &Type::Real, &ty)
+                }
+                ctx.def_place(place, val);
+            }
+        });
+    }
+
+    /// Saves the values of all retained variables (see [`retain_vars`](Self::retain_vars))
+    pub fn store_retained_vars(&mut self) {
+        for (place, state) in self.retained_vars.clone() {
+            let mut val = self.use_place(place);
+            let ty = place.ty(self.db);
+            if ty == Type::Integer {
+                val = self.insert_cast(val, &ty, &Type::Real)
+            }
+            self.def_place(PlaceKind::OpState(state), val);
+        }
+    }
+
+    /// Requests that the simulator does not step over `time`.
+    /// If multiple breakpoints are requested the earliest one is used.
+    pub fn request_breakpoint(&mut self, time: Value) {
+        let prev = self.use_place(PlaceKind::Breakpoint);
+        let earlier = self.func.ins().flt(time, prev);
+        let next = self.make_select(earlier, |_, earlier| if earlier { time } else { prev });
+        self.def_place(PlaceKind::Breakpoint, next);
+    }
+
+    /// Implements the `transition` filter. The current ramp is tracked with four operator
+    /// states: the target value, the start time, the value at the start time and the end time.
+    /// A change of the input starts a new ramp after `delay` that interrupts the current one.
+    /// Outside of transient analysis the input is passed through unchanged.
+    pub fn transition(&mut self, val: Value, delay: Value, rise: Value, fall: Value) -> Value {
+        let states = self.op_states(BuiltIn::transition);
+        let [target, start, start_val, end] = states.map(PlaceKind::OpState);
+        let time = self.use_param(ParamKind::Abstime);
+        let transient = self.use_param(ParamKind::EnableIntegration);
+        self.make_cond(transient, |ctx, transient| {
+            if transient {
+                let prev_target = ctx.use_place(target);
+                let changed = ctx.func.ins().fne(val, prev_target);
+                ctx.make_cond(changed, |ctx, changed| {
+                    if changed {
+                        let current = ctx.transition_ramp(states, time);
+                        let start_time = ctx.func.ins().fadd(time, delay);
+                        let rising = ctx.func.ins().fgt(val, current);
+                        let duration =
+                            ctx.make_select(rising, |_, rising| if rising { rise } else { fall });
+                        let end_time = ctx.func.ins().fadd(start_time, duration);
+                        ctx.def_place(target, val);
+                        ctx.def_place(start, start_time);
+                        ctx.def_place(start_val, current);
+                        ctx.def_place(end, end_time);
+                    }
+                });
+            } else {
+                ctx.def_place(target, val);
+                ctx.def_place(start, time);
+                ctx.def_place(start_val, val);
+                ctx.def_place(end, time);
+            }
+        });
+
+        // the corners of the ramp must be hit exactly
+        let start_time = self.use_place(start);
+        let end_time = self.use_place(end);
+        let before_start = self.func.ins().flt(time, start_time);
+        let breakpoint = self.make_select(before_start, |ctx, before_start| {
+            if before_start {
+                start_time
+            } else {
+                let before_end = ctx.func.ins().flt(time, end_time);
+                ctx.make_select(
+                    before_end,
+                    |_, before_end| {
+                        if before_end {
+                            end_time
+                        } else {
+                            INFINITY
+                        }
+                    },
+                )
+            }
+        });
+        self.request_breakpoint(breakpoint);
+
+        self.transition_ramp(states, time)
+    }
+
+    /// The output of a `transition` filter at `time`
+    fn transition_ramp(&mut self, states: [OpState; 4], time: Value) -> Value {
+        let [target, start, start_val, end] =
+            states.map(|state| self.use_place(PlaceKind::OpState(state)));
+        let done = self.func.ins().fge(time, end);
+        self.make_select(done, |ctx, done| {
+            if done {
+                return target;
+            }
+            let started = ctx.func.ins().fgt(time, start);
+            ctx.make_select(started, |ctx, started| {
+                if !started {
+                    return start_val;
+                }
+                let delta = ctx.func.ins().fsub(target, start_val);
+                let elapsed = ctx.func.ins().fsub(time, start);
+                let duration = ctx.func.ins().fsub(end, start);
+                let fraction = ctx.func.ins().fdiv(elapsed, duration);
+                let offset = ctx.func.ins().fmul(delta, fraction);
+                ctx.func.ins().fadd(start_val, offset)
+            })
+        })
+    }
+
+    /// Implements the `slew` filter. The output is computed relative to the output at the
+    /// last timepoint before the current one, so that repeated evaluations at the same time
+    /// (newton iterations) see the same starting point. Outside of transient analysis the
+    /// input is passed through unchanged.
+    pub fn slew(&mut self, val: Value, pos_rate: Value, neg_rate: Value) -> Value {
+        let states = self.op_states(BuiltIn::slew);
+        let [last_val, last_time, base_val, base_time] = states.map(PlaceKind::OpState);
+        let time = self.use_param(ParamKind::Abstime);
+        let transient = self.use_param(ParamKind::EnableIntegration);
+        let res = self.make_select(transient, |ctx, transient| {
+            if !transient {
+                ctx.def_place(base_val, val);
+                ctx.def_place(base_time, time);
+                return val;
+            }
+
+            let prev_time = ctx.use_place(last_time);
+            let advanced = ctx.func.ins().fgt(time, prev_time);
+            ctx.make_cond(advanced, |ctx, advanced| {
+                if advanced {
+                    let prev_val = ctx.use_place(last_val);
+                    ctx.def_place(base_val, prev_val);
+                    ctx.def_place(base_time, prev_time);
+                }
+            });
+
+            let start_val = ctx.use_place(base_val);
+            let start_time = ctx.use_place(base_time);
+            let dt = ctx.func.ins().fsub(time, start_time);
+            let max_rise = ctx.func.ins().fmul(pos_rate, dt);
+            let max = ctx.func.ins().fadd(start_val, max_rise);
+            let max_fall = ctx.func.ins().fmul(neg_rate, dt);
+            let min = ctx.func.ins().fadd(start_val, max_fall);
+
+            let too_large = ctx.func.ins().fgt(val, max);
+            ctx.make_select(too_large, |ctx, too_large| {
+                if too_large {
+                    // the output reaches the input once it rose by the remaining difference
+                    let remaining = ctx.func.ins().fsub(val, max);
+                    let remaining = ctx.func.ins().fdiv(remaining, pos_rate);
+                    let reached = ctx.func.ins().fadd(time, remaining);
+                    ctx.request_breakpoint(reached);
+                    return max;
+                }
+                let too_small = ctx.func.ins().flt(val, min);
+                ctx.make_select(too_small, |ctx, too_small| {
+                    if too_small {
+                        let remaining = ctx.func.ins().fsub(val, min);
+                        let remaining = ctx.func.ins().fdiv(remaining, neg_rate);
+                        let reached = ctx.func.ins().fadd(time, remaining);
+                        ctx.request_breakpoint(reached);
+                        min
+                    } else {
+                        val
+                    }
+                })
+            })
+        });
+        self.def_place(last_val, res);
+        self.def_place(last_time, time);
+        res
+    }
+
+    /// Implements the `cross` and `above` events. The monitored expression is compared
+    /// against its value at the last timepoint before the current one (like `slew`),
+    /// so repeated evaluations at the same time detect the same crossing. When a crossing
+    /// is detected a breakpoint is requested at the (linearly interpolated) crossing time,
+    /// unless the crossing was already located within the tolerances. As that breakpoint lies
+    /// in the past the simulator is expected to reject the timestep and retry.
+    /// `above` additionally triggers outside of transient analysis if `val` is not negative.
+    pub fn cross(
+        &mut self,
+        op: BuiltIn,
+        val: Value,
+        dir: Value,
+        time_tol: Option<Value>,
+        expr_tol: Option<Value>,
+    ) -> Value {
+        let states = self.op_states(op);
+        let [last_val, last_time, base_val, base_time] = states.map(PlaceKind::OpState);
+        let time = self.use_param(ParamKind::Abstime);
+        let transient = self.use_param(ParamKind::EnableIntegration);
+        let detected = self.make_select(transient, |ctx, transient| {
+            if !transient {
+                ctx.def_place(base_val, val);
+                ctx.def_place(base_time, time);
+                return if op == BuiltIn::above { ctx.func.ins().fge(val, F_ZERO) } else { FALSE };
+            }
+
+            let prev_time = ctx.use_place(last_time);
+            let advanced = ctx.func.ins().fgt(time, prev_time);
+            ctx.make_cond(advanced, |ctx, advanced| {
+                if advanced {
+                    let prev_val = ctx.use_place(last_val);
+                    ctx.def_place(base_val, prev_val);
+                    ctx.def_place(base_time, prev_time);
+                }
+            });
+
+            let start_val = ctx.use_place(base_val);
+            let start_time = ctx.use_place(base_time);
+            let was_below = ctx.func.ins().flt(start_val, F_ZERO);
+            let crossed = ctx.make_select(was_below, |ctx, was_below| {
+                let (crossed, allowed) = if was_below {
+                    (ctx.func.ins().fge(val, F_ZERO), ctx.func.ins().ige(dir, ZERO))
+                } else {
+                    let was_above = ctx.func.ins().fgt(start_val, F_ZERO);
+                    let crossed = ctx.make_select(was_above, |ctx, was_above| {
+                        if was_above {
+                            ctx.func.ins().fle(val, F_ZERO)
+                        } else {
+                            FALSE
+                        }
+                    });
+                    (crossed, ctx.func.ins().ile(dir, ZERO))
+                };
+                ctx.make_select(crossed, |_, crossed| if crossed { allowed } else { FALSE })
+            });
+
+            ctx.make_cond(crossed, |ctx, crossed| {
+                if !crossed {
+                    return;
+                }
+                let delta = ctx.func.ins().fsub(start_val, val);
+                let fraction = ctx.func.ins().fdiv(start_val, delta);
+                let dt = ctx.func.ins().fsub(time, start_time);
+                let offset = ctx.func.ins().fmul(dt, fraction);
+                let crossing = ctx.func.ins().fadd(start_time, offset);
+                let located = match time_tol {
+                    Some(time_tol) => {
+                        let err = ctx.func.ins().fsub(time, crossing);
+                        ctx.func.ins().fle(err, time_tol)
+                    }
+                    None => FALSE,
+                };
+                let located = match expr_tol {
+                    Some(expr_tol) => ctx.make_select(located, |ctx, located| {
+                        if located {
+                            return TRUE;
+                        }
+                        let min = ctx.func.ins().fneg(expr_tol);
+                        let above_min = ctx.func.ins().fge(val, min);
+                        ctx.make_select(above_min, |ctx, above_min| {
+                            if above_min {
+                                ctx.func.ins().fle(val, expr_tol)
+                            } else {
+                                FALSE
+                            }
+                        })
+                    }),
+                    None => located,
+                };
+                ctx.make_cond(located, |ctx, located| {
+                    if !located {
+                        ctx.request_breakpoint(crossing)
+                    }
+                });
+            });
+            crossed
+        });
+        self.def_place(last_val, val);
+        self.def_place(last_time, time);
+        detected
+    }
+
+    /// Implements the `timer` event. The time at which the timer fires next is kept as
+    /// an operator state (using the same scheme as `cross` for newton iterations) and
+    /// requested as a breakpoint. Without a `period` the timer fires only once.
+    pub fn timer(&mut self, start: Value, period: Option<Value>, tol: Value) -> Value {
+        let states = self.op_states(BuiltIn::timer);
+        let [last_next, last_time, base_next] = states.map(PlaceKind::OpState);
+        let time = self.use_param(ParamKind::Abstime);
+        let transient = self.use_param(ParamKind::EnableIntegration);
+        let detected = self.make_select(transient, |ctx, transient| {
+            if !transient {
+                ctx.def_place(base_next, start);
+                ctx.def_place(last_next, start);
+                return FALSE;
+            }
+
+            let prev_time = ctx.use_place(last_time);
+            let advanced = ctx.func.ins().fgt(time, prev_time);
+            ctx.make_cond(advanced, |ctx, advanced| {
+                if advanced {
+                    let prev_next = ctx.use_place(last_next);
+                    ctx.def_place(base_next, prev_next);
+                }
+            });
+
+            let next = ctx.use_place(base_next);
+            let earliest = ctx.func.ins().fsub(next, tol);
+            let due = ctx.func.ins().fge(time, earliest);
+            let new_next = ctx.make_select(due, |ctx, due| {
+                if !due {
+                    return next;
+                }
+                match period {
+                    Some(period) => {
+                        let elapsed = ctx.func.ins().fsub(time, next);
+                        let periods = ctx.func.ins().fdiv(elapsed, period);
+                        let periods = ctx.func.ins().floor(periods);
+                        let periods = ctx.func.ins().fadd(periods, F_ONE);
+                        let offset = ctx.func.ins().fmul(periods, period);
+                        ctx.func.ins().fadd(next, offset)
+                    }
+                    None => INFINITY,
+                }
+            });
+            ctx.def_place(last_next, new_next);
+            ctx.request_breakpoint(new_next);
+            due
+        });
+        self.def_place(last_time, time);
+        detected
+    }
+
+    /// Implements the `zi_*` filters `y = N(1/z)/D(1/z) * val`. The input is sampled by a
+    /// `timer` with the given `period` starting at `start`. At each sample the output is
+    /// computed from the current and previous samples (direct form I) and held until the
+    /// next sample. The previous samples are operator states that use the same scheme as
+    /// `slew` for newton iterations. Outside of transient analysis the filter is assumed to
+    /// be in steady state and the input is scaled with the dc gain `N(1)/D(1)`.
+    pub fn zi(
+        &mut self,
+        op: BuiltIn,
+        val: Value,
+        num: &[Value],
+        den: &[Value],
+        period: Value,
+        start: Value,
+    ) -> Value {
+        let dc_gain = |ctx: &mut Self| {
+            let mut sums = [num, den].map(|coeffs| {
+                coeffs.iter().fold(F_ZERO, |sum, &coeff| ctx.func.ins().fadd(sum, coeff))
+            });
+            sums[0] = ctx.func.ins().fmul(val, sums[0]);
+            ctx.func.ins().fdiv(sums[0], sums[1])
+        };
+
+        if self.no_equations {
+            return dc_gain(self);
+        }
+
+        // past inputs followed by past outputs, the first output is the held value
+        let inputs = num.len() - 1;
+        let outputs = den.len().max(2) - 1;
+        let len = inputs + outputs;
+        let states: Vec<_> = (0..2 * len + 1)
+            .map(|_| self.intern.op_states.push_and_get_key(OpStateKind::Operator(op)))
+            .map(PlaceKind::OpState)
+            .collect();
+        let (last, base) = states[..2 * len].split_at(len);
+        let last_time = states[2 * len];
+
+        let sample = self.timer(start, Some(period), F_ZERO);
+        let time = self.use_param(ParamKind::Abstime);
+        let transient = self.use_param(ParamKind::EnableIntegration);
+        let res = self.make_select(transient, |ctx, transient| {
+            if !transient {
+                let res = dc_gain(ctx);
+                for (i, (&last, &base)) in zip(last, base).enumerate() {
+                    let val = if i < inputs { val } else { res };
+                    ctx.def_place(last, val);
+                    ctx.def_place(base, val);
+                }
+                return res;
+            }
+
+            let prev_time = ctx.use_place(last_time);
+            let advanced = ctx.func.ins().fgt(time, prev_time);
+            ctx.make_cond(advanced, |ctx, advanced| {
+                if advanced {
+                    for (&last, &base) in zip(last, base) {
+                        let val = ctx.use_place(last);
+                        ctx.def_place(base, val);
+                    }
+                }
+            });
+
+            let prev: Vec<_> = base.iter().map(|&base| ctx.use_place(base)).collect();
+            let ((then_bb, then_vals), (else_bb, else_vals)) =
+                ctx.make_cond(sample, |ctx, sample| {
+                    if !sample {
+                        return prev.clone();
+                    }
+                    let (prev_inputs, prev_outputs) = prev.split_at(inputs);
+                    let mut acc = ctx.func.ins().fmul(num[0], val);
+                    for (&n_k, &u_k) in zip(&num[1..], prev_inputs) {
+                        let term = ctx.func.ins().fmul(n_k, u_k);
+                        acc = ctx.func.ins().fadd(acc, term);
+                    }
+                    for (&d_k, &y_k) in zip(&den[1..], prev_outputs) {
+                        let term = ctx.func.ins().fmul(d_k, y_k);
+                        acc = ctx.func.ins().fsub(acc, term);
+                    }
+                    let res = ctx.func.ins().fdiv(acc, den[0]);
+
+                    // shift the new samples into the registers
+                    let mut next = Vec::with_capacity(len);
+                    if inputs != 0 {
+                        next.push(val);
+                        next.extend_from_slice(&prev_inputs[..inputs - 1]);
+                    }
+                    next.push(res);
+                    next.extend_from_slice(&prev_outputs[..outputs - 1]);
+                    next
+                });
+
+            let mut res = F_ZERO;
+            for (i, (&last, (then_val, else_val))) in
+                zip(last, zip(then_vals, else_vals)).enumerate()
+            {
+                let val = ctx.func.ins().phi(&[(then_bb, then_val), (else_bb, else_val)]);
+                ctx.def_place(last, val);
+                if i == inputs {
+                    res = val;
+                }
+            }
+            res
+        });
+        self.def_place(last_time, time);
+        res
+    }
+
     pub fn insert_cast(&mut self, val: Value, src: &Type, dst: &Type) -> Value {
         let op = match (dst, src) {
             (Type::Real, Type::Integer) => Opcode::IFcast,
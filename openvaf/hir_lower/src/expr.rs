@@ -2,16 +2,18 @@ use hir::builtin::{
     FLICKER_NOISE_NAME, NOISE_TABLE_FILE_NAME, NOISE_TABLE_INLINE_NAME, WHITE_NOISE_NAME,
 };
 use hir::signatures::{
-    ABS_INT, ABS_REAL, BOOL_EQ, DDX_POT, IDTMOD_IC, IDTMOD_IC_MODULUS, IDTMOD_IC_MODULUS_OFFSET,
-    IDTMOD_IC_MODULUS_OFFSET_NATURE, IDTMOD_IC_MODULUS_OFFSET_TOL, IDTMOD_NO_IC, IDT_IC,
-    IDT_IC_ASSERT, IDT_IC_ASSERT_NATURE, IDT_IC_ASSERT_TOL, IDT_NO_IC, INT_EQ, INT_OP,
-    LIMIT_BUILTIN_FUNCTION, MAX_INT, MAX_REAL, NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES,
-    NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW, REAL_EQ, REAL_OP, SIMPARAM_DEFAULT,
-    SIMPARAM_NO_DEFAULT, STR_EQ,
+    ABSDELAY_MAX, ABS_INT, ABS_REAL, BOOL_EQ, DDX_POT, FFLUSH_ALL, FOPEN_NO_MODE, IDTMOD_IC,
+    IDTMOD_IC_MODULUS, IDTMOD_IC_MODULUS_OFFSET, IDTMOD_IC_MODULUS_OFFSET_NATURE,
+    IDTMOD_IC_MODULUS_OFFSET_TOL, IDTMOD_NO_IC, IDT_IC, IDT_IC_ASSERT, IDT_IC_ASSERT_NATURE,
+    IDT_IC_ASSERT_TOL, IDT_NO_IC, INT_EQ, INT_OP, LIMIT_BUILTIN_FUNCTION, MAX_INT, MAX_REAL,
+    NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES, NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW,
+    REAL_EQ, REAL_OP, SIMPARAM_DEFAULT, SIMPARAM_NO_DEFAULT, SLEW_NO_MAX, STR_EQ,
+};
+use hir::{
+    Body, BuiltIn, Expr, ExprId, Literal, /*ParamSysFun,*/ Ref, ResolvedFun, Type, Variable,
 };
-use hir::{Body, BuiltIn, Expr, ExprId, Literal, /*ParamSysFun,*/ Ref, ResolvedFun, Type};
 use mir::builder::InstBuilder;
-use mir::{Opcode, Value, FALSE, F_ZERO, GRAVESTONE, INFINITY, TRUE, ZERO};
+use mir::{Opcode, Value, FALSE, F_ONE, F_TWO, F_ZERO, GRAVESTONE, INFINITY, TRUE, ZERO};
 use mir_build::RetBuilder;
 use stdx::iter::zip;
 use syntax::ast::{BinaryOp, UnaryOp};
@@ -19,7 +21,8 @@ use syntax::ast::{BinaryOp, UnaryOp};
 use crate::body::BodyLoweringCtx;
 use crate::fmt::DisplayKind;
 use crate::{
-    CallBackKind, CurrentKind, IdtKind, ImplicitEquationKind, NoiseTable, ParamKind, PlaceKind,
+    AbsdelayEquations, ArrayIndex, CallBackKind, CurrentKind, FileOp, IdtKind,
+    ImplicitEquationKind, NoiseTable, ParamKind, PlaceKind,
 };
 
 impl BodyLoweringCtx<'_, '_, '_> {
@@ -32,7 +35,7 @@ impl BodyLoweringCtx<'_, '_, '_> {
             Expr::Read(Ref::ParamSysFun(param)) => {
                 self.ctx.use_param(ParamKind::ParamSysFun(param))
             }
-            Expr::Read(Ref::Parameter(param)) => self.ctx.use_param(ParamKind::Param(param)),
+            Expr::Read(Ref::Parameter(param)) => self.ctx.read_param(param),
             Expr::Read(Ref::FunctionReturn(fun)) => {
                 self.ctx.use_place(PlaceKind::FunctionReturn(fun))
             }
@@ -53,6 +56,10 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 ResolvedFun::User { func, limit } => self.lower_user_fun(func, limit, args),
                 ResolvedFun::BuiltIn(builtin) => self.lower_builtin(expr, builtin, args),
             },
+            Expr::Index { var, index } => {
+                let index = self.lower_array_index(var, index);
+                self.ctx.read_array_element(var, index)
+            }
             Expr::Array(vals) => self.lower_array(expr, vals),
             Expr::Literal(lit) => match *lit {
                 Literal::String(ref str) => self.ctx.sconst(str),
@@ -101,8 +108,19 @@ impl BodyLoweringCtx<'_, '_, '_> {
         }
     }
 
+    pub(crate) fn lower_array_index(&mut self, var: Variable, index: ExprId) -> ArrayIndex {
+        if let Some(index) = self.body.as_literalsignedint(&index) {
+            if let Some(pos) = var.bounds(self.ctx.db).unwrap().position(index) {
+                return ArrayIndex::Const(pos);
+            }
+        }
+        ArrayIndex::Dynamic(self.lower_expr(index))
+    }
+
     fn lower_array(&mut self, _expr: ExprId, _args: &[ExprId]) -> Value {
-        todo!("arrays")
+        unreachable!(
+            "array literals outside of filters and noise tables are rejected during validation"
+        )
     }
     fn lower_bin_op(&mut self, expr: ExprId, lhs: ExprId, rhs: ExprId, op: BinaryOp) -> Value {
         let signature = self.body.get_call_signature(expr);
@@ -241,7 +259,7 @@ impl BodyLoweringCtx<'_, '_, '_> {
         };
         self.ctx.def_place(PlaceKind::FunctionReturn(fun), init);
 
-        let body = fun.body(self.ctx.db);
+        let body = fun.instantiated_body(self.ctx.db, self.body.instance());
         BodyLoweringCtx { body: body.borrow(), path: self.path, ctx: self.ctx }.lower_entry_stmts();
 
         // write outputs back to original (including possibly required cast)
@@ -253,7 +271,7 @@ impl BodyLoweringCtx<'_, '_, '_> {
                     val = self.ctx.insert_cast(val, src, &dst)
                 }
                 let dst = self.body.get_expr(expr).as_assignment_lhs();
-                self.ctx.def_place(dst.into(), val);
+                self.lower_assignment(dst, val);
             }
         }
 
@@ -443,6 +461,59 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 self.ctx.seal_block(unreachable_bb);
                 GRAVESTONE
             }
+            BuiltIn::fwrite => {
+                self.ins_fdisplay(false, args);
+                GRAVESTONE
+            }
+            BuiltIn::fdisplay | BuiltIn::fstrobe | BuiltIn::fmonitor | BuiltIn::fdebug => {
+                self.ins_fdisplay(true, args);
+                GRAVESTONE
+            }
+            BuiltIn::fscanf => self.ins_fscanf(args),
+            BuiltIn::fopen => {
+                let name = self.lower_expr(args[0]);
+                // without a mode the file is opened as a multi channel descriptor
+                let mode = if signature == FOPEN_NO_MODE {
+                    self.ctx.sconst("")
+                } else {
+                    self.lower_expr(args[1])
+                };
+                self.ctx.call1(CallBackKind::File(FileOp::Open), &[name, mode])
+            }
+            BuiltIn::fflush => {
+                let fd = if signature == FFLUSH_ALL {
+                    self.ctx.iconst(-1)
+                } else {
+                    self.lower_expr(args[0])
+                };
+                self.ctx.call1(CallBackKind::File(FileOp::Flush), &[fd])
+            }
+            BuiltIn::fclose | BuiltIn::feof | BuiltIn::ftell | BuiltIn::fseek | BuiltIn::rewind => {
+                let op = match builtin {
+                    BuiltIn::fclose => FileOp::Close,
+                    BuiltIn::feof => FileOp::Eof,
+                    BuiltIn::ftell => FileOp::Tell,
+                    BuiltIn::fseek => FileOp::Seek,
+                    _ => FileOp::Rewind,
+                };
+                let args: Vec<_> = args.iter().map(|arg| self.lower_expr(*arg)).collect();
+                self.ctx.call1(CallBackKind::File(op), &args)
+            }
+            // $fgets(str, fd) and $ferror(fd, str) write the string to their variable argument
+            BuiltIn::fgets | BuiltIn::ferror => {
+                let (op, fd, dst) = if builtin == BuiltIn::fgets {
+                    (FileOp::Gets, args[1], args[0])
+                } else {
+                    (FileOp::Error, args[0], args[1])
+                };
+                let fd = self.lower_expr(fd);
+                let inst = self.ctx.call(CallBackKind::File(op), &[fd]);
+                let results = self.ctx.dfg().inst_results(inst);
+                let (res, str) = (results[0], results[1]);
+                let dst = self.body.get_expr(dst).as_assignment_lhs();
+                self.lower_assignment(dst, str);
+                res
+            }
             BuiltIn::analysis => {
                 let arg = self.lower_expr(args[0]);
                 self.ctx.call1(CallBackKind::Analysis, &[arg])
@@ -502,10 +573,22 @@ impl BodyLoweringCtx<'_, '_, '_> {
                     self.ctx.func.interner.get_or_intern(name)
                 };
                 let log = builtin == BuiltIn::noise_table_log;
-                let noise_table = NoiseTable::new([(0.0, 0.0)], log, name, idx);
+                let vals = self.body.noise_table(self.ctx.db, expr);
+                let noise_table = NoiseTable::new(vals.iter().copied(), log, name, idx);
                 self.ctx.call1(CallBackKind::NoiseTable(Box::new(noise_table)), &[])
             }
 
+            BuiltIn::ac_stim => {
+                let analysis = match args.first() {
+                    Some(&arg) => self.body.as_literal(arg).unwrap().unwrap_str(),
+                    None => "ac",
+                };
+                let analysis = self.ctx.func.interner.get_or_intern(analysis);
+                let mag = args.get(1).map_or(F_ONE, |arg| self.lower_expr(*arg));
+                let phase = args.get(2).map_or(F_ZERO, |arg| self.lower_expr(*arg));
+                self.ctx.call1(CallBackKind::AcStim { analysis }, &[mag, phase])
+            }
+
             BuiltIn::abstime => self.ctx.use_param(ParamKind::Abstime),
 
             BuiltIn::ddt => {
@@ -623,9 +706,22 @@ impl BodyLoweringCtx<'_, '_, '_> {
                 let arg0 = self.lower_expr(args[0]);
                 self.ctx.call1(CallBackKind::SimParamStr, &[arg0])
             }
-            BuiltIn::param_given => self
-                .ctx
-                .use_param(ParamKind::ParamGiven { param: self.body.into_parameter(args[0]) }),
+            BuiltIn::param_given => {
+                let param = self.body.into_parameter(args[0]);
+                match param.instance() {
+                    Some(inst) => {
+                        let overrides = inst.param_overrides(self.ctx.db);
+                        if overrides.iter().any(|&(it, _)| it == param) {
+                            TRUE
+                        } else {
+                            FALSE
+                        }
+                    }
+                    None => self.ctx.use_param(ParamKind::ParamGiven { param }),
+                }
+            }
+            // all ports of an instance must be connected
+            BuiltIn::port_connected if self.body.instance().is_some() => TRUE,
             BuiltIn::port_connected => {
                 self.ctx.use_param(ParamKind::PortConnected { port: self.body.into_node(args[0]) })
             }
@@ -670,35 +766,90 @@ impl BodyLoweringCtx<'_, '_, '_> {
             }
             BuiltIn::finish | BuiltIn::stop => GRAVESTONE,
 
-            /* TODO: absdelay
+            BuiltIn::absdelay if self.ctx.no_equations => self.lower_expr(args[0]),
             BuiltIn::absdelay => {
                 let arg = self.lower_expr(args[0]);
                 let mut delay = self.lower_expr(args[1]);
-                let (eq1, res) = self.ctx.implicit_equation(ImplicitEquationKind::Absdelay);
-                let (eq2, intermediate) = self.ctx.implicit_equation(ImplicitEquationKind::Absdelay);
                 if signature == ABSDELAY_MAX {
                     let max_delay = self.lower_expr(args[2]);
                     let use_delay = self.ctx.ins().fle(delay, max_delay);
                     delay = self.lower_select_with(use_delay, |_| delay, |_| max_delay);
-                } else {
-                    delay = self.ctx.call1(CallBackKind::StoreDelayTime(eq1), &[delay]);
                 }
+                self.lower_absdelay(arg, delay)
+            }
+            BuiltIn::slew | BuiltIn::transition if self.ctx.no_equations => {
+                self.lower_expr(args[0])
+            }
+            BuiltIn::transition => {
+                let val = self.lower_expr(args[0]);
+                let delay = args.get(1).map_or(F_ZERO, |arg| self.lower_expr(*arg));
+                let rise = args.get(2).map_or(F_ZERO, |arg| self.lower_expr(*arg));
+                let fall = args.get(3).map_or(rise, |arg| self.lower_expr(*arg));
+                // the time tolerance is ignored, breakpoints are always placed exactly
+                self.ctx.transition(val, delay, rise, fall)
+            }
+            BuiltIn::slew if signature == SLEW_NO_MAX => self.lower_expr(args[0]),
+            BuiltIn::slew => {
+                let val = self.lower_expr(args[0]);
+                let pos_rate = self.lower_expr(args[1]);
+                let neg_rate = match args.get(2) {
+                    Some(arg) => self.lower_expr(*arg),
+                    None => self.ctx.ins().fneg(pos_rate),
+                };
+                self.ctx.slew(val, pos_rate, neg_rate)
+            }
+            BuiltIn::limit => self.lower_expr(args[0]),
 
-                let mut resist_val = self.ctx.ins().fsub(res, arg);
-                resist_val = self.ctx.ins().fdiv(resist_val, delay);
-                self.ctx.def_resist_residual(resist_val, eq1);
-                self.ctx.def_react_residual(intermediate, eq1);
-
-                let mut resist_val = self.ctx.ins().fsub(res, intermediate);
-                resist_val = self.ctx.ins().fdiv(resist_val, delay);
-                self.ctx.def_resist_residual(resist_val, eq2);
-                let react_val = self.ctx.ins().fdiv(res, F_THREE);
-                self.ctx.def_react_residual(react_val, eq2);
+            BuiltIn::laplace_nd
+            | BuiltIn::laplace_np
+            | BuiltIn::laplace_zd
+            | BuiltIn::laplace_zp => {
+                let arg = self.lower_expr(args[0]);
+                // the tolerance is ignored
+                let [num, den] = self.lower_transfer_function(builtin, &args[1..3]);
+                if self.ctx.no_equations {
+                    // without equations only the dc gain N(0)/D(0) can be represented
+                    let res = self.ctx.ins().fmul(arg, num[0]);
+                    self.ctx.ins().fdiv(res, den[0])
+                } else {
+                    self.lower_laplace(arg, &num, &den)
+                }
+            }
+            BuiltIn::zi_nd | BuiltIn::zi_np | BuiltIn::zi_zd | BuiltIn::zi_zp => {
+                let arg = self.lower_expr(args[0]);
+                let [num, den] = self.lower_transfer_function(builtin, &args[1..3]);
+                let period = self.lower_expr(args[3]);
+                let start = args.get(5).map_or(F_ZERO, |arg| self.lower_expr(*arg));
+                let res = self.ctx.zi(builtin, arg, &num, &den, period, start);
+                match args.get(4) {
+                    Some(&rise_fall) if !self.ctx.no_equations => {
+                        let rise_fall = self.lower_expr(rise_fall);
+                        self.ctx.transition(res, F_ZERO, rise_fall, rise_fall)
+                    }
+                    _ => res,
+                }
+            }
 
-                res
-            }*/
-            BuiltIn::slew | BuiltIn::transition | BuiltIn::limit | BuiltIn::absdelay => {
-                self.lower_expr(args[0])
+            BuiltIn::cross | BuiltIn::above | BuiltIn::timer if self.ctx.no_equations => FALSE,
+            BuiltIn::cross => {
+                let val = self.lower_expr(args[0]);
+                let dir = args.get(1).map_or(ZERO, |arg| self.lower_expr(*arg));
+                let time_tol = args.get(2).map(|arg| self.lower_expr(*arg));
+                let expr_tol = args.get(3).map(|arg| self.lower_expr(*arg));
+                self.ctx.cross(BuiltIn::cross, val, dir, time_tol, expr_tol)
+            }
+            BuiltIn::above => {
+                let val = self.lower_expr(args[0]);
+                let dir = self.ctx.iconst(1);
+                let time_tol = args.get(1).map(|arg| self.lower_expr(*arg));
+                let expr_tol = args.get(2).map(|arg| self.lower_expr(*arg));
+                self.ctx.cross(BuiltIn::above, val, dir, time_tol, expr_tol)
+            }
+            BuiltIn::timer => {
+                let start = self.lower_expr(args[0]);
+                let period = args.get(1).map(|arg| self.lower_expr(*arg));
+                let tol = args.get(2).map_or(F_ZERO, |arg| self.lower_expr(*arg));
+                self.ctx.timer(start, period, tol)
             }
 
             _ => unreachable!(),
@@ -767,6 +918,214 @@ impl BodyLoweringCtx<'_, '_, '_> {
         val
     }
 
+    /// Approximates `absdelay(arg, delay)` with a second order pade approximation
+    /// `y = x * (1 - sT/2 + (sT)^2/12) / (1 + sT/2 + (sT)^2/12)`. The transfer function is
+    /// realized with two implicit equations:
+    ///
+    /// ```text
+    /// 0 = u + v/2 - x + ddt(T*v/12)
+    /// 0 = v - ddt(T*u)
+    /// y = x - v
+    /// ```
+    ///
+    /// Simulators that support a real delay line can recognize these equations by their
+    /// [`ImplicitEquationKind::Absdelay`] kind and replace them.
+    /// Implements `absdelay` with four implicit equations (see [`AbsdelayEquations`]).
+    /// The output is the delayed input provided by the simulator if it sets the
+    /// `EXACT_ABSDELAY` flag and a second order pade approximation otherwise.
+    fn lower_absdelay(&mut self, arg: Value, delay: Value) -> Value {
+        let absdelay = self.ctx.intern.absdelays.next_key();
+        let (eq_input, input) = self.ctx.implicit_equation(ImplicitEquationKind::Absdelay);
+        let resist = self.ctx.ins().fsub(input, arg);
+        self.ctx.def_resist_residual(resist, eq_input);
+
+        let (eq_u, u) = self.ctx.implicit_equation(ImplicitEquationKind::Absdelay);
+        let (eq_v, v) = self.ctx.implicit_equation(ImplicitEquationKind::Absdelay);
+
+        let half_v = self.ctx.ins().fdiv(v, F_TWO);
+        let mut resist = self.ctx.ins().fadd(u, half_v);
+        resist = self.ctx.ins().fsub(resist, input);
+        let twelve = self.ctx.fconst(12.0);
+        let mut react = self.ctx.ins().fmul(delay, v);
+        react = self.ctx.ins().fdiv(react, twelve);
+        self.ctx.def_resist_residual(resist, eq_u);
+        self.ctx.def_react_residual(react, eq_u);
+
+        let mut react = self.ctx.ins().fmul(delay, u);
+        react = self.ctx.ins().fneg(react);
+        self.ctx.def_resist_residual(v, eq_v);
+        self.ctx.def_react_residual(react, eq_v);
+
+        let (eq_output, output) = self.ctx.implicit_equation(ImplicitEquationKind::Absdelay);
+        let exact = self.ctx.use_param(ParamKind::ExactAbsdelay);
+        let delayed = self.ctx.make_select(exact, |ctx, exact| {
+            if exact {
+                ctx.use_param(ParamKind::DelayedValue(absdelay))
+            } else {
+                ctx.ins().fsub(input, v)
+            }
+        });
+        let resist = self.ctx.ins().fsub(output, delayed);
+        self.ctx.def_resist_residual(resist, eq_output);
+
+        self.ctx.def_place(PlaceKind::AbsdelayDelay(absdelay), delay);
+        self.ctx.intern.absdelays.push(AbsdelayEquations {
+            input: eq_input,
+            output: eq_output,
+            pade: [eq_u, eq_v],
+        });
+        output
+    }
+
+    /// Lowers the numerator and denominator of a `laplace_*` or `zi_*` filter to polynomial
+    /// coefficients in ascending powers of `s` (or `1/z` respectively). Polynomials specified
+    /// by their roots are expanded.
+    fn lower_transfer_function(&mut self, builtin: BuiltIn, args: &[ExprId]) -> [Vec<Value>; 2] {
+        let roots = match builtin {
+            BuiltIn::laplace_zp | BuiltIn::zi_zp => [true, true],
+            BuiltIn::laplace_zd | BuiltIn::zi_zd => [true, false],
+            BuiltIn::laplace_np | BuiltIn::zi_np => [false, true],
+            _ => [false, false],
+        };
+        let laplace = matches!(
+            builtin,
+            BuiltIn::laplace_nd | BuiltIn::laplace_np | BuiltIn::laplace_zd | BuiltIn::laplace_zp
+        );
+
+        [0, 1].map(|i| {
+            let vals = self.lower_real_array(args[i]);
+            if roots[i] {
+                self.expand_roots(&vals, laplace)
+            } else {
+                vals
+            }
+        })
+    }
+
+    /// Lowers the entries of the array literal `arr` as real values.
+    fn lower_real_array(&mut self, arr: ExprId) -> Vec<Value> {
+        let entries = match self.body.get_expr(arr) {
+            Expr::Array(entries) => entries,
+            _ => unreachable!("filter coefficients must be array literals"),
+        };
+        entries
+            .iter()
+            .map(|&entry| {
+                let val = self.lower_expr(entry);
+                match self.resolved_ty(entry) {
+                    Type::Integer => self.ctx.insert_cast(val, &Type::Integer, &Type::Real),
+                    _ => val,
+                }
+            })
+            .collect()
+    }
+
+    /// Expands the product of the factors `(1 - s/root)` (or `s` if `root` is zero) for
+    /// `laplace` filters and `(1 - root/z)` for `zi` filters into the real coefficients of a
+    /// polynomial. `roots` contains (real, imaginary) pairs so the expansion is carried out
+    /// with complex arithmetic. The imaginary parts cancel for conjugate pairs and are dropped.
+    fn expand_roots(&mut self, roots: &[Value], laplace: bool) -> Vec<Value> {
+        let mut re = vec![F_ONE];
+        let mut im = vec![F_ZERO];
+        for root in roots.chunks_exact(2) {
+            let (root_re, root_im) = (root[0], root[1]);
+            // the factor a + b*x
+            let [a, b_re, b_im] = if laplace {
+                let real_zero = self.ctx.ins().feq(root_re, F_ZERO);
+                let is_zero = self.lower_select_with(
+                    real_zero,
+                    |sel| sel.ctx.ins().feq(root_im, F_ZERO),
+                    |_| FALSE,
+                );
+                self.lower_multi_select(is_zero, |ctx, is_zero| {
+                    if is_zero {
+                        return [F_ZERO, F_ONE, F_ZERO];
+                    }
+                    // -1/root = (-re + i*im) / |root|^2
+                    let re_sq = ctx.ctx.ins().fmul(root_re, root_re);
+                    let im_sq = ctx.ctx.ins().fmul(root_im, root_im);
+                    let norm = ctx.ctx.ins().fadd(re_sq, im_sq);
+                    let b_re = ctx.ctx.ins().fneg(root_re);
+                    let b_re = ctx.ctx.ins().fdiv(b_re, norm);
+                    let b_im = ctx.ctx.ins().fdiv(root_im, norm);
+                    [F_ONE, b_re, b_im]
+                })
+            } else {
+                [F_ONE, self.ctx.ins().fneg(root_re), self.ctx.ins().fneg(root_im)]
+            };
+
+            re.push(F_ZERO);
+            im.push(F_ZERO);
+            for k in (0..re.len()).rev() {
+                let mut new_re = self.ctx.ins().fmul(a, re[k]);
+                let mut new_im = self.ctx.ins().fmul(a, im[k]);
+                if k != 0 {
+                    let (prev_re, prev_im) = (re[k - 1], im[k - 1]);
+                    let val = self.ctx.ins().fmul(b_re, prev_re);
+                    new_re = self.ctx.ins().fadd(new_re, val);
+                    let val = self.ctx.ins().fmul(b_im, prev_im);
+                    new_re = self.ctx.ins().fsub(new_re, val);
+                    let val = self.ctx.ins().fmul(b_re, prev_im);
+                    new_im = self.ctx.ins().fadd(new_im, val);
+                    let val = self.ctx.ins().fmul(b_im, prev_re);
+                    new_im = self.ctx.ins().fadd(new_im, val);
+                }
+                re[k] = new_re;
+                im[k] = new_im;
+            }
+        }
+        re
+    }
+
+    /// Realizes the filter `y = N(s)/D(s) * u` of order `n` with implicit equations for the
+    /// states `x_k = s^k x_0` (controllable canonical form):
+    ///
+    /// ```text
+    /// 0 = sum(d_k * x_k, k < n) - u + ddt(d_n * x_(n-1))
+    /// 0 = x_k - ddt(x_(k-1))                          for 0 < k < n
+    /// y = sum(n_k * x_k, k < n) + n_n * (u - sum(d_k * x_k, k < n)) / d_n
+    /// ```
+    fn lower_laplace(&mut self, arg: Value, num: &[Value], den: &[Value]) -> Value {
+        let order = den.len() - 1;
+        let d_n = den[order];
+        if order == 0 {
+            let res = self.ctx.ins().fmul(arg, num[0]);
+            return self.ctx.ins().fdiv(res, d_n);
+        }
+
+        let (equations, states): (Vec<_>, Vec<_>) =
+            (0..order).map(|_| self.ctx.implicit_equation(ImplicitEquationKind::Laplace)).unzip();
+
+        let mut feedback = F_ZERO;
+        for (&d_k, &x_k) in zip(den, &states) {
+            let val = self.ctx.ins().fmul(d_k, x_k);
+            feedback = self.ctx.ins().fadd(feedback, val);
+        }
+
+        let resist = self.ctx.ins().fsub(feedback, arg);
+        let react = self.ctx.ins().fmul(d_n, states[order - 1]);
+        self.ctx.def_resist_residual(resist, equations[0]);
+        self.ctx.def_react_residual(react, equations[0]);
+        for k in 1..order {
+            let react = self.ctx.ins().fneg(states[k - 1]);
+            self.ctx.def_resist_residual(states[k], equations[k]);
+            self.ctx.def_react_residual(react, equations[k]);
+        }
+
+        let mut res = F_ZERO;
+        for (&n_k, &x_k) in zip(num, &states) {
+            let val = self.ctx.ins().fmul(n_k, x_k);
+            res = self.ctx.ins().fadd(res, val);
+        }
+        if let Some(&n_n) = num.get(order) {
+            let highest = self.ctx.ins().fsub(arg, feedback);
+            let highest = self.ctx.ins().fdiv(highest, d_n);
+            let val = self.ctx.ins().fmul(n_n, highest);
+            res = self.ctx.ins().fadd(res, val);
+        }
+        res
+    }
+
     pub fn resolved_ty(&self, expr: ExprId) -> Type {
         self.body
             .needs_cast(expr)
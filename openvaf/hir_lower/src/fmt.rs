@@ -1,5 +1,6 @@
 use hir::{ExprId, Literal, Type};
-use mir::GRAVESTONE;
+use mir::{Value, GRAVESTONE};
+use stdx::iter::zip;
 
 use crate::body::BodyLoweringCtx;
 use crate::callbacks::CallBackKind;
@@ -36,6 +37,21 @@ pub struct FmtArg {
 
 impl BodyLoweringCtx<'_, '_, '_> {
     pub fn ins_display(&mut self, kind: DisplayKind, newline: bool, args: &[ExprId]) {
+        let (call_args, arg_tys) = self.lower_fmt_args(newline, args);
+        self.ctx.call(CallBackKind::Print { kind, arg_tys }, &call_args);
+    }
+
+    /// Lowers `$fdisplay` and friends, the first argument is the file descriptor.
+    pub fn ins_fdisplay(&mut self, newline: bool, args: &[ExprId]) {
+        let fd = self.lower_expr(args[0]);
+        let (mut call_args, arg_tys) = self.lower_fmt_args(newline, &args[1..]);
+        call_args.insert(0, fd);
+        self.ctx.call(CallBackKind::FilePrint { arg_tys }, &call_args);
+    }
+
+    /// Translates the display arguments into a C format literal (the first returned value)
+    /// and the values it formats.
+    fn lower_fmt_args(&mut self, newline: bool, args: &[ExprId]) -> (Vec<Value>, Box<[FmtArg]>) {
         let mut fmt_lit = String::new();
         let mut call_args = vec![GRAVESTONE];
         let mut arg_tys = Vec::new();
@@ -150,7 +166,74 @@ impl BodyLoweringCtx<'_, '_, '_> {
         }
 
         call_args[0] = self.ctx.sconst(&fmt_lit);
-        self.ctx
-            .call(CallBackKind::Print { kind, arg_tys: arg_tys.into_boxed_slice() }, &call_args);
+        (call_args, arg_tys.into_boxed_slice())
+    }
+
+    /// Lowers `$fscanf(fd, fmt, outputs...)`. The Verilog-A conversions are translated to
+    /// their C counterparts and the matched values are written to the outputs.
+    pub fn ins_fscanf(&mut self, args: &[ExprId]) -> Value {
+        let fd = self.lower_expr(args[0]);
+        let lit = match self.body.as_literal(args[1]) {
+            Some(Literal::String(lit)) => lit,
+            _ => unreachable!("$fscanf requires a literal format string"),
+        };
+
+        let mut fmt_lit = String::with_capacity(lit.len());
+        let mut arg_tys = Vec::new();
+        let mut chars = lit.chars();
+        while let Some(c) = chars.next() {
+            fmt_lit.push(c);
+            if c != '%' {
+                continue;
+            }
+
+            let mut c = chars.next().unwrap();
+            let suppress = c == '*';
+            while matches!(c, '*' | '0'..='9') {
+                fmt_lit.push(c);
+                c = chars.next().unwrap();
+            }
+
+            let ty = match c {
+                '%' => {
+                    fmt_lit.push('%');
+                    continue;
+                }
+                'd' | 'D' => {
+                    fmt_lit.push('d');
+                    Type::Integer
+                }
+                'h' | 'H' | 'x' | 'X' => {
+                    fmt_lit.push('x');
+                    Type::Integer
+                }
+                'o' | 'O' => {
+                    fmt_lit.push('o');
+                    Type::Integer
+                }
+                _ => {
+                    fmt_lit.push_str("lf");
+                    Type::Real
+                }
+            };
+
+            if !suppress {
+                arg_tys.push(ty);
+            }
+        }
+
+        let outputs = &args[2..2 + arg_tys.len()];
+        let mut call_args = vec![fd, self.ctx.sconst(&fmt_lit)];
+        call_args.extend(outputs.iter().map(|&arg| self.lower_expr(arg)));
+        let inst = self
+            .ctx
+            .call(CallBackKind::FileScan { arg_tys: arg_tys.into_boxed_slice() }, &call_args);
+
+        let results = self.ctx.dfg().inst_results(inst).to_vec();
+        for (&arg, &val) in zip(outputs, &results[1..]) {
+            let dst = self.body.get_expr(arg).as_assignment_lhs();
+            self.lower_assignment(dst, val);
+        }
+        results[0]
     }
 }
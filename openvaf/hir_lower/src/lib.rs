@@ -3,7 +3,8 @@ use std::iter::FilterMap;
 use ahash::{AHashMap, AHashSet};
 use bitset::HybridBitSet;
 use hir::{
-    Branch, BranchWrite, CompilationDB, Module, Node, ParamSysFun, Parameter, Type, Variable,
+    Branch, BranchWrite, BuiltIn, CompilationDB, Module, Node, ParamSysFun, Parameter, Type,
+    Variable,
 };
 use indexmap::IndexMap;
 use lasso::Rodeo;
@@ -15,7 +16,7 @@ use stdx::{impl_debug_display, impl_idx_from};
 use typed_index_collections::TiVec;
 use typed_indexmap::{map, TiMap, TiSet};
 
-pub use callbacks::{CallBackKind, NoiseTable, ParamInfoKind};
+pub use callbacks::{CallBackKind, FileOp, NoiseTable, ParamInfoKind};
 
 use crate::body::BodyLoweringCtx;
 use crate::ctx::LoweringCtx;
@@ -44,6 +45,21 @@ pub enum ImplicitEquationKind {
     Ddt,
     NoiseSrc,
     Idt(IdtKind),
+    /// One of the equations that realize an `absdelay` (see [`AbsdelayEquations`])
+    Absdelay,
+    /// One of the states of the state space realization of a `laplace_*` filter
+    Laplace,
+    /// An `ac_stim` that is not used linearly
+    AcStim,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OpStateKind {
+    /// Internal state of an analog operator or event (like `transition`)
+    Operator(BuiltIn),
+    /// A variable that is assigned by an event and therefore
+    /// retains its value between evaluations
+    Var(Variable),
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
@@ -79,8 +95,11 @@ pub enum ParamKind {
     Abstime,
     EnableIntegration,
     EnableLim,
+    InitialStep,
+    FinalStep,
     PrevState(LimitState),
     NewState(LimitState),
+    PrevOpState(OpState),
     Voltage { hi: Node, lo: Option<Node> },
     Current(CurrentKind),
     Temperature,
@@ -89,6 +108,10 @@ pub enum ParamKind {
     ParamSysFun(ParamSysFun),
     HiddenState(Variable),
     ImplicitUnknown(ImplicitEquation),
+    // the simulator provides the delayed values of all `absdelay` operators
+    ExactAbsdelay,
+    // the input of an `absdelay` at `$abstime - delay` (provided by the simulator)
+    DelayedValue(Absdelay),
 }
 
 impl ParamKind {
@@ -110,7 +133,12 @@ impl ParamKind {
                 | ParamKind::HiddenState(_)
                 | ParamKind::PrevState(_)
                 | ParamKind::NewState(_)
+                | ParamKind::PrevOpState(_)
                 | ParamKind::EnableLim
+                | ParamKind::InitialStep
+                | ParamKind::FinalStep
+                | ParamKind::ExactAbsdelay
+                | ParamKind::DelayedValue(_)
         )
     }
 }
@@ -153,6 +181,12 @@ impl IdtKind {
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum PlaceKind {
     Var(Variable),
+    /// An element of an array variable. Arrays are scalarized
+    /// so that each element is an independent place.
+    ArrayElement {
+        var: Variable,
+        pos: u32,
+    },
     FunctionReturn(hir::Function),
     FunctionArg(hir::FunctionArg),
     Contribute {
@@ -171,18 +205,29 @@ pub enum PlaceKind {
     ParamMin(Parameter),
     ParamMax(Parameter),
     BoundStep,
+    /// Internal state of an analog operator (like `transition`) that is
+    /// kept between evaluations
+    OpState(OpState),
+    /// The next point in time the simulator must not step over
+    Breakpoint,
+    /// The delay of an `absdelay` that is passed to the simulator
+    AbsdelayDelay(Absdelay),
 }
 
 impl PlaceKind {
     pub fn ty(&self, db: &CompilationDB) -> Type {
         match *self {
             PlaceKind::Var(var) => var.ty(db),
+            PlaceKind::ArrayElement { var, .. } => var.ty(db).base_type().clone(),
             PlaceKind::FunctionReturn(fun) => fun.return_ty(db),
             PlaceKind::FunctionArg(arg) => arg.ty(db),
 
             PlaceKind::ImplicitResidual { .. }
             | PlaceKind::Contribute { .. }
-            | PlaceKind::BoundStep => Type::Real,
+            | PlaceKind::BoundStep
+            | PlaceKind::OpState(_)
+            | PlaceKind::Breakpoint
+            | PlaceKind::AbsdelayDelay(_) => Type::Real,
             PlaceKind::ParamMin(param) | PlaceKind::ParamMax(param) | PlaceKind::Param(param) => {
                 param.ty(db)
             }
@@ -199,12 +244,24 @@ impl From<hir::AssignmentLhs> for PlaceKind {
     fn from(hir: hir::AssignmentLhs) -> Self {
         match hir {
             hir::AssignmentLhs::Variable(var) => PlaceKind::Var(var),
+            hir::AssignmentLhs::ArrayElement { .. } => {
+                unreachable!("array elements with a dynamic index are not a single place")
+            }
             hir::AssignmentLhs::FunctionReturn(fun) => PlaceKind::FunctionReturn(fun),
             hir::AssignmentLhs::FunctionArg(arg) => PlaceKind::FunctionArg(arg),
         }
     }
 }
 
+/// The index of an array element access
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ArrayIndex {
+    /// The position of the element is known at compile time
+    Const(u32),
+    /// The index is only known at runtime
+    Dynamic(Value),
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ImplicitEquation(u32);
 impl_idx_from!(ImplicitEquation(u32));
@@ -212,6 +269,25 @@ impl_debug_display! {
     match ImplicitEquation {ImplicitEquation(i) => "inode{}", i;}
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Absdelay(u32);
+impl_idx_from!(Absdelay(u32));
+impl_debug_display! {
+    match Absdelay {Absdelay(i) => "absdelay{}", i;}
+}
+
+/// The implicit equations of an `absdelay`. The delayed value is approximated with a second
+/// order pade approximation unless the simulator provides it ([`ParamKind::ExactAbsdelay`]).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct AbsdelayEquations {
+    /// The unknown of this equation is the (undelayed) input
+    pub input: ImplicitEquation,
+    /// The unknown of this equation is the delayed input
+    pub output: ImplicitEquation,
+    /// The states of the pade approximation
+    pub pade: [ImplicitEquation; 2],
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct LimitState(u32);
 impl_idx_from!(LimitState(u32));
@@ -219,6 +295,13 @@ impl_debug_display! {
     match LimitState {LimitState(i) => "lim_state{}", i;}
 }
 
+#[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OpState(u32);
+impl_idx_from!(OpState(u32));
+impl_debug_display! {
+    match OpState {OpState(i) => "op_state{}", i;}
+}
+
 /// A mapping between abstractions used in the MIR and the corresponding
 /// information from the HIR. This allows the MIR to remain independent of the frontend/HIR
 #[derive(Debug, PartialEq, Default, Clone)]
@@ -230,6 +313,8 @@ pub struct HirInterner {
     pub tagged_reads: IndexMap<Value, Variable, ahash::RandomState>,
     pub implicit_equations: TiVec<ImplicitEquation, ImplicitEquationKind>,
     pub lim_state: TiMap<LimitState, Value, Vec<(Value, bool)>>,
+    pub op_states: TiVec<OpState, OpStateKind>,
+    pub absdelays: TiVec<Absdelay, AbsdelayEquations>,
 }
 
 pub type LiveParams<'a> = FilterMap<
@@ -492,10 +577,15 @@ impl<'a> MirBuilder<'a> {
         // ... and normal analog blocks afterwards
         body_ctx.body = analog_body.borrow();
         body_ctx.lower_entry_stmts();
+        // module instances are flattened into the parent module
+        for inst in self.module.instances(self.db) {
+            ctx.lower_instance(inst, &path);
+        }
 
         for var in self.required_vars {
             ctx.dec_place(PlaceKind::Var(var));
         }
+        ctx.store_retained_vars();
         let is_output = self.is_output;
         ctx.intern.outputs = ctx
             .places
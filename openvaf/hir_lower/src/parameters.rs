@@ -91,6 +91,7 @@ impl HirInterner {
                             &[],
                             ConstraintKind::From,
                             ops,
+                            &ty,
                             invalid,
                             exit,
                         );
@@ -100,6 +101,7 @@ impl HirInterner {
                             &[],
                             ConstraintKind::Exclude,
                             ops,
+                            &ty,
                             invalid,
                             exit,
                         );
@@ -117,6 +119,7 @@ impl HirInterner {
                             &[],
                             ConstraintKind::From,
                             ops,
+                            &ty,
                             invalid,
                             exit,
                         );
@@ -126,6 +129,7 @@ impl HirInterner {
                             &[],
                             ConstraintKind::Exclude,
                             ops,
+                            &ty,
                             invalid,
                             exit,
                         );
@@ -287,6 +291,7 @@ impl HirInterner {
                 &precomputed_vals,
                 ConstraintKind::From,
                 ops,
+                &ty,
                 invalid,
                 exit,
             );
@@ -296,6 +301,7 @@ impl HirInterner {
                 &precomputed_vals,
                 ConstraintKind::Exclude,
                 ops,
+                &ty,
                 invalid,
                 exit,
             );
@@ -322,23 +328,27 @@ impl BodyLoweringCtx<'_, '_, '_> {
         precomputed_vals: &[(Value, Value)],
         kind: ConstraintKind,
         ops: CmpOps,
+        ty: &Type,
         invalid: FuncRef,
         global_exit: Block,
     ) {
         let mut exit = None;
+        let mut from_bound = -1;
 
         for (i, bound) in bounds.iter().enumerate() {
             if bound.kind != kind {
                 continue;
             }
 
-            let exit = match exit {
-                Some(exit) => exit,
-                None => {
-                    let bb = self.ctx.create_block();
-                    exit = Some(bb);
-                    bb
+            // every excluded value gets its own error block so that the violated bound can be
+            // reported, a value outside of all from ranges can only be attributed to a bound if
+            // there is exactly one such range
+            let exit = match kind {
+                ConstraintKind::From => {
+                    from_bound = if exit.is_none() { i as i32 } else { -1 };
+                    *exit.get_or_insert_with(|| self.ctx.create_block())
                 }
+                ConstraintKind::Exclude => self.ctx.create_block(),
             };
 
             match bound.val {
@@ -375,13 +385,22 @@ impl BodyLoweringCtx<'_, '_, '_> {
                     self.ctx.switch_to_block(next_bb);
                 }
             }
+
+            if kind == ConstraintKind::Exclude {
+                // error if the value matches
+                let next_bb = self.ctx.current_block();
+                self.ctx.switch_to_block(exit);
+                self.call_invalid(invalid, param_val, ty, i as i32);
+                self.ctx.ins().jump(global_exit);
+                self.ctx.switch_to_block(next_bb);
+            }
         }
 
         match kind {
             ConstraintKind::From => {
                 if let Some(exit) = exit {
                     // error on fallthrough
-                    self.ctx.ins().call(invalid, &[]);
+                    self.call_invalid(invalid, param_val, ty, from_bound);
                     self.ctx.ins().jump(global_exit);
 
                     self.ctx.switch_to_block(exit);
@@ -390,14 +409,17 @@ impl BodyLoweringCtx<'_, '_, '_> {
 
             ConstraintKind::Exclude => {
                 self.ctx.ins().jump(global_exit);
-
-                if let Some(exit) = exit {
-                    // error on fallthrough
-                    self.ctx.switch_to_block(exit);
-                    self.ctx.ins().call(invalid, &[]);
-                    self.ctx.ins().jump(global_exit);
-                }
             }
         }
     }
+
+    fn call_invalid(&mut self, invalid: FuncRef, param_val: Value, ty: &Type, bound: i32) {
+        let val = match ty {
+            Type::Real => param_val,
+            Type::Integer => self.ctx.ins().ifcast(param_val),
+            _ => self.ctx.fconst(f64::NAN),
+        };
+        let bound = self.ctx.iconst(bound);
+        self.ctx.ins().call(invalid, &[val, bound]);
+    }
 }
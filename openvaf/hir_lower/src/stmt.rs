@@ -1,6 +1,9 @@
-use hir::{BranchWrite, Case, CaseCond, ContributeKind, ExprId, Node, Stmt, StmtId, Type};
+use hir::{
+    AssignmentLhs, BranchWrite, Case, CaseCond, ContributeKind, Event, ExprId, GlobalEvent, Node,
+    Stmt, StmtId, Type, Variable,
+};
 use mir::builder::InstBuilder;
-use mir::{Opcode, F_ZERO};
+use mir::{Opcode, Value, FALSE, F_ZERO, TRUE};
 
 use crate::body::BodyLoweringCtx;
 use crate::{CallBackKind, CurrentKind, ParamKind, PlaceKind};
@@ -17,13 +20,10 @@ impl BodyLoweringCtx<'_, '_, '_> {
             Stmt::Expr(expr) => {
                 self.lower_expr(expr);
             }
-            Stmt::EventControl { body, .. } => {
-                // TODO handle porperly
-                self.lower_stmt(body);
-            }
+            Stmt::EventControl { events, body } => self.lower_event_control(events, body),
             Stmt::Assignment { lhs, rhs } => {
                 let val_ = self.lower_expr(rhs);
-                self.ctx.def_place(lhs.into(), val_);
+                self.lower_assignment(lhs, val_);
             }
             Stmt::Contribute { kind, branch, rhs } => {
                 self.contribute(kind == ContributeKind::Potential, branch, rhs)
@@ -54,6 +54,119 @@ impl BodyLoweringCtx<'_, '_, '_> {
         }
     }
 
+    fn lower_event_control(&mut self, events: &[Event], body: StmtId) {
+        let mut detected = FALSE;
+        let mut global = FALSE;
+        for event in events {
+            match *event {
+                Event::Global { kind, ref phases } => {
+                    let active = self.lower_global_event(kind, phases);
+                    global =
+                        self.ctx.make_select(global, |_, prev| if prev { TRUE } else { active });
+                }
+                Event::Monitored(expr) => {
+                    // all events must be evaluated so their state is updated
+                    let val = self.lower_expr(expr);
+                    detected =
+                        self.ctx.make_select(detected, |_, prev| if prev { TRUE } else { val });
+                }
+            }
+        }
+
+        self.ctx.make_cond(detected, |ctx, detected| {
+            if detected {
+                ctx.call(CallBackKind::EventDetected, &[]);
+            }
+        });
+
+        let triggered =
+            self.ctx.make_select(detected, |_, detected| if detected { TRUE } else { global });
+        self.ctx.make_cond(triggered, |ctx, triggered| {
+            if triggered {
+                BodyLoweringCtx { body: self.body, path: self.path, ctx }.lower_stmt(body);
+            }
+        });
+    }
+
+    /// Collects all variables that are assigned within the body of an event control
+    pub(crate) fn collect_event_writes(
+        &self,
+        stmt: StmtId,
+        in_event: bool,
+        dst: &mut Vec<Variable>,
+    ) {
+        // TODO(msrv): let .. else
+        let stmt = if let Some(stmt) = self.body.get_stmt(stmt) {
+            stmt
+        } else {
+            return;
+        };
+        match stmt {
+            Stmt::Assignment {
+                lhs: AssignmentLhs::Variable(var) | AssignmentLhs::ArrayElement { var, .. },
+                ..
+            } if in_event => dst.push(var),
+            Stmt::EventControl { body, .. } => self.collect_event_writes(body, true, dst),
+            Stmt::Block { body } => {
+                for stmt in body {
+                    self.collect_event_writes(*stmt, in_event, dst)
+                }
+            }
+            Stmt::If { then_branch, else_branch, .. } => {
+                self.collect_event_writes(then_branch, in_event, dst);
+                self.collect_event_writes(else_branch, in_event, dst);
+            }
+            Stmt::ForLoop { init, incr, body, .. } => {
+                self.collect_event_writes(init, in_event, dst);
+                self.collect_event_writes(incr, in_event, dst);
+                self.collect_event_writes(body, in_event, dst);
+            }
+            Stmt::WhileLoop { body, .. } => self.collect_event_writes(body, in_event, dst),
+            Stmt::Case { case_arms, .. } => {
+                for arm in case_arms {
+                    self.collect_event_writes(arm.body, in_event, dst)
+                }
+            }
+            Stmt::Expr(_) | Stmt::Assignment { .. } | Stmt::Contribute { .. } => (),
+        }
+    }
+
+    /// `initial_step`/`final_step` are active when the simulator signals the
+    /// corresponding step and (if any phases are listed) one of the phases is running
+    fn lower_global_event(&mut self, kind: GlobalEvent, phases: &[String]) -> Value {
+        let step = match kind {
+            GlobalEvent::InitialStep => ParamKind::InitialStep,
+            GlobalEvent::FinalStep => ParamKind::FinalStep,
+        };
+        let step = self.ctx.use_param(step);
+        if phases.is_empty() {
+            return step;
+        }
+
+        self.ctx.make_select(step, |ctx, step| {
+            if !step {
+                return FALSE;
+            }
+            let mut active = FALSE;
+            for phase in phases {
+                let phase = ctx.sconst(phase);
+                let is_phase = ctx.call1(CallBackKind::Analysis, &[phase]);
+                let is_phase = ctx.insert_cast(is_phase, &Type::Integer, &Type::Bool);
+                active = ctx.make_select(active, |_, prev| if prev { TRUE } else { is_phase });
+            }
+            active
+        })
+    }
+
+    pub(crate) fn lower_assignment(&mut self, lhs: AssignmentLhs, val: Value) {
+        if let AssignmentLhs::ArrayElement { var, index } = lhs {
+            let index = self.lower_array_index(var, index);
+            self.ctx.write_array_element(var, index, val)
+        } else {
+            self.ctx.def_place(lhs.into(), val)
+        }
+    }
+
     fn lower_case(&mut self, discr: ExprId, case_arms: &[Case]) {
         let discr_op = match self.body.expr_type(discr) {
             Type::Real => Opcode::Feq,
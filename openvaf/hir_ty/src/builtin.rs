@@ -72,6 +72,11 @@ impl BuiltinInfo {
     }
 }
 
+/// The overloads of a builtin function. Empty for builtins that are type checked by hand.
+pub fn signatures(builtin: BuiltIn) -> &'static [SignatureData] {
+    builtin_info(builtin).signatures
+}
+
 impl From<BuiltIn> for BuiltinInfo {
     fn from(builtin: BuiltIn) -> Self {
         builtin_info(builtin)
@@ -263,11 +268,11 @@ bultins! {
 
 
     TRANSITION = const {
-        fn TRANSITION_NO_ARGS(Val(Integer)) -> Real;
-        fn TRANSITION_DELAY(Val(Integer),Val(Real)) -> Real;
-        fn TRANSITION_DELAY_RISET(Val(Integer),Val(Real)) -> Real;
-        fn TRANSITION_DELAY_RISET_FALLT(Val(Integer),Val(Real),Val(Real)) -> Real;
-        fn TRANSITION_DELAY_RISET_FALLT_TOL(Val(Integer),Val(Real),Val(Real), Val(Real)) -> Real;
+        fn TRANSITION_NO_ARGS(Val(Real)) -> Real;
+        fn TRANSITION_DELAY(Val(Real),Val(Real)) -> Real;
+        fn TRANSITION_DELAY_RISET(Val(Real),Val(Real),Val(Real)) -> Real;
+        fn TRANSITION_DELAY_RISET_FALLT(Val(Real),Val(Real),Val(Real),Val(Real)) -> Real;
+        fn TRANSITION_DELAY_RISET_FALLT_TOL(Val(Real),Val(Real),Val(Real),Val(Real),Val(Real)) -> Real;
     }
 
 
@@ -291,7 +296,7 @@ bultins! {
         fn FFLUSH_DESCRIPTOR(Val(Integer)) -> Integer;
     }
 
-    const fn FERROR(Val(Integer),Var(String)) -> Integer;
+    fn FERROR(Val(Integer),Var(String)) -> Integer;
 
     FINISH = {
         fn FINISH_ONE() -> Void;
@@ -368,6 +373,25 @@ bultins! {
     }
 
     fn BOUND_STEP(Val(Real)) -> Void;
+
+    CROSS = const {
+        fn CROSS_NO_DIRECTION(Val(Real)) -> Bool;
+        fn CROSS_DIRECTION(Val(Real),Val(Integer)) -> Bool;
+        fn CROSS_DIRECTION_TIME_TOL(Val(Real),Val(Integer),Val(Real)) -> Bool;
+        fn CROSS_DIRECTION_TIME_TOL_EXPR_TOL(Val(Real),Val(Integer),Val(Real),Val(Real)) -> Bool;
+    }
+
+    ABOVE = const {
+        fn ABOVE_NO_TOL(Val(Real)) -> Bool;
+        fn ABOVE_TIME_TOL(Val(Real),Val(Real)) -> Bool;
+        fn ABOVE_TIME_TOL_EXPR_TOL(Val(Real),Val(Real),Val(Real)) -> Bool;
+    }
+
+    TIMER = const {
+        fn TIMER_START(Val(Real)) -> Bool;
+        fn TIMER_START_PERIOD(Val(Real),Val(Real)) -> Bool;
+        fn TIMER_START_PERIOD_TOL(Val(Real),Val(Real),Val(Real)) -> Bool;
+    }
 }
 
 // TODO TABLE_MODEL
@@ -399,6 +423,13 @@ const FDISPLAY_FUN: BuiltinInfo = BuiltinInfo::varargs(
     &[SignatureData { args: Cow::Borrowed(&[Val(Integer)]), return_ty: Type::Void }],
     true,
 );
+const FSCANF: BuiltinInfo = BuiltinInfo::varargs(
+    &[SignatureData {
+        args: Cow::Borrowed(&[Val(Integer), Literal(String)]),
+        return_ty: Type::Integer,
+    }],
+    true,
+);
 const SWRITE: BuiltinInfo = BuiltinInfo::varargs(
     &[SignatureData { args: Cow::Borrowed(&[Var(String)]), return_ty: Type::Void }],
     true,
@@ -475,7 +506,6 @@ copied_builtins! {
     FWRITE = FDISPLAY_FUN
     FDEBUG = FDISPLAY_FUN
     SSCANF = FDISPLAY_FUN
-    FSCANF = FDISPLAY_FUN
 
     REWIND = BASIC_IO
     FEOF = BASIC_IO
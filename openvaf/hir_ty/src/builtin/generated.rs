@@ -4,7 +4,7 @@ use hir_def::BuiltIn;
 
 use crate::builtin::*;
 
-const BUILTIN_INFO: [BuiltinInfo; 111usize] = [
+const BUILTIN_INFO: [BuiltinInfo; 114usize] = [
     ABS,
     ACOS,
     ACOSH,
@@ -116,5 +116,8 @@ const BUILTIN_INFO: [BuiltinInfo; 111usize] = [
     LAST_CROSSING,
     SLEW,
     TRANSITION,
+    CROSS,
+    ABOVE,
+    TIMER,
 ];
 pub(crate) fn builtin_info(builtin: BuiltIn) -> BuiltinInfo { BUILTIN_INFO[builtin as u8 as usize] }
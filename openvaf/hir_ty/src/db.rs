@@ -3,13 +3,14 @@ use std::sync::Arc;
 use hir_def::db::HirDefDB;
 use hir_def::nameres::{ResolvedPath, ScopeDefItem};
 use hir_def::{
-    AliasParamId, BranchId, DefWithBodyId, DisciplineId, Lookup, NatureAttrId, NatureId, NodeId,
-    ParamId, ParamSysFun, Type,
+    AliasParamId, BranchId, DefWithBodyId, DisciplineId, ExprId, Lookup, ModuleInstId,
+    NatureAttrId, NatureId, NodeId, ParamId, ParamSysFun, Type,
 };
-use stdx::Upcast;
+use stdx::{Ieee64, Upcast};
 
 use crate::inference::InferenceResult;
-use crate::lower::{BranchTy, DisciplineTy, NatureTy};
+use crate::lower::{BranchTy, DisciplineTy, ModuleInstTy, NatureTy};
+use crate::noise_table::{noise_table_query, NoiseTableError};
 
 #[derive(Debug, PartialEq, Eq, Clone, Hash)]
 pub struct LimitSignature {
@@ -26,10 +27,19 @@ pub trait HirTyDB: HirDefDB + Upcast<dyn HirDefDB> {
     fn discipline_info(&self, nature: DisciplineId) -> Arc<DisciplineTy>;
     #[salsa::invoke(BranchTy::branch_info_query)]
     fn branch_info(&self, branch: BranchId) -> Option<Arc<BranchTy>>;
+    #[salsa::invoke(ModuleInstTy::module_inst_info_query)]
+    fn module_inst_info(&self, inst: ModuleInstId) -> Option<Arc<ModuleInstTy>>;
 
     #[salsa::invoke(InferenceResult::infere_body_query)]
     fn inference_result(&self, id: DefWithBodyId) -> Arc<InferenceResult>;
 
+    #[salsa::invoke(noise_table_query)]
+    fn noise_table(
+        &self,
+        id: DefWithBodyId,
+        call: ExprId,
+    ) -> Result<Arc<[(Ieee64, Ieee64)]>, NoiseTableError>;
+
     #[salsa::cycle(nature_attr_ty_recover)]
     fn nature_attr_ty(&self, id: NatureAttrId) -> Option<Type>;
 
@@ -14,7 +14,7 @@ use stdx::iter::zip;
 use stdx::pretty::List;
 use stdx::{impl_display, pretty};
 use syntax::ast::{self, AssignOp};
-use syntax::sourcemap::{FileSpan, SourceMap};
+use syntax::sourcemap::{CtxSpan, FileSpan, SourceMap};
 use syntax::{Parse, SourceFile, TextSize};
 use typed_index_collections::TiSlice;
 
@@ -275,6 +275,41 @@ impl Diagnostic for InferenceDiagnosticWrapped<'_> {
                         "help: expected one of the following\nbranch current access: I(branch), I(a,b)\nnode voltage: V(x)\nexplicit voltage: V(x,y)\ntemperature: $temperature".to_owned(),
                     ])
             }
+            InferenceDiagnostic::InvalidIndexBase { e, ref found_ty } => {
+                let src = self
+                    .parse
+                    .to_file_span(self.body_sm.expr_map_back[e].as_ref().unwrap().range(), self.sm);
+
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: "expected an array variable".to_owned(),
+                    }])
+                    .with_message(format!("cannot index into {}", found_ty))
+                    .with_notes(vec!["help: only array variables can be indexed".to_owned()])
+            }
+            InferenceDiagnostic::IndexOutOfBounds { e, index, var } => {
+                let src = self
+                    .parse
+                    .to_file_span(self.body_sm.expr_map_back[e].as_ref().unwrap().range(), self.sm);
+                let var = self.db.var_data(var);
+
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: "index out of bounds".to_owned(),
+                    }])
+                    .with_message(format!(
+                        "index {} is out of bounds for array '{}{}'",
+                        index,
+                        var.name,
+                        var.bounds.unwrap()
+                    ))
+            }
             InferenceDiagnostic::NonStandardUnknown { e, .. } => {
                 let src = self
                     .parse
@@ -485,6 +520,35 @@ impl Diagnostic for InferenceDiagnosticWrapped<'_> {
         }
     }
 
+    fn primary_span(&self, _root_file: FileId, _db: &dyn BaseDB) -> Option<CtxSpan> {
+        let expr_range = |expr: ExprId| self.body_sm.expr_map_back[expr].as_ref().unwrap().range();
+        let range = match *self.diag {
+            InferenceDiagnostic::InvalidAssignDst { e: expr, .. }
+            | InferenceDiagnostic::PathResolveError { expr, .. }
+            | InferenceDiagnostic::ArgCntMismatch { expr, .. }
+            | InferenceDiagnostic::ExpectedProbe { e: expr }
+            | InferenceDiagnostic::InvalidLimitFunction { expr, .. }
+            | InferenceDiagnostic::TypeMismatch(TypeMismatch { expr, .. })
+            | InferenceDiagnostic::DisplayTypeMismatch { err: TypeMismatch { expr, .. }, .. }
+            | InferenceDiagnostic::ArrayTypeMismatch(ArrayTypeMismatch {
+                found_expr: expr, ..
+            })
+            | InferenceDiagnostic::InvalidUnknown { e: expr }
+            | InferenceDiagnostic::InvalidIndexBase { e: expr, .. }
+            | InferenceDiagnostic::IndexOutOfBounds { e: expr, .. }
+            | InferenceDiagnostic::NonStandardUnknown { e: expr, .. } => expr_range(expr),
+            InferenceDiagnostic::SignatureMismatch(ref err) => {
+                expr_range(err.type_mismatches.first()?.expr)
+            }
+            InferenceDiagnostic::MissingFmtArg { fmt_lit, lit_range }
+            | InferenceDiagnostic::InvalidFmtSpecifierChar { fmt_lit, lit_range, .. }
+            | InferenceDiagnostic::InvalidFmtSpecifierEnd { fmt_lit, lit_range } => {
+                lit_range + expr_range(fmt_lit).start() + TextSize::from(1u32)
+            }
+        };
+        Some(self.parse.to_ctx_span(range, self.sm))
+    }
+
     fn lint(&self, _root_file: FileId, _db: &dyn BaseDB) -> Option<(Lint, LintSrc)> {
         if let InferenceDiagnostic::NonStandardUnknown { stmt, .. } = *self.diag {
             Some((non_standard_code, self.body_sm.lint_src(stmt, non_standard_code)))
@@ -6,12 +6,12 @@ use ahash::AHashMap;
 use arena::ArenaMap;
 use hir_def::body::Body;
 use hir_def::db::HirDefDB;
-use hir_def::expr::{CaseCond, Literal};
+use hir_def::expr::{CaseCond, Event, Literal};
 use hir_def::nameres::diagnostics::PathResolveError;
 use hir_def::nameres::{NatureAccess, ResolvedPath, ScopeDefItem, ScopeDefItemKind};
 use hir_def::{
     BranchId, BuiltIn, DefWithBodyId, Expr, ExprId, FunctionArgLoc, FunctionId, LocalFunctionArgId,
-    Lookup, NatureId, NodeId, ParamSysFun, Path, Stmt, StmtId, Type, VarId,
+    Lookup, ModuleInstId, NatureId, NodeId, ParamSysFun, Path, Stmt, StmtId, Type, VarId,
 };
 use stdx::impl_from;
 use stdx::iter::zip;
@@ -25,7 +25,7 @@ use crate::builtin::{
 };
 use crate::db::{Alias, HirTyDB};
 use crate::diagnostics::{ArrayTypeMismatch, SignatureMismatch, TypeMismatch};
-use crate::inference::fmt_parser::parse_real_fmt_spec;
+use crate::inference::fmt_parser::{parse_real_fmt_spec, SCAN_FMT_CANDIDATES};
 use crate::lower::{BranchTy, DisciplineAccess};
 use crate::types::{default_return_ty, BuiltinInfo, Signature, SignatureData, Ty, TyRequirement};
 
@@ -42,6 +42,7 @@ pub enum ResolvedFun {
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
 pub enum AssignDst {
     Var(VarId),
+    ArrayElement { var: VarId, index: ExprId },
     FunVar { fun: FunctionId, arg: Option<LocalFunctionArgId> },
     Flow(BranchWrite),
     Potential(BranchWrite),
@@ -55,12 +56,18 @@ pub enum BranchWrite {
 
 impl AssignDst {
     pub fn ty(&self, db: &dyn HirDefDB) -> Type {
-        if let AssignDst::Var(var) = *self {
-            let var = var.lookup(db);
-            let tree = var.item_tree(db);
-            tree[var.id].ty.clone()
-        } else {
-            Type::Real
+        match *self {
+            AssignDst::Var(var) => {
+                let var = var.lookup(db);
+                let tree = var.item_tree(db);
+                tree[var.id].ty.clone()
+            }
+            AssignDst::ArrayElement { var, .. } => {
+                let var = var.lookup(db);
+                let tree = var.item_tree(db);
+                tree[var.id].ty.base_type().clone()
+            }
+            _ => Type::Real,
         }
     }
 }
@@ -92,7 +99,13 @@ impl InferenceResult {
                     .infere_expr(body.entry_stmts[0], db.param_exprs(param).default)
                     .and_then(|ty| ty.to_value()),
             },
-            DefWithBodyId::VarId(var) => Some(db.var_data(var).ty.clone()),
+            // array variables can not have an initializer so the body always
+            // contains the initial value of each element
+            DefWithBodyId::VarId(var) => Some(db.var_data(var).ty.base_type().clone()),
+            DefWithBodyId::ModuleInstId(inst) => {
+                ctx.infere_module_inst(inst);
+                return Arc::new(ctx.result);
+            }
             _ => None,
         };
 
@@ -116,6 +129,29 @@ struct Ctx<'a> {
 }
 
 impl Ctx<'_> {
+    /// The entry stmts of an instance are the parameter overrides followed by the port
+    /// connections. Overrides are checked against the type of the overwritten parameter
+    /// while each connection must be a net.
+    fn infere_module_inst(&mut self, inst: ModuleInstId) {
+        let info = self.db.module_inst_info(inst);
+        let num_overrides = self.db.module_inst_data(inst).param_overrides.len();
+        let (overrides, connections) = self.body.entry_stmts.split_at(num_overrides);
+
+        for (i, &stmt) in overrides.iter().enumerate() {
+            let param = info.as_ref().and_then(|info| info.param_overrides[i]);
+            self.expr_stmt_ty = param.map(|param| self.db.param_ty(param));
+            self.infere_stmt(stmt);
+        }
+
+        self.expr_stmt_ty = None;
+        for &stmt in connections {
+            let expr = self.body.stmts[stmt].unwrap_expr();
+            if let Some(ty) = self.infere_expr(stmt, expr) {
+                self.expect::<false>(expr, None, ty, Cow::Borrowed(&[TyRequirement::Node]));
+            }
+        }
+    }
+
     pub fn infere_stmt(&mut self, stmt: StmtId) {
         match self.body.stmts[stmt] {
             Stmt::Expr(expr) => {
@@ -130,6 +166,13 @@ impl Ctx<'_> {
                 self.infere_cond(stmt, cond)
             }
 
+            Stmt::EventControl { ref events, .. } => {
+                for event in events {
+                    if let Event::Monitored(expr) = *event {
+                        self.infere_expr(stmt, expr);
+                    }
+                }
+            }
             Stmt::Case { discr, ref case_arms } => {
                 if let Some(ty) = self.infere_expr(stmt, discr) {
                     let req = ty.to_value().map_or(TyRequirement::AnyVal, TyRequirement::Val);
@@ -197,7 +240,24 @@ impl Ctx<'_> {
         let e = self.infere_expr(stmt, expr);
 
         let (dst, ty) = match e? {
+            Ty::Var(Type::Array { .. }, _) => {
+                // arrays can only be assigned element by element
+                self.result.diagnostics.push(InferenceDiagnostic::InvalidAssignDst {
+                    e: expr,
+                    maybe_different_operand: None,
+                    assignment_kind,
+                });
+                return None;
+            }
             Ty::Var(ty, var) => (AssignDst::Var(var), ty),
+            Ty::Val(ty) if matches!(self.body.exprs[expr], Expr::Index { .. }) => {
+                let (base, index) = match self.body.exprs[expr] {
+                    Expr::Index { base, index } => (base, index),
+                    _ => unreachable!(),
+                };
+                let var = self.result.expr_types[base].unwrap_var();
+                (AssignDst::ArrayElement { var, index }, ty)
+            }
             Ty::FunctionVar { fun, ty, arg } => (AssignDst::FunVar { fun, arg }, ty),
             Ty::Val(Type::Real)
                 if matches!(
@@ -248,7 +308,10 @@ impl Ctx<'_> {
 
         // check that the correct operator is used
         match (&dst, assignment_kind) {
-            (AssignDst::Var(_) | AssignDst::FunVar { .. }, ast::AssignOp::Contribute) => {
+            (
+                AssignDst::Var(_) | AssignDst::ArrayElement { .. } | AssignDst::FunVar { .. },
+                ast::AssignOp::Contribute,
+            ) => {
                 self.result.diagnostics.push(InferenceDiagnostic::InvalidAssignDst {
                     e: expr,
                     maybe_different_operand: Some(ast::AssignOp::Assign),
@@ -284,7 +347,9 @@ impl Ctx<'_> {
             }
 
             Expr::Path { ref path, port: false } => match self.resolve_path(stmt, expr, path)? {
-                ScopeDefItem::BlockId(_) | ScopeDefItem::ModuleId(_) => Ty::Scope,
+                ScopeDefItem::BlockId(_)
+                | ScopeDefItem::ModuleId(_)
+                | ScopeDefItem::ModuleInstId(_) => Ty::Scope,
                 ScopeDefItem::NatureId(nature) => Ty::Nature(nature),
                 ScopeDefItem::DisciplineId(discipline) => Ty::Discipline(discipline),
                 ScopeDefItem::NodeId(node) => Ty::Node(node),
@@ -389,6 +454,7 @@ impl Ctx<'_> {
             Expr::Call { ref fun, ref args } => {
                 self.infere_fun_call(stmt, expr, fun.as_ref()?, args)?
             }
+            Expr::Index { base, index } => self.infere_index(stmt, base, index)?,
             Expr::Array(ref args) if args.is_empty() => Ty::Val(Type::EmptyArray),
             Expr::Array(ref args) => self.infere_array(stmt, args)?,
             Expr::Literal(Literal::Float(_)) => Ty::Literal(Type::Real),
@@ -409,6 +475,48 @@ impl Ctx<'_> {
         Some(ty)
     }
 
+    fn infere_index(&mut self, stmt: StmtId, base: ExprId, index: ExprId) -> Option<Ty> {
+        let base_ty = self.infere_expr(stmt, base);
+        if let Some(ty) = self.infere_expr(stmt, index) {
+            self.expect::<false>(
+                index,
+                None,
+                ty,
+                Cow::Borrowed(&[TyRequirement::Val(Type::Integer)]),
+            );
+        }
+
+        match base_ty? {
+            Ty::Var(Type::Array { ty, .. }, var) => {
+                let bounds = self.db.var_data(var).bounds?;
+                let const_index = match self.body.exprs[index] {
+                    Expr::Literal(Literal::Int(val)) => Some(val),
+                    Expr::UnaryOp { expr, op: UnaryOp::Neg } => match self.body.exprs[expr] {
+                        Expr::Literal(Literal::Int(val)) => Some(-val),
+                        _ => None,
+                    },
+                    _ => None,
+                };
+                if let Some(val) = const_index {
+                    if bounds.position(val).is_none() {
+                        self.result.diagnostics.push(InferenceDiagnostic::IndexOutOfBounds {
+                            e: index,
+                            index: val,
+                            var,
+                        });
+                    }
+                }
+                Some(Ty::Val(*ty))
+            }
+            found_ty => {
+                self.result
+                    .diagnostics
+                    .push(InferenceDiagnostic::InvalidIndexBase { e: base, found_ty });
+                None
+            }
+        }
+    }
+
     fn infere_fun_call(
         &mut self,
         stmt: StmtId,
@@ -635,6 +743,12 @@ impl Ctx<'_> {
             | BuiltIn::error
             | BuiltIn::info
             | BuiltIn::fatal => self.infere_display(stmt, args),
+            BuiltIn::fwrite
+            | BuiltIn::fdisplay
+            | BuiltIn::fstrobe
+            | BuiltIn::fmonitor
+            | BuiltIn::fdebug => self.infere_display(stmt, &args[1..]),
+            BuiltIn::fscanf => self.infere_scan(args[1], &args[2..]),
 
             _ => (),
         }
@@ -771,6 +885,93 @@ impl Ctx<'_> {
             }
         }
     }
+
+    /// Checks that the outputs of `$fscanf` are variables that match the conversions
+    /// of the (literal) format string `fmt_expr`.
+    fn infere_scan(&mut self, fmt_expr: ExprId, args: &[ExprId]) {
+        let lit = if let Expr::Literal(Literal::String(ref lit)) = self.body.exprs[fmt_expr] {
+            lit
+        } else {
+            // the signature requires a literal so an error was already reported
+            return;
+        };
+
+        let mut i = 0;
+        let mut chars = lit.char_indices();
+        while let Some((start, c)) = chars.next() {
+            if c != '%' {
+                continue;
+            }
+
+            let mut pos = chars.next();
+            let suppress = matches!(pos, Some((_, '*')));
+            if suppress {
+                pos = chars.next();
+            }
+            while let Some((_, '0'..='9')) = pos {
+                pos = chars.next();
+            }
+
+            let (off, c) = if let Some(pos) = pos {
+                pos
+            } else {
+                self.result.diagnostics.push(InferenceDiagnostic::InvalidFmtSpecifierEnd {
+                    fmt_lit: fmt_expr,
+                    lit_range: TextRange::new(
+                        start.try_into().unwrap(),
+                        lit.len().try_into().unwrap(),
+                    ),
+                });
+                return;
+            };
+            let end: TextSize = (off + c.len_utf8()).try_into().unwrap();
+
+            let ty = match c {
+                '%' => continue,
+                'd' | 'D' | 'h' | 'H' | 'x' | 'X' | 'o' | 'O' => Type::Integer,
+                'e' | 'E' | 'f' | 'F' | 'g' | 'G' => Type::Real,
+                _ => {
+                    self.result.diagnostics.push(InferenceDiagnostic::InvalidFmtSpecifierChar {
+                        fmt_lit: fmt_expr,
+                        lit_range: TextRange::new(off.try_into().unwrap(), end),
+                        err_char: c,
+                        candidates: SCAN_FMT_CANDIDATES,
+                    });
+                    return;
+                }
+            };
+
+            if suppress {
+                continue;
+            }
+
+            let lit_range = TextRange::new(start.try_into().unwrap(), end);
+            let arg = if let Some(&arg) = args.get(i) {
+                arg
+            } else {
+                self.result
+                    .diagnostics
+                    .push(InferenceDiagnostic::MissingFmtArg { fmt_lit: fmt_expr, lit_range });
+                continue;
+            };
+            i += 1;
+
+            match self.result.expr_types[arg] {
+                Ty::Var(ref ty_, _) | Ty::FunctionVar { ty: ref ty_, .. } if *ty_ == ty => (),
+                _ => self.result.diagnostics.push(InferenceDiagnostic::DisplayTypeMismatch {
+                    err: TypeMismatch {
+                        expected: Cow::Owned(vec![TyRequirement::Var(ty)]),
+                        found_ty: self.result.expr_types[arg].clone(),
+                        expr: arg,
+                    },
+                    fmt_lit: fmt_expr,
+                    lit_range,
+                    lint_ctx: None,
+                }),
+            }
+        }
+    }
+
     fn infere_limit(&mut self, stmt: StmtId, expr: ExprId, args: &[ExprId]) {
         let sig = if let Some(sig) = self.result.resolved_signatures.get(&expr) {
             *sig
@@ -967,7 +1168,7 @@ impl Ctx<'_> {
             }
         }
 
-        Some(Ty::Val(ty))
+        Some(Ty::Val(Type::Array { ty: Box::new(ty), len: args.len() as u32 }))
     }
 
     fn infere_bin_op(
@@ -1261,6 +1462,15 @@ pub enum InferenceDiagnostic {
     InvalidUnknown {
         e: ExprId,
     },
+    InvalidIndexBase {
+        e: ExprId,
+        found_ty: Ty,
+    },
+    IndexOutOfBounds {
+        e: ExprId,
+        index: i32,
+        var: VarId,
+    },
     NonStandardUnknown {
         e: ExprId,
         stmt: StmtId,
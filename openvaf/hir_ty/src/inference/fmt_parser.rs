@@ -45,6 +45,12 @@ impl ParserState {
     }
 }
 
+/// Conversions supported by `$fscanf`
+pub const SCAN_FMT_CANDIDATES: &[char] = &[
+    '*', '0', '1', '2', '3', '4', '5', '6', '7', '8', '9', '%', 'd', 'D', 'h', 'H', 'x', 'X', 'o',
+    'O', 'e', 'E', 'f', 'F', 'g', 'G',
+];
+
 pub struct ParseResult {
     pub dynamic_args: Vec<TextSize>,
     pub err: Option<InferenceDiagnostic>,
@@ -3,8 +3,8 @@ use std::sync::Arc;
 use hir_def::nameres::diagnostics::PathResolveError;
 use hir_def::nameres::DefMap;
 use hir_def::{
-    BranchId, DisciplineId, Intern, Lookup, NatureAttrId, NatureAttrLoc, NatureId, NatureRef,
-    NatureRefKind, NodeId,
+    BranchId, DisciplineId, Intern, Lookup, ModuleId, ModuleInstId, NatureAttrId, NatureAttrLoc,
+    NatureId, NatureRef, NatureRefKind, NodeId, ParamId,
 };
 use syntax::name::{kw, Name};
 
@@ -302,3 +302,53 @@ impl BranchTy {
         }
     }
 }
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ModuleInstTy {
+    pub module: ModuleId,
+    /// The parameter of `module` that is set by each override (in the same order as the
+    /// overrides). `None` if the override could not be resolved.
+    pub param_overrides: Vec<Option<ParamId>>,
+    /// The port of `module` that is connected by each connection (in the same order as the
+    /// connections). `None` if the connection could not be resolved.
+    pub port_connections: Vec<Option<NodeId>>,
+}
+
+impl ModuleInstTy {
+    pub fn module_inst_info_query(
+        db: &dyn HirTyDB,
+        inst: ModuleInstId,
+    ) -> Option<Arc<ModuleInstTy>> {
+        let data = db.module_inst_data(inst);
+        let def_map = inst.lookup(db.upcast()).scope.def_map(db.upcast());
+        let module: ModuleId =
+            def_map.resolve_local_item_in_scope(def_map.root(), &data.module).ok()?;
+
+        let module_data = db.module_data(module);
+        let params = &module_data.params;
+        let param_overrides = data
+            .param_overrides
+            .iter()
+            .enumerate()
+            .map(|(i, name)| match name {
+                Some(name) => {
+                    params.iter().copied().find(|param| &db.param_data(*param).name == name)
+                }
+                None => params.get(i).copied(),
+            })
+            .collect();
+
+        let ports = &module_data.ports;
+        let port_connections = data
+            .port_connections
+            .iter()
+            .enumerate()
+            .map(|(i, name)| match name {
+                Some(name) => ports.iter().copied().find(|port| &db.node_data(*port).name == name),
+                None => ports.get(i).copied(),
+            })
+            .collect();
+
+        Some(Arc::new(ModuleInstTy { module, param_overrides, port_connections }))
+    }
+}
@@ -0,0 +1,222 @@
+use std::io;
+use std::sync::Arc;
+
+use arena::Arena;
+use basedb::{FileId, FileReadError};
+use hir_def::{BuiltIn, DefWithBodyId, Expr, ExprId, Literal};
+use stdx::Ieee64;
+use syntax::ast::{self, ArgListOwner, LiteralKind, UnaryOp};
+use syntax::AstNode;
+
+use crate::builtin::{NOISE_TABLE_INLINE, NOISE_TABLE_INLINE_NAME};
+use crate::db::HirTyDB;
+use crate::inference::ResolvedFun;
+
+/// An error encountered while resolving the frequency/power pairs of a
+/// `noise_table` or `noise_table_log` call.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NoiseTableError {
+    /// The vector argument is not an array literal or one of its entries is not a number literal.
+    ExpectedNumberLiteral {
+        expr: ExprId,
+    },
+    /// The file argument is not a string literal.
+    ExpectedFileName {
+        expr: ExprId,
+    },
+    OddLength {
+        expr: ExprId,
+        len: usize,
+    },
+    Empty {
+        expr: ExprId,
+    },
+    FileRead {
+        expr: ExprId,
+        path: String,
+        err: FileReadError,
+    },
+    InvalidLine {
+        expr: ExprId,
+        path: String,
+        line: u32,
+    },
+    InvalidValue {
+        expr: ExprId,
+        val: Ieee64,
+        log: bool,
+    },
+}
+
+impl NoiseTableError {
+    pub fn expr(&self) -> ExprId {
+        match *self {
+            NoiseTableError::ExpectedNumberLiteral { expr }
+            | NoiseTableError::ExpectedFileName { expr }
+            | NoiseTableError::OddLength { expr, .. }
+            | NoiseTableError::Empty { expr }
+            | NoiseTableError::FileRead { expr, .. }
+            | NoiseTableError::InvalidLine { expr, .. }
+            | NoiseTableError::InvalidValue { expr, .. } => expr,
+        }
+    }
+}
+
+/// Resolves the (frequency, power) pairs of the `noise_table`/`noise_table_log` call `call`
+/// in the order they were specified.
+///
+/// Inline tables must be array literals of (optionally negated) number literals.
+/// Tables read from a file contain one pair per line separated by whitespace,
+/// everything after a `#` is a comment. Relative file names are resolved like
+/// `` `include `` directives: first relative to the directory of the root file and
+/// then relative to each include directory.
+pub(crate) fn noise_table_query(
+    db: &dyn HirTyDB,
+    def: DefWithBodyId,
+    call: ExprId,
+) -> Result<Arc<[(Ieee64, Ieee64)]>, NoiseTableError> {
+    let body = db.body(def);
+    let infer = db.inference_result(def);
+    let arg = match body.exprs[call] {
+        Expr::Call { ref args, .. } => args[0],
+        _ => unreachable!("noise table is not a call"),
+    };
+    let log =
+        infer.resolved_calls.get(&call) == Some(&ResolvedFun::BuiltIn(BuiltIn::noise_table_log));
+
+    let vals = match infer.resolved_signatures.get(&call) {
+        Some(&(NOISE_TABLE_INLINE | NOISE_TABLE_INLINE_NAME)) => {
+            let entries = match body.exprs[arg] {
+                Expr::Array(ref entries) => entries,
+                _ => return Err(NoiseTableError::ExpectedNumberLiteral { expr: arg }),
+            };
+            if entries.len() % 2 != 0 {
+                return Err(NoiseTableError::OddLength { expr: arg, len: entries.len() });
+            }
+            let mut vals = Vec::with_capacity(entries.len() / 2);
+            for pair in entries.chunks_exact(2) {
+                let freq = number_literal(&body.exprs, pair[0])?;
+                let pwr = number_literal(&body.exprs, pair[1])?;
+                vals.push((freq, pwr));
+            }
+            vals
+        }
+        _ => {
+            let path = match body.exprs[arg] {
+                Expr::Literal(Literal::String(ref path)) => path,
+                _ => return Err(NoiseTableError::ExpectedFileName { expr: arg }),
+            };
+            read_table_file(db, def, arg, path)?
+        }
+    };
+
+    if vals.is_empty() {
+        return Err(NoiseTableError::Empty { expr: arg });
+    }
+
+    for &(freq, pwr) in &vals {
+        for val in [freq, pwr] {
+            let invalid = if log { val <= 0.0 } else { val < 0.0 };
+            if invalid || !val.is_finite() {
+                return Err(NoiseTableError::InvalidValue { expr: arg, val: val.into(), log });
+            }
+        }
+    }
+
+    Ok(vals.into_iter().map(|(freq, pwr)| (freq.into(), pwr.into())).collect())
+}
+
+fn number_literal(exprs: &Arena<Expr>, expr: ExprId) -> Result<f64, NoiseTableError> {
+    let val = match exprs[expr] {
+        Expr::Literal(Literal::Int(val)) => val as f64,
+        Expr::Literal(Literal::Float(val)) => val.into(),
+        Expr::UnaryOp { expr: arg, op: UnaryOp::Neg } => return Ok(-number_literal(exprs, arg)?),
+        _ => return Err(NoiseTableError::ExpectedNumberLiteral { expr }),
+    };
+    Ok(val)
+}
+
+/// The data files read by the `noise_table`/`noise_table_log` calls of the compilation unit
+/// `root_file` in order of appearance. The calls are found syntactically so that the files are
+/// known without running the frontend (for example when checking whether a cached compilation
+/// result is up to date). Files that can not be read are ignored.
+pub fn noise_table_files(db: &dyn HirTyDB, root_file: FileId) -> Vec<FileId> {
+    let mut files = Vec::new();
+    let parse = db.parse(root_file);
+    for call in parse.tree().syntax().descendants().filter_map(ast::Call::cast) {
+        let is_noise_table = match call.function_ref() {
+            Some(ast::FunctionRef::Path(path)) if path.qualifier().is_none() => path
+                .ident_token()
+                .map_or(false, |name| matches!(name.text(), "noise_table" | "noise_table_log")),
+            _ => false,
+        };
+        if !is_noise_table {
+            continue;
+        }
+        let path = match call.arg_list().and_then(|args| args.args().next()) {
+            Some(ast::Expr::Literal(lit)) => match lit.kind() {
+                LiteralKind::String(lit) => lit.unescaped_value(),
+                _ => continue,
+            },
+            _ => continue,
+        };
+        if let Ok((file, _)) = find_table_file(db, root_file, &path) {
+            if !files.contains(&file) {
+                files.push(file)
+            }
+        }
+    }
+    files
+}
+
+/// Resolves a table file like an `` `include `` directive: first relative to the directory
+/// of the root file and then relative to each include directory.
+fn find_table_file(
+    db: &dyn HirTyDB,
+    root_file: FileId,
+    path: &str,
+) -> Result<(FileId, Arc<str>), FileReadError> {
+    let workdir = db.file_path(root_file).parent();
+    let include_dirs = db.include_dirs(root_file);
+    let candidates = workdir.iter().chain(include_dirs.iter()).filter_map(|dir| dir.join(path));
+
+    for candidate in candidates {
+        let file = db.file_id(candidate);
+        match db.file_text(file) {
+            Ok(contents) => return Ok((file, contents)),
+            Err(FileReadError::Io(io::ErrorKind::NotFound)) => (),
+            Err(err) => return Err(err),
+        }
+    }
+    Err(FileReadError::Io(io::ErrorKind::NotFound))
+}
+
+fn read_table_file(
+    db: &dyn HirTyDB,
+    def: DefWithBodyId,
+    expr: ExprId,
+    path: &str,
+) -> Result<Vec<(f64, f64)>, NoiseTableError> {
+    let root_file = def.file(db.upcast());
+    let (_, text) = find_table_file(db, root_file, path)
+        .map_err(|err| NoiseTableError::FileRead { expr, path: path.to_owned(), err })?;
+
+    let mut vals = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default();
+        let mut entries = line.split_whitespace().map(str::parse::<f64>);
+        let pair = match (entries.next(), entries.next(), entries.next()) {
+            (None, _, _) => continue,
+            (Some(Ok(freq)), Some(Ok(pwr)), None) => (freq, pwr),
+            _ => {
+                return Err(NoiseTableError::InvalidLine {
+                    expr,
+                    path: path.to_owned(),
+                    line: i as u32 + 1,
+                })
+            }
+        };
+        vals.push(pair);
+    }
+    Ok(vals)
+}
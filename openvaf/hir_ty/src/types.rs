@@ -127,6 +127,14 @@ impl Ty {
             unreachable!("expected node found {:?}", self)
         }
     }
+    pub fn unwrap_var(&self) -> VarId {
+        if let Ty::Var(_, id) = *self {
+            id
+        } else {
+            unreachable!("expected variable found {:?}", self)
+        }
+    }
+
     pub fn unwrap_node(&self) -> NodeId {
         if let Ty::Node(id) = *self {
             id
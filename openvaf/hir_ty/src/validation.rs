@@ -1,7 +1,12 @@
+use std::io;
+
 use basedb::diagnostics::{Diagnostic, Label, LabelStyle, Report};
-use basedb::lints::builtin::{const_simparam, trivial_probe, variant_const_simparam};
+use basedb::lints::builtin::{
+    analog_filter_fallback, approximated_absdelay, const_simparam, trivial_probe,
+    variant_const_simparam,
+};
 use basedb::lints::{self, Lint, LintSrc};
-use basedb::{AstIdMap, BaseDB, FileId};
+use basedb::{AstIdMap, BaseDB, FileId, FileReadError};
 pub use body::BodyValidationDiagnostic;
 use hir_def::body::BodySourceMap;
 use hir_def::{
@@ -9,12 +14,13 @@ use hir_def::{
     NodeTypeDecl,
 };
 use syntax::name::Name;
-use syntax::sourcemap::{FileSpan, SourceMap};
+use syntax::sourcemap::{CtxSpan, FileSpan, SourceMap};
 use syntax::{Parse, SourceFile, TextRange};
 pub use types::TypeValidationDiagnostic;
 
 use crate::db::HirTyDB;
 use crate::inference::BranchWrite;
+use crate::noise_table::NoiseTableError;
 use crate::validation::body::{BodyCtx, IllegalCtxAccess, IllegalCtxAccessKind};
 use crate::validation::types::DuplicateItem;
 
@@ -130,10 +136,52 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                 let src = self.body_sm.lint_src(stmt, trivial_probe);
                 Some((trivial_probe, src))
             }
+            BodyValidationDiagnostic::ApproximatedAbsdelay { stmt, .. } => {
+                let src = self.body_sm.lint_src(stmt, approximated_absdelay);
+                Some((approximated_absdelay, src))
+            }
+            BodyValidationDiagnostic::AnalogFilterFallback { stmt, .. } => {
+                let src = self.body_sm.lint_src(stmt, analog_filter_fallback);
+                Some((analog_filter_fallback, src))
+            }
             _ => None,
         }
     }
 
+    fn primary_span(&self, _root_file: FileId, _db: &dyn BaseDB) -> Option<CtxSpan> {
+        let range = match *self.diag {
+            BodyValidationDiagnostic::IllegalContribute { stmt, .. } => {
+                self.body_sm.stmt_map_back[stmt].as_ref()?.range()
+            }
+            BodyValidationDiagnostic::ExpectedPort { expr, .. }
+            | BodyValidationDiagnostic::TrivialBranchAccess { expr, .. }
+            | BodyValidationDiagnostic::PotentialOfPortFlow { expr, .. }
+            | BodyValidationDiagnostic::WriteToInputArg { expr, .. }
+            | BodyValidationDiagnostic::IllegalParamAccess { expr, .. }
+            | BodyValidationDiagnostic::IllegalCtxAccess(IllegalCtxAccess { expr, .. })
+            | BodyValidationDiagnostic::ConstSimparam { expr, .. }
+            | BodyValidationDiagnostic::UnsupportedFunction { expr, .. }
+            | BodyValidationDiagnostic::ApproximatedAbsdelay { expr, .. }
+            | BodyValidationDiagnostic::AnalogFilterFallback { expr, .. }
+            | BodyValidationDiagnostic::ExpectedEvent { expr }
+            | BodyValidationDiagnostic::ExpectedArrayLiteral { expr }
+            | BodyValidationDiagnostic::UnsupportedArrayLiteral { expr }
+            | BodyValidationDiagnostic::NonLiteralAnalysisName { expr }
+            | BodyValidationDiagnostic::EmptyFilterCoefficients { expr }
+            | BodyValidationDiagnostic::OddRootCount { expr, .. }
+            | BodyValidationDiagnostic::ImproperTransferFunction { expr, .. }
+            | BodyValidationDiagnostic::IncompatibleNatureAccess { access_expr: expr, .. }
+            | BodyValidationDiagnostic::IllegalNatureAccess { access_expr: expr, .. }
+            | BodyValidationDiagnostic::IncompatibleImplicitBranch { access: expr, .. } => {
+                self.body_sm.expr_map_back[expr].as_ref()?.range()
+            }
+            BodyValidationDiagnostic::InvalidNoiseTable(ref err) => {
+                self.body_sm.expr_map_back[err.expr()].as_ref()?.range()
+            }
+        };
+        Some(self.parse.to_ctx_span(range, self.sm))
+    }
+
     fn build_report(&self, _root_file: basedb::FileId, _db: &dyn basedb::BaseDB) -> Report {
         match *self.diag {
             BodyValidationDiagnostic::ExpectedPort { expr, node } => {
@@ -337,6 +385,11 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
                         "analysis function '{}' is not allowed in constants",
                         name
                     )),
+                    IllegalCtxAccessKind::Event { name } => res
+                        .with_message(format!("event '{}' is not allowed in {}", name, ctx))
+                        .with_notes(vec![
+                            "help: events may only be used in an event control '@(...)' of the main analog block".to_owned(),
+                        ]),
                     IllegalCtxAccessKind::Var(var) => {
                         let name = var.lookup(self.db.upcast()).name(self.db.upcast());
                         let def = var.lookup(self.db.upcast()).ast_ptr(self.db.upcast()).range();
@@ -377,6 +430,182 @@ impl Diagnostic for BodyValidationDiagnosticWrapped<'_> {
 
                 res
             }
+            BodyValidationDiagnostic::ApproximatedAbsdelay { expr, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message("absdelay is approximated unless the simulator provides the delayed values")
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "possibly approximated delay".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: simulators without support for the 'absdelays' OSDI descriptor entry use a second order pade approximation".to_owned(),
+                        "help: the approximation is only accurate for signals that change slowly compared to the delay".to_owned(),
+                        "info: simulators that provide the delayed values (like melange) allow this lint".to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::AnalogFilterFallback { expr, .. } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::warning()
+                    .with_message("the time tolerance of transition is ignored")
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "time tolerance ignored".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: breakpoints are placed exactly at the corners of the transition instead".to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::ExpectedEvent { expr } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_message("expected an event".to_owned())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "expected an event".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: supported events are 'initial_step', 'final_step', 'cross', 'above' and 'timer'".to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::InvalidNoiseTable(ref err) => {
+                let FileSpan { range, file } = self.expr_src(err.expr());
+                let (message, label) = match *err {
+                    NoiseTableError::ExpectedNumberLiteral { .. } => (
+                        "noise table entries must be number literals".to_owned(),
+                        "not a number literal",
+                    ),
+                    NoiseTableError::ExpectedFileName { .. } => (
+                        "noise table file name must be a string literal".to_owned(),
+                        "expected a string literal",
+                    ),
+                    NoiseTableError::OddLength { len, .. } => (
+                        format!("noise table has an odd number of entries ({len})"),
+                        "expected (frequency, power) pairs",
+                    ),
+                    NoiseTableError::Empty { .. } => (
+                        "noise table is empty".to_owned(),
+                        "expected at least one (frequency, power) pair",
+                    ),
+                    NoiseTableError::FileRead { ref path, err: FileReadError::Io(err), .. } => (
+                        format!("failed to read noise table '{path}': {}", io::Error::from(err)),
+                        "failed to read file",
+                    ),
+                    NoiseTableError::FileRead { ref path, .. } => (
+                        format!("failed to read noise table '{path}': file contents are not valid text"),
+                        "failed to read file",
+                    ),
+                    NoiseTableError::InvalidLine { ref path, line, .. } => (
+                        format!("invalid entry in noise table '{path}' at line {line}"),
+                        "noise table read here",
+                    ),
+                    NoiseTableError::InvalidValue { val, log: true, .. } => (
+                        format!("noise_table_log requires positive frequencies and powers but found {:?}", f64::from(val)),
+                        "invalid noise table",
+                    ),
+                    NoiseTableError::InvalidValue { val, log: false, .. } => (
+                        format!("noise table frequencies and powers must not be negative but found {:?}", f64::from(val)),
+                        "invalid noise table",
+                    ),
+                };
+                let mut res = Report::error().with_message(message).with_labels(vec![Label {
+                    style: LabelStyle::Primary,
+                    file_id: file,
+                    range: range.into(),
+                    message: label.to_owned(),
+                }]);
+                if let NoiseTableError::InvalidLine { .. } = err {
+                    res = res.with_notes(vec![
+                        "help: each line must contain a frequency and a power separated by whitespace, '#' starts a comment".to_owned(),
+                    ]);
+                }
+                res
+            }
+            BodyValidationDiagnostic::ExpectedArrayLiteral { expr } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_message("filter coefficients must be array literals".to_owned())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "expected an array literal".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: the order of the filter must be known at compile time, for example '{1.0, tau}".to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::UnsupportedArrayLiteral { expr } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_message("array literals are not supported here".to_owned())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "unsupported array literal".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: array literals can only be passed to noise_table, noise_table_log, laplace_* and zi_* filters".to_owned(),
+                    ])
+            }
+            BodyValidationDiagnostic::NonLiteralAnalysisName { expr } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_message(
+                        "the analysis name of 'ac_stim' must be a string literal".to_owned(),
+                    )
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "expected a string literal".to_owned(),
+                    }])
+            }
+            BodyValidationDiagnostic::EmptyFilterCoefficients { expr } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_message("filter coefficients must not be empty".to_owned())
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "expected at least one coefficient".to_owned(),
+                    }])
+            }
+            BodyValidationDiagnostic::OddRootCount { expr, len } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_message(format!("filter roots have an odd number of entries ({len})"))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "expected (real, imaginary) pairs".to_owned(),
+                    }])
+            }
+            BodyValidationDiagnostic::ImproperTransferFunction { expr, num_order, den_order } => {
+                let FileSpan { range, file } = self.expr_src(expr);
+                Report::error()
+                    .with_message(format!(
+                        "laplace filter is not proper: numerator has order {num_order} but denominator has order {den_order}"
+                    ))
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: file,
+                        range: range.into(),
+                        message: "improper transfer function".to_owned(),
+                    }])
+                    .with_notes(vec![
+                        "help: the order of the numerator must not exceed the order of the denominator".to_owned(),
+                    ])
+            }
             BodyValidationDiagnostic::UnsupportedFunction { expr, func } => {
                 let FileSpan { range, file } = self.expr_src(expr);
 
@@ -714,6 +943,50 @@ impl Diagnostic for TypeValidationDiagnosticWrapped<'_> {
                 }
                 .into_report(self.db, self.parse, self.map, self.sm)
             }
+            TypeValidationDiagnostic::ParamOverrideCntMismatch { inst, expected, found } => {
+                let range = inst.lookup(self.db.upcast()).ast_ptr(self.db.upcast()).range();
+                let src = self.parse.to_file_span(range, self.sm);
+                let module = &self.db.module_inst_data(inst).module;
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: format!("expected at most {expected} parameter overrides"),
+                    }])
+                    .with_message(format!(
+                        "too many parameter overrides: '{module}' has {expected} parameters but {found} values were provided"
+                    ))
+            }
+            TypeValidationDiagnostic::PortConnectionCntMismatch { inst, expected, found } => {
+                let range = inst.lookup(self.db.upcast()).ast_ptr(self.db.upcast()).range();
+                let src = self.parse.to_file_span(range, self.sm);
+                let module = &self.db.module_inst_data(inst).module;
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: format!("expected {expected} port connections"),
+                    }])
+                    .with_message(format!(
+                        "invalid port connections: '{module}' has {expected} ports but {found} connections were provided"
+                    ))
+                    .with_notes(vec!["help: every port must be connected exactly once".to_owned()])
+            }
+            TypeValidationDiagnostic::RecursiveInstance { inst, module } => {
+                let range = inst.lookup(self.db.upcast()).ast_ptr(self.db.upcast()).range();
+                let src = self.parse.to_file_span(range, self.sm);
+                let name = self.db.module_data(module).name.clone();
+                Report::error()
+                    .with_labels(vec![Label {
+                        style: LabelStyle::Primary,
+                        file_id: src.file,
+                        range: src.range.into(),
+                        message: format!("'{name}' is instantiated recursively"),
+                    }])
+                    .with_message(format!("recursive instantiation of module '{name}'"))
+            }
         }
     }
 
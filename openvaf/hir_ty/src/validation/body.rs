@@ -2,6 +2,7 @@ use std::mem::replace;
 
 use ahash::{HashMap, HashSet};
 use hir_def::body::Body;
+use hir_def::expr::Event;
 use hir_def::{
     BranchId, BuiltIn, DefWithBodyId, DisciplineId, Expr, ExprId, FunctionArgLoc, Literal, Lookup,
     NatureId, NodeId, ParamId, Path, Stmt, StmtId, VarId,
@@ -12,12 +13,12 @@ use syntax::name::{AsIdent, Name};
 
 use crate::builtin::{
     ABSDELAY_MAX, DDT_TOL, IDT_IC_ASSERT_TOL, NATURE_ACCESS_BRANCH, NATURE_ACCESS_NODES,
-    NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW, NOISE_TABLE_INLINE, NOISE_TABLE_INLINE_NAME,
-    TRANSITION_DELAY_RISET_FALLT_TOL,
+    NATURE_ACCESS_NODE_GND, NATURE_ACCESS_PORT_FLOW, TRANSITION_DELAY_RISET_FALLT_TOL,
 };
 use crate::db::HirTyDB;
 use crate::inference::{BranchWrite, InferenceResult, ResolvedFun};
 use crate::lower::BranchKind;
+use crate::noise_table::NoiseTableError;
 use crate::types::{Signature, Ty};
 
 #[derive(PartialEq, Eq, Clone, Debug)]
@@ -25,6 +26,7 @@ pub enum IllegalCtxAccessKind {
     NatureAccess,
     AnalogOperator { name: Name, is_standard: bool, non_const_dominator: Box<[ExprId]> },
     AnalysisFun { name: Name },
+    Event { name: Name },
     Var(VarId),
 }
 
@@ -79,6 +81,49 @@ pub enum BodyValidationDiagnostic {
         func: BuiltIn,
     },
 
+    ApproximatedAbsdelay {
+        expr: ExprId,
+        stmt: StmtId,
+    },
+
+    AnalogFilterFallback {
+        expr: ExprId,
+        stmt: StmtId,
+    },
+
+    ExpectedEvent {
+        expr: ExprId,
+    },
+
+    InvalidNoiseTable(NoiseTableError),
+
+    ExpectedArrayLiteral {
+        expr: ExprId,
+    },
+
+    UnsupportedArrayLiteral {
+        expr: ExprId,
+    },
+
+    NonLiteralAnalysisName {
+        expr: ExprId,
+    },
+
+    EmptyFilterCoefficients {
+        expr: ExprId,
+    },
+
+    OddRootCount {
+        expr: ExprId,
+        len: usize,
+    },
+
+    ImproperTransferFunction {
+        expr: ExprId,
+        num_order: usize,
+        den_order: usize,
+    },
+
     IncompatibleNatureAccess {
         candidates: [Option<(Name, Name)>; 2],
         access_nature: Option<NatureId>,
@@ -146,6 +191,7 @@ pub enum BodyCtx {
     AnalogInitialBlock,
     Conditional,
     EventControl,
+    EventExpr,
     Function,
     ConstOrAnalysis,
     Const,
@@ -153,7 +199,7 @@ pub enum BodyCtx {
 
 impl BodyCtx {
     fn allow_nature_access(self) -> bool {
-        matches!(self, Self::AnalogBlock | Self::Conditional | Self::EventControl)
+        matches!(self, Self::AnalogBlock | Self::Conditional | Self::EventControl | Self::EventExpr)
     }
 
     fn allow_contribute(self) -> bool {
@@ -164,6 +210,10 @@ impl BodyCtx {
         matches!(self, Self::AnalogBlock)
     }
 
+    fn allow_event(self) -> bool {
+        matches!(self, Self::EventExpr)
+    }
+
     fn allow_analysis_fun(self) -> bool {
         !matches!(self, Self::Const)
     }
@@ -179,6 +229,7 @@ impl_display! {
        BodyCtx::AnalogInitialBlock => "analog initial block";
        BodyCtx::Conditional => "conditions";
        BodyCtx::EventControl => "events";
+       BodyCtx::EventExpr => "event expressions";
        BodyCtx::Function => "analog functions";
        BodyCtx::ConstOrAnalysis => "constant or analysis";
        BodyCtx::Const => "constants";
@@ -214,7 +265,12 @@ impl BodyValidator<'_> {
 
                 return;
             }
-            Stmt::EventControl { body, .. } => {
+            Stmt::EventControl { ref events, body } => {
+                for event in events {
+                    if let Event::Monitored(expr) = *event {
+                        self.validate_event(expr, stmt);
+                    }
+                }
                 let old = replace(&mut self.ctx, BodyCtx::EventControl);
                 self.validate_stmt(body);
                 self.ctx = old;
@@ -280,6 +336,22 @@ impl BodyValidator<'_> {
             .validate_expr(expr)
     }
 
+    fn validate_event(&mut self, expr: ExprId, stmt: StmtId) {
+        let is_event = matches!(
+            self.infer.resolved_calls.get(&expr),
+            Some(ResolvedFun::BuiltIn(builtin)) if builtin.is_event()
+        );
+        if !is_event {
+            self.diagnostics.push(BodyValidationDiagnostic::ExpectedEvent { expr });
+        }
+
+        // events are only allowed in the analog block (and not in conditions)
+        let ctx = if self.ctx == BodyCtx::AnalogBlock { BodyCtx::EventExpr } else { self.ctx };
+        let old = replace(&mut self.ctx, ctx);
+        self.validate_expr(expr, stmt);
+        self.ctx = old;
+    }
+
     fn validate_assignment_dst(&mut self, expr: ExprId, stmt: StmtId) {
         ExprValidator { parent: self, cond_diagnostic_sink: None, write: true, stmt }
             .validate_expr(expr)
@@ -496,6 +568,22 @@ impl ExprValidator<'_, '_> {
                 }
             }
 
+            // array literals are only supported as arguments of `noise_table` and the
+            // `laplace_*`/`zi_*` filters, which handle them above
+            Expr::Array(_) => {
+                self.report(BodyValidationDiagnostic::UnsupportedArrayLiteral { expr });
+                return;
+            }
+
+            Expr::Index { base, index } => {
+                self.validate_expr(base);
+                // the index is only read even if the array element is written
+                let write = std::mem::replace(&mut self.write, false);
+                self.validate_expr(index);
+                self.write = write;
+                return;
+            }
+
             Expr::Path { port: false, .. } => {
                 match self.parent.infer.expr_types[expr] {
                     Ty::FunctionVar { arg: Some(arg), fun, .. } => {
@@ -578,6 +666,14 @@ impl ExprValidator<'_, '_> {
                 )
             }
 
+            _ if call.is_event() => self.check_access(
+                |_| IllegalCtxAccessKind::Event {
+                    name: name.as_ref().and_then(|p| p.as_ident()).unwrap(),
+                },
+                expr,
+                self.parent.ctx.allow_event(),
+            ),
+
             _ if call.is_analysis_var() && !self.parent.ctx.allow_analysis_fun() => self
                 .report_illegal_access(
                     IllegalCtxAccessKind::AnalysisFun {
@@ -679,10 +775,20 @@ impl ExprValidator<'_, '_> {
                 }
             }
 
-            (
-                BuiltIn::noise_table | BuiltIn::noise_table_log,
-                Some(NOISE_TABLE_INLINE | NOISE_TABLE_INLINE_NAME),
-            ) => self.validate_const_expr(args[0]),
+            (BuiltIn::noise_table | BuiltIn::noise_table_log, Some(_)) => {
+                if let Err(err) = self.parent.db.noise_table(self.parent.owner, expr) {
+                    self.report(BodyValidationDiagnostic::InvalidNoiseTable(err))
+                }
+                // the table itself is resolved at compile time and only contains literals
+                args = &args[1..];
+            }
+            (BuiltIn::ac_stim, Some(_)) => {
+                if let Some(&name) = args.first() {
+                    if !matches!(self.parent.body.exprs[name], Expr::Literal(Literal::String(_))) {
+                        self.report(BodyValidationDiagnostic::NonLiteralAnalysisName { expr: name })
+                    }
+                }
+            }
             (func @ (BuiltIn::simparam | BuiltIn::simparam_str), _) => {
                 if self.parent.ctx == BodyCtx::Const {
                     let known = if let Expr::Literal(Literal::String(name)) =
@@ -715,9 +821,41 @@ impl ExprValidator<'_, '_> {
                 }
             }
 
-            (BuiltIn::absdelay, Some(ABSDELAY_MAX))
-            | (BuiltIn::transition, Some(TRANSITION_DELAY_RISET_FALLT_TOL))
-            | (BuiltIn::ddt, Some(DDT_TOL))
+            (BuiltIn::absdelay, _) => {
+                self.report(BodyValidationDiagnostic::ApproximatedAbsdelay {
+                    expr,
+                    stmt: self.stmt,
+                });
+                if signature == Some(ABSDELAY_MAX) {
+                    // Do not type check const expr twice
+                    self.validate_const_expr(args[2]);
+                    args = &args[..2];
+                }
+            }
+
+            (BuiltIn::transition, Some(TRANSITION_DELAY_RISET_FALLT_TOL)) => {
+                self.report(BodyValidationDiagnostic::AnalogFilterFallback {
+                    expr,
+                    stmt: self.stmt,
+                });
+                // Do not type check const expr twice
+                self.validate_const_expr(args[4]);
+                args = &args[..4];
+            }
+
+            (BuiltIn::cross | BuiltIn::above | BuiltIn::timer, _)
+                if self.parent.ctx == BodyCtx::EventExpr =>
+            {
+                // the arguments of an event are ordinary analog block expressions
+                let old = replace(&mut self.parent.ctx, BodyCtx::AnalogBlock);
+                for arg in args {
+                    self.validate_expr(*arg)
+                }
+                self.parent.ctx = old;
+                return;
+            }
+
+            (BuiltIn::ddt, Some(DDT_TOL))
             | (BuiltIn::idt | BuiltIn::idtmod, Some(IDT_IC_ASSERT_TOL)) => {
                 if let [other_args @ .., const_expr] = args {
                     // Do not type check const expr twice
@@ -737,10 +875,21 @@ impl ExprValidator<'_, '_> {
                 | BuiltIn::zi_zp,
                 Some(_),
             ) => {
+                self.validate_transfer_function(expr, call, args);
                 if let [_expr, const_args @ ..] = args {
                     args = &args[..1];
-                    for arg in const_args {
-                        self.validate_const_expr(*arg)
+                    for (i, arg) in const_args.iter().enumerate() {
+                        match self.parent.body.exprs[*arg] {
+                            // the coefficients are consumed at compile time
+                            Expr::Array(ref entries) => {
+                                for entry in entries {
+                                    self.validate_const_expr(*entry)
+                                }
+                            }
+                            // coefficients that are not array literals were reported above
+                            _ if i < 2 => (),
+                            _ => self.validate_const_expr(*arg),
+                        }
                     }
                 }
             }
@@ -748,11 +897,80 @@ impl ExprValidator<'_, '_> {
             _ => (),
         }
 
+        let takes_arrays = matches!(
+            call,
+            BuiltIn::noise_table
+                | BuiltIn::noise_table_log
+                | BuiltIn::laplace_nd
+                | BuiltIn::laplace_np
+                | BuiltIn::laplace_zp
+                | BuiltIn::laplace_zd
+                | BuiltIn::zi_nd
+                | BuiltIn::zi_np
+                | BuiltIn::zi_zd
+                | BuiltIn::zi_zp
+        );
         for arg in args {
+            // arguments of calls with type errors that were not handled above
+            if takes_arrays && matches!(self.parent.body.exprs[*arg], Expr::Array(_)) {
+                continue;
+            }
             self.validate_expr(*arg)
         }
     }
 
+    /// The coefficients (or roots) of `laplace_*` and `zi_*` filters determine the order
+    /// of the filter and must therefore be array literals. Roots are specified as
+    /// (real, imaginary) pairs and continuous filters must be proper.
+    fn validate_transfer_function(&mut self, expr: ExprId, call: BuiltIn, args: &[ExprId]) {
+        let roots = match call {
+            BuiltIn::laplace_zp | BuiltIn::zi_zp => [true, true],
+            BuiltIn::laplace_zd | BuiltIn::zi_zd => [true, false],
+            BuiltIn::laplace_np | BuiltIn::zi_np => [false, true],
+            _ => [false, false],
+        };
+
+        let mut orders = [0; 2];
+        for ((arg, roots), order) in args[1..3].iter().zip(roots).zip(&mut orders) {
+            let len = match self.parent.body.exprs[*arg] {
+                Expr::Array(ref entries) => entries.len(),
+                _ => {
+                    self.report(BodyValidationDiagnostic::ExpectedArrayLiteral { expr: *arg });
+                    return;
+                }
+            };
+
+            // an empty list of roots represents the polynomial 1
+            if len == 0 && !roots {
+                self.report(BodyValidationDiagnostic::EmptyFilterCoefficients { expr: *arg });
+                return;
+            }
+
+            if roots {
+                if len % 2 != 0 {
+                    self.report(BodyValidationDiagnostic::OddRootCount { expr: *arg, len });
+                    return;
+                }
+                *order = len / 2;
+            } else {
+                *order = len - 1;
+            }
+        }
+
+        let [num_order, den_order] = orders;
+        let continuous = matches!(
+            call,
+            BuiltIn::laplace_nd | BuiltIn::laplace_np | BuiltIn::laplace_zd | BuiltIn::laplace_zp
+        );
+        if continuous && num_order > den_order {
+            self.report(BodyValidationDiagnostic::ImproperTransferFunction {
+                expr,
+                num_order,
+                den_order,
+            })
+        }
+    }
+
     fn validate_const_expr(&mut self, expr: ExprId) {
         let old = replace(&mut self.parent.ctx, BodyCtx::Const);
         let sink = self.cond_diagnostic_sink.take();
@@ -1,13 +1,15 @@
 use std::iter::once;
 
+use ahash::AHashSet;
 use basedb::{AstId, ErasedAstId, FileId};
 use hir_def::nameres::diagnostics::PathResolveError;
 use hir_def::nameres::{DefMap, ScopeDefItem};
 use hir_def::{
     AliasParamId, Branch, BranchId, BranchKind, DisciplineId, ItemLoc, ItemTree,
-    LocalDisciplineAttrId, LocalNatureAttrId, Lookup, ModuleId, ModuleLoc, NatureId, NodeId,
-    NodeTypeDecl, Path, ScopeId,
+    LocalDisciplineAttrId, LocalNatureAttrId, Lookup, ModuleId, ModuleInstId, ModuleLoc, NatureId,
+    NodeId, NodeTypeDecl, Path, ScopeId,
 };
+use stdx::iter::zip;
 use syntax::ast::ArgListOwner;
 use syntax::name::Name;
 use syntax::{ast, AstNode, SyntaxNodePtr};
@@ -34,6 +36,9 @@ pub enum TypeValidationDiagnostic {
     NodeWithoutDiscipline { decl: ErasedAstId, name: Name },
     ExpectedPort { node: NodeId, src: ErasedAstId },
     IncompatibleBranch { branch: BranchId, node1: NodeId, node2: NodeId },
+    ParamOverrideCntMismatch { inst: ModuleInstId, expected: usize, found: usize },
+    PortConnectionCntMismatch { inst: ModuleInstId, expected: usize, found: usize },
+    RecursiveInstance { inst: ModuleInstId, module: ModuleId },
 }
 
 impl TypeValidationDiagnostic {
@@ -78,11 +83,108 @@ impl TypeValidationCtx<'_> {
                 ScopeDefItem::NodeId(node) => self.verify_node(*node, loc),
                 ScopeDefItem::BranchId(branch) => self.verify_branch(*branch),
                 ScopeDefItem::AliasParamId(alias) => self.verify_alias(*alias),
+                ScopeDefItem::ModuleInstId(inst) => self.verify_module_inst(*inst, module),
                 _ => (),
             }
         }
     }
 
+    fn verify_module_inst(&mut self, inst: ModuleInstId, parent: ModuleId) {
+        let loc = inst.lookup(self.db.upcast());
+        let data = self.db.module_inst_data(inst);
+        let ast = loc.source(self.db.upcast());
+        let decl = ast.syntax().parent().and_then(ast::ModuleInst::cast);
+
+        let info = match self.db.module_inst_info(inst) {
+            Some(info) => info,
+            None => {
+                let err = self
+                    .def_map
+                    .resolve_local_item_in_scope::<ModuleId>(self.def_map.root(), &data.module);
+                if let (Err(err), Some(module)) = (err, decl.and_then(|decl| decl.module())) {
+                    let src = SyntaxNodePtr::new(module.syntax());
+                    self.report(TypeValidationDiagnostic::PathError { err, src });
+                }
+                return;
+            }
+        };
+
+        if self.instantiates(info.module, parent, &mut Vec::new()) {
+            self.report(TypeValidationDiagnostic::RecursiveInstance { inst, module: info.module });
+            return;
+        }
+
+        let module = self.db.module_data(info.module);
+
+        let override_args =
+            decl.iter().flat_map(|decl| decl.param_overrides()).flat_map(|it| it.args());
+        for ((name, param), arg) in
+            zip(zip(&data.param_overrides, &info.param_overrides), override_args)
+        {
+            if let (Some(name), None) = (name, param) {
+                let err =
+                    PathResolveError::NotFoundIn { name: name.clone(), scope: module.name.clone() };
+                let src = SyntaxNodePtr::new(arg.name_ref().unwrap().syntax());
+                self.report(TypeValidationDiagnostic::PathError { err, src });
+            }
+        }
+
+        let num_ordered = data.param_overrides.iter().filter(|name| name.is_none()).count();
+        if num_ordered > module.params.len() {
+            self.report(TypeValidationDiagnostic::ParamOverrideCntMismatch {
+                inst,
+                expected: module.params.len(),
+                found: num_ordered,
+            });
+        }
+
+        let mut unresolved_port = false;
+        for ((name, port), arg) in
+            zip(zip(&data.port_connections, &info.port_connections), ast.args())
+        {
+            if let (Some(name), None) = (name, port) {
+                unresolved_port = true;
+                let err =
+                    PathResolveError::NotFoundIn { name: name.clone(), scope: module.name.clone() };
+                let src = SyntaxNodePtr::new(arg.name_ref().unwrap().syntax());
+                self.report(TypeValidationDiagnostic::PathError { err, src });
+            }
+        }
+
+        // every port must be connected exactly once
+        let connected: AHashSet<_> = info.port_connections.iter().flatten().collect();
+        if !unresolved_port && connected.len() != module.ports.len()
+            || data.port_connections.len() != module.ports.len()
+        {
+            self.report(TypeValidationDiagnostic::PortConnectionCntMismatch {
+                inst,
+                expected: module.ports.len(),
+                found: data.port_connections.len(),
+            });
+        }
+    }
+
+    /// Returns whether `module` (indirectly) instantiates `target`
+    fn instantiates(
+        &self,
+        module: ModuleId,
+        target: ModuleId,
+        visited: &mut Vec<ModuleId>,
+    ) -> bool {
+        if module == target {
+            return true;
+        }
+        if visited.contains(&module) {
+            return false;
+        }
+        visited.push(module);
+        self.db.module_data(module).instances.iter().any(|inst| {
+            self.db
+                .module_inst_info(*inst)
+                .is_some_and(|info| self.instantiates(info.module, target, visited))
+        })
+    }
+
     fn resolve_node(
         &mut self,
         node: &Path,
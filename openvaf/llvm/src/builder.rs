@@ -47,6 +47,14 @@ extern "C" {
         Name: *const c_char,
     ) -> &'a Value;
 
+    pub fn LLVMBuildInsertValue<'a>(
+        arg1: &Builder<'a>,
+        AggVal: &'a Value,
+        EltVal: &'a Value,
+        Index: c_uint,
+        Name: *const c_char,
+    ) -> &'a Value;
+
     // Arithmetic
     pub fn LLVMBuildAdd<'a>(
         arg1: &Builder<'a>,
@@ -54,6 +54,7 @@ pub use crate::instructions::{
     InstructionData, InstructionFormat, Opcode, PhiMap, PhiNode, ValueList, ValueListPool,
 };
 pub use crate::layout::{InstCursor, InstIter, Layout};
+pub use crate::serialize::{JsonField, JsonSection, JsonStr};
 use crate::write::DummyResolver;
 pub use stdx::Ieee64;
 
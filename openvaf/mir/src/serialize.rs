@@ -26,13 +26,36 @@ macro_rules! w {
     };
 }
 
+/// A field of an entry in a [`JsonSection`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonField {
+    /// A value of the function, serialized as its index in `vals`
+    Value(Value),
+    Int(i64),
+    Bool(bool),
+    Str(String),
+}
+
+/// An additional list of objects that is serialized by [`Function::to_json`] after the
+/// outputs. This allows callers to attach information (like the residual of a DAE system)
+/// that references values of the function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonSection {
+    pub name: &'static str,
+    pub entries: Vec<Vec<(&'static str, JsonField)>>,
+}
+
 impl Function {
+    /// Serializes the function (reachable blocks in reverse postorder) as JSON.
+    /// Instructions, values and blocks are referenced by their index in the
+    /// `instructions`, `vals` and `cfg` lists.
     pub fn to_json(
         &self,
         cfg: &ControlFlowGraph,
         intern: &Rodeo,
         mut param_name: impl FnMut(Param) -> (&'static str, String),
         outputs: impl Iterator<Item = (String, Value)>,
+        sections: &[JsonSection],
     ) -> String {
         let mut inst_map = IndexSet::default();
         let bb_map = cfg
@@ -60,6 +83,13 @@ impl Function {
         }
         let outputs: Vec<_> =
             outputs.map(|(name, val)| (name, val_map.insert_full(val).0)).collect();
+        for section in sections {
+            for (_, field) in section.entries.iter().flatten() {
+                if let JsonField::Value(val) = *field {
+                    val_map.insert(val);
+                }
+            }
+        }
         let mut serializer = Serializer {
             cfg,
             func: self,
@@ -91,6 +121,13 @@ impl Function {
             wln!(sel, ",");
             sel.serialize_key("outputs");
             sel.serialize_dict_entries(outputs.into_iter());
+            for section in sections {
+                wln!(sel, ",");
+                sel.serialize_key(section.name);
+                sel.serialize_list_entries_with(section.entries.iter(), |sel, entry| {
+                    sel.serialize_section_entry(entry)
+                });
+            }
         });
         serializer.buf
     }
@@ -146,6 +183,23 @@ impl Serializer<'_> {
         })
     }
 
+    fn serialize_section_entry(&mut self, entry: &[(&'static str, JsonField)]) {
+        self.serialize_dict(|sel| {
+            for (i, (key, field)) in entry.iter().enumerate() {
+                if i != 0 {
+                    wln!(sel, ",");
+                }
+                sel.serialize_key(key);
+                match field {
+                    JsonField::Value(val) => w!(sel, "{}", sel.val_map.get_index_of(val).unwrap()),
+                    JsonField::Int(val) => w!(sel, "{val}"),
+                    JsonField::Bool(val) => w!(sel, "{val}"),
+                    JsonField::Str(val) => w!(sel, "{}", JsonStr(val)),
+                }
+            }
+        })
+    }
+
     fn serialize_val(
         &mut self,
         val: Value,
@@ -159,14 +213,20 @@ impl Serializer<'_> {
                 }
                 ValueDef::Param(param) => {
                     let (kind, name) = param_name(param);
-                    wln!(sel, "\"{kind}\": \"{name}\",")
+                    wln!(sel, "\"{kind}\": {},", JsonStr(name))
                 }
                 ValueDef::Const(Const::Float(val)) => {
-                    wln!(sel, "\"fconst\": {},", f64::from(val))
+                    let val = f64::from(val);
+                    // JSON has no representation for non-finite numbers
+                    if val.is_finite() {
+                        wln!(sel, "\"fconst\": {val:?},")
+                    } else {
+                        wln!(sel, "\"fconst\": \"{val}\",")
+                    }
                 }
                 ValueDef::Const(Const::Int(val)) => wln!(sel, "\"iconst\": {val},"),
                 ValueDef::Const(Const::Str(val)) => {
-                    wln!(sel, "\"sconst\": \"{}\",", &sel.intern[val])
+                    wln!(sel, "\"sconst\": {},", JsonStr(&sel.intern[val]))
                 }
                 ValueDef::Const(Const::Bool(val)) => wln!(sel, "\"bconst\": {val},"),
                 ValueDef::Invalid => unreachable!(),
@@ -273,7 +333,6 @@ impl Serializer<'_> {
         mut f: impl FnMut(&mut Self, I::Item),
     ) where
         I: Iterator,
-        I::Item: Display,
     {
         self.serialize_list(|sel| {
             if let Some(first) = entries.next() {
@@ -294,7 +353,28 @@ impl Serializer<'_> {
     }
 
     fn serialize_key(&mut self, key: impl Display) {
-        w!(self, "\"{key}\": ");
+        w!(self, "{}: ", JsonStr(key.to_string()));
+    }
+}
+
+/// Displays a string as a quoted and escaped JSON string
+pub struct JsonStr<T>(pub T);
+
+impl<T: AsRef<str>> Display for JsonStr<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_char('"')?;
+        for c in self.0.as_ref().chars() {
+            match c {
+                '"' => f.write_str("\\\"")?,
+                '\\' => f.write_str("\\\\")?,
+                '\n' => f.write_str("\\n")?,
+                '\r' => f.write_str("\\r")?,
+                '\t' => f.write_str("\\t")?,
+                c if c.is_control() => write!(f, "\\u{:04x}", c as u32)?,
+                c => f.write_char(c)?,
+            }
+        }
+        f.write_char('"')
     }
 }
 
@@ -32,6 +32,10 @@ pub fn main_command() -> Command {
             supported_targets(),
             target_cpu(),
             codegen_opts(),
+            sensitivity(),
+            message_format(),
+            depfile(),
+            depfile_json(),
             interface(),
             expand(),
             dump_json(),
@@ -57,6 +61,10 @@ pub const OPT_LVL: &str = "opt_lvl";
 pub const DEFINE: &str = "define";
 pub const PRINT_EXPANSION: &str = "print-expansion";
 pub const DUMP_JSON: &str = "dump-json";
+pub const SENSITIVITY: &str = "sensitivity";
+pub const MESSAGE_FORMAT: &str = "message-format";
+pub const DEPFILE: &str = "depfile";
+pub const DEPFILE_JSON: &str = "depfile-json";
 pub const ALLOW: &str = "allow";
 pub const WARN: &str = "warn";
 pub const DENY: &str = "deny";
@@ -141,6 +149,41 @@ fn codegen_opts() -> Arg {
         .value_hint(ValueHint::Other)
 }
 
+fn sensitivity() -> Arg {
+    Arg::new(SENSITIVITY)
+        .long(SENSITIVITY)
+        .help("Generate derivatives with respect to this parameter.")
+        .long_help("Generate derivatives of the residual and operating point variables with respect to this parameter.\nThe derivatives are available with the write_sensitivity function of the OSDI descriptor.\nParameters can also be selected with the (* sensitivity *) attribute.")
+        .value_name("PARAM")
+        .action(ArgAction::Append)
+        .required(false)
+        .value_hint(ValueHint::Other)
+}
+
+fn message_format() -> Arg {
+    Arg::new(MESSAGE_FORMAT)
+        .long(MESSAGE_FORMAT)
+        .help("Set the format in which errors and warnings are emitted.")
+        .long_help("Set the format in which errors and warnings are emitted:\n\nhuman - human readable messages printed to stderr\njson - one JSON object per diagnostic printed to stdout\nsarif - a SARIF 2.1.0 log printed to stdout")
+        .value_name("FMT")
+        .value_hint(ValueHint::Other)
+        .value_parser(["human", "json", "sarif"])
+        .default_value("human")
+        .required(false)
+}
+
+fn depfile() -> Arg {
+    flag(DEPFILE, DEPFILE)
+        .help("Write a Makefile style .d file listing all input files next to the output.")
+        .long_help("Write a Makefile style .d file listing the input file and all `include-ed files next to the output.\nThe file can be included by make or used as a ninja depfile. Built-in headers like disciplines.vams are not listed.")
+}
+
+fn depfile_json() -> Arg {
+    flag(DEPFILE_JSON, DEPFILE_JSON)
+        .help("Also write all input files (including built-in headers) to a .deps.json file.")
+        .requires(DEPFILE)
+}
+
 fn input() -> Arg {
     input_file_path_arg(INPUT)
         .help("The root Verilog-A file.")
@@ -268,7 +311,13 @@ directives (`include) resolved is emitted to stdout.",
 }
 
 fn dump_json() -> Arg {
-    flag(DUMP_JSON, "dump-json").help("Abort after lowering and serialize MIR as json.")
+    flag(DUMP_JSON, "dump-json").help("Abort after lowering and serialize MIR as json.").long_help(
+        "Aborts the compilation after the MIR is generated.
+The eval function and the DAE system (residual and Jacobian) of
+each module are written to <input>_<module>.json instead.
+The output contains a \"version\" field that is incremented whenever
+the schema changes incompatibly.",
+    )
 }
 
 fn def_arg() -> Arg {
@@ -4,12 +4,15 @@ use std::process::exit;
 use anyhow::{bail, Context, Result};
 use camino::Utf8PathBuf;
 use clap::ArgMatches;
-use openvaf::{builtin_lints, get_target_names, host_triple, AbsPathBuf, LintLevel, OptLevel};
+use openvaf::{
+    builtin_lints, get_target_names, host_triple, AbsPathBuf, LintLevel, MessageFormat, OptLevel,
+};
 use termcolor::{Color, ColorChoice, ColorSpec, WriteColor};
 
 use crate::cli_def::{
-    ALLOW, BATCHMODE, CACHE_DIR, CODEGEN, DEFINE, DENY, DRYRUN, INCLUDE, INPUT, LINTS, OPT_LVL,
-    OUTPUT, SUPPORTED_TARGETS, TARGET, TARGET_CPU, WARN,
+    ALLOW, BATCHMODE, CACHE_DIR, CODEGEN, DEFINE, DENY, DEPFILE, DEPFILE_JSON, DRYRUN, INCLUDE,
+    INPUT, LINTS, MESSAGE_FORMAT, OPT_LVL, OUTPUT, SENSITIVITY, SUPPORTED_TARGETS, TARGET,
+    TARGET_CPU, WARN,
 };
 use crate::{CompilationDestination, Opts};
 
@@ -75,6 +78,10 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         .get_many::<String>(DEFINE)
         .map_or_else(Vec::new, |values| values.cloned().collect());
 
+    let sensitivities = matches
+        .get_many::<String>(SENSITIVITY)
+        .map_or_else(Vec::new, |values| values.cloned().collect());
+
     let include: Result<_> = matches.get_many::<Utf8PathBuf>(INCLUDE).map_or_else(
         || Ok(Vec::new()),
         |include| include.map(|path| Ok(AbsPathBuf::assert(path.canonicalize()?))).collect(),
@@ -104,6 +111,9 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
     let target_cpu: String =
         matches.get_one(TARGET_CPU).cloned().unwrap_or_else(|| default_cpu.to_owned());
 
+    let message_format: MessageFormat =
+        matches.get_one::<String>(MESSAGE_FORMAT).unwrap().parse().map_err(anyhow::Error::msg)?;
+
     Ok(Opts {
         input,
         lints,
@@ -114,6 +124,10 @@ pub fn matches_to_opts(matches: ArgMatches) -> Result<Opts> {
         opt_lvl,
         target,
         target_cpu,
+        sensitivities,
+        message_format,
+        depfile: matches.get_flag(DEPFILE),
+        depfile_json: matches.get_flag(DEPFILE_JSON),
         dry_run: matches.get_flag(DRYRUN),
     })
 }
@@ -0,0 +1,101 @@
+use crate::format::delta;
+use crate::source::{Directive, Role, Tok};
+
+/// A `` `ifdef ``/`` `ifndef `` region up to (and including) the matching `` `endif ``.
+pub(crate) struct Region {
+    /// The index of the `` `ifdef `` token and of every `` `elsif ``/`` `else `` token
+    pub branches: Vec<usize>,
+    /// The index of the `` `endif `` token
+    pub end: usize,
+    /// Every branch of the region changes the nesting (of blocks and brackets) in the same way.
+    /// Each branch can then be formatted starting from the state at the `` `ifdef ``.
+    pub balanced: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CondRole {
+    Start,
+    Branch,
+    End,
+}
+
+pub(crate) struct Conditionals {
+    /// Nested regions always come before the region that contains them
+    pub regions: Vec<Region>,
+    pub roles: Vec<Option<(usize, CondRole)>>,
+}
+
+impl Conditionals {
+    pub fn new(toks: &[Tok]) -> Conditionals {
+        let mut roles = vec![None; toks.len()];
+        let mut regions = Vec::new();
+        let mut open: Vec<Vec<usize>> = Vec::new();
+
+        for (i, tok) in toks.iter().enumerate() {
+            match tok.role {
+                Role::Directive(Directive::IfDef) => open.push(vec![i]),
+                Role::Directive(Directive::ElsIf | Directive::Else) => {
+                    if let Some(branches) = open.last_mut() {
+                        branches.push(i)
+                    }
+                }
+                Role::Directive(Directive::EndIf) => {
+                    if let Some(branches) = open.pop() {
+                        let id = regions.len();
+                        roles[branches[0]] = Some((id, CondRole::Start));
+                        for &branch in &branches[1..] {
+                            roles[branch] = Some((id, CondRole::Branch));
+                        }
+                        roles[i] = Some((id, CondRole::End));
+                        regions.push(Region { branches, end: i, balanced: false });
+                    }
+                }
+                _ => (),
+            }
+        }
+
+        // regions that are never closed are ignored, the preprocessor reports an error for those
+
+        let mut res = Conditionals { regions, roles };
+        for id in 0..res.regions.len() {
+            res.regions[id].balanced = res.is_balanced(toks, id);
+        }
+        res
+    }
+
+    fn is_balanced(&self, toks: &[Tok], region: usize) -> bool {
+        let Region { ref branches, end, .. } = self.regions[region];
+        let deltas = branches.iter().enumerate().map(|(i, &start)| {
+            let branch_end = branches.get(i + 1).copied().unwrap_or(end);
+            self.branch_delta(toks, start + 1, branch_end)
+        });
+
+        let mut deltas = deltas.collect::<Vec<_>>();
+        // without an `else` there is an implicit empty branch
+        if toks[branches[branches.len() - 1]].role != Role::Directive(Directive::Else) {
+            deltas.push((0, 0))
+        }
+        deltas.windows(2).all(|pair| pair[0] == pair[1])
+    }
+
+    /// The change in nesting caused by the tokens in `start..end`.
+    /// Nested regions are assumed to take their first branch.
+    fn branch_delta(&self, toks: &[Tok], start: usize, end: usize) -> (i32, i32) {
+        let mut res = (0, 0);
+        let mut i = start;
+        while i < end {
+            if let Some((nested, CondRole::Start)) = self.roles[i] {
+                let nested = &self.regions[nested];
+                let first_end = nested.branches.get(1).copied().unwrap_or(nested.end);
+                let (depth, parens) = self.branch_delta(toks, i + 1, first_end);
+                res = (res.0 + depth, res.1 + parens);
+                i = nested.end + 1;
+                continue;
+            }
+            let (depth, parens) = delta(&toks[i]);
+            res = (res.0 + depth, res.1 + parens);
+            i += 1;
+        }
+        res
+    }
+}
@@ -0,0 +1,511 @@
+use tokens::lexer::TokenKind::{self, *};
+use tokens::SyntaxKind;
+
+use crate::conditional::{CondRole, Conditionals};
+use crate::layout::{align, Break, Layout};
+use crate::source::{Role, Tok};
+use crate::{Config, Indent};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Block {
+    /// `begin`
+    Begin,
+    /// `case`, `casex` and `casez`
+    Case,
+    /// a keyword that opens a block with a header (`module`, `function`, ...)
+    Header,
+    /// `end`, `endmodule`, `endfunction`, ...
+    End,
+    None,
+}
+
+fn block(tok: &Tok) -> Block {
+    if tok.role != Role::Code || tok.kind != SimpleIdent {
+        return Block::None;
+    }
+    match tok.text {
+        "begin" => Block::Begin,
+        "case" | "casex" | "casez" => Block::Case,
+        "module" | "macromodule" | "connectmodule" | "function" | "task" | "discipline"
+        | "nature" | "paramset" | "generate" => Block::Header,
+        "end" | "endmodule" | "endconnectmodule" | "endfunction" | "endtask" | "endcase"
+        | "enddiscipline" | "endnature" | "endparamset" | "endgenerate" => Block::End,
+        _ => Block::None,
+    }
+}
+
+fn bracket(kind: TokenKind) -> i32 {
+    match kind {
+        OpenParen | OpenBracket | OpenBrace | ArrStart | AttrOpenParen => 1,
+        CloseParen | CloseBracket | CloseBrace | AttrCloseParen => -1,
+        _ => 0,
+    }
+}
+
+fn is_keyword(text: &str) -> bool {
+    SyntaxKind::from_keyword(text).is_some()
+        || matches!(text, "repeat" | "casex" | "casez" | "generate" | "genvar")
+}
+
+/// The change in block and bracket nesting caused by `tok`.
+pub(crate) fn delta(tok: &Tok) -> (i32, i32) {
+    if tok.role != Role::Code {
+        return (0, 0);
+    }
+    let depth = match block(tok) {
+        Block::Begin | Block::Case | Block::Header => 1,
+        Block::End => -1,
+        Block::None => 0,
+    };
+    (depth, bracket(tok.kind))
+}
+
+/// Whether removing the whitespace between `a` and `b` would change how they are lexed
+fn merges(a: &Tok, b: &Tok) -> bool {
+    let joined = format!("{}{}", a.text, b.text);
+    let tokens = lexer::tokenize(&joined);
+    !(tokens.len() == 2 && tokens[0].kind == a.kind && tokens[1].kind == b.kind)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+enum Label {
+    #[default]
+    None,
+    /// directly after `begin`
+    Begin,
+    /// after `begin :`
+    Colon,
+}
+
+/// The syntactic context that determines the indentation of a line.
+#[derive(Debug, Clone, Default)]
+struct State {
+    /// The number of open blocks (`module`, `begin`, `case`, ...)
+    depth: u32,
+    /// The number of open brackets
+    parens: u32,
+    /// A statement (or declaration) was started but not finished yet
+    pending: bool,
+    /// The pending statement opened a block (`module foo(...);`). Its remaining lines
+    /// are not indented any further because the block itself is already indented.
+    header: bool,
+    /// The pending statement started with a macro call
+    macro_stmt: bool,
+    /// The bracket depth at which the expression of a `case` statement ends
+    case_parens: Option<u32>,
+    /// The bracket depth of each `?` that has not been matched by a `:` yet
+    ternary: Vec<u32>,
+    label: Label,
+}
+
+impl State {
+    /// Called before the first token of each line.
+    fn newline(&mut self) {
+        // macros usually expand to complete statements, so a line that consists of a
+        // macro call does not cause the next line to be indented
+        if self.macro_stmt && self.parens == 0 {
+            self.finish_stmt();
+        }
+    }
+
+    /// Updates the state after `tok`. Returns whether the next token should start on a new line.
+    fn update(&mut self, tok: &Tok) -> bool {
+        if tok.role != Role::Code {
+            return false;
+        }
+
+        match self.label {
+            Label::Begin if tok.kind == Colon => {
+                self.label = Label::Colon;
+                return false;
+            }
+            Label::Colon if matches!(tok.kind, SimpleIdent | EscapedIdent) => {
+                self.label = Label::None;
+                return true;
+            }
+            _ => self.label = Label::None,
+        }
+
+        match tok.kind {
+            Semi if self.parens == 0 => {
+                self.finish_stmt();
+                return true;
+            }
+            Question => self.ternary.push(self.parens),
+            Colon if self.is_ternary_colon() => {
+                self.ternary.pop();
+            }
+            _ => (),
+        }
+
+        match bracket(tok.kind) {
+            1 => self.parens += 1,
+            -1 => {
+                self.parens = self.parens.saturating_sub(1);
+                if self.case_parens == Some(self.parens) {
+                    self.case_parens = None;
+                    self.finish_stmt();
+                    return false;
+                }
+            }
+            _ => (),
+        }
+
+        match block(tok) {
+            Block::Begin => {
+                self.depth += 1;
+                self.finish_stmt();
+                self.label = Label::Begin;
+                return true;
+            }
+            Block::End => {
+                self.depth = self.depth.saturating_sub(1);
+                self.finish_stmt();
+                return false;
+            }
+            Block::Case => {
+                self.depth += 1;
+                self.header = true;
+                self.case_parens = Some(self.parens);
+            }
+            Block::Header => {
+                self.depth += 1;
+                self.header = true;
+            }
+            Block::None => (),
+        }
+
+        if !self.pending {
+            self.pending = true;
+            self.macro_stmt = tok.kind == CompilerDirective;
+        }
+        false
+    }
+
+    fn finish_stmt(&mut self) {
+        self.pending = false;
+        self.header = false;
+        self.macro_stmt = false;
+        self.ternary.clear();
+    }
+
+    fn is_ternary_colon(&self) -> bool {
+        self.ternary.last() == Some(&self.parens)
+    }
+}
+
+pub(crate) struct Formatter<'a, 'b> {
+    src: &'a str,
+    toks: &'b [Tok<'a>],
+    cond: &'b Conditionals,
+    layout: &'b Layout,
+    config: &'b Config,
+    dst: String,
+    /// The offset in `dst` of each token that was emitted
+    pos: Vec<Option<usize>>,
+    state: State,
+    /// The state at the start of each `` `ifdef `` region that is currently being formatted
+    saved: Vec<State>,
+    /// The previous token (including comments and directives)
+    prev: Option<usize>,
+    /// The previous token with [`Role::Code`]
+    prev_code: Option<usize>,
+    break_next: bool,
+    prev_unary: bool,
+    prev_spaced_colon: bool,
+    /// inside the port of a port flow (`I(<a>)`)
+    port: bool,
+}
+
+impl<'a, 'b> Formatter<'a, 'b> {
+    pub fn new(
+        src: &'a str,
+        toks: &'b [Tok<'a>],
+        cond: &'b Conditionals,
+        layout: &'b Layout,
+        config: &'b Config,
+    ) -> Formatter<'a, 'b> {
+        Formatter {
+            src,
+            toks,
+            cond,
+            layout,
+            config,
+            dst: String::with_capacity(src.len() + src.len() / 8),
+            pos: vec![None; toks.len()],
+            state: State::default(),
+            saved: Vec::new(),
+            prev: None,
+            prev_code: None,
+            break_next: false,
+            prev_unary: false,
+            prev_spaced_colon: false,
+            port: false,
+        }
+    }
+
+    pub fn run(mut self) -> String {
+        let cond = self.cond;
+        let mut i = 0;
+        while i < self.toks.len() {
+            match cond.roles[i] {
+                Some((region, CondRole::Start)) if !cond.regions[region].balanced => {
+                    i = self.verbatim(region);
+                    continue;
+                }
+                Some((_, CondRole::Start)) => self.saved.push(self.state.clone()),
+                Some((_, CondRole::Branch)) => {
+                    if let Some(state) = self.saved.last() {
+                        self.state = state.clone();
+                    }
+                }
+                Some((_, CondRole::End)) => {
+                    self.saved.pop();
+                }
+                None => (),
+            }
+            self.token(i);
+            i += 1;
+        }
+
+        self.trim_line_end();
+        if !self.dst.is_empty() {
+            self.dst.push('\n');
+        }
+        align(&self.dst, &self.pos, &self.layout.align)
+    }
+
+    fn token(&mut self, i: usize) {
+        let tok = &self.toks[i];
+        self.lead(i);
+        self.pos[i] = Some(self.dst.len());
+        self.dst.push_str(tok.text);
+        self.prev = Some(i);
+        match tok.role {
+            Role::Comment => return,
+            Role::Code => {
+                self.prev_spaced_colon = tok.kind == Colon && self.spaced_colon();
+                self.prev_unary = self.is_unary(i);
+                match tok.kind {
+                    Lt if self.prev_code.map(|prev| self.toks[prev].kind) == Some(OpenParen) => {
+                        self.port = true
+                    }
+                    Gt | CloseParen => self.port = false,
+                    _ => (),
+                }
+                self.prev_code = Some(i);
+            }
+            _ => (),
+        }
+        self.break_next = self.state.update(tok);
+    }
+
+    /// An `` `ifdef `` region whose branches can't be formatted independently is copied
+    /// unchanged. Formatting continues as if the first branch was taken.
+    fn verbatim(&mut self, region: usize) -> usize {
+        let (toks, cond) = (self.toks, self.cond);
+        let start = cond.regions[region].branches[0];
+        let end = cond.regions[region].end;
+        self.lead(start);
+        self.dst.push_str(&self.src[toks[start].start..toks[end].start + toks[end].text.len()]);
+        self.simulate(region);
+        self.prev = Some(end);
+        self.break_next = false;
+        end + 1
+    }
+
+    /// Updates the state for the first branch of `region` without emitting anything.
+    fn simulate(&mut self, region: usize) {
+        let (toks, cond) = (self.toks, self.cond);
+        let branches = &cond.regions[region].branches;
+        let end = branches.get(1).copied().unwrap_or(cond.regions[region].end);
+        let mut i = branches[0] + 1;
+        while i < end {
+            if toks[i].ws.contains('\n') {
+                self.state.newline();
+            }
+            if let Some((nested, CondRole::Start)) = cond.roles[i] {
+                self.simulate(nested);
+                i = cond.regions[nested].end + 1;
+                continue;
+            }
+            if toks[i].role == Role::Code {
+                self.prev_code = Some(i);
+            }
+            self.state.update(&toks[i]);
+            i += 1;
+        }
+    }
+
+    /// Emits the whitespace in front of the token `i`.
+    fn lead(&mut self, i: usize) {
+        let tok = &self.toks[i];
+        if self.prev.is_none() {
+            self.indent(i);
+            return;
+        }
+
+        // line continuations are kept as is
+        if tok.ws.contains('\\') {
+            if tok.ws.contains('\n') {
+                self.state.newline();
+            }
+            self.dst.push_str(tok.ws);
+            return;
+        }
+
+        let mut newlines = match self.layout.breaks[i] {
+            Break::Keep => tok.ws.matches('\n').count(),
+            Break::Line | Break::Close => tok.ws.matches('\n').count().max(1),
+            Break::Join => 0,
+        };
+        if newlines == 0 && self.needs_break(tok) {
+            newlines = 1;
+        }
+
+        if newlines == 0 {
+            let space = self.space(i);
+            self.dst.push_str(space);
+            return;
+        }
+
+        self.state.newline();
+        self.trim_line_end();
+        for _ in 0..newlines.min(self.config.max_blank_lines as usize + 1) {
+            self.dst.push('\n');
+        }
+        self.indent(i);
+    }
+
+    fn needs_break(&self, tok: &Tok) -> bool {
+        if tok.role == Role::Comment || self.state.parens != 0 {
+            return false;
+        }
+        if block(tok) == Block::End {
+            return true;
+        }
+        self.break_next && !(tok.kind == Colon && self.state.label == Label::Begin)
+    }
+
+    fn indent(&mut self, i: usize) {
+        let tok = &self.toks[i];
+        if matches!(tok.role, Role::Directive(_) | Role::Define) {
+            return;
+        }
+
+        let mut level = self.state.depth;
+        if block(tok) == Block::End || self.layout.breaks[i] == Break::Close {
+            level = level.saturating_sub(1);
+        } else if self.is_continuation(tok) {
+            level += 1;
+        }
+
+        for _ in 0..level {
+            match self.config.indent {
+                Indent::Spaces(width) => {
+                    self.dst.extend((0..width).map(|_| ' '));
+                }
+                Indent::Tabs => self.dst.push('\t'),
+            }
+        }
+    }
+
+    /// Whether a line starting with `tok` continues the statement of the previous line
+    fn is_continuation(&self, tok: &Tok) -> bool {
+        if !self.state.pending || self.state.header || block(tok) == Block::Begin {
+            return false;
+        }
+        // attributes are often placed on the line before the item they belong to
+        self.prev_code.map(|prev| self.toks[prev].kind) != Some(AttrCloseParen)
+    }
+
+    fn trim_line_end(&mut self) {
+        let len = self.dst.trim_end_matches([' ', '\t']).len();
+        self.dst.truncate(len);
+    }
+
+    fn spaced_colon(&self) -> bool {
+        self.state.is_ternary_colon() || self.state.label == Label::Begin
+    }
+
+    fn is_unary(&self, i: usize) -> bool {
+        match self.toks[i].kind {
+            Not | Tilde => return true,
+            Minus | Plus | And | Or | Caret | NXorL | NXorR => (),
+            _ => return false,
+        }
+        let prev = match self.prev_code {
+            Some(prev) => &self.toks[prev],
+            None => return true,
+        };
+        !matches!(
+            prev.kind,
+            SimpleIdent
+                | EscapedIdent
+                | SystemCallIdent
+                | Literal { .. }
+                | CompilerDirective
+                | CloseParen
+                | CloseBracket
+                | CloseBrace
+        )
+    }
+
+    /// The whitespace between the token `i` and the previous token on the same line
+    fn space(&self, i: usize) -> &'a str {
+        let tok = &self.toks[i];
+        let prev = &self.toks[self.prev.unwrap()];
+        let keep = !tok.ws.is_empty();
+        let space = |space: bool| if space { " " } else { "" };
+
+        match (prev.role, tok.role) {
+            // the whitespace around comments is usually used for alignment
+            (Role::Comment, _) | (_, Role::Comment | Role::Raw) => return tok.ws,
+            (Role::Code, Role::Code) => (),
+            _ => return " ",
+        }
+        // escaped identifiers are terminated by whitespace
+        if prev.kind == EscapedIdent {
+            return " ";
+        }
+        if prev.kind == Unknown || tok.kind == Unknown {
+            return space(keep);
+        }
+        // `FOO (x) and `FOO(x) are different macro calls
+        if prev.kind == CompilerDirective && tok.kind == OpenParen {
+            return space(keep);
+        }
+
+        // removing whitespace must never merge two tokens
+        space(self.space_between(prev, tok) || (keep && merges(prev, tok)))
+    }
+
+    fn space_between(&self, prev: &Tok, tok: &Tok) -> bool {
+        match tok.kind {
+            Comma | Semi | CloseParen | CloseBracket | CloseBrace => return false,
+            AttrCloseParen => return true,
+            _ => (),
+        }
+        match prev.kind {
+            OpenParen | OpenBracket | OpenBrace | ArrStart | At | Pound | Dot => return false,
+            AttrOpenParen | Comma | Semi => return true,
+            _ => (),
+        }
+        if self.prev_unary || self.port {
+            return false;
+        }
+
+        let is_name = |tok: &Tok| match tok.kind {
+            SimpleIdent => !is_keyword(tok.text),
+            SystemCallIdent => true,
+            _ => false,
+        };
+        match tok.kind {
+            OpenParen => !is_name(prev),
+            OpenBracket | Dot => !(is_name(prev) || matches!(prev.kind, CloseParen | CloseBracket)),
+            Colon => self.spaced_colon(),
+            _ if prev.kind == Colon => self.prev_spaced_colon || self.state.parens == 0,
+            _ => true,
+        }
+    }
+}
@@ -0,0 +1,489 @@
+//! Structural formatting decisions derived from the syntax tree.
+//!
+//! The syntax tree is built from the preprocessed token stream. Every syntax token that was
+//! written directly in the formatted file is mapped back to its source token. A construct is only
+//! restructured if every source token it spans is part of the tree. Constructs that contain
+//! compiler directives, macro calls or inactive `` `ifdef `` branches are therefore left to
+//! the whitespace rules of the [`Formatter`](crate::format::Formatter).
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::iter::once;
+use std::mem::take;
+use std::sync::Arc;
+
+use syntax::ast::{self, AssignOp, AstNode, AttrsOwner};
+use syntax::sourcemap::{SourceContext, SourceMap};
+use syntax::{
+    preprocess, Parse, SourceFile, SourceProvider, SyntaxKind, SyntaxNode, SyntaxToken, TextSize, T,
+};
+use tokens::lexer::TokenKind;
+use vfs::{FileId, FileReadError, Vfs, VfsPath};
+
+use crate::source::{Role, Tok};
+use crate::{AttrPlacement, BeginPlacement, Config};
+
+/// How the line break in front of a token is changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub(crate) enum Break {
+    /// Keep the line breaks of the source
+    #[default]
+    Keep,
+    /// Start a new line
+    Line,
+    /// Start a new line that is indented one level less than usual (the `)` of a port list)
+    Close,
+    /// Continue the previous line
+    Join,
+}
+
+/// A single line declaration or statement. The `anchors` of consecutive items are aligned.
+pub(crate) struct AlignItem {
+    pub first: usize,
+    pub last: usize,
+    pub anchors: Vec<usize>,
+}
+
+pub(crate) struct Layout {
+    /// The change to the line break in front of each token
+    pub breaks: Vec<Break>,
+    /// Runs of sibling items that are aligned when they are placed on consecutive lines
+    pub align: Vec<Vec<AlignItem>>,
+}
+
+impl Layout {
+    pub fn new(src: &str, toks: &[Tok], config: &Config) -> Layout {
+        let sources = Sources::new(src);
+        let preprocess = preprocess(&sources, sources.root);
+        let parse = SourceFile::parse(&sources, sources.root, &preprocess);
+        let mut builder = Builder::new(toks, &parse, &preprocess.sm, config);
+        for node in parse.syntax_node().descendants() {
+            builder.node(&node);
+        }
+        builder.layout
+    }
+}
+
+/// Provides the formatted file (and the standard library) to the preprocessor.
+/// Other includes are never resolved.
+struct Sources {
+    vfs: RefCell<Vfs>,
+    root: FileId,
+    include_dirs: Arc<[VfsPath]>,
+}
+
+impl Sources {
+    fn new(src: &str) -> Sources {
+        let mut vfs = Vfs::default();
+        vfs.insert_std_lib();
+        let root = vfs.add_virt_file("/source.va", src.to_owned().into());
+        let include_dirs = Arc::new([VfsPath::new_virtual_path("/std".to_owned())]);
+        Sources { vfs: RefCell::new(vfs), root, include_dirs }
+    }
+}
+
+impl SourceProvider for Sources {
+    fn include_dirs(&self, _root_file: FileId) -> Arc<[VfsPath]> {
+        self.include_dirs.clone()
+    }
+
+    fn macro_flags(&self, _file_root: FileId) -> Arc<[Arc<str>]> {
+        Arc::new([])
+    }
+
+    fn file_text(&self, file: FileId) -> Result<Arc<str>, FileReadError> {
+        self.vfs.borrow().file_contents(file).map(Arc::from)
+    }
+
+    fn file_path(&self, file: FileId) -> VfsPath {
+        self.vfs.borrow().file_path(file)
+    }
+
+    fn file_id(&self, path: VfsPath) -> FileId {
+        self.vfs.borrow_mut().ensure_file_id(path)
+    }
+}
+
+struct Builder<'a, 'b> {
+    toks: &'b [Tok<'a>],
+    config: &'b Config,
+    /// The source token of each syntax token (identified by its offset in the tree)
+    index: HashMap<TextSize, usize>,
+    /// Whether a source token is part of the syntax tree
+    mapped: Vec<bool>,
+    layout: Layout,
+}
+
+impl<'a, 'b> Builder<'a, 'b> {
+    fn new(
+        toks: &'b [Tok<'a>],
+        parse: &Parse<SourceFile>,
+        sm: &SourceMap,
+        config: &'b Config,
+    ) -> Builder<'a, 'b> {
+        let mut index = HashMap::new();
+        let mut mapped = vec![false; toks.len()];
+        let tokens = parse.syntax_node().descendants_with_tokens().filter_map(|it| it.into_token());
+        for token in tokens {
+            if token.kind().is_trivia() {
+                continue;
+            }
+            let span = parse.to_ctx_span(token.text_range(), sm);
+            if span.ctx != SourceContext::ROOT {
+                continue;
+            }
+            let start = u32::from(span.range.start()) as usize;
+            if let Ok(i) = toks.binary_search_by_key(&start, |tok| tok.start) {
+                if toks[i].role == Role::Code && toks[i].text == token.text() {
+                    index.insert(token.text_range().start(), i);
+                    mapped[i] = true;
+                }
+            }
+        }
+
+        let layout = Layout { breaks: vec![Break::Keep; toks.len()], align: Vec::new() };
+        Builder { toks, config, index, mapped, layout }
+    }
+
+    fn node(&mut self, node: &SyntaxNode) {
+        match node.kind() {
+            SyntaxKind::MODULE_DECL => {
+                self.module_header(node);
+                self.align(node);
+            }
+            SyntaxKind::BLOCK_STMT => {
+                self.begin(node);
+                self.align(node);
+            }
+            SyntaxKind::IF_STMT => self.else_(node),
+            SyntaxKind::ATTR_LIST => self.attributes(node),
+            _ => (),
+        }
+    }
+
+    /// The source token of a syntax token
+    fn idx(&self, token: Option<SyntaxToken>) -> Option<usize> {
+        self.index.get(&token?.text_range().start()).copied()
+    }
+
+    /// The source token of the first syntax token of `node` that is not trivia
+    fn first(&self, node: &SyntaxNode) -> Option<usize> {
+        let mut tokens = node.descendants_with_tokens().filter_map(|it| it.into_token());
+        self.idx(tokens.find(|token| !token.kind().is_trivia()))
+    }
+
+    /// The first and last source token of `node` if the whole node can be restructured
+    fn range(&self, node: &SyntaxNode) -> Option<(usize, usize)> {
+        if node.descendants().any(|node| node.kind() == SyntaxKind::ERROR) {
+            return None;
+        }
+        let mut tokens = node
+            .descendants_with_tokens()
+            .filter_map(|it| it.into_token())
+            .filter(|token| !token.kind().is_trivia());
+        let first = self.idx(tokens.next())?;
+        let last = match tokens.last() {
+            Some(token) => self.idx(Some(token))?,
+            None => first,
+        };
+        self.is_clean(first, last).then_some((first, last))
+    }
+
+    /// Whether the source tokens `first..=last` only consist of comments and tokens that are
+    /// part of the syntax tree
+    fn is_clean(&self, first: usize, last: usize) -> bool {
+        first <= last
+            && (first..=last).all(|i| self.mapped[i] || self.toks[i].role == Role::Comment)
+    }
+
+    fn set(&mut self, i: usize, brk: Break) {
+        // a line break can only be removed between two code tokens (not after a line comment)
+        if brk == Break::Join
+            && (i == 0 || self.toks[i - 1].role != Role::Code || self.toks[i].ws.contains('\\'))
+        {
+            return;
+        }
+        self.layout.breaks[i] = brk;
+    }
+
+    fn placement(&self) -> Option<Break> {
+        match self.config.begin {
+            BeginPlacement::Preserve => None,
+            BeginPlacement::SameLine => Some(Break::Join),
+            BeginPlacement::NextLine => Some(Break::Line),
+        }
+    }
+
+    /// The port list of a module is placed on a single line if the header fits into
+    /// [`Config::max_width`]. Otherwise each port is placed on its own line.
+    fn module_header(&mut self, node: &SyntaxNode) {
+        let module = ast::ModuleDecl::cast(node.clone()).unwrap();
+        let ports = match module.module_ports() {
+            Some(ports) => ports,
+            None => return,
+        };
+        let (first, semi) =
+            match (self.idx(module.module_token()), self.idx(module.semicolon_token())) {
+                (Some(first), Some(semi)) => (first, semi),
+                _ => return,
+            };
+        let header = &self.toks[first..=semi];
+        if ports.syntax().descendants().any(|node| node.kind() == SyntaxKind::ERROR)
+            || !self.is_clean(first, semi)
+            || header.iter().any(|tok| tok.role == Role::Comment)
+        {
+            return;
+        }
+
+        for i in first + 1..=semi {
+            self.set(i, Break::Join);
+        }
+        if header_width(header) <= self.config.max_width as usize {
+            return;
+        }
+        for port in ports.syntax().children() {
+            if let Some(i) = self.first(&port) {
+                self.set(i, Break::Line);
+            }
+        }
+        if let Some(close) = self.idx(ports.r_paren_token()) {
+            self.set(close, Break::Close);
+        }
+    }
+
+    /// Attributes of declarations (most importantly parameters) are placed on the same line
+    /// as the declaration or on their own line.
+    fn attributes(&mut self, node: &SyntaxNode) {
+        let brk = match self.config.attributes {
+            AttrPlacement::Preserve => return,
+            AttrPlacement::SameLine => Break::Join,
+            AttrPlacement::OwnLine => Break::Line,
+        };
+        let decl = match node.parent() {
+            Some(parent) if is_decl(parent.kind()) => parent,
+            _ => return,
+        };
+        let close = ast::AttrList::cast(node.clone()).and_then(|attrs| attrs.r_attr_paren_token());
+        let (close, (_, last)) = match (self.idx(close), self.range(&decl)) {
+            (Some(close), Some(range)) => (close, range),
+            _ => return,
+        };
+        let next = close + 1;
+        // multiple attribute lists stay together
+        if next <= last
+            && self.toks[next].role == Role::Code
+            && self.toks[next].kind != TokenKind::AttrOpenParen
+        {
+            self.set(next, brk)
+        }
+    }
+
+    /// `begin` is placed on the same line as the statement it belongs to or on the next line.
+    fn begin(&mut self, node: &SyntaxNode) {
+        let brk = match self.placement() {
+            Some(brk) => brk,
+            None => return,
+        };
+        if !node.parent().is_some_and(|parent| is_block_owner(parent.kind())) {
+            return;
+        }
+        let block = ast::BlockStmt::cast(node.clone()).unwrap();
+        if block.attrs().next().is_some() {
+            return;
+        }
+        // the header must directly precede `begin`
+        match self.idx(block.begin_token()) {
+            Some(begin) if begin != 0 && self.mapped[begin - 1] => self.set(begin, brk),
+            _ => (),
+        }
+    }
+
+    /// `else` is placed after the `end` of the preceding block like `begin`.
+    fn else_(&mut self, node: &SyntaxNode) {
+        let brk = match self.placement() {
+            Some(brk) => brk,
+            None => return,
+        };
+        let stmt = ast::IfStmt::cast(node.clone()).unwrap();
+        let else_ = match self.idx(stmt.else_token()) {
+            Some(else_) if else_ != 0 => else_,
+            _ => return,
+        };
+        if self.mapped[else_ - 1] && self.toks[else_ - 1].text == "end" {
+            self.set(else_, brk)
+        }
+    }
+
+    /// Collects the runs of consecutive parameter declarations and contributions in a module
+    /// or block.
+    fn align(&mut self, node: &SyntaxNode) {
+        let mut run = Vec::new();
+        let mut run_kind = None;
+        for child in node.children() {
+            let item = match child.kind() {
+                SyntaxKind::PARAM_DECL if self.config.align_parameters => self.parameter(&child),
+                SyntaxKind::ASSIGN_STMT if self.config.align_contributions => {
+                    self.contribution(&child)
+                }
+                _ => None,
+            };
+            if item.is_none() || run_kind != Some(child.kind()) {
+                self.finish_run(&mut run);
+                run_kind = item.as_ref().map(|_| child.kind());
+            }
+            run.extend(item);
+        }
+        self.finish_run(&mut run);
+    }
+
+    fn finish_run(&mut self, run: &mut Vec<AlignItem>) {
+        if run.len() > 1 {
+            self.layout.align.push(take(run))
+        } else {
+            run.clear()
+        }
+    }
+
+    /// Aligns the name and the `=` of the (first) parameter
+    fn parameter(&self, node: &SyntaxNode) -> Option<AlignItem> {
+        let decl = ast::ParamDecl::cast(node.clone())?;
+        let (_, last) = self.range(node)?;
+        let first = self.idx(decl.parameter_token().or_else(|| decl.localparam_token()))?;
+        let param = decl.paras().next()?;
+        let name = self.idx(param.name()?.syntax().first_token())?;
+        let eq = self.idx(param.eq_token())?;
+        Some(AlignItem { first, last, anchors: vec![name, eq] })
+    }
+
+    /// Aligns the `<+`
+    fn contribution(&self, node: &SyntaxNode) -> Option<AlignItem> {
+        let stmt = ast::AssignStmt::cast(node.clone())?;
+        let assign = stmt.assign()?;
+        if assign.op()? != AssignOp::Contribute {
+            return None;
+        }
+        let (_, last) = self.range(node)?;
+        let first = self.first(assign.syntax())?;
+        let op = assign
+            .syntax()
+            .children_with_tokens()
+            .filter_map(|it| it.into_token())
+            .find(|token| token.kind() == T![<+]);
+        let op = self.idx(op)?;
+        Some(AlignItem { first, last, anchors: vec![op] })
+    }
+}
+
+fn is_decl(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::PARAM_DECL
+            | SyntaxKind::ALIAS_PARAM
+            | SyntaxKind::VAR_DECL
+            | SyntaxKind::NET_DECL
+            | SyntaxKind::PORT_DECL
+            | SyntaxKind::BODY_PORT_DECL
+            | SyntaxKind::BRANCH_DECL
+    )
+}
+
+/// Whether `begin` can follow the header of a statement of this kind
+fn is_block_owner(kind: SyntaxKind) -> bool {
+    matches!(
+        kind,
+        SyntaxKind::ANALOG_BEHAVIOUR
+            | SyntaxKind::IF_STMT
+            | SyntaxKind::FOR_STMT
+            | SyntaxKind::WHILE_STMT
+            | SyntaxKind::EVENT_STMT
+            | SyntaxKind::CASE
+    )
+}
+
+/// The width of a module header that is placed on a single line
+fn header_width(header: &[Tok]) -> usize {
+    let is_word = |tok: &Tok| matches!(tok.kind, TokenKind::SimpleIdent | TokenKind::EscapedIdent);
+    let spaces = header.windows(2).filter(|pair| {
+        matches!(pair[0].kind, TokenKind::Comma | TokenKind::AttrOpenParen)
+            || pair[1].kind == TokenKind::AttrCloseParen
+            || (is_word(&pair[0]) && is_word(&pair[1]))
+    });
+    header.iter().map(|tok| tok.text.len()).sum::<usize>() + spaces.count()
+}
+
+/// Pads the anchors of each run of items that are placed on consecutive lines of `dst`
+/// (at byte offsets `pos`) so that they start in the same column.
+pub(crate) fn align(dst: &str, pos: &[Option<usize>], runs: &[Vec<AlignItem>]) -> String {
+    let line_starts: Vec<usize> =
+        once(0).chain(dst.match_indices('\n').map(|(i, _)| i + 1)).collect();
+    let line = |offset: usize| line_starts.partition_point(|&start| start <= offset) - 1;
+
+    let mut padding = Vec::new();
+    for run in runs {
+        let mut lines: Vec<(usize, Vec<usize>)> = Vec::new();
+        for item in run {
+            let placed = (|| {
+                let first = pos[item.first]?;
+                let line_no = line(first);
+                let anchors = item.anchors.iter().map(|&i| pos[i]).collect::<Option<Vec<_>>>()?;
+                let single_line = line(pos[item.last]?) == line_no
+                    && dst[line_starts[line_no]..first].trim().is_empty();
+                single_line.then_some((line_no, anchors))
+            })();
+
+            let continues = match (&placed, lines.last()) {
+                (Some((line_no, anchors)), Some((prev, prev_anchors))) => {
+                    prev + 1 == *line_no && prev_anchors.len() == anchors.len()
+                }
+                _ => false,
+            };
+            if !continues {
+                pad(dst, &line_starts, &lines, &mut padding);
+                lines.clear();
+            }
+            lines.extend(placed);
+        }
+        pad(dst, &line_starts, &lines, &mut padding);
+    }
+
+    padding.sort_unstable();
+    let mut res =
+        String::with_capacity(dst.len() + padding.iter().map(|(_, width)| width).sum::<usize>());
+    let mut copied = 0;
+    for (offset, width) in padding {
+        res.push_str(&dst[copied..offset]);
+        res.extend((0..width).map(|_| ' '));
+        copied = offset;
+    }
+    res.push_str(&dst[copied..]);
+    res
+}
+
+fn pad(
+    dst: &str,
+    line_starts: &[usize],
+    lines: &[(usize, Vec<usize>)],
+    padding: &mut Vec<(usize, usize)>,
+) {
+    if lines.len() < 2 {
+        return;
+    }
+    // the padding inserted in front of the previous anchors of each line
+    let mut shift = vec![0; lines.len()];
+    for anchor in 0..lines[0].1.len() {
+        let columns: Vec<usize> = lines
+            .iter()
+            .zip(&shift)
+            .map(|((line, anchors), shift)| {
+                dst[line_starts[*line]..anchors[anchor]].chars().count() + shift
+            })
+            .collect();
+        let max = columns.iter().copied().max().unwrap_or(0);
+        for (i, ((_, anchors), column)) in lines.iter().zip(columns).enumerate() {
+            if column != max {
+                padding.push((anchors[anchor], max - column));
+                shift[i] += max - column;
+            }
+        }
+    }
+}
@@ -0,0 +1,145 @@
+//! A formatter for Verilog-A source files.
+//!
+//! Real world compact models rely heavily on macros, includes and `` `ifdef `` so the formatter
+//! works on the output of the lexer and only ever changes whitespace and line breaks.
+//! The basic layout is derived from the tokens alone:
+//!
+//! * every line is indented according to the nesting of blocks (`module`, `begin`, `case`, ...)
+//!   with an additional level for lines that continue a statement,
+//! * spaces around operators, commas, brackets and attributes are normalized,
+//! * statements that follow a `;` or `begin` on the same line (and `end` keywords that follow
+//!   a statement) are moved to their own line,
+//! * compiler directives are moved to the first column, trailing whitespace is removed and
+//!   consecutive blank lines are collapsed.
+//!
+//! On top of that the syntax tree is used to restructure modules, declarations and statements
+//! in the style selected by the [`Config`]:
+//!
+//! * the port list of a module is placed on a single line (or split into one port per line if
+//!   it is longer than [`Config::max_width`]),
+//! * attributes are placed on the same line as the declaration they belong to (or on their own line),
+//! * `begin` is placed on the same line as `analog`, `if`, `else`, ... (or on the next line),
+//! * the names and default values of consecutive parameter declarations and the `<+` of
+//!   consecutive contributions are aligned.
+//!
+//! The syntax tree is built from the preprocessed source. A construct is only restructured if
+//! it does not contain any compiler directive or macro call, so regions under `` `ifdef `` are
+//! never restructured. `` `ifdef `` regions are indented when all of their branches change the
+//! nesting in the same way. Otherwise the whole region is copied unchanged. Macro definitions
+//! are never changed.
+//!
+//! As a safety net the result is lexed again and compared to the original tokens, a file is
+//! never changed in any way other than whitespace.
+
+use std::error::Error;
+use std::fmt::{self, Display};
+
+use crate::conditional::Conditionals;
+use crate::format::Formatter;
+use crate::layout::Layout;
+
+mod conditional;
+mod format;
+mod layout;
+mod source;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(u32),
+    Tabs,
+}
+
+/// Where `begin` is placed relative to the statement it belongs to (and `else` relative to the
+/// `end` of the preceding block)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BeginPlacement {
+    /// `if (x) begin` and `end else begin`
+    SameLine,
+    /// `begin` and `else` start a new line
+    NextLine,
+    /// keep the line breaks of the source
+    Preserve,
+}
+
+/// Where the attributes of a declaration are placed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttrPlacement {
+    /// `(* units="V" *) parameter real vth = 0.5;`
+    SameLine,
+    /// the attributes are placed on the line before the declaration
+    OwnLine,
+    /// keep the line breaks of the source
+    Preserve,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// The indentation used for each level of nesting
+    pub indent: Indent,
+    /// Longer runs of blank lines are collapsed to this many blank lines
+    pub max_blank_lines: u32,
+    /// Module headers that are longer than this are split into one port per line
+    pub max_width: u32,
+    pub begin: BeginPlacement,
+    pub attributes: AttrPlacement,
+    /// Align the names and default values of parameters declared on consecutive lines
+    pub align_parameters: bool,
+    /// Align the `<+` of contributions on consecutive lines
+    pub align_contributions: bool,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            indent: Indent::Spaces(4),
+            max_blank_lines: 1,
+            max_width: 100,
+            begin: BeginPlacement::SameLine,
+            attributes: AttrPlacement::SameLine,
+            align_parameters: true,
+            align_contributions: true,
+        }
+    }
+}
+
+/// Formatting would have changed the tokens of a file. This is always a bug in the formatter,
+/// the file is left unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatError {
+    /// The byte offset of the first token that would have been changed
+    pub offset: usize,
+}
+
+impl Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "formatting would change the token at byte {}, this is a bug in openvaf-fmt",
+            self.offset
+        )
+    }
+}
+
+impl Error for FormatError {}
+
+/// Formats the Verilog-A source `src`.
+pub fn format(src: &str, config: &Config) -> Result<String, FormatError> {
+    let toks = source::lex(src);
+    let cond = Conditionals::new(&toks);
+    let layout = Layout::new(src, &toks, config);
+    let res = Formatter::new(src, &toks, &cond, &layout, config).run();
+
+    let new_toks = source::lex(&res);
+    let changed = toks.iter().zip(&new_toks).position(|(old, new)| !old.same_as(new));
+    match changed {
+        Some(i) => Err(FormatError { offset: toks[i].start }),
+        None if toks.len() != new_toks.len() => {
+            let offset = toks.get(new_toks.len()).map_or(src.len(), |tok| tok.start);
+            Err(FormatError { offset })
+        }
+        None => Ok(res),
+    }
+}
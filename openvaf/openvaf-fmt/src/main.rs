@@ -0,0 +1,160 @@
+//! `openvaf-fmt` formats Verilog-A files in place. If no files are passed the source is read
+//! from stdin and the formatted source is written to stdout.
+
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::process::exit;
+
+use anyhow::{Context, Result};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command, ValueHint};
+use openvaf_fmt::{AttrPlacement, BeginPlacement, Config, Indent};
+
+const CHECK: &str = "check";
+const INDENT: &str = "indent";
+const TABS: &str = "tabs";
+const MAX_BLANK_LINES: &str = "max-blank-lines";
+const MAX_WIDTH: &str = "max-width";
+const BEGIN: &str = "begin";
+const ATTRIBUTES: &str = "attributes";
+const NO_ALIGN_PARAMETERS: &str = "no-align-parameters";
+const NO_ALIGN_CONTRIBUTIONS: &str = "no-align-contributions";
+const FILES: &str = "files";
+
+fn main_command() -> Command {
+    Command::new("openvaf-fmt")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Formats Verilog-A source files")
+        .args([
+            Arg::new(CHECK)
+                .long(CHECK)
+                .action(ArgAction::SetTrue)
+                .help("List the files that are not formatted instead of changing them."),
+            Arg::new(INDENT)
+                .long(INDENT)
+                .value_name("WIDTH")
+                .value_parser(value_parser!(u32))
+                .default_value("4")
+                .help("Number of spaces used for each level of indentation."),
+            Arg::new(TABS)
+                .long(TABS)
+                .action(ArgAction::SetTrue)
+                .conflicts_with(INDENT)
+                .help("Indent with tabs instead of spaces."),
+            Arg::new(MAX_BLANK_LINES)
+                .long(MAX_BLANK_LINES)
+                .value_name("N")
+                .value_parser(value_parser!(u32))
+                .default_value("1")
+                .help("Maximum number of consecutive blank lines."),
+            Arg::new(MAX_WIDTH)
+                .long(MAX_WIDTH)
+                .value_name("WIDTH")
+                .value_parser(value_parser!(u32))
+                .default_value("100")
+                .help("Module headers that are longer are split into one port per line."),
+            Arg::new(BEGIN)
+                .long(BEGIN)
+                .value_parser(["same-line", "next-line", "preserve"])
+                .default_value("same-line")
+                .help("Place `begin` (and `else` after `end`) on the same line as the statement or on the next line."),
+            Arg::new(ATTRIBUTES)
+                .long(ATTRIBUTES)
+                .value_parser(["same-line", "own-line", "preserve"])
+                .default_value("same-line")
+                .help("Place the attributes of declarations on the same line as the declaration or on their own line."),
+            Arg::new(NO_ALIGN_PARAMETERS)
+                .long(NO_ALIGN_PARAMETERS)
+                .action(ArgAction::SetTrue)
+                .help("Do not align parameter declarations on consecutive lines."),
+            Arg::new(NO_ALIGN_CONTRIBUTIONS)
+                .long(NO_ALIGN_CONTRIBUTIONS)
+                .action(ArgAction::SetTrue)
+                .help("Do not align contributions on consecutive lines."),
+            Arg::new(FILES)
+                .value_name("FILE")
+                .num_args(0..)
+                .value_parser(value_parser!(PathBuf))
+                .value_hint(ValueHint::FilePath)
+                .help("The files to format. Reads from stdin if no files are specified."),
+        ])
+}
+
+fn main() {
+    let matches = main_command().get_matches();
+    match run(matches) {
+        Ok(code) => exit(code),
+        Err(err) => {
+            eprintln!("error: {err:#}");
+            exit(1)
+        }
+    }
+}
+
+fn run(matches: ArgMatches) -> Result<i32> {
+    let indent = if matches.get_flag(TABS) {
+        Indent::Tabs
+    } else {
+        Indent::Spaces(*matches.get_one(INDENT).unwrap())
+    };
+    let begin = match matches.get_one::<String>(BEGIN).unwrap().as_str() {
+        "same-line" => BeginPlacement::SameLine,
+        "next-line" => BeginPlacement::NextLine,
+        _ => BeginPlacement::Preserve,
+    };
+    let attributes = match matches.get_one::<String>(ATTRIBUTES).unwrap().as_str() {
+        "same-line" => AttrPlacement::SameLine,
+        "own-line" => AttrPlacement::OwnLine,
+        _ => AttrPlacement::Preserve,
+    };
+    let config = Config {
+        indent,
+        max_blank_lines: *matches.get_one(MAX_BLANK_LINES).unwrap(),
+        max_width: *matches.get_one(MAX_WIDTH).unwrap(),
+        begin,
+        attributes,
+        align_parameters: !matches.get_flag(NO_ALIGN_PARAMETERS),
+        align_contributions: !matches.get_flag(NO_ALIGN_CONTRIBUTIONS),
+    };
+    let check = matches.get_flag(CHECK);
+    let files: Vec<PathBuf> =
+        matches.get_many(FILES).map(|files| files.cloned().collect()).unwrap_or_default();
+
+    if files.is_empty() {
+        let mut src = String::new();
+        io::stdin().read_to_string(&mut src).context("failed to read stdin")?;
+        let formatted = openvaf_fmt::format(&src, &config)?;
+        if check {
+            return Ok(i32::from(formatted != src));
+        }
+        io::stdout().write_all(formatted.as_bytes())?;
+        return Ok(0);
+    }
+
+    let mut code = 0;
+    for file in files {
+        let src = fs::read_to_string(&file)
+            .with_context(|| format!("failed to read {}", file.display()))?;
+        let formatted = match openvaf_fmt::format(&src, &config) {
+            Ok(formatted) => formatted,
+            Err(err) => {
+                eprintln!("error: {}: {err}", file.display());
+                code = 1;
+                continue;
+            }
+        };
+
+        if formatted == src {
+            continue;
+        }
+        if check {
+            println!("{}", file.display());
+            code = 1;
+        } else {
+            fs::write(&file, formatted)
+                .with_context(|| format!("failed to write {}", file.display()))?;
+        }
+    }
+
+    Ok(code)
+}
@@ -0,0 +1,149 @@
+use tokens::lexer::TokenKind;
+
+/// A token of the source file together with the whitespace in front of it.
+/// Whitespace tokens themselves never appear in the token stream the formatter works on.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Tok<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// The whitespace between the previous token and this token
+    pub ws: &'a str,
+    /// The byte offset of `text` within the source file
+    pub start: usize,
+    pub role: Role,
+}
+
+impl Tok<'_> {
+    /// Whether two tokens are the same except for surrounding whitespace
+    pub fn same_as(&self, other: &Tok) -> bool {
+        let kind = |tok: &Tok| match tok.kind {
+            TokenKind::Define { .. } => TokenKind::Define { end: 0 },
+            kind => kind,
+        };
+        kind(self) == kind(other) && self.text == other.text
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Role {
+    Code,
+    Comment,
+    /// A compiler directive (macro calls are [`Role::Code`])
+    Directive(Directive),
+    /// The argument of the preceding compiler directive (for example the name after `` `ifdef ``)
+    DirectiveArg,
+    /// A token that is part of a directive that is passed through unchanged (`` `timescale ``)
+    Raw,
+    /// A complete macro definition including its body
+    Define,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Directive {
+    IfDef,
+    ElsIf,
+    Else,
+    EndIf,
+    Other,
+}
+
+enum Args {
+    None,
+    One,
+    Line,
+}
+
+fn directive(name: &str) -> Option<(Directive, Args)> {
+    let res = match name {
+        "`ifdef" | "`ifndef" => (Directive::IfDef, Args::One),
+        "`elsif" => (Directive::ElsIf, Args::One),
+        "`else" => (Directive::Else, Args::None),
+        "`endif" => (Directive::EndIf, Args::None),
+        "`include" | "`undef" => (Directive::Other, Args::One),
+        "`resetall" | "`celldefine" | "`endcelldefine" | "`nounconnected_drive" => {
+            (Directive::Other, Args::None)
+        }
+        "`timescale"
+        | "`default_nettype"
+        | "`default_discipline"
+        | "`default_transition"
+        | "`unconnected_drive"
+        | "`line"
+        | "`pragma"
+        | "`begin_keywords"
+        | "`end_keywords" => (Directive::Other, Args::Line),
+        _ => return None,
+    };
+    Some(res)
+}
+
+/// Lexes `src` into the tokens the formatter operates on.
+///
+/// Macro definitions are whitespace sensitive, so the complete `` `define `` (including its
+/// body) is turned into a single token that is never changed. Trailing whitespace is removed
+/// from line comments and macro definitions.
+pub(crate) fn lex(src: &str) -> Vec<Tok<'_>> {
+    let raw = lexer::tokenize(src);
+    let mut offsets = Vec::with_capacity(raw.len() + 1);
+    let mut offset = 0;
+    offsets.push(0);
+    for token in &raw {
+        offset += u32::from(token.len) as usize;
+        offsets.push(offset);
+    }
+
+    let mut res = Vec::with_capacity(raw.len() / 2);
+    let mut ws_start = 0;
+    let mut args = Args::None;
+    let mut i = 0;
+    while i < raw.len() {
+        let kind = raw[i].kind;
+        let start = offsets[i];
+        let mut end = offsets[i + 1];
+        let mut next = i + 1;
+        let ws = &src[ws_start..start];
+        if ws.contains('\n') {
+            args = Args::None;
+        }
+
+        let role = match kind {
+            TokenKind::Whitespace => {
+                i += 1;
+                continue;
+            }
+            TokenKind::Define { end: body_end } => {
+                next = body_end.clamp(i + 1, raw.len());
+                end = start + src[start..offsets[next]].trim_end_matches([' ', '\t', '\r']).len();
+                Role::Define
+            }
+            TokenKind::LineComment => {
+                end = start + src[start..end].trim_end_matches([' ', '\t', '\r']).len();
+                Role::Comment
+            }
+            TokenKind::BlockComment { .. } => Role::Comment,
+            _ => match args {
+                Args::One => {
+                    args = Args::None;
+                    Role::DirectiveArg
+                }
+                Args::Line => Role::Raw,
+                Args::None if kind == TokenKind::CompilerDirective => {
+                    match directive(&src[start..end]) {
+                        Some((directive, directive_args)) => {
+                            args = directive_args;
+                            Role::Directive(directive)
+                        }
+                        None => Role::Code,
+                    }
+                }
+                Args::None => Role::Code,
+            },
+        };
+
+        res.push(Tok { kind, text: &src[start..end], ws, start, role });
+        ws_start = end;
+        i = next;
+    }
+
+    res
+}
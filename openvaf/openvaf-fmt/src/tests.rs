@@ -0,0 +1,485 @@
+use expect_test::{expect, Expect};
+
+use crate::{format, AttrPlacement, BeginPlacement, Config, Indent};
+
+fn check_with(src: &str, config: &Config, expect: Expect) {
+    let actual = format(src, config).unwrap();
+    expect.assert_eq(&actual);
+    assert_eq!(format(&actual, config).unwrap(), actual, "formatting is not idempotent");
+}
+
+fn check(src: &str, expect: Expect) {
+    check_with(src, &Config::default(), expect)
+}
+
+#[test]
+fn module_items() {
+    check(
+        r#"
+`include "disciplines.vams"
+module diode(A,C);
+inout A,C;   electrical A,C,CI;
+  (*desc= "Saturation current", units = "A"*)   parameter real is=1e-14 from [0:inf);
+(*desc="Emission coefficient"*)
+parameter real n = 1.0 from(0:inf) exclude 2;
+      branch (A,CI) br_a_ci;
+real vd,id;
+endmodule
+"#,
+        expect![[r#"
+            `include "disciplines.vams"
+            module diode(A, C);
+                inout A, C;
+                electrical A, C, CI;
+                (* desc = "Saturation current", units = "A" *) parameter real is = 1e-14 from [0:inf);
+                (* desc = "Emission coefficient" *) parameter real n = 1.0 from (0:inf) exclude 2;
+                branch (A, CI) br_a_ci;
+                real vd, id;
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn analog_block() {
+    check(
+        r#"
+module test(a,b);
+inout a,b; electrical a,b;
+analog begin : evaluate
+if(V(a,b)>0) begin
+id=is*(limexp(V(a,b)/(n*$vt))-1); end
+else id = -is;
+case(mode)
+0,1: x=y?-1:+1;
+default: begin x=0; end
+endcase
+I(a,b)<+id+ddt(V(a,b)*c0)
+  +white_noise(4*`P_K*$temperature/r, "thermal");
+I(<a>) <+ !flag;
+end
+endmodule
+"#,
+        expect![[r#"
+            module test(a, b);
+                inout a, b;
+                electrical a, b;
+                analog begin : evaluate
+                    if (V(a, b) > 0) begin
+                        id = is * (limexp(V(a, b) / (n * $vt)) - 1);
+                    end else id = -is;
+                    case (mode)
+                        0, 1: x = y ? -1 : +1;
+                        default: begin
+                            x = 0;
+                        end
+                    endcase
+                    I(a, b) <+ id + ddt(V(a, b) * c0)
+                        + white_noise(4 * `P_K * $temperature / r, "thermal");
+                    I(<a>) <+ !flag;
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn functions() {
+    check(
+        r#"
+analog function real clamp;
+input x,lo,hi; real x,lo,hi;
+begin
+clamp = min(max(x,lo),hi);
+end
+endfunction
+"#,
+        expect![[r#"
+            analog function real clamp;
+                input x, lo, hi;
+                real x, lo, hi;
+                begin
+                    clamp = min(max(x, lo), hi);
+                end
+            endfunction
+        "#]],
+    );
+}
+
+#[test]
+fn macros() {
+    check(
+        r#"
+`define  MAX(a,b)  ((a)>(b) ? (a):(b))
+`define STMT(x) \
+    x=x+1;   \
+      x = x*2;
+module test;
+real x;
+analog begin
+`STMT(x)
+x=`MAX(x,1)+`MAX (x, 2);
+end
+endmodule
+"#,
+        expect![[r#"
+            `define  MAX(a,b)  ((a)>(b) ? (a):(b))
+            `define STMT(x) \
+                x=x+1;   \
+                  x = x*2;
+            module test;
+                real x;
+                analog begin
+                    `STMT(x)
+                    x = `MAX(x, 1) + `MAX (x, 2);
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn comments() {
+    check(
+        "
+// header
+
+
+module test; /* block */ real x;
+  real y;      // aligned comment
+    /* multi
+       line */
+analog x = 1;
+endmodule
+",
+        expect![[r#"
+            // header
+
+            module test; /* block */
+                real x;
+                real y;      // aligned comment
+                /* multi
+                   line */
+                analog x = 1;
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn ifdef() {
+    check(
+        r#"
+module test;
+analog begin
+`ifdef SELFHEATING
+if (rth>0)
+begin
+`else
+if (1) begin
+`endif
+x=1;
+end
+`ifdef DEBUG
+  begin
+`endif
+  y=2;
+`ifdef DEBUG
+  end
+`endif
+end
+endmodule
+"#,
+        expect![[r#"
+            module test;
+                analog begin
+            `ifdef SELFHEATING
+                    if (rth > 0)
+                    begin
+            `else
+                    if (1) begin
+            `endif
+                        x = 1;
+                    end
+            `ifdef DEBUG
+              begin
+            `endif
+                        y = 2;
+            `ifdef DEBUG
+              end
+            `endif
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn config() {
+    let config = Config { indent: Indent::Tabs, max_blank_lines: 0, ..Config::default() };
+    check_with(
+        "module test;\n\n\nreal x;\n\nanalog x = 1;\nendmodule",
+        &config,
+        expect![[r#"
+            module test;
+            	real x;
+            	analog x = 1;
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn tokens_are_never_merged() {
+    check(
+        "module test; analog begin x = a - -b; y = \\esc ; z = a / /* c */ b; end endmodule",
+        expect![[r#"
+            module test;
+                analog begin
+                    x = a - -b;
+                    y = \esc ;
+                    z = a / /* c */ b;
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn module_header() {
+    let config = Config { max_width: 60, ..Config::default() };
+    check_with(
+        r#"
+module short(a,
+    b,c);
+endmodule
+module long(drain, gate, source, bulk, thermal_node, gate_resistance_node, substrate_network_node);
+endmodule
+"#,
+        &config,
+        expect![[r#"
+            module short(a, b, c);
+            endmodule
+            module long(
+                drain,
+                gate,
+                source,
+                bulk,
+                thermal_node,
+                gate_resistance_node,
+                substrate_network_node
+            );
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn begin_placement() {
+    let src = r#"
+module test;
+analog
+begin
+if (x)
+begin
+y = 1;
+end
+else
+begin
+y = 2;
+end
+@(initial_step) begin
+z = 0;
+end
+end
+endmodule
+"#;
+    check(
+        src,
+        expect![[r#"
+            module test;
+                analog begin
+                    if (x) begin
+                        y = 1;
+                    end else begin
+                        y = 2;
+                    end
+                    @(initial_step) begin
+                        z = 0;
+                    end
+                end
+            endmodule
+        "#]],
+    );
+    let config = Config { begin: BeginPlacement::NextLine, ..Config::default() };
+    check_with(
+        src,
+        &config,
+        expect![[r#"
+            module test;
+                analog
+                begin
+                    if (x)
+                    begin
+                        y = 1;
+                    end
+                    else
+                    begin
+                        y = 2;
+                    end
+                    @(initial_step)
+                    begin
+                        z = 0;
+                    end
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn attribute_placement() {
+    let src = r#"
+module test;
+(* desc="Threshold voltage", units="V" *) parameter real vth = 0.5;
+(* desc="Mobility" *)
+parameter real u0 = 0.05;
+(* desc="Bulk" *) (* units="V" *) real vb;
+endmodule
+"#;
+    let config = Config { attributes: AttrPlacement::OwnLine, ..Config::default() };
+    check_with(
+        src,
+        &config,
+        expect![[r#"
+            module test;
+                (* desc = "Threshold voltage", units = "V" *)
+                parameter real vth = 0.5;
+                (* desc = "Mobility" *)
+                parameter real u0 = 0.05;
+                (* desc = "Bulk" *) (* units = "V" *)
+                real vb;
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn alignment() {
+    check(
+        r#"
+module test(a, b);
+inout a, b;
+electrical a, b;
+parameter real vth = 0.5 from [0:inf);
+parameter integer mode = 1;
+parameter real u0 = 0.05;
+
+parameter real single = 1;
+analog begin
+I(a, b) <+ V(a, b) / r;
+I(a) <+ c * ddt(V(a));
+I(b) <+ 0;
+x = 1;
+I(a, b) <+ y;
+end
+endmodule
+"#,
+        expect![[r#"
+            module test(a, b);
+                inout a, b;
+                electrical a, b;
+                parameter real    vth  = 0.5 from [0:inf);
+                parameter integer mode = 1;
+                parameter real    u0   = 0.05;
+
+                parameter real single = 1;
+                analog begin
+                    I(a, b) <+ V(a, b) / r;
+                    I(a)    <+ c * ddt(V(a));
+                    I(b)    <+ 0;
+                    x = 1;
+                    I(a, b) <+ y;
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn macros_and_ifdef_are_not_restructured() {
+    check(
+        r#"
+module test(a,
+`ifdef THERMAL
+    dt,
+`endif
+    b);
+`PARAM(vth, 0.5)
+parameter real u0 = 0.05;
+parameter real rs = `DEFAULT_RS;
+analog begin
+if (x)
+`ifdef DEBUG
+begin
+`endif
+y = 1;
+`ifdef DEBUG
+end
+`endif
+I(a) <+ `GMIN * V(a);
+I(b) <+ 0;
+end
+endmodule
+"#,
+        expect![[r#"
+            module test(a,
+            `ifdef THERMAL
+                dt,
+            `endif
+                b);
+                `PARAM(vth, 0.5)
+                parameter real u0 = 0.05;
+                parameter real rs = `DEFAULT_RS;
+                analog begin
+                    if (x)
+            `ifdef DEBUG
+            begin
+            `endif
+                        y = 1;
+            `ifdef DEBUG
+            end
+            `endif
+                    I(a) <+ `GMIN * V(a);
+                    I(b) <+ 0;
+                end
+            endmodule
+        "#]],
+    );
+}
+
+#[test]
+fn malformed_case_item() {
+    check(
+        r#"
+module test;
+analog begin
+case (m)
+0: x = 1 * * y;
+1, 2: x = 1;
+endcase
+end
+endmodule
+"#,
+        expect![[r#"
+            module test;
+                analog begin
+                    case (m)
+                        0: x = 1 * * y;
+                        1, 2: x = 1;
+                    endcase
+                end
+            endmodule
+        "#]],
+    );
+}
@@ -0,0 +1,35 @@
+use std::fs;
+use std::path::Path;
+
+use mini_harness::{harness, Result};
+use openvaf_fmt::{format, Config};
+use stdx::{ignore_never, project_root};
+
+/// Every file of the model must be formatted without errors and formatting
+/// the result again must not change anything.
+fn integration_test(dir: &Path) -> Result {
+    let config = Config::default();
+    let mut files: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::io::Result<_>>()?;
+    files.sort();
+    for file in files {
+        if !matches!(file.extension().and_then(|ext| ext.to_str()), Some("va" | "inc" | "include"))
+        {
+            continue;
+        }
+        let src = fs::read_to_string(&file)?;
+        let formatted =
+            format(&src, &config).map_err(|err| format!("{}: {err}", file.display()))?;
+        let again = format(&formatted, &config)
+            .map_err(|err| format!("{} (second pass): {err}", file.display()))?;
+        if formatted != again {
+            return Err(format!("formatting {} is not idempotent", file.display()).into());
+        }
+    }
+    Ok(())
+}
+
+harness! {
+    Test::from_dir_filtered("integration", &integration_test, &Path::is_dir, &ignore_never, &project_root().join("integration_tests"))
+}
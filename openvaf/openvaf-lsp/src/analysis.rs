@@ -0,0 +1,310 @@
+//! Semantic queries (diagnostics, go to definition, hover and completion) on top of a
+//! [`CompilationDB`]. All positions are byte offsets into a file of the VFS. The conversion
+//! to LSP positions happens in `server.rs`.
+//!
+//! The syntax tree is built from the preprocessed token stream, so syntax ranges are always
+//! mapped back to the file they originate from with the sourcemap.
+
+use std::fmt::Display;
+
+use basedb::diagnostics::{DiagnosticSink, Report, Severity};
+use basedb::{BaseDB, FileId};
+use hir::CompilationDB;
+use hir_def::db::HirDefDB;
+use hir_def::nameres::{DefMap, DefMapSource, ResolvedPath, ScopeDefItem};
+use hir_def::{Lookup, Path, ScopeId};
+use hir_ty::builtin::signatures;
+use hir_ty::db::HirTyDB;
+use syntax::ast;
+use syntax::name::AsName;
+use syntax::sourcemap::FileSpan;
+use syntax::{AstNode, SyntaxKind, SyntaxNode, SyntaxToken, TextRange, TextSize};
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Default)]
+struct ReportCollector(Vec<Report>);
+
+impl DiagnosticSink for ReportCollector {
+    fn add_report(&mut self, report: Report) {
+        self.0.push(report)
+    }
+
+    fn summary(&mut self, _target_name: &dyn Display) -> bool {
+        self.0.iter().any(|report| report.severity >= Severity::Error)
+    }
+}
+
+/// All diagnostics of the compilation unit (including diagnostics in included files).
+pub fn diagnostics(db: &CompilationDB) -> Vec<Report> {
+    let mut sink = ReportCollector::default();
+    db.compilation_unit().diagnostics(db, &mut sink);
+    sink.0
+}
+
+/// Returns the declaration of the item at `offset`.
+pub fn goto_definition(db: &CompilationDB, file: FileId, offset: TextSize) -> Option<FileSpan> {
+    let token = token_at(db, file, offset)?;
+    let item = match resolve_token(db, &token)? {
+        ResolvedPath::ScopeDefItem(item) => item,
+        ResolvedPath::FlowAttriubte { .. } | ResolvedPath::PotentialAttribute { .. } => {
+            return None
+        }
+    };
+    let root_file = db.compilation_unit().root_file();
+    let parse = db.parse(root_file);
+    let range = item.text_range(db, &db.ast_id_map(root_file), &parse)?;
+    Some(parse.to_file_span(range, &db.sourcemap(root_file)))
+}
+
+pub struct HoverResult {
+    /// The range of the hovered identifier
+    pub range: FileSpan,
+    pub markdown: String,
+}
+
+/// Describes the item at `offset`: its declaration (including the type) and attributes.
+pub fn hover(db: &CompilationDB, file: FileId, offset: TextSize) -> Option<HoverResult> {
+    let token = token_at(db, file, offset)?;
+    let root_file = db.compilation_unit().root_file();
+    let parse = db.parse(root_file);
+    let range = parse.to_file_span(token.text_range(), &db.sourcemap(root_file));
+
+    let mut markdown = String::from("```verilog-a\n");
+    match resolve_token(db, &token)? {
+        ResolvedPath::ScopeDefItem(item) => {
+            markdown.push_str(&signature(db, item, &token));
+            markdown.push_str("\n```");
+            let attrs = attributes(db, item);
+            if !attrs.is_empty() {
+                markdown.push_str("\n\n");
+            }
+            for (name, val) in attrs {
+                markdown.push_str(&format!("* `{name}` = `{val}`\n"));
+            }
+        }
+        ResolvedPath::FlowAttriubte { name, .. }
+        | ResolvedPath::PotentialAttribute { name, .. } => {
+            markdown.push_str(&format!("nature attribute {name}\n```"));
+        }
+    }
+
+    Some(HoverResult { range, markdown })
+}
+
+pub struct Completion {
+    pub label: String,
+    pub detail: String,
+    /// The text that is replaced by `label`
+    pub replace: TextRange,
+}
+
+/// Completes the system function whose name is being typed in front of `offset`.
+pub fn completions(db: &CompilationDB, file: FileId, offset: TextSize) -> Vec<Completion> {
+    let Ok(text) = db.file_text(file) else { return Vec::new() };
+    let end = usize::from(offset).min(text.len());
+    let start = text[..end]
+        .rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_' || c == '$'))
+        .map_or(0, |pos| pos + 1);
+    let prefix = &text[start..end];
+    if !prefix.starts_with('$') {
+        return Vec::new();
+    }
+
+    let replace = TextRange::new(TextSize::from(start as u32), TextSize::from(end as u32));
+    DefMap::builtin_items()
+        .filter(|(name, _)| name.starts_with(prefix))
+        .map(|(name, item)| {
+            let detail = match item {
+                ScopeDefItem::BuiltIn(builtin) => match signatures(builtin) {
+                    [signature, ..] => format!("{name}{signature}"),
+                    [] => "function".to_owned(),
+                },
+                item => item.item_kind().to_owned(),
+            };
+            Completion { label: name.to_string(), detail, replace }
+        })
+        .collect()
+}
+
+/// Finds the identifier (or system function) token at `offset`.
+fn token_at(db: &CompilationDB, file: FileId, offset: TextSize) -> Option<SyntaxToken> {
+    let root_file = db.compilation_unit().root_file();
+    let parse = db.parse(root_file);
+    let sm = db.sourcemap(root_file);
+    parse.tree().syntax().descendants_with_tokens().filter_map(|it| it.into_token()).find(|token| {
+        if !matches!(token.kind(), SyntaxKind::IDENT | SyntaxKind::SYSFUN) {
+            return false;
+        }
+        let span = parse.to_file_span(token.text_range(), &sm);
+        span.file == file && span.range.contains_inclusive(offset)
+    })
+}
+
+/// Resolves the item that `token` refers to (or declares).
+fn resolve_token(db: &CompilationDB, token: &SyntaxToken) -> Option<ResolvedPath> {
+    let parent = token.parent()?;
+
+    if let Some(path) = ast::Path::cast(parent.clone()) {
+        let path = Path::resolve(path)?;
+        return scope_at(db, &parent).resolve_path(db, &path).ok();
+    }
+
+    if ast::SysFun::can_cast(parent.kind()) {
+        let path = Path::new_ident(token.as_name());
+        return scope_at(db, &parent).resolve_path(db, &path).ok();
+    }
+
+    // the name of a declaration is resolved in the scope that contains the declaration
+    let name = ast::Name::cast(parent)?;
+    let decl = name.syntax().parent()?;
+    let scope = if ast::ModuleDecl::can_cast(decl.kind()) || ast::Function::can_cast(decl.kind()) {
+        scope_at(db, &decl.parent()?)
+    } else if ast::BlockScope::can_cast(decl.kind()) {
+        scope_at(db, &decl.parent()?.parent()?)
+    } else {
+        scope_at(db, &decl)
+    };
+    scope.resolve_path(db, &Path::new_ident(name.as_name())).ok()
+}
+
+/// The innermost scope that contains `node`.
+/// This mirrors how scopes are entered during body lowering: modules, analog functions and
+/// named blocks (that declare items) each open a new scope.
+fn scope_at(db: &CompilationDB, node: &SyntaxNode) -> ScopeId {
+    let root_file = db.compilation_unit().root_file();
+    let mut scope =
+        ScopeId { root_file, local_scope: db.def_map(root_file).entry(), src: DefMapSource::Root };
+
+    let ancestors: Vec<_> = node.ancestors().collect();
+    for node in ancestors.into_iter().rev() {
+        let name = if let Some(module) = ast::ModuleDecl::cast(node.clone()) {
+            module.name()
+        } else if let Some(fun) = ast::Function::cast(node.clone()) {
+            fun.name()
+        } else if let Some(block) = ast::BlockStmt::cast(node) {
+            block.block_scope().and_then(|scope| scope.name())
+        } else {
+            continue;
+        };
+        let Some(name) = name else { continue };
+
+        let item = scope.def_map(db)[scope.local_scope].declarations.get(&name.as_name()).copied();
+        scope = match item {
+            Some(ScopeDefItem::ModuleId(module)) => module.lookup(db).scope,
+            Some(ScopeDefItem::FunctionId(fun)) => ScopeId {
+                root_file,
+                local_scope: db.function_def_map(fun).entry(),
+                src: DefMapSource::Function(fun),
+            },
+            Some(ScopeDefItem::BlockId(block)) => match db.block_def_map(block) {
+                Some(def_map) => ScopeId {
+                    root_file,
+                    local_scope: def_map.entry(),
+                    src: DefMapSource::Block(block),
+                },
+                None => continue,
+            },
+            _ => continue,
+        };
+    }
+
+    scope
+}
+
+/// A Verilog-A like declaration of `item`
+fn signature(db: &CompilationDB, item: ScopeDefItem, token: &SyntaxToken) -> String {
+    match item {
+        ScopeDefItem::VarId(var) => {
+            let data = db.var_data(var);
+            format!("{} {}", data.ty, data.name)
+        }
+        ScopeDefItem::ParamId(param) => {
+            let mut res = format!("parameter {} {}", db.param_ty(param), db.param_data(param).name);
+            let default = source(db, item)
+                .and_then(ast::Param::cast)
+                .and_then(|param| param.default())
+                .map(|default| default.syntax().text().to_string());
+            if let Some(default) = default {
+                res.push_str(&format!(" = {default}"));
+            }
+            res
+        }
+        ScopeDefItem::NodeId(node) => {
+            let data = db.node_data(node);
+            let direction = match (data.is_input, data.is_output) {
+                (true, true) => "inout ",
+                (true, false) => "input ",
+                (false, true) => "output ",
+                (false, false) => "",
+            };
+            match &data.discipline {
+                Some(discipline) => format!("{direction}{discipline} {}", data.name),
+                None => format!("{direction}{}", data.name),
+            }
+        }
+        ScopeDefItem::FunctionId(fun) => {
+            let data = db.function_data(fun);
+            let args: Vec<_> =
+                data.args.iter().map(|arg| format!("{} {}", arg.ty, arg.name)).collect();
+            format!("analog function {} {}({})", data.return_ty, data.name, args.join(", "))
+        }
+        ScopeDefItem::FunctionReturn(fun) => {
+            let data = db.function_data(fun);
+            format!("{} {}", data.return_ty, data.name)
+        }
+        ScopeDefItem::FunctionArgId(arg) => {
+            let arg = arg.lookup(db);
+            let data = db.function_data(arg.fun);
+            let arg = &data.args[arg.id];
+            let direction = match (arg.is_input, arg.is_output) {
+                (true, true) => "inout",
+                (false, true) => "output",
+                _ => "input",
+            };
+            format!("{direction} {} {}", arg.ty, arg.name)
+        }
+        ScopeDefItem::BranchId(branch) => format!("branch {}", db.branch_data(branch).name),
+        ScopeDefItem::ModuleId(module) => format!("module {}", db.module_data(module).name),
+        ScopeDefItem::BuiltIn(builtin) => {
+            let signatures: Vec<_> =
+                signatures(builtin).iter().map(|sig| format!("{}{sig}", token.text())).collect();
+            if signatures.is_empty() {
+                format!("function {}", token.text())
+            } else {
+                signatures.join("\n")
+            }
+        }
+        item => format!("{} {}", item.item_kind(), token.text()),
+    }
+}
+
+/// The syntax node that declares `item`
+fn source(db: &CompilationDB, item: ScopeDefItem) -> Option<SyntaxNode> {
+    let root_file = db.compilation_unit().root_file();
+    let ast_id = item.ast_id(db)?;
+    let tree = db.parse(root_file).tree();
+    Some(db.ast_id_map(root_file).get_syntax(ast_id).to_node(tree.syntax()))
+}
+
+/// The attributes (like `desc` and `units`) attached to the declaration of `item`
+fn attributes(db: &CompilationDB, item: ScopeDefItem) -> Vec<(String, String)> {
+    let Some(mut node) = source(db, item) else { return Vec::new() };
+    // attributes are attached to the declaration list and not the individual declaration
+    if ast::Var::can_cast(node.kind()) || ast::Param::can_cast(node.kind()) {
+        node = match node.parent() {
+            Some(parent) => parent,
+            None => return Vec::new(),
+        };
+    }
+    let mut attrs: Vec<_> = ast::attrs(&node)
+        .filter_map(|attr| {
+            let name = attr.name()?.as_name().to_string();
+            let val = attr.val().map_or_else(String::new, |val| val.syntax().text().to_string());
+            Some((name, val))
+        })
+        .collect();
+    attrs.reverse();
+    attrs
+}
@@ -0,0 +1,71 @@
+use hir::CompilationDB;
+use syntax::TextSize;
+
+use super::{completions, diagnostics, goto_definition, hover};
+
+const SRC: &str = r#"`include "disciplines.vams"
+module test(a, b);
+    inout electrical a, b;
+    (* desc="resistance", units="Ohm" *) parameter real r = 1.0 from (0:inf);
+    real x;
+    analog begin
+        x = V(a, b) / r;
+        I(a, b) <+ x;
+        $strobe("%g", x);
+    end
+endmodule
+"#;
+
+fn offset(pattern: &str, pos: usize) -> TextSize {
+    TextSize::from((SRC.find(pattern).unwrap() + pos) as u32)
+}
+
+#[test]
+fn no_diagnostics() {
+    let db = CompilationDB::new_virtual(SRC).unwrap();
+    assert!(diagnostics(&db).is_empty());
+}
+
+#[test]
+fn definition() {
+    let db = CompilationDB::new_virtual(SRC).unwrap();
+    let root_file = db.compilation_unit().root_file();
+
+    let def = goto_definition(&db, root_file, offset("/ r", 2)).unwrap();
+    assert_eq!(def.file, root_file);
+    assert!(SRC[def.range].starts_with("r = 1.0"));
+
+    let def = goto_definition(&db, root_file, offset("<+ x", 3)).unwrap();
+    assert_eq!(&SRC[def.range], "x");
+    assert_eq!(def.range.start(), offset("real x", 5));
+}
+
+#[test]
+fn hover_info() {
+    let db = CompilationDB::new_virtual(SRC).unwrap();
+    let root_file = db.compilation_unit().root_file();
+
+    let res = hover(&db, root_file, offset("/ r", 2)).unwrap();
+    assert_eq!(&SRC[res.range.range], "r");
+    assert!(res.markdown.contains("parameter real r = 1.0"), "{}", res.markdown);
+    assert!(res.markdown.contains("`units` = `\"Ohm\"`"), "{}", res.markdown);
+
+    let res = hover(&db, root_file, offset("V(a", 2)).unwrap();
+    assert!(res.markdown.contains("inout electrical a"), "{}", res.markdown);
+
+    let res = hover(&db, root_file, offset("$strobe", 1)).unwrap();
+    assert!(res.markdown.contains("$strobe"), "{}", res.markdown);
+}
+
+#[test]
+fn system_function_completion() {
+    let db = CompilationDB::new_virtual(SRC).unwrap();
+    let root_file = db.compilation_unit().root_file();
+
+    let res = completions(&db, root_file, offset("$strobe", 4));
+    assert!(res.iter().any(|completion| completion.label == "$strobe"));
+    assert!(res.iter().all(|completion| completion.label.starts_with("$str")));
+    assert_eq!(res[0].replace.start(), offset("$strobe", 0));
+
+    assert!(completions(&db, root_file, offset("real x", 2)).is_empty());
+}
@@ -0,0 +1,40 @@
+//! `openvaf-lsp` is a language server for Verilog-A that communicates over stdio.
+//! It provides diagnostics, go to definition, hover and completion of system functions.
+
+use std::process::exit;
+
+use anyhow::Result;
+use lsp_server::Connection;
+
+use crate::server::{capabilities, Config, Server};
+
+mod analysis;
+mod server;
+
+fn main() {
+    // stdout is used for the protocol so logs are written to stderr
+    let env = env_logger::Env::default().filter("OPENVAF_LOG").write_style("OPENVAF_LOG_STYLE");
+    env_logger::Builder::new()
+        .format_timestamp(None)
+        .filter(Some("salsa"), log::LevelFilter::Off)
+        .filter_level(log::LevelFilter::Warn)
+        .parse_env(env)
+        .init();
+
+    if let Err(err) = run() {
+        log::error!("{err:?}");
+        exit(1)
+    }
+}
+
+fn run() -> Result<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = serde_json::to_value(capabilities())?;
+    let params: lsp_types::InitializeParams =
+        serde_json::from_value(connection.initialize(capabilities)?)?;
+    let config = Config::from_json(params.initialization_options.as_ref());
+
+    Server::new(connection, config).run()?;
+    io_threads.join()?;
+    Ok(())
+}
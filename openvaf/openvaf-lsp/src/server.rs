@@ -0,0 +1,430 @@
+//! The LSP main loop. Every open document that is not included by another open document is
+//! compiled as a root file in its own [`CompilationDB`]. Included documents (like the
+//! `.include` files of a model) are analyzed as part of the root that includes them, on their
+//! own they would be full of errors for the declarations of the including file.
+//! Edits are written into the VFS of every database that contains the file so salsa only
+//! recomputes the queries that depend on the changed text.
+
+use std::collections::{HashMap, HashSet};
+use std::{fs, iter};
+
+use anyhow::Result;
+use basedb::diagnostics::{LabelStyle, Severity};
+use basedb::line_index::{LineColUtf16, LineIndex};
+use basedb::{AbsPathBuf, BaseDB, FileId, VfsEntry, VfsPath, VfsStorage};
+use hir::CompilationDB;
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::notification::{
+    DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument, Notification as _,
+    PublishDiagnostics,
+};
+use lsp_types::request::{Completion, GotoDefinition, HoverRequest, Request as _};
+use lsp_types::{
+    CompletionItem, CompletionItemKind, CompletionOptions, CompletionResponse,
+    DiagnosticRelatedInformation, GotoDefinitionResponse, Hover, HoverContents,
+    HoverProviderCapability, Location, MarkupContent, MarkupKind, NumberOrString, OneOf, Position,
+    PublishDiagnosticsParams, Range, ServerCapabilities, TextDocumentPositionParams,
+    TextDocumentSyncCapability, TextDocumentSyncKind, TextEdit, Url,
+};
+use syntax::sourcemap::FileSpan;
+use syntax::{TextRange, TextSize};
+
+use crate::analysis;
+
+#[cfg(test)]
+mod tests;
+
+pub fn capabilities() -> ServerCapabilities {
+    ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        definition_provider: Some(OneOf::Left(true)),
+        hover_provider: Some(HoverProviderCapability::Simple(true)),
+        completion_provider: Some(CompletionOptions {
+            trigger_characters: Some(vec!["$".to_owned()]),
+            ..CompletionOptions::default()
+        }),
+        ..ServerCapabilities::default()
+    }
+}
+
+/// Settings passed by the client as `initializationOptions`:
+/// `{"includeDirs": ["<dir>"], "defines": ["<macro>"]}`
+#[derive(Default)]
+pub struct Config {
+    pub include_dirs: Vec<AbsPathBuf>,
+    pub macro_flags: Vec<String>,
+}
+
+impl Config {
+    pub fn from_json(options: Option<&serde_json::Value>) -> Config {
+        let strings = |key: &str| -> Vec<String> {
+            options
+                .and_then(|options| options.get(key))
+                .and_then(|list| list.as_array())
+                .map(|list| list.iter().filter_map(|it| it.as_str().map(str::to_owned)).collect())
+                .unwrap_or_default()
+        };
+        let include_dirs = strings("includeDirs")
+            .into_iter()
+            .filter_map(|dir| match AbsPathBuf::try_from(dir.as_str()) {
+                Ok(dir) => Some(dir),
+                Err(_) => {
+                    log::warn!("ignoring include directory {dir}: path must be absolute");
+                    None
+                }
+            })
+            .collect();
+        Config { include_dirs, macro_flags: strings("defines") }
+    }
+}
+
+pub struct Server {
+    connection: Connection,
+    config: Config,
+    roots: HashMap<Url, CompilationDB>,
+    /// The text of every open document
+    documents: HashMap<Url, String>,
+    /// Documents that diagnostics were published for during the last update
+    published: HashSet<Url>,
+}
+
+impl Server {
+    pub fn new(connection: Connection, config: Config) -> Server {
+        Server {
+            connection,
+            config,
+            roots: HashMap::new(),
+            documents: HashMap::new(),
+            published: HashSet::new(),
+        }
+    }
+
+    pub fn run(mut self) -> Result<()> {
+        while let Ok(msg) = self.connection.receiver.recv() {
+            match msg {
+                Message::Request(req) => {
+                    if self.connection.handle_shutdown(&req)? {
+                        return Ok(());
+                    }
+                    let resp = self.on_request(req);
+                    self.connection.sender.send(Message::Response(resp))?;
+                }
+                Message::Notification(not) => self.on_notification(not)?,
+                Message::Response(_) => (),
+            }
+        }
+        Ok(())
+    }
+
+    fn on_request(&self, req: Request) -> Response {
+        match req.method.as_str() {
+            GotoDefinition::METHOD => self.handle::<GotoDefinition>(req, |server, params| {
+                server.goto_definition(params.text_document_position_params)
+            }),
+            HoverRequest::METHOD => self.handle::<HoverRequest>(req, |server, params| {
+                server.hover(params.text_document_position_params)
+            }),
+            Completion::METHOD => self.handle::<Completion>(req, |server, params| {
+                server.completion(params.text_document_position)
+            }),
+            _ => Response::new_err(
+                req.id,
+                ErrorCode::MethodNotFound as i32,
+                format!("unknown request {}", req.method),
+            ),
+        }
+    }
+
+    fn handle<R: lsp_types::request::Request>(
+        &self,
+        req: Request,
+        f: impl FnOnce(&Self, R::Params) -> R::Result,
+    ) -> Response {
+        match serde_json::from_value(req.params) {
+            Ok(params) => Response::new_ok(req.id, f(self, params)),
+            Err(err) => Response::new_err(req.id, ErrorCode::InvalidParams as i32, err.to_string()),
+        }
+    }
+
+    fn on_notification(&mut self, not: Notification) -> Result<()> {
+        match not.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let params: lsp_types::DidOpenTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                let doc = params.text_document;
+                self.set_file_contents(&to_vfs_path(&doc.uri), || doc.text.clone().into());
+                self.documents.insert(doc.uri, doc.text);
+                self.update_roots();
+            }
+            DidChangeTextDocument::METHOD => {
+                let params: lsp_types::DidChangeTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                // only full document sync is supported so the last change contains the whole file
+                if let Some(change) = params.content_changes.into_iter().last() {
+                    let uri = params.text_document.uri;
+                    self.set_file_contents(&to_vfs_path(&uri), || change.text.clone().into());
+                    self.documents.insert(uri, change.text);
+                    // the edit may have added or removed an `include
+                    self.update_roots();
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let params: lsp_types::DidCloseTextDocumentParams =
+                    serde_json::from_value(not.params)?;
+                let uri = params.text_document.uri;
+                self.roots.remove(&uri);
+                self.documents.remove(&uri);
+                // other documents may include the closed file, they see the contents on disk again
+                let path = to_vfs_path(&uri);
+                if let Some(file) = path.as_path() {
+                    self.set_file_contents(&path, || fs::read(file).into());
+                }
+                // documents that were only included by the closed file become roots again
+                self.update_roots();
+            }
+            _ => return Ok(()),
+        }
+        self.publish_diagnostics()
+    }
+
+    /// Updates the contents of `path` in every database that contains the file.
+    fn set_file_contents(&mut self, path: &VfsPath, contents: impl Fn() -> VfsEntry) {
+        for db in self.roots.values_mut() {
+            let file = db.vfs().read().file_id(path);
+            if let Some(file) = file {
+                db.vfs().write().set_file_contents(file, contents());
+                let db: &mut dyn BaseDB = db;
+                db.apply_vfs_changes();
+            }
+        }
+    }
+
+    /// Compiles every open document that is not included by another root as a root file and
+    /// drops the roots that are now included by another root.
+    fn update_roots(&mut self) {
+        let new_roots: Vec<Url> = self
+            .documents
+            .keys()
+            .filter(|uri| !self.roots.contains_key(uri) && self.including_root(uri).is_none())
+            .cloned()
+            .collect();
+        for uri in new_roots {
+            let db = CompilationDB::new(
+                to_vfs_path(&uri),
+                Ok(self.documents[&uri].clone().into_bytes()),
+                self.config.include_dirs.iter().map(|dir| Ok(VfsPath::from(dir.clone()))),
+                self.config.macro_flags.iter().map(String::as_str),
+                iter::empty(),
+            );
+            let mut db = match db {
+                Ok(db) => db,
+                Err(err) => {
+                    log::error!("failed to open {uri}: {err}");
+                    continue;
+                }
+            };
+            // included files are read from disk, use the text of open documents instead
+            for (doc, text) in &self.documents {
+                let path = to_vfs_path(doc);
+                if doc != &uri && includes_file(&db, &path) {
+                    let file = db.vfs().read().file_id(&path).unwrap();
+                    db.vfs().write().set_file_contents(file, text.clone().into());
+                    let db: &mut dyn BaseDB = &mut db;
+                    db.apply_vfs_changes();
+                }
+            }
+            self.roots.insert(uri, db);
+        }
+
+        // removed one by one so that one of two files that include each other remains a root
+        let roots: Vec<Url> = self.roots.keys().cloned().collect();
+        for uri in roots {
+            if self.including_root(&uri).is_some() {
+                self.roots.remove(&uri);
+            }
+        }
+    }
+
+    /// The database of another root that includes the document `uri`.
+    fn including_root(&self, uri: &Url) -> Option<&CompilationDB> {
+        let path = to_vfs_path(uri);
+        self.roots
+            .iter()
+            .find(|(root, db)| *root != uri && includes_file(db, &path))
+            .map(|(_, db)| db)
+    }
+
+    fn publish_diagnostics(&mut self) -> Result<()> {
+        let mut diagnostics: HashMap<Url, Vec<lsp_types::Diagnostic>> =
+            self.roots.keys().map(|uri| (uri.clone(), Vec::new())).collect();
+
+        for db in self.roots.values() {
+            for report in analysis::diagnostics(db) {
+                let Some(primary) =
+                    report.labels.iter().find(|label| label.style == LabelStyle::Primary)
+                else {
+                    continue;
+                };
+                let Some(uri) = to_url(db, primary.file_id) else { continue };
+                let related_information = report
+                    .labels
+                    .iter()
+                    .filter(|label| label.style == LabelStyle::Secondary)
+                    .filter_map(|label| {
+                        let span =
+                            FileSpan { file: label.file_id, range: text_range(&label.range) };
+                        Some(DiagnosticRelatedInformation {
+                            location: to_location(db, span)?,
+                            message: label.message.clone(),
+                        })
+                    })
+                    .collect();
+                let mut message = report.message.clone();
+                if !primary.message.is_empty() {
+                    message = format!("{message}\n{}", primary.message);
+                }
+                for note in &report.notes {
+                    message = format!("{message}\n{note}");
+                }
+                let severity = match report.severity {
+                    Severity::Bug | Severity::Error => lsp_types::DiagnosticSeverity::ERROR,
+                    Severity::Warning => lsp_types::DiagnosticSeverity::WARNING,
+                    Severity::Note => lsp_types::DiagnosticSeverity::INFORMATION,
+                    Severity::Help => lsp_types::DiagnosticSeverity::HINT,
+                };
+                let range = to_range(&db.line_index(primary.file_id), text_range(&primary.range));
+                let diagnostic = lsp_types::Diagnostic {
+                    range,
+                    severity: Some(severity),
+                    code: report.code.clone().map(NumberOrString::String),
+                    source: Some("openvaf".to_owned()),
+                    message,
+                    related_information: Some(related_information),
+                    ..lsp_types::Diagnostic::default()
+                };
+                diagnostics.entry(uri).or_default().push(diagnostic);
+            }
+        }
+
+        let published = diagnostics.keys().cloned().collect();
+        // clear diagnostics of files that are no longer part of any open document
+        for uri in self.published.difference(&published) {
+            diagnostics.insert(uri.clone(), Vec::new());
+        }
+        self.published = published;
+
+        for (uri, diagnostics) in diagnostics {
+            let params = PublishDiagnosticsParams { uri, diagnostics, version: None };
+            let not = Notification::new(PublishDiagnostics::METHOD.to_owned(), params);
+            self.connection.sender.send(Message::Notification(not))?;
+        }
+        Ok(())
+    }
+
+    /// Finds the database and file of a position in a document.
+    /// Documents that are not roots themselves are looked up in the include tree of the roots.
+    fn file_pos(
+        &self,
+        pos: &TextDocumentPositionParams,
+    ) -> Option<(&CompilationDB, FileId, TextSize)> {
+        let uri = &pos.text_document.uri;
+        let db = self.roots.get(uri).or_else(|| self.including_root(uri))?;
+        let file = db.vfs().read().file_id(&to_vfs_path(&pos.text_document.uri))?;
+        let offset = to_offset(&db.line_index(file), pos.position)?;
+        Some((db, file, offset))
+    }
+
+    fn goto_definition(&self, pos: TextDocumentPositionParams) -> Option<GotoDefinitionResponse> {
+        let (db, file, offset) = self.file_pos(&pos)?;
+        let span = analysis::goto_definition(db, file, offset)?;
+        Some(GotoDefinitionResponse::Scalar(to_location(db, span)?))
+    }
+
+    fn hover(&self, pos: TextDocumentPositionParams) -> Option<Hover> {
+        let (db, file, offset) = self.file_pos(&pos)?;
+        let res = analysis::hover(db, file, offset)?;
+        Some(Hover {
+            contents: HoverContents::Markup(MarkupContent {
+                kind: MarkupKind::Markdown,
+                value: res.markdown,
+            }),
+            range: Some(to_range(&db.line_index(res.range.file), res.range.range)),
+        })
+    }
+
+    fn completion(&self, pos: TextDocumentPositionParams) -> Option<CompletionResponse> {
+        let (db, file, offset) = self.file_pos(&pos)?;
+        let line_index = db.line_index(file);
+        let items = analysis::completions(db, file, offset)
+            .into_iter()
+            .map(|completion| CompletionItem {
+                kind: Some(CompletionItemKind::FUNCTION),
+                detail: Some(completion.detail),
+                text_edit: Some(lsp_types::CompletionTextEdit::Edit(TextEdit {
+                    range: to_range(&line_index, completion.replace),
+                    new_text: completion.label.clone(),
+                })),
+                label: completion.label,
+                ..CompletionItem::default()
+            })
+            .collect();
+        Some(CompletionResponse::Array(items))
+    }
+}
+
+/// Whether `path` is the root file of `db` or (transitively) included by it.
+fn includes_file(db: &CompilationDB, path: &VfsPath) -> bool {
+    // the VFS also contains the paths that were searched for an include but do not exist
+    let Some(file) = db.vfs().read().file_id(path) else { return false };
+    db.sourcemap(db.compilation_unit().root_file()).files().contains(&file)
+}
+
+fn to_vfs_path(uri: &Url) -> VfsPath {
+    match uri.to_file_path().ok().and_then(|path| AbsPathBuf::try_from(path).ok()) {
+        Some(path) => path.into(),
+        // documents that were not saved yet (like `untitled:Untitled-1`)
+        None => VfsPath::new_virtual_path(format!("/{}", uri.path().trim_start_matches('/'))),
+    }
+}
+
+fn to_url(db: &CompilationDB, file: FileId) -> Option<Url> {
+    let path = db.file_path(file);
+    match path.as_path() {
+        Some(path) => Url::from_file_path(path).ok(),
+        None if file == db.compilation_unit().root_file() => {
+            let path = path.to_string();
+            Url::parse(&format!("untitled:{}", path.trim_start_matches('/'))).ok()
+        }
+        // files from the standard library
+        None => None,
+    }
+}
+
+fn to_location(db: &CompilationDB, span: FileSpan) -> Option<Location> {
+    let uri = to_url(db, span.file)?;
+    Some(Location { uri, range: to_range(&db.line_index(span.file), span.range) })
+}
+
+fn text_range(range: &std::ops::Range<usize>) -> TextRange {
+    TextRange::new(TextSize::from(range.start as u32), TextSize::from(range.end as u32))
+}
+
+fn to_position(line_index: &LineIndex, offset: TextSize) -> Position {
+    let pos = line_index.to_utf16(line_index.line_col(offset));
+    Position { line: pos.line, character: pos.col }
+}
+
+fn to_range(line_index: &LineIndex, range: TextRange) -> Range {
+    Range {
+        start: to_position(line_index, range.start()),
+        end: to_position(line_index, range.end()),
+    }
+}
+
+fn to_offset(line_index: &LineIndex, pos: Position) -> Option<TextSize> {
+    if pos.line as usize >= line_index.newlines.len() {
+        return None;
+    }
+    let line_col = line_index.to_utf8(LineColUtf16 { line: pos.line, col: pos.character });
+    Some(line_index.offset(line_col))
+}
@@ -0,0 +1,74 @@
+use std::collections::HashMap;
+use std::{env, fs};
+
+use lsp_server::{Connection, Message, Notification};
+use lsp_types::notification::{DidOpenTextDocument, Notification as _, PublishDiagnostics};
+use lsp_types::{
+    DidOpenTextDocumentParams, HoverContents, Position, PublishDiagnosticsParams,
+    TextDocumentIdentifier, TextDocumentItem, TextDocumentPositionParams, Url,
+};
+
+use super::{Config, Server};
+
+const MODULE: &str = r#"`include "disciplines.vams"
+module test(a, b);
+    inout electrical a, b;
+    parameter real r = 1.0;
+    analog begin
+        `include "test.include"
+    end
+endmodule
+"#;
+
+const INCLUDE: &str = "I(a, b) <+ V(a, b) / r;\n";
+
+fn open(server: &mut Server, uri: &Url, text: &str) {
+    let text_document = TextDocumentItem {
+        uri: uri.clone(),
+        language_id: "verilog-a".to_owned(),
+        version: 0,
+        text: text.to_owned(),
+    };
+    let params = DidOpenTextDocumentParams { text_document };
+    let not = Notification::new(DidOpenTextDocument::METHOD.to_owned(), params);
+    server.on_notification(not).unwrap();
+}
+
+#[test]
+fn include_file() {
+    let dir = env::temp_dir().join("openvaf_lsp_include");
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("test.va"), MODULE).unwrap();
+    // the open document is analyzed instead of the (broken) contents on disk
+    fs::write(dir.join("test.include"), "I(a, b) <+ V(a, b) / undefined;\n").unwrap();
+    let module = Url::from_file_path(dir.join("test.va")).unwrap();
+    let include = Url::from_file_path(dir.join("test.include")).unwrap();
+
+    let (connection, client) = Connection::memory();
+    let mut server = Server::new(connection, Config::default());
+    // on its own the include file is a root, until the module that includes it is opened
+    open(&mut server, &include, INCLUDE);
+    assert_eq!(server.roots.keys().collect::<Vec<_>>(), [&include]);
+    open(&mut server, &module, MODULE);
+    assert_eq!(server.roots.keys().collect::<Vec<_>>(), [&module]);
+
+    let mut diagnostics = HashMap::new();
+    for msg in client.receiver.try_iter() {
+        if let Message::Notification(not) = msg {
+            assert_eq!(not.method, PublishDiagnostics::METHOD);
+            let params: PublishDiagnosticsParams = serde_json::from_value(not.params).unwrap();
+            diagnostics.insert(params.uri, params.diagnostics);
+        }
+    }
+    assert!(diagnostics[&include].is_empty(), "{:?}", diagnostics[&include]);
+    assert!(diagnostics[&module].is_empty(), "{:?}", diagnostics[&module]);
+
+    let pos = TextDocumentPositionParams {
+        text_document: TextDocumentIdentifier { uri: include },
+        position: Position { line: 0, character: INCLUDE.find("r;").unwrap() as u32 },
+    };
+    let HoverContents::Markup(hover) = server.hover(pos).unwrap().contents else {
+        unreachable!("hover is always markdown")
+    };
+    assert!(hover.value.contains("parameter real r = 1.0"), "{}", hover.value);
+}
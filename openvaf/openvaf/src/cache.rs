@@ -8,7 +8,7 @@ use hir::CompilationDB;
 use crate::Opts;
 
 // TODO: use high level hir API instead of low leve database API
-fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
+fn hash(db: &CompilationDB, defines: &[String], sensitivities: &[String]) -> md5::Digest {
     let mut hash_builder = md5::Context::new();
     let cu = db.compilation_unit();
 
@@ -20,6 +20,11 @@ fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
         hash_builder.consume(def)
     }
 
+    hash_builder.consume(sensitivities.len().to_ne_bytes());
+    for param in sensitivities {
+        hash_builder.consume(param)
+    }
+
     hash_builder.consume(env!("CARGO_PKG_VERSION"));
     let lints = db.global_lint_overwrites(cu.root_file());
     if cfg!(debug_assertions) && !lints.is_empty() {
@@ -35,6 +40,8 @@ fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
 
     // Hash the full preprocessor result
     let preprocess = cu.preprocess(db);
+    // resolving the data files may read them into the vfs so this must happen before locking it
+    let data_files = cu.data_files(db);
     let vfs = db.vfs().read();
     for token in &*preprocess.ts {
         if !token.kind.is_trivia() {
@@ -45,11 +52,16 @@ fn hash(db: &CompilationDB, defines: &[String]) -> md5::Digest {
         }
     }
 
+    // ... and the contents of all data files (like noise tables)
+    for file in data_files {
+        hash_builder.consume(vfs.file_contents_unchecked(file));
+    }
+
     hash_builder.compute()
 }
 
 pub fn file_name(db: &CompilationDB, opts: &Opts) -> String {
-    let hash = u128::from_ne_bytes(*hash(db, &opts.defines));
+    let hash = u128::from_ne_bytes(*hash(db, &opts.defines, &opts.sensitivities));
     let hash = base_n::encode(hash, base_n::CASE_INSENSITIVE);
     format!("{}.osdi", hash)
 }
@@ -0,0 +1,93 @@
+//! Dependency information for build systems that need to know when a model must be recompiled.
+//!
+//! The `.d` file uses the Makefile syntax emitted by `gcc -MD -MP` so that it can be included by
+//! make and read by ninja (`deps = gcc`). Besides the sources it lists the data files read during
+//! compilation (like the tables of `noise_table`). Only files that exist on disk are listed there.
+//! The optional `.deps.json` file additionally lists the built-in standard library headers (like
+//! `disciplines.vams`) that are compiled into OpenVAF:
+//!
+//! ```json
+//! {
+//!     "output": "model.osdi",
+//!     "inputs": [{ "path": "/path/to/model.va", "virtual": false }]
+//! }
+//! ```
+
+use std::fs::write;
+
+use anyhow::{Context, Result};
+use basedb::diagnostics::write_json_str;
+use basedb::VfsStorage;
+use camino::Utf8Path;
+use hir::CompilationDB;
+
+#[cfg(test)]
+mod tests;
+
+/// Writes `model.d` (and `model.deps.json` if `json` is set) next to `model.osdi`.
+pub(crate) fn write_dep_info(db: &CompilationDB, lib_file: &Utf8Path, json: bool) -> Result<()> {
+    let cu = db.compilation_unit();
+    let mut files = cu.preprocess(db).sm.files();
+    // resolving the data files may read them into the vfs so this must happen before locking it
+    for file in cu.data_files(db) {
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    let vfs = db.vfs().read();
+    let paths: Vec<_> = files.into_iter().map(|file| vfs.file_path(file)).collect();
+
+    let mut depfile = escape_make(lib_file.as_str());
+    depfile.push(':');
+    for path in &paths {
+        if let Some(path) = path.as_path() {
+            depfile.push_str(" \\\n  ");
+            depfile.push_str(&escape_make(&path.display().to_string()));
+        }
+    }
+    depfile.push('\n');
+    // Phony targets for the included files prevent make from failing when an include is removed.
+    for path in paths.iter().skip(1).filter_map(|path| path.as_path()) {
+        depfile.push('\n');
+        depfile.push_str(&escape_make(&path.display().to_string()));
+        depfile.push_str(":\n");
+    }
+    let path = lib_file.with_extension("d");
+    write(&path, depfile).with_context(|| format!("failed to write {path}"))?;
+
+    if json {
+        let mut dst = String::from("{\"output\":");
+        write_json_str(&mut dst, lib_file.as_str());
+        dst.push_str(",\"inputs\":[");
+        for (i, path) in paths.iter().enumerate() {
+            if i != 0 {
+                dst.push(',');
+            }
+            dst.push_str("{\"path\":");
+            write_json_str(&mut dst, &path.to_string());
+            dst.push_str(",\"virtual\":");
+            dst.push_str(if path.as_path().is_some() { "false" } else { "true" });
+            dst.push('}');
+        }
+        dst.push_str("]}\n");
+        let path = lib_file.with_extension("deps.json");
+        write(&path, dst).with_context(|| format!("failed to write {path}"))?;
+    }
+
+    Ok(())
+}
+
+fn escape_make(path: &str) -> String {
+    let mut res = String::with_capacity(path.len());
+    for c in path.chars() {
+        match c {
+            ' ' | '#' => {
+                res.push('\\');
+                res.push(c)
+            }
+            '$' => res.push_str("$$"),
+            c => res.push(c),
+        }
+    }
+    res
+}
@@ -0,0 +1,53 @@
+use std::{env, fs};
+
+use basedb::diagnostics::write_json_str;
+use camino::Utf8PathBuf;
+use hir::CompilationDB;
+use paths::AbsPathBuf;
+
+use super::{escape_make, write_dep_info};
+
+const MODEL: &str = r#"`include "disciplines.vams"
+module model(inout a, inout b);
+    electrical a, b;
+    analog begin
+        `include "my model.include"
+        I(a, b) <+ noise_table("noise.tbl", "table");
+    end
+endmodule
+"#;
+
+#[test]
+fn escape() {
+    assert_eq!(escape_make("/models/my model#2/$model.va"), "/models/my\\ model\\#2/$$model.va");
+}
+
+#[test]
+fn dep_info() {
+    let dir = env::temp_dir().join("openvaf_depfile");
+    fs::create_dir_all(&dir).unwrap();
+    let dir = Utf8PathBuf::from_path_buf(dir.canonicalize().unwrap()).unwrap();
+    fs::write(dir.join("model.va"), MODEL).unwrap();
+    fs::write(dir.join("my model.include"), "I(a, b) <+ V(a, b);\n").unwrap();
+    fs::write(dir.join("noise.tbl"), "1 1e-12\n1k 2e-12\n").unwrap();
+
+    let root_file = AbsPathBuf::assert(dir.join("model.va").into_std_path_buf());
+    let db = CompilationDB::new_fs(root_file, &[], &[], &[]).unwrap();
+    let lib_file = dir.join("model.osdi");
+    write_dep_info(&db, &lib_file, true).unwrap();
+
+    let path = |name: &str| escape_make(dir.join(name).as_str());
+    let (lib, model, include, table) =
+        (path("model.osdi"), path("model.va"), path("my model.include"), path("noise.tbl"));
+    // the standard library (disciplines.vams) is not on disk
+    let expected =
+        format!("{lib}: \\\n  {model} \\\n  {include} \\\n  {table}\n\n{include}:\n\n{table}:\n");
+    assert_eq!(fs::read_to_string(dir.join("model.d")).unwrap(), expected);
+
+    let json = fs::read_to_string(dir.join("model.deps.json")).unwrap();
+    let mut table_entry = String::from("{\"path\":");
+    write_json_str(&mut table_entry, dir.join("noise.tbl").as_str());
+    table_entry.push_str(",\"virtual\":false}");
+    assert!(json.contains(&table_entry), "{json}");
+    assert!(json.contains("disciplines.vams\",\"virtual\":true}"), "{json}");
+}
@@ -1,18 +1,22 @@
-use std::fs::{create_dir_all, remove_file};
+use std::fs::{create_dir_all, remove_file, write};
 use std::io::Write;
 use std::time::Instant;
 
+use anyhow::bail;
 use anyhow::Context;
 use anyhow::Result;
-use basedb::diagnostics::{ConsoleSink, DiagnosticSink};
+use basedb::diagnostics::{DiagnosticSink, OutputSink};
 use basedb::BaseDB;
 use camino::Utf8PathBuf;
+use depfile::write_dep_info;
 use hir::CompilationDB;
+use lasso::Rodeo;
 use linker::link;
 use mir_llvm::LLVMBackend;
-use sim_back::collect_modules;
+use sim_back::{collect_modules, CompiledModule};
 use termcolor::{Color, ColorChoice, ColorSpec, StandardStream, WriteColor};
 
+pub use basedb::diagnostics::MessageFormat;
 pub use basedb::lints::builtin as builtin_lints;
 pub use basedb::lints::LintLevel;
 pub use llvm::OptLevel;
@@ -21,6 +25,7 @@ pub use target::host_triple;
 pub use target::spec::{get_target_names, Target};
 
 mod cache;
+mod depfile;
 
 #[derive(Debug, Clone)]
 pub enum CompilationDestination {
@@ -45,64 +50,45 @@ pub struct Opts {
     pub opt_lvl: OptLevel,
     pub target: Target,
     pub target_cpu: String,
+    /// parameters for which sensitivities (derivatives) are generated
+    pub sensitivities: Vec<String>,
+    pub message_format: MessageFormat,
+    /// write a Makefile style `.d` file listing all input files next to the compiled library
+    pub depfile: bool,
+    /// additionally write the input files to a `.deps.json` file
+    pub depfile_json: bool,
+}
+/// Writes the JSON export (see [`sim_back::JSON_SCHEMA_VERSION`]) of each module
+/// to `<input>_<module>.json` next to the input file.
+pub fn dump_json(opts: &Opts) -> Result<CompilationTermination> {
+    let input =
+        opts.input.canonicalize().with_context(|| format!("failed to resolve {}", opts.input))?;
+    let input = AbsPathBuf::assert(input);
+    let db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
+    let mut sink = OutputSink::new(&db, opts.message_format);
+    let modules = collect_modules(&db, false, &mut sink);
+    sink.finish().context("failed to write diagnostics")?;
+    let modules = if let Some(modules) = modules {
+        modules
+    } else {
+        return Ok(CompilationTermination::FatalDiagnostic);
+    };
+
+    let mut literals = Rodeo::new();
+    for module in &modules {
+        let module = CompiledModule::new(&db, module, &mut literals);
+        let json = module.to_json(&db, &literals);
+        let path = opts.input.with_file_name(format!(
+            "{}_{}.json",
+            opts.input.file_stem().unwrap(),
+            module.info.module.name(&db)
+        ));
+        if !opts.dry_run {
+            write(&path, json).with_context(|| format!("failed to write {path}"))?;
+        }
+    }
+    Ok(CompilationTermination::Compiled { lib_file: Utf8PathBuf::default() })
 }
-// pub fn dump_json(opts: &Opts) -> Result<CompilationTermination> {
-//     let input =
-//         opts.input.canonicalize().with_context(|| format!("failed to resolve {}", opts.input))?;
-//     let input = AbsPathBuf::assert(input);
-//     let db = CompilationDB::new_fs(input, &opts.include, &opts.defines, &opts.lints)?;
-//     let modules = if let Some(modules) = collect_modules(&db, true, &mut ConsoleSink::new(&db)) {
-//         modules
-//     } else {
-//         return Ok(CompilationTermination::FatalDiagnostic);
-//     };
-//     for module in modules {
-//         let (func, intern, strings, cfg) = module.build_opvar_mir(&db);
-//         let json = func.to_json(
-//             &cfg,
-//             &strings,
-//             |param| match *intern.params.get_index(param).unwrap().0 {
-//                 ParamKind::Param(param) => ("parameters", param.name(&db)),
-//                 ParamKind::Abstime => ("sim_state", "$abstime".to_owned()),
-//                 ParamKind::EnableIntegration => todo!(),
-//                 ParamKind::Voltage { hi, lo: Some(lo) } => {
-//                     ("voltages", format!("({}, {})", &hi.name(&db), &lo.name(&db)))
-//                 }
-//                 ParamKind::Voltage { hi, lo: None } => ("voltages", format!("({})", &hi.name(&db))),
-//                 ParamKind::Current(hir_lower::CurrentKind::Unnamed { hi, lo: Some(lo) }) => {
-//                     ("currents", format!("({}, {})", &hi.name(&db), &lo.name(&db)))
-//                 }
-//                 ParamKind::Current(hir_lower::CurrentKind::Unnamed { hi, lo: None }) => {
-//                     ("currents", format!("({})", hi.name(&db)))
-//                 }
-//                 ParamKind::Current(hir_lower::CurrentKind::Branch(br)) => {
-//                     ("currents", br.name(&db))
-//                 }
-//                 ParamKind::Temperature => ("sim_state", "$temperature".to_owned()),
-//                 ParamKind::ParamGiven { param } => ("param_given", param.name(&db)),
-//                 ParamKind::PortConnected { port } => ("port_connected", port.name(&db).to_string()),
-//                 ParamKind::ParamSysFun(param) => ("params", format!("${param:?}")),
-//                 _ => unreachable!(),
-//             },
-//             intern.outputs.iter().filter_map(|(kind, val)| {
-//                 let name = match *kind {
-//                     PlaceKind::Var(var) => var.name(&db).to_string(),
-//                     _ => return None,
-//                 };
-//                 Some((name, val.expand()?))
-//             }),
-//         );
-//         let path = opts.input.with_file_name(format!(
-//             "{}_{}.json",
-//             opts.input.file_stem().unwrap(),
-//             module.module.name(&db)
-//         ));
-//         if !opts.dry_run {
-//             std::fs::write(path, json)?;
-//         }
-//     }
-//     Ok(CompilationTermination::Compiled { lib_file: Utf8PathBuf::default() })
-// }
 
 pub fn expand(opts: &Opts) -> Result<CompilationTermination> {
     let start = Instant::now();
@@ -136,10 +122,12 @@ pub fn expand(opts: &Opts) -> Result<CompilationTermination> {
     }
     println!();
 
-    let mut sink = ConsoleSink::new(&db);
+    let mut sink = OutputSink::new(&db, opts.message_format);
     sink.add_diagnostics(&*preprocess.diagnostics, cu.root_file(), &db);
 
-    if sink.summary(&opts.input.file_name().unwrap()) {
+    let fatal = sink.summary(&opts.input.file_name().unwrap());
+    sink.finish().context("failed to write diagnostics")?;
+    if fatal {
         return Ok(CompilationTermination::FatalDiagnostic);
     }
 
@@ -166,6 +154,9 @@ pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
             let file_name = cache::file_name(&db, opts);
             let lib_file = cache_dir.join(file_name);
             if cfg!(not(debug_assertions)) && lib_file.exists() {
+                if opts.depfile {
+                    write_dep_info(&db, &lib_file, opts.depfile_json)?;
+                }
                 return Ok(CompilationTermination::Compiled { lib_file });
             }
             create_dir_all(cache_dir).context("failed to create cache directory")?;
@@ -174,12 +165,25 @@ pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
         CompilationDestination::Path { lib_file } => lib_file.clone(),
     };
 
-    let modules = if let Some(modules) = collect_modules(&db, false, &mut ConsoleSink::new(&db)) {
+    let mut sink = OutputSink::new(&db, opts.message_format);
+    let modules = collect_modules(&db, false, &mut sink);
+    sink.finish().context("failed to write diagnostics")?;
+    let mut modules = if let Some(modules) = modules {
         modules
     } else {
         return Ok(CompilationTermination::FatalDiagnostic);
     };
 
+    for param in &opts.sensitivities {
+        let mut found = false;
+        for module in &mut modules {
+            found |= module.select_sensitivity(param);
+        }
+        if !found {
+            bail!("no module has a parameter called \"{param}\" (requested with --sensitivity)");
+        }
+    }
+
     let back = LLVMBackend::new(&opts.codegen_opts, &opts.target, opts.target_cpu.clone(), &[]);
     if opts.dry_run {
         return Ok(CompilationTermination::Compiled { lib_file });
@@ -196,6 +200,10 @@ pub fn compile(opts: &Opts) -> Result<CompilationTermination> {
         remove_file(obj_file).context("failed to delete intermediate compile artifact")?;
     }
 
+    if opts.depfile {
+        write_dep_info(&db, &lib_file, opts.depfile_json)?;
+    }
+
     let seconds = Instant::elapsed(&start).as_secs_f64();
     let mut stderr = StandardStream::stderr(ColorChoice::Auto);
     stderr.set_color(ColorSpec::new().set_fg(Some(Color::Green)).set_bold(true))?;
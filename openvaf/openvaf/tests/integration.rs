@@ -6,17 +6,27 @@ use expect_test::expect_file;
 use float_cmp::assert_approx_eq;
 use llvm::OptLevel;
 use mini_harness::{harness, Result};
-use openvaf::{CompilationDestination, CompilationTermination};
+use openvaf::{CompilationDestination, CompilationTermination, MessageFormat};
 use stdx::{ignore_dev_tests, openvaf_test_data, project_root};
 use target::spec::Target;
 
-use crate::load::{load_osdi_lib, EvalFlags, OsdiDescriptor};
+use crate::load::{
+    load_osdi_lib, osdi_str, EvalFlags, OsdiDescriptor, PARA_BOUND_DYNAMIC, PARA_BOUND_EXCLUDE,
+    PARA_BOUND_MAX_INCLUSIVE, PARA_BOUND_MIN_INCLUSIVE, PARA_DEFAULT_DYNAMIC,
+};
 use crate::mock_sim::{MockSimulation, ALPHA};
 
 mod load;
 mod mock_sim;
 
 fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
+    compile_and_load_with_sensitivities(root_file, Vec::new())
+}
+
+fn compile_and_load_with_sensitivities(
+    root_file: &Utf8Path,
+    sensitivities: Vec<String>,
+) -> &'static OsdiDescriptor {
     let openvaf_opts = openvaf::Opts {
         defines: Vec::new(),
         codegen_opts: Vec::new(),
@@ -27,6 +37,10 @@ fn compile_and_load(root_file: &Utf8Path) -> &'static OsdiDescriptor {
         opt_lvl: OptLevel::Aggressive,
         target: Target::host_target().unwrap(),
         target_cpu: "native".to_owned(),
+        sensitivities,
+        message_format: MessageFormat::Human,
+        depfile: false,
+        depfile_json: false,
         dry_run: false,
     };
 
@@ -215,8 +229,247 @@ fn test_noise() -> Result<()> {
     Ok(())
 }
 
+fn test_noise_table() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("noise_table.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    instance.eval(&model, &mut sim, EvalFlags::empty());
+
+    // (frequency, inline table with linear interpolation, file table with log interpolation)
+    let expected = [
+        (0.5, 1e-12, 1e-10),
+        (500.5, 1.5e-12, 1e-10 * 1e-2f64.powf(500.5f64.log10() / 3.0)),
+        (10f64.powf(1.5), 1e-12 + 1e-12 * (10f64.powf(1.5) - 1.0) / 999.0, 1e-11),
+        (10f64.powf(4.5), 2e-12, 1e-12 * 0.1f64.sqrt()),
+        (1e7, 2e-12, 1e-13),
+    ];
+    for (freq, lin, log) in expected {
+        instance.load_noise(&model, &mut sim, freq);
+        float_cmp::assert_approx_eq!(f64, sim.read_noise(0), lin, epsilon = 1e-20);
+        float_cmp::assert_approx_eq!(f64, sim.read_noise(1), log, epsilon = 1e-20);
+    }
+    Ok(())
+}
+
+fn test_noise_correlated() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    const PWR: f64 = 4.0;
+    const GATE_FAC: f64 = 0.5;
+
+    let main_file = openvaf_test_data("osdi").join("noise_correlated.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let names: Vec<_> = desc.noise().iter().map(|src| unsafe { osdi_str(src.name) }).collect();
+    assert_eq!(names, ["channel", "channel", "gate"]);
+    let [corr] = desc.noise_correlations() else { panic!("expected exactly one correlation") };
+    assert_eq!((corr.src_1, corr.src_2), (0, 1));
+
+    let model = desc.new_model();
+    model.set_real_param(1, PWR);
+    model.set_real_param(2, GATE_FAC);
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    instance.eval(&model, &mut sim, EvalFlags::empty());
+    for freq in [1.0, 1e3, 1e6] {
+        instance.load_noise(&model, &mut sim, freq);
+        instance.load_noise_correlated(&model, &mut sim, freq);
+        assert_approx_eq!(sim.read_noise(0), PWR);
+        assert_approx_eq!(sim.read_noise(1), GATE_FAC * GATE_FAC * PWR);
+        let (re, im) = sim.read_cross_noise(0);
+        assert_approx_eq!(re, GATE_FAC * PWR);
+        assert_eq!(im, 0.0);
+    }
+    Ok(())
+}
+
+fn test_param_meta() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    let main_file = openvaf_test_data("osdi").join("param_bounds.va");
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let param_id = |name: &str| {
+        desc.params().iter().position(|param| unsafe { osdi_str(*param.name) } == name).unwrap()
+    };
+    let meta = desc.param_meta();
+
+    let r = &meta[param_id("r")];
+    assert_eq!(r.flags, 0);
+    assert_eq!(r.default_val, 1e3);
+    let [bound] = r.bounds() else { panic!("expected exactly one bound") };
+    assert_eq!(bound.flags, 0);
+    assert_eq!((bound.min, bound.max), (0.0, f64::INFINITY));
+
+    let tc = &meta[param_id("tc")];
+    assert_eq!(tc.default_val, 0.0);
+    let [from, exclude] = tc.bounds() else { panic!("expected exactly two bounds") };
+    assert_eq!(from.flags, PARA_BOUND_MIN_INCLUSIVE | PARA_BOUND_MAX_INCLUSIVE);
+    assert_eq!((from.min, from.max), (-1.0, 1.0));
+    assert_eq!(
+        exclude.flags,
+        PARA_BOUND_EXCLUDE | PARA_BOUND_MIN_INCLUSIVE | PARA_BOUND_MAX_INCLUSIVE
+    );
+    assert_eq!((exclude.min, exclude.max), (0.5, 0.5));
+
+    // depends on another parameter
+    let rmin = &meta[param_id("rmin")];
+    assert_eq!(rmin.flags, PARA_DEFAULT_DYNAMIC);
+    assert!(rmin.default_val.is_nan());
+    let [bound] = rmin.bounds() else { panic!("expected exactly one bound") };
+    assert_ne!(bound.flags & PARA_BOUND_DYNAMIC, 0);
+    assert_eq!(bound.min, 0.0);
+    assert!(bound.max.is_nan());
+
+    let mode = &meta[param_id("mode")];
+    assert_eq!(mode.default_val, 2.0);
+    assert_eq!(mode.bounds().len(), 2);
+
+    let flavor = &meta[param_id("flavor")];
+    assert_eq!(unsafe { osdi_str(flavor.default_str) }, "fast");
+    let [bound] = flavor.bounds() else { panic!("expected exactly one bound") };
+    assert_eq!(unsafe { osdi_str(bound.str_val) }, "slow");
+
+    let model = desc.new_model();
+    model.process_params()?;
+    model.set_real_param(param_id("tc") as u32, 0.5);
+    let err = model.process_params().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "value 0.5 supplied for parameter 'tc' violates the bound 'exclude 0.5'"
+    );
+
+    let model = desc.new_model();
+    // rmin defaults to r/2 so 0 is the only value that does not violate other bounds as well
+    model.set_real_param(param_id("r") as u32, 0.0);
+    let err = model.process_params().unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "value 0 supplied for parameter 'r' violates the bound 'from (0:inf)'"
+    );
+    Ok(())
+}
+
+fn test_file_io() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    // the model uses absolute paths so the test does not depend on the working directory
+    let dir = std::env::temp_dir().join("openvaf_file_io");
+    std::fs::create_dir_all(&dir)?;
+    let dir_str = dir.to_str().unwrap().replace('\\', "/");
+    let src = format!(
+        r#"`include "disciplines.va"
+module file_io(a, c);
+    inout a, c;
+    electrical a, c;
+    integer fd, mcd, cnt;
+    real val;
+    analog begin
+        @(initial_step) begin
+            fd = $fopen("{dir_str}/data.txt", "w");
+            $fdisplay(fd, "%g", 2.5);
+            $fclose(fd);
+            fd = $fopen("{dir_str}/data.txt", "r");
+            cnt = $fscanf(fd, "%g", val);
+            $fclose(fd);
+            mcd = $fopen("{dir_str}/log.txt");
+            $fdisplay(mcd | 1, "val = %g", val);
+            $fclose(mcd);
+        end
+        I(a, c) <+ val * cnt * V(a, c);
+    end
+endmodule
+"#
+    );
+    let main_file = dir.join("file_io.va");
+    std::fs::write(&main_file, src)?;
+
+    let desc = compile_and_load(main_file.as_path().try_into().unwrap());
+    let model = desc.new_model();
+    model.process_params()?;
+    let mut instance = model.new_instance();
+    let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+    sim.set_voltage("a", 1.0);
+    instance.eval(&model, &mut sim, EvalFlags::empty());
+    instance.load_dae(&model, &mut sim);
+    desc.close_files(instance.data);
+
+    assert_approx_eq!(sim.read_residual("a").0, 2.5);
+    assert_eq!(std::fs::read_to_string(dir.join("log.txt"))?, "val = 2.5\n");
+    Ok(())
+}
+
+fn test_sensitivity() -> Result<()> {
+    if stdx::IS_CI && cfg!(windows) {
+        return Ok(());
+    }
+
+    const R: f64 = 1e3;
+    const V: f64 = 2.0;
+
+    let main_file = openvaf_test_data("osdi").join("sensitivity.va");
+    let desc = compile_and_load_with_sensitivities(
+        main_file.as_path().try_into().unwrap(),
+        vec!["r".to_owned()],
+    );
+    let param_r =
+        desc.params().iter().position(|param| unsafe { osdi_str(*param.name) } == "r").unwrap();
+    assert_eq!(desc.num_sensitivity_params, 1);
+    assert_eq!(unsafe { *desc.sensitivity_params }, param_r as u32);
+
+    // evaluates the device at V(a, c) = V and returns the resistive residual of a
+    // together with the sensitivity matrix
+    let eval = |r: f64| -> Result<(f64, Vec<f64>)> {
+        let model = desc.new_model();
+        model.set_real_param(param_r as u32, r);
+        model.process_params()?;
+        let mut instance = model.new_instance();
+        let mut sim = instance.mock_simulation(&model, desc.num_terminals, 300.0)?;
+        sim.set_voltage("a", V);
+        instance.eval(&model, &mut sim, EvalFlags::CALC_SENSITIVITY);
+        instance.load_dae(&model, &mut sim);
+        // one column for r, the rows are the resistive residuals,
+        // the reactive residuals and the opvars
+        let mut sensitivities = vec![0.0; (2 * desc.num_nodes + desc.num_opvars) as usize];
+        desc.write_sensitivity(instance.data, model.data, sensitivities.as_mut_ptr());
+        Ok((sim.read_residual("a").0, sensitivities))
+    };
+
+    let (_, sensitivities) = eval(R)?;
+    let (hi, _) = eval(R * (1.0 + 1e-6))?;
+    let (lo, _) = eval(R * (1.0 - 1e-6))?;
+    let di_dr = (hi - lo) / (2e-6 * R);
+    float_cmp::assert_approx_eq!(f64, di_dr, -V / (R * R), epsilon = 1e-12);
+
+    let node = |name: &str| {
+        desc.nodes().iter().position(|node| unsafe { osdi_str(node.name) } == name).unwrap()
+    };
+    let (a, c) = (node("a"), node("c"));
+    let num_nodes = desc.num_nodes as usize;
+    float_cmp::assert_approx_eq!(f64, sensitivities[a], di_dr, epsilon = 1e-12);
+    float_cmp::assert_approx_eq!(f64, sensitivities[c], -di_dr, epsilon = 1e-12);
+    // the charge does not depend on r
+    assert_eq!(sensitivities[num_nodes + a], 0.0);
+    assert_eq!(sensitivities[num_nodes + c], 0.0);
+    // the opvar i is the current from a to c
+    float_cmp::assert_approx_eq!(f64, sensitivities[2 * num_nodes], di_dr, epsilon = 1e-12);
+    Ok(())
+}
+
 harness! {
     // TODO: run this in CI, somehow this test is flakey tough regarding the linker invocation (and really slow)
     Test::from_dir("integration", &integration_test, &ignore_dev_tests, &project_root().join("integration_tests")),
-    [Test::new("$limit", &test_limit),Test::new("noise", &test_noise)]
+    [Test::new("$limit", &test_limit),Test::new("noise", &test_noise),Test::new("noise_table", &test_noise_table),Test::new("param_meta", &test_param_meta),Test::new("noise_correlated", &test_noise_correlated),Test::new("file_io", &test_file_io),Test::new("sensitivity", &test_sensitivity)]
 }
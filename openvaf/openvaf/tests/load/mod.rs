@@ -1,7 +1,7 @@
 use std::alloc::{alloc_zeroed, handle_alloc_error, Layout};
 use std::cell::Cell;
 use std::ffi::{c_char, CStr};
-use std::fmt::Debug;
+use std::fmt::{self, Debug};
 use std::mem::{align_of, swap};
 use std::panic::catch_unwind;
 use std::process::exit;
@@ -33,6 +33,12 @@ impl OsdiDescriptor {
         unsafe { slice::from_raw_parts(self.param_opvar, self.num_params as usize) }
     }
 
+    pub fn param_meta(&self) -> &[OsdiParamMeta] {
+        // # SAFETY: OsdiDescriptor can only be constructed from FFI and is assumed to contain
+        // valid data
+        unsafe { slice::from_raw_parts(self.param_meta, self.num_params as usize) }
+    }
+
     pub fn collapsible(&self) -> &[OsdiNodePair] {
         // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
         unsafe { slice::from_raw_parts(self.collapsible, self.num_collapsible as usize) }
@@ -43,6 +49,13 @@ impl OsdiDescriptor {
         unsafe { slice::from_raw_parts(self.noise_sources, self.num_noise_src as usize) }
     }
 
+    pub fn noise_correlations(&self) -> &[OsdiNoiseCorrelation] {
+        // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
+        unsafe {
+            slice::from_raw_parts(self.noise_correlations, self.num_noise_correlations as usize)
+        }
+    }
+
     pub fn matrix_entries(&self) -> &[OsdiJacobianEntry] {
         // SAFETY: self.data is a valid allocation and the descriptor is assumed valid
         unsafe { slice::from_raw_parts(self.jacobian_entries, self.num_jacobian_entries as usize) }
@@ -56,15 +69,24 @@ impl OsdiDescriptor {
         if res.num_errors != 0 {
             let mut msg = String::default();
 
+            let violations = self.bound_violations(res.errors, res.num_errors);
             for i in 0..res.num_errors as usize {
                 let err = unsafe { &*res.errors.add(i) };
 
                 match err.code {
                     INIT_ERR_OUT_OF_BOUNDS => {
-                        let param = unsafe { err.payload.parameter_id };
-                        let param = unsafe { *self.params()[param as usize].name };
-                        let param = unsafe { osdi_str(param) };
-                        format_to!(msg, "value supplied for parameter '{param}' is out of bounds\n")
+                        let id = unsafe { err.payload.parameter_id } as usize;
+                        let violation = unsafe { &*violations.add(i) };
+                        let param = unsafe { osdi_str(*self.params()[id].name) };
+                        format_to!(msg, "value ");
+                        if !violation.value.is_nan() {
+                            format_to!(msg, "{} ", violation.value);
+                        }
+                        format_to!(msg, "supplied for parameter '{param}' ");
+                        match self.param_meta()[id].bounds().get(violation.bound as usize) {
+                            Some(bound) => format_to!(msg, "violates the bound '{bound}'\n"),
+                            None => format_to!(msg, "is out of bounds\n"),
+                        }
                     }
 
                     code => format_to!(msg, "unknown error: {code}\n"),
@@ -94,6 +116,38 @@ impl Drop for OsdiInitInfo {
     }
 }
 
+impl OsdiParamMeta {
+    pub fn bounds(&self) -> &[OsdiParamBound] {
+        // SAFETY: the descriptor is assumed valid
+        unsafe { slice::from_raw_parts(self.bounds, self.num_bounds as usize) }
+    }
+}
+
+impl fmt::Display for OsdiParamBound {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = if self.flags & PARA_BOUND_EXCLUDE != 0 { "exclude" } else { "from" };
+        if self.flags & PARA_BOUND_DYNAMIC != 0 {
+            return write!(f, "{kind} <depends on other parameters>");
+        }
+
+        // string bounds are stored without numeric values
+        if self.min.is_nan() {
+            let val = unsafe { osdi_str(self.str_val) };
+            return write!(f, "{kind} \"{val}\"");
+        }
+
+        let min_inclusive = self.flags & PARA_BOUND_MIN_INCLUSIVE != 0;
+        let max_inclusive = self.flags & PARA_BOUND_MAX_INCLUSIVE != 0;
+        if self.min == self.max && min_inclusive && max_inclusive {
+            return write!(f, "{kind} {}", self.min);
+        }
+
+        let open = if min_inclusive { '[' } else { '(' };
+        let close = if max_inclusive { ']' } else { ')' };
+        write!(f, "{kind} {open}{}:{}{close}", self.min, self.max)
+    }
+}
+
 pub(super) unsafe fn osdi_str(raw: *mut c_char) -> &'static str {
     CStr::from_ptr(raw).to_str().expect("All OSDI strings must be encoded in UTF-8")
 }
@@ -540,6 +594,10 @@ bitflags! {
         const ANALYSIS_IC = ANALYSIS_IC;
         const ANALYSIS_STATIC = ANALYSIS_STATIC;
         const ANALYSIS_NODESET = ANALYSIS_NODESET;
+        const INITIAL_STEP = INITIAL_STEP;
+        const FINAL_STEP = FINAL_STEP;
+        const CALC_SENSITIVITY = CALC_SENSITIVITY;
+        const PHASE_AWARE = PHASE_AWARE;
     }
 }
 
@@ -550,5 +608,6 @@ bitflags! {
         const EVAL_RET_FLAG_FATAL = EVAL_RET_FLAG_FATAL;
         const EVAL_RET_FLAG_FINISH = EVAL_RET_FLAG_FINISH;
         const EVAL_RET_FLAG_STOP = EVAL_RET_FLAG_STOP;
+        const EVAL_RET_FLAG_EVENT = EVAL_RET_FLAG_EVENT;
     }
 }
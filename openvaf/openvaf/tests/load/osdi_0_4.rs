@@ -36,10 +36,16 @@ pub const ANALYSIS_TRAN: u32 = 8192;
 pub const ANALYSIS_IC: u32 = 16384;
 pub const ANALYSIS_STATIC: u32 = 32768;
 pub const ANALYSIS_NODESET: u32 = 65536;
+pub const INITIAL_STEP: u32 = 131072;
+pub const FINAL_STEP: u32 = 262144;
+pub const CALC_SENSITIVITY: u32 = 524288;
+pub const PHASE_AWARE: u32 = 1048576;
+pub const EXACT_ABSDELAY: u32 = 2097152;
 pub const EVAL_RET_FLAG_LIM: u32 = 1;
 pub const EVAL_RET_FLAG_FATAL: u32 = 2;
 pub const EVAL_RET_FLAG_FINISH: u32 = 4;
 pub const EVAL_RET_FLAG_STOP: u32 = 8;
+pub const EVAL_RET_FLAG_EVENT: u32 = 16;
 pub const LOG_LVL_MASK: u32 = 7;
 pub const LOG_LVL_DEBUG: u32 = 0;
 pub const LOG_LVL_DISPLAY: u32 = 1;
@@ -49,6 +55,12 @@ pub const LOG_LVL_ERR: u32 = 4;
 pub const LOG_LVL_FATAL: u32 = 5;
 pub const LOG_FMT_ERR: u32 = 16;
 pub const INIT_ERR_OUT_OF_BOUNDS: u32 = 1;
+pub const PARA_BOUND_EXCLUDE: u32 = 1;
+pub const PARA_BOUND_MIN_INCLUSIVE: u32 = 2;
+pub const PARA_BOUND_MAX_INCLUSIVE: u32 = 4;
+pub const PARA_BOUND_DYNAMIC: u32 = 8;
+pub const PARA_DEFAULT_DYNAMIC: u32 = 1;
+pub const PARA_BOUND_UNKNOWN: u32 = 0xFFFFFFFF;
 
 #[repr(C)]
 pub struct OsdiLimFunction {
@@ -82,6 +94,11 @@ pub struct OsdiInitError {
     pub payload: OsdiInitErrorPayload,
 }
 #[repr(C)]
+pub struct OsdiBoundViolation {
+    pub bound: u32,
+    pub value: f64,
+}
+#[repr(C)]
 pub struct OsdiInitInfo {
     pub flags: u32,
     pub num_errors: u32,
@@ -124,6 +141,40 @@ pub struct OsdiNoiseSource {
     pub nodes: OsdiNodePair,
 }
 #[repr(C)]
+pub struct OsdiNoiseCorrelation {
+    pub src_1: u32,
+    pub src_2: u32,
+}
+#[repr(C)]
+pub struct OsdiAcStim {
+    pub analysis: *mut c_char,
+    pub nodes: OsdiNodePair,
+}
+#[repr(C)]
+pub struct OsdiAbsdelay {
+    pub input: u32,
+    pub output: u32,
+    pub pade_1: u32,
+    pub pade_2: u32,
+    pub delay_offset: u32,
+    pub value_offset: u32,
+}
+#[repr(C)]
+pub struct OsdiParamBound {
+    pub flags: u32,
+    pub min: f64,
+    pub max: f64,
+    pub str_val: *mut c_char,
+}
+#[repr(C)]
+pub struct OsdiParamMeta {
+    pub flags: u32,
+    pub default_val: f64,
+    pub default_str: *mut c_char,
+    pub num_bounds: u32,
+    pub bounds: *mut OsdiParamBound,
+}
+#[repr(C)]
 #[non_exhaustive]
 pub struct OsdiDescriptor {
     pub name: *mut c_char,
@@ -173,6 +224,24 @@ pub struct OsdiDescriptor {
     pub inputs: *mut OsdiNodePair,
     pub load_jacobian_with_offset_resist: fn(*mut c_void, *mut c_void, usize),
     pub load_jacobian_with_offset_react: fn(*mut c_void, *mut c_void, usize),
+    pub num_op_states: u32,
+    pub op_state_offset: u32,
+    pub breakpoint_offset: u32,
+    pub num_ac_stims: u32,
+    pub ac_stims: *mut OsdiAcStim,
+    pub load_ac_stim: fn(*mut c_void, *mut c_void, *mut f64),
+    pub files_offset: u32,
+    pub close_files: fn(*mut c_void),
+    pub num_sensitivity_params: u32,
+    pub sensitivity_params: *mut u32,
+    pub write_sensitivity: fn(*mut c_void, *mut c_void, *mut f64),
+    pub param_meta: *mut OsdiParamMeta,
+    pub num_noise_correlations: u32,
+    pub noise_correlations: *mut OsdiNoiseCorrelation,
+    pub load_noise_correlated: fn(*mut c_void, *mut c_void, f64, *mut f64),
+    pub bound_violations: fn(*mut OsdiInitError, u32) -> *mut OsdiBoundViolation,
+    pub num_absdelays: u32,
+    pub absdelays: *mut OsdiAbsdelay,
 }
 impl OsdiDescriptor {
     pub fn access(
@@ -301,4 +370,29 @@ impl OsdiDescriptor {
     ) {
         (self.load_jacobian_with_offset_react)(inst, model, offset)
     }
+    pub fn load_ac_stim(&self, inst: *mut c_void, model: *mut c_void, ac_stim: *mut f64) {
+        (self.load_ac_stim)(inst, model, ac_stim)
+    }
+    pub fn close_files(&self, inst: *mut c_void) {
+        (self.close_files)(inst)
+    }
+    pub fn write_sensitivity(&self, inst: *mut c_void, model: *mut c_void, dst: *mut f64) {
+        (self.write_sensitivity)(inst, model, dst)
+    }
+    pub fn load_noise_correlated(
+        &self,
+        inst: *mut c_void,
+        model: *mut c_void,
+        freq: f64,
+        cross_dens: *mut f64,
+    ) {
+        (self.load_noise_correlated)(inst, model, freq, cross_dens)
+    }
+    pub fn bound_violations(
+        &self,
+        errors: *mut OsdiInitError,
+        num_errors: u32,
+    ) -> *mut OsdiBoundViolation {
+        (self.bound_violations)(errors, num_errors)
+    }
 }
@@ -25,6 +25,7 @@ pub struct MockSimulation {
     pub state_1: Vec<f64>,
     pub state_2: Vec<f64>,
     pub noise_dense: Vec<f64>,
+    pub cross_noise_dense: Vec<f64>,
 }
 impl MockSimulation {
     fn new() -> MockSimulation {
@@ -47,6 +48,7 @@ impl MockSimulation {
             state_1: Vec::new(),
             state_2: Vec::new(),
             noise_dense: Vec::new(),
+            cross_noise_dense: Vec::new(),
         }
     }
 
@@ -77,6 +79,10 @@ impl MockSimulation {
         self.noise_dense[src]
     }
 
+    pub fn read_cross_noise(&mut self, correlation: usize) -> (f64, f64) {
+        (self.cross_noise_dense[2 * correlation], self.cross_noise_dense[2 * correlation + 1])
+    }
+
     pub fn set_voltage(&mut self, node: &str, voltage: f64) {
         let i = self.nodes.get_index_of(node).unwrap();
         self.solve[i] = voltage
@@ -178,6 +184,7 @@ impl OsdiInstance {
         sim.state_1.resize(self.descriptor.num_states as usize, 0.0);
         sim.state_2.resize(self.descriptor.num_states as usize, 0.0);
         sim.noise_dense.resize(self.descriptor.num_noise_src as usize, 0.0);
+        sim.cross_noise_dense.resize(2 * self.descriptor.num_noise_correlations as usize, 0.0);
         Ok(sim)
     }
 
@@ -196,6 +203,11 @@ impl OsdiInstance {
         self.descriptor.load_noise(self.data, model.data, freq, sim.noise_dense.as_mut_ptr())
     }
 
+    pub fn load_noise_correlated(&self, model: &OsdiModel, sim: &mut MockSimulation, freq: f64) {
+        let dst = sim.cross_noise_dense.as_mut_ptr();
+        self.descriptor.load_noise_correlated(self.data, model.data, freq, dst)
+    }
+
     pub fn load_dae(&self, model: &OsdiModel, sim: &mut MockSimulation) {
         self.descriptor.load_residual_resist(
             self.data,
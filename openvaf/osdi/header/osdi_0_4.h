@@ -45,11 +45,17 @@
 #define ANALYSIS_IC 16384
 #define ANALYSIS_STATIC 32768
 #define ANALYSIS_NODESET 65536
+#define INITIAL_STEP 131072
+#define FINAL_STEP 262144
+#define CALC_SENSITIVITY 524288
+#define PHASE_AWARE 1048576
+#define EXACT_ABSDELAY 2097152
 
 #define EVAL_RET_FLAG_LIM 1
 #define EVAL_RET_FLAG_FATAL 2
 #define EVAL_RET_FLAG_FINISH 4
 #define EVAL_RET_FLAG_STOP 8
+#define EVAL_RET_FLAG_EVENT 16
 
 
 #define LOG_LVL_MASK 7
@@ -63,6 +69,15 @@
 
 #define INIT_ERR_OUT_OF_BOUNDS 1
 
+#define PARA_BOUND_EXCLUDE 1
+#define PARA_BOUND_MIN_INCLUSIVE 2
+#define PARA_BOUND_MAX_INCLUSIVE 4
+#define PARA_BOUND_DYNAMIC 8
+
+#define PARA_DEFAULT_DYNAMIC 1
+
+#define PARA_BOUND_UNKNOWN 0xFFFFFFFF
+
 
 
 typedef struct OsdiLimFunction {
@@ -96,6 +111,11 @@ typedef struct OsdiInitError {
   OsdiInitErrorPayload payload;
 }OsdiInitError;
 
+typedef struct OsdiBoundViolation {
+  uint32_t bound;
+  double value;
+}OsdiBoundViolation;
+
 typedef struct OsdiInitInfo {
   uint32_t flags;
   uint32_t num_errors;
@@ -138,6 +158,40 @@ typedef struct OsdiNoiseSource {
   OsdiNodePair nodes;
 }OsdiNoiseSource;
 
+typedef struct OsdiNoiseCorrelation {
+  uint32_t src_1;
+  uint32_t src_2;
+}OsdiNoiseCorrelation;
+
+typedef struct OsdiAcStim {
+  char *analysis;
+  OsdiNodePair nodes;
+}OsdiAcStim;
+
+typedef struct OsdiAbsdelay {
+  uint32_t input;
+  uint32_t output;
+  uint32_t pade_1;
+  uint32_t pade_2;
+  uint32_t delay_offset;
+  uint32_t value_offset;
+}OsdiAbsdelay;
+
+typedef struct OsdiParamBound {
+  uint32_t flags;
+  double min;
+  double max;
+  char *str_val;
+}OsdiParamBound;
+
+typedef struct OsdiParamMeta {
+  uint32_t flags;
+  double default_val;
+  char *default_str;
+  uint32_t num_bounds;
+  OsdiParamBound *bounds;
+}OsdiParamMeta;
+
 typedef struct OsdiDescriptor {
   char *name;
 
@@ -202,6 +256,32 @@ typedef struct OsdiDescriptor {
   OsdiNodePair* inputs;
   void (*load_jacobian_with_offset_resist)(void *inst, void* model, size_t offset);
   void (*load_jacobian_with_offset_react)(void *inst, void* model, size_t offset);
+
+  uint32_t num_op_states;
+  uint32_t op_state_offset;
+  uint32_t breakpoint_offset;
+
+  uint32_t num_ac_stims;
+  OsdiAcStim *ac_stims;
+  void (*load_ac_stim)(void *inst, void *model, double *ac_stim);
+
+  uint32_t files_offset;
+  void (*close_files)(void *inst);
+
+  uint32_t num_sensitivity_params;
+  uint32_t *sensitivity_params;
+  void (*write_sensitivity)(void *inst, void *model, double *dst);
+
+  OsdiParamMeta *param_meta;
+
+  uint32_t num_noise_correlations;
+  OsdiNoiseCorrelation *noise_correlations;
+  void (*load_noise_correlated)(void *inst, void *model, double freq, double *cross_dens);
+
+  OsdiBoundViolation *(*bound_violations)(OsdiInitError *errors, uint32_t num_errors);
+
+  uint32_t num_absdelays;
+  OsdiAbsdelay *absdelays;
 }OsdiDescriptor;
 
 
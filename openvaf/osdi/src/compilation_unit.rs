@@ -1,14 +1,15 @@
-use hir::CompilationDB;
+use hir::{CompilationDB, Type};
 use hir_lower::fmt::{DisplayKind, FmtArg, FmtArgKind};
-use hir_lower::{CallBackKind, HirInterner};
+use hir_lower::{CallBackKind, FileOp, HirInterner};
 use lasso::Rodeo;
 use llvm::Linkage;
 use llvm::{
-    IntPredicate, LLVMAddIncoming, LLVMAppendBasicBlockInContext, LLVMBuildAdd,
-    LLVMBuildArrayMalloc, LLVMBuildBr, LLVMBuildCall2, LLVMBuildCondBr, LLVMBuildFMul,
-    LLVMBuildFree, LLVMBuildICmp, LLVMBuildInBoundsGEP2, LLVMBuildLoad2, LLVMBuildPhi,
-    LLVMGetParam, LLVMIsDeclaration, LLVMPositionBuilderAtEnd, LLVMSetLinkage,
-    LLVMSetUnnamedAddress, UnnamedAddr, UNNAMED,
+    False, IntPredicate, LLVMAddIncoming, LLVMAppendBasicBlockInContext, LLVMBuildAdd,
+    LLVMBuildAlloca, LLVMBuildArrayMalloc, LLVMBuildBr, LLVMBuildCall2, LLVMBuildCondBr,
+    LLVMBuildFMul, LLVMBuildFree, LLVMBuildICmp, LLVMBuildInBoundsGEP2, LLVMBuildInsertValue,
+    LLVMBuildIntCast2, LLVMBuildLoad2, LLVMBuildPhi, LLVMBuildRet, LLVMBuildSelect, LLVMBuildStore,
+    LLVMGetParam, LLVMGetUndef, LLVMIsDeclaration, LLVMPositionBuilderAtEnd, LLVMSetLinkage,
+    LLVMSetUnnamedAddress, LLVMStructTypeInContext, UnnamedAddr, UNNAMED,
 };
 use mir::{FuncRef, Function};
 use mir_llvm::{CallbackFun, CodegenCx, LLVMBackend, ModuleLlvm};
@@ -220,11 +221,16 @@ pub fn general_callbacks<'ll>(
                 | CallBackKind::BuiltinLimit { .. }
                 | CallBackKind::StoreLimit(_)
                 | CallBackKind::LimDiscontinuity
+                | CallBackKind::EventDetected
                 | CallBackKind::Analysis
                 | CallBackKind::NoiseTable(_)
+                | CallBackKind::AcStim { .. }
                 | CallBackKind::WhiteNoise { .. }
                 | CallBackKind::FlickerNoise { .. }
-                | CallBackKind::TimeDerivative => return None,
+                | CallBackKind::TimeDerivative
+                | CallBackKind::File(_)
+                | CallBackKind::FilePrint { .. }
+                | CallBackKind::FileScan { .. } => return None,
 
                 CallBackKind::Print { kind, arg_tys } => {
                     let (fun, fun_ty) = print_callback(builder.cx, *kind, arg_tys);
@@ -248,105 +254,12 @@ fn print_callback<'ll>(
     let fun = cx.declare_int_fn(&name, fun_ty);
     unsafe {
         let entry_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
-        let alloc_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
-        let write_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
-        let err_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
-        let exit_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
         let llbuilder = llvm::LLVMCreateBuilderInContext(cx.llcx);
 
         LLVMPositionBuilderAtEnd(llbuilder, entry_bb);
         let handle = LLVMGetParam(fun, 0);
-        let fmt_lit = LLVMGetParam(fun, 1);
-        let mut args = vec![cx.const_null_ptr(), cx.const_usize(0), LLVMGetParam(fun, 1)];
-
-        let exp_table = cx.get_declared_value("EXP").expect("constant EXP missing from stdlib");
-        let exp_table_ty = cx.ty_array(cx.ty_double(), 11);
-        let char_table =
-            cx.get_declared_value("FMT_CHARS").expect("constant FMT_CHARS missing from stdlib");
-        let char_table_ty = cx.ty_array(cx.ty_char(), 11);
-        let fmt_char_idx =
-            cx.get_func_by_name("fmt_char_idx").expect("fmt_char_idx missing from stdlib");
-        let fmt_char_idx_ty = cx.ty_func(&[cx.ty_double()], cx.ty_int());
-        let fmt_binary = cx.get_func_by_name("fmt_binary").expect("fmt_binary missing from stdlib");
-        let fmt_binary_ty = cx.ty_func(&[cx.ty_int()], cx.ty_ptr());
-        let mut free = Vec::new();
-
-        for (i, arg) in arg_tys.iter().enumerate() {
-            let val = LLVMGetParam(fun, i as u32 + 2);
-            match arg.kind {
-                FmtArgKind::Binary => {
-                    let formatted_str = LLVMBuildCall2(
-                        llbuilder,
-                        fmt_binary_ty,
-                        fmt_binary,
-                        [val].as_ptr(),
-                        1,
-                        UNNAMED,
-                    );
-                    free.push(formatted_str);
-                }
-                FmtArgKind::EngineerReal => {
-                    let idx = LLVMBuildCall2(
-                        llbuilder,
-                        fmt_char_idx_ty,
-                        fmt_char_idx,
-                        [val].as_ptr(),
-                        1,
-                        UNNAMED,
-                    );
-                    let exp = LLVMBuildInBoundsGEP2(
-                        llbuilder,
-                        exp_table_ty,
-                        exp_table,
-                        [cx.const_int(0), idx].as_ptr(),
-                        2,
-                        UNNAMED,
-                    );
-                    let exp = LLVMBuildLoad2(llbuilder, cx.ty_double(), exp, UNNAMED);
-                    let num = LLVMBuildFMul(llbuilder, val, exp, UNNAMED);
-                    args.push(num);
-                    let scale_char = LLVMBuildInBoundsGEP2(
-                        llbuilder,
-                        char_table_ty,
-                        char_table,
-                        [cx.const_int(0), idx].as_ptr(),
-                        2,
-                        UNNAMED,
-                    );
-                    args.push(scale_char);
-                }
-                FmtArgKind::Other => args.push(val),
-            }
-        }
-        args.extend((1..(2 + arg_tys.len())).map(|arg| LLVMGetParam(fun, arg as u32)));
-        let (fun_ty, fun) = cx.intrinsic("snprintf").unwrap();
-        let len = LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), args.len() as u32, UNNAMED);
-        let is_err = LLVMBuildICmp(llbuilder, IntPredicate::IntSLT, len, cx.const_int(0), UNNAMED);
-        LLVMBuildCondBr(llbuilder, is_err, err_bb, alloc_bb);
-
-        LLVMPositionBuilderAtEnd(llbuilder, alloc_bb);
-        let data_len = LLVMBuildAdd(llbuilder, len, cx.const_int(1), UNNAMED);
-        let ptr = LLVMBuildArrayMalloc(llbuilder, cx.ty_char(), data_len, UNNAMED);
-        let null_ptr = cx.const_null_ptr();
-        let is_err = LLVMBuildICmp(llbuilder, llvm::IntPredicate::IntEQ, null_ptr, ptr, UNNAMED);
-        LLVMBuildCondBr(llbuilder, is_err, err_bb, write_bb);
-
-        LLVMPositionBuilderAtEnd(llbuilder, write_bb);
-        let data_len = LLVMBuildAdd(llbuilder, len, cx.const_int(1), UNNAMED);
-        args[0] = ptr;
-        args[1] = data_len;
-        let len = LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), args.len() as u32, UNNAMED);
-        let is_err = LLVMBuildICmp(llbuilder, IntPredicate::IntSLT, len, cx.const_int(0), UNNAMED);
-        for alloc in free.iter() {
-            LLVMBuildFree(llbuilder, alloc);
-        }
-        LLVMBuildCondBr(llbuilder, is_err, err_bb, exit_bb);
+        let (msg, failed) = build_fmt(cx, llbuilder, fun, 1, arg_tys);
 
-        LLVMPositionBuilderAtEnd(llbuilder, err_bb);
-        LLVMBuildBr(llbuilder, exit_bb);
-
-        LLVMPositionBuilderAtEnd(llbuilder, exit_bb);
-        let flags = LLVMBuildPhi(llbuilder, cx.ty_int(), UNNAMED);
         let lvl = match kind {
             DisplayKind::Debug => LOG_LVL_DEBUG,
             DisplayKind::Display | DisplayKind::Monitor => LOG_LVL_DISPLAY,
@@ -358,9 +271,7 @@ fn print_callback<'ll>(
         let lvl_and_err = lvl | LOG_FMT_ERR;
         let lvl = cx.const_unsigned_int(lvl);
         let lvl_and_err = cx.const_unsigned_int(lvl_and_err);
-        LLVMAddIncoming(flags, [lvl, lvl_and_err].as_ptr(), [write_bb, err_bb].as_ptr(), 2);
-        let msg = LLVMBuildPhi(llbuilder, cx.ty_ptr(), UNNAMED);
-        LLVMAddIncoming(msg, [ptr, fmt_lit].as_ptr(), [write_bb, err_bb].as_ptr(), 2);
+        let flags = LLVMBuildSelect(llbuilder, failed, lvl_and_err, lvl, UNNAMED);
         let fun_ptr = cx.get_declared_value("osdi_log").expect("symbol osdi_log is missing");
         let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int()], cx.ty_void());
         let fun = LLVMBuildLoad2(llbuilder, cx.ty_ptr(), fun_ptr, UNNAMED);
@@ -371,3 +282,289 @@ fn print_callback<'ll>(
 
     (fun, fun_ty)
 }
+
+/// Formats the parameters of `fun` starting at `fmt_param` (the format literal followed by
+/// `arg_tys`) into a newly allocated string. Returns the string and a flag that is set if
+/// formatting failed, in that case the format literal is returned instead.
+/// The builder is positioned at the end of the exit block afterwards.
+unsafe fn build_fmt<'ll>(
+    cx: &CodegenCx<'_, 'll>,
+    llbuilder: &llvm::Builder<'ll>,
+    fun: &'ll llvm::Value,
+    fmt_param: u32,
+    arg_tys: &[FmtArg],
+) -> (&'ll llvm::Value, &'ll llvm::Value) {
+    let alloc_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
+    let write_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
+    let err_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
+    let exit_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
+
+    let fmt_lit = LLVMGetParam(fun, fmt_param);
+    let mut args = vec![cx.const_null_ptr(), cx.const_usize(0), fmt_lit];
+
+    let exp_table = cx.get_declared_value("EXP").expect("constant EXP missing from stdlib");
+    let exp_table_ty = cx.ty_array(cx.ty_double(), 11);
+    let char_table =
+        cx.get_declared_value("FMT_CHARS").expect("constant FMT_CHARS missing from stdlib");
+    let char_table_ty = cx.ty_array(cx.ty_char(), 11);
+    let fmt_char_idx =
+        cx.get_func_by_name("fmt_char_idx").expect("fmt_char_idx missing from stdlib");
+    let fmt_char_idx_ty = cx.ty_func(&[cx.ty_double()], cx.ty_int());
+    let fmt_binary = cx.get_func_by_name("fmt_binary").expect("fmt_binary missing from stdlib");
+    let fmt_binary_ty = cx.ty_func(&[cx.ty_int()], cx.ty_ptr());
+    let mut free = Vec::new();
+
+    for (i, arg) in arg_tys.iter().enumerate() {
+        let val = LLVMGetParam(fun, fmt_param + 1 + i as u32);
+        match arg.kind {
+            FmtArgKind::Binary => {
+                let formatted_str = LLVMBuildCall2(
+                    llbuilder,
+                    fmt_binary_ty,
+                    fmt_binary,
+                    [val].as_ptr(),
+                    1,
+                    UNNAMED,
+                );
+                free.push(formatted_str);
+            }
+            FmtArgKind::EngineerReal => {
+                let idx = LLVMBuildCall2(
+                    llbuilder,
+                    fmt_char_idx_ty,
+                    fmt_char_idx,
+                    [val].as_ptr(),
+                    1,
+                    UNNAMED,
+                );
+                let exp = LLVMBuildInBoundsGEP2(
+                    llbuilder,
+                    exp_table_ty,
+                    exp_table,
+                    [cx.const_int(0), idx].as_ptr(),
+                    2,
+                    UNNAMED,
+                );
+                let exp = LLVMBuildLoad2(llbuilder, cx.ty_double(), exp, UNNAMED);
+                let num = LLVMBuildFMul(llbuilder, val, exp, UNNAMED);
+                args.push(num);
+                let scale_char = LLVMBuildInBoundsGEP2(
+                    llbuilder,
+                    char_table_ty,
+                    char_table,
+                    [cx.const_int(0), idx].as_ptr(),
+                    2,
+                    UNNAMED,
+                );
+                args.push(scale_char);
+            }
+            FmtArgKind::Other => args.push(val),
+        }
+    }
+    args.extend(
+        (fmt_param..(fmt_param + 1 + arg_tys.len() as u32)).map(|arg| LLVMGetParam(fun, arg)),
+    );
+    let (fun_ty, fun) = cx.intrinsic("snprintf").unwrap();
+    let len = LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), args.len() as u32, UNNAMED);
+    let is_err = LLVMBuildICmp(llbuilder, IntPredicate::IntSLT, len, cx.const_int(0), UNNAMED);
+    LLVMBuildCondBr(llbuilder, is_err, err_bb, alloc_bb);
+
+    LLVMPositionBuilderAtEnd(llbuilder, alloc_bb);
+    let data_len = LLVMBuildAdd(llbuilder, len, cx.const_int(1), UNNAMED);
+    let ptr = LLVMBuildArrayMalloc(llbuilder, cx.ty_char(), data_len, UNNAMED);
+    let null_ptr = cx.const_null_ptr();
+    let is_err = LLVMBuildICmp(llbuilder, llvm::IntPredicate::IntEQ, null_ptr, ptr, UNNAMED);
+    LLVMBuildCondBr(llbuilder, is_err, err_bb, write_bb);
+
+    LLVMPositionBuilderAtEnd(llbuilder, write_bb);
+    let data_len = LLVMBuildAdd(llbuilder, len, cx.const_int(1), UNNAMED);
+    args[0] = ptr;
+    args[1] = data_len;
+    let len = LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), args.len() as u32, UNNAMED);
+    let is_err = LLVMBuildICmp(llbuilder, IntPredicate::IntSLT, len, cx.const_int(0), UNNAMED);
+    for alloc in free.iter() {
+        LLVMBuildFree(llbuilder, alloc);
+    }
+    LLVMBuildCondBr(llbuilder, is_err, err_bb, exit_bb);
+
+    LLVMPositionBuilderAtEnd(llbuilder, err_bb);
+    LLVMBuildBr(llbuilder, exit_bb);
+
+    LLVMPositionBuilderAtEnd(llbuilder, exit_bb);
+    let failed = LLVMBuildPhi(llbuilder, cx.ty_bool(), UNNAMED);
+    let incoming = [cx.const_bool(false), cx.const_bool(true)];
+    LLVMAddIncoming(failed, incoming.as_ptr(), [write_bb, err_bb].as_ptr(), 2);
+    let msg = LLVMBuildPhi(llbuilder, cx.ty_ptr(), UNNAMED);
+    LLVMAddIncoming(msg, [ptr, fmt_lit].as_ptr(), [write_bb, err_bb].as_ptr(), 2);
+    (msg, failed)
+}
+
+/// Callbacks of the `$f*` system tasks. These access the table of files owned by the
+/// instance (`files` points to the `files` field of the instance data).
+pub fn file_callback<'ll>(
+    cx: &CodegenCx<'_, 'll>,
+    kind: &CallBackKind,
+    files: &'ll llvm::Value,
+    handle: &'ll llvm::Value,
+) -> CallbackFun<'ll> {
+    let ptr_ty = cx.ty_ptr();
+    let int_ty = cx.ty_int();
+    match kind {
+        CallBackKind::File(FileOp::Gets) => str_result_callback(cx, "osdi_fgets", files),
+        CallBackKind::File(FileOp::Error) => str_result_callback(cx, "osdi_ferror", files),
+        CallBackKind::File(op) => {
+            let (name, num_args) = match op {
+                FileOp::Open => ("osdi_fopen", 3),
+                FileOp::Close => ("osdi_fclose", 1),
+                FileOp::Flush => ("osdi_fflush", 1),
+                FileOp::Eof => ("osdi_feof", 1),
+                FileOp::Tell => ("osdi_ftell", 1),
+                FileOp::Seek => ("osdi_fseek", 3),
+                FileOp::Rewind => ("osdi_rewind", 1),
+                FileOp::Gets | FileOp::Error => unreachable!(),
+            };
+            let fun = cx
+                .get_func_by_name(name)
+                .unwrap_or_else(|| unreachable!("stdlib function {name} is missing"));
+            let (state, args) = if *op == FileOp::Open {
+                (vec![files, handle], vec![ptr_ty; 4])
+            } else {
+                let mut args = vec![int_ty; num_args + 1];
+                args[0] = ptr_ty;
+                (vec![files], args)
+            };
+            let fun_ty = cx.ty_func(&args, int_ty);
+            CallbackFun { fun_ty, fun, state: state.into_boxed_slice(), num_state: 0 }
+        }
+        CallBackKind::FilePrint { arg_tys } => {
+            let (fun, fun_ty) = file_print_callback(cx, arg_tys);
+            CallbackFun { fun_ty, fun, state: Box::new([handle, files]), num_state: 0 }
+        }
+        CallBackKind::FileScan { arg_tys } => {
+            let (fun, fun_ty) = file_scan_callback(cx, arg_tys);
+            CallbackFun { fun_ty, fun, state: Box::new([files]), num_state: 0 }
+        }
+        _ => unreachable!("{kind:?} is not a file callback"),
+    }
+}
+
+/// Wraps a stdlib function that returns an integer and writes a string to an out pointer
+/// into a function that returns both values.
+fn str_result_callback<'ll>(
+    cx: &CodegenCx<'_, 'll>,
+    name: &str,
+    files: &'ll llvm::Value,
+) -> CallbackFun<'ll> {
+    let ptr_ty = cx.ty_ptr();
+    let int_ty = cx.ty_int();
+    let stdlib_fun = cx
+        .get_func_by_name(name)
+        .unwrap_or_else(|| unreachable!("stdlib function {name} is missing"));
+    let stdlib_fun_ty = cx.ty_func(&[ptr_ty, int_ty, ptr_ty], int_ty);
+    unsafe {
+        let ret_ty = LLVMStructTypeInContext(cx.llcx, [int_ty, ptr_ty].as_ptr(), 2, False);
+        let fun_ty = cx.ty_func(&[ptr_ty, int_ty], ret_ty);
+        let fun = cx.declare_int_fn(&cx.local_callback_name(), fun_ty);
+        let entry_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
+        let llbuilder = llvm::LLVMCreateBuilderInContext(cx.llcx);
+        LLVMPositionBuilderAtEnd(llbuilder, entry_bb);
+
+        let dst = LLVMBuildAlloca(llbuilder, ptr_ty, UNNAMED);
+        let args = [LLVMGetParam(fun, 0), LLVMGetParam(fun, 1), dst];
+        let res = LLVMBuildCall2(llbuilder, stdlib_fun_ty, stdlib_fun, args.as_ptr(), 3, UNNAMED);
+        let str = LLVMBuildLoad2(llbuilder, ptr_ty, dst, UNNAMED);
+        let mut ret = LLVMGetUndef(ret_ty);
+        ret = LLVMBuildInsertValue(llbuilder, ret, res, 0, UNNAMED);
+        ret = LLVMBuildInsertValue(llbuilder, ret, str, 1, UNNAMED);
+        LLVMBuildRet(llbuilder, ret);
+        llvm::LLVMDisposeBuilder(llbuilder);
+
+        CallbackFun { fun_ty, fun, state: Box::new([files]), num_state: 0 }
+    }
+}
+
+/// Formats the message of `$fdisplay` (and friends) just like `print_callback` and
+/// writes it to the file descriptor with `osdi_fwrite`.
+fn file_print_callback<'ll>(
+    cx: &CodegenCx<'_, 'll>,
+    arg_tys: &[FmtArg],
+) -> (&'ll llvm::Value, &'ll llvm::Type) {
+    let mut args = vec![cx.ty_ptr(), cx.ty_ptr(), cx.ty_int(), cx.ty_ptr()];
+    args.extend(arg_tys.iter().map(|arg| lltype(&arg.ty, cx)));
+    let fun_ty = cx.ty_func(&args, cx.ty_void());
+    let name = cx.local_callback_name();
+    let fun = cx.declare_int_fn(&name, fun_ty);
+    unsafe {
+        let entry_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
+        let llbuilder = llvm::LLVMCreateBuilderInContext(cx.llcx);
+
+        LLVMPositionBuilderAtEnd(llbuilder, entry_bb);
+        let handle = LLVMGetParam(fun, 0);
+        let files = LLVMGetParam(fun, 1);
+        let fd = LLVMGetParam(fun, 2);
+        let (msg, failed) = build_fmt(cx, llbuilder, fun, 3, arg_tys);
+        let failed = LLVMBuildIntCast2(llbuilder, failed, cx.ty_c_bool(), False, UNNAMED);
+
+        let write = cx.get_func_by_name("osdi_fwrite").expect("osdi_fwrite missing from stdlib");
+        let write_ty = cx.ty_func(
+            &[cx.ty_ptr(), cx.ty_ptr(), cx.ty_int(), cx.ty_ptr(), cx.ty_c_bool()],
+            cx.ty_void(),
+        );
+        let args = [files, handle, fd, msg, failed];
+        LLVMBuildCall2(llbuilder, write_ty, write, args.as_ptr(), args.len() as u32, UNNAMED);
+        llvm::LLVMBuildRetVoid(llbuilder);
+        llvm::LLVMDisposeBuilder(llbuilder);
+    }
+
+    (fun, fun_ty)
+}
+
+/// Calls `osdi_fscanf` with a pointer to each output. The outputs are initialized with their
+/// previous value so that outputs which are not matched remain unchanged.
+fn file_scan_callback<'ll>(
+    cx: &CodegenCx<'_, 'll>,
+    arg_tys: &[Type],
+) -> (&'ll llvm::Value, &'ll llvm::Type) {
+    let ptr_ty = cx.ty_ptr();
+    let int_ty = cx.ty_int();
+    let arg_tys: Vec<_> = arg_tys.iter().map(|ty| lltype(ty, cx)).collect();
+    let mut args = vec![ptr_ty, int_ty, ptr_ty];
+    args.extend_from_slice(&arg_tys);
+    let mut fields = vec![int_ty];
+    fields.extend_from_slice(&arg_tys);
+    unsafe {
+        let ret_ty = LLVMStructTypeInContext(cx.llcx, fields.as_ptr(), fields.len() as u32, False);
+        let fun_ty = cx.ty_func(&args, ret_ty);
+        let fun = cx.declare_int_fn(&cx.local_callback_name(), fun_ty);
+        let entry_bb = LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
+        let llbuilder = llvm::LLVMCreateBuilderInContext(cx.llcx);
+        LLVMPositionBuilderAtEnd(llbuilder, entry_bb);
+
+        let mut scan_args: Vec<_> = (0..3).map(|i| LLVMGetParam(fun, i)).collect();
+        for (i, &ty) in arg_tys.iter().enumerate() {
+            let slot = LLVMBuildAlloca(llbuilder, ty, UNNAMED);
+            LLVMBuildStore(llbuilder, LLVMGetParam(fun, 3 + i as u32), slot);
+            scan_args.push(slot);
+        }
+        let scan = cx.get_func_by_name("osdi_fscanf").expect("osdi_fscanf missing from stdlib");
+        let scan_ty = cx.ty_variadic_func(&[ptr_ty, int_ty, ptr_ty], int_ty);
+        let cnt = LLVMBuildCall2(
+            llbuilder,
+            scan_ty,
+            scan,
+            scan_args.as_ptr(),
+            scan_args.len() as u32,
+            UNNAMED,
+        );
+
+        let mut ret = LLVMBuildInsertValue(llbuilder, LLVMGetUndef(ret_ty), cnt, 0, UNNAMED);
+        for (i, &ty) in arg_tys.iter().enumerate() {
+            let val = LLVMBuildLoad2(llbuilder, ty, scan_args[3 + i], UNNAMED);
+            ret = LLVMBuildInsertValue(llbuilder, ret, val, 1 + i as u32, UNNAMED);
+        }
+        LLVMBuildRet(llbuilder, ret);
+        llvm::LLVMDisposeBuilder(llbuilder);
+
+        (fun, fun_ty)
+    }
+}
@@ -12,12 +12,12 @@ use sim_back::SimUnknownKind;
 use typed_index_collections::TiVec;
 
 use crate::bitfield::{is_flag_set, is_flag_set_mem, is_flag_unset};
-use crate::compilation_unit::{general_callbacks, OsdiCompilationUnit};
+use crate::compilation_unit::{file_callback, general_callbacks, OsdiCompilationUnit};
 use crate::inst_data::OsdiInstanceParam;
 use crate::metadata::osdi_0_4::{
     ANALYSIS_IC, CALC_NOISE, CALC_OP, CALC_REACT_JACOBIAN, CALC_REACT_LIM_RHS, CALC_REACT_RESIDUAL,
-    CALC_RESIST_JACOBIAN, CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL, ENABLE_LIM, EVAL_RET_FLAG_LIM,
-    INIT_LIM,
+    CALC_RESIST_JACOBIAN, CALC_RESIST_LIM_RHS, CALC_RESIST_RESIDUAL, CALC_SENSITIVITY, ENABLE_LIM,
+    EVAL_RET_FLAG_LIM, EXACT_ABSDELAY, FINAL_STEP, INITIAL_STEP, INIT_LIM, PHASE_AWARE,
 };
 use crate::metadata::OsdiLimFunction;
 use crate::OsdiLimId;
@@ -216,6 +216,29 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                         ParamKind::EnableLim => {
                             is_flag_set_mem(cx, ENABLE_LIM, &flags, builder.llbuilder)
                         }
+                        ParamKind::InitialStep | ParamKind::FinalStep => {
+                            // simulators that do not set PHASE_AWARE (like ngspice) never signal
+                            // the initial/final step, so the event bodies run during every eval
+                            let step = if *kind == ParamKind::InitialStep {
+                                INITIAL_STEP
+                            } else {
+                                FINAL_STEP
+                            };
+                            let flags = flags.read(builder.llbuilder);
+                            let is_step = is_flag_set(cx, step, flags, builder.llbuilder);
+                            let is_legacy =
+                                is_flag_unset(cx, PHASE_AWARE, flags, builder.llbuilder);
+                            LLVMBuildOr(builder.llbuilder, is_step, is_legacy, UNNAMED)
+                        }
+                        ParamKind::PrevOpState(state) => {
+                            return inst_data.op_state_loc(cx, state, instance).into()
+                        }
+                        ParamKind::ExactAbsdelay => {
+                            is_flag_set_mem(cx, EXACT_ABSDELAY, &flags, builder.llbuilder)
+                        }
+                        ParamKind::DelayedValue(absdelay) => {
+                            return inst_data.absdelay_loc(cx, absdelay, true, instance).into()
+                        }
                     }
                 };
                 BuilderVal::Eager(val)
@@ -232,9 +255,13 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         builder.params = params;
 
         builder.callbacks = general_callbacks(intern, &mut builder, ret_flags, handle, simparam);
+        let files = unsafe { inst_data.files_ptr(instance, builder.llbuilder) };
 
         for (func, kind) in intern.callbacks.iter_enumerated() {
             let cb = match *kind {
+                CallBackKind::File(_)
+                | CallBackKind::FilePrint { .. }
+                | CallBackKind::FileScan { .. } => file_callback(cx, kind, files, handle),
                 CallBackKind::BuiltinLimit { name, num_args } => {
                     let id = module
                         .lim_table
@@ -263,6 +290,14 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     let fun_ty = cx.ty_func(&[cx.ty_ptr()], cx.ty_void());
                     CallbackFun { fun_ty, fun, state: Box::new([ret_flags]), num_state: 0 }
                 }
+                CallBackKind::EventDetected => {
+                    let fun = builder
+                        .cx
+                        .get_func_by_name("event_detected")
+                        .expect("stdlib function event_detected is missing");
+                    let fun_ty = cx.ty_func(&[cx.ty_ptr()], cx.ty_void());
+                    CallbackFun { fun_ty, fun, state: Box::new([ret_flags]), num_state: 0 }
+                }
                 CallBackKind::Analysis => {
                     let fun = builder
                         .cx
@@ -329,8 +364,29 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 }
             };
             Self::build_store_results(&builder, llfunc, &flags, CALC_NOISE, &store_noise);
+            let store_sensitivities = |builder: &Builder<'_, '_, 'll>| {
+                for entry in &inst_data.sensitivities {
+                    inst_data.store_eval_output(entry.val, instance, builder)
+                }
+            };
+            Self::build_store_results(
+                &builder,
+                llfunc,
+                &flags,
+                CALC_SENSITIVITY,
+                &store_sensitivities,
+            );
+
+            for stim in &inst_data.ac_stims {
+                for eval_output in stim.eval_outputs() {
+                    inst_data.store_eval_output(eval_output, instance, &builder)
+                }
+            }
 
             inst_data.store_bound_step(instance, &builder);
+            inst_data.store_breakpoint(instance, &builder);
+            inst_data.store_op_states(module, instance, &builder);
+            inst_data.store_absdelays(module, instance, &builder);
 
             let ret_flags = builder.load(cx.ty_int(), ret_flags);
             builder.ret(ret_flags);
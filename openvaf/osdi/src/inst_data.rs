@@ -1,6 +1,6 @@
 use ahash::RandomState;
 use hir::{CompilationDB, ParamSysFun, Parameter, Variable};
-use hir_lower::{HirInterner, LimitState, ParamKind, PlaceKind};
+use hir_lower::{Absdelay, HirInterner, LimitState, OpState, ParamKind, PlaceKind};
 use indexmap::IndexMap;
 use llvm::{
     IntPredicate, LLVMBuildFAdd, LLVMBuildFSub, LLVMBuildGEP2, LLVMBuildICmp, LLVMBuildIntCast2,
@@ -25,7 +25,7 @@ pub enum OsdiInstanceParam {
     User(Parameter),
 }
 
-pub const NUM_CONST_FIELDS: u32 = 8;
+pub const NUM_CONST_FIELDS: u32 = 11;
 pub const PARAM_GIVEN: u32 = 0;
 pub const JACOBIAN_PTR_RESIST: u32 = 1;
 pub const JACOBIAN_PTR_REACT: u32 = 2;
@@ -34,6 +34,9 @@ pub const COLLAPSED: u32 = 4;
 pub const TEMPERATURE: u32 = 5;
 pub const CONNECTED: u32 = 6;
 pub const STATE_IDX: u32 = 7;
+pub const OP_STATE: u32 = 8;
+pub const FILES: u32 = 9;
+pub const ABSDELAYS: u32 = 10;
 
 #[derive(PartialEq, Eq, Debug, Clone, Copy)]
 pub enum EvalOutput {
@@ -187,6 +190,77 @@ impl NoiseSource {
     }
 }
 
+#[derive(Debug)]
+pub struct AcStim {
+    pub factor: EvalOutput,
+    pub mag: EvalOutput,
+    pub phase: EvalOutput,
+}
+
+impl AcStim {
+    pub fn new<'ll>(
+        stim: &dae::AcStimSource,
+        module: &OsdiModule<'_>,
+        slots: &mut TiMap<EvalOutputSlot, mir::Value, &'ll llvm::Type>,
+        ty_real: &'ll llvm::Type,
+    ) -> AcStim {
+        let mut get_output = |mut val| {
+            val = strip_optbarrier(module.eval, val);
+            EvalOutput::new(module, val, slots, false, ty_real)
+        };
+        AcStim {
+            factor: get_output(stim.factor),
+            mag: get_output(stim.mag),
+            phase: get_output(stim.phase),
+        }
+    }
+
+    pub fn eval_outputs(&self) -> [EvalOutput; 3] {
+        [self.factor, self.mag, self.phase]
+    }
+}
+
+/// An entry of the (dense, row-major) sensitivity matrix written by `write_sensitivity`
+#[derive(Debug)]
+pub struct SensitivityEntry {
+    /// position of the entry within the matrix
+    pub pos: u32,
+    pub val: EvalOutput,
+}
+
+impl SensitivityEntry {
+    /// The sensitivity matrix has a column for each sensitivity parameter and the following rows:
+    /// the resistive residuals, the reactive residuals and the operating point variables.
+    fn build<'ll>(
+        module: &OsdiModule<'_>,
+        slots: &mut TiMap<EvalOutputSlot, mir::Value, &'ll llvm::Type>,
+        ty_real: &'ll llvm::Type,
+    ) -> Vec<SensitivityEntry> {
+        let dae_system = &module.dae_system;
+        let num_cols = dae_system.sensitivity_params.len() as u32;
+        let num_nodes = dae_system.unknowns.len() as u32;
+        let mut entries = Vec::new();
+        let mut add_entry = |row: u32, col: u32, mut val| {
+            val = strip_optbarrier(module.eval, val);
+            if val != F_ZERO {
+                let val = EvalOutput::new(module, val, slots, false, ty_real);
+                entries.push(SensitivityEntry { pos: row * num_cols + col, val })
+            }
+        };
+
+        for entry in &dae_system.sensitivities {
+            let row: u32 = entry.row.into();
+            add_entry(row, entry.col, entry.resist);
+            add_entry(num_nodes + row, entry.col, entry.react);
+        }
+        for entry in &dae_system.opvar_sensitivities {
+            let row = module.info.op_vars.get_index_of(&entry.var).unwrap() as u32;
+            add_entry(2 * num_nodes + row, entry.col, entry.val);
+        }
+        entries
+    }
+}
+
 pub struct OsdiInstanceData<'ll> {
     /// llvm type for the instance data struct
     pub ty: &'ll llvm::Type,
@@ -206,9 +280,12 @@ pub struct OsdiInstanceData<'ll> {
 
     pub residual: TiVec<SimUnknown, Residual>,
     pub noise: Vec<NoiseSource>,
+    pub ac_stims: Vec<AcStim>,
+    pub sensitivities: Vec<SensitivityEntry>,
     pub opvars: IndexMap<Variable, EvalOutput, RandomState>,
     pub jacobian: TiVec<MatrixEntryId, MatrixEntry>,
     pub bound_step: Option<EvalOutputSlot>,
+    pub breakpoint: Option<EvalOutputSlot>,
 }
 
 impl<'ll> OsdiInstanceData<'ll> {
@@ -266,12 +343,25 @@ impl<'ll> OsdiInstanceData<'ll> {
             .iter()
             .map(|source| NoiseSource::new(source, module, &mut eval_outputs, ty_f64))
             .collect();
+        let ac_stims = module
+            .dae_system
+            .ac_stims
+            .iter()
+            .map(|stim| AcStim::new(stim, module, &mut eval_outputs, ty_f64))
+            .collect();
+        let sensitivities = SensitivityEntry::build(module, &mut eval_outputs, ty_f64);
         let bound_step = module.intern.outputs.get(&PlaceKind::BoundStep).and_then(|val| {
             let mut val = val.expand()?;
             val = strip_optbarrier(module.eval, val);
             let slot = eval_outputs.insert_full(val, ty_f64).0;
             Some(slot)
         });
+        let breakpoint = module.intern.outputs.get(&PlaceKind::Breakpoint).and_then(|val| {
+            let mut val = val.expand()?;
+            val = strip_optbarrier(module.eval, val);
+            let slot = eval_outputs.insert_full(val, ty_f64).0;
+            Some(slot)
+        });
 
         let param_given = bitfield::arr_ty(params.len() as u32, cx);
         let jacobian_ptr = cx.ty_array(cx.ty_ptr(), module.dae_system.jacobian.len() as u32);
@@ -285,6 +375,8 @@ impl<'ll> OsdiInstanceData<'ll> {
             module.init.cache_slots.raw.values().map(|ty| lltype(ty, cx)).collect();
 
         let state_idx = cx.ty_array(cx.ty_int(), module.intern.lim_state.len() as u32);
+        let op_state = cx.ty_array(ty_f64, module.intern.op_states.len() as u32);
+        let absdelays = cx.ty_array(ty_f64, 2 * module.intern.absdelays.len() as u32);
         let static_fields: [_; NUM_CONST_FIELDS as usize] = [
             param_given,
             jacobian_ptr,
@@ -294,6 +386,9 @@ impl<'ll> OsdiInstanceData<'ll> {
             temperature,
             connected_ports,
             state_idx,
+            op_state,
+            cx.ty_ptr(),
+            absdelays,
         ];
 
         let fields: Vec<_> = static_fields
@@ -320,9 +415,12 @@ impl<'ll> OsdiInstanceData<'ll> {
             cache_slots,
             residual,
             noise,
+            ac_stims,
+            sensitivities,
             opvars,
             jacobian,
             bound_step,
+            breakpoint,
         }
     }
 
@@ -341,6 +439,21 @@ impl<'ll> OsdiInstanceData<'ll> {
         Some(elem)
     }
 
+    pub unsafe fn store_breakpoint(
+        &self,
+        ptr: &'ll llvm::Value,
+        builder: &mir_llvm::Builder<'_, '_, 'll>,
+    ) {
+        if let Some(slot) = self.breakpoint {
+            self.store_eval_output_slot(slot, ptr, builder);
+        }
+    }
+
+    pub fn breakpoint_elem(&self) -> Option<u32> {
+        let elem = self.eval_output_slot_elem(self.breakpoint?);
+        Some(elem)
+    }
+
     pub unsafe fn param_ptr(
         &self,
         param: OsdiInstanceParam,
@@ -614,6 +727,107 @@ impl<'ll> OsdiInstanceData<'ll> {
         LLVMBuildLoad2(llbuilder, cx.ty_int(), ptr, UNNAMED)
     }
 
+    /// Pointer to the table of files opened by this instance (NULL until the first `$fopen`)
+    pub unsafe fn files_ptr(
+        &self,
+        ptr: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> &'ll llvm::Value {
+        LLVMBuildStructGEP2(llbuilder, self.ty, ptr, FILES, UNNAMED)
+    }
+
+    pub fn op_state_loc(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        state: OpState,
+        ptr: &'ll llvm::Value,
+    ) -> MemLoc<'ll> {
+        MemLoc {
+            ptr,
+            ptr_ty: self.ty,
+            ty: cx.ty_double(),
+            indices: vec![
+                cx.const_unsigned_int(0),
+                cx.const_unsigned_int(OP_STATE),
+                cx.const_unsigned_int(state.into()),
+            ]
+            .into_boxed_slice(),
+        }
+    }
+
+    /// The delay of an `absdelay` (written by eval) if `value` is false and otherwise
+    /// the delayed value that is provided by the simulator
+    pub fn absdelay_loc(
+        &self,
+        cx: &CodegenCx<'_, 'll>,
+        absdelay: Absdelay,
+        value: bool,
+        ptr: &'ll llvm::Value,
+    ) -> MemLoc<'ll> {
+        let pos = 2 * u32::from(absdelay) + value as u32;
+        MemLoc {
+            ptr,
+            ptr_ty: self.ty,
+            ty: cx.ty_double(),
+            indices: vec![
+                cx.const_unsigned_int(0),
+                cx.const_unsigned_int(ABSDELAYS),
+                cx.const_unsigned_int(pos),
+            ]
+            .into_boxed_slice(),
+        }
+    }
+
+    /// Writes the delay of each `absdelay` to the instance data so that simulators
+    /// can provide the delayed values
+    pub unsafe fn store_absdelays(
+        &self,
+        module: &OsdiModule<'_>,
+        ptr: &'ll llvm::Value,
+        builder: &mir_llvm::Builder<'_, '_, 'll>,
+    ) {
+        let intern = module.intern;
+        for absdelay in intern.absdelays.keys() {
+            let Some(val) = intern.outputs.get(&PlaceKind::AbsdelayDelay(absdelay)) else {
+                continue;
+            };
+            let Some(val) = val.expand() else { continue };
+            let val = strip_optbarrier(module.eval, val);
+            let val = builder.values[val].get(builder);
+            let ptr = self.absdelay_loc(builder.cx, absdelay, false, ptr).to_ptr(builder.llbuilder);
+            builder.store(ptr, val)
+        }
+    }
+
+    /// Writes the new values of all analog operator states back to the instance data.
+    /// All values are computed before the first store so that reads of the previous
+    /// state are never affected by the stores.
+    pub unsafe fn store_op_states(
+        &self,
+        module: &OsdiModule<'_>,
+        ptr: &'ll llvm::Value,
+        builder: &mir_llvm::Builder<'_, '_, 'll>,
+    ) {
+        let intern = module.intern;
+        let vals: Vec<_> = intern
+            .op_states
+            .keys()
+            .filter_map(|state| {
+                let val = intern.outputs.get(&PlaceKind::OpState(state))?.expand()?;
+                let val = strip_optbarrier(module.eval, val);
+                if intern.params.get(&ParamKind::PrevOpState(state)) == Some(&val) {
+                    return None;
+                }
+                Some((state, builder.values[val].get(builder)))
+            })
+            .collect();
+
+        for (state, val) in vals {
+            let ptr = self.op_state_loc(builder.cx, state, ptr).to_ptr(builder.llbuilder);
+            builder.store(ptr, val)
+        }
+    }
+
     pub unsafe fn read_node_voltage(
         &self,
         cx: &CodegenCx<'_, 'll>,
@@ -950,8 +1164,13 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     | ParamKind::Abstime
                     | ParamKind::EnableIntegration
                     | ParamKind::EnableLim
+                    | ParamKind::InitialStep
+                    | ParamKind::FinalStep
                     | ParamKind::PrevState(_)
                     | ParamKind::NewState(_)
+                    | ParamKind::PrevOpState(_)
+                    | ParamKind::ExactAbsdelay
+                    | ParamKind::DelayedValue(_)
                     | ParamKind::ImplicitUnknown(_) => unreachable!(),
                 }
             }
@@ -1024,8 +1243,13 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                     | ParamKind::EnableIntegration { .. }
                     | ParamKind::Abstime
                     | ParamKind::EnableLim
+                    | ParamKind::InitialStep
+                    | ParamKind::FinalStep
                     | ParamKind::PrevState(_)
                     | ParamKind::NewState(_)
+                    | ParamKind::PrevOpState(_)
+                    | ParamKind::ExactAbsdelay
+                    | ParamKind::DelayedValue(_)
                     | ParamKind::ImplicitUnknown(_) => unreachable!(),
                 }
             }
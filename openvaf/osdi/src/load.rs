@@ -4,8 +4,7 @@ use llvm::{
     LLVMDisposeBuilder, LLVMGetParam, LLVMPositionBuilderAtEnd, LLVMSetFastMath,
     LLVMSetPartialFastMath, UNNAMED,
 };
-use sim_back::dae::NoiseSourceKind;
-use stdx::iter::zip;
+use sim_back::dae::{correlated_noise_sources, NoiseSourceKind};
 use typed_index_collections::TiVec;
 
 use crate::compilation_unit::OsdiCompilationUnit;
@@ -57,32 +56,8 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             let freq = LLVMGetParam(llfunc, 2);
             let dst = LLVMGetParam(llfunc, 3);
 
-            for (i, (src, eval_outputs)) in
-                zip(&module.dae_system.noise_sources, &self.inst_data.noise).enumerate()
-            {
-                let fac = self.load_eval_output(eval_outputs.factor, inst, model, llbuilder);
-                let mut pwr = match src.kind {
-                    NoiseSourceKind::WhiteNoise { .. } => {
-                        self.load_eval_output(eval_outputs.args[0], inst, model, llbuilder)
-                    }
-                    NoiseSourceKind::FlickerNoise { .. } => {
-                        let mut pwr =
-                            self.load_eval_output(eval_outputs.args[0], inst, model, llbuilder);
-                        let exp =
-                            self.load_eval_output(eval_outputs.args[1], inst, model, llbuilder);
-                        let (ty, fun) = self
-                            .cx
-                            .intrinsic("llvm.pow.f64")
-                            .unwrap_or_else(|| unreachable!("intrinsic {} not found", name));
-                        let freq_exp =
-                            LLVMBuildCall2(llbuilder, ty, fun, [freq, exp].as_ptr(), 2, UNNAMED);
-                        LLVMSetPartialFastMath(freq_exp);
-                        pwr = LLVMBuildFDiv(llbuilder, pwr, freq_exp, UNNAMED);
-                        LLVMSetFastMath(pwr);
-                        pwr
-                    }
-                    NoiseSourceKind::NoiseTable { .. } => unimplemented!("noise tables"),
-                };
+            for i in 0..module.dae_system.noise_sources.len() {
+                let (fac, mut pwr) = self.noise_source(i, inst, model, freq, llbuilder);
                 // Multiply with squared factor because factor is in terms of signal, but
                 // we are computing the power, which is scaled by factor**2. 
                 pwr = LLVMBuildFMul(llbuilder, pwr, fac, UNNAMED);
@@ -108,6 +83,206 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         llfunc
     }
 
+    /// Writes the cross spectral densities of all correlated noise source pairs to `dst` as
+    /// consecutive (real, imaginary) pairs. Noise sources that share the same name are fully
+    /// correlated so the cross spectral density of two such sources is
+    /// `fac_1*fac_2*sqrt(pwr_1*pwr_2)`. The factors are always real so the imaginary part is zero.
+    pub fn load_noise_correlated(&self) -> &'ll llvm::Value {
+        let OsdiCompilationUnit { cx, module, .. } = self;
+        let void_ptr = cx.ty_ptr();
+        let f64_ptr_ty = cx.ty_ptr();
+        let fun_ty = cx.ty_func(&[void_ptr, void_ptr, cx.ty_double(), f64_ptr_ty], cx.ty_void());
+        let name = &format!("load_noise_correlated_{}", module.sym);
+        let llfunc = cx.declare_int_c_fn(name, fun_ty);
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let llbuilder = LLVMCreateBuilderInContext(cx.llcx);
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+            let inst = LLVMGetParam(llfunc, 0);
+            let model = LLVMGetParam(llfunc, 1);
+            let freq = LLVMGetParam(llfunc, 2);
+            let dst = LLVMGetParam(llfunc, 3);
+
+            let correlations = correlated_noise_sources(&module.dae_system.noise_sources);
+            for (i, &(src1, src2)) in correlations.iter().enumerate() {
+                let (fac1, pwr1) = self.noise_source(src1, inst, model, freq, llbuilder);
+                let (fac2, pwr2) = self.noise_source(src2, inst, model, freq, llbuilder);
+                let pwr = LLVMBuildFMul(llbuilder, pwr1, pwr2, UNNAMED);
+                LLVMSetFastMath(pwr);
+                let (ty, fun) = cx
+                    .intrinsic("llvm.sqrt.f64")
+                    .unwrap_or_else(|| unreachable!("intrinsic llvm.sqrt.f64 not found"));
+                let mut cross = LLVMBuildCall2(llbuilder, ty, fun, [pwr].as_ptr(), 1, UNNAMED);
+                LLVMSetPartialFastMath(cross);
+                cross = LLVMBuildFMul(llbuilder, cross, fac1, UNNAMED);
+                LLVMSetFastMath(cross);
+                cross = LLVMBuildFMul(llbuilder, cross, fac2, UNNAMED);
+                LLVMSetFastMath(cross);
+
+                for (j, val) in [cross, cx.const_real(0f64)].into_iter().enumerate() {
+                    let dst = LLVMBuildGEP2(
+                        llbuilder,
+                        cx.ty_double(),
+                        dst,
+                        [cx.const_unsigned_int((2 * i + j) as u32)].as_ptr(),
+                        1,
+                        UNNAMED,
+                    );
+                    LLVMBuildStore(llbuilder, val, dst);
+                }
+            }
+
+            LLVMBuildRetVoid(llbuilder);
+            LLVMDisposeBuilder(llbuilder);
+        }
+
+        llfunc
+    }
+
+    /// Builds the factor and the (unscaled) power spectral density of a noise source at `freq`.
+    unsafe fn noise_source(
+        &self,
+        src: usize,
+        inst: &'ll llvm::Value,
+        model: &'ll llvm::Value,
+        freq: &'ll llvm::Value,
+        llbuilder: &llvm::Builder<'ll>,
+    ) -> (&'ll llvm::Value, &'ll llvm::Value) {
+        let cx = self.cx;
+        let eval_outputs = &self.inst_data.noise[src];
+        let fac = self.load_eval_output(eval_outputs.factor, inst, model, llbuilder);
+        let pwr = match self.module.dae_system.noise_sources[src].kind {
+            NoiseSourceKind::WhiteNoise { .. } => {
+                self.load_eval_output(eval_outputs.args[0], inst, model, llbuilder)
+            }
+            NoiseSourceKind::FlickerNoise { .. } => {
+                let mut pwr = self.load_eval_output(eval_outputs.args[0], inst, model, llbuilder);
+                let exp = self.load_eval_output(eval_outputs.args[1], inst, model, llbuilder);
+                let (ty, fun) = self
+                    .cx
+                    .intrinsic("llvm.pow.f64")
+                    .unwrap_or_else(|| unreachable!("intrinsic llvm.pow.f64 not found"));
+                let freq_exp = LLVMBuildCall2(llbuilder, ty, fun, [freq, exp].as_ptr(), 2, UNNAMED);
+                LLVMSetPartialFastMath(freq_exp);
+                pwr = LLVMBuildFDiv(llbuilder, pwr, freq_exp, UNNAMED);
+                LLVMSetFastMath(pwr);
+                pwr
+            }
+            NoiseSourceKind::NoiseTable { log, ref vals } => {
+                let table: Vec<_> = vals
+                    .iter()
+                    .flat_map(|&(freq, pwr)| {
+                        [cx.const_real(freq.into()), cx.const_real(pwr.into())]
+                    })
+                    .collect();
+                let table = cx.const_arr_ptr(cx.ty_double(), &table);
+                let fun = cx
+                    .get_func_by_name("noise_table")
+                    .expect("stdlib function noise_table is missing");
+                let fun_ty = cx.ty_func(
+                    &[cx.ty_ptr(), cx.ty_int(), cx.ty_double(), cx.ty_int()],
+                    cx.ty_double(),
+                );
+                let args = [
+                    table,
+                    cx.const_unsigned_int(vals.len() as u32),
+                    freq,
+                    cx.const_int(log as i32),
+                ];
+                LLVMBuildCall2(llbuilder, fun_ty, fun, args.as_ptr(), 4, UNNAMED)
+            }
+        };
+        (fac, pwr)
+    }
+
+    /// Writes the phasors of all `ac_stim` sources to `dst` as consecutive (real, imaginary)
+    /// pairs: `dst[2*i] = fac*mag*cos(phase)` and `dst[2*i+1] = fac*mag*sin(phase)`.
+    pub fn load_ac_stim(&self) -> &'ll llvm::Value {
+        let OsdiCompilationUnit { cx, module, .. } = self;
+        let void_ptr = cx.ty_ptr();
+        let fun_ty = cx.ty_func(&[void_ptr, void_ptr, cx.ty_ptr()], cx.ty_void());
+        let name = &format!("load_ac_stim_{}", module.sym);
+        let llfunc = cx.declare_int_c_fn(name, fun_ty);
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let llbuilder = LLVMCreateBuilderInContext(cx.llcx);
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+            let inst = LLVMGetParam(llfunc, 0);
+            let model = LLVMGetParam(llfunc, 1);
+            let dst = LLVMGetParam(llfunc, 2);
+
+            for (i, stim) in self.inst_data.ac_stims.iter().enumerate() {
+                let fac = self.load_eval_output(stim.factor, inst, model, llbuilder);
+                let mag = self.load_eval_output(stim.mag, inst, model, llbuilder);
+                let phase = self.load_eval_output(stim.phase, inst, model, llbuilder);
+                let amplitude = LLVMBuildFMul(llbuilder, fac, mag, UNNAMED);
+                LLVMSetFastMath(amplitude);
+                for (j, intrinsic) in ["llvm.cos.f64", "llvm.sin.f64"].into_iter().enumerate() {
+                    let (ty, fun) = cx
+                        .intrinsic(intrinsic)
+                        .unwrap_or_else(|| unreachable!("intrinsic {} not found", intrinsic));
+                    let val = LLVMBuildCall2(llbuilder, ty, fun, [phase].as_ptr(), 1, UNNAMED);
+                    LLVMSetPartialFastMath(val);
+                    let val = LLVMBuildFMul(llbuilder, amplitude, val, UNNAMED);
+                    LLVMSetFastMath(val);
+                    let dst = LLVMBuildGEP2(
+                        llbuilder,
+                        cx.ty_double(),
+                        dst,
+                        [cx.const_unsigned_int((2 * i + j) as u32)].as_ptr(),
+                        1,
+                        UNNAMED,
+                    );
+                    LLVMBuildStore(llbuilder, val, dst);
+                }
+            }
+
+            LLVMBuildRetVoid(llbuilder);
+            LLVMDisposeBuilder(llbuilder);
+        }
+
+        llfunc
+    }
+
+    /// Writes the sensitivities calculated by the last call to eval (with `CALC_SENSITIVITY`)
+    /// to the dense row-major matrix `dst`. Entries that are always zero are not written.
+    pub fn write_sensitivity(&self) -> &'ll llvm::Value {
+        let OsdiCompilationUnit { cx, module, .. } = self;
+        let void_ptr = cx.ty_ptr();
+        let fun_ty = cx.ty_func(&[void_ptr, void_ptr, cx.ty_ptr()], cx.ty_void());
+        let name = &format!("write_sensitivity_{}", module.sym);
+        let llfunc = cx.declare_int_c_fn(name, fun_ty);
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let llbuilder = LLVMCreateBuilderInContext(cx.llcx);
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+            let inst = LLVMGetParam(llfunc, 0);
+            let model = LLVMGetParam(llfunc, 1);
+            let dst = LLVMGetParam(llfunc, 2);
+
+            for entry in &self.inst_data.sensitivities {
+                let val = self.load_eval_output(entry.val, inst, model, llbuilder);
+                let dst = LLVMBuildGEP2(
+                    llbuilder,
+                    cx.ty_double(),
+                    dst,
+                    [cx.const_unsigned_int(entry.pos)].as_ptr(),
+                    1,
+                    UNNAMED,
+                );
+                LLVMBuildStore(llbuilder, val, dst);
+            }
+
+            LLVMBuildRetVoid(llbuilder);
+            LLVMDisposeBuilder(llbuilder);
+        }
+
+        llfunc
+    }
+
     pub fn load_residual(&self, reactive: bool) -> &'ll llvm::Value {
         let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
         let ptr_ty = cx.ty_ptr();
@@ -1,25 +1,28 @@
 use std::iter::once;
 
-use hir::{CompilationDB, ParamSysFun, Type};
-use hir_lower::CurrentKind;
+use hir::{CompilationDB, ConstraintKind, ConstraintValue, Literal, ParamSysFun, Parameter, Type};
+use hir_lower::{CurrentKind, HirInterner, ImplicitEquationKind};
 use lasso::{Rodeo, Spur};
 use llvm::{LLVMABISizeOfType, LLVMOffsetOfElement, TargetData};
 use mir::{ValueDef, F_ZERO};
 use mir_llvm::CodegenCx;
-use sim_back::dae::MatrixEntry;
+use sim_back::dae::{correlated_noise_sources, MatrixEntry};
 use sim_back::SimUnknownKind;
 use smol_str::SmolStr;
 
 use crate::compilation_unit::{OsdiCompilationUnit, OsdiModule};
 use crate::inst_data::{
-    OsdiInstanceParam, COLLAPSED, JACOBIAN_PTR_REACT, JACOBIAN_PTR_RESIST, NODE_MAPPING, STATE_IDX
+    OsdiInstanceParam, ABSDELAYS, COLLAPSED, FILES, JACOBIAN_PTR_REACT, JACOBIAN_PTR_RESIST,
+    NODE_MAPPING, OP_STATE, STATE_IDX,
 };
 use crate::load::JacobianLoadType;
 use crate::metadata::osdi_0_4::{
-    OsdiDescriptor, OsdiJacobianEntry, OsdiNode, OsdiNodePair, OsdiNoiseSource, OsdiParamOpvar,
-    OsdiTys, JACOBIAN_ENTRY_REACT, JACOBIAN_ENTRY_REACT_CONST, JACOBIAN_ENTRY_RESIST,
-    JACOBIAN_ENTRY_RESIST_CONST, PARA_KIND_INST, PARA_KIND_MODEL, PARA_KIND_OPVAR, PARA_TY_INT,
-    PARA_TY_REAL, PARA_TY_STR,
+    OsdiAbsdelay, OsdiAcStim, OsdiDescriptor, OsdiJacobianEntry, OsdiNode, OsdiNodePair,
+    OsdiNoiseCorrelation, OsdiNoiseSource, OsdiParamBound, OsdiParamMeta, OsdiParamOpvar, OsdiTys,
+    JACOBIAN_ENTRY_REACT, JACOBIAN_ENTRY_REACT_CONST, JACOBIAN_ENTRY_RESIST,
+    JACOBIAN_ENTRY_RESIST_CONST, PARA_BOUND_DYNAMIC, PARA_BOUND_EXCLUDE, PARA_BOUND_MAX_INCLUSIVE,
+    PARA_BOUND_MIN_INCLUSIVE, PARA_DEFAULT_DYNAMIC, PARA_KIND_INST, PARA_KIND_MODEL,
+    PARA_KIND_OPVAR, PARA_TY_INT, PARA_TY_REAL, PARA_TY_STR,
 };
 use crate::ty_len;
 
@@ -135,6 +138,30 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         inst_params.chain(model_params).chain(opvars).collect()
     }
 
+    /// Default values and bounds of all parameters (in the same order as `param_opvar`)
+    pub fn param_meta(&self) -> Vec<OsdiParamMeta> {
+        let OsdiCompilationUnit { inst_data, model_data, module, db, .. } = self;
+
+        let inst_params = inst_data.params.keys().map(|param| match *param {
+            OsdiInstanceParam::Builtin(builtin) => OsdiParamMeta {
+                flags: 0,
+                default_val: builtin.default_value(),
+                default_str: String::new(),
+                num_bounds: 0,
+                bounds: Vec::new(),
+            },
+            OsdiInstanceParam::User(param) => user_param_meta(db, param),
+        });
+
+        let model_params = model_data
+            .params
+            .keys()
+            .filter(|param| !module.info.params[*param].is_instance)
+            .map(|param| user_param_meta(db, *param));
+
+        inst_params.chain(model_params).collect()
+    }
+
     pub fn nodes(&self, target_data: &TargetData, db: &CompilationDB) -> Vec<OsdiNode> {
         let OsdiCompilationUnit { inst_data, module, .. } = self;
         module
@@ -142,7 +169,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             .unknowns
             .iter_enumerated()
             .map(|(id, unknown)| {
-                let (name, units, is_flow) = sim_unknown_info(*unknown, db);
+                let (name, units, is_flow) = sim_unknown_info(*unknown, module.intern, db);
                 let resist_residual_off =
                     inst_data.residual_off(id, false, target_data).unwrap_or(u32::MAX);
                 let react_residual_off =
@@ -262,6 +289,11 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             });
             
             let state_idx_off = LLVMOffsetOfElement(target_data, inst_data.ty, STATE_IDX) as u32;
+            let op_state_offset = LLVMOffsetOfElement(target_data, inst_data.ty, OP_STATE) as u32;
+            let files_offset = LLVMOffsetOfElement(target_data, inst_data.ty, FILES) as u32;
+            let breakpoint_offset = inst_data.breakpoint_elem().map_or(u32::MAX, |elem| {
+                LLVMOffsetOfElement(target_data, inst_data.ty, elem) as u32
+            });
 
             let instance_size = LLVMABISizeOfType(target_data, inst_data.ty) as u32;
             let model_size = LLVMABISizeOfType(target_data, model_data.ty) as u32;
@@ -278,6 +310,67 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 })
                 .collect();
 
+            let ac_stims: Vec<_> = module
+                .dae_system
+                .ac_stims
+                .iter()
+                .map(|stim| {
+                    let node_1: u32 = stim.hi.into();
+                    let node_2: u32 = stim.lo.map_or(u32::MAX, u32::from);
+                    let analysis = cx.literals.resolve(&stim.analysis).to_owned();
+                    OsdiAcStim { analysis, nodes: OsdiNodePair { node_1, node_2 } }
+                })
+                .collect();
+
+            // the delay and the delayed value of each absdelay are stored next to each other
+            let absdelays_offset = LLVMOffsetOfElement(target_data, inst_data.ty, ABSDELAYS) as u32;
+            let absdelays: Vec<_> = module
+                .intern
+                .absdelays
+                .iter_enumerated()
+                .filter_map(|(absdelay, equations)| {
+                    let unknown = |equation| {
+                        let kind = SimUnknownKind::Implicit(equation);
+                        module.dae_system.unknowns.index(&kind).map(u32::from)
+                    };
+                    let delay_offset = absdelays_offset + 16 * u32::from(absdelay);
+                    Some(OsdiAbsdelay {
+                        input: unknown(equations.input)?,
+                        output: unknown(equations.output)?,
+                        pade_1: unknown(equations.pade[0])?,
+                        pade_2: unknown(equations.pade[1])?,
+                        delay_offset,
+                        value_offset: delay_offset + 8,
+                    })
+                })
+                .collect();
+
+            let noise_correlations: Vec<_> =
+                correlated_noise_sources(&module.dae_system.noise_sources)
+                    .into_iter()
+                    .map(|(src_1, src_2)| OsdiNoiseCorrelation {
+                        src_1: src_1 as u32,
+                        src_2: src_2 as u32,
+                    })
+                    .collect();
+
+            // instance parameters have the ids 0..inst_data.params.len() and are followed by the
+            // model parameters
+            let sensitivity_params: Vec<_> = module
+                .dae_system
+                .sensitivity_params
+                .iter()
+                .map(|param| {
+                    match inst_data.params.get_index_of(&OsdiInstanceParam::User(*param)) {
+                        Some(pos) => pos as u32,
+                        None => {
+                            let pos = model_data.params.get_index_of(param).unwrap();
+                            (inst_data.params.len() + pos) as u32
+                        }
+                    }
+                })
+                .collect();
+
             OsdiDescriptor {
                 name: module.info.module.name(db),
                 num_nodes: module.dae_system.unknowns.len() as u32,
@@ -329,22 +422,123 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
                 inputs: inputs, 
                 load_jacobian_with_offset_resist: self.load_jacobian(JacobianLoadType::Resist, true),
                 load_jacobian_with_offset_react: self.load_jacobian(JacobianLoadType::React, true),
+                num_op_states: module.intern.op_states.len() as u32,
+                op_state_offset,
+                breakpoint_offset,
+                num_ac_stims: ac_stims.len() as u32,
+                ac_stims,
+                load_ac_stim: self.load_ac_stim(),
+                files_offset,
+                close_files: self.close_files(),
+                num_sensitivity_params: sensitivity_params.len() as u32,
+                sensitivity_params,
+                write_sensitivity: self.write_sensitivity(),
+                param_meta: self.param_meta(),
+                num_noise_correlations: noise_correlations.len() as u32,
+                noise_correlations,
+                load_noise_correlated: self.load_noise_correlated(),
+                bound_violations: self.bound_violations(),
+                num_absdelays: absdelays.len() as u32,
+                absdelays,
             }
         }
     }
 }
 
+/// Bounds and default values can be arbitrary expressions (that may depend on other parameters).
+/// Only those that are constant are reported as values, the remaining ones are flagged as dynamic.
+fn user_param_meta(db: &CompilationDB, param: Parameter) -> OsdiParamMeta {
+    let ty = param.ty(db);
+    let body = param.init(db);
+    let body = body.borrow();
+    let is_array = matches!(ty, Type::Array { .. });
+    let is_str = *ty.base_type() == Type::String;
+
+    let mut flags = 0;
+    let mut default_val = f64::NAN;
+    let mut default_str = String::new();
+    let default = param.default(db);
+    if is_array {
+        flags |= PARA_DEFAULT_DYNAMIC;
+    } else if is_str {
+        match body.as_literal(default) {
+            Some(Literal::String(val)) => default_str = val.to_string(),
+            _ => flags |= PARA_DEFAULT_DYNAMIC,
+        }
+    } else {
+        match body.const_eval_real(default) {
+            Some(val) => default_val = val,
+            None => flags |= PARA_DEFAULT_DYNAMIC,
+        }
+    }
+
+    let const_val = |expr| -> Option<(f64, String)> {
+        if is_str {
+            match body.as_literal(expr) {
+                Some(Literal::String(val)) => Some((f64::NAN, val.to_string())),
+                _ => None,
+            }
+        } else {
+            Some((body.const_eval_real(expr)?, String::new()))
+        }
+    };
+
+    let bounds: Vec<_> = param
+        .bounds(db)
+        .iter()
+        .map(|bound| {
+            let mut flags = match bound.kind {
+                ConstraintKind::From => 0,
+                ConstraintKind::Exclude => PARA_BOUND_EXCLUDE,
+            };
+            let (min, max, str_val) = match bound.val {
+                ConstraintValue::Value(val) => {
+                    flags |= PARA_BOUND_MIN_INCLUSIVE | PARA_BOUND_MAX_INCLUSIVE;
+                    match const_val(val) {
+                        Some((val, str_val)) => (val, val, str_val),
+                        None => {
+                            flags |= PARA_BOUND_DYNAMIC;
+                            (f64::NAN, f64::NAN, String::new())
+                        }
+                    }
+                }
+                ConstraintValue::Range(range) => {
+                    if range.start_inclusive {
+                        flags |= PARA_BOUND_MIN_INCLUSIVE;
+                    }
+                    if range.end_inclusive {
+                        flags |= PARA_BOUND_MAX_INCLUSIVE;
+                    }
+                    let start = body.const_eval_real(range.start);
+                    let end = body.const_eval_real(range.end);
+                    if start.is_none() || end.is_none() {
+                        flags |= PARA_BOUND_DYNAMIC;
+                    }
+                    (start.unwrap_or(f64::NAN), end.unwrap_or(f64::NAN), String::new())
+                }
+            };
+            OsdiParamBound { flags, min, max, str_val }
+        })
+        .collect();
+
+    OsdiParamMeta { flags, default_val, default_str, num_bounds: bounds.len() as u32, bounds }
+}
+
 impl OsdiModule<'_> {
     pub fn intern_node_strs(&self, intern: &mut Rodeo, db: &CompilationDB) {
         for &unknown in self.dae_system.unknowns.iter() {
-            let (name, units, _) = sim_unknown_info(unknown, db);
+            let (name, units, _) = sim_unknown_info(unknown, self.intern, db);
             intern.get_or_intern(&name);
             intern.get_or_intern(&units);
         }
     }
 }
 
-fn sim_unknown_info(unknown: SimUnknownKind, db: &CompilationDB) -> (String, String, bool) {
+fn sim_unknown_info(
+    unknown: SimUnknownKind,
+    intern: &HirInterner,
+    db: &CompilationDB,
+) -> (String, String, bool) {
     let name;
     let discipline;
     let is_flow;
@@ -376,7 +570,11 @@ fn sim_unknown_info(unknown: SimUnknownKind, db: &CompilationDB) -> (String, Str
             is_flow = true;
         }
         SimUnknownKind::Implicit(equ) => {
-            name = format!("implicit_equation_{}", u32::from(equ));
+            // the equations of each absdelay are listed in the `absdelays` descriptor entry
+            name = match intern.implicit_equations[equ] {
+                ImplicitEquationKind::Absdelay => format!("absdelay_{}", u32::from(equ)),
+                _ => format!("implicit_equation_{}", u32::from(equ)),
+            };
             discipline = None;
             is_flow = false;
         }
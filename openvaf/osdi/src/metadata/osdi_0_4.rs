@@ -59,10 +59,16 @@ pub const ANALYSIS_TRAN: u32 = 8192;
 pub const ANALYSIS_IC: u32 = 16384;
 pub const ANALYSIS_STATIC: u32 = 32768;
 pub const ANALYSIS_NODESET: u32 = 65536;
+pub const INITIAL_STEP: u32 = 131072;
+pub const FINAL_STEP: u32 = 262144;
+pub const CALC_SENSITIVITY: u32 = 524288;
+pub const PHASE_AWARE: u32 = 1048576;
+pub const EXACT_ABSDELAY: u32 = 2097152;
 pub const EVAL_RET_FLAG_LIM: u32 = 1;
 pub const EVAL_RET_FLAG_FATAL: u32 = 2;
 pub const EVAL_RET_FLAG_FINISH: u32 = 4;
 pub const EVAL_RET_FLAG_STOP: u32 = 8;
+pub const EVAL_RET_FLAG_EVENT: u32 = 16;
 pub const LOG_LVL_MASK: u32 = 7;
 pub const LOG_LVL_DEBUG: u32 = 0;
 pub const LOG_LVL_DISPLAY: u32 = 1;
@@ -72,6 +78,12 @@ pub const LOG_LVL_ERR: u32 = 4;
 pub const LOG_LVL_FATAL: u32 = 5;
 pub const LOG_FMT_ERR: u32 = 16;
 pub const INIT_ERR_OUT_OF_BOUNDS: u32 = 1;
+pub const PARA_BOUND_EXCLUDE: u32 = 1;
+pub const PARA_BOUND_MIN_INCLUSIVE: u32 = 2;
+pub const PARA_BOUND_MAX_INCLUSIVE: u32 = 4;
+pub const PARA_BOUND_DYNAMIC: u32 = 8;
+pub const PARA_DEFAULT_DYNAMIC: u32 = 1;
+pub const PARA_BOUND_UNKNOWN: u32 = 0xFFFFFFFF;
 
 pub struct OsdiLimFunction<'ll> {
     pub name: String,
@@ -147,6 +159,14 @@ impl OsdiTyBuilder<'_, '_, '_> {
         self.osdi_init_error = Some(ty);
     }
 }
+impl OsdiTyBuilder<'_, '_, '_> {
+    fn osdi_bound_violation(&mut self) {
+        let ctx = self.ctx;
+        let fields = [ctx.ty_int(), ctx.ty_double()];
+        let ty = ctx.ty_struct("OsdiBoundViolation", &fields);
+        self.osdi_bound_violation = Some(ty);
+    }
+}
 impl OsdiTyBuilder<'_, '_, '_> {
     fn osdi_init_info(&mut self) {
         let ctx = self.ctx;
@@ -292,6 +312,130 @@ impl OsdiTyBuilder<'_, '_, '_> {
         self.osdi_noise_source = Some(ty);
     }
 }
+pub struct OsdiNoiseCorrelation {
+    pub src_1: u32,
+    pub src_2: u32,
+}
+impl OsdiNoiseCorrelation {
+    pub fn to_ll_val<'ll>(&self, ctx: &CodegenCx<'_, 'll>, tys: &'ll OsdiTys) -> &'ll llvm::Value {
+        let fields = [ctx.const_unsigned_int(self.src_1), ctx.const_unsigned_int(self.src_2)];
+        let ty = tys.osdi_noise_correlation;
+        ctx.const_struct(ty, &fields)
+    }
+}
+impl OsdiTyBuilder<'_, '_, '_> {
+    fn osdi_noise_correlation(&mut self) {
+        let ctx = self.ctx;
+        let fields = [ctx.ty_int(), ctx.ty_int()];
+        let ty = ctx.ty_struct("OsdiNoiseCorrelation", &fields);
+        self.osdi_noise_correlation = Some(ty);
+    }
+}
+pub struct OsdiAcStim {
+    pub analysis: String,
+    pub nodes: OsdiNodePair,
+}
+impl OsdiAcStim {
+    pub fn to_ll_val<'ll>(&self, ctx: &CodegenCx<'_, 'll>, tys: &'ll OsdiTys) -> &'ll llvm::Value {
+        let fields = [ctx.const_str_uninterned(&self.analysis), self.nodes.to_ll_val(ctx, tys)];
+        let ty = tys.osdi_ac_stim;
+        ctx.const_struct(ty, &fields)
+    }
+}
+impl OsdiTyBuilder<'_, '_, '_> {
+    fn osdi_ac_stim(&mut self) {
+        let ctx = self.ctx;
+        let fields = [ctx.ty_ptr(), self.osdi_node_pair.unwrap()];
+        let ty = ctx.ty_struct("OsdiAcStim", &fields);
+        self.osdi_ac_stim = Some(ty);
+    }
+}
+pub struct OsdiAbsdelay {
+    pub input: u32,
+    pub output: u32,
+    pub pade_1: u32,
+    pub pade_2: u32,
+    pub delay_offset: u32,
+    pub value_offset: u32,
+}
+impl OsdiAbsdelay {
+    pub fn to_ll_val<'ll>(&self, ctx: &CodegenCx<'_, 'll>, tys: &'ll OsdiTys) -> &'ll llvm::Value {
+        let fields = [
+            ctx.const_unsigned_int(self.input),
+            ctx.const_unsigned_int(self.output),
+            ctx.const_unsigned_int(self.pade_1),
+            ctx.const_unsigned_int(self.pade_2),
+            ctx.const_unsigned_int(self.delay_offset),
+            ctx.const_unsigned_int(self.value_offset),
+        ];
+        let ty = tys.osdi_absdelay;
+        ctx.const_struct(ty, &fields)
+    }
+}
+impl OsdiTyBuilder<'_, '_, '_> {
+    fn osdi_absdelay(&mut self) {
+        let ctx = self.ctx;
+        let fields =
+            [ctx.ty_int(), ctx.ty_int(), ctx.ty_int(), ctx.ty_int(), ctx.ty_int(), ctx.ty_int()];
+        let ty = ctx.ty_struct("OsdiAbsdelay", &fields);
+        self.osdi_absdelay = Some(ty);
+    }
+}
+pub struct OsdiParamBound {
+    pub flags: u32,
+    pub min: f64,
+    pub max: f64,
+    pub str_val: String,
+}
+impl OsdiParamBound {
+    pub fn to_ll_val<'ll>(&self, ctx: &CodegenCx<'_, 'll>, tys: &'ll OsdiTys) -> &'ll llvm::Value {
+        let fields = [
+            ctx.const_unsigned_int(self.flags),
+            ctx.const_real(self.min),
+            ctx.const_real(self.max),
+            ctx.const_str_uninterned(&self.str_val),
+        ];
+        let ty = tys.osdi_param_bound;
+        ctx.const_struct(ty, &fields)
+    }
+}
+impl OsdiTyBuilder<'_, '_, '_> {
+    fn osdi_param_bound(&mut self) {
+        let ctx = self.ctx;
+        let fields = [ctx.ty_int(), ctx.ty_double(), ctx.ty_double(), ctx.ty_ptr()];
+        let ty = ctx.ty_struct("OsdiParamBound", &fields);
+        self.osdi_param_bound = Some(ty);
+    }
+}
+pub struct OsdiParamMeta {
+    pub flags: u32,
+    pub default_val: f64,
+    pub default_str: String,
+    pub num_bounds: u32,
+    pub bounds: Vec<OsdiParamBound>,
+}
+impl OsdiParamMeta {
+    pub fn to_ll_val<'ll>(&self, ctx: &CodegenCx<'_, 'll>, tys: &'ll OsdiTys) -> &'ll llvm::Value {
+        let arr_4: Vec<_> = self.bounds.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
+        let fields = [
+            ctx.const_unsigned_int(self.flags),
+            ctx.const_real(self.default_val),
+            ctx.const_str_uninterned(&self.default_str),
+            ctx.const_unsigned_int(self.num_bounds),
+            ctx.const_arr_ptr(tys.osdi_param_bound, &arr_4),
+        ];
+        let ty = tys.osdi_param_meta;
+        ctx.const_struct(ty, &fields)
+    }
+}
+impl OsdiTyBuilder<'_, '_, '_> {
+    fn osdi_param_meta(&mut self) {
+        let ctx = self.ctx;
+        let fields = [ctx.ty_int(), ctx.ty_double(), ctx.ty_ptr(), ctx.ty_int(), ctx.ty_ptr()];
+        let ty = ctx.ty_struct("OsdiParamMeta", &fields);
+        self.osdi_param_meta = Some(ty);
+    }
+}
 pub struct OsdiDescriptor<'ll> {
     pub name: String,
     pub num_nodes: u32,
@@ -339,6 +483,24 @@ pub struct OsdiDescriptor<'ll> {
     pub inputs: Vec<OsdiNodePair>,
     pub load_jacobian_with_offset_resist: &'ll llvm::Value,
     pub load_jacobian_with_offset_react: &'ll llvm::Value,
+    pub num_op_states: u32,
+    pub op_state_offset: u32,
+    pub breakpoint_offset: u32,
+    pub num_ac_stims: u32,
+    pub ac_stims: Vec<OsdiAcStim>,
+    pub load_ac_stim: &'ll llvm::Value,
+    pub files_offset: u32,
+    pub close_files: &'ll llvm::Value,
+    pub num_sensitivity_params: u32,
+    pub sensitivity_params: Vec<u32>,
+    pub write_sensitivity: &'ll llvm::Value,
+    pub param_meta: Vec<OsdiParamMeta>,
+    pub num_noise_correlations: u32,
+    pub noise_correlations: Vec<OsdiNoiseCorrelation>,
+    pub load_noise_correlated: &'ll llvm::Value,
+    pub bound_violations: &'ll llvm::Value,
+    pub num_absdelays: u32,
+    pub absdelays: Vec<OsdiAbsdelay>,
 }
 impl<'ll> OsdiDescriptor<'ll> {
     pub fn to_ll_val(&self, ctx: &CodegenCx<'_, 'll>, tys: &'ll OsdiTys) -> &'ll llvm::Value {
@@ -348,6 +510,13 @@ impl<'ll> OsdiDescriptor<'ll> {
         let arr_9: Vec<_> = self.noise_sources.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
         let arr_14: Vec<_> = self.param_opvar.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
         let arr_43: Vec<_> = self.inputs.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
+        let arr_50: Vec<_> = self.ac_stims.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
+        let arr_55: Vec<_> =
+            self.sensitivity_params.iter().map(|it| ctx.const_unsigned_int(*it)).collect();
+        let arr_57: Vec<_> = self.param_meta.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
+        let arr_59: Vec<_> =
+            self.noise_correlations.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
+        let arr_63: Vec<_> = self.absdelays.iter().map(|it| it.to_ll_val(ctx, tys)).collect();
         let fields = [
             ctx.const_str_uninterned(&self.name),
             ctx.const_unsigned_int(self.num_nodes),
@@ -395,6 +564,24 @@ impl<'ll> OsdiDescriptor<'ll> {
             ctx.const_arr_ptr(tys.osdi_node_pair, &arr_43),
             self.load_jacobian_with_offset_resist,
             self.load_jacobian_with_offset_react,
+            ctx.const_unsigned_int(self.num_op_states),
+            ctx.const_unsigned_int(self.op_state_offset),
+            ctx.const_unsigned_int(self.breakpoint_offset),
+            ctx.const_unsigned_int(self.num_ac_stims),
+            ctx.const_arr_ptr(tys.osdi_ac_stim, &arr_50),
+            self.load_ac_stim,
+            ctx.const_unsigned_int(self.files_offset),
+            self.close_files,
+            ctx.const_unsigned_int(self.num_sensitivity_params),
+            ctx.const_arr_ptr(ctx.ty_int(), &arr_55),
+            self.write_sensitivity,
+            ctx.const_arr_ptr(tys.osdi_param_meta, &arr_57),
+            ctx.const_unsigned_int(self.num_noise_correlations),
+            ctx.const_arr_ptr(tys.osdi_noise_correlation, &arr_59),
+            self.load_noise_correlated,
+            self.bound_violations,
+            ctx.const_unsigned_int(self.num_absdelays),
+            ctx.const_arr_ptr(tys.osdi_absdelay, &arr_63),
         ];
         let ty = tys.osdi_descriptor;
         ctx.const_struct(ty, &fields)
@@ -450,6 +637,24 @@ impl OsdiTyBuilder<'_, '_, '_> {
             ctx.ty_ptr(),
             ctx.ty_ptr(),
             ctx.ty_ptr(),
+            ctx.ty_int(),
+            ctx.ty_int(),
+            ctx.ty_int(),
+            ctx.ty_int(),
+            ctx.ty_ptr(),
+            ctx.ty_ptr(),
+            ctx.ty_int(),
+            ctx.ty_ptr(),
+            ctx.ty_int(),
+            ctx.ty_ptr(),
+            ctx.ty_ptr(),
+            ctx.ty_ptr(),
+            ctx.ty_int(),
+            ctx.ty_ptr(),
+            ctx.ty_ptr(),
+            ctx.ty_ptr(),
+            ctx.ty_int(),
+            ctx.ty_ptr(),
         ];
         let ty = ctx.ty_struct("OsdiDescriptor", &fields);
         self.osdi_descriptor = Some(ty);
@@ -462,12 +667,18 @@ pub struct OsdiTys<'ll> {
     pub osdi_sim_info: &'ll llvm::Type,
     pub osdi_init_error_payload: &'ll llvm::Type,
     pub osdi_init_error: &'ll llvm::Type,
+    pub osdi_bound_violation: &'ll llvm::Type,
     pub osdi_init_info: &'ll llvm::Type,
     pub osdi_node_pair: &'ll llvm::Type,
     pub osdi_jacobian_entry: &'ll llvm::Type,
     pub osdi_node: &'ll llvm::Type,
     pub osdi_param_opvar: &'ll llvm::Type,
     pub osdi_noise_source: &'ll llvm::Type,
+    pub osdi_noise_correlation: &'ll llvm::Type,
+    pub osdi_ac_stim: &'ll llvm::Type,
+    pub osdi_absdelay: &'ll llvm::Type,
+    pub osdi_param_bound: &'ll llvm::Type,
+    pub osdi_param_meta: &'ll llvm::Type,
     pub osdi_descriptor: &'ll llvm::Type,
 }
 impl<'ll> OsdiTys<'ll> {
@@ -480,12 +691,18 @@ impl<'ll> OsdiTys<'ll> {
             osdi_sim_info: None,
             osdi_init_error_payload: None,
             osdi_init_error: None,
+            osdi_bound_violation: None,
             osdi_init_info: None,
             osdi_node_pair: None,
             osdi_jacobian_entry: None,
             osdi_node: None,
             osdi_param_opvar: None,
             osdi_noise_source: None,
+            osdi_noise_correlation: None,
+            osdi_ac_stim: None,
+            osdi_absdelay: None,
+            osdi_param_bound: None,
+            osdi_param_meta: None,
             osdi_descriptor: None,
         };
         builder.osdi_lim_function();
@@ -493,12 +710,18 @@ impl<'ll> OsdiTys<'ll> {
         builder.osdi_sim_info();
         builder.osdi_init_error_payload();
         builder.osdi_init_error();
+        builder.osdi_bound_violation();
         builder.osdi_init_info();
         builder.osdi_node_pair();
         builder.osdi_jacobian_entry();
         builder.osdi_node();
         builder.osdi_param_opvar();
         builder.osdi_noise_source();
+        builder.osdi_noise_correlation();
+        builder.osdi_ac_stim();
+        builder.osdi_absdelay();
+        builder.osdi_param_bound();
+        builder.osdi_param_meta();
         builder.osdi_descriptor();
         builder.finish()
     }
@@ -511,12 +734,18 @@ struct OsdiTyBuilder<'a, 'b, 'll> {
     osdi_sim_info: Option<&'ll llvm::Type>,
     osdi_init_error_payload: Option<&'ll llvm::Type>,
     osdi_init_error: Option<&'ll llvm::Type>,
+    osdi_bound_violation: Option<&'ll llvm::Type>,
     osdi_init_info: Option<&'ll llvm::Type>,
     osdi_node_pair: Option<&'ll llvm::Type>,
     osdi_jacobian_entry: Option<&'ll llvm::Type>,
     osdi_node: Option<&'ll llvm::Type>,
     osdi_param_opvar: Option<&'ll llvm::Type>,
     osdi_noise_source: Option<&'ll llvm::Type>,
+    osdi_noise_correlation: Option<&'ll llvm::Type>,
+    osdi_ac_stim: Option<&'ll llvm::Type>,
+    osdi_absdelay: Option<&'ll llvm::Type>,
+    osdi_param_bound: Option<&'ll llvm::Type>,
+    osdi_param_meta: Option<&'ll llvm::Type>,
     osdi_descriptor: Option<&'ll llvm::Type>,
 }
 impl<'ll> OsdiTyBuilder<'_, '_, 'll> {
@@ -527,12 +756,18 @@ impl<'ll> OsdiTyBuilder<'_, '_, 'll> {
             osdi_sim_info: self.osdi_sim_info.unwrap(),
             osdi_init_error_payload: self.osdi_init_error_payload.unwrap(),
             osdi_init_error: self.osdi_init_error.unwrap(),
+            osdi_bound_violation: self.osdi_bound_violation.unwrap(),
             osdi_init_info: self.osdi_init_info.unwrap(),
             osdi_node_pair: self.osdi_node_pair.unwrap(),
             osdi_jacobian_entry: self.osdi_jacobian_entry.unwrap(),
             osdi_node: self.osdi_node.unwrap(),
             osdi_param_opvar: self.osdi_param_opvar.unwrap(),
             osdi_noise_source: self.osdi_noise_source.unwrap(),
+            osdi_noise_correlation: self.osdi_noise_correlation.unwrap(),
+            osdi_ac_stim: self.osdi_ac_stim.unwrap(),
+            osdi_absdelay: self.osdi_absdelay.unwrap(),
+            osdi_param_bound: self.osdi_param_bound.unwrap(),
+            osdi_param_meta: self.osdi_param_meta.unwrap(),
             osdi_descriptor: self.osdi_descriptor.unwrap(),
         }
     }
@@ -2,7 +2,7 @@ use hir_lower::{CallBackKind, ParamInfoKind, ParamKind, PlaceKind};
 
 use llvm::IntPredicate::IntSLT;
 use llvm::{
-    LLVMAppendBasicBlockInContext, LLVMBuildBr, LLVMBuildCondBr, LLVMBuildRetVoid,
+    LLVMAppendBasicBlockInContext, LLVMBuildBr, LLVMBuildCall2, LLVMBuildCondBr, LLVMBuildRetVoid,
     LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMGetParam, LLVMPositionBuilderAtEnd,
     UNNAMED,
 };
@@ -10,7 +10,7 @@ use mir::ControlFlowGraph;
 use mir_llvm::{Builder, BuilderVal, CallbackFun, CodegenCx};
 use sim_back::SimUnknownKind;
 
-use crate::compilation_unit::{general_callbacks, OsdiCompilationUnit};
+use crate::compilation_unit::{file_callback, general_callbacks, OsdiCompilationUnit};
 use crate::inst_data::OsdiInstanceParam;
 
 impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
@@ -43,7 +43,10 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
             .get_func_by_name("push_invalid_param_err")
             .expect("stdlib function push_invalid_param_err is missing");
 
-        let ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_ptr(), cx.ty_ptr(), cx.ty_int()], cx.ty_void());
+        let ty = cx.ty_func(
+            &[cx.ty_ptr(), cx.ty_ptr(), cx.ty_ptr(), cx.ty_int(), cx.ty_double(), cx.ty_int()],
+            cx.ty_void(),
+        );
 
         (ty, val)
     }
@@ -189,6 +192,38 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         llfunc
     }
 
+    /// Closes all files opened by the instance, see `osdi_close_files` in the stdlib.
+    pub fn close_files(&self) -> &'ll llvm::Value {
+        let OsdiCompilationUnit { inst_data, cx, module, .. } = self;
+        let fun_ty = cx.ty_func(&[cx.ty_ptr()], cx.ty_void());
+        let name = &format!("close_files_{}", module.sym);
+        let llfunc = cx.declare_int_c_fn(name, fun_ty);
+        let close_files = cx
+            .get_func_by_name("osdi_close_files")
+            .expect("stdlib function osdi_close_files is missing");
+
+        unsafe {
+            let entry = LLVMAppendBasicBlockInContext(cx.llcx, llfunc, UNNAMED);
+            let llbuilder = LLVMCreateBuilderInContext(cx.llcx);
+            LLVMPositionBuilderAtEnd(llbuilder, entry);
+            let inst = LLVMGetParam(llfunc, 0);
+            let files = inst_data.files_ptr(inst, llbuilder);
+            LLVMBuildCall2(llbuilder, fun_ty, close_files, [files].as_ptr(), 1, UNNAMED);
+            LLVMBuildRetVoid(llbuilder);
+            LLVMDisposeBuilder(llbuilder);
+        }
+
+        llfunc
+    }
+
+    /// The bound violations of the errors returned by the setup functions are stored by the
+    /// stdlib, see `osdi_bound_violations`.
+    pub fn bound_violations(&self) -> &'ll llvm::Value {
+        self.cx
+            .get_func_by_name("osdi_bound_violations")
+            .expect("stdlib function osdi_bound_violations is missing")
+    }
+
     pub fn setup_instance_prototype(&self) -> &'ll llvm::Value {
         let name = &format!("setup_instance_{}", &self.module.sym);
         let cx = &self.cx;
@@ -312,7 +347,7 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
         unsafe { inst_data.store_temperature(&builder, instance, temperature) };
         unsafe { inst_data.store_connected_ports(&builder, instance, connected_terminals) };
 
-        let trivial_cb = cx.trivial_callbacks(&[]);
+        let trivial_cb = cx.trivial_callbacks(&[cx.ty_double(), cx.ty_int()]);
 
         let err_cap = unsafe { builder.alloca(cx.ty_int()) };
 
@@ -332,8 +367,12 @@ impl<'ll> OsdiCompilationUnit<'_, '_, 'll> {
 
         let invalid_param_err = Self::invalid_param_err(cx);
         builder.callbacks = general_callbacks(intern, &mut builder, ret_flags, handle, simparam);
+        let files = unsafe { inst_data.files_ptr(instance, builder.llbuilder) };
         for (call_id, call) in intern.callbacks.iter_enumerated() {
             let cb = match call {
+                CallBackKind::File(_)
+                | CallBackKind::FilePrint { .. }
+                | CallBackKind::FileScan { .. } => file_callback(cx, call, files, handle),
                 CallBackKind::ParamInfo(ParamInfoKind::Invalid, param) => {
                     if let Some(id) =
                         inst_data.params.get_index_of(&OsdiInstanceParam::User(*param))
@@ -7,8 +7,23 @@ extern void *memcpy (void *__restrict __dest, const void *__restrict __src,
 		     size_t __n);
 extern void *malloc (size_t __size);
 extern void *realloc (void *__ptr, size_t __size);
+extern void *memmove (void *__dest, const void *__src, size_t __n);
 extern double log(double);
+extern double exp(double);
 extern int strcmp(const char*, const char*);
+extern void *calloc(size_t __nmemb, size_t __size);
+extern void free(void *__ptr);
+extern void *fopen(const char *__filename, const char *__modes);
+extern int fclose(void *__stream);
+extern int fflush(void *__stream);
+extern int fputs(const char *__s, void *__stream);
+extern char *fgets(char *__s, int __n, void *__stream);
+extern int feof(void *__stream);
+extern int ferror(void *__stream);
+extern long ftell(void *__stream);
+extern int fseek(void *__stream, long __off, int __whence);
+extern int vfscanf(void *__stream, const char *__format,
+                   __builtin_va_list __arg);
 #define NULL ((void*)0)
 #else
 #include <math.h>
@@ -90,22 +105,45 @@ char *simparam_str(void *params_, void *handle, uint32_t *flags, char *name) {
   return "�";
 }
 
+// The bound violations of the errors are stored in the same allocation as the
+// errors (behind the capacity of the error array) so that the size of
+// OsdiInitError does not change. The capacity only depends on the number of
+// errors which allows osdi_bound_violations to find them again.
+static uint32_t err_capacity(uint32_t len) {
+  uint32_t cap = 8;
+  while (cap < len) {
+    cap *= 2;
+  }
+  return cap;
+}
+
+static OsdiBoundViolation *err_bound_violations(OsdiInitError *errors,
+                                                uint32_t cap) {
+  return (OsdiBoundViolation *)(errors + cap);
+}
+
 void push_error(OsdiInitError **dst, uint32_t *len, uint32_t *cap,
-                OsdiInitError err) {
-  if (*dst == NULL) {
-    *cap = 8;
-    *dst = malloc(8 * sizeof(OsdiInitError));
-  } else if (*cap <= *len) {
-    *cap = 2 * (*len);
-    *dst = realloc(*dst, *cap * sizeof(OsdiInitError));
+                OsdiInitError err, OsdiBoundViolation violation) {
+  uint32_t new_cap = err_capacity(*len + 1);
+  if (*dst == NULL || *cap != new_cap) {
+    size_t size = sizeof(OsdiInitError) + sizeof(OsdiBoundViolation);
+    OsdiInitError *errors = realloc(*dst, new_cap * size);
+    if (*dst != NULL) {
+      memmove(err_bound_violations(errors, new_cap),
+              err_bound_violations(errors, *cap),
+              *len * sizeof(OsdiBoundViolation));
+    }
+    *dst = errors;
+    *cap = new_cap;
   }
 
   (*dst)[*len] = err;
+  err_bound_violations(*dst, *cap)[*len] = violation;
   *len += 1;
 }
 
 void push_invalid_param_err(void **dst, uint32_t *len, uint32_t *cap,
-                            uint32_t param) {
+                            uint32_t param, double value, uint32_t bound) {
   OsdiInitError err = (OsdiInitError){
       .code = INIT_ERR_OUT_OF_BOUNDS,
       .payload =
@@ -113,8 +151,22 @@ void push_invalid_param_err(void **dst, uint32_t *len, uint32_t *cap,
               .parameter_id = param,
           },
   };
+  OsdiBoundViolation violation = (OsdiBoundViolation){
+      .bound = bound,
+      .value = value,
+  };
 
-  push_error((OsdiInitError **)dst, len, cap, err);
+  push_error((OsdiInitError **)dst, len, cap, err, violation);
+}
+
+// returns the bound violation of each error returned by setup_model or
+// setup_instance
+OsdiBoundViolation *osdi_bound_violations(OsdiInitError *errors,
+                                          uint32_t num_errors) {
+  if (errors == NULL) {
+    return NULL;
+  }
+  return err_bound_violations(errors, err_capacity(num_errors));
 }
 
 void bound_step(double *dst, double val) { *dst = val; }
@@ -159,6 +211,8 @@ char *fmt_binary(int val) {
 
 void lim_discontinuity(int *flags) { *flags |= EVAL_RET_FLAG_LIM; }
 
+void event_detected(int *flags) { *flags |= EVAL_RET_FLAG_EVENT; }
+
 double store_lim(void *sim_info_, int idx, double val) {
   OsdiSimInfo *sim_info = (OsdiSimInfo *)sim_info_;
   sim_info->next_state[idx] = val;
@@ -178,6 +232,30 @@ int analysis(void *sim_info_, char *name) {
          ((flags & ANALYSIS_NODESET) && !strcmp(name, "nodeset"));
 }
 
+// table contains len (frequency, power) pairs sorted by frequency. The power
+// is interpolated linearly (or linearly in log-log scale if log is set) and
+// held constant outside of the table.
+double noise_table(const double *table, uint32_t len, double freq, int log_) {
+  if (freq <= table[0]) {
+    return table[1];
+  }
+  if (freq >= table[2 * (len - 1)]) {
+    return table[2 * len - 1];
+  }
+
+  uint32_t i = 1;
+  while (table[2 * i] < freq) {
+    i++;
+  }
+  double f0 = table[2 * i - 2], p0 = table[2 * i - 1];
+  double f1 = table[2 * i], p1 = table[2 * i + 1];
+  if (log_) {
+    double t = log(freq / f0) / log(f1 / f0);
+    return exp(log(p0) + t * log(p1 / p0));
+  }
+  return p0 + (freq - f0) * (p1 - p0) / (f1 - f0);
+}
+
 double store_delay(void *sim_info_, double *dst, double val) {
   OsdiSimInfo *sim_info = (OsdiSimInfo *)sim_info_;
   if (sim_info->flags & ANALYSIS_IC) {
@@ -187,3 +265,253 @@ double store_delay(void *sim_info_, double *dst, double val) {
 
   return *dst;
 }
+
+// Files opened with $fopen are stored in a table owned by each instance. The
+// table is allocated on the first call to $fopen. Both kinds of descriptors
+// refer to the same slots: A multi channel descriptor (mcd) has one bit set for
+// each slot (bit 0 is stdout) while a file descriptor has bit 31 set and stores
+// the slot in the lower bits (1 is stdout and 2 is stderr).
+#define FD_BIT 0x80000000u
+#define FIRST_FILE 3
+#define MAX_FILES 31
+
+typedef struct OsdiFile {
+  void *fp;
+  char *name;
+  char *mode;
+  // buffer for the last line read with $fgets
+  char *line;
+} OsdiFile;
+
+typedef struct OsdiFiles {
+  OsdiFile files[MAX_FILES];
+} OsdiFiles;
+
+static OsdiFile *file_slot(void **files, uint32_t slot) {
+  OsdiFiles *table = *files;
+  if (table == NULL || slot < FIRST_FILE || slot >= MAX_FILES ||
+      table->files[slot].fp == NULL) {
+    return NULL;
+  }
+  return &table->files[slot];
+}
+
+// returns the file of a file descriptor (NULL for invalid descriptors)
+static OsdiFile *fd_file(void **files, int fd) {
+  unsigned fd_ = fd;
+  if (!(fd_ & FD_BIT)) {
+    return NULL;
+  }
+  return file_slot(files, fd_ & ~FD_BIT);
+}
+
+// returns whether the descriptor (file or mcd) refers to slot
+static bool has_slot(int fd, uint32_t slot) {
+  unsigned fd_ = fd;
+  if (fd_ & FD_BIT) {
+    return (fd_ & ~FD_BIT) == slot;
+  }
+  return (fd_ >> slot) & 1;
+}
+
+static void close_file(OsdiFile *file) {
+  fclose(file->fp);
+  free(file->name);
+  free(file->mode);
+  free(file->line);
+  *file = (OsdiFile){NULL, NULL, NULL, NULL};
+}
+
+// Opening the same file with the same mode again returns the existing
+// descriptor because the instance setup (and therefore $fopen) may run
+// multiple times. An empty mode opens the file for writing and returns a mcd.
+int osdi_fopen(void **files, void *handle, char *name, char *mode) {
+  bool mcd = mode[0] == '\0';
+  OsdiFiles *table = *files;
+  if (table == NULL) {
+    table = calloc(1, sizeof(OsdiFiles));
+    if (table == NULL) {
+      return 0;
+    }
+    *files = table;
+  }
+
+  uint32_t slot = 0;
+  for (uint32_t i = FIRST_FILE; i < MAX_FILES; i++) {
+    OsdiFile *file = &table->files[i];
+    if (file->fp == NULL) {
+      if (slot == 0) {
+        slot = i;
+      }
+    } else if (!strcmp(file->name, name) && !strcmp(file->mode, mode)) {
+      return mcd ? 1u << i : FD_BIT | i;
+    }
+  }
+
+  void *fp = slot == 0 ? NULL : fopen(name, mcd ? "w" : mode);
+  char *name_ = concat(name, "");
+  char *mode_ = concat(mode, "");
+  if (fp == NULL || name_ == NULL || mode_ == NULL) {
+    if (fp != NULL) {
+      fclose(fp);
+    }
+    free(name_);
+    free(mode_);
+    char *msg = concat("failed to open file ", name);
+    if (msg == NULL) {
+      osdi_log(handle, "failed to open file %s", LOG_LVL_WARN | LOG_FMT_ERR);
+    } else {
+      osdi_log(handle, msg, LOG_LVL_WARN);
+    }
+    return 0;
+  }
+
+  table->files[slot] = (OsdiFile){fp, name_, mode_, NULL};
+  return mcd ? 1u << slot : FD_BIT | slot;
+}
+
+int osdi_fclose(void **files, int fd) {
+  for (uint32_t i = FIRST_FILE; i < MAX_FILES; i++) {
+    OsdiFile *file = file_slot(files, i);
+    if (file != NULL && has_slot(fd, i)) {
+      close_file(file);
+    }
+  }
+  return 0;
+}
+
+// closes all files of an instance, called by the simulator before the
+// instance is freed
+void osdi_close_files(void **files) {
+  OsdiFiles *table = *files;
+  if (table == NULL) {
+    return;
+  }
+  for (uint32_t i = FIRST_FILE; i < MAX_FILES; i++) {
+    if (table->files[i].fp != NULL) {
+      close_file(&table->files[i]);
+    }
+  }
+  free(table);
+  *files = NULL;
+}
+
+// Writes the message created by $fdisplay (and friends) to all files of the
+// descriptor. Messages for stdout and stderr are passed to osdi_log which
+// takes ownership of the message.
+void osdi_fwrite(void **files, void *handle, int fd, char *msg, bool fmt_err) {
+  if (fmt_err) {
+    osdi_log(handle, msg, LOG_LVL_ERR | LOG_FMT_ERR);
+    return;
+  }
+
+  for (uint32_t i = FIRST_FILE; i < MAX_FILES; i++) {
+    OsdiFile *file = file_slot(files, i);
+    if (file != NULL && has_slot(fd, i)) {
+      fputs(msg, file->fp);
+    }
+  }
+
+  // stdout is bit 0 of a mcd but slot 1 of a file descriptor
+  unsigned fd_ = fd;
+  bool is_stdout = fd_ & FD_BIT ? fd_ == (FD_BIT | 1) : fd_ & 1;
+  if (is_stdout) {
+    osdi_log(handle, msg, LOG_LVL_DISPLAY);
+  } else if (fd_ == (FD_BIT | 2)) {
+    osdi_log(handle, msg, LOG_LVL_ERR);
+  } else {
+    free(msg);
+  }
+}
+
+// flushes all files of the descriptor, -1 flushes all files of the instance
+int osdi_fflush(void **files, int fd) {
+  for (uint32_t i = FIRST_FILE; i < MAX_FILES; i++) {
+    OsdiFile *file = file_slot(files, i);
+    if (file != NULL && (fd == -1 || has_slot(fd, i))) {
+      fflush(file->fp);
+    }
+  }
+  return 0;
+}
+
+int osdi_feof(void **files, int fd) {
+  OsdiFile *file = fd_file(files, fd);
+  return file == NULL || feof(file->fp) != 0;
+}
+
+int osdi_ftell(void **files, int fd) {
+  OsdiFile *file = fd_file(files, fd);
+  return file == NULL ? -1 : (int)ftell(file->fp);
+}
+
+int osdi_fseek(void **files, int fd, int offset, int whence) {
+  OsdiFile *file = fd_file(files, fd);
+  return file == NULL || fseek(file->fp, offset, whence) ? -1 : 0;
+}
+
+int osdi_rewind(void **files, int fd) { return osdi_fseek(files, fd, 0, 0); }
+
+// Reads a line (including the newline) into a buffer that stays valid until
+// the next call to $fgets for the same file or until the file is closed.
+// Returns the number of characters read.
+int osdi_fgets(void **files, int fd, char **line) {
+  *line = "";
+  OsdiFile *file = fd_file(files, fd);
+  if (file == NULL) {
+    return 0;
+  }
+
+  free(file->line);
+  size_t cap = 128;
+  size_t len = 0;
+  char *buf = malloc(cap);
+  file->line = buf;
+  if (buf == NULL) {
+    return 0;
+  }
+  buf[0] = '\0';
+
+  while (fgets(buf + len, cap - len, file->fp) != NULL) {
+    len += strlen(buf + len);
+    if (buf[len - 1] == '\n' || len + 1 < cap) {
+      break;
+    }
+    char *new_buf = realloc(buf, 2 * cap);
+    if (new_buf == NULL) {
+      break;
+    }
+    buf = new_buf;
+    file->line = buf;
+    cap *= 2;
+  }
+
+  *line = buf;
+  return len;
+}
+
+int osdi_ferror(void **files, int fd, char **msg) {
+  OsdiFile *file = fd_file(files, fd);
+  if (file == NULL) {
+    *msg = "invalid file descriptor";
+    return -1;
+  }
+  if (ferror(file->fp)) {
+    *msg = "I/O error";
+    return 1;
+  }
+  *msg = "";
+  return 0;
+}
+
+int osdi_fscanf(void **files, int fd, char *fmt, ...) {
+  OsdiFile *file = fd_file(files, fd);
+  if (file == NULL) {
+    return -1;
+  }
+  __builtin_va_list args;
+  __builtin_va_start(args, fmt);
+  int res = vfscanf(file->fp, fmt, args);
+  __builtin_va_end(args);
+  return res;
+}
@@ -86,7 +86,10 @@ pub(super) fn var_decl(p: &mut Parser, m: Marker) {
 
 fn var(p: &mut Parser) -> bool {
     let m = p.start();
-    name_r(p, TokenSet::new(&[T![,], T![=], T![;]]));
+    name_r(p, TokenSet::new(&[T![,], T![=], T![;], T!['[']]));
+    if p.at(T!['[']) {
+        array_range(p);
+    }
     if p.eat(T![=]) {
         expr(p);
     }
@@ -94,6 +97,16 @@ fn var(p: &mut Parser) -> bool {
     true
 }
 
+fn array_range(p: &mut Parser) {
+    let m = p.start();
+    p.bump(T!['[']);
+    expr(p);
+    p.expect(T![:]);
+    expr(p);
+    p.expect(T![']']);
+    m.complete(p, RANGE);
+}
+
 pub(super) fn parameter_decl(p: &mut Parser, m: Marker) {
     p.bump_any();
     eat_ty(p);
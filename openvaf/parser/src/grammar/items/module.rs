@@ -119,6 +119,9 @@ fn module_items(p: &mut Parser) {
             NET_TYPE => {
                 net_decl::<true>(p, m);
             }
+            IDENT if p.nth_at(1, T![#]) || (p.nth_at(1, IDENT) && p.nth_at(2, T!['('])) => {
+                module_inst(p, m);
+            }
             IDENT => {
                 net_decl::<false>(p, m);
             }
@@ -180,6 +183,57 @@ fn net_dec_list(p: &mut Parser) {
     decl_list(p, T![;], decl_name, NET_RECOVERY);
 }
 
+fn module_inst(p: &mut Parser, m: Marker) {
+    name_ref_r(p, TokenSet::new(&[T![#], IDENT, T![;]]));
+    if p.at(T![#]) {
+        let m = p.start();
+        p.bump(T![#]);
+        inst_args(p);
+        m.complete(p, PARAM_OVERRIDES);
+    }
+    decl_list(p, T![;], instance, MODULE_ITEM_OR_ATTR_RECOVERY);
+    p.expect(T![;]);
+    m.complete(p, MODULE_INST);
+}
+
+fn instance(p: &mut Parser) -> bool {
+    let m = p.start();
+    name_r(p, TokenSet::new(&[T!['('], T![,], T![;]]));
+    inst_args(p);
+    m.complete(p, INSTANCE);
+    true
+}
+
+const INST_ARGS_RECOVERY: TokenSet = TokenSet::new(&[T![')'], T![;], ENDMODULE_KW, EOF]);
+
+/// Parses the (ordered or named) arguments of a module instance.
+/// This list is used both for parameter overrides and port connections.
+fn inst_args(p: &mut Parser) {
+    if !p.expect(T!['(']) {
+        return;
+    }
+    while !p.at_ts(INST_ARGS_RECOVERY) {
+        let m = p.start();
+        if p.eat(T![.]) {
+            name_ref_r(p, TokenSet::new(&[T!['('], T![,], T![')']]));
+            if p.expect(T!['(']) {
+                if !p.at(T![')']) {
+                    expr(p);
+                }
+                p.expect(T![')']);
+            }
+        } else if expr(p).is_none() {
+            m.abandon(p);
+            break;
+        }
+        m.complete(p, INST_ARG);
+        if !p.at(T![')']) && !p.expect(T![,]) {
+            break;
+        }
+    }
+    p.expect(T![')']);
+}
+
 const FUNCTION_RECOVER: TokenSet = TokenSet::new(&[EOF, ENDMODULE_KW, ENDFUNCTION_KW]);
 const FUN_ITEM_TS: TokenSet = TokenSet::new(&[PARAMETER_KW, LOCALPARAM_KW])
     .union(TYPE_TS)
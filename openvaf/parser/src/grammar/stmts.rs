@@ -1,4 +1,5 @@
 use super::*;
+use crate::grammar::expressions::EXPR_RECOVERY_SET;
 
 pub(super) const STMT_TS: TokenSet =
     TokenSet::new(&[IF_KW, WHILE_KW, FOR_KW, CASE_KW, BEGIN_KW, T![;], IDENT, SYSFUN, T![@]]);
@@ -60,27 +61,36 @@ fn assign_or_expr(p: &mut Parser) -> bool {
 fn event_stmt(p: &mut Parser, m: Marker) {
     p.bump(T![@]);
     p.expect(T!['(']);
-    p.expect_ts_r(
-        TokenSet::new(&[INITIAL_STEP_KW, FINAL_STEP_KW]),
-        TokenSet::new(&[T![')'], T!['(']]),
-    );
-    if p.eat(T!['(']) {
-        while !p.at_ts(TokenSet::new(&[T![')'], T![begin], ENDMODULE_KW])) {
-            let mut succ = p.expect(STR_LIT);
-            if !p.at(T![')']) {
-                succ |= p.expect_with(T![,], &[T![')'], T![,]]);
-                if !succ {
-                    p.bump_any()
-                }
-            }
-        }
-        p.eat(T![')']);
+    event(p);
+    while p.eat(T![or]) {
+        event(p);
     }
     p.expect(T![')']);
     stmt_with_attrs(p);
     m.complete(p, EVENT_STMT);
 }
 
+fn event(p: &mut Parser) {
+    let m = p.start();
+    if p.eat_ts(TokenSet::new(&[INITIAL_STEP_KW, FINAL_STEP_KW])) {
+        if p.eat(T!['(']) {
+            while !p.at_ts(TokenSet::new(&[T![')'], T![begin], ENDMODULE_KW])) {
+                let mut succ = p.expect(STR_LIT);
+                if !p.at(T![')']) {
+                    succ |= p.expect_with(T![,], &[T![')'], T![,]]);
+                    if !succ {
+                        p.bump_any()
+                    }
+                }
+            }
+            p.eat(T![')']);
+        }
+    } else {
+        expr(p);
+    }
+    m.complete(p, EVENT);
+}
+
 fn if_stmt(p: &mut Parser, m: Marker) {
     p.bump(IF_KW);
     p.expect(T!['(']);
@@ -144,7 +154,9 @@ fn case_stmt(p: &mut Parser, m: Marker) {
     m.complete(p, CASE_STMT);
 }
 
-const CASE_COND_RECOVERY: TokenSet = TokenSet::new(&[ENDCASE_KW, EOF, T![:], ENDMODULE_KW]);
+// the value list must stop wherever an expression refuses to consume a token,
+// otherwise a malformed case item never makes progress
+const CASE_COND_RECOVERY: TokenSet = EXPR_RECOVERY_SET.union(TokenSet::new(&[EOF, T![:]]));
 
 fn case_item(p: &mut Parser) {
     let m = p.start();
@@ -236,6 +236,19 @@ impl SourceMap {
         &self.ctx_tree[ctx]
     }
 
+    /// Returns every file that was read while preprocessing (the root file and all files pulled
+    /// in with `` `include ``) in the order in which they were first encountered.
+    #[must_use]
+    pub fn files(&self) -> Vec<FileId> {
+        let mut files = Vec::new();
+        for ctx in &self.ctx_tree {
+            if !files.contains(&ctx.decl.file) {
+                files.push(ctx.decl.file)
+            }
+        }
+        files
+    }
+
     pub(crate) fn add_ctx(&mut self, decl: FileSpan, call_site: CtxSpan) -> SourceContext {
         self.ctx_tree.push_and_get_key(SourceContextData { decl, call_site: Some(call_site) })
     }
@@ -181,3 +181,23 @@ fn source_map_triple_replacement() {
         "source_map_triple_replacement",
     )
 }
+
+#[test]
+fn included_files() {
+    let sources = TestSourceProvider::new(vec![]);
+    let root = sources.vfs.borrow_mut().add_virt_file(
+        "/root.va",
+        "`include \"disciplines.vams\"\n`include \"params.va\"\n`include \"params.va\"\n"
+            .to_owned()
+            .into(),
+    );
+    sources
+        .vfs
+        .borrow_mut()
+        .add_virt_file("/params.va", "`include \"constants.vams\"\n".to_owned().into());
+    let Preprocess { diagnostics, sm, .. } = preprocess(&sources, root);
+    assert_eq!(diagnostics.as_slice(), &[]);
+    let files: Vec<_> =
+        sm.files().into_iter().map(|file| sources.file_path(file).to_string()).collect();
+    assert_eq!(files, ["/root.va", "/std/disciplines.vams", "/params.va", "/std/constants.vams"]);
+}
@@ -0,0 +1,25 @@
+use lasso::Spur;
+use mir::Value;
+
+use crate::dae::SimUnknown;
+
+/// A small-signal stimulus created with `ac_stim`. During the small-signal analysis `analysis`
+/// the phasor `factor * mag * exp(j * phase)` is added to the residual of `hi` (and subtracted
+/// from the residual of `lo`). The stimulus is zero during all other analyses.
+#[derive(Debug)]
+pub struct AcStimSource {
+    pub analysis: Spur,
+    pub mag: Value,
+    pub phase: Value,
+    pub hi: SimUnknown,
+    pub lo: Option<SimUnknown>,
+    pub factor: Value,
+}
+
+impl AcStimSource {
+    pub fn map_vals(&mut self, mut f: impl FnMut(Value) -> Value) {
+        self.factor = f(self.factor);
+        self.mag = f(self.mag);
+        self.phase = f(self.phase);
+    }
+}
@@ -40,7 +40,10 @@ impl<'a> Context<'a> {
                 PlaceKind::Contribute { .. }
                 | PlaceKind::ImplicitResidual { .. }
                 | PlaceKind::CollapseImplicitEquation(_)
-                | PlaceKind::IsVoltageSrc(_) => true,
+                | PlaceKind::IsVoltageSrc(_)
+                | PlaceKind::OpState(_)
+                | PlaceKind::Breakpoint
+                | PlaceKind::AbsdelayDelay(_) => true,
                 PlaceKind::Var(var) => module.op_vars.contains_key(&var),
                 _ => false,
             },
@@ -115,7 +118,14 @@ impl<'a> Context<'a> {
         } else {
             for (kind, val) in self.intern.outputs.iter() {
                 if matches!(kind, PlaceKind::Var(var) if self.module.op_vars.contains_key(var))
-                    || matches!(kind, PlaceKind::CollapseImplicitEquation(_) | PlaceKind::BoundStep)
+                    || matches!(
+                        kind,
+                        PlaceKind::CollapseImplicitEquation(_)
+                            | PlaceKind::BoundStep
+                            | PlaceKind::OpState(_)
+                            | PlaceKind::Breakpoint
+                            | PlaceKind::AbsdelayDelay(_)
+                    )
                 {
                     self.output_values.insert(val.unwrap_unchecked());
                 }
@@ -129,7 +139,7 @@ impl<'a> Context<'a> {
         self.op_dependent_insts.ensure(dfg.num_insts());
 
         for (cb, uses) in self.intern.callback_uses.iter_mut_enumerated() {
-            if self.intern.callbacks[cb].is_noise() {
+            if self.intern.callbacks[cb].is_small_signal_src() {
                 uses.retain(|&inst| {
                     if self.func.layout.inst_block(inst).is_none() {
                         return false;
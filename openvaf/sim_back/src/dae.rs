@@ -1,12 +1,14 @@
+use hir::{Parameter, Variable};
 use indexmap::IndexSet;
 use mir::{strip_optbarrier, Value, F_ZERO};
 use stdx::{impl_debug_display, impl_idx_from};
 use typed_index_collections::TiVec;
 use typed_indexmap::TiSet;
 
+pub use crate::ac_stim::AcStimSource;
 use crate::context::Context;
 use crate::dae::builder::Builder;
-pub use crate::noise::{NoiseSource, NoiseSourceKind};
+pub use crate::noise::{correlated_noise_sources, NoiseSource, NoiseSourceKind};
 use crate::{topology, SimUnknownKind};
 
 mod builder;
@@ -43,6 +45,14 @@ pub struct DaeSystem {
     pub small_signal_parameters: IndexSet<Value, ahash::RandomState>,
     /// noise
     pub noise_sources: Vec<NoiseSource>,
+    /// small-signal stimuli (`ac_stim`)
+    pub ac_stims: Vec<AcStimSource>,
+    /// parameters selected for sensitivity analysis, the columns of the sensitivity matrix
+    pub sensitivity_params: Vec<Parameter>,
+    /// derivatives of the residual with respect to the `sensitivity_params`
+    pub sensitivities: Vec<Sensitivity>,
+    /// derivatives of the operating point variables with respect to the `sensitivity_params`
+    pub opvar_sensitivities: Vec<OpVarSensitivity>,
     /// model inputs (node pairs)
     pub model_inputs: Vec<(u32, u32)>, 
     /// Jacobian entry counts
@@ -97,6 +107,22 @@ impl DaeSystem {
             }
         });
 
+        self.ac_stims.retain_mut(|stim| {
+            stim.map_vals(&mut sparsify);
+            stim.factor != F_ZERO && stim.mag != F_ZERO
+        });
+
+        self.sensitivities.retain_mut(|entry| {
+            entry.resist = sparsify(entry.resist);
+            entry.react = sparsify(entry.react);
+            entry.resist != F_ZERO || entry.react != F_ZERO
+        });
+
+        self.opvar_sensitivities.retain_mut(|entry| {
+            entry.val = sparsify(entry.val);
+            entry.val != F_ZERO
+        });
+
         self.jacobian.raw.retain_mut(|matrix_entry| {
             matrix_entry.resist = sparsify(matrix_entry.resist);
             matrix_entry.react = sparsify(matrix_entry.react);
@@ -215,6 +241,24 @@ pub struct MatrixEntry {
     pub react: Value,
 }
 
+/// The derivative of a residual with respect to a parameter (`sensitivity_params[col]`)
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct Sensitivity {
+    pub row: SimUnknown,
+    pub col: u32,
+    pub resist: Value,
+    pub react: Value,
+}
+
+/// The derivative of an operating point variable with respect to a parameter
+/// (`sensitivity_params[col]`)
+#[derive(PartialEq, Eq, Clone, Copy, Hash, Debug)]
+pub struct OpVarSensitivity {
+    pub var: Variable,
+    pub col: u32,
+    pub val: Value,
+}
+
 #[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
 pub struct MatrixEntryId(u32);
 impl_idx_from!(MatrixEntryId(u32));
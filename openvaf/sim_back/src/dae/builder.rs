@@ -3,8 +3,8 @@ use std::vec;
 
 use ahash::AHashMap;
 use bitset::BitSet;
-use hir::{BranchWrite, CompilationDB, Node, ParamSysFun};
-use hir_lower::{HirInterner, ImplicitEquation, ParamKind, CurrentKind};
+use hir::{BranchWrite, CompilationDB, Node, ParamSysFun, Variable};
+use hir_lower::{HirInterner, ImplicitEquation, ParamKind, CurrentKind, PlaceKind};
 use indexmap::IndexSet;
 use mir::builder::InstBuilder;
 use mir::cursor::{Cursor, FuncCursor};
@@ -15,12 +15,13 @@ use mir::{
 use mir_autodiff::auto_diff;
 use typed_index_collections::TiVec;
 
+use crate::ac_stim::AcStimSource;
 use crate::context::Context;
-use crate::dae::{DaeSystem, MatrixEntry, Residual, SimUnknown};
+use crate::dae::{DaeSystem, MatrixEntry, OpVarSensitivity, Residual, Sensitivity, SimUnknown};
 use crate::noise::NoiseSource;
 use crate::topology::{BranchInfo, Contribution};
 use crate::util::{add, is_op_dependent, update_optbarrier};
-use crate::SimUnknownKind;
+use crate::{ModuleInfo, SimUnknownKind};
 
 impl Residual {
     fn add(&mut self, cursor: &mut FuncCursor, negate: bool, mut val: Value) {
@@ -57,6 +58,7 @@ pub(super) struct Builder<'a> {
     pub(super) system: DaeSystem,
     pub(super) cursor: FuncCursor<'a>,
     pub(super) db: &'a CompilationDB,
+    pub(super) module: &'a ModuleInfo,
     pub(super) intern: &'a mut HirInterner,
     pub(super) cfg: &'a mut ControlFlowGraph,
     pub(super) dom_tree: &'a mut DominatorTree,
@@ -71,6 +73,7 @@ impl<'a> Builder<'a> {
             system: DaeSystem::default(),
             cursor: FuncCursor::new(&mut ctx.func).at_exit(),
             db: ctx.db,
+            module: ctx.module,
             intern: &mut ctx.intern,
             cfg: &mut ctx.cfg,
             dom_tree: &mut ctx.dom_tree,
@@ -87,14 +90,28 @@ impl<'a> Builder<'a> {
             builder.build_node(node)
         }
 
+        // module instances are flattened into the parent so their internal nodes
+        // become internal nodes of the compiled module
+        let mut instances = ctx.module.module.instances(builder.db);
+        let mut i = 0;
+        while let Some(&inst) = instances.get(i) {
+            for node in inst.internal_nodes(builder.db) {
+                builder.build_node(node)
+            }
+            instances.extend(inst.instances(builder.db));
+            i += 1;
+        }
+
         builder
     }
 
     pub(super) fn finish(mut self) -> DaeSystem {
         let sim_unknown_reads = self.sim_unknown_reads();
-        let derivative_info = self.intern.unknowns(&self.cursor, true);
-        let extra_derivatives = self
+        let mut derivative_info = self.intern.unknowns(&self.cursor, true);
+        let sensitivity_unknowns = self.sensitivity_unknowns(&mut derivative_info);
+        let mut extra_derivatives = self
             .jacobian_derivatives(sim_unknown_reads.iter().map(|&(_, val)| val), &derivative_info);
+        extra_derivatives.extend(self.sensitivity_derivatives(&sensitivity_unknowns));
         // TODO(pref): incrementially update dom_tree (for switch branches) instead
         self.dom_tree.compute(self.cursor.func, self.cfg, true, false, true);
         let derivatives =
@@ -104,6 +121,7 @@ impl<'a> Builder<'a> {
         self.cursor.goto_exit();
 
         self.build_jacobian(&sim_unknown_reads, &derivative_info, &derivatives);
+        self.build_sensitivities(&sensitivity_unknowns, &derivatives);
         self.build_lim_rhs(&derivative_info, derivatives);
         self.ensure_optbarriers();
 
@@ -351,6 +369,84 @@ impl<'a> Builder<'a> {
         }
     }
 
+    /// Registers the parameters selected for sensitivity analysis as unknowns so that `auto_diff`
+    /// generates derivatives with respect to them. Returns the unknown that corresponds to each
+    /// column of the sensitivity matrix (`None` if the parameter is never read).
+    fn sensitivity_unknowns(
+        &mut self,
+        derivative_info: &mut KnownDerivatives,
+    ) -> Vec<Option<Unknown>> {
+        self.system.sensitivity_params = self
+            .module
+            .params
+            .iter()
+            .filter_map(|(param, info)| info.sensitivity.then_some(*param))
+            .collect();
+
+        self.system
+            .sensitivity_params
+            .iter()
+            .map(|&param| {
+                let val = self.intern.params.get(&ParamKind::Param(param)).copied()?;
+                if self.cursor.func.dfg.value_dead(val) {
+                    return None;
+                }
+                Some(derivative_info.unknowns.ensure(val).0)
+            })
+            .collect()
+    }
+
+    fn op_var_val(&self, var: Variable) -> Option<Value> {
+        self.intern.outputs.get(&PlaceKind::Var(var)).and_then(|val| val.expand())
+    }
+
+    fn sensitivity_derivatives(&self, unknowns: &[Option<Unknown>]) -> Vec<(Value, Unknown)> {
+        let residuals =
+            self.system.residual.iter().flat_map(|residual| [residual.resist, residual.react]);
+        let op_vars = self.module.op_vars.keys().filter_map(|&var| self.op_var_val(var));
+
+        let mut res = Vec::new();
+        for val in residuals.chain(op_vars) {
+            if self.cursor.func.dfg.value_def(val).as_const().is_none() {
+                res.extend(unknowns.iter().flatten().map(|&unknown| (val, unknown)))
+            }
+        }
+        res
+    }
+
+    fn build_sensitivities(
+        &mut self,
+        unknowns: &[Option<Unknown>],
+        derivatives: &AHashMap<(Value, Unknown), Value>,
+    ) {
+        for (col, unknown) in unknowns.iter().enumerate() {
+            let unknown = match *unknown {
+                Some(unknown) => unknown,
+                None => continue,
+            };
+            let col = col as u32;
+            let ddx = |val| derivatives.get(&(val, unknown)).copied().unwrap_or(F_ZERO);
+
+            for (row, residual) in self.system.residual.iter_enumerated() {
+                let resist = ddx(residual.resist);
+                let react = ddx(residual.react);
+                if resist != F_ZERO || react != F_ZERO {
+                    self.system.sensitivities.push(Sensitivity { row, col, resist, react });
+                }
+            }
+
+            for &var in self.module.op_vars.keys() {
+                let val = match self.op_var_val(var) {
+                    Some(val) => ddx(val),
+                    None => continue,
+                };
+                if val != F_ZERO {
+                    self.system.opvar_sensitivities.push(OpVarSensitivity { var, col, val });
+                }
+            }
+        }
+    }
+
     pub fn jacobian_derivatives(
         &self,
         simulation_unknown: impl Iterator<Item = Value>,
@@ -509,6 +605,8 @@ impl<'a> Builder<'a> {
             &mut self.cursor,
             false,
         );
+        let hi = self.ensure_unknown(SimUnknownKind::Implicit(eq));
+        self.add_ac_stims(contrib, hi, None);
     }
 
     fn mfactor_multiply(&mut self, mfactor: Value, srcfactor : Value) -> Value {
@@ -569,6 +667,18 @@ impl<'a> Builder<'a> {
             src
         });
         noise.extend(current_noise);
+        // unlike noise power, a stimulus scales linearly with mfactor
+        let ac_stims = current_src
+            .ac_stims
+            .iter()
+            .map(|src| {
+                let mut src = src.clone();
+                if mfactor != F_ONE {
+                    src.factor = self.cursor.ins().fmul(src.factor, mfactor);
+                }
+                src
+            })
+            .collect();
 
         Contribution {
             unknown: current_src.unknown,
@@ -577,6 +687,7 @@ impl<'a> Builder<'a> {
             resist_small_signal: current_src.resist_small_signal,
             react_small_signal: current_src.react_small_signal,
             noise,
+            ac_stims,
         }
     }
 
@@ -602,6 +713,7 @@ impl<'a> Builder<'a> {
             resist_small_signal: voltage_src.resist_small_signal,
             react_small_signal: voltage_src.react_small_signal,
             noise,
+            ac_stims: voltage_src.ac_stims.clone(),
         }
     }
 
@@ -644,6 +756,19 @@ impl<'a> Builder<'a> {
             src
         });
         noise.extend(current_noise);
+        // Stimuli are joined the same way as noise sources
+        let voltage_stims = voltage_src.ac_stims.iter().map(|src| {
+            let mut src = src.clone();
+            src.factor = select(src.factor, F_ZERO);
+            src
+        });
+        let mut ac_stims: Vec<_> = voltage_stims.collect();
+        let current_stims = current_src.ac_stims.iter().map(|src| {
+            let mut src = src.clone();
+            src.factor = select(F_ZERO, src.factor);
+            src
+        });
+        ac_stims.extend(current_stims);
         // Build remaining phi commands
         let phi_resist = select(voltage_src.resist, current_src.resist);
         let phi_react = select(voltage_src.react, current_src.react);
@@ -670,6 +795,11 @@ impl<'a> Builder<'a> {
                 noise[ii].factor = self.mfactor_multiply(mfactor, noise[ii].factor);
             }
         }
+        if mfactor != F_ONE {
+            for src in &mut ac_stims[voltage_src.ac_stims.len()..] {
+                src.factor = self.cursor.ins().fmul(src.factor, mfactor);
+            }
+        }
         
         Contribution {
             unknown: Some(unknown),
@@ -678,6 +808,7 @@ impl<'a> Builder<'a> {
             resist_small_signal: phi_resist_ss,
             react_small_signal: phi_react_ss,
             noise,
+            ac_stims,
         }
     }
 
@@ -700,6 +831,18 @@ impl<'a> Builder<'a> {
         self.system.noise_sources.extend(contrib.noise.iter().map(|src| {
             let factor = src.factor;            
             NoiseSource { name: src.name, kind: src.kind.clone(), hi, lo, factor }
+        }));
+        self.add_ac_stims(contrib, hi, lo);
+    }
+
+    fn add_ac_stims(&mut self, contrib: &Contribution, hi: SimUnknown, lo: Option<SimUnknown>) {
+        self.system.ac_stims.extend(contrib.ac_stims.iter().map(|src| AcStimSource {
+            analysis: src.analysis,
+            mag: src.mag,
+            phase: src.phase,
+            hi,
+            lo,
+            factor: src.factor,
         }))
     }
 
@@ -762,11 +905,26 @@ impl<'a> Builder<'a> {
             noise_src.map_vals(|val| ensure_optbarrier(val, false));
         }
 
+        for stim in &mut self.system.ac_stims {
+            stim.map_vals(|val| ensure_optbarrier(val, false));
+        }
+
         for entry in &mut self.system.jacobian {
             let is_kirchoff =
                 matches!(self.system.unknowns[entry.row], SimUnknownKind::KirchoffLaw(_));
             entry.resist = ensure_optbarrier(entry.resist, is_kirchoff);
             entry.react = ensure_optbarrier(entry.react, is_kirchoff);
         }
+
+        for entry in &mut self.system.sensitivities {
+            let is_kirchoff =
+                matches!(self.system.unknowns[entry.row], SimUnknownKind::KirchoffLaw(_));
+            entry.resist = ensure_optbarrier(entry.resist, is_kirchoff);
+            entry.react = ensure_optbarrier(entry.react, is_kirchoff);
+        }
+
+        for entry in &mut self.system.opvar_sensitivities {
+            entry.val = ensure_optbarrier(entry.val, false);
+        }
     }
 }
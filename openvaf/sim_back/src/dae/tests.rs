@@ -3,15 +3,18 @@ use std::fs;
 use expect_test::expect_file;
 use hir::diagnostics::ConsoleSink;
 use hir::CompilationDB;
+use hir_lower::{CurrentKind, ParamKind, PlaceKind};
 use indoc::indoc;
 use lasso::Rodeo;
+use mir::F_ZERO;
 use stdx::{integration_test_dir, openvaf_test_data};
 
 use crate::context::{Context, OptimiziationStage};
-use crate::dae::DaeSystem;
-use crate::topology;
+use crate::dae::{correlated_noise_sources, DaeSystem};
+use crate::{topology, SimUnknownKind};
 
-fn run_test(src: &str) {
+/// Runs the compilation pipeline up to the sparsified DAE system and passes the result to `check`.
+fn with_dae_system(src: &str, check: impl FnOnce(&Context, &DaeSystem, &Rodeo)) {
     let db = CompilationDB::new_virtual(src).unwrap();
     let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
     let mut literals = Rodeo::new();
@@ -24,13 +27,55 @@ fn run_test(src: &str) {
     context.compute_cfg();
     context.optimize(OptimiziationStage::Final);
     dae_system.sparsify(&mut context);
-    let name = module.module.name(&db);
-    let test_dir = openvaf_test_data("dae");
-    let topology = format!("{dae_system:#?}");
     assert!(context.func.validate());
-    expect_file![test_dir.join(format!("{name}_system.snap"))].assert_eq(&topology);
-    let func = format!("{:#?}", context.func);
-    expect_file![test_dir.join(format!("{name}_mir.snap"))].assert_eq(&func)
+    check(&context, &dae_system, &literals)
+}
+
+fn run_test(src: &str) {
+    with_dae_system(src, |context, dae_system, _| {
+        let name = context.module.module.name(context.db);
+        let test_dir = openvaf_test_data("dae");
+        let topology = format!("{dae_system:#?}");
+        expect_file![test_dir.join(format!("{name}_system.snap"))].assert_eq(&topology);
+        let func = format!("{:#?}", context.func);
+        expect_file![test_dir.join(format!("{name}_mir.snap"))].assert_eq(&func)
+    })
+}
+
+/// The names of all live model parameters (excluding parameters of module instances).
+fn live_params(context: &Context) -> Vec<String> {
+    context
+        .intern
+        .live_params(&context.func.dfg)
+        .filter_map(|(_, kind, _)| match *kind {
+            ParamKind::Param(param) => {
+                assert!(param.instance().is_none(), "instance parameter {param:?} is live");
+                Some(param.name(context.db).to_string())
+            }
+            ParamKind::ParamGiven { param } => panic!("$param_given({param:?}) is live"),
+            _ => None,
+        })
+        .collect()
+}
+
+/// The hierarchical names of all nodes that are unknowns of the DAE system.
+fn node_unknowns(context: &Context, dae_system: &DaeSystem) -> Vec<String> {
+    let mut nodes: Vec<_> = dae_system
+        .unknowns
+        .iter()
+        .filter_map(|unknown| match *unknown {
+            SimUnknownKind::KirchoffLaw(node) => {
+                let name = node.name(context.db);
+                Some(match node.instance() {
+                    Some(inst) => format!("{}.{name}", inst.path(context.db)),
+                    None => name.to_string(),
+                })
+            }
+            _ => None,
+        })
+        .collect();
+    nodes.sort();
+    nodes
 }
 
 #[test]
@@ -146,3 +191,407 @@ fn dyn_switch_branch() {
     "#};
     run_test(src);
 }
+
+#[test]
+fn subcircuit() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module subcircuit(inout a, inout c);
+            electrical a, c, x;
+            parameter real rs=2.0;
+            res #(.r(rs)) r1 (a, x);
+            rc #(1e-12) rc1 (.p(x), .n(c));
+        endmodule
+
+        module rc(inout p, inout n);
+            electrical p, n, m;
+            parameter real cap=1e-15;
+            res r1 (p, m);
+            analog I(m, n) <+ ddt(cap * V(m, n));
+        endmodule
+
+        module res(inout p, inout n);
+            electrical p, n;
+            parameter real r=1.0;
+            analog I(p, n) <+ V(p, n) / r;
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn instance_port_flow() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module instance_port_flow(inout a, inout c);
+            electrical a, c, o;
+            res r1 (a, c);
+            sense s1 (a, o);
+        endmodule
+
+        module sense(inout p, inout n);
+            electrical p, n;
+            analog V(n) <+ 10 * I(<p>);
+        endmodule
+
+        module res(inout p, inout n);
+            electrical p, n;
+            parameter real r=1.0;
+            analog I(p, n) <+ V(p, n) / r;
+        endmodule
+    "#};
+    with_dae_system(src, |context, dae_system, _| {
+        assert_eq!(node_unknowns(context, dae_system), ["a", "c", "o"]);
+        // the port flow of the instance is the port flow of the node it is connected to
+        let port_flows: Vec<_> = context
+            .intern
+            .live_params(&context.func.dfg)
+            .filter_map(|(_, kind, _)| match *kind {
+                ParamKind::Current(CurrentKind::Port(node)) => Some(node),
+                _ => None,
+            })
+            .collect();
+        let &[node] = port_flows.as_slice() else {
+            panic!("expected exactly one port flow: {port_flows:?}")
+        };
+        assert_eq!(node.name(context.db), "a");
+        assert!(node.instance().is_none());
+    })
+}
+
+#[test]
+fn instance_param_overrides() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module instance_param_overrides(inout a, inout c);
+            electrical a, c;
+            parameter real rs=2.0;
+            res #(.r(2 * rs)) r1 (a, c);
+            res r2 (a, c);
+        endmodule
+
+        module res(inout p, inout n);
+            electrical p, n;
+            parameter real r=1.0;
+            analog I(p, n) <+ V(p, n) / ($param_given(r) ? r : 1k);
+        endmodule
+    "#};
+    with_dae_system(src, |context, dae_system, _| {
+        // instance parameters and $param_given are resolved at compile time,
+        // only the parameter of the top level module remains
+        assert_eq!(live_params(context), ["rs"]);
+        assert_eq!(node_unknowns(context, dae_system), ["a", "c"]);
+    })
+}
+
+#[test]
+fn nested_instances() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module nested_instances(inout a, inout c);
+            electrical a, c, m;
+            parameter real r=1k;
+            divider #(.r(r)) x1 (a, m);
+            res r1 (m, c);
+        endmodule
+
+        module divider(inout p, inout n);
+            electrical p, n, mid;
+            parameter real r=1.0;
+            res #(.r(r / 2)) y1 (p, mid);
+            res #(.r(r / 2)) y2 (mid, n);
+        endmodule
+
+        module res(inout p, inout n);
+            electrical p, n;
+            parameter real r=1.0;
+            analog I(p, n) <+ V(p, n) / r;
+        endmodule
+    "#};
+    with_dae_system(src, |context, dae_system, _| {
+        assert_eq!(node_unknowns(context, dae_system), ["a", "c", "m", "x1.mid"]);
+        // the overrides of y1 and y2 are derived from the parameter of the top level module
+        assert_eq!(live_params(context), ["r"]);
+    })
+}
+
+#[test]
+fn array_variables() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module array_variables(inout a, inout c);
+            electrical a, c;
+            parameter integer order = 2;
+            real coeffs[0:2], pows[0:2];
+            real i;
+            integer k;
+            analog begin
+                coeffs[0] = 1e-3;
+                coeffs[1] = 1e-4;
+                coeffs[2] = 1e-5;
+                pows[0] = 1.0;
+                for (k = 1; k <= order; k = k + 1)
+                    pows[k] = pows[k - 1] * V(a, c);
+                i = 0.0;
+                for (k = 0; k <= order; k = k + 1)
+                    i = i + coeffs[k] * pows[k];
+                I(a, c) <+ i;
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn delay_line() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module delay_line(inout inp, inout out);
+            electrical inp, out;
+            parameter real td = 1n;
+            analog begin
+                I(out) <+ absdelay(V(inp), td);
+            end
+        endmodule
+    "#};
+    with_dae_system(src, |context, dae_system, _| {
+        let intern = &context.intern;
+        assert_eq!(intern.absdelays.len(), 1);
+        let absdelay = intern.absdelays.keys().next().unwrap();
+        let equations = intern.absdelays[absdelay];
+        let unknown =
+            |equation| dae_system.unknowns.unwrap_index(&SimUnknownKind::Implicit(equation));
+        let [input, output, ..] =
+            [equations.input, equations.output, equations.pade[0], equations.pade[1]].map(unknown);
+
+        // the contribution is only connected to the delay line by the output so that the delayed
+        // value provided by the simulator reaches the circuit
+        let out = dae_system
+            .unknowns
+            .unwrap_index(&SimUnknownKind::KirchoffLaw(context.module.module.ports(context.db)[1]));
+        let cols: Vec<_> = dae_system
+            .jacobian
+            .iter()
+            .filter(|entry| entry.row == out)
+            .map(|entry| entry.col)
+            .collect();
+        assert_eq!(cols, [output]);
+        // the delayed signal must not depend on the output
+        assert!(dae_system.jacobian.iter().all(|entry| entry.row != input || entry.col != output));
+
+        assert!(intern.params.contains_key(&ParamKind::ExactAbsdelay));
+        assert!(intern.params.contains_key(&ParamKind::DelayedValue(absdelay)));
+        assert!(intern.outputs.contains_key(&PlaceKind::AbsdelayDelay(absdelay)));
+    });
+}
+
+#[test]
+fn ramp() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module ramp(inout inp, inout out);
+            electrical inp, out;
+            parameter real tr = 1n;
+            parameter real sr = 1e9;
+            analog begin
+                I(out) <+ transition(V(inp), 0, tr) + slew(V(inp), sr);
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn comparator() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module comparator(inout inp, inout out);
+            electrical inp, out;
+            parameter real vth = 0.5;
+            parameter real period = 1n;
+            real state;
+            analog begin
+                @(cross(V(inp) - vth, +1, 1p))
+                    state = 1;
+                @(above(vth - V(inp)))
+                    state = 0;
+                @(timer(0, period))
+                    state = !state;
+                I(out) <+ state;
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn step_events() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module step_events(inout a, inout b);
+            electrical a, b;
+            parameter real r = 1k;
+            real g, scale;
+            analog begin
+                @(initial_step)
+                    g = 1 / r;
+                @(initial_step("ac", "tran"))
+                    scale = 2;
+                @(final_step)
+                    $strobe("final current %g", I(a, b));
+                I(a, b) <+ scale * g * V(a, b);
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn noise_tables() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module noise_tables(inout a, inout b);
+            electrical a, b;
+            parameter real r = 1k;
+            analog begin
+                I(a, b) <+ V(a, b) / r;
+                I(a, b) <+ noise_table('{1k, 2e-12, 1, 1e-12, 1M, 4e-12}, "lin");
+                I(a, b) <+ 2 * noise_table_log('{1, 1e-10, 1k, 1e-12});
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn laplace_filters() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module laplace_filters(inout inp, inout out);
+            electrical inp, out;
+            parameter real tau = 1n;
+            analog begin
+                I(out) <+ laplace_nd(V(inp), '{1}, '{1, tau});
+                I(out) <+ laplace_zp(V(inp), '{0, 0}, '{-1e9, 1e9, -1e9, -1e9});
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn zi_filter() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module zi_filter(inout inp, inout out);
+            electrical inp, out;
+            parameter real period = 1n;
+            analog begin
+                I(out) <+ zi_nd(V(inp), '{0.5, 0.5}, '{1, -0.2}, period);
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn ac_source() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module ac_source(inout a, inout b, inout c);
+            electrical a, b, c;
+            parameter real mag = 1.0;
+            parameter real phase = 0.5;
+            analog begin
+                I(a, b) <+ V(a, b) / 1k;
+                I(a, b) <+ ac_stim("ac", mag, phase);
+                V(c) <+ 2 * ac_stim();
+                I(b) <+ V(b) * ac_stim("ac", mag);
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn file_io() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module file_io(inout a);
+            electrical a;
+            integer fd, cnt;
+            real scale;
+            string line;
+            analog begin
+                @(initial_step) begin
+                    fd = $fopen("scale.txt", "r");
+                    cnt = $fscanf(fd, "%g", scale);
+                    cnt = $fgets(line, fd);
+                    $fclose(fd);
+                end
+                I(a) <+ scale * V(a);
+                $fdisplay(1, "V(a) = %g %s", V(a), line);
+            end
+        endmodule
+    "#};
+    run_test(src);
+}
+
+#[test]
+fn sensitivity() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module sensitivity(inout a, inout c);
+            electrical a, c;
+            (* sensitivity *) parameter real r=1.0;
+            parameter real cj=1.0;
+            (* units="A" *) real i;
+            analog begin
+                i = V(a,c)/r;
+                I(a, c) <+ i + ddt(cj*V(a,c));
+            end
+        endmodule
+    "#};
+    with_dae_system(src, |context, dae_system, _| {
+        let db = context.db;
+        let params: Vec<_> =
+            dae_system.sensitivity_params.iter().map(|param| param.name(db)).collect();
+        assert_eq!(params, ["r"]);
+        // only the resistive residuals of both nodes depend on r
+        assert_eq!(dae_system.sensitivities.len(), 2);
+        assert!(dae_system
+            .sensitivities
+            .iter()
+            .all(|entry| entry.col == 0 && entry.resist != F_ZERO && entry.react == F_ZERO));
+        assert_eq!(dae_system.opvar_sensitivities.len(), 1);
+        assert_eq!(dae_system.opvar_sensitivities[0].var.name(db), "i");
+    })
+}
+
+#[test]
+fn correlated_noise() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module correlated_noise(inout d, inout g, inout s);
+            electrical d, g, s;
+            analog begin
+                I(d, s) <+ V(d, s) / 1k;
+                I(d, s) <+ white_noise(4e-21, "channel");
+                I(g, s) <+ 0.5 * white_noise(4e-21, "channel");
+                I(g, s) <+ white_noise(1e-22, "gate");
+            end
+        endmodule
+    "#};
+    with_dae_system(src, |_, dae_system, literals| {
+        assert_eq!(dae_system.noise_sources.len(), 3);
+        let pairs = correlated_noise_sources(&dae_system.noise_sources);
+        let &[(i, j)] = pairs.as_slice() else {
+            panic!("expected exactly one correlated pair: {pairs:?}")
+        };
+        let src1 = &dae_system.noise_sources[i];
+        let src2 = &dae_system.noise_sources[j];
+        assert_eq!(literals.resolve(&src1.name), "channel");
+        assert_eq!(src1.name, src2.name);
+        assert_ne!(src1.hi, src2.hi);
+    })
+}
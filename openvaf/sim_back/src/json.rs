@@ -0,0 +1,196 @@
+//! Machine readable (JSON) export of a compiled module (`openvaf --dump-json`).
+//!
+//! The schema is versioned with [`JSON_SCHEMA_VERSION`]. Fields are only ever added within
+//! a version, any other change (renaming/removing fields or changing their meaning)
+//! increments the version. Each module is exported as:
+//!
+//! ```text
+//! {
+//!     "version": 1,
+//!     "module": "<name>",
+//!     "eval": {
+//!         "cfg": [{"predecessors": [<block>], "successors": [<block>], "instructions": [<inst>]}],
+//!         "instructions": [{"opcode": "<opcode>", "arguments": [<val>], "results": [<val>]}],
+//!         "vals": [<value definition and "uses": [<inst>]>],
+//!         "inputs": {"<kind>": {"<name>": <val>}},
+//!         "outputs": {"<variable>": <val>},
+//!         "parameters": [{"name": "<name>", "type": "<type>", "instance": <bool>}],
+//!         "unknowns": [{"name": "<name>", "kind": "node" | "flow" | "implicit"}],
+//!         "residual": [{"unknown": <unknown>, "resist": <val>, "react": <val>,
+//!                       "resist_lim_rhs": <val>, "react_lim_rhs": <val>}],
+//!         "jacobian": [{"row": <unknown>, "col": <unknown>, "resist": <val>, "react": <val>}]
+//!     }
+//! }
+//! ```
+//!
+//! `<block>`, `<inst>`, `<val>` and `<unknown>` are indices into `cfg`, `instructions`, `vals`
+//! and `unknowns`. Blocks are listed in reverse postorder so the first block is the entry.
+//! Phi instructions list their `arguments` as `{"<block>": <val>}`.
+//! A value is either the result of an instruction (`"instruction": <inst>, "idx": <n>`),
+//! an input (`"<kind>": "<name>"` as listed in `inputs`) or a constant (`"fconst"`, `"iconst"`,
+//! `"bconst"` or `"sconst"`). Non-finite `fconst` values are stored as the strings
+//! `"inf"`, `"-inf"` and `"NaN"`.
+//!
+//! The input kinds are `parameters`, `param_given`, `port_connected`, `voltages` (`V(hi,lo)`),
+//! `currents` (`I(hi,lo)`, `I(<branch>)` or `I(<port>)`), `implicit_unknowns`, `hidden_states`,
+//! `lim_states`, `op_states`, `delayed_values` (`absdelay` inputs provided by the simulator),
+//! `sim_state` (like `$temperature` and `$abstime`) and `cache_slots` (values computed once
+//! during instance setup).
+
+use hir::CompilationDB;
+use hir_lower::{CurrentKind, ParamKind, PlaceKind};
+use lasso::Rodeo;
+use mir::{ControlFlowGraph, JsonField, JsonSection, JsonStr, Param};
+use stdx::format_to;
+
+use crate::{CompiledModule, SimUnknownKind};
+
+#[cfg(test)]
+mod tests;
+
+/// The version of the schema produced by [`CompiledModule::to_json`]
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+impl CompiledModule<'_> {
+    /// Serializes the eval function and the DAE system of this module.
+    /// The schema is documented at the top of `sim_back/src/json.rs`.
+    pub fn to_json(&self, db: &CompilationDB, literals: &Rodeo) -> String {
+        let mut cfg = ControlFlowGraph::new();
+        cfg.compute(&self.eval);
+
+        let param_name = |param: Param| -> (&'static str, String) {
+            let Some((kind, _)) = self.intern.params.get_index(param) else {
+                let slot = usize::from(param) - self.intern.params.len();
+                return ("cache_slots", format!("cache{slot}"));
+            };
+            match *kind {
+                ParamKind::Param(param) => ("parameters", param.name(db)),
+                ParamKind::ParamSysFun(param) => ("parameters", format!("${param:?}")),
+                ParamKind::ParamGiven { param } => ("param_given", param.name(db)),
+                ParamKind::PortConnected { port } => ("port_connected", port.name(db).to_string()),
+                ParamKind::Voltage { hi, lo: Some(lo) } => {
+                    ("voltages", format!("V({},{})", hi.name(db), lo.name(db)))
+                }
+                ParamKind::Voltage { hi, lo: None } => ("voltages", format!("V({})", hi.name(db))),
+                ParamKind::Current(CurrentKind::Unnamed { hi, lo: Some(lo) }) => {
+                    ("currents", format!("I({},{})", hi.name(db), lo.name(db)))
+                }
+                ParamKind::Current(CurrentKind::Unnamed { hi, lo: None }) => {
+                    ("currents", format!("I({})", hi.name(db)))
+                }
+                ParamKind::Current(CurrentKind::Branch(br)) => {
+                    ("currents", format!("I({})", br.name(db)))
+                }
+                ParamKind::Current(CurrentKind::Port(port)) => {
+                    ("currents", format!("I(<{}>)", port.name(db)))
+                }
+                ParamKind::ImplicitUnknown(equation) => {
+                    ("implicit_unknowns", format!("implicit_equation_{}", u32::from(equation)))
+                }
+                ParamKind::HiddenState(var) => ("hidden_states", var.name(db).to_string()),
+                ParamKind::PrevState(state) => ("lim_states", format!("prev_{state}")),
+                ParamKind::NewState(state) => ("lim_states", format!("new_{state}")),
+                ParamKind::PrevOpState(state) => ("op_states", state.to_string()),
+                ParamKind::Abstime => ("sim_state", "$abstime".to_owned()),
+                ParamKind::Temperature => ("sim_state", "$temperature".to_owned()),
+                ParamKind::EnableIntegration => ("sim_state", "enable_integration".to_owned()),
+                ParamKind::EnableLim => ("sim_state", "enable_lim".to_owned()),
+                ParamKind::InitialStep => ("sim_state", "initial_step".to_owned()),
+                ParamKind::FinalStep => ("sim_state", "final_step".to_owned()),
+                ParamKind::ExactAbsdelay => ("sim_state", "exact_absdelay".to_owned()),
+                ParamKind::DelayedValue(absdelay) => ("delayed_values", absdelay.to_string()),
+            }
+        };
+
+        let outputs = self.intern.outputs.iter().filter_map(|(kind, val)| {
+            let PlaceKind::Var(var) = *kind else { return None };
+            Some((var.name(db).to_string(), val.expand()?))
+        });
+
+        let sections = [
+            JsonSection { name: "parameters", entries: self.json_parameters(db) },
+            JsonSection { name: "unknowns", entries: self.json_unknowns(db) },
+            JsonSection { name: "residual", entries: self.json_residual() },
+            JsonSection { name: "jacobian", entries: self.json_jacobian() },
+        ];
+
+        let eval = self.eval.to_json(&cfg, literals, param_name, outputs, &sections);
+        let mut json = format!("{{\n    \"version\": {JSON_SCHEMA_VERSION},\n");
+        format_to!(json, "    \"module\": {},\n", JsonStr(self.info.module.name(db)));
+        format_to!(json, "    \"eval\": {}\n}}\n", eval.replace('\n', "\n    "));
+        json
+    }
+
+    fn json_parameters(&self, db: &CompilationDB) -> Vec<Vec<(&'static str, JsonField)>> {
+        self.info
+            .params
+            .iter()
+            .map(|(param, info)| {
+                vec![
+                    ("name", JsonField::Str(info.name.to_string())),
+                    ("type", JsonField::Str(param.ty(db).to_string())),
+                    ("instance", JsonField::Bool(info.is_instance)),
+                ]
+            })
+            .collect()
+    }
+
+    fn json_unknowns(&self, db: &CompilationDB) -> Vec<Vec<(&'static str, JsonField)>> {
+        self.dae_system
+            .unknowns
+            .iter()
+            .map(|unknown| {
+                let (name, kind) = match *unknown {
+                    SimUnknownKind::KirchoffLaw(node) => (node.name(db).to_string(), "node"),
+                    SimUnknownKind::Current(CurrentKind::Unnamed { hi, lo: Some(lo) }) => {
+                        (format!("I({},{})", hi.name(db), lo.name(db)), "flow")
+                    }
+                    SimUnknownKind::Current(CurrentKind::Unnamed { hi, lo: None }) => {
+                        (format!("I({})", hi.name(db)), "flow")
+                    }
+                    SimUnknownKind::Current(CurrentKind::Branch(br)) => {
+                        (format!("I({})", br.name(db)), "flow")
+                    }
+                    SimUnknownKind::Current(CurrentKind::Port(port)) => {
+                        (format!("I(<{}>)", port.name(db)), "flow")
+                    }
+                    SimUnknownKind::Implicit(equation) => {
+                        (format!("implicit_equation_{}", u32::from(equation)), "implicit")
+                    }
+                };
+                vec![("name", JsonField::Str(name)), ("kind", JsonField::Str(kind.to_owned()))]
+            })
+            .collect()
+    }
+
+    fn json_residual(&self) -> Vec<Vec<(&'static str, JsonField)>> {
+        self.dae_system
+            .residual
+            .iter_enumerated()
+            .map(|(unknown, residual)| {
+                vec![
+                    ("unknown", JsonField::Int(u32::from(unknown).into())),
+                    ("resist", JsonField::Value(residual.resist)),
+                    ("react", JsonField::Value(residual.react)),
+                    ("resist_lim_rhs", JsonField::Value(residual.resist_lim_rhs)),
+                    ("react_lim_rhs", JsonField::Value(residual.react_lim_rhs)),
+                ]
+            })
+            .collect()
+    }
+
+    fn json_jacobian(&self) -> Vec<Vec<(&'static str, JsonField)>> {
+        self.dae_system
+            .jacobian
+            .iter()
+            .map(|entry| {
+                vec![
+                    ("row", JsonField::Int(u32::from(entry.row).into())),
+                    ("col", JsonField::Int(u32::from(entry.col).into())),
+                    ("resist", JsonField::Value(entry.resist)),
+                    ("react", JsonField::Value(entry.react)),
+                ]
+            })
+            .collect()
+    }
+}
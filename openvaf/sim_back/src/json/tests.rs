@@ -0,0 +1,56 @@
+use std::fs;
+
+use hir::diagnostics::ConsoleSink;
+use hir::CompilationDB;
+use indoc::indoc;
+use lasso::Rodeo;
+use serde_json::Value;
+use stdx::integration_test_dir;
+
+use crate::CompiledModule;
+
+fn to_json(src: &str) -> Value {
+    let db = CompilationDB::new_virtual(src).unwrap();
+    let module = crate::collect_modules(&db, false, &mut ConsoleSink::new(&db)).unwrap().remove(0);
+    let mut literals = Rodeo::new();
+    let module = CompiledModule::new(&db, &module, &mut literals);
+    let json = module.to_json(&db, &literals);
+    serde_json::from_str(&json).unwrap_or_else(|err| panic!("invalid json ({err}):\n{json}"))
+}
+
+#[test]
+fn resistor() {
+    let src = fs::read_to_string(integration_test_dir("RESISTOR").join("resistor.va")).unwrap();
+    let json = to_json(&src);
+
+    assert_eq!(json["version"], 1);
+    assert_eq!(json["module"], "resistor_va");
+    let eval = &json["eval"];
+    for section in ["cfg", "instructions", "vals", "inputs", "outputs", "unknowns", "jacobian"] {
+        assert!(!eval[section].is_null(), "missing {section}");
+    }
+    let params = eval["parameters"].as_array().unwrap();
+    assert!(params.iter().any(|param| param["name"] == "R" && param["type"] == "real"));
+    assert!(!eval["inputs"]["voltages"]["V(A,B)"].is_null());
+
+    // the residual of both terminals
+    let num_vals = eval["vals"].as_array().unwrap().len() as u64;
+    let residual = eval["residual"].as_array().unwrap();
+    assert_eq!(residual.len(), 2);
+    for entry in residual {
+        assert!(entry["resist"].as_u64().unwrap() < num_vals);
+    }
+}
+
+#[test]
+fn escaped_module_name() {
+    let src = indoc! {r#"
+        `include "disciplines.vams"
+        module \res"istor (inout a, inout b);
+            electrical a, b;
+            analog I(a, b) <+ V(a, b);
+        endmodule
+    "#};
+    let json = to_json(src);
+    assert_eq!(json["module"], "\\res\"istor");
+}
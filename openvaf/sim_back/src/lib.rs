@@ -5,6 +5,7 @@ use mir::Function;
 use mir_opt::{simplify_cfg, sparse_conditional_constant_propagation};
 use stdx::impl_debug_display;
 
+pub use json::JSON_SCHEMA_VERSION;
 pub use module_info::{collect_modules, ModuleInfo};
 
 use crate::context::{Context, OptimiziationStage};
@@ -13,9 +14,11 @@ use crate::init::Initialization;
 use crate::node_collapse::NodeCollapse;
 use crate::topology::Topology;
 
+mod ac_stim;
 mod context;
 pub mod dae;
 pub mod init;
+mod json;
 mod module_info;
 pub mod node_collapse;
 mod noise;
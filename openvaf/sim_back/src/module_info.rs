@@ -1,5 +1,5 @@
 use ahash::AHashSet;
-use hir::diagnostics::{BaseDB, ConsoleSink, Diagnostic, FileId, Label, LabelStyle, Report};
+use hir::diagnostics::{BaseDB, Diagnostic, FileId, Label, LabelStyle, Report};
 use hir::{
     CompilationDB, CompilationUnit, DiagnosticSink, Module, ParamSysFun, Parameter,
     ResolvedAliasParameter, ScopeDef, Variable,
@@ -16,7 +16,7 @@ mod tests;
 pub fn collect_modules(
     db: &CompilationDB,
     all_vars_opvars: bool,
-    sink: &mut ConsoleSink,
+    sink: &mut impl DiagnosticSink,
 ) -> Option<Vec<ModuleInfo>> {
     let cu = db.compilation_unit();
     let name = cu.name(db);
@@ -27,9 +27,16 @@ pub fn collect_modules(
         return None;
     }
 
-    let res = cu
-        .modules(db)
+    let modules = cu.modules(db);
+    // modules instantiated by another module are only compiled as part of their parent
+    let instantiated: AHashSet<_> = modules
+        .iter()
+        .flat_map(|module| module.instances(db))
+        .map(|inst| inst.module(db))
+        .collect();
+    let res = modules
         .into_iter()
+        .filter(|module| !instantiated.contains(module))
         .map(|module| ModuleInfo::collect(db, cu, module, sink, all_vars_opvars))
         .collect();
 
@@ -52,7 +59,7 @@ impl ModuleInfo {
         db: &CompilationDB,
         cu: CompilationUnit,
         module: Module,
-        sink: &mut ConsoleSink,
+        sink: &mut impl DiagnosticSink,
         all_vars_opvars: bool,
     ) -> ModuleInfo {
         let mut params: IndexMap<Parameter, ParamInfo, ahash::RandomState> = IndexMap::default();
@@ -78,6 +85,11 @@ impl ModuleInfo {
                     // * have a description or units attribute
                     // * belong to a module (not a block/function) -> no path
 
+                    // array variables are not supported as output variables
+                    if var.bounds(db).is_some() {
+                        continue;
+                    }
+
                     // check for units or description
                     let units = var.get_attr(db, &ast, "units");
                     let desc = var.get_attr(db, &ast, "desc");
@@ -167,6 +179,9 @@ impl ModuleInfo {
                         }
                     };
 
+                    // parameters marked with (* sensitivity *) are always differentiated
+                    let sensitivity = param.get_attr(db, &ast, "sensitivity").is_some();
+
                     params.insert(
                         param,
                         ParamInfo {
@@ -176,6 +191,7 @@ impl ModuleInfo {
                             description: desc,
                             group,
                             is_instance,
+                            sensitivity,
                         },
                     );
                 }
@@ -195,6 +211,21 @@ impl ModuleInfo {
 
         ModuleInfo { module, params, op_vars, sys_fun_alias }
     }
+
+    /// Marks the parameter called `name` (or one of its aliases) for sensitivity analysis.
+    /// Returns `false` if the module has no such parameter.
+    pub fn select_sensitivity(&mut self, name: &str) -> bool {
+        let param = self
+            .params
+            .values_mut()
+            .find(|info| info.name == name || info.alias.iter().any(|alias| alias == name));
+        if let Some(info) = param {
+            info.sensitivity = true;
+            true
+        } else {
+            false
+        }
+    }
 }
 
 struct IllegalAttr {
@@ -253,6 +284,8 @@ pub struct ParamInfo {
     pub description: String,
     pub group: String,
     pub is_instance: bool,
+    /// whether derivatives with respect to this parameter are generated
+    pub sensitivity: bool,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -33,3 +33,18 @@ impl NoiseSource {
         }
     }
 }
+
+/// Contributions of noise sources with the same name (like induced gate noise that shares its
+/// origin with the channel noise of a MOSFET) stem from a single physical source and are therefore
+/// fully correlated. Returns the indices `(i, j)` with `i < j` of all such pairs.
+pub fn correlated_noise_sources(sources: &[NoiseSource]) -> Vec<(usize, usize)> {
+    let mut res = Vec::new();
+    for (i, src) in sources.iter().enumerate() {
+        for (j, other) in sources.iter().enumerate().skip(i + 1) {
+            if src.name == other.name {
+                res.push((i, j))
+            }
+        }
+    }
+    res
+}
@@ -61,6 +61,7 @@ pub(crate) struct Contribution {
     pub resist_small_signal: Value,
     pub react_small_signal: Value,
     pub noise: Vec<Noise>,
+    pub ac_stims: Vec<AcStim>,
 }
 
 impl Contribution {
@@ -70,6 +71,7 @@ impl Contribution {
             && self.resist_small_signal == F_ZERO
             && self.react_small_signal == F_ZERO
             && self.noise.is_empty()
+            && self.ac_stims.is_empty()
     }
 }
 
@@ -82,6 +84,7 @@ impl Default for Contribution {
             resist_small_signal: F_ZERO,
             react_small_signal: F_ZERO,
             noise: Vec::new(),
+            ac_stims: Vec::new(),
         }
     }
 }
@@ -143,6 +146,34 @@ impl Noise {
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct AcStim {
+    pub analysis: Spur,
+    pub mag: Value,
+    pub phase: Value,
+    pub factor: Value,
+}
+
+impl AcStim {
+    pub fn new(
+        inst: Inst,
+        cb: &CallBackKind,
+        factor: Value,
+        ssa_builder: &mut SSAVariableBuilder,
+        func: &mut Function,
+    ) -> AcStim {
+        let analysis = match *cb {
+            CallBackKind::AcStim { analysis } => analysis,
+            _ => unreachable!(),
+        };
+        let [mag, phase] = [0, 1].map(|i| {
+            let arg = func.dfg.instr_args(inst)[i];
+            ssa_builder.define_at_exit(func, F_ZERO, arg, inst)
+        });
+        AcStim { analysis, mag, phase, factor }
+    }
+}
+
 /// An intermediat representation the toplology of a circuit. It represents circuit
 /// topology as a set of contributions to branches and implicit equations. These contributions
 /// are divided into resistive/reactive voltage/current
@@ -259,6 +290,7 @@ impl Topology {
                                 resist_small_signal: F_ZERO,
                                 react_small_signal: F_ZERO,
                                 noise: Vec::new(),
+                                ac_stims: Vec::new(),
                             },
                             current_src: Contribution {
                                 unknown: current,
@@ -267,6 +299,7 @@ impl Topology {
                                 resist_small_signal: F_ZERO,
                                 react_small_signal: F_ZERO,
                                 noise: Vec::new(),
+                                ac_stims: Vec::new(),
                             },
                         };
                         branches.insert_full(branch, contrib);
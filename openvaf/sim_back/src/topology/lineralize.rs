@@ -14,7 +14,7 @@ use mir::{
 };
 use typed_indexmap::TiSet;
 
-use crate::topology::{Contribution, Noise};
+use crate::topology::{AcStim, Contribution, Noise};
 use crate::util::{add, update_optbarrier};
 
 #[derive(Debug)]
@@ -42,9 +42,9 @@ impl<'a> super::Builder<'a> {
     ) {
         let mut ssa_builder = mir_build::SSAVariableBuilder::new(self.cfg);
         for (operator_inst, evaluation) in analog_operators {
-            let arg0 = self.func.dfg.instr_args(operator_inst)[0];
             let cb = self.func.dfg.func_ref(operator_inst).unwrap();
-            let is_noise = intern.callbacks[cb].is_noise();
+            let is_small_signal = intern.callbacks[cb].is_small_signal_src();
+            let is_ac_stim = matches!(intern.callbacks[cb], CallBackKind::AcStim { .. });
             match evaluation {
                 Evaluation::Dead => {
                     cov_mark::hit!(dead_noise);
@@ -59,19 +59,30 @@ impl<'a> super::Builder<'a> {
                         let inst = self.func.dfg.value_def(resistive_contribute).inst().unwrap();
                         let contribute = self.topology.as_contribution(*contribute).unwrap();
                         let contribute = self.topology.get_mut(contribute);
-                        if is_noise {
+                        if is_small_signal {
                             dimension = FuncCursor::new(self.func)
                                 .after_inst(inst)
                                 .ins()
                                 .ensure_optbarrier(dimension);
-                            let noise = Noise::new(
-                                operator_inst,
-                                cb,
-                                dimension,
-                                &mut ssa_builder,
-                                self.func,
-                            );
-                            contribute.noise.push(noise)
+                            if is_ac_stim {
+                                let stim = AcStim::new(
+                                    operator_inst,
+                                    cb,
+                                    dimension,
+                                    &mut ssa_builder,
+                                    self.func,
+                                );
+                                contribute.ac_stims.push(stim)
+                            } else {
+                                let noise = Noise::new(
+                                    operator_inst,
+                                    cb,
+                                    dimension,
+                                    &mut ssa_builder,
+                                    self.func,
+                                );
+                                contribute.noise.push(noise)
+                            }
                         } else {
                             update_optbarrier(
                                 self.func,
@@ -85,7 +96,9 @@ impl<'a> super::Builder<'a> {
                     }
                 }
                 Evaluation::Equation => {
-                    let eq = if is_noise {
+                    let eq = if is_ac_stim {
+                        ImplicitEquationKind::AcStim
+                    } else if is_small_signal {
                         ImplicitEquationKind::NoiseSrc
                     } else {
                         ImplicitEquationKind::Ddt
@@ -106,7 +119,21 @@ impl<'a> super::Builder<'a> {
                     }
 
                     let neg_eq_val = FuncCursor::new(self.func).at_exit().ins().fneg(eq_val);
-                    let contributions = if is_noise {
+                    let contributions = if is_ac_stim {
+                        self.topology.small_signal_vals.insert(eq_val);
+                        Contribution {
+                            unknown: Some(eq_val),
+                            resist: neg_eq_val,
+                            ac_stims: vec![AcStim::new(
+                                operator_inst,
+                                &intern.callbacks[cb],
+                                F_ONE,
+                                &mut ssa_builder,
+                                self.func,
+                            )],
+                            ..Contribution::default()
+                        }
+                    } else if is_small_signal {
                         self.topology.small_signal_vals.insert(eq_val);
                         Contribution {
                             unknown: Some(eq_val),
@@ -121,6 +148,8 @@ impl<'a> super::Builder<'a> {
                             ..Contribution::default()
                         }
                     } else {
+                        // noise tables have no arguments, so only ddt reads its argument here
+                        let arg0 = self.func.dfg.instr_args(operator_inst)[0];
                         let arg0 =
                             ssa_builder.define_at_exit(self.func, F_ZERO, arg0, operator_inst);
                         Contribution {
@@ -179,7 +208,8 @@ impl<'a> super::Builder<'a> {
                 }
                 CallBackKind::WhiteNoise { .. }
                 | CallBackKind::FlickerNoise { .. }
-                | CallBackKind::NoiseTable(_) => {
+                | CallBackKind::NoiseTable(_)
+                | CallBackKind::AcStim { .. } => {
                     for inst in take(uses) {
                         analog_operators.push((
                             inst,
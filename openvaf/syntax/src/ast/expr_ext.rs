@@ -21,6 +21,26 @@ impl ast::Expr {
             None
         }
     }
+
+    /// Returns the value of an (optionally signed) integer literal
+    pub fn as_int_const(&self) -> Option<i32> {
+        match self {
+            ast::Expr::Literal(lit) => match lit.kind() {
+                LiteralKind::IntNumber(lit) => Some(lit.value()),
+                _ => None,
+            },
+            ast::Expr::PrefixExpr(expr) => {
+                let val = expr.expr()?.as_int_const()?;
+                match expr.op_kind()? {
+                    UnaryOp::Neg => Some(-val),
+                    UnaryOp::Identity => Some(val),
+                    _ => None,
+                }
+            }
+            ast::Expr::ParenExpr(expr) => expr.expr()?.as_int_const(),
+            _ => None,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
@@ -305,6 +325,12 @@ impl ast::SelectExpr {
     }
 }
 
+impl ast::IndexExpr {
+    pub fn index(&self) -> Option<ast::Expr> {
+        support::children(self.syntax()).nth(1)
+    }
+}
+
 pub enum AsssigmentOp {
     /// a variable assignment stmt
     /// lhs must be an identifier (example `I = V(a,c)/R;`)
@@ -6,8 +6,8 @@ use std::iter::successors;
 use stdx::impl_debug;
 
 use super::{
-    AnalogBehaviour, ArgListOwner, Assign, AstChildTokens, AstChildren, Constraint, EventStmt,
-    Expr, ForStmt, Function, ModulePortKind, Path, PortFlow, Range, Stmt, StrLit,
+    AnalogBehaviour, ArgListOwner, Assign, AstChildTokens, AstChildren, Constraint, Event, Expr,
+    ForStmt, Function, ModulePortKind, Path, PortFlow, Range, Stmt, StrLit,
 };
 use crate::ast::{self, support, AstNode};
 use crate::SyntaxKind::{IDENT, ROOT_KW};
@@ -228,7 +228,7 @@ impl ForStmt {
     }
 }
 
-impl EventStmt {
+impl Event {
     pub fn sim_phases(&self) -> AstChildTokens<StrLit> {
         support::child_token(self.syntax())
     }
@@ -108,6 +108,13 @@ pub enum SyntaxError {
         range: TextRange,
         ty: TextRange,
     },
+    IllegalArrayBound {
+        range: TextRange,
+    },
+    ArrayVarWithInitializer {
+        var: String,
+        range: TextRange,
+    },
 }
 
 use SyntaxError::*;
@@ -137,5 +144,7 @@ impl_display! {
         IllegalNetType{found,..} => "{} nets are currently not supported!",found;
         RangeConstraintForNonNumericParameter{param,..} => "non-numeric parameter '{}' has range bounds", param;
         PortNotDeclaredInModule{name,..} => "port '{name}' was not declared in the module head";
+        IllegalArrayBound{..} => "array bounds must be integer constants";
+        ArrayVarWithInitializer{var,..} => "array variable '{}' can not be initialized", var;
     }
 }
@@ -24,6 +24,7 @@ pub(crate) fn validate(root: &SyntaxNode, errors: &mut Vec<SyntaxError>) {
                 ast::Name(name) => validate_name(name,errors),
                 ast::ModuleDecl(module) => validate_module(module,errors),
                 ast::ParamDecl(param) => validate_param(param, errors),
+                ast::Var(var) => validate_var(var, errors),
                 _ => validate_net_type_token(node,errors)
             }
         }
@@ -52,6 +53,23 @@ fn validate_param(param_decl: ast::ParamDecl, errors: &mut Vec<SyntaxError>) {
     }
 }
 
+fn validate_var(var: ast::Var, errors: &mut Vec<SyntaxError>) {
+    if let Some(range) = var.range() {
+        for bound in [range.start(), range.end()].into_iter().flatten() {
+            if bound.as_int_const().is_none() {
+                errors.push(SyntaxError::IllegalArrayBound { range: bound.syntax().text_range() });
+            }
+        }
+
+        if let (Some(name), Some(default)) = (var.name(), var.default()) {
+            errors.push(SyntaxError::ArrayVarWithInitializer {
+                var: name.text().to_owned(),
+                range: default.syntax().text_range(),
+            });
+        }
+    }
+}
+
 fn validate_net_type_token(node: SyntaxNode, errors: &mut Vec<SyntaxError>) {
     if matches!(node.kind(), SyntaxKind::NET_DECL | SyntaxKind::PORT_DECL) {
         if let Some(token) = support::token(&node, NET_TYPE) {
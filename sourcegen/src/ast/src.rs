@@ -95,6 +95,7 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "initial",
         "final_step",
         "aliasparam",
+        "or",
     ],
     literals: &["INT_NUMBER", "STD_REAL_NUMBER", "SI_REAL_NUMBER", "STR_LIT"],
     tokens: &["ERROR", "IDENT", "SYSFUN", "NET_TYPE", "WHITESPACE", "COMMENT"],
@@ -120,6 +121,7 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "DISCIPLINE_DECL",
         "DISCIPLINE_ATTR",
         "EVENT_STMT",
+        "EVENT",
         "FOR_STMT",
         "FUNCTION",
         "FUNCTION_ARG",
@@ -128,6 +130,10 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "MODULE_DECL",
         "MODULE_PORT",
         "MODULE_PORTS",
+        "MODULE_INST",
+        "PARAM_OVERRIDES",
+        "INSTANCE",
+        "INST_ARG",
         "NAME",
         "NAME_REF",
         "SYS_FUN",
@@ -147,6 +153,7 @@ pub(crate) const KINDS_SRC: KindsSrc = KindsSrc {
         "PREFIX_EXPR",
         "RANGE",
         "SELECT_EXPR",
+        "INDEX_EXPR",
         "TYPE",
         "VAR",
         "VAR_DECL",
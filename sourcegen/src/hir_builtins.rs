@@ -24,41 +24,18 @@ const ANALOG_OPERATORS: [&str; 17] = [
     "transition",
 ];
 
-const UNSUPPORTED: [&str; 50] = [
+const EVENTS: [&str; 3] = ["cross", "above", "timer"];
+
+const UNSUPPORTED: [&str; 25] = [
     "simprobe",
     "analog_node_alias",
     "analog_port_alias",
     "test_plusargs",
     "value_plusargs",
-    "zi_nd",
-    "zi_np",
-    "zi_zd",
-    "zi_zp",
-    "laplace_nd",
-    "laplace_np",
-    "laplace_zd",
-    "laplace_zp",
     "last_crossing",
-    "slew",
-    "transition",
-    "fclose",
-    "fopen",
-    "fdisplay",
-    "fwrite",
-    "fstrobe",
-    "fmonitor",
-    "fgets",
-    "fscanf",
     "swrite",
     "sformat",
     "sscanf",
-    "rewind",
-    "fseek",
-    "ftell",
-    "fflush",
-    "ferror",
-    "feof",
-    "fdebug",
     "dist_chi_square",
     "dist_exponential",
     "dist_poisson",
@@ -209,6 +186,7 @@ fn generate_builtins() {
         .chain(ANALYSIS_FUNS)
         .chain(ANALOG_OPERATORS_SYSFUN)
         .chain(ANALOG_OPERATORS)
+        .chain(EVENTS)
         .map(|builtin| {
             let is_sysfun = builtin.starts_with('$');
 
@@ -229,6 +207,7 @@ fn generate_builtins() {
 
     let analysis_funs = ANALYSIS_FUNS.into_iter().map(|op| format_ident!("{}", op));
     let analog_operators = ANALOG_OPERATORS.into_iter().map(|op| format_ident!("{}", op));
+    let events = EVENTS.into_iter().map(|op| format_ident!("{}", op));
     let unsupported = UNSUPPORTED.into_iter().map(|op| format_ident!("{}", op));
     let analog_operators_sysfun =
         ANALOG_OPERATORS_SYSFUN.into_iter().map(|op| format_ident!("{}", &op[1..]));
@@ -289,6 +268,14 @@ fn generate_builtins() {
                     _ => false
                 }
             }
+
+            #[allow(clippy::match_like_matches_macro)]
+            pub fn is_event(self)->bool{
+                match self{
+                    #(BuiltIn::#events)|* =>true,
+                    _ => false
+                }
+            }
         }
 
         pub fn insert_builtin_scope(dst: &mut IndexMap<Name, ScopeDefItem, RandomState>){
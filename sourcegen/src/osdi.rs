@@ -501,6 +501,7 @@ impl ToTokens for OsdiStructInterp<'_, '_> {
                 | "OsdiSimParas"
                 | "OsdiInitInfo"
                 | "OsdiInitErrorPayload"
+                | "OsdiBoundViolation"
                 | "OsdiSimInfo"
         ) {
             assert!(!self.info.is_union, "union code generation is not implemented (yet)");
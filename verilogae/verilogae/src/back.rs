@@ -2,7 +2,9 @@ use std::borrow::Borrow;
 
 use camino::Utf8Path;
 use hir::Type;
-use hir_lower::{CallBackKind, CurrentKind, HirInterner, ParamInfoKind, ParamKind, PlaceKind};
+use hir_lower::{
+    CallBackKind, CurrentKind, FileOp, HirInterner, ParamInfoKind, ParamKind, PlaceKind,
+};
 use lasso::Rodeo;
 use llvm::{OptLevel, UNNAMED};
 use mir::{ControlFlowGraph, FuncRef, Function};
@@ -30,6 +32,53 @@ pub fn sim_param_str_stub<'ll>(cx: &CodegenCx<'_, 'll>) -> CallbackFun<'ll> {
     cx.const_callback(&[ty_str], empty_str)
 }
 
+/// File I/O is not available when evaluating model functions: every file operation fails and
+/// string results are empty.
+pub fn file_op_stub<'ll>(cx: &CodegenCx<'_, 'll>, op: FileOp) -> CallbackFun<'ll> {
+    let args = match op {
+        FileOp::Open => vec![cx.ty_ptr(), cx.ty_ptr()],
+        op => vec![cx.ty_int(); op.num_params() as usize],
+    };
+    if op.num_returns() == 1 {
+        return cx.const_callback(&args, cx.const_int(0));
+    }
+    let empty_str = cx.literals.get("").unwrap();
+    let empty_str = cx.const_str(empty_str);
+    let ty = cx.ty_struct("FileStrResult", &[cx.ty_int(), cx.ty_ptr()]);
+    cx.const_callback(&args, cx.const_struct(ty, &[cx.const_int(0), empty_str]))
+}
+
+/// Stub for `$fscanf` that matches nothing and leaves all outputs unchanged.
+pub fn file_scan_stub<'ll>(cx: &CodegenCx<'_, 'll>, arg_tys: &[Type]) -> CallbackFun<'ll> {
+    let outputs: Vec<_> = arg_tys.iter().map(|ty| lltype(ty, cx)).collect();
+    let mut args = vec![cx.ty_int(), cx.ty_ptr()];
+    args.extend_from_slice(&outputs);
+    let mut fields = vec![cx.ty_int()];
+    fields.extend_from_slice(&outputs);
+    let ret_ty = cx.ty_struct("FileScanResult", &fields);
+    let fun_ty = cx.ty_func(&args, ret_ty);
+    let fun = cx.declare_int_fn(&cx.local_callback_name(), fun_ty);
+    unsafe {
+        let bb = llvm::LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
+        let builder = llvm::LLVMCreateBuilderInContext(cx.llcx);
+        llvm::LLVMPositionBuilderAtEnd(builder, bb);
+        let mut ret = llvm::LLVMBuildInsertValue(
+            builder,
+            cx.const_undef(ret_ty),
+            cx.const_int(0),
+            0,
+            UNNAMED,
+        );
+        for i in 0..outputs.len() as u32 {
+            let val = llvm::LLVMGetParam(fun, 2 + i);
+            ret = llvm::LLVMBuildInsertValue(builder, ret, val, 1 + i, UNNAMED);
+        }
+        llvm::LLVMBuildRet(builder, ret);
+        llvm::LLVMDisposeBuilder(builder);
+    }
+    CallbackFun { fun_ty, fun, state: Box::new([]), num_state: 0 }
+}
+
 pub fn lltype<'ll>(ty: &Type, cx: &CodegenCx<'_, 'll>) -> &'ll llvm::Type {
     match ty {
         Type::Real => cx.ty_double(),
@@ -59,16 +108,23 @@ pub fn stub_callbacks<'ll>(
                 | CallBackKind::NodeDerivative(_)
                 | CallBackKind::TimeDerivative
                 | CallBackKind::FlickerNoise { .. }
-                | CallBackKind::WhiteNoise { .. }
-                | CallBackKind::NoiseTable(_) => {
+                | CallBackKind::WhiteNoise { .. } => {
                     cx.const_callback(&[cx.ty_double()], cx.const_real(0.0))
                 }
+                CallBackKind::NoiseTable(_) => cx.const_callback(&[], cx.const_real(0.0)),
+                CallBackKind::AcStim { .. } => {
+                    cx.const_callback(&[cx.ty_double(), cx.ty_double()], cx.const_real(0.0))
+                }
                 CallBackKind::Print { .. }
                 | CallBackKind::ParamInfo(_, _)
                 | CallBackKind::BuiltinLimit { .. }
                 | CallBackKind::StoreLimit(_)
                 | CallBackKind::LimDiscontinuity
-                | CallBackKind::CollapseHint(_, _) => return None,
+                | CallBackKind::EventDetected
+                | CallBackKind::CollapseHint(_, _)
+                | CallBackKind::FilePrint { .. } => return None,
+                CallBackKind::File(op) => file_op_stub(cx, *op),
+                CallBackKind::FileScan { arg_tys } => file_scan_stub(cx, arg_tys),
                 CallBackKind::Analysis => cx.const_callback(&[cx.ty_ptr()], cx.const_int(1)),
             };
 
@@ -353,9 +409,15 @@ impl CodegenCtx<'_, '_> {
                     ParamKind::ImplicitUnknown(_)
                     | ParamKind::Abstime
                     | ParamKind::PrevState(_)
-                    | ParamKind::NewState(_) => codegen.builder.cx.const_real(0.0),
-                    ParamKind::EnableIntegration | ParamKind::EnableLim => {
-                        codegen.builder.cx.const_bool(false)
+                    | ParamKind::NewState(_)
+                    | ParamKind::PrevOpState(_)
+                    | ParamKind::DelayedValue(_) => codegen.builder.cx.const_real(0.0),
+                    ParamKind::EnableIntegration
+                    | ParamKind::EnableLim
+                    | ParamKind::ExactAbsdelay => codegen.builder.cx.const_bool(false),
+                    // the model is evaluated exactly once
+                    ParamKind::InitialStep | ParamKind::FinalStep => {
+                        codegen.builder.cx.const_bool(true)
                     }
                 };
 
@@ -528,9 +590,14 @@ impl CodegenCtx<'_, '_> {
         &self,
         cx: &CodegenCx<'_, 'll>,
         set: bool,
+        extra_args: &[&'ll llvm::Type],
     ) -> (&'ll llvm::Value, &'ll llvm::Type) {
         let name = cx.local_callback_name();
-        let fun_ty = cx.ty_func(&[cx.ty_ptr(), cx.ty_c_bool()], cx.ty_void());
+        let mut args = vec![cx.ty_ptr(), cx.ty_c_bool()];
+        // additional arguments passed by the callback (like the offending value for
+        // `ParamInfoKind::Invalid`) are ignored
+        args.extend_from_slice(extra_args);
+        let fun_ty = cx.ty_func(&args, cx.ty_void());
         let fun = cx.declare_int_fn(&name, fun_ty);
         unsafe {
             let bb = llvm::LLVMAppendBasicBlockInContext(cx.llcx, fun, UNNAMED);
@@ -564,8 +631,10 @@ impl CodegenCtx<'_, '_> {
         let mut int_off = real_cnt;
         let mut str_off = int_off + int_cnt;
 
-        let param_info_set_cb = self.param_flag_cb(builder.cx, true);
-        let param_info_unset_cb = self.param_flag_cb(builder.cx, false);
+        let param_info_set_cb = self.param_flag_cb(builder.cx, true, &[]);
+        let param_info_unset_cb = self.param_flag_cb(builder.cx, false, &[]);
+        let param_info_invalid_cb =
+            self.param_flag_cb(builder.cx, true, &[builder.cx.ty_double(), builder.cx.ty_int()]);
         for (param, info) in self.model_info.params.iter() {
             let off = match info.ty {
                 Type::Real => &mut real_off,
@@ -588,7 +657,9 @@ impl CodegenCtx<'_, '_> {
                 (ParamInfoKind::MinExclusive, false, 0b001),
                 (ParamInfoKind::MaxExclusive, false, 0b010),
             ] {
-                let (fun, fun_ty) = if set {
+                let (fun, fun_ty) = if kind == ParamInfoKind::Invalid {
+                    param_info_invalid_cb
+                } else if set {
                     param_info_set_cb
                 } else {
                     bits = !bits;
@@ -679,10 +750,13 @@ impl CodegenCtx<'_, '_> {
                     ParamKind::ImplicitUnknown(_)
                     | ParamKind::Abstime
                     | ParamKind::PrevState(_)
-                    | ParamKind::NewState(_) => builder.cx.const_real(0.0),
-                    ParamKind::EnableIntegration | ParamKind::EnableLim => {
-                        builder.cx.const_bool(false)
-                    }
+                    | ParamKind::NewState(_)
+                    | ParamKind::PrevOpState(_)
+                    | ParamKind::DelayedValue(_) => builder.cx.const_real(0.0),
+                    ParamKind::EnableIntegration
+                    | ParamKind::EnableLim
+                    | ParamKind::ExactAbsdelay => builder.cx.const_bool(false),
+                    ParamKind::InitialStep | ParamKind::FinalStep => builder.cx.const_bool(true),
                 };
 
                 val.into()